@@ -21,6 +21,15 @@ declare interface ResizeDetail {
     height: number;
 }
 
+declare interface ImeDetail {
+    visible: boolean;
+    height: number;
+}
+
+declare interface ZoomDetail {
+    zoom: number;
+}
+
 declare interface ElementRect {
     x: number;
     y: number;
@@ -49,6 +58,12 @@ declare interface CaretDetail {
     bounds: ElementRect,
 }
 
+declare interface MaskValidateDetail {
+    value: string,
+    rawValue: string,
+    complete: boolean,
+}
+
 declare interface KeyDetail {
     modifiers: number,
     ctrlKey: boolean,
@@ -84,6 +99,10 @@ declare interface BoundsChangeDetail {
     originBounds: ElementRect,
 }
 
+declare interface FullscreenChangeDetail {
+    fullscreen: boolean,
+}
+
 declare interface TouchInfo {
     identifier: number;
     offsetX: number;
@@ -299,5 +318,28 @@ declare function clearTimeout(timer: number): void;
 
 declare function setInterval(callback: () => void, interval: number): number;
 
+interface IdleDeadline {
+    /**
+     * Whether callback is called because timeout reached while app is busy
+     */
+    didTimeout: boolean;
+    /**
+     * Milliseconds left before next frame
+     */
+    timeRemaining(): number;
+}
+
+/**
+ * Call callback when no window is painting or animating
+ * @param callback
+ * @param options timeout in milliseconds after which callback is called even if app is busy
+ */
+declare function requestIdleCallback(
+    callback: (deadline: IdleDeadline) => void,
+    options?: { timeout?: number },
+): number;
+
+declare function cancelIdleCallback(id: number): void;
+
 declare function clearInterval(timer: number): void;
 
@@ -0,0 +1,81 @@
+use deft::app::{App, IApp, WinitApp};
+use deft::event_loop::AppEventWakeUp;
+use deft::loader::StaticModuleLoader;
+use quick_js::loader::JsModuleLoader;
+use winit::application::ApplicationHandler;
+use winit::event::{DeviceEvent, DeviceId, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::WindowId;
+
+struct HelloAppImpl {}
+
+impl IApp for HelloAppImpl {
+    fn create_module_loader(&mut self) -> Box<dyn JsModuleLoader + Send + Sync + 'static> {
+        let mut ml = StaticModuleLoader::new();
+        ml.add_module("index.js".to_string(), include_str!("hello.js").to_string());
+        Box::new(ml)
+    }
+}
+
+/// User event of the host app
+enum HostEvent {
+    Deft,
+}
+
+/// Host app owning the event loop and forwarding events to deft
+struct HostApp {
+    deft: WinitApp,
+}
+
+impl ApplicationHandler<HostEvent> for HostApp {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.deft.resumed(event_loop);
+    }
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        self.deft.suspended(event_loop);
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: HostEvent) {
+        match event {
+            HostEvent::Deft => self.deft.user_event(event_loop, AppEventWakeUp),
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        // Deft never exits the event loop of host, the host decides when to quit
+        let close_requested = matches!(event, WindowEvent::CloseRequested);
+        self.deft.window_event(event_loop, window_id, event);
+        if close_requested {
+            event_loop.exit();
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        self.deft.device_event(event_loop, device_id, event);
+    }
+
+    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {
+        self.deft.memory_warning(event_loop);
+    }
+}
+
+fn main() {
+    let event_loop = EventLoop::<HostEvent>::with_user_event().build().unwrap();
+    let proxy = event_loop.create_proxy();
+    let deft = deft::embed(App::new(HelloAppImpl {}), move || {
+        proxy.send_event(HostEvent::Deft).is_ok()
+    })
+    .unwrap();
+    event_loop.run_app(&mut HostApp { deft }).unwrap();
+}
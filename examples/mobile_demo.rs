@@ -36,3 +36,12 @@ pub fn openharmony(openharmony_app: winit::platform::ohos::ability::OpenHarmonyA
     let app = App::new(AppImpl {});
     deft::ohos_bootstrap(openharmony_app, app);
 }
+
+/// Entry called from main of the Xcode project, build the static lib with
+/// `scripts/build-ios-example.sh`
+#[cfg(target_os = "ios")]
+#[no_mangle]
+pub extern "C" fn deft_main() {
+    let app = App::new(AppImpl {});
+    deft::ios_bootstrap(app);
+}
@@ -0,0 +1,47 @@
+use deft::app::{App, IApp};
+use deft::bootstrap;
+use deft::element::label::Label;
+use deft::event::ClickEventListener;
+use deft::ext::ext_window::WindowAttrs;
+use deft::view::{create_window, ElementBuilder, Observable};
+
+struct NativeUiAppImpl {}
+
+impl IApp for NativeUiAppImpl {
+    fn on_start(&mut self) {
+        let count = Observable::new(0);
+        let counter = ElementBuilder::new("label")
+            .unwrap()
+            .bind(&count, |el, v| {
+                el.get_backend_mut_as::<Label>()
+                    .set_text(format!("Clicked {} times", v));
+            });
+        let button = ElementBuilder::new("button")
+            .unwrap()
+            .style("padding", "4px 10px")
+            .child(ElementBuilder::new("label").unwrap().text("Click me"))
+            .on(ClickEventListener::new(move |_e, _ctx| {
+                count.update(|v| *v += 1);
+            }));
+        let body = ElementBuilder::new("body")
+            .unwrap()
+            .style("gap", "8px")
+            .style("padding", "10px")
+            .child(counter)
+            .child(button);
+        let attrs = WindowAttrs {
+            title: Some("Native UI".to_string()),
+            ..Default::default()
+        };
+        create_window(attrs, body).unwrap();
+    }
+
+    fn execute_js_main(&self) -> bool {
+        false
+    }
+}
+
+fn main() {
+    let app = App::new(NativeUiAppImpl {});
+    bootstrap(app);
+}
@@ -1,5 +1,7 @@
 declare type WindowType = "normal" | "menu"
 declare type RenderBackend = "SoftBuffer" | "GL" | "SoftGL"
+declare type SurfaceColorSpace = "srgb" | "display-p3"
+declare type WindowEffect = "none" | "blur" | "acrylic" | "mica" | "vibrancy"
 declare interface WindowAttrs {
     width ?: number
     height ?: number
@@ -13,7 +15,53 @@ declare interface WindowAttrs {
     minimizable ?: boolean,
     maximizable ?: boolean,
     closable ?: boolean,
+    resizeMargin ?: number,
+    resizeEdges ?: "all" | "corners" | "none",
+    storageNamespace ?: string,
     preferredRenderers ?: RenderBackend | RenderBackend[],
+    colorSpace ?: SurfaceColorSpace,
+    /**
+     * Make the background of window transparent so that content behind it shows through,
+     * backends that can't composite alpha, e.g. softbuffer, are only used if no other works
+     */
+    transparent ?: boolean,
+    /**
+     * Draw shadow around undecorated window, defaults to true if not transparent
+     */
+    shadow ?: boolean,
+    /**
+     * Radius of corners of undecorated window in logical pixels, windows 11 only supports
+     * the radius of system so that small radiuses use small rounded corners
+     */
+    cornerRadius ?: number,
+    /**
+     * Material drawn behind the window, the window is transparent if specified
+     */
+    effect ?: WindowEffect,
+    /**
+     * Shape of window as svg path in logical pixels, pixels out of it are transparent
+     */
+    shape ?: string,
+    /**
+     * Distance in logical pixels within which window dragged by `drag` snaps to screen edges
+     * or other windows, disabled by default
+     */
+    snapThreshold ?: number,
+    /**
+     * Maximum frame rate, frames are paced by refresh rate of monitor if not capped
+     */
+    maxFps ?: number,
+    /**
+     * Frame rate while window is not focused, not throttled by default
+     */
+    unfocusedFps ?: number,
+}
+
+declare interface StorageDetail {
+    namespace: string;
+    key: string;
+    oldValue: string | null;
+    newValue: string | null;
 }
 
 declare interface ResizeDetail {
@@ -21,6 +69,37 @@ declare interface ResizeDetail {
     height: number;
 }
 
+declare interface ImeDetail {
+    visible: boolean;
+    height: number;
+}
+
+declare interface ZoomDetail {
+    zoom: number;
+}
+
+declare interface RendererChangeDetail {
+    renderer: string;
+    reason: string;
+}
+
+declare interface VisibilityChangeDetail {
+    visible: boolean;
+}
+
+declare interface ThemeChangeDetail {
+    theme: string;
+}
+
+declare interface SnapDetail {
+    x: number;
+    y: number;
+    /**
+     * Edges of window sticking to screen edges or other windows, empty if pulled away
+     */
+    edges: ("left" | "top" | "right" | "bottom")[];
+}
+
 declare interface ElementRect {
     x: number;
     y: number;
@@ -41,6 +120,11 @@ declare interface MouseDetail {
     windowY: number;
     screenX: number;
     screenY: number;
+    /**
+     * Relative motion of mouse since last mousemove event, only reported in pointer lock mode
+     */
+    movementX: number;
+    movementY: number;
 }
 
 declare interface CaretDetail {
@@ -49,6 +133,29 @@ declare interface CaretDetail {
     bounds: ElementRect,
 }
 
+declare interface MaskValidateDetail {
+    value: string,
+    rawValue: string,
+    complete: boolean,
+}
+
+declare interface TruncationChangeDetail {
+    truncated: boolean,
+}
+
+declare interface LinkClickDetail {
+    href: string,
+}
+
+declare interface LinkHoverDetail {
+    href?: string,
+}
+
+declare interface TimeUpdateDetail {
+    currentTime: number,
+    duration: number,
+}
+
 declare interface KeyDetail {
     modifiers: number,
     ctrlKey: boolean,
@@ -84,6 +191,14 @@ declare interface BoundsChangeDetail {
     originBounds: ElementRect,
 }
 
+declare interface FullscreenChangeDetail {
+    fullscreen: boolean,
+}
+
+declare interface PointerLockChangeDetail {
+    locked: boolean,
+}
+
 declare interface TouchInfo {
     identifier: number;
     offsetX: number;
@@ -96,6 +211,44 @@ declare interface TouchDetail {
     touches: TouchInfo[],
 }
 
+declare interface GestureZoomDetail {
+    /**
+     * Scale relative to the distance of fingers when gesture started
+     */
+    scale: number;
+    /**
+     * Scale relative to last event
+     */
+    deltaScale: number;
+    offsetX: number;
+    offsetY: number;
+    windowX: number;
+    windowY: number;
+}
+
+declare interface GestureRotateDetail {
+    /**
+     * Rotation in radians since gesture started, clockwise is positive
+     */
+    rotation: number;
+    deltaRotation: number;
+    offsetX: number;
+    offsetY: number;
+    windowX: number;
+    windowY: number;
+}
+
+declare interface GesturePanDetail {
+    translationX: number;
+    translationY: number;
+    deltaX: number;
+    deltaY: number;
+    offsetX: number;
+    offsetY: number;
+    windowX: number;
+    windowY: number;
+}
+
 declare type Align =
     'auto'
     | 'flex-start'
@@ -135,7 +288,7 @@ declare interface StyleProps extends Record<string, number | string>{
     borderBottom?: string;
     borderLeft?: string;
 
-    display?: "none" | "flex",
+    display?: "none" | "flex" | "grid",
 
     width?: number | string,
     height?: number | string,
@@ -175,6 +328,16 @@ declare interface StyleProps extends Record<string, number | string>{
     flexWrap?: 'no-wrap' | 'wrap' | 'wrap-reverse',
     columnGap?: number,
     rowGap?: number,
+    /**
+     * e.g. "100px 1fr 2fr" or "repeat(3, 1fr)"
+     */
+    gridTemplateColumns?: string,
+    gridTemplateRows?: string,
+    /**
+     * e.g. "2", "span 2", "1 / 3" or "2 / span 2"
+     */
+    gridColumn?: string | number,
+    gridRow?: string | number,
     top?: number | string,
     right?: number | string,
     bottom?: number | string,
@@ -193,13 +356,75 @@ declare interface StyleProps extends Record<string, number | string>{
     borderRadius?: number | string,
 }
 
-declare interface LocalStorage {
-    getItem(key: string): string | null,
-
-    setItem(key: string, value: string): void,
+declare class LocalStorage {
+    /**
+     *
+     * @param namespace {string}
+     */
+    constructor(namespace?: string);
+    /**
+     * Get storage of another namespace, the namespace should be enabled by engine.enable_localstorage_with_namespace
+     * @param namespace {string}
+     * @returns {LocalStorage}
+     */
+    static namespace(namespace: string): LocalStorage;
+    /**
+     *
+     * @param key {string}
+     * @returns {string | null}
+     */
+    getItem(key: string): string | null;
+    /**
+     * Throw QuotaExceededError if quota of the namespace exceeded
+     * @param key {string}
+     * @param value {string}
+     */
+    setItem(key: string, value: string): void;
+    /**
+     *
+     * @param key {string}
+     */
+    removeItem(key: string): void;
+    /**
+     *
+     * @returns {string[]}
+     */
+    keys(): string[];
+    /**
+     * Used bytes of keys and values
+     * @returns {number}
+     */
+    get usage(): number;
+}
+declare class LargeStorage {
+    /**
+     *
+     * @param namespace {string}
+     */
+    constructor(namespace?: string);
+    /**
+     *
+     * @param key {string}
+     * @returns {Promise<string | null>}
+     */
+    getItem(key: string): Promise<string | null>;
+    /**
+     *
+     * @param key {string}
+     * @param value {string}
+     * @returns {Promise<void>}
+     */
+    setItem(key: string, value: string): Promise<void>;
+    /**
+     *
+     * @param key {string}
+     * @returns {Promise<void>}
+     */
+    removeItem(key: string): Promise<void>;
 }
 // @ts-ignore
 declare const localStorage: LocalStorage;
+declare const largeStorage: LargeStorage | undefined;
 
 declare interface TrayMenu {
     kind ?: "standard" | "checkmark" | "separator"
@@ -221,6 +446,8 @@ declare interface TypefaceParams {
 }
 
 declare function typeface_create(name: string, params: TypefaceParams): boolean;
+declare function typeface_get_fallback_fonts(): string[];
+declare function typeface_set_fallback_fonts(families: string[]): void;
 
 declare function env_exe_dir(): String;
 declare function env_exe_path(): String;
@@ -245,6 +472,7 @@ declare function fetch_response_status(rsp): Promise<number>;
 declare function fetch_response_headers(rsp): Promise<{name: string, value: string}[]>;
 declare function fetch_response_save(rsp, path: string): Promise<number>;
 declare function fetch_response_body_string(rsp): Promise<string>;
+declare function fetch_response_body_bytes(rsp): Promise<ArrayBuffer>;
 
 declare function AudioRef_create(path: string);
 declare function AudioRef_destroy(id): void;
@@ -299,6 +527,29 @@ declare function clearTimeout(timer: number): void;
 
 declare function setInterval(callback: () => void, interval: number): number;
 
+interface IdleDeadline {
+    /**
+     * Whether callback is called because timeout reached while app is busy
+     */
+    didTimeout: boolean;
+    /**
+     * Milliseconds left before next frame
+     */
+    timeRemaining(): number;
+}
+
+/**
+ * Call callback when no window is painting or animating
+ * @param callback
+ * @param options timeout in milliseconds after which callback is called even if app is busy
+ */
+declare function requestIdleCallback(
+    callback: (deadline: IdleDeadline) => void,
+    options?: { timeout?: number },
+): number;
+
+declare function cancelIdleCallback(id: number): void;
+
 declare function clearInterval(timer: number): void;
 
 
@@ -318,6 +569,44 @@ declare class Process {
      * @param code {number}
      */
     exit(code: number): void;
+    /**
+     * Register a callback called before app quits, async cleanup is awaited until quit timeout.
+     * Call event.cancel() to cancel quitting.
+     * @param callback {(event: BeforeQuitEvent) => (void | Promise<void>)}
+     * @returns {() => void} function to unregister the callback
+     */
+    onBeforeQuit(callback: (event: BeforeQuitEvent) => (void | Promise<void>)): () => void;
+    /**
+     * Register a callback called when app comes to foreground or goes to background,
+     * only reported on mobile platforms
+     * @param callback {(state: "foreground" | "background") => void}
+     * @returns {() => void} function to unregister the callback
+     */
+    onLifecycleChange(callback: (state: "foreground" | "background") => void): () => void;
+    /**
+     * Max milliseconds to wait for before-quit callbacks, default is 5000
+     * @param value {number}
+     */
+    set quitTimeout(value: number);
+    /**
+     * Save app state for crash recovery, it is written to disk periodically
+     * @param state {any} JSON serializable value
+     */
+    setRecoveryState(state: any): void;
+    /**
+     * Get the state saved by last session if it was not shut down cleanly
+     * @returns {any | null}
+     */
+    getRecoveredState(): any | null;
+    /**
+     * Drop recovery state of last session and current session
+     */
+    clearRecoveryState(): void;
+    /**
+     *
+     * @returns {boolean}
+     */
+    get isRecoveryEnabled(): boolean;
     /**
      *
      * @param value {boolean}
@@ -344,138 +633,826 @@ declare class Process {
      */
     setPromiseRejectionTracker(handler: Function): void;
 }
-declare class FileDialog {
+declare class Metrics {
     /**
      *
-     * @param options {ShowFileDialogOptions}
-     * @returns {Promise<string[]>}
+     * @param name {string}
+     * @param value {number}
      */
-    show(options: ShowFileDialogOptions): Promise<string[]>;
-}
-declare class Page {
-    constructor(handle: any);
-    handle: any;
-    close(): void;
-}
-declare class Popup {
-    constructor(handle: any);
-    handle: any;
-    close(): void;
-}
-declare class StandardMenuItem {
-    constructor(label: any, callback: any);
+    increment(name: string, value?: number): void;
     /**
      *
-     * @param value {boolean}
+     * @param name {string}
+     * @param ms {number}
      */
-    set disabled(value: boolean);
+    timing(name: string, ms: number): void;
     /**
-     *
-     * @returns {boolean}
+     * Measure time of callback, async callback is awaited
+     * @template T
+     * @param name {string}
+     * @param callback {() => T}
+     * @returns {T}
      */
-    get disabled(): boolean;
-    get handle(): any;
-    
-}
-declare class Menu {
+    measure<T>(name: string, callback: () => T): T;
+    /**
+     * Metrics are collected only after any exporter added
+     * @param options {MetricsExporterOptions}
+     */
+    addExporter(options: MetricsExporterOptions): void;
     /**
      *
-     * @param item {StandardMenuItem}
+     * @returns {boolean}
      */
-    addStandardItem(item: StandardMenuItem): void;
-    addSeparator(): void;
-    get handle(): any;
-    
+    get enabled(): boolean;
+    flush(): void;
 }
+declare const metrics: Metrics;
 /**
- * @typedef {IEvent<ResizeDetail>} IResizeEvent
+ * Token to cancel pending fs operations, cancelled operations are rejected
  */
-declare class Window {
+declare class CancelToken {
+    get id(): number;
+    get cancelled(): boolean;
+    cancel(): void;
     /**
-     *
-     * @param windowHandle
-     * @returns {Window}
+     * Release resources of token, it could not be used anymore
      */
-    static fromHandle(windowHandle: any): Window;
-    static supportMultipleWindows(): any;
+    release(): void;
+}
+type FsOptions = {
+    cancelToken?: CancelToken;
+};
+type FsStat = {
+    size: number;
+    is_dir: boolean;
+    is_file: boolean;
+};
+type FsDirEntry = {
+    name: string;
+    size: number;
+    is_dir: boolean;
+    is_file: boolean;
+    modified?: number;
+};
+declare class FileHandle {
+    constructor(handle: any);
     /**
-     *
-     * @param attrs {WindowAttrs}
+     * Read bytes into buffer from current position
+     * @returns {Promise<number>} bytes read, 0 means end of file
      */
-    constructor(attrs?: WindowAttrs);
-    get handle(): any;
+    read(buffer: Uint8Array, offset?: number, length?: number, options?: FsOptions): Promise<number>;
     /**
-     *
-     * @returns {BodyElement}
+     * Write data at current position, string is encoded as utf-8
+     * @returns {Promise<number>} bytes written
      */
-    get body(): BodyElement;
+    write(data: ArrayBuffer | Uint8Array | number[] | string, options?: FsOptions): Promise<number>;
     /**
-     *
-     * @returns {{width: number, height: number}}
+     * @returns {Promise<number>} new position from start
      */
-    get innerSize(): {
-        width: number;
-        height: number;
-    };
+    seek(offset: number, whence?: "start" | "current" | "end"): Promise<number>;
+    stat(): Promise<FsStat>;
+    truncate(length?: number): Promise<void>;
     /**
-     *
-     * @param content {Element}
-     * @param x {number}
-     * @param y {number}
-     * @return {Page}
+     * Flush and close file
      */
-    createPage(content: Element, x: number, y: number): Page;
+    close(): Promise<void>;
+}
+declare class FileSystem {
+    open(path: string, mode?: "r" | "r+" | "w" | "w+" | "wx" | "a" | "a+", options?: FsOptions): Promise<FileHandle>;
     /**
-     *
-     * @param content {Element}
-     * @param target {{x: number, y: number, width?: number, height?: number}}
-     * @return {Popup}
+     * List entries of dir with metadata
      */
-    popup(content: Element, target: {
-        x: number;
-        y: number;
-        width?: number;
-        height?: number;
-    }): Popup;
+    readDir(path: string, options?: FsOptions): Promise<FsDirEntry[]>;
+    stat(path: string): Promise<FsStat>;
     /**
-     *
-     * @param menu {Menu}
-     * @param x {number}
-     * @param y {number}
+     * Copy file or dir recursively
      */
-    popupMenu(menu: Menu, x: number, y: number): void;
+    copy(src: string, dest: string, options?: FsOptions): Promise<void>;
     /**
-     *
-     * @param message {string | Element}
-     * @param options {AlertOptions}
+     * Move file or dir
      */
-    showAlert(message: string | Element, options?: AlertOptions): void;
+    move(src: string, dest: string, options?: FsOptions): Promise<void>;
     /**
-     *
-     * @param message {string | Element}
-     * @param options {ConfirmOptions}
-     * @returns {Promise<boolean>}
+     * Remove file or dir recursively
      */
-    showConfirm(message: string | Element, options?: ConfirmOptions): Promise<boolean>;
+    remove(path: string, options?: FsOptions): Promise<void>;
+}
+declare const fs: FileSystem;
+/**
+ * Async iterator of items produced by rust streams
+ */
+declare class AsyncStream<T> implements AsyncIterableIterator<T> {
+    constructor(id: number);
+    next(): Promise<IteratorResult<T>>;
     /**
-     *
-     * @param content {Element}
-     * @param title {string}
-     * @returns {{close(): void}}
+     * Stop iterating, called by `break` in `for await` loops
      */
-    showDialog(content: Element, title: string): {
-        close(): void;
-    };
+    return(value?: any): Promise<IteratorResult<T>>;
+    [Symbol.asyncIterator](): AsyncIterableIterator<T>;
+}
+type InputLatencyEntry = {
+    windowId: number;
+    latency: number;
+    time: number;
+};
+type FrameStats = {
+    windowId: number;
+    frames: number;
+    droppedFrames: number;
+    fps: number;
+    refreshRate: number | null;
+};
+type SurfacePoolStats = {
+    budgetBytes: number;
+    liveBytes: number;
+    pooledBytes: number;
+    hits: number;
+    misses: number;
+};
+type GcStats = {
+    count: number;
+    idleCount: number;
+    forcedCount: number;
+    memoryPressureCount: number;
+    totalTime: number;
+    lastTime: number;
+    maxTime: number;
+    framesSinceLast: number;
+};
+declare class Performance {
     /**
-     *
-     * @param title {string}
+     * Milliseconds since app started
+     * @returns {number}
      */
-    set title(title: string);
+    now(): number;
     /**
-     *
-     * @returns {string}
+     * Latest latencies from receipt of input to completion of the next painted frame
+     * @returns {InputLatencyEntry[]}
      */
-    get title(): string;
+    getInputLatencies(): InputLatencyEntry[];
+    clearInputLatencies(): void;
+    /**
+     * Frames and dropped frames of each window since it is created or stats are reset
+     * @returns {FrameStats[]}
+     */
+    getFrameStats(): FrameStats[];
+    resetFrameStats(): void;
+    /**
+     * Layer surfaces held by renderers of all windows
+     * @returns {SurfacePoolStats}
+     */
+    getSurfacePoolStats(): SurfacePoolStats;
+    /**
+     * Limit bytes of layer surfaces, idle surfaces are released when exceeded
+     * @param bytes {number}
+     */
+    setSurfaceMemoryBudget(bytes: number): void;
+    /**
+     * Release idle layer surfaces, which is done automatically on memory pressure
+     */
+    trimSurfacePool(): void;
+}
+declare const performance: Performance;
+declare type MetricsExporterOptions = {
+    type: "log";
+} | {
+    type: "statsd";
+    address: string;
+    prefix?: string;
+} | {
+    type: "http";
+    url: string;
+};
+declare class RenderDebug {
+    /**
+     * Outline areas repainted in each frame
+     * @param value {boolean}
+     */
+    showRepaintAreas(value: boolean): void;
+    /**
+     * Outline bounds of composited layers
+     * @param value {boolean}
+     */
+    showLayerBorders(value: boolean): void;
+    /**
+     * Tint painted elements, areas painted many times become deeper red
+     * @param value {boolean}
+     */
+    showOverdraw(value: boolean): void;
+    /**
+     * Show layer count, cached surface memory and last frame time on top of windows
+     * @param value {boolean}
+     */
+    showHud(value: boolean): void;
+}
+declare class A11y {
+    /**
+     * Accessibility preferences of system, could also be tested in stylesheets with
+     * `@media (prefers-reduced-motion: reduce)`, `@media (prefers-contrast: more)`
+     * and `@media (min-text-scale: 1.5)`
+     * @returns {A11yPreferences}
+     */
+    get preferences(): A11yPreferences;
+    /**
+     * Override preferences of system, e.g. with settings of the app
+     * @param preferences {Partial<A11yPreferences>}
+     */
+    setPreferences(preferences: Partial<A11yPreferences>): void;
+}
+interface SystemMetricsInfo {
+    /**
+     * Accent color of system theme, like "#0078d4"
+     */
+    accentColor: string;
+    fontFamily: string;
+    /**
+     * Default font size in px
+     */
+    fontSize: number;
+    scrollbarWidth: number;
+    /**
+     * Max interval between clicks of a double click in milliseconds
+     */
+    doubleClickInterval: number;
+    /**
+     * Interval of caret switching between visible and hidden in milliseconds, 0 if not blinking
+     */
+    caretBlinkInterval: number;
+}
+declare class SystemMetrics {
+    /**
+     * Metrics of system, accent color, font and scrollbar width are also available in stylesheets as
+     * `var(--deft-accent-color)`, `var(--deft-system-font-family)`, `var(--deft-system-font-size)`
+     * and `var(--deft-scrollbar-width)`
+     */
+    get(): SystemMetricsInfo;
+    /**
+     * change is emitted when any metric changes with the theme of system
+     */
+    addEventListener(type: "change", listener: (metrics: SystemMetricsInfo) => void): void;
+    removeEventListener(type: "change", listener: (metrics: SystemMetricsInfo) => void): void;
+}
+/**
+ * Display connected to the system, bounds are in logical pixels of the monitor
+ */
+interface MonitorInfo {
+    index: number;
+    name: string | null;
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+    scaleFactor: number;
+    /**
+     * Refresh rate in Hz if available
+     */
+    refreshRate: number | null;
+    primary: boolean;
+}
+declare class Screen {
+    /**
+     * Monitors connected to the system, bounds are in logical pixels of each monitor
+     */
+    getMonitors(): MonitorInfo[];
+    /**
+     * monitorschange is emitted with all monitors when displays are added, removed or changed
+     */
+    addEventListener(type: "monitorschange", listener: (monitors: MonitorInfo[]) => void): void;
+    removeEventListener(type: "monitorschange", listener: (monitors: MonitorInfo[]) => void): void;
+}
+declare type ProtocolBody = string | ArrayBuffer | Uint8Array | number[];
+declare type ProtocolResult = ProtocolBody | {
+    status?: number;
+    headers?: Record<string, string>;
+    body?: ProtocolBody;
+};
+declare type ProtocolRequest = {
+    url: string;
+    method: string;
+    headers: Record<string, string>;
+    body?: string | ArrayBuffer;
+};
+/**
+ * Custom url scheme handlers used by fetch and image sources
+ */
+declare class Protocol {
+    /**
+     * Serve urls like `{scheme}://...` with handler, registering schemes like "https" intercepts the requests.
+     * Image sources require the handler to return synchronously, fetch accepts promises.
+     * Modules could only be served by handlers registered in rust.
+     */
+    register(scheme: string, handler: (request: ProtocolRequest) => ProtocolResult | Promise<ProtocolResult>): void;
+    unregister(scheme: string): void;
+    /**
+     * Fetch url with protocol handlers, null if no handler serves its scheme
+     */
+    static fetch(url: string, options?: FetchOptions): Promise<FetchResponse | null>;
+}
+interface ProxyConfig {
+    /**
+     * e.g. "http://host:port", "https://host:port" or "socks5://host:port"
+     */
+    url: string;
+    username?: string;
+    password?: string;
+    /**
+     * Hosts connected directly, e.g. "localhost" or ".example.com"
+     */
+    noProxy?: string[];
+}
+interface NetworkChangeEvent {
+    online: boolean;
+    /**
+     * Local address of the default route
+     */
+    address: string | null;
+}
+interface NetworkStatus {
+    online: boolean;
+    /**
+     * Only reported on some platforms like android, "unknown" otherwise
+     */
+    connectionType: "wifi" | "ethernet" | "cellular" | "unknown" | "none";
+}
+interface NetworkEventMap {
+    /**
+     * Default route changed
+     */
+    networkchange: NetworkChangeEvent;
+    /**
+     * Connectivity or connection type changed
+     */
+    networkstatuschange: NetworkStatus;
+}
+declare class Network {
+    /**
+     * Use proxy for http, fetch and websocket connections
+     */
+    setProxy(config: ProxyConfig): void;
+    /**
+     * Use proxy of system settings or environment variables like HTTPS_PROXY, which is the default
+     */
+    useSystemProxy(): void;
+    /**
+     * Connect directly without any proxy
+     */
+    disableProxy(): void;
+    /**
+     * Trust a root certificate besides the built-in ones
+     * @param pem PEM encoded certificate
+     */
+    addRootCertificate(pem: string): void;
+    /**
+     * Reject https and wss connections to host during tls handshakes unless its certificate matches
+     * one of the pinned hashes
+     * @param hash sha256 digest of DER encoded certificate, e.g. "sha256/<base64 digest>"
+     */
+    pinCertificate(host: string, hash: string): void;
+    /**
+     * Current connectivity, connection type is only reported on some platforms like android
+     */
+    status(): NetworkStatus;
+    addEventListener<K extends keyof NetworkEventMap>(type: K, listener: (event: NetworkEventMap[K]) => void): void;
+    removeEventListener<K extends keyof NetworkEventMap>(type: K, listener: (event: NetworkEventMap[K]) => void): void;
+}
+type DownloadState = "queued" | "downloading" | "paused" | "completed" | "failed" | "canceled";
+interface DownloadInfo {
+    id: number;
+    url: string;
+    path: string;
+    headers: Record<string, string>;
+    state: DownloadState;
+    received: number;
+    /**
+     * null if server does not report the size
+     */
+    total: number | null;
+    /**
+     * Bytes per second, zero unless downloading
+     */
+    speed: number;
+    error: string | null;
+}
+interface DownloadSummary {
+    queued: number;
+    downloading: number;
+    paused: number;
+    completed: number;
+    failed: number;
+    /**
+     * Bytes received of unfinished downloads
+     */
+    received: number;
+    /**
+     * Bytes to receive of unfinished downloads, null if any size is unknown
+     */
+    total: number | null;
+    speed: number;
+}
+interface DownloadEvent {
+    type: "progress" | "statechange";
+    download: DownloadInfo;
+    summary: DownloadSummary;
+}
+interface DownloadEventMap {
+    progress: DownloadEvent;
+    statechange: DownloadEvent;
+    /**
+     * Emitted whenever progress or state of any download changes
+     */
+    summary: DownloadSummary;
+}
+/**
+ * Queued downloads saved across restarts, unfinished downloads are resumed from their partial files
+ */
+declare class DownloadManager {
+    /**
+     * Queue a download, data is written to `${path}.part` and renamed to path when completed
+     * @returns id of download
+     */
+    add(url: string, path: string, options?: {headers?: Record<string, string>}): number;
+    pause(id: number): void;
+    /**
+     * Resume a paused or failed download
+     */
+    resume(id: number): void;
+    /**
+     * Stop the download and delete its partial file
+     */
+    cancel(id: number): void;
+    /**
+     * Remove a completed, failed or canceled download from the list, the downloaded file is kept
+     */
+    remove(id: number): void;
+    get(id: number): DownloadInfo | undefined;
+    list(): DownloadInfo[];
+    summary(): DownloadSummary;
+    /**
+     * Limit of downloads running at the same time, default is 3
+     */
+    setMaxConcurrent(max: number): void;
+    addEventListener<K extends keyof DownloadEventMap>(type: K, listener: (event: DownloadEventMap[K]) => void): void;
+    removeEventListener<K extends keyof DownloadEventMap>(type: K, listener: (event: DownloadEventMap[K]) => void): void;
+}
+interface OAuthConfig {
+    authorizationEndpoint: string;
+    tokenEndpoint: string;
+    clientId: string;
+    /**
+     * Only for confidential clients, native apps should rely on PKCE instead
+     */
+    clientSecret?: string;
+    scopes?: string[];
+    /**
+     * Loopback uri like "http://127.0.0.1:8765/callback" or custom scheme uri like "com.example.app:/oauth",
+     * defaults to a loopback uri with a free port
+     */
+    redirectUri?: string;
+    /**
+     * Extra params of the authorization request
+     */
+    params?: Record<string, string>;
+    /**
+     * Store refresh token in secure storage under this account, on android the activity should
+     * implement `secureStorageGet`, `secureStorageSet` and `secureStorageDelete`
+     */
+    account?: string;
+    /**
+     * Seconds to wait for the redirect, default is 300
+     */
+    timeout?: number;
+}
+interface OAuthTokens {
+    accessToken: string;
+    tokenType: string | null;
+    /**
+     * Milliseconds since epoch
+     */
+    expiresAt: number | null;
+    refreshToken: string | null;
+    idToken: string | null;
+    scope: string | null;
+}
+/**
+ * OAuth authorization code flow with PKCE in the system browser
+ */
+declare class OAuth {
+    authorize(config: OAuthConfig): Promise<OAuthTokens>;
+    /**
+     * @param refreshToken defaults to the token stored for config.account
+     */
+    refresh(config: OAuthConfig, refreshToken?: string): Promise<OAuthTokens>;
+    getRefreshToken(account: string): string | null;
+    /**
+     * Delete the refresh token stored for account
+     */
+    signOut(account: string): void;
+    /**
+     * Forward custom scheme redirect to the pending authorization, false if no authorization is waiting for it
+     */
+    handleRedirect(url: string): boolean;
+}
+interface Product {
+    id: string;
+    title: string;
+    description: string;
+    /**
+     * Localized price with currency symbol
+     */
+    price: string;
+    priceMicros: number;
+    currency: string;
+}
+interface Purchase {
+    productId: string;
+    transactionId: string;
+    purchaseTime: number;
+    state: "purchased" | "pending" | "restored";
+    /**
+     * JWS representation of the StoreKit 2 transaction on ios, purchase token on android
+     */
+    receipt: string;
+}
+/**
+ * In-app purchase with Play Billing on android and StoreKit on ios, bridged by the host app
+ */
+declare class InAppPurchase {
+    /**
+     * Whether the host app implements the store bridge
+     */
+    isSupported(): boolean;
+    queryProducts(ids: string[]): Promise<Product[]>;
+    purchase(productId: string): Promise<Purchase>;
+    restore(): Promise<Purchase[]>;
+    /**
+     * Acknowledge or consume a purchase after its content is delivered
+     * @param consume true for consumable products which could be purchased again
+     */
+    finish(purchase: Purchase, consume?: boolean): Promise<void>;
+    /**
+     * Post purchase to the verification server of app, resolved with json response of server
+     */
+    verify(url: string, purchase: Purchase): Promise<any>;
+    addEventListener(type: "purchaseupdate", listener: (purchases: Purchase[]) => void): void;
+    removeEventListener(type: "purchaseupdate", listener: (purchases: Purchase[]) => void): void;
+}
+/**
+ * Json config file in data dir, reloaded when edited by others
+ */
+declare class Config<T extends Record<string, any>> {
+    constructor(name: string);
+    static load<T extends Record<string, any>>(defaults?: T, name?: string): Config<T>;
+    get<K extends keyof T>(key: K): T[K];
+    /**
+     * Set value and save config file
+     */
+    set<K extends keyof T>(key: K, value: T[K]): void;
+    /**
+     * Listen changes, including edits of the file by others
+     */
+    addChangeListener(listener: (keys: string[]) => void): void;
+    removeChangeListener(listener: (keys: string[]) => void): void;
+}
+declare class Autofill {
+    /**
+     * Set desktop autofill provider, e.g. a password manager.
+     * The callback is called with autocomplete hint of the focused empty input, null to remove provider
+     * @param provider {((hint: string) => string | null) | null}
+     */
+    setProvider(provider: ((hint: string) => string | null) | null): void;
+}
+declare const autofill: Autofill;
+declare class FileDialog {
+    /**
+     *
+     * @param options {ShowFileDialogOptions}
+     * @returns {Promise<string[]>}
+     */
+    show(options: ShowFileDialogOptions): Promise<string[]>;
+}
+declare class Page {
+    constructor(handle: any);
+    handle: any;
+    close(): void;
+    /**
+     * Close page after animation finished, the page is closed anyway after timeout
+     * @param animation {() => Promise<void>}
+     * @param timeout {number} in milliseconds
+     */
+    closeWithAnimation(animation: () => Promise<void>, timeout?: number): Promise<void>;
+}
+/**
+ * Error rejected by async native functions. Sync native functions throw messages of errors as
+ * strings, because callbacks of the js engine could not throw objects.
+ */
+declare class DeftError extends Error {
+    /**
+     * Kind of error, e.g. "ERR_INVALID_STATE", "ERR_NOT_FOUND" and "ERR_IO"
+     */
+    code?: string;
+    cause?: DeftError;
+}
+declare class Popup {
+    constructor(handle: any);
+    handle: any;
+    close(): void;
+    /**
+     * Actual position and placement after flipping, null before the content is laid out
+     * @returns {PopupPlacement | null}
+     */
+    get placement(): PopupPlacement | null;
+}
+declare class StandardMenuItem {
+    constructor(label: any, callback: any);
+    /**
+     *
+     * @param value {boolean}
+     */
+    set disabled(value: boolean);
+    /**
+     *
+     * @returns {boolean}
+     */
+    get disabled(): boolean;
+    /**
+     * Shortcut hint shown after label, the shortcut itself should be handled by the app
+     * @param value {string | null} e.g. "CmdOrCtrl+S", which is shown as "Cmd+S" on macos
+     */
+    set accelerator(value: string | null);
+    /**
+     *
+     * @returns {string | null}
+     */
+    get accelerator(): string | null;
+    get handle(): any;
+    
+}
+declare class Menu {
+    /**
+     *
+     * @param item {StandardMenuItem}
+     */
+    addStandardItem(item: StandardMenuItem): void;
+    addSeparator(): void;
+    get handle(): any;
+    
+}
+interface NativeMenuItem {
+    /**
+     * Passed to menuselect listeners
+     */
+    id?: string;
+    /**
+     * Inferred from items and checked if missing
+     */
+    kind?: "standard" | "checkmark" | "separator" | "submenu";
+    label?: string;
+    /**
+     * e.g. "CmdOrCtrl+Shift+S"
+     */
+    accelerator?: string;
+    enabled?: boolean;
+    checked?: boolean;
+    items?: NativeMenuItem[];
+}
+interface MenuSelectDetail {
+    id: string | null;
+    /**
+     * New state of checkmark items
+     */
+    checked: boolean | null;
+}
+/**
+ * Menu shown by native widgets on windows and macos, rendered by elements on other platforms
+ */
+declare class NativeMenu {
+    constructor(items: NativeMenuItem[]);
+    /**
+     * Show as context menu at position relative to window
+     */
+    popup(window: Window, x: number, y: number): void;
+    setChecked(id: string, checked: boolean): void;
+    setEnabled(id: string, enabled: boolean): void;
+    /**
+     * menuselect is emitted when an item is selected, checkmark items are toggled before
+     */
+    addEventListener(type: "menuselect", listener: (e: IEvent<MenuSelectDetail>) => void): void;
+    removeEventListener(type: "menuselect", listener: (e: IEvent<MenuSelectDetail>) => void): void;
+    get handle(): any;
+}
+/**
+ * @typedef {IEvent<ResizeDetail>} IResizeEvent
+ * @typedef {IEvent<ImeDetail>} IImeEvent
+ * @typedef {IEvent<ZoomDetail>} IZoomEvent
+ * @typedef {IEvent<RendererChangeDetail>} IRendererChangeEvent
+ * @typedef {IEvent<VisibilityChangeDetail>} IVisibilityChangeEvent
+ * @typedef {IEvent<SnapDetail>} ISnapEvent
+ * @typedef {IEvent<ThemeChangeDetail>} IThemeChangeEvent
+ */
+declare class Window {
+    /**
+     *
+     * @param windowHandle
+     * @returns {Window}
+     */
+    static fromHandle(windowHandle: any): Window;
+    static supportMultipleWindows(): any;
+    /**
+     *
+     * @param attrs {WindowAttrs}
+     */
+    constructor(attrs?: WindowAttrs);
+    get handle(): any;
+    /**
+     *
+     * @returns {BodyElement}
+     */
+    get body(): BodyElement;
+    /**
+     *
+     * @returns {{width: number, height: number}}
+     */
+    get innerSize(): {
+        width: number;
+        height: number;
+    };
+    /**
+     *
+     * @param content {Element}
+     * @param x {number}
+     * @param y {number}
+     * @return {Page}
+     */
+    createPage(content: Element, x: number, y: number): Page;
+    /**
+     *
+     * @param content {Element}
+     * @param target {{x: number, y: number, width?: number, height?: number}}
+     * @param options {PopupOptions} placement relative to target, defaults to "bottom-start"
+     *  flipping and shifting to stay inside the window or screen
+     * @return {Popup}
+     */
+    popup(content: Element, target: {
+        x: number;
+        y: number;
+        width?: number;
+        height?: number;
+    }, options?: PopupOptions): Popup;
+    /**
+     *
+     * @param menu {Menu}
+     * @param x {number}
+     * @param y {number}
+     */
+    popupMenu(menu: Menu | NativeMenu, x: number, y: number): void;
+    /**
+     * Show menu as native menubar on windows and macos, where the menubar is shared by the app
+     * and top level items should be submenus. Throws on other platforms, e.g. linux where windows
+     * are not created by gtk, render the menubar with elements there.
+     */
+    setMenu(menu: NativeMenu | null): void;
+    /**
+     * Register shortcut handled before key events of elements, keys are matched by physical position
+     * so that shortcuts work with any keyboard layout. Shortcuts of window take precedence over
+     * shortcuts registered by `deft.registerShortcut`. Throws if the shortcut is registered already.
+     * @param accelerator e.g. "Ctrl+Shift+P", "CmdOrCtrl+S", "Alt+F4"
+     * @returns id of shortcut
+     */
+    registerShortcut(accelerator: string, callback: () => void): number;
+    unregisterShortcut(id: number): void;
+    setShortcutEnabled(id: number, enabled: boolean): void;
+    /**
+     *
+     * @param message {string | Element}
+     * @param options {AlertOptions}
+     */
+    showAlert(message: string | Element, options?: AlertOptions): void;
+    /**
+     *
+     * @param message {string | Element}
+     * @param options {ConfirmOptions}
+     * @returns {Promise<boolean>}
+     */
+    showConfirm(message: string | Element, options?: ConfirmOptions): Promise<boolean>;
+    /**
+     *
+     * @param content {Element}
+     * @param title {string}
+     * @returns {{close(): void}}
+     */
+    showDialog(content: Element, title: string): {
+        close(): void;
+    };
+    /**
+     *
+     * @param title {string}
+     */
+    set title(title: string);
+    /**
+     *
+     * @returns {string}
+     */
+    get title(): string;
     /**
      *
      * @returns {{width: number, height: number}}
@@ -489,6 +1466,11 @@ declare class Window {
      * @param size {Size}
      */
     resize(size: Size): void;
+    /**
+     * Move window to the center of monitor
+     * @param index {number} index of monitor in `deft.screen.getMonitors()`
+     */
+    moveToMonitor(index: number): void;
     drag(): void;
     /**
      *
@@ -532,6 +1514,18 @@ declare class Window {
         y: number;
     };
     close(): void;
+    /**
+     * Close window after animation finished, the window is closed anyway after timeout
+     * @param animation {() => Promise<void>}
+     * @param timeout {number} in milliseconds
+     */
+    closeWithAnimation(animation: () => Promise<void>, timeout?: number): void;
+    /**
+     * Play animation before window is closed by user or close(), e.g. fading out
+     * @param animation {(() => Promise<void>) | null} null to remove animation
+     * @param timeout {number} in milliseconds
+     */
+    setCloseAnimation(animation: (() => Promise<void>) | null, timeout?: number): void;
     /**
      *
      * @param visible {boolean}
@@ -546,32 +1540,309 @@ declare class Window {
     exitFullscreen(): void;
     get fullscreen(): any;
     /**
-     *
-     * @param callback {(event: IResizeEvent) => void}
+     * Release pointer locked by element.requestPointerLock
+     */
+    exitPointerLock(): void;
+    /**
+     * Kiosk mode shows window in fullscreen on top of other windows, confines cursor in it and
+     * ignores closing by users, e.g. Alt+F4 or close shortcuts. Kiosk mode entered with passcode
+     * could only be exited with the same passcode, an error is thrown if passcode mismatches.
+     * @param enabled {boolean}
+     * @param passcode {string | undefined}
+     */
+    setKiosk(enabled: boolean, passcode?: string): void;
+    /**
+     * @returns {boolean}
+     */
+    isKiosk(): boolean;
+    /**
+     * Stats of the last painted frame
+     * @returns {RenderStats}
+     */
+    get renderStats(): RenderStats;
+    /**
+     * Highlight text matching query and scroll the first match into view, press Escape to clear
+     * @param query {string}
+     * @param options {FindOptions}
+     * @returns {FindResult}
+     */
+    findInPage(query: string, options?: FindOptions): FindResult;
+    /**
+     * Attached element whose `id` attribute is id
+     * @param id {string}
+     * @returns {Element | null}
+     */
+    getElementById(id: string): Element | null;
+    /**
+     * Attached element whose `data-testid` attribute is testId, for ui automation
+     * @param testId {string}
+     * @returns {Element | null}
+     */
+    getElementByTestId(testId: string): Element | null;
+    /**
+     * Feed synthetic input through the same handlers as real input, e.g.
+     * `{type: "click", x: 10, y: 20}` or `{type: "keydown", key: "a", ctrlKey: true}`.
+     * Modifier keys pressed by injected keydown are held until injected keyup
+     * @param input {InjectedInput}
+     */
+    injectInput(input: InjectedInput): void;
+    /**
+     * Record input events of window to file as json lines, which could be replayed to reproduce bugs
+     * @param path {string}
+     */
+    startInputRecording(path: string): void;
+    stopInputRecording(): void;
+    /**
+     * Inject recorded inputs with original intervals divided by speed. Timers are frozen and advanced
+     * by recorded intervals unless deterministicTimers is false, only one replay could freeze timers
+     * at a time
+     * @param path {string}
+     * @param options {ReplayOptions}
+     */
+    replayInput(path: string, options?: ReplayOptions): void;
+    stopInputReplay(): void;
+    /**
+     * Fired when all inputs of a replay are injected
+     * @param callback {(event: IVoidEvent) => void}
+     */
+    bindInputReplayEnd(callback: (event: IVoidEvent) => void): void;
+    /**
+     * Move to next match of findInPage
+     * @returns {FindResult}
+     */
+    findNext(): FindResult;
+    /**
+     * Move to previous match of findInPage
+     * @returns {FindResult}
+     */
+    findPrevious(): FindResult;
+    /**
+     * Clear highlights of findInPage
+     */
+    clearFind(): void;
+    /**
+     * Set hit margin of edge resizing for undecorated window
+     * @param margin {number}
+     */
+    set resizeMargin(margin: number);
+    /**
+     *
+     * @param edges {"all" | "corners" | "none"}
+     */
+    set resizeEdges(edges: "all" | "corners" | "none");
+    /**
+     * Distance in logical pixels within which window dragged by `drag` snaps to screen edges
+     * or other windows, 0 to disable
+     * @param threshold {number}
+     */
+    set snapThreshold(threshold: number);
+    /**
+     * @returns {number}
+     */
+    get snapThreshold(): number;
+    /**
+     * Material drawn behind the window, only windows created with transparent, effect or shape
+     * attributes support effects
+     * @param effect {WindowEffect}
+     */
+    set effect(effect: WindowEffect);
+    /**
+     * @returns {WindowEffect}
+     */
+    get effect(): WindowEffect;
+    /**
+     * Shape of window as svg path in logical pixels, pixels out of it are transparent.
+     * Window is restored to rectangle if shape is null.
+     * @param shape {string | null}
+     */
+    set shape(shape: string | null);
+    /**
+     * Fired when dragged window snaps to or is pulled away from screen edges or other windows
+     * @param callback {(event: ISnapEvent) => void}
+     */
+    bindSnap(callback: (event: ISnapEvent) => void): void;
+    /**
+     *
+     * @param callback {(event: IResizeEvent) => void}
+     */
+    bindResize(callback: (event: IResizeEvent) => void): void;
+    /**
+     * Storage namespace of the window, specified by storageNamespace attribute
+     * @returns {LocalStorage}
+     */
+    get localStorage(): LocalStorage;
+    /**
+     *
+     * @param callback {(event: IStorageEvent) => void}
+     */
+    bindStorage(callback: (event: IStorageEvent) => void): void;
+    /**
+     *
+     * @param callback {(event: IVoidEvent) => void}
+     */
+    bindResizeStart(callback: (event: IVoidEvent) => void): void;
+    /**
+     *
+     * @param callback {(event: IVoidEvent) => void}
+     */
+    bindResizeEnd(callback: (event: IVoidEvent) => void): void;
+    /**
+     *
+     * @param callback {(event: IVoidEvent) => void}
+     */
+    bindClose(callback: (event: IVoidEvent) => void): void;
+    /**
+     *
+     * @param callback {(event: IVoidEvent) => void}
+     */
+    bindFocus(callback: (event: IVoidEvent) => void): void;
+    /**
+     *
+     * @param callback {(event: IVoidEvent) => void}
+     */
+    bindBlur(callback: (event: IVoidEvent) => void): void;
+    /**
+     * Fired when soft keyboard is shown, hidden or resized
+     * @param callback {(event: IImeEvent) => void}
+     */
+    bindIme(callback: (event: IImeEvent) => void): void;
+    /**
+     * Show soft keyboard, e.g. for custom editing surfaces. No-op on desktop platforms
+     */
+    showSoftKeyboard(): void;
+    hideSoftKeyboard(): void;
+    /**
+     * Scale layout and rendering of the window, independent of scale factor of system.
+     * Ctrl+= and Ctrl+- change zoom by default, Ctrl+0 resets it.
+     * Zoom is saved in localstorage of the window if enabled
+     * @param factor {number} 1.0 is the default size
+     */
+    setZoom(factor: number): void;
+    /**
+     *
+     * @returns {number}
+     */
+    getZoom(): number;
+    /**
+     * Fired when zoom of window changed
+     * @param callback {(event: IZoomEvent) => void}
+     */
+    bindZoom(callback: (event: IZoomEvent) => void): void;
+    /**
+     * Color space of window surface, colors and images with embedded color profiles are
+     * converted to it when drawing. Use "display-p3" on wide gamut monitors
+     * @param colorSpace {"srgb" | "display-p3"}
+     */
+    setColorSpace(colorSpace: SurfaceColorSpace): void;
+    /**
+     * @returns {"srgb" | "display-p3"}
+     */
+    getColorSpace(): SurfaceColorSpace;
+    /**
+     * Request hdr surface for viewing hdr images or videos, which requires gl renderer on
+     * windows or macos. Sdr content is drawn at sdr white level
+     * @param enabled {boolean}
+     * @returns {boolean} whether hdr is active
+     */
+    setHdrEnabled(enabled: boolean): boolean;
+    /**
+     * @returns {boolean}
+     */
+    isHdr(): boolean;
+    /**
+     * Luminance which sdr white is mapped to on hdr surface, usually "sdr content brightness"
+     * of system display settings
+     * @param nits {number} defaults to 80
+     */
+    setSdrWhiteLevel(nits: number): void;
+    /**
+     * Name of render backend, e.g. "gl" or "softbuffer"
+     * @returns {string}
+     */
+    getRenderer(): string;
+    /**
+     * Render content of window to png image without presenting it, works with headless backend
+     * @returns {Uint8Array}
+     */
+    renderToImage(): Uint8Array;
+    /**
+     * Snapshot of layers, element objects, matrices, invalid areas and paint styles, which could be
+     * attached to rendering bug reports
+     * @param options {RenderTreeDumpOptions}
+     * @returns {RenderTreeDump}
+     */
+    dumpRenderTree(options?: RenderTreeDumpOptions): RenderTreeDump;
+    /**
+     * Fired when render backend changed, e.g. fell back to software rendering after gpu context lost
+     * @param callback {(event: IRendererChangeEvent) => void}
+     */
+    bindRendererChange(callback: (event: IRendererChangeEvent) => void): void;
+    /**
+     * Whether window is minimized or fully covered by other windows
+     * @returns {boolean}
+     */
+    isOccluded(): boolean;
+    /**
+     * Stop producing frames while window is occluded, default true
+     * @param value {boolean}
+     */
+    set pauseWhenOccluded(value: boolean);
+    /**
+     * @returns {boolean}
+     */
+    get pauseWhenOccluded(): boolean;
+    /**
+     * Maximum frame rate of window, 0 to follow refresh rate of monitor again
+     * @param fps {number}
+     */
+    set maxFps(fps: number);
+    /**
+     * @returns {number} the cap, refresh rate of monitor if not capped
+     */
+    get maxFps(): number;
+    /**
+     * Frame rate while window is not focused, 0 to disable throttling, not throttled by default
+     * @param fps {number}
+     */
+    set unfocusedFps(fps: number);
+    /**
+     * @returns {number}
+     */
+    get unfocusedFps(): number;
+    /**
+     * Same as setting `maxFps`
+     * @param fps {number}
      */
-    bindResize(callback: (event: IResizeEvent) => void): void;
+    setMaxFps(fps: number): void;
     /**
-     *
-     * @param callback {(event: IVoidEvent) => void}
+     * Current frame rate after caps and throttling are applied
+     * @returns {number}
      */
-    bindClose(callback: (event: IVoidEvent) => void): void;
+    getFps(): number;
     /**
-     *
-     * @param callback {(event: IVoidEvent) => void}
+     * Fired when window becomes occluded or visible again, not reported on all platforms
+     * @param callback {(event: IVisibilityChangeEvent) => void}
      */
-    bindFocus(callback: (event: IVoidEvent) => void): void;
+    bindVisibilityChange(callback: (event: IVisibilityChangeEvent) => void): void;
     /**
-     *
-     * @param callback {(event: IVoidEvent) => void}
+     * Theme of window, "light" or "dark" unless overridden by `DEFT_THEME`
+     * @returns {string}
      */
-    bindBlur(callback: (event: IVoidEvent) => void): void;
+    get theme(): string;
+    /**
+     * Fired when theme of system changed, stylesheets could test it with
+     * `@media (prefers-color-scheme: dark)`
+     * @param callback {(event: IThemeChangeEvent) => void}
+     */
+    bindThemeChange(callback: (event: IThemeChangeEvent) => void): void;
     bindEvent(type: any, callback: any): void;
     /**
      * @typedef {("resize", event)} addEventListener
      * @param type
      * @param callback
+     * @param options {EventListenerOptions | undefined}
      */
-    addEventListener(type: any, callback: any): void;
+    addEventListener(type: any, callback: any, options?: EventListenerOptions): void;
     removeEventListener(type: any, callback: any): void;
     
 }
@@ -596,6 +1867,12 @@ declare class EventObject<D, E> {
      * @type {E}
      */
     currentTarget: E;
+    /**
+     * Path from root to target element with `id` and `data-testid` attributes, empty if target
+     * is not an element
+     * @returns {ElementPathEntry[]}
+     */
+    get targetPath(): ElementPathEntry[];
     stopPropagation(): void;
     preventDefault(): void;
     result(): {
@@ -612,11 +1889,32 @@ declare class EventRegistry {
     bindEvent(type: any, callback: any): void;
     
 }
+type EventListenerOptions = {
+    once?: boolean;
+    passive?: boolean;
+};
+type EventEmitterListenerOptions = {
+    once?: boolean;
+    /**
+     * Object holding the listener, e.g. an element or a window, the listener is released with it.
+     * Without WeakRef support the owner is kept alive until all of its listeners are removed.
+     */
+    owner?: object;
+};
 declare class EventBinder {
     constructor(target: any, addApi: any, removeApi: any, self: any, contextGetter: any);
     bindEvent(type: any, callback: any): void;
-    addEventListener(type: any, callback: any): any;
+    /**
+     * Listeners of once option are removed after called once, and passive listeners can't
+     * prevent default behaviors. Listeners are released with the target, the callback must not
+     * keep the target alive.
+     * @param type {string}
+     * @param callback {Function}
+     * @param options {EventListenerOptions | undefined}
+     */
+    addEventListener(type: string, callback: Function, options?: EventListenerOptions): any;
     removeEventListener(type: any, callback: any): void;
+    removeAllEventListeners(): void;
     
 }
 declare class SystemTray {
@@ -635,6 +1933,13 @@ declare class SystemTray {
 }
 declare class Element {
     static fromHandle(elementHandle: any): any;
+    /**
+     * Take an element detached by `detachForReuse` out of the pool of tag, a new element is
+     * created if the pool is empty
+     * @param tag {string}
+     * @returns {Element}
+     */
+    static createFromPool(tag: string): Element;
     /**
      *
      * @param el {any}
@@ -650,6 +1955,23 @@ declare class Element {
      */
     handle: number;
     createEventBinder(target: any, addEventListenerApi: any, removeEventListenerApi: any): EventBinder;
+    /**
+     * Remove element from its parent and keep it in the pool of its tag for reuse by
+     * `Element.createFromPool`, children are removed so that they are not retained by the pool.
+     * Event listeners, styles, classes, attributes and backend state like text are reset when
+     * it's taken out of the pool. Elements are dropped if the pool is full.
+     */
+    detachForReuse(): void;
+    /**
+     * Release resources retained by an element before it's pooled
+     * @protected
+     */
+    protected _clearForPool(): void;
+    /**
+     * Reset state of an element taken out of the pool
+     * @protected
+     */
+    protected _resetForReuse(): void;
     /**
      * Get the eid of the element
      * @returns {number}
@@ -690,6 +2012,22 @@ declare class Element {
      * @returns {boolean}
      */
     get focusable(): boolean;
+    /**
+     * Make element a focus scope, which remembers its last focused descendant and restores it
+     * when the scope is focused again, e.g. after a dialog is closed or a tab is reselected
+     * @param value {boolean}
+     */
+    set focusScope(value: boolean);
+    /**
+     * Whether element is a focus scope
+     * @returns {boolean}
+     */
+    get focusScope(): boolean;
+    /**
+     * Whether element or one of its descendants is under the mouse cursor
+     * @returns {boolean}
+     */
+    get hover(): boolean;
     /**
      * Get the root of current element
      * @returns {Element}
@@ -699,6 +2037,50 @@ declare class Element {
      * Request focus on the current element
      */
     focus(): void;
+    /**
+     * Show element covering the whole window, until exitFullscreen is called or Escape is pressed.
+     * The backdrop could be styled with `body[fullscreen-backdrop]` selector
+     */
+    requestFullscreen(): void;
+    exitFullscreen(): void;
+    /**
+     *
+     * @returns {boolean}
+     */
+    get fullscreen(): boolean;
+    /**
+     * Hide cursor and report relative motion of mouse as movementX/movementY of mousemove events,
+     * until exitPointerLock is called, Escape is pressed or window loses focus
+     */
+    requestPointerLock(): void;
+    exitPointerLock(): void;
+    /**
+     * @returns {boolean}
+     */
+    get pointerLocked(): boolean;
+    /**
+     *
+     * @param callback {(event: IPointerLockChangeEvent) => void}
+     */
+    bindPointerLockChange(callback: (event: IPointerLockChangeEvent) => void): void;
+    /**
+     * Move element into a frameless always-on-top window, e.g. picture-in-picture of a player.
+     * State and event listeners of element are kept, and it is moved back when the window is closed
+     * or popIn is called
+     * @param options {{width?: number, height?: number, title?: string}}
+     * @returns {Window}
+     */
+    popOut(options?: {
+        width?: number;
+        height?: number;
+        title?: string;
+    }): Window;
+    popIn(): void;
+    /**
+     *
+     * @returns {boolean}
+     */
+    get poppedOut(): boolean;
     set tooltip(text: any);
     get tooltip(): any;
     /**
@@ -746,6 +2128,51 @@ declare class Element {
      * @returns {number}
      */
     get scrollLeft(): number;
+    /**
+     * Distance to the top or bottom edge of content within which reachtop or reachbottom is emitted
+     * @param value {number}
+     */
+    set scrollEdgeThreshold(value: number);
+    /**
+     * Distance to the top or bottom edge of content within which reachtop or reachbottom is emitted
+     * @returns {number}
+     */
+    get scrollEdgeThreshold(): number;
+    /**
+     * Save scroll position together with the element at the top edge of viewport
+     * @returns {ScrollState}
+     */
+    saveScrollState(): ScrollState;
+    /**
+     * Restore scroll position after the next layout, keeping the saved anchor element at the same position if it still exists
+     * @param state {ScrollState}
+     */
+    restoreScrollState(state: ScrollState): void;
+    /**
+     * Scroll to the given offsets, animated if behavior is "smooth"
+     * @param options {ScrollToOptions}
+     */
+    scrollTo(options: ScrollToOptions): void;
+    /**
+     * Fraction of fling velocity kept every millisecond after touch is released
+     * @param value {"normal" | "fast" | number}
+     */
+    set scrollDecelerationRate(value: "normal" | "fast" | number);
+    /**
+     * Fraction of fling velocity kept every millisecond after touch is released
+     * @returns {number}
+     */
+    get scrollDecelerationRate(): number;
+    /**
+     * Whether content could be dragged or flung beyond the edges and spring back
+     * @param value {boolean}
+     */
+    set scrollBounce(value: boolean);
+    /**
+     * Whether content could be dragged or flung beyond the edges and spring back
+     * @returns {boolean}
+     */
+    get scrollBounce(): boolean;
     /**
      * Make element draggable
      * @param value {boolean}
@@ -776,6 +2203,22 @@ declare class Element {
      * @returns {ElementRect}
      */
     getBoundingClientRect(): ElementRect;
+    /**
+     * Path from root to element with `id` and `data-testid` attributes, for ui automation
+     * @returns {ElementPathEntry[]}
+     */
+    getPath(): ElementPathEntry[];
+    /**
+     * Dump the layout tree of element, including style inputs and computed layout of every node
+     * @returns {LayoutDump}
+     */
+    debugLayout(): LayoutDump;
+    /**
+     * Trace which default style, css rule or inline style wins the cascade for a property
+     * @param property {string} e.g. "width" or "background-color"
+     * @returns {StyleTrace}
+     */
+    debugComputedStyle(property: string): StyleTrace;
     /**
      * The scrollWidth read-only property is a measurement of the height of an element's content, including content not visible on the screen due to overflow.
      * @returns {number}
@@ -794,6 +2237,11 @@ declare class Element {
      * @param callback {(event: IBoundsChangeEvent) => void}
      */
     bindBoundsChange(callback: (event: IBoundsChangeEvent) => void): void;
+    /**
+     *
+     * @param callback {(event: IFullscreenChangeEvent) => void}
+     */
+    bindFullscreenChange(callback: (event: IFullscreenChangeEvent) => void): void;
     /**
      *
      * @param callback {(event: IVoidEvent) => void}
@@ -851,6 +2299,21 @@ declare class Element {
     bindKeyUp(callback: (e: IKeyEvent) => void): void;
     bindSizeChanged(callback: any): void;
     bindScroll(callback: any): void;
+    /**
+     *
+     * @param callback {(e: IScrollEvent) => void}
+     */
+    bindScrollEnd(callback: (e: IScrollEvent) => void): void;
+    /**
+     *
+     * @param callback {(e: IVoidEvent) => void}
+     */
+    bindReachTop(callback: (e: IVoidEvent) => void): void;
+    /**
+     *
+     * @param callback {(e: IVoidEvent) => void}
+     */
+    bindReachBottom(callback: (e: IVoidEvent) => void): void;
     bindMouseWheel(callback: any): void;
     bindDragStart(callback: any): void;
     bindDragOver(callback: any): void;
@@ -859,6 +2322,21 @@ declare class Element {
     bindTouchMove(callback: any): void;
     bindTouchEnd(callback: any): void;
     bindTouchCancel(callback: any): void;
+    /**
+     * Two-finger pinch
+     * @param callback {(e: IGestureZoomEvent) => void}
+     */
+    bindGestureZoom(callback: (e: IGestureZoomEvent) => void): void;
+    /**
+     * Two-finger rotation
+     * @param callback {(e: IGestureRotateEvent) => void}
+     */
+    bindGestureRotate(callback: (e: IGestureRotateEvent) => void): void;
+    /**
+     * Two fingers moving together
+     * @param callback {(e: IGesturePanEvent) => void}
+     */
+    bindGesturePan(callback: (e: IGesturePanEvent) => void): void;
     /**
      *
      * @param callback {(e: IDroppedFileEvent) => void}
@@ -870,6 +2348,24 @@ declare class Element {
      */
     bindHoveredFile(callback: (e: IHoveredFileEvent) => void): void;
     bindEvent(type: any, callback: any): void;
+    /**
+     * @param type {string}
+     * @param callback {Function}
+     * @param options {EventListenerOptions | undefined} passive listeners can't prevent default
+     * behaviors, e.g. scrolling by wheel
+     */
+    addEventListener(type: string, callback: Function, options?: EventListenerOptions): void;
+    removeEventListener(type: any, callback: any): void;
+    /**
+     * Dispatch a custom event to this element, listeners receive detail as event.detail
+     * @param type {string}
+     * @param detail {any}
+     * @param options {{bubbles?: boolean}}
+     * @returns {boolean} false if any listener called preventDefault
+     */
+    dispatchEvent(type: string, detail: any, options: {
+        bubbles?: boolean;
+    }): boolean;
     /**
      *
      * @param value {boolean}
@@ -906,6 +2402,16 @@ declare class LabelElement extends Element {
      * @param text {string}
      */
     set text(text: string);
+    /**
+     * Whether some lines are hidden by -deft-line-clamp
+     * @returns {boolean}
+     */
+    isTruncated(): boolean;
+    /**
+     *
+     * @param callback {(e: ITruncationChangeEvent) => void}
+     */
+    bindTruncationChange(callback: (e: ITruncationChangeEvent) => void): void;
 }
 declare class CheckboxElement extends Element {
     constructor();
@@ -1016,69 +2522,233 @@ declare class SelectElement extends Element {
      */
     get placeholder(): string;
     /**
-     *
-     * @param value {boolean}
+     *
+     * @param value {boolean}
+     */
+    set disabled(value: boolean);
+    /**
+     *
+     * @returns {boolean}
+     */
+    get disabled(): boolean;
+    bindChange(callback: any): void;
+}
+/**
+ * @typedef {{
+ *   type: "text" | "link" | "ruby" | "image" | "embed",
+ *   text ?: string,
+ *   href ?: string,
+ *   annotation ?: string,
+ *   src ?: string,
+ *   width ?: number,
+ *   height ?: number,
+ *   verticalAlign ?: "baseline" | "top" | "middle" | "bottom",
+ *   element ?: Element,
+ *   weight ?: string,
+ *   textDecorationLine ?: string,
+ *   fontFamilies ?: string[],
+ *   fontSize ?: number,
+ *   color ?: string,
+ *   backgroundColor ?: string
+ * }} TextUnit
+ */
+declare class RichTextElement extends Element {
+    constructor();
+    /**
+     * Detach an element embedded by embed units, lines referring it should be updated too
+     * @param child {Element}
+     */
+    removeChild(child: Element): void;
+    /**
+     *
+     * @param units {TextUnit[]}
+     */
+    addLine(units: TextUnit[]): void;
+    /**
+     * Whether some lines are hidden by -deft-line-clamp
+     * @returns {boolean}
+     */
+    isTruncated(): boolean;
+    /**
+     *
+     * @param index {number}
+     * @param units {TextUnit[]}
+     */
+    insertLine(index: number, units: TextUnit[]): void;
+    /**
+     *
+     * @param index {number}
+     */
+    deleteLine(index: number): void;
+    /**
+     *
+     * @param index {number}
+     * @param units {TextUnit[]}
+     */
+    updateLine(index: number, units: TextUnit[]): void;
+    clear(): void;
+    /**
+     *
+     * @param units {TextUnit[]}
+     * @return {[number, number]}
+     */
+    measureLine(units: TextUnit[]): [number, number];
+    /**
+     *
+     * @returns {string | undefined}
+     */
+    get selectionText(): string | undefined;
+    /**
+     *
+     * @param callback {(e: ILinkClickEvent) => void}
+     */
+    bindLinkClick(callback: (e: ILinkClickEvent) => void): void;
+    /**
+     *
+     * @param callback {(e: ILinkHoverEvent) => void}
+     */
+    bindLinkHover(callback: (e: ILinkHoverEvent) => void): void;
+}
+/**
+ * Editor of text with bold, italic, underline, links and inline images
+ */
+declare class RichTextEditElement extends Element {
+    constructor();
+    /**
+     * Replace content and clear edit history
+     * @param content {RichContent}
+     */
+    set content(content: RichContent);
+    /**
+     * Content in delta format, lines are separated by "\n" in text inserts
+     * @returns {RichContent}
+     */
+    get content(): RichContent;
+    /**
+     * Plain text, images are replaced by object replacement characters
+     * @returns {string}
+     */
+    get text(): string;
+    /**
+     *
+     * @returns {string | undefined}
+     */
+    get selectionText(): string | undefined;
+    /**
+     * Attributes shared by selected text, or attributes of text typed at caret
+     * @returns {RichTextAttributes}
+     */
+    getFormat(): RichTextAttributes;
+    /**
+     * Format selected text, or text typed next if nothing is selected. An empty link removes links
+     * @param format {RichTextFormat}
+     */
+    format(format: RichTextFormat): void;
+    /**
+     * Replace selection with an inline image
+     * @param image {RichTextImage}
+     */
+    insertImage(image: RichTextImage): void;
+    /**
+     *
+     * @param start {number}
+     * @param end {number}
+     */
+    setSelectionByCharOffset(start: number, end: number): void;
+    /**
+     *
+     * @param maxHistory {number}
+     */
+    set maxHistory(maxHistory: number);
+    /**
+     *
+     * @returns {number}
+     */
+    get maxHistory(): number;
+    undo(): void;
+    redo(): void;
+    /**
+     *
+     * @param value {boolean}
+     */
+    set disabled(value: boolean);
+    /**
+     *
+     * @returns {boolean}
+     */
+    get disabled(): boolean;
+    /**
+     *
+     * @param callback {(e: ITextEvent) => void}
+     */
+    bindTextChange(callback: (e: ITextEvent) => void): void;
+    /**
+     *
+     * @param callback {(e: ILinkClickEvent) => void}
+     */
+    bindLinkClick(callback: (e: ILinkClickEvent) => void): void;
+}
+declare class ImageElement extends Element {
+    constructor();
+    set src(src: any);
+}
+/**
+ * Element showing frames submitted by native code, e.g. video decoders or game engines
+ */
+declare class ExternalTextureElement extends Element {
+    constructor();
+    /**
+     * Id passed to native code for submitting frames
+     * @returns {number}
      */
-    set disabled(value: boolean);
+    get textureId(): number;
     /**
-     *
-     * @returns {boolean}
+     * Drop current frame
      */
-    get disabled(): boolean;
-    bindChange(callback: any): void;
+    clear(): void;
 }
 /**
- * @typedef {{
- *   type: "text",
- *   text: string,
- *   weight ?: string,
- *   textDecorationLine ?: string,
- *   fontFamilies ?: string[],
- *   fontSize ?: number,
- *   color ?: string,
- *   backgroundColor ?: string
- * }} TextUnit
+ * Element playing video decoded by ffmpeg, requires feature "video" and ffmpeg executables in PATH.
+ * The audio track is not played.
  */
-declare class RichTextElement extends Element {
+declare class VideoElement extends Element {
     constructor();
     /**
-     *
-     * @param units {TextUnit[]}
+     * @param src {string} file path or url supported by ffmpeg
      */
-    addLine(units: TextUnit[]): void;
+    set src(src: string);
+    get src(): string;
+    play(): void;
+    pause(): void;
     /**
-     *
-     * @param index {number}
-     * @param units {TextUnit[]}
+     * @param time {number} seconds
      */
-    insertLine(index: number, units: TextUnit[]): void;
+    seek(time: number): void;
     /**
-     *
-     * @param index {number}
+     * Current position in seconds
      */
-    deleteLine(index: number): void;
+    get currentTime(): number;
+    set currentTime(time: number);
     /**
-     *
-     * @param index {number}
-     * @param units {TextUnit[]}
+     * Duration in seconds, 0 before metadata is loaded
      */
-    updateLine(index: number, units: TextUnit[]): void;
-    clear(): void;
+    get duration(): number;
+    get paused(): boolean;
+    get ended(): boolean;
+    /**
+     * Restart from the beginning when ended
+     */
+    set loop(value: boolean);
     /**
      *
-     * @param units {TextUnit[]}
-     * @return {[number, number]}
+     * @param callback {(e: ITimeUpdateEvent) => void}
      */
-    measureLine(units: TextUnit[]): [number, number];
+    bindTimeUpdate(callback: (e: ITimeUpdateEvent) => void): void;
     /**
      *
-     * @returns {string | undefined}
+     * @param callback {(e: IVoidEvent) => void}
      */
-    get selectionText(): string | undefined;
-}
-declare class ImageElement extends Element {
-    constructor();
-    set src(src: any);
+    bindEnded(callback: (e: IVoidEvent) => void): void;
 }
 declare class TextInputElement extends Element {
     constructor();
@@ -1108,6 +2778,36 @@ declare class TextInputElement extends Element {
      * @returns {"text" | "password"}
      */
     get type(): "text" | "password";
+    /**
+     *
+     * @param value {string} e.g. "username", "current-password", "one-time-code"
+     */
+    set autocomplete(value: string);
+    /**
+     *
+     * @returns {string}
+     */
+    get autocomplete(): string;
+    /**
+     * Input mask, `#` accepts a digit, `A` a letter, `*` a letter or digit, others are literals
+     * @param value {string} e.g. "(###) ###-####"
+     */
+    set mask(value: string);
+    /**
+     *
+     * @returns {string}
+     */
+    get mask(): string;
+    /**
+     * Text without literals of mask
+     * @returns {string}
+     */
+    get unmaskedText(): string;
+    /**
+     * Custom items appended to the built-in editing context menu
+     * @param menu {Menu | null}
+     */
+    set contextMenu(menu: Menu | null);
     /**
      *
      * @param value {boolean}
@@ -1128,6 +2828,11 @@ declare class TextInputElement extends Element {
      * @param callback {(e: ICaretEvent) => void}
      */
     bindCaretChange(callback: (e: ICaretEvent) => void): void;
+    /**
+     *
+     * @param callback {(e: IMaskValidateEvent) => void}
+     */
+    bindMaskValidate(callback: (e: IMaskValidateEvent) => void): void;
 }
 declare class TextEditElement extends Element {
     constructor();
@@ -1168,6 +2873,11 @@ declare class TextEditElement extends Element {
      * @param charOffset {number}
      */
     setCaretByCharOffset(charOffset: number): void;
+    /**
+     * Custom items appended to the built-in editing context menu
+     * @param menu {Menu | null}
+     */
+    set contextMenu(menu: Menu | null);
     /**
      *
      * @param value {boolean}
@@ -1227,10 +2937,274 @@ declare class ScrollElement extends ContainerBasedElement {
      */
     set scrollY(value: "auto" | "always" | "never");
     scrollBy(value: any): void;
+    /**
+     * Enable pulling content down at the top edge to refresh, by touch or trackpad
+     * @param value {boolean}
+     */
+    set pullToRefresh(value: boolean);
+    /**
+     * Set the element revealed above content when pulling, it is added as a child and positioned absolutely
+     * @param indicator {Element}
+     */
+    set refreshIndicator(indicator: Element);
+    /**
+     * @returns {Element | null}
+     */
+    get refreshIndicator(): Element | null;
+    /**
+     * Whether the refresh indicator is kept visible for refreshing
+     * @returns {boolean}
+     */
+    get refreshing(): boolean;
+    /**
+     * Collapse the refresh indicator
+     */
+    finishRefresh(): void;
+    /**
+     * The indicator is collapsed when the Promise returned by callback is settled
+     * @param callback {(e: IVoidEvent) => (Promise<any> | void)}
+     */
+    bindRefresh(callback: (e: IVoidEvent) => (Promise<any> | void)): void;
 }
 declare class RadioGroupElement extends ContainerBasedElement {
     constructor();
 }
+/**
+ * Records canvas operations of custom element, commands are executed by the renderer later
+ */
+declare class RenderContext {
+    /**
+     *
+     * @param width {number}
+     * @param height {number}
+     */
+    constructor(width: number, height: number);
+    /**
+     * @type {any[][]}
+     */
+    commands: any[][];
+    /**
+     * @type {number}
+     */
+    width: number;
+    /**
+     * @type {number}
+     */
+    height: number;
+    /**
+     *
+     * @param color {string}
+     */
+    set fillStyle(color: string);
+    /**
+     *
+     * @param color {string}
+     */
+    set strokeStyle(color: string);
+    /**
+     *
+     * @param width {number}
+     */
+    set lineWidth(width: number);
+    fillRect(x: number, y: number, width: number, height: number): void;
+    strokeRect(x: number, y: number, width: number, height: number): void;
+    fillCircle(cx: number, cy: number, radius: number): void;
+    strokeCircle(cx: number, cy: number, radius: number): void;
+    line(x1: number, y1: number, x2: number, y2: number): void;
+    /**
+     *
+     * @param svgPath {string} svg path data, e.g. "M0 0 L10 10 Z"
+     */
+    fillPath(svgPath: string): void;
+    /**
+     *
+     * @param svgPath {string} svg path data, e.g. "M0 0 L10 10 Z"
+     */
+    strokePath(svgPath: string): void;
+    save(): void;
+    restore(): void;
+    translate(x: number, y: number): void;
+    /**
+     *
+     * @param degrees {number}
+     */
+    rotate(degrees: number): void;
+    scale(x: number, y: number): void;
+    clipRect(x: number, y: number, width: number, height: number): void;
+}
+declare type CustomElementDefinition = {
+    onRender?: (ctx: RenderContext, element: Element) => void;
+    onEvent?: (type: string, event: IEvent<any>, element: Element) => void;
+    events?: string[];
+    /**
+     * Intrinsic size of element, maxWidth/maxHeight is Infinity if unconstrained.
+     * Children of element are ignored by layout when measure is defined.
+     */
+    measure?: (maxWidth: number, maxHeight: number, element: Element) => {
+        width: number;
+        height: number;
+    };
+};
+declare class CustomElementBase extends ContainerBasedElement {
+    constructor();
+    /**
+     * Call onRender again in next frame
+     */
+    requestRender(): void;
+    /**
+     * Call measure again in next layout
+     */
+    requestLayout(): void;
+}
+declare namespace deft {
+    /**
+     * Define an element rendered by js callbacks
+     * @param tag {string}
+     * @param definition {CustomElementDefinition}
+     * @returns class to create the element
+     */
+    function registerElement(tag: string, definition: CustomElementDefinition): typeof CustomElementBase;
+    /**
+     * Event emitter shared by app code and component libraries.
+     * Listeners subscribed with an owner, e.g. an element or a window, are held by the owner instead
+     * of the emitter, so they are released with the owner even if they reference it.
+     * Owners are tracked by WeakRef; on engines without it they are kept alive until all of their
+     * listeners are removed.
+     */
+    class EventEmitter {
+        /**
+         * @param type {string}
+         * @param listener {Function} called with arguments of `emit`
+         * @param options {EventEmitterListenerOptions | undefined}
+         * @returns {() => void} function to remove the listener
+         */
+        on(type: string, listener: (...args: any[]) => void, options?: EventEmitterListenerOptions): () => void;
+        /**
+         * Listen to the first event of type only
+         */
+        once(type: string, listener: (...args: any[]) => void, options?: EventEmitterListenerOptions): () => void;
+        off(type: string, listener: (...args: any[]) => void): void;
+        /**
+         * Call listeners of type in the order they are added, errors thrown by listeners are reported
+         * without stopping the others
+         * @returns {boolean} whether any listener is called
+         */
+        emit(type: string, ...args: any[]): boolean;
+        /**
+         * Remove listeners of type, or all listeners if type is not specified
+         */
+        removeAllListeners(type?: string): void;
+        listenerCount(type: string): number;
+    }
+    const debug: RenderDebug;
+    const a11y: A11y;
+    const systemMetrics: SystemMetrics;
+    const screen: Screen;
+    const network: Network;
+    const downloads: DownloadManager;
+    /**
+     * Only available when built with feature "iap"
+     */
+    const iap: InAppPurchase | undefined;
+    /**
+     * Only available when built with feature "oauth"
+     */
+    const oauth: OAuth | undefined;
+    const protocol: Protocol;
+    /**
+     * Whether app is started with `--safe-mode`, i.e. software rendering without animations
+     */
+    const safeMode: boolean;
+    /**
+     * Whether app is running without visible windows, e.g. started by `bootstrap_headless` or with `DEFT_HEADLESS=1`
+     */
+    const headless: boolean;
+    /**
+     * Version of deft
+     */
+    const version: string;
+    /**
+     * Native features compiled in, e.g. "websocket", "audio", "sqlite", "tray"
+     */
+    const features: ReadonlySet<string>;
+    /**
+     * Throw if a native feature is not compiled in
+     */
+    function require(feature: string): void;
+    /**
+     * Register shortcut working in any window of app, throws if the shortcut is registered already
+     * @param accelerator e.g. "Ctrl+Shift+P", "CmdOrCtrl+S", "Alt+F4"
+     * @returns id of shortcut
+     */
+    function registerShortcut(accelerator: string, callback: () => void): number;
+    function unregisterShortcut(id: number): void;
+    function setShortcutEnabled(id: number, enabled: boolean): void;
+    /**
+     * Font families tried in order when chars are missing in the fonts of text, e.g. CJK and emoji fonts
+     */
+    function getFallbackFonts(): string[];
+    /**
+     * Replace the fallback font list, only text laid out afterwards is affected
+     * @param families an empty list restores the default list of the platform
+     */
+    function setFallbackFonts(families: string[]): void;
+    /**
+     * Collect garbage of js heap now, which is otherwise done in idle periods between frames
+     * @returns duration in milliseconds
+     */
+    function gc(): number;
+    function getGcStats(): GcStats;
+    /**
+     * Whether to collect garbage automatically in idle periods between frames, enabled by default
+     */
+    function setIdleGcEnabled(enabled: boolean): void;
+    /**
+     * Called on memory pressure after image and layer caches are trimmed and garbage is collected,
+     * release caches of app here
+     */
+    function setMemoryPressureHandler(handler: (() => void) | null): void;
+    /**
+     * Trim caches and collect garbage as if memory pressure is reported by system
+     */
+    function trimMemory(): void;
+    /**
+     * Run callback with selector matching and window updates deferred, they are flushed once
+     * when the callback returns. Changes made by js are also batched until the current task ends.
+     */
+    function batch<T>(callback: () => T): T;
+    namespace config {
+        /**
+         * Load config from `{name}.json` in data dir, keys missing in file take values of defaults
+         * @param defaults default values
+         * @param name defaults to "config"
+         */
+        function load<T extends Record<string, any>>(defaults?: T, name?: string): Config<T>;
+    }
+    /**
+     * Start devtools server, then open the returned url with a session token in a browser to
+     * inspect the app. Only available when built with feature "devtools"
+     * @param address {string} e.g. "127.0.0.1:9222"
+     * @returns url like http://{address}/?token={token}
+     */
+    function startDevtools(address: string): string;
+    /**
+     * Start element inspector server, then open the returned url in a browser or connect to
+     * ws://{address}/?token={token} from other tools to inspect and edit styles of elements.
+     * Only available when built with feature "inspector" or "devtools"
+     * @param address {string} e.g. "127.0.0.1:9223"
+     * @returns url like http://{address}/?token={token}
+     */
+    function startInspector(address: string): string;
+    /**
+     * Start debug server speaking Debug Adapter Protocol, attach to it from VS Code with the
+     * returned token in the `token` field of the attach configuration. It only pauses on uncaught
+     * exceptions and evaluates expressions in the global scope.
+     * Only available when built with feature "debugger"
+     * @param address {string} defaults to "127.0.0.1:4711"
+     * @returns token of the debug session
+     */
+    function startDebugger(address?: string): string;
+}
 declare class WebSocket {
     constructor(url: any);
     client: any;
@@ -1288,18 +3262,324 @@ declare class Sqlite {
      */
     static open(path: string): Promise<SqliteConn>;
 }
+/**
+ * @typedef {number | string} ObjectKey
+ * @typedef {{lower?: ObjectKey, upper?: ObjectKey, lowerOpen?: boolean, upperOpen?: boolean}} ObjectKeyRangeDef
+ * @typedef {{offset?: number, limit?: number, direction?: "next" | "prev"}} ObjectQueryOptions
+ */
+declare class ObjectKeyRange {
+    static only(key: ObjectKey): ObjectKeyRangeDef;
+    static lowerBound(lower: ObjectKey, open?: boolean): ObjectKeyRangeDef;
+    static upperBound(upper: ObjectKey, open?: boolean): ObjectKeyRangeDef;
+    static bound(lower: ObjectKey, upper: ObjectKey, lowerOpen?: boolean, upperOpen?: boolean): ObjectKeyRangeDef;
+}
+declare class ObjectStoreIndex {
+    constructor(store: any, name: any);
+    get(key: ObjectKey): Promise<any>;
+    getAll(range?: ObjectKeyRangeDef, options?: ObjectQueryOptions): Promise<any[]>;
+    count(range?: ObjectKeyRangeDef): Promise<number>;
+    openCursor(range?: ObjectKeyRangeDef, direction?: "next" | "prev"): AsyncGenerator<{
+        key: ObjectKey;
+        value: any;
+    }>;
+}
+declare class ObjectStore {
+    constructor(db: any, name: any, schema: any, transaction?: any);
+    get name(): string;
+    get keyPath(): string | null;
+    get indexNames(): string[];
+    get(key: ObjectKey): Promise<any>;
+    getAll(range?: ObjectKeyRangeDef, options?: ObjectQueryOptions): Promise<any[]>;
+    count(range?: ObjectKeyRangeDef): Promise<number>;
+    openCursor(range?: ObjectKeyRangeDef, direction?: "next" | "prev"): AsyncGenerator<{
+        key: ObjectKey;
+        value: any;
+    }>;
+    index(name: string): ObjectStoreIndex;
+    /**
+     * Insert or replace a record, return the key
+     */
+    put(value: any, key?: ObjectKey): Promise<ObjectKey>;
+    /**
+     * Insert a record, fail if the key exists
+     */
+    add(value: any, key?: ObjectKey): Promise<ObjectKey>;
+    delete(key: ObjectKey): Promise<void>;
+    clear(): Promise<void>;
+}
+declare class ObjectStoreTransaction {
+    constructor(db: any, database: any);
+    /**
+     * Writes of the returned store are buffered until commit, reads are executed immediately
+     */
+    objectStore(name: string): ObjectStore;
+    /**
+     * Execute all buffered writes atomically, return keys of written records
+     */
+    commit(): Promise<ObjectKey[]>;
+    abort(): void;
+}
+declare class ObjectStoreDatabase {
+    /**
+     * Open a database, in-memory database is used if path is empty
+     */
+    static open(path?: string): Promise<ObjectStoreDatabase>;
+    constructor(db: any);
+    get objectStoreNames(): string[];
+    createObjectStore(name: string, options?: {
+        keyPath?: string;
+        autoIncrement?: boolean;
+    }): Promise<ObjectStore>;
+    deleteObjectStore(name: string): Promise<void>;
+    /**
+     * Create an index and build entries for existing records
+     */
+    createIndex(storeName: string, name: string, keyPath: string, options?: {
+        unique?: boolean;
+    }): Promise<void>;
+    deleteIndex(storeName: string, name: string): Promise<void>;
+    objectStore(name: string): ObjectStore;
+    transaction(): ObjectStoreTransaction;
+    /**
+     * Run callback with a transaction and commit it, the transaction is aborted if callback throws
+     */
+    runTransaction(callback: (tx: ObjectStoreTransaction) => Promise<void>): Promise<ObjectKey[]>;
+}
 declare const workerContext: WorkerContext;
 declare class FetchResponse {
-    constructor(resp: any, status: any);
+    constructor(resp: any, status: any, served?: {
+        headers: Record<string, string>;
+        body: ArrayBuffer;
+    });
     _resp: any;
     status: any;
     ok: boolean;
+    /**
+     * @returns lower case names to values
+     */
+    getHeaders(): Promise<Record<string, string>>;
+    text(): Promise<string>;
+    arrayBuffer(): Promise<ArrayBuffer>;
     json(): Promise<any>;
 }
 declare type IResizeEvent = IEvent<ResizeDetail>;
-declare type TextUnit = {
+declare type IImeEvent = IEvent<ImeDetail>;
+declare type IZoomEvent = IEvent<ZoomDetail>;
+declare type IRendererChangeEvent = IEvent<RendererChangeDetail>;
+declare type IVisibilityChangeEvent = IEvent<VisibilityChangeDetail>;
+declare type ISnapEvent = IEvent<SnapDetail>;
+declare type IThemeChangeEvent = IEvent<ThemeChangeDetail>;
+declare type FindOptions = {
+    caseSensitive?: boolean;
+};
+declare type FindResult = {
+    count: number;
+    activeIndex: number;
+};
+declare type ElementPathEntry = {
+    eid: number;
+    tag: string;
+    id?: string;
+    testId?: string;
+};
+declare type InjectedInput = {
+    type: "mousemove";
+    x: number;
+    y: number;
+} | {
+    type: "mousedown" | "mouseup" | "click";
+    x?: number;
+    y?: number;
+    button?: "left" | "right" | "middle" | "back" | "forward";
+} | {
+    type: "wheel";
+    x?: number;
+    y?: number;
+    deltaX?: number;
+    deltaY?: number;
+    deltaMode?: number;
+} | {
+    type: "keydown" | "keyup";
+    key: string;
+    ctrlKey?: boolean;
+    shiftKey?: boolean;
+    altKey?: boolean;
+    metaKey?: boolean;
+    repeat?: boolean;
+} | {
     type: "text";
     text: string;
+} | {
+    type: "touch";
+    id?: number;
+    phase: "start" | "move" | "end" | "cancel";
+    x: number;
+    y: number;
+};
+declare type ReplayOptions = {
+    speed?: number;
+    deterministicTimers?: boolean;
+};
+declare type A11yPreferences = {
+    reduceMotion: boolean;
+    highContrast: boolean;
+    textScale: number;
+};
+declare type RenderStats = {
+    layerCount: number;
+    paintedElements: number;
+    culledElements: number;
+    cachedSurfaceBytes: number;
+    frameTimeMs: number;
+};
+declare type RenderTreeDumpOptions = {
+    layerImages?: boolean;
+};
+declare type RichTextAttributes = {
+    bold?: boolean;
+    italic?: boolean;
+    underline?: boolean;
+    link?: string;
+};
+declare type RichTextFormat = {
+    bold?: boolean;
+    italic?: boolean;
+    underline?: boolean;
+    link?: string;
+};
+declare type RichTextImage = {
+    image: string;
+    width?: number;
+    height?: number;
+};
+declare type RichDeltaOp = {
+    insert: string | RichTextImage;
+    attributes?: RichTextAttributes;
+};
+declare type RichContent = {
+    ops: RichDeltaOp[];
+};
+declare type Placement = "top" | "top-start" | "top-end" | "bottom" | "bottom-start" | "bottom-end" | "left" | "left-start" | "left-end" | "right" | "right-start" | "right-end";
+declare type PopupOptions = {
+    placement?: Placement;
+    offset?: number;
+    flip?: boolean;
+    shift?: boolean;
+    padding?: number;
+};
+declare type PopupPlacement = {
+    x: number;
+    y: number;
+    placement: Placement;
+    arrowOffset: number;
+};
+declare type StyleSummary = {
+    backgroundColor: string;
+    backgroundImage: boolean;
+    borderWidth: number[];
+    borderColor: string[];
+    borderRadius: number[];
+};
+declare type ElementObjectDump = {
+    id: number;
+    tag: string;
+    coord: number[];
+    layerCoord: number[];
+    width: number;
+    height: number;
+    transform?: string;
+    style: StyleSummary;
+    children: ElementObjectDump[];
+};
+declare type LayerDump = {
+    elementId: number;
+    layerType: "root" | "children";
+    matrix: string;
+    totalMatrix: string;
+    width: number;
+    height: number;
+    originAbsolutePos: number[];
+    scrollOffset: number[];
+    surfaceBounds: ElementRect;
+    visibleBounds: ElementRect;
+    clipRect?: ElementRect;
+    invalidArea: "full" | "partial" | "none";
+    invalidRects: ElementRect[];
+    elements: ElementObjectDump[];
+    layers: LayerDump[];
+    image?: string;
+};
+declare type LayerRootDump = {
+    elementId: number;
+    x: number;
+    y: number;
+    layer?: LayerDump;
+};
+declare type RenderTreeDump = {
+    scaleFactor: number;
+    viewport: ElementRect;
+    layerRoots: LayerRootDump[];
+};
+declare type LayoutBox = {
+    left: number;
+    top: number;
+    width: number;
+    height: number;
+    margin: number[];
+    border: number[];
+    padding: number[];
+};
+declare type LayoutDump = {
+    id: number;
+    tag: string;
+    style: Record<string, string>;
+    layout: LayoutBox;
+    children: LayoutDump[];
+};
+declare type CascadeEntry = {
+    origin: "default" | "rule" | "inline" | "hover" | "animation";
+    selector?: string;
+    value: string;
+};
+declare type StyleTrace = {
+    property: string;
+    value: string;
+    winner?: CascadeEntry;
+    cascade: CascadeEntry[];
+};
+declare type ScrollState = {
+    scrollLeft: number;
+    scrollTop: number;
+    anchor?: number;
+    anchorOffset: number;
+};
+declare type ScrollToOptions = {
+    left?: number;
+    top?: number;
+    behavior?: "auto" | "smooth";
+};
+declare type ObjectKey = number | string;
+declare type ObjectKeyRangeDef = {
+    lower?: ObjectKey;
+    upper?: ObjectKey;
+    lowerOpen?: boolean;
+    upperOpen?: boolean;
+};
+declare type ObjectQueryOptions = {
+    offset?: number;
+    limit?: number;
+    direction?: "next" | "prev";
+};
+declare type TextUnit = {
+    type: "text" | "link" | "ruby" | "image" | "embed";
+    text?: string;
+    href?: string;
+    annotation?: string;
+    src?: string;
+    width?: number;
+    height?: number;
+    verticalAlign?: "baseline" | "top" | "middle" | "bottom";
+    element?: Element;
     weight?: string;
     textDecorationLine?: string;
     fontFamilies?: string[];
@@ -1318,16 +3598,31 @@ declare type IEvent<T> = {
     preventDefault(): void;
 };
 declare type IBoundsChangeEvent = IEvent<BoundsChangeDetail>;
+declare type IFullscreenChangeEvent = IEvent<FullscreenChangeDetail>;
+declare type IPointerLockChangeEvent = IEvent<PointerLockChangeDetail>;
 declare type IVoidEvent = IEvent<void>;
 declare type ICaretEvent = IEvent<CaretDetail>;
+declare type IMaskValidateEvent = IEvent<MaskValidateDetail>;
+declare type ITruncationChangeEvent = IEvent<TruncationChangeDetail>;
+declare type ILinkClickEvent = IEvent<LinkClickDetail>;
+declare type ITimeUpdateEvent = IEvent<TimeUpdateDetail>;
+declare type ILinkHoverEvent = IEvent<LinkHoverDetail>;
 declare type IMouseEvent = IEvent<MouseDetail>;
 declare type IKeyEvent = IEvent<KeyDetail>;
 declare type IMouseWheelEvent = IEvent<MouseWheelDetail>;
 declare type ITextEvent = IEvent<TextDetail>;
 declare type ITouchEvent = IEvent<TouchDetail>;
+declare type IGestureZoomEvent = IEvent<GestureZoomDetail>;
+declare type IGestureRotateEvent = IEvent<GestureRotateDetail>;
+declare type IGesturePanEvent = IEvent<GesturePanDetail>;
 declare type IScrollEvent = IEvent<ScrollDetail>;
 declare type IDroppedFileEvent = IEvent<string>;
 declare type IHoveredFileEvent = IEvent<string>;
+declare type IStorageEvent = IEvent<StorageDetail>;
+declare type BeforeQuitEvent = {
+    code: number;
+    cancel: () => void;
+};
 declare class Clipboard {
     /**
      *
@@ -1342,6 +3637,12 @@ declare class Clipboard {
     writeText(text: string): Promise<void>;
 }
 declare class Stylesheet {
+    /**
+     * Add a stylesheet, only elements which its rules could apply to are restyled
+     * @param code {string}
+     * @returns {number} id of the stylesheet
+     */
+    add(code: string): number;
     /**
      *
      * @param code {string}
@@ -1350,9 +3651,15 @@ declare class Stylesheet {
     append(code: string): StylesheetItem;
     /**
      *
-     * @param stylesheet {StylesheetItem}
+     * @param stylesheet {number | StylesheetItem}
+     * @param code {string}
+     */
+    replace(stylesheet: number | StylesheetItem, code: string): void;
+    /**
+     *
+     * @param stylesheet {number | StylesheetItem}
      */
-    remove(stylesheet: StylesheetItem): void;
+    remove(stylesheet: number | StylesheetItem): void;
 }
 declare class ContainerBasedElement extends Element {
     /**
@@ -1383,11 +3690,17 @@ declare class ContainerBasedElement extends Element {
      * @returns {Element[]}
      */
     get children(): Element[];
+    protected _clearForPool(): void;
     
 }
 declare class StylesheetItem {
     constructor(id: any);
     id: any;
+    /**
+     * Replace source of the stylesheet, the stylesheet is unchanged if the source is invalid
+     * @param code {string}
+     */
+    replace(code: string): void;
     update(code: any): void;
 }
 
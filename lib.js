@@ -8,12 +8,15 @@ const VT_SCROLL = "scroll"
 const VT_TEXT_INPUT = "text-input"
 const VT_TEXT_EDIT = "text-edit"
 const VT_IMAGE = "image"
+const VT_EXTERNAL_TEXTURE = "external-texture"
+const VT_VIDEO = "video"
 const VT_BODY = "body"
 const VT_PARAGRAPH = "paragraph"
 const VT_CHECKBOX = "checkbox"
 const VT_RADIO = "radio"
 const VT_RADIO_GROUP = "radio-group"
 const VT_RICH_TEXT = "rich-text"
+const VT_RICH_TEXT_EDIT = "rich-text-edit"
 const VT_SELECT = "select"
 const VT_DIALOG = "dialog";
 const VT_DIALOG_TITLE = "dialog-title";
@@ -44,28 +47,57 @@ class StylesheetItem {
         this.id = id;
     }
 
+    /**
+     * Replace source of the stylesheet, the stylesheet is unchanged if the source is invalid
+     * @param code {string}
+     */
+    replace(code) {
+        stylesheet_replace(this.id, code);
+    }
+
     update(code) {
-        stylesheet_update(this.id, code);
+        this.replace(code);
     }
 }
 
 class Stylesheet {
+    /**
+     * Add a stylesheet, only elements which its rules could apply to are restyled
+     * @param code {string}
+     * @returns {number} id of the stylesheet
+     */
+    add(code) {
+        return stylesheet_add(code);
+    }
+
     /**
      *
      * @param code {string}
      * @returns {StylesheetItem}
      */
     append(code) {
-        const id = stylesheet_add(code);
-        return new StylesheetItem(id);
+        return new StylesheetItem(this.add(code));
+    }
+
+    /**
+     *
+     * @param stylesheet {number | StylesheetItem}
+     * @param code {string}
+     */
+    replace(stylesheet, code) {
+        stylesheet_replace(Stylesheet.#getId(stylesheet), code);
     }
 
     /**
      *
-     * @param stylesheet {StylesheetItem}
+     * @param stylesheet {number | StylesheetItem}
      */
     remove(stylesheet) {
-        stylesheet_remove(stylesheet.id);
+        stylesheet_remove(Stylesheet.#getId(stylesheet));
+    }
+
+    static #getId(stylesheet) {
+        return stylesheet instanceof StylesheetItem ? stylesheet.id : stylesheet;
     }
 }
 
@@ -86,6 +118,16 @@ export class Navigator {
 }
 
 export class Process {
+    /**
+     * @type {((event: BeforeQuitEvent) => (void | Promise<void>))[]}
+     */
+    #beforeQuitCallbacks = [];
+
+    /**
+     * @type {((state: "foreground" | "background") => void)[]}
+     */
+    #lifecycleCallbacks = [];
+
     /**
      *
      * @param code {number}
@@ -94,6 +136,117 @@ export class Process {
         process_exit(code);
     }
 
+    /**
+     * Register a callback called before app quits, async cleanup is awaited until quit timeout.
+     * Call event.cancel() to cancel quitting.
+     * @param callback {(event: BeforeQuitEvent) => (void | Promise<void>)}
+     * @returns {() => void} function to unregister the callback
+     */
+    onBeforeQuit(callback) {
+        if (!this.#beforeQuitCallbacks.length) {
+            process_set_before_quit_handler(code => this.#handleBeforeQuit(code));
+        }
+        this.#beforeQuitCallbacks.push(callback);
+        return () => {
+            this.#beforeQuitCallbacks = this.#beforeQuitCallbacks.filter(it => it !== callback);
+            if (!this.#beforeQuitCallbacks.length) {
+                process_set_before_quit_handler(null);
+            }
+        }
+    }
+
+    /**
+     * Register a callback called when app comes to foreground or goes to background,
+     * only reported on mobile platforms
+     * @param callback {(state: "foreground" | "background") => void}
+     * @returns {() => void} function to unregister the callback
+     */
+    onLifecycleChange(callback) {
+        if (!this.#lifecycleCallbacks.length) {
+            process_set_lifecycle_handler(state => {
+                for (const cb of this.#lifecycleCallbacks.slice()) {
+                    try {
+                        cb(state);
+                    } catch (error) {
+                        console.error("Failed to call lifecycle callback", error);
+                    }
+                }
+            });
+        }
+        this.#lifecycleCallbacks.push(callback);
+        return () => {
+            this.#lifecycleCallbacks = this.#lifecycleCallbacks.filter(it => it !== callback);
+            if (!this.#lifecycleCallbacks.length) {
+                process_set_lifecycle_handler(null);
+            }
+        }
+    }
+
+    /**
+     * Max milliseconds to wait for before-quit callbacks, default is 5000
+     * @param value {number}
+     */
+    set quitTimeout(value) {
+        process_set_quit_timeout(value);
+    }
+
+    /**
+     * Save app state for crash recovery, it is written to disk periodically
+     * @param state {any} JSON serializable value
+     */
+    setRecoveryState(state) {
+        recovery_set_state(JSON.stringify(state));
+    }
+
+    /**
+     * Get the state saved by last session if it was not shut down cleanly
+     * @returns {any | null}
+     */
+    getRecoveredState() {
+        const state = recovery_get_previous_state();
+        return state == null ? null : JSON.parse(state);
+    }
+
+    /**
+     * Drop recovery state of last session and current session
+     */
+    clearRecoveryState() {
+        recovery_clear();
+    }
+
+    /**
+     *
+     * @returns {boolean}
+     */
+    get isRecoveryEnabled() {
+        return recovery_is_enabled();
+    }
+
+    async #handleBeforeQuit(code) {
+        let cancelled = false;
+        const event = {
+            code,
+            cancel() {
+                cancelled = true;
+            },
+        };
+        for (const cb of this.#beforeQuitCallbacks.slice()) {
+            try {
+                await cb(event);
+            } catch (error) {
+                console.error("Failed to call before-quit callback", error);
+            }
+            if (cancelled) {
+                break;
+            }
+        }
+        if (cancelled) {
+            process_cancel_quit();
+        } else {
+            process_confirm_quit(code);
+        }
+    }
+
     /**
      *
      * @param value {boolean}
@@ -136,496 +289,2271 @@ export class Process {
 }
 
 
-export class FileDialog {
+/**
+ * @typedef {{type: "log"} | {type: "statsd", address: string, prefix?: string} | {type: "http", url: string}} MetricsExporterOptions
+ */
+export class Metrics {
     /**
      *
-     * @param options {ShowFileDialogOptions}
-     * @returns {Promise<string[]>}
+     * @param name {string}
+     * @param value {number}
      */
-    show(options) {
-        return new Promise((resolve, reject) => {
-            dialog_show_file_dialog({
-                dialogType: options.dialogType,
-            }, options.window?.handle, (result, data) => {
-                if (result) {
-                    resolve(data);
-                } else {
-                    reject(data);
-                }
-            })
-        })
-
-    }
-}
-
-export class Page {
-    handle
-    constructor(handle) {
-        this.handle = handle;
+    increment(name, value = 1) {
+        metrics_increment(name, value);
     }
-    close() {
-        Page_close(this.handle);
-    }
-}
 
-export class Popup {
-    handle
-    constructor(handle) {
-        this.handle = handle;
-    }
-    close() {
-        Popup_close(this.handle);
+    /**
+     *
+     * @param name {string}
+     * @param ms {number}
+     */
+    timing(name, ms) {
+        metrics_timing(name, ms);
     }
-}
 
-export class StandardMenuItem {
-    #handle;
-    constructor(label, callback) {
-        this.#handle = StandardMenuItem_js_new(label, callback);
+    /**
+     * Measure time of callback, async callback is awaited
+     * @template T
+     * @param name {string}
+     * @param callback {() => T}
+     * @returns {T}
+     */
+    measure(name, callback) {
+        const start = Date.now();
+        const result = callback();
+        if (result instanceof Promise) {
+            return result.finally(() => this.timing(name, Date.now() - start));
+        }
+        this.timing(name, Date.now() - start);
+        return result;
     }
 
     /**
-     *
-     * @param value {boolean}
+     * Metrics are collected only after any exporter added
+     * @param options {MetricsExporterOptions}
      */
-    set disabled(value) {
-        StandardMenuItem_set_disabled(this.#handle, value);
+    addExporter(options) {
+        if (options.type === "log") {
+            metrics_add_log_exporter();
+        } else if (options.type === "statsd") {
+            metrics_add_statsd_exporter(options.address, options.prefix || "");
+        } else if (options.type === "http") {
+            metrics_add_http_exporter(options.url);
+        } else {
+            throw new Error("Unknown metrics exporter type: " + options.type);
+        }
     }
 
     /**
      *
      * @returns {boolean}
      */
-    get disabled() {
-        return StandardMenuItem_get_disabled(this.#handle);
+    get enabled() {
+        return metrics_is_enabled();
     }
 
-    get handle() {
-        return this.#handle;
+    flush() {
+        metrics_flush();
     }
 }
 
-export class Menu {
-    #handle
+/**
+ * Token to cancel pending fs operations, cancelled operations are rejected
+ */
+export class CancelToken {
+    #id;
+    #cancelled = false;
 
     constructor() {
-        this.#handle = Menu_new();
+        this.#id = fs_create_cancel_token();
+    }
+
+    get id() {
+        return this.#id;
     }
 
     /**
-     *
-     * @param item {StandardMenuItem}
+     * @returns {boolean}
      */
-    addStandardItem(item) {
-        Menu_add_standard_item(this.#handle, item.handle);
+    get cancelled() {
+        return this.#cancelled;
     }
 
-    addSeparator() {
-        Menu_add_separator(this.#handle);
+    cancel() {
+        if (!this.#cancelled) {
+            this.#cancelled = true;
+            fs_cancel(this.#id);
+        }
     }
 
-    get handle() {
-        return this.#handle;
+    /**
+     * Release resources of token, it could not be used anymore
+     */
+    release() {
+        fs_release_cancel_token(this.#id);
     }
-
 }
 
 /**
- * @typedef {IEvent<ResizeDetail>} IResizeEvent
+ * @typedef {{cancelToken?: CancelToken}} FsOptions
+ * @typedef {{size: number, is_dir: boolean, is_file: boolean}} FsStat
+ * @typedef {{name: string, size: number, is_dir: boolean, is_file: boolean, modified?: number}} FsDirEntry
  */
-export class Window {
+export class FileHandle {
+    #handle;
+
+    constructor(handle) {
+        this.#handle = handle;
+    }
 
     /**
-     * @type EventRegistry
+     * Read bytes into buffer from current position
+     * @param buffer {Uint8Array}
+     * @param offset {number} offset in buffer
+     * @param length {number} max bytes to read
+     * @param options {FsOptions}
+     * @returns {Promise<number>} bytes read, 0 means end of file
      */
-    #eventRegistry;
+    async read(buffer, offset = 0, length = buffer.length - offset, options = {}) {
+        const data = await FileHandle_read(this.#handle, length, options.cancelToken?.id);
+        buffer.set(new Uint8Array(data), offset);
+        return data.byteLength;
+    }
 
     /**
-     * @type EventBinder
+     * Write data at current position
+     * @param data {ArrayBuffer | Uint8Array | number[] | string} string is encoded as utf-8
+     * @param options {FsOptions}
+     * @returns {Promise<number>} bytes written
      */
-    #eventBinder;
-
-    #windowHandle;
-
-    #body;
+    async write(data, options = {}) {
+        const bytes = typeof data === "string"
+            ? Bytes_toArrayBuffer(unescape(encodeURIComponent(data)))
+            : data;
+        return await FileHandle_write(this.#handle, bytes, options.cancelToken?.id);
+    }
 
     /**
      *
-     * @param attrs {WindowAttrs}
+     * @param offset {number}
+     * @param whence {"start" | "current" | "end"}
+     * @returns {Promise<number>} new position from start
      */
-    constructor(attrs = null) {
-        attrs = attrs || {};
-        attrs.preferredRenderers = [].concat(attrs.preferredRenderers || [])
-        this.#windowHandle = Window_create(attrs);
-        this.#eventBinder = new EventBinder(this.#windowHandle, Window_bind_js_event_listener, Window_unbind_js_event_listener, this);
-        Window_set_js_context(this.#windowHandle, this);
-        this.#body = new BodyElement();
-        Window_set_body(this.#windowHandle, this.#body.handle);
+    async seek(offset, whence = "start") {
+        return await FileHandle_seek(this.#handle, offset, whence);
     }
 
     /**
      *
-     * @param windowHandle
-     * @returns {Window}
+     * @returns {Promise<FsStat>}
      */
-    static fromHandle(windowHandle) {
-        return Window_get_js_context(windowHandle);
+    async stat() {
+        return await FileHandle_stat(this.#handle);
     }
 
-    static supportMultipleWindows() {
-        return Window_support_multiple_windows();
+    /**
+     *
+     * @param length {number}
+     * @returns {Promise<void>}
+     */
+    async truncate(length = 0) {
+        await FileHandle_truncate(this.#handle, length);
     }
 
-    get handle() {
-        return this.#windowHandle
+    /**
+     * Flush and close file
+     * @returns {Promise<void>}
+     */
+    async close() {
+        await FileHandle_close(this.#handle);
     }
+}
 
+export class FileSystem {
     /**
-     *
-     * @returns {BodyElement}
+     * Open file
+     * @param path {string}
+     * @param mode {"r" | "r+" | "w" | "w+" | "wx" | "a" | "a+"}
+     * @param options {FsOptions}
+     * @returns {Promise<FileHandle>}
      */
-    get body() {
-        return this.#body;
+    async open(path, mode = "r", options = {}) {
+        const handle = await FileHandle_open(path, mode, options.cancelToken?.id);
+        return new FileHandle(handle);
     }
 
-
     /**
-     *
-     * @returns {{width: number, height: number}}
+     * List entries of dir with metadata
+     * @param path {string}
+     * @param options {FsOptions}
+     * @returns {Promise<FsDirEntry[]>}
      */
-    get innerSize() {
-        const [width, height] = Window_get_inner_size(this.handle);
-        return {width, height}
+    async readDir(path, options = {}) {
+        return await fs_read_dir_entries(path, options.cancelToken?.id);
     }
 
     /**
      *
-     * @param content {Element}
-     * @param x {number}
-     * @param y {number}
-     * @return {Page}
+     * @param path {string}
+     * @returns {Promise<FsStat>}
      */
-    createPage(content, x, y) {
-        x = x ?? Number.NaN;
-        y = y ?? Number.NaN;
-        const page = Window_create_page(this.#windowHandle, content.handle, x, y);
-        return new Page(page);
+    async stat(path) {
+        return await fs_stat(path);
     }
 
     /**
-     *
-     * @param content {Element}
-     * @param target {{x: number, y: number, width?: number, height?: number}}
-     * @return {Popup}
+     * Copy file or dir recursively
+     * @param src {string}
+     * @param dest {string}
+     * @param options {FsOptions}
+     * @returns {Promise<void>}
      */
-    popup(content, target) {
-        const rect = {
-            x: target.x,
-            y: target.y,
-            width: target.width || 0,
-            height: target.height || 0,
-        }
-        const handle = Window_popup(this.handle, content.handle, rect);
-        return new Popup(handle);
+    async copy(src, dest, options = {}) {
+        await fs_copy(src, dest, options.cancelToken?.id);
     }
 
     /**
-     *
-     * @param menu {Menu}
-     * @param x {number}
-     * @param y {number}
+     * Move file or dir
+     * @param src {string}
+     * @param dest {string}
+     * @param options {FsOptions}
+     * @returns {Promise<void>}
      */
-    popupMenu(menu, x, y) {
-        Window_popup_menu(this.#windowHandle, menu.handle, x, y);
+    async move(src, dest, options = {}) {
+        await fs_move(src, dest, options.cancelToken?.id);
     }
 
     /**
-     *
-     * @param message {string | Element}
-     * @param options {AlertOptions}
+     * Remove file or dir recursively
+     * @param path {string}
+     * @param options {FsOptions}
+     * @returns {Promise<void>}
      */
-    showAlert(message, options = {}) {
-        options = options || {};
-        this.showConfirm(message, {
-            ...options,
-            hideCancel: true,
-        }).finally(() => {
-            options?.callback && options.callback();
-        });
+    async remove(path, options = {}) {
+        await fs_remove(path, options.cancelToken?.id);
+    }
+}
+
+/**
+ * Async iterator of items produced by rust streams
+ * @template T
+ */
+export class AsyncStream {
+    #id;
+    #closed = false;
+
+    constructor(id) {
+        this.#id = id;
     }
 
     /**
-     *
+     * @returns {Promise<IteratorResult<T>>}
+     */
+    async next() {
+        if (this.#closed) {
+            return {done: true, value: undefined};
+        }
+        try {
+            const result = await AsyncStream_next(this.#id);
+            if (result.done) {
+                this.#close();
+            }
+            return result;
+        } catch (error) {
+            this.#close();
+            throw error;
+        }
+    }
+
+    /**
+     * Stop iterating, called by `break` in `for await` loops
+     * @returns {Promise<IteratorResult<T>>}
+     */
+    async return(value) {
+        this.#close();
+        return {done: true, value};
+    }
+
+    [Symbol.asyncIterator]() {
+        return this;
+    }
+
+    #close() {
+        if (!this.#closed) {
+            this.#closed = true;
+            AsyncStream_close(this.#id);
+        }
+    }
+}
+
+/**
+ * @typedef {{windowId: number, latency: number, time: number}} InputLatencyEntry
+ * @typedef {{windowId: number, frames: number, droppedFrames: number, fps: number, refreshRate: number | null}} FrameStats
+ * @typedef {{budgetBytes: number, liveBytes: number, pooledBytes: number, hits: number, misses: number}} SurfacePoolStats
+ * @typedef {{count: number, idleCount: number, forcedCount: number, memoryPressureCount: number, totalTime: number, lastTime: number, maxTime: number, framesSinceLast: number}} GcStats
+ */
+export class Performance {
+    #start = Date.now();
+
+    /**
+     * Milliseconds since app started
+     * @returns {number}
+     */
+    now() {
+        return Date.now() - this.#start;
+    }
+
+    /**
+     * Latest latencies from receipt of input to completion of the next painted frame
+     * @returns {InputLatencyEntry[]}
+     */
+    getInputLatencies() {
+        return performance_get_input_latencies();
+    }
+
+    clearInputLatencies() {
+        performance_clear_input_latencies();
+    }
+
+    /**
+     * Frames and dropped frames of each window since it is created or stats are reset
+     * @returns {FrameStats[]}
+     */
+    getFrameStats() {
+        return performance_get_frame_stats();
+    }
+
+    resetFrameStats() {
+        performance_reset_frame_stats();
+    }
+
+    /**
+     * Layer surfaces held by renderers of all windows
+     * @returns {SurfacePoolStats}
+     */
+    getSurfacePoolStats() {
+        return performance_get_surface_pool_stats();
+    }
+
+    /**
+     * Limit bytes of layer surfaces, idle surfaces are released when exceeded
+     * @param bytes {number}
+     */
+    setSurfaceMemoryBudget(bytes) {
+        performance_set_surface_memory_budget(bytes);
+    }
+
+    /**
+     * Release idle layer surfaces, which is done automatically on memory pressure
+     */
+    trimSurfacePool() {
+        performance_trim_surface_pool();
+    }
+}
+
+export class RenderDebug {
+    /**
+     * Outline areas repainted in each frame
+     * @param value {boolean}
+     */
+    showRepaintAreas(value) {
+        debug_set_show_repaint_areas(value);
+    }
+
+    /**
+     * Outline bounds of composited layers
+     * @param value {boolean}
+     */
+    showLayerBorders(value) {
+        debug_set_show_layer_borders(value);
+    }
+
+    /**
+     * Tint painted elements, areas painted many times become deeper red
+     * @param value {boolean}
+     */
+    showOverdraw(value) {
+        debug_set_show_overdraw(value);
+    }
+
+    /**
+     * Show layer count, cached surface memory and last frame time on top of windows
+     * @param value {boolean}
+     */
+    showHud(value) {
+        debug_set_show_hud(value);
+    }
+}
+
+/**
+ * @typedef {{reduceMotion: boolean, highContrast: boolean, textScale: number}} A11yPreferences
+ */
+export class A11y {
+    /**
+     * Accessibility preferences of system, could also be tested in stylesheets with
+     * `@media (prefers-reduced-motion: reduce)`, `@media (prefers-contrast: more)`
+     * and `@media (min-text-scale: 1.5)`
+     * @returns {A11yPreferences}
+     */
+    get preferences() {
+        return a11y_get_preferences();
+    }
+
+    /**
+     * Override preferences of system, e.g. with settings of the app
+     * @param preferences {Partial<A11yPreferences>}
+     */
+    setPreferences(preferences) {
+        a11y_set_preferences({...this.preferences, ...preferences});
+    }
+}
+
+/**
+ * @typedef {{accentColor: string, fontFamily: string, fontSize: number, scrollbarWidth: number, doubleClickInterval: number, caretBlinkInterval: number}} SystemMetricsInfo
+ */
+export class SystemMetrics {
+    #listeners = [];
+
+    /**
+     * Metrics of system, accent color, font and scrollbar width are also available in stylesheets as
+     * `var(--deft-accent-color)`, `var(--deft-system-font-family)`, `var(--deft-system-font-size)`
+     * and `var(--deft-scrollbar-width)`
+     * @returns {SystemMetricsInfo}
+     */
+    get() {
+        return system_metrics_get();
+    }
+
+    /**
+     * change is emitted when any metric changes with the theme of system
+     * @param type {"change"}
+     * @param listener {(metrics: SystemMetricsInfo) => void}
+     */
+    addEventListener(type, listener) {
+        if (type !== "change") {
+            return;
+        }
+        this.#listeners.push(listener);
+        if (this.#listeners.length === 1) {
+            system_metrics_set_change_handler(metrics => {
+                for (const listener of this.#listeners.slice()) {
+                    listener(metrics);
+                }
+            });
+        }
+    }
+
+    /**
+     * @param type {"change"}
+     * @param listener {(metrics: SystemMetricsInfo) => void}
+     */
+    removeEventListener(type, listener) {
+        if (type !== "change") {
+            return;
+        }
+        this.#listeners = this.#listeners.filter(it => it !== listener);
+        if (!this.#listeners.length) {
+            system_metrics_set_change_handler(null);
+        }
+    }
+}
+
+/**
+ * @typedef {{index: number, name: string | null, x: number, y: number, width: number, height: number, scaleFactor: number, refreshRate: number | null, primary: boolean}} MonitorInfo
+ */
+export class Screen {
+    #listeners = [];
+
+    /**
+     * Monitors connected to the system, bounds are in logical pixels of each monitor
+     * @returns {MonitorInfo[]}
+     */
+    getMonitors() {
+        return screen_get_monitors();
+    }
+
+    /**
+     * monitorschange is emitted with all monitors when displays are added, removed or changed
+     * @param type {"monitorschange"}
+     * @param listener {(monitors: MonitorInfo[]) => void}
+     */
+    addEventListener(type, listener) {
+        if (type !== "monitorschange") {
+            return;
+        }
+        this.#listeners.push(listener);
+        if (this.#listeners.length === 1) {
+            screen_set_change_handler(monitors => {
+                for (const listener of this.#listeners.slice()) {
+                    listener(monitors);
+                }
+            });
+        }
+    }
+
+    /**
+     * @param type {"monitorschange"}
+     * @param listener {(monitors: MonitorInfo[]) => void}
+     */
+    removeEventListener(type, listener) {
+        if (type !== "monitorschange") {
+            return;
+        }
+        this.#listeners = this.#listeners.filter(it => it !== listener);
+        if (!this.#listeners.length) {
+            screen_set_change_handler(null);
+        }
+    }
+}
+
+/**
+ * @typedef {{url: string, username?: string, password?: string, noProxy?: string[]}} ProxyConfig
+ * @typedef {{online: boolean, address: string | null}} NetworkChangeEvent
+ * @typedef {{online: boolean, connectionType: "wifi" | "ethernet" | "cellular" | "unknown" | "none"}} NetworkStatus
+ */
+export class Network {
+    static #handlerSetters = {
+        networkchange: handler => network_set_change_handler(handler),
+        networkstatuschange: handler => network_set_status_handler(handler),
+    };
+    #listeners = new Map();
+
+    /**
+     * Use proxy for http, fetch and websocket connections
+     * @param config {ProxyConfig} url is like "http://host:port", "https://host:port" or "socks5://host:port"
+     */
+    setProxy(config) {
+        network_set_proxy(config);
+    }
+
+    /**
+     * Use proxy of system settings or environment variables like HTTPS_PROXY, which is the default
+     */
+    useSystemProxy() {
+        network_use_system_proxy();
+    }
+
+    /**
+     * Connect directly without any proxy
+     */
+    disableProxy() {
+        network_disable_proxy();
+    }
+
+    /**
+     * Trust a root certificate besides the built-in ones
+     * @param pem {string} PEM encoded certificate
+     */
+    addRootCertificate(pem) {
+        network_add_root_certificate(pem);
+    }
+
+    /**
+     * Reject https and wss connections to host during tls handshakes unless its certificate matches
+     * one of the pinned hashes
+     * @param host {string}
+     * @param hash {string} sha256 digest of DER encoded certificate, e.g. "sha256/<base64 digest>"
+     */
+    pinCertificate(host, hash) {
+        network_pin_certificate(host, hash);
+    }
+
+    /**
+     * Current connectivity, connection type is only reported on some platforms like android
+     * @returns {NetworkStatus}
+     */
+    status() {
+        return network_status();
+    }
+
+    /**
+     * networkchange is emitted when the default route changes,
+     * networkstatuschange when connectivity or connection type changes
+     * @param type {"networkchange" | "networkstatuschange"}
+     * @param listener {(event: NetworkChangeEvent | NetworkStatus) => void}
+     */
+    addEventListener(type, listener) {
+        let listeners = this.#listeners.get(type);
+        if (!listeners) {
+            listeners = [];
+            this.#listeners.set(type, listeners);
+        }
+        listeners.push(listener);
+        if (listeners.length === 1) {
+            Network.#handlerSetters[type]?.(event => this.#emit(type, event));
+        }
+    }
+
+    /**
+     * @param type {"networkchange" | "networkstatuschange"}
+     * @param listener {(event: NetworkChangeEvent | NetworkStatus) => void}
+     */
+    removeEventListener(type, listener) {
+        const listeners = this.#listeners.get(type);
+        if (!listeners) {
+            return;
+        }
+        const remaining = listeners.filter(it => it !== listener);
+        this.#listeners.set(type, remaining);
+        if (!remaining.length) {
+            Network.#handlerSetters[type]?.(null);
+        }
+    }
+
+    #emit(type, event) {
+        for (const listener of (this.#listeners.get(type) || []).slice()) {
+            try {
+                listener(event);
+            } catch (error) {
+                console.error(`Failed to call ${type} listener`, error);
+            }
+        }
+    }
+}
+
+/**
+ * @typedef {{authorizationEndpoint: string, tokenEndpoint: string, clientId: string, clientSecret?: string, scopes?: string[], redirectUri?: string, params?: Record<string, string>, account?: string, timeout?: number}} OAuthConfig
+ * @typedef {{accessToken: string, tokenType: string | null, expiresAt: number | null, refreshToken: string | null, idToken: string | null, scope: string | null}} OAuthTokens
+ */
+/**
+ * OAuth authorization code flow with PKCE in the system browser
+ */
+export class OAuth {
+    /**
+     * Open the authorization page in the system browser and exchange the redirected code for tokens.
+     * Refresh token is stored in secure storage when config.account is set
+     * @param config {OAuthConfig}
+     * @returns {Promise<OAuthTokens>}
+     */
+    authorize(config) {
+        return oauth_authorize(config);
+    }
+
+    /**
+     * @param config {OAuthConfig}
+     * @param refreshToken {string | undefined} defaults to the token stored for config.account
+     * @returns {Promise<OAuthTokens>}
+     */
+    refresh(config, refreshToken) {
+        return oauth_refresh(config, refreshToken);
+    }
+
+    /**
+     * @param account {string}
+     * @returns {string | null}
+     */
+    getRefreshToken(account) {
+        return oauth_get_refresh_token(account);
+    }
+
+    /**
+     * Delete the refresh token stored for account
+     * @param account {string}
+     */
+    signOut(account) {
+        oauth_sign_out(account);
+    }
+
+    /**
+     * Forward custom scheme redirect to the pending authorization
+     * @param url {string}
+     * @returns {boolean} false if no authorization is waiting for it
+     */
+    handleRedirect(url) {
+        return oauth_handle_redirect(url);
+    }
+}
+
+/**
+ * @typedef {{id: string, title: string, description: string, price: string, priceMicros: number, currency: string}} Product
+ * @typedef {{productId: string, transactionId: string, purchaseTime: number, state: "purchased" | "pending" | "restored", receipt: string}} Purchase
+ */
+/**
+ * In-app purchase with Play Billing on android and StoreKit on ios, bridged by the host app
+ */
+export class InAppPurchase {
+    #listeners = [];
+
+    /**
+     * Whether the host app implements the store bridge
+     * @returns {boolean}
+     */
+    isSupported() {
+        return iap_is_supported();
+    }
+
+    /**
+     * @param ids {string[]}
+     * @returns {Promise<Product[]>}
+     */
+    queryProducts(ids) {
+        return iap_query_products(ids);
+    }
+
+    /**
+     * Resolved when the store completes or defers the purchase, deferred purchases are
+     * delivered to purchaseupdate listeners when completed
+     * @param productId {string}
+     * @returns {Promise<Purchase>}
+     */
+    purchase(productId) {
+        return iap_purchase(productId);
+    }
+
+    /**
+     * @returns {Promise<Purchase[]>}
+     */
+    restore() {
+        return iap_restore();
+    }
+
+    /**
+     * Acknowledge or consume a purchase after its content is delivered
+     * @param purchase {Purchase}
+     * @param consume {boolean} true for consumable products which could be purchased again
+     * @returns {Promise<void>}
+     */
+    finish(purchase, consume = false) {
+        return iap_finish(purchase, consume);
+    }
+
+    /**
+     * Post purchase to the verification server of app, which should verify it with the store
+     * @param url {string}
+     * @param purchase {Purchase}
+     * @returns {Promise<any>} json response of server
+     */
+    async verify(url, purchase) {
+        return JSON.parse(await iap_verify(url, purchase));
+    }
+
+    /**
+     * @param type {"purchaseupdate"}
+     * @param listener {(purchases: Purchase[]) => void}
+     */
+    addEventListener(type, listener) {
+        if (type !== "purchaseupdate") {
+            return;
+        }
+        this.#listeners.push(listener);
+        if (this.#listeners.length === 1) {
+            iap_set_update_handler(purchases => {
+                for (const listener of this.#listeners.slice()) {
+                    try {
+                        listener(purchases);
+                    } catch (error) {
+                        console.error("Failed to call purchaseupdate listener", error);
+                    }
+                }
+            });
+        }
+    }
+
+    /**
+     * @param type {"purchaseupdate"}
+     * @param listener {(purchases: Purchase[]) => void}
+     */
+    removeEventListener(type, listener) {
+        if (type !== "purchaseupdate") {
+            return;
+        }
+        this.#listeners = this.#listeners.filter(it => it !== listener);
+        if (!this.#listeners.length) {
+            iap_set_update_handler(null);
+        }
+    }
+}
+
+/**
+ * @typedef {"queued" | "downloading" | "paused" | "completed" | "failed" | "canceled"} DownloadState
+ * @typedef {{id: number, url: string, path: string, headers: Record<string, string>, state: DownloadState, received: number, total: number | null, speed: number, error: string | null}} DownloadInfo
+ * @typedef {{queued: number, downloading: number, paused: number, completed: number, failed: number, received: number, total: number | null, speed: number}} DownloadSummary
+ * @typedef {{type: "progress" | "statechange", download: DownloadInfo, summary: DownloadSummary}} DownloadEvent
+ */
+/**
+ * Queued downloads saved across restarts, unfinished downloads are resumed from their partial files
+ */
+export class DownloadManager {
+    #listeners = new Map();
+    #listening = false;
+
+    /**
+     * Queue a download, data is written to `${path}.part` and renamed to path when completed
+     * @param url {string}
+     * @param path {string}
+     * @param options {{headers?: Record<string, string>}}
+     * @returns {number} id of download
+     */
+    add(url, path, options) {
+        return download_add(url, path, options);
+    }
+
+    /**
+     * @param id {number}
+     */
+    pause(id) {
+        download_pause(id);
+    }
+
+    /**
+     * Resume a paused or failed download
+     * @param id {number}
+     */
+    resume(id) {
+        download_resume(id);
+    }
+
+    /**
+     * Stop the download and delete its partial file
+     * @param id {number}
+     */
+    cancel(id) {
+        download_cancel(id);
+    }
+
+    /**
+     * Remove a completed, failed or canceled download from the list, the downloaded file is kept
+     * @param id {number}
+     */
+    remove(id) {
+        download_remove(id);
+    }
+
+    /**
+     * @param id {number}
+     * @returns {DownloadInfo | undefined}
+     */
+    get(id) {
+        return download_get(id);
+    }
+
+    /**
+     * @returns {DownloadInfo[]}
+     */
+    list() {
+        return download_list();
+    }
+
+    /**
+     * @returns {DownloadSummary}
+     */
+    summary() {
+        return download_summary();
+    }
+
+    /**
+     * Limit of downloads running at the same time, default is 3
+     * @param max {number}
+     */
+    setMaxConcurrent(max) {
+        download_set_max_concurrent(max);
+    }
+
+    /**
+     * summary is emitted with DownloadSummary whenever progress or state of any download changes
+     * @param type {"progress" | "statechange" | "summary"}
+     * @param listener {(event: DownloadEvent | DownloadSummary) => void}
+     */
+    addEventListener(type, listener) {
+        let listeners = this.#listeners.get(type);
+        if (!listeners) {
+            listeners = [];
+            this.#listeners.set(type, listeners);
+        }
+        listeners.push(listener);
+        if (!this.#listening) {
+            this.#listening = true;
+            download_set_handler(event => {
+                this.#emit(event.type, event);
+                this.#emit("summary", event.summary);
+            });
+        }
+    }
+
+    /**
+     * @param type {"progress" | "statechange" | "summary"}
+     * @param listener {(event: DownloadEvent | DownloadSummary) => void}
+     */
+    removeEventListener(type, listener) {
+        const listeners = this.#listeners.get(type);
+        if (!listeners) {
+            return;
+        }
+        this.#listeners.set(type, listeners.filter(it => it !== listener));
+    }
+
+    #emit(type, event) {
+        for (const listener of (this.#listeners.get(type) || []).slice()) {
+            try {
+                listener(event);
+            } catch (error) {
+                console.error(`Failed to call ${type} listener`, error);
+            }
+        }
+    }
+}
+
+/**
+ * @typedef {string | ArrayBuffer | Uint8Array | number[]} ProtocolBody
+ * @typedef {ProtocolBody | {status?: number, headers?: Record<string, string>, body?: ProtocolBody}} ProtocolResult
+ * @typedef {{url: string, method: string, headers: Record<string, string>, body?: string | ArrayBuffer}} ProtocolRequest
+ */
+/**
+ * Custom url scheme handlers used by fetch and image sources
+ */
+export class Protocol {
+    static #handlers = new Map();
+
+    /**
+     * Serve urls like `{scheme}://...` with handler, registering schemes like "https" intercepts the requests.
+     * Image sources require the handler to return synchronously, fetch accepts promises.
+     * Modules could only be served by handlers registered in rust.
+     * @param scheme {string}
+     * @param handler {(request: ProtocolRequest) => ProtocolResult | Promise<ProtocolResult>}
+     */
+    register(scheme, handler) {
+        scheme = scheme.toLowerCase();
+        Protocol.#handlers.set(scheme, handler);
+        protocol_register(scheme, request => {
+            const result = handler(request);
+            if (result && typeof result.then === "function") {
+                throw new Error(`protocol handler of ${scheme} should return synchronously for images`);
+            }
+            return Protocol.#normalize(result);
+        });
+    }
+
+    /**
+     * @param scheme {string}
+     */
+    unregister(scheme) {
+        scheme = scheme.toLowerCase();
+        Protocol.#handlers.delete(scheme);
+        protocol_unregister(scheme);
+    }
+
+    /**
+     * Fetch url with protocol handlers, null if no handler serves its scheme
+     * @param url {string}
+     * @param options {FetchOptions}
+     * @returns {Promise<FetchResponse | null>}
+     */
+    static async fetch(url, options = {}) {
+        const scheme = /^([a-zA-Z][a-zA-Z0-9+.-]*):/.exec(url)?.[1]?.toLowerCase();
+        if (!scheme) {
+            return null;
+        }
+        const headers = {};
+        for (const [name, value] of Object.entries(options.headers || {})) {
+            headers[name.toLowerCase()] = value;
+        }
+        const request = {url, method: (options.method || "GET").toUpperCase(), headers, body: options.body};
+        const handler = Protocol.#handlers.get(scheme);
+        if (handler) {
+            const result = Protocol.#normalize(await handler(request));
+            return new FetchResponse(null, result.status ?? 200, {
+                headers: result.headers || {},
+                body: result.data || new ArrayBuffer(0),
+            });
+        }
+        if (protocol_has_native_handler(scheme)) {
+            const result = await protocol_fetch(request);
+            return new FetchResponse(null, result.status, {headers: result.headers, body: result.body});
+        }
+        return null;
+    }
+
+    static #normalize(result) {
+        const isBody = value => typeof value === "string" || value instanceof ArrayBuffer
+            || ArrayBuffer.isView(value) || Array.isArray(value);
+        const response = isBody(result) ? {body: result} : (result || {});
+        const {status, body} = response;
+        const headers = {};
+        for (const [name, value] of Object.entries(response.headers || {})) {
+            headers[name.toLowerCase()] = String(value);
+        }
+        let data;
+        if (typeof body === "string") {
+            data = Bytes_toArrayBuffer(unescape(encodeURIComponent(body)));
+        } else if (body instanceof ArrayBuffer) {
+            data = body;
+        } else if (ArrayBuffer.isView(body)) {
+            data = body.buffer.slice(body.byteOffset, body.byteOffset + body.byteLength);
+        } else if (Array.isArray(body)) {
+            data = new Uint8Array(body).buffer;
+        }
+        return {status, headers, data};
+    }
+}
+
+/**
+ * Json config file in data dir, reloaded when edited by others
+ * @template {Record<string, any>} T
+ */
+export class Config {
+    static #listenerBound = false;
+    static #configs = new Map();
+
+    #name;
+    #listeners = [];
+
+    constructor(name) {
+        this.#name = name;
+    }
+
+    /**
+     * Load config from `{name}.json` in data dir, keys missing in file take values of defaults
+     * @template {Record<string, any>} T
+     * @param defaults {T}
+     * @param name {string}
+     * @returns {Config<T>}
+     */
+    static load(defaults = {}, name = "config") {
+        if (!Config.#listenerBound) {
+            Config.#listenerBound = true;
+            config_add_change_listener((name, keys) => {
+                Config.#configs.get(name)?.#emitChange(keys);
+            });
+        }
+        config_load(name, JSON.stringify(defaults));
+        let config = Config.#configs.get(name);
+        if (!config) {
+            config = new Config(name);
+            Config.#configs.set(name, config);
+        }
+        return config;
+    }
+
+    /**
+     * @template {keyof T} K
+     * @param key {K}
+     * @returns {T[K]}
+     */
+    get(key) {
+        const value = config_get(this.#name, key);
+        return value === undefined || value === null ? undefined : JSON.parse(value);
+    }
+
+    /**
+     * Set value and save config file
+     * @template {keyof T} K
+     * @param key {K}
+     * @param value {T[K]}
+     */
+    set(key, value) {
+        config_set(this.#name, key, JSON.stringify(value));
+    }
+
+    /**
+     * Listen changes, including edits of the file by others
+     * @param listener {(keys: string[]) => void}
+     */
+    addChangeListener(listener) {
+        this.#listeners.push(listener);
+    }
+
+    /**
+     * @param listener {(keys: string[]) => void}
+     */
+    removeChangeListener(listener) {
+        this.#listeners = this.#listeners.filter(l => l !== listener);
+    }
+
+    #emitChange(keys) {
+        for (const listener of this.#listeners) {
+            try {
+                listener(keys);
+            } catch (error) {
+                console.error("config change listener error", error);
+            }
+        }
+    }
+}
+
+export class Autofill {
+    /**
+     * Set desktop autofill provider, e.g. a password manager.
+     * The callback is called with autocomplete hint of the focused empty input, null to remove provider
+     * @param provider {((hint: string) => string | null) | null}
+     */
+    setProvider(provider) {
+        autofill_set_provider(provider);
+    }
+}
+
+export class FileDialog {
+    /**
+     *
+     * @param options {ShowFileDialogOptions}
+     * @returns {Promise<string[]>}
+     */
+    show(options) {
+        return new Promise((resolve, reject) => {
+            dialog_show_file_dialog({
+                dialogType: options.dialogType,
+            }, options.window?.handle, (result, data) => {
+                if (result) {
+                    resolve(data);
+                } else {
+                    reject(data);
+                }
+            })
+        })
+
+    }
+}
+
+export class Page {
+    handle
+    constructor(handle) {
+        this.handle = handle;
+    }
+    close() {
+        Page_close(this.handle);
+    }
+
+    /**
+     * Close page after animation finished, the page is closed anyway after timeout
+     * @param animation {() => Promise<void>}
+     * @param timeout {number} in milliseconds
+     * @returns {Promise<void>}
+     */
+    async closeWithAnimation(animation, timeout = 1000) {
+        const timer = new Promise(resolve => setTimeout(resolve, timeout));
+        try {
+            await Promise.race([Promise.resolve().then(animation), timer]);
+        } finally {
+            this.close();
+        }
+    }
+}
+
+export class Popup {
+    handle
+    constructor(handle) {
+        this.handle = handle;
+    }
+    close() {
+        Popup_close(this.handle);
+    }
+
+    /**
+     * Actual position and placement after flipping, null before the content is laid out
+     * @returns {PopupPlacement | null}
+     */
+    get placement() {
+        return Popup_get_placement(this.handle);
+    }
+}
+
+export class StandardMenuItem {
+    #handle;
+    constructor(label, callback) {
+        this.#handle = StandardMenuItem_js_new(label, callback);
+    }
+
+    /**
+     *
+     * @param value {boolean}
+     */
+    set disabled(value) {
+        StandardMenuItem_set_disabled(this.#handle, value);
+    }
+
+    /**
+     *
+     * @returns {boolean}
+     */
+    get disabled() {
+        return StandardMenuItem_get_disabled(this.#handle);
+    }
+
+    /**
+     * Shortcut hint shown after label, the shortcut itself should be handled by the app
+     * @param value {string | null} e.g. "CmdOrCtrl+S", which is shown as "Cmd+S" on macos
+     */
+    set accelerator(value) {
+        StandardMenuItem_set_accelerator(this.#handle, value);
+    }
+
+    /**
+     *
+     * @returns {string | null}
+     */
+    get accelerator() {
+        return StandardMenuItem_get_accelerator(this.#handle);
+    }
+
+    get handle() {
+        return this.#handle;
+    }
+}
+
+export class Menu {
+    #handle
+
+    constructor() {
+        this.#handle = Menu_new();
+    }
+
+    /**
+     *
+     * @param item {StandardMenuItem}
+     */
+    addStandardItem(item) {
+        Menu_add_standard_item(this.#handle, item.handle);
+    }
+
+    addSeparator() {
+        Menu_add_separator(this.#handle);
+    }
+
+    get handle() {
+        return this.#handle;
+    }
+
+}
+
+/**
+ * @typedef {{id?: string, kind?: "standard" | "checkmark" | "separator" | "submenu", label?: string, accelerator?: string, enabled?: boolean, checked?: boolean, items?: NativeMenuItem[]}} NativeMenuItem
+ * @typedef {{id: string | null, checked: boolean | null}} MenuSelectDetail
+ */
+/**
+ * Menu shown by native widgets on windows and macos, rendered by elements on other platforms
+ */
+export class NativeMenu {
+    #handle;
+    #eventRegistry;
+
+    /**
+     * @param items {NativeMenuItem[]} kind is inferred from items and checked if missing
+     */
+    constructor(items) {
+        this.#handle = Menu_create(items);
+        this.#eventRegistry = new EventRegistry(this.#handle, Menu_bind_event, Menu_remove_event_listener, this);
+    }
+
+    /**
+     * Show as context menu
+     * @param window {Window}
+     * @param x {number} relative to window
+     * @param y {number}
+     */
+    popup(window, x, y) {
+        Menu_popup(this.#handle, window.handle, x, y);
+    }
+
+    /**
+     * @param id {string}
+     * @param checked {boolean}
+     */
+    setChecked(id, checked) {
+        Menu_set_checked(this.#handle, id, checked);
+    }
+
+    /**
+     * @param id {string}
+     * @param enabled {boolean}
+     */
+    setEnabled(id, enabled) {
+        Menu_set_enabled(this.#handle, id, enabled);
+    }
+
+    /**
+     * menuselect is emitted when an item is selected, checkmark items are toggled before
+     * @param type {"menuselect"}
+     * @param listener {(e: IEvent<MenuSelectDetail>) => void}
+     */
+    addEventListener(type, listener) {
+        this.#eventRegistry.addEventListener(type, listener);
+    }
+
+    /**
+     * @param type {"menuselect"}
+     * @param listener {(e: IEvent<MenuSelectDetail>) => void}
+     */
+    removeEventListener(type, listener) {
+        this.#eventRegistry.removeEventListener(type, listener);
+    }
+
+    get handle() {
+        return this.#handle;
+    }
+}
+
+/**
+ * @typedef {IEvent<ResizeDetail>} IResizeEvent
+ * @typedef {IEvent<ImeDetail>} IImeEvent
+ * @typedef {IEvent<ZoomDetail>} IZoomEvent
+ * @typedef {IEvent<RendererChangeDetail>} IRendererChangeEvent
+ * @typedef {IEvent<VisibilityChangeDetail>} IVisibilityChangeEvent
+ * @typedef {IEvent<SnapDetail>} ISnapEvent
+ * @typedef {IEvent<ThemeChangeDetail>} IThemeChangeEvent
+ * @typedef {{caseSensitive?: boolean}} FindOptions
+ * @typedef {{count: number, activeIndex: number}} FindResult
+ * @typedef {{eid: number, tag: string, id?: string, testId?: string}} ElementPathEntry
+ * @typedef {{type: "mousemove", x: number, y: number}
+ *   | {type: "mousedown" | "mouseup" | "click", x?: number, y?: number, button?: "left" | "right" | "middle" | "back" | "forward"}
+ *   | {type: "wheel", x?: number, y?: number, deltaX?: number, deltaY?: number, deltaMode?: number}
+ *   | {type: "keydown" | "keyup", key: string, ctrlKey?: boolean, shiftKey?: boolean, altKey?: boolean, metaKey?: boolean, repeat?: boolean}
+ *   | {type: "text", text: string}
+ *   | {type: "touch", id?: number, phase: "start" | "move" | "end" | "cancel", x: number, y: number}} InjectedInput
+ * @typedef {{speed?: number, deterministicTimers?: boolean}} ReplayOptions
+ * @typedef {{layerCount: number, paintedElements: number, culledElements: number, cachedSurfaceBytes: number, frameTimeMs: number}} RenderStats
+ * @typedef {{layerImages?: boolean}} RenderTreeDumpOptions
+ * @typedef {{bold?: boolean, italic?: boolean, underline?: boolean, link?: string}} RichTextAttributes
+ * @typedef {{bold?: boolean, italic?: boolean, underline?: boolean, link?: string}} RichTextFormat
+ * @typedef {{image: string, width?: number, height?: number}} RichTextImage
+ * @typedef {{insert: string | RichTextImage, attributes?: RichTextAttributes}} RichDeltaOp
+ * @typedef {{ops: RichDeltaOp[]}} RichContent
+ * @typedef {"top" | "top-start" | "top-end" | "bottom" | "bottom-start" | "bottom-end" | "left" | "left-start" | "left-end" | "right" | "right-start" | "right-end"} Placement
+ * @typedef {{placement?: Placement, offset?: number, flip?: boolean, shift?: boolean, padding?: number}} PopupOptions
+ * @typedef {{x: number, y: number, placement: Placement, arrowOffset: number}} PopupPlacement
+ * @typedef {{backgroundColor: string, backgroundImage: boolean, borderWidth: number[], borderColor: string[], borderRadius: number[]}} StyleSummary
+ * @typedef {{id: number, tag: string, coord: number[], layerCoord: number[], width: number, height: number, transform?: string, style: StyleSummary, children: ElementObjectDump[]}} ElementObjectDump
+ * @typedef {{elementId: number, layerType: "root" | "children", matrix: string, totalMatrix: string, width: number, height: number, originAbsolutePos: number[], scrollOffset: number[], surfaceBounds: ElementRect, visibleBounds: ElementRect, clipRect?: ElementRect, invalidArea: "full" | "partial" | "none", invalidRects: ElementRect[], elements: ElementObjectDump[], layers: LayerDump[], image?: string}} LayerDump
+ * @typedef {{elementId: number, x: number, y: number, layer?: LayerDump}} LayerRootDump
+ * @typedef {{scaleFactor: number, viewport: ElementRect, layerRoots: LayerRootDump[]}} RenderTreeDump
+ * @typedef {{left: number, top: number, width: number, height: number, margin: number[], border: number[], padding: number[]}} LayoutBox
+ * @typedef {{id: number, tag: string, style: Record<string, string>, layout: LayoutBox, children: LayoutDump[]}} LayoutDump
+ * @typedef {{origin: "default" | "rule" | "inline" | "hover" | "animation", selector?: string, value: string}} CascadeEntry
+ * @typedef {{property: string, value: string, winner?: CascadeEntry, cascade: CascadeEntry[]}} StyleTrace
+ * @typedef {{scrollLeft: number, scrollTop: number, anchor?: number, anchorOffset: number}} ScrollState
+ * @typedef {{left?: number, top?: number, behavior?: "auto" | "smooth"}} ScrollToOptions
+ */
+export class Window {
+
+    /**
+     * @type EventRegistry
+     */
+    #eventRegistry;
+
+    /**
+     * @type EventBinder
+     */
+    #eventBinder;
+
+    #windowHandle;
+
+    #body;
+
+    #closeAnimationListener = null;
+
+    /**
+     *
+     * @param attrs {WindowAttrs}
+     */
+    constructor(attrs = null) {
+        attrs = attrs || {};
+        attrs.preferredRenderers = [].concat(attrs.preferredRenderers || [])
+        this.#windowHandle = Window_create(attrs);
+        this.#eventBinder = new EventBinder(this.#windowHandle, Window_bind_js_event_listener, Window_unbind_js_event_listener, this);
+        Window_set_js_context(this.#windowHandle, this);
+        this.#body = new BodyElement();
+        Window_set_body(this.#windowHandle, this.#body.handle);
+    }
+
+    /**
+     *
+     * @param windowHandle
+     * @returns {Window}
+     */
+    static fromHandle(windowHandle) {
+        return Window_get_js_context(windowHandle);
+    }
+
+    static supportMultipleWindows() {
+        return Window_support_multiple_windows();
+    }
+
+    get handle() {
+        return this.#windowHandle
+    }
+
+    /**
+     *
+     * @returns {BodyElement}
+     */
+    get body() {
+        return this.#body;
+    }
+
+
+    /**
+     *
+     * @returns {{width: number, height: number}}
+     */
+    get innerSize() {
+        const [width, height] = Window_get_inner_size(this.handle);
+        return {width, height}
+    }
+
+    /**
+     *
+     * @param content {Element}
+     * @param x {number}
+     * @param y {number}
+     * @return {Page}
+     */
+    createPage(content, x, y) {
+        x = x ?? Number.NaN;
+        y = y ?? Number.NaN;
+        const page = Window_create_page(this.#windowHandle, content.handle, x, y);
+        Element_retain_js_context(content.handle, content);
+        return new Page(page);
+    }
+
+    /**
+     *
+     * @param content {Element}
+     * @param target {{x: number, y: number, width?: number, height?: number}}
+     * @param options {PopupOptions} placement relative to target, defaults to "bottom-start"
+     *  flipping and shifting to stay inside the window or screen
+     * @return {Popup}
+     */
+    popup(content, target, options) {
+        const rect = {
+            x: target.x,
+            y: target.y,
+            width: target.width || 0,
+            height: target.height || 0,
+        }
+        const handle = Window_popup(this.handle, content.handle, rect, options);
+        Element_retain_js_context(content.handle, content);
+        return new Popup(handle);
+    }
+
+    /**
+     *
+     * @param menu {Menu | NativeMenu}
+     * @param x {number}
+     * @param y {number}
+     */
+    popupMenu(menu, x, y) {
+        if (menu instanceof NativeMenu) {
+            menu.popup(this, x, y);
+            return;
+        }
+        Window_popup_menu(this.#windowHandle, menu.handle, x, y);
+    }
+
+    /**
+     * Show menu as native menubar on windows and macos, where the menubar is shared by the app
+     * and top level items should be submenus. Throws on other platforms, e.g. linux where windows
+     * are not created by gtk, render the menubar with elements there.
+     * @param menu {NativeMenu | null}
+     */
+    setMenu(menu) {
+        Window_set_menu(this.#windowHandle, menu ? menu.handle : null);
+    }
+
+    /**
+     * Register shortcut handled before key events of elements, keys are matched by physical position
+     * so that shortcuts work with any keyboard layout. Shortcuts of window take precedence over
+     * shortcuts registered by `deft.registerShortcut`. Throws if the shortcut is registered already.
+     * @param accelerator {string} e.g. "Ctrl+Shift+P", "CmdOrCtrl+S", "Alt+F4"
+     * @param callback {() => void}
+     * @returns {number} id of shortcut
+     */
+    registerShortcut(accelerator, callback) {
+        return Window_register_shortcut(this.#windowHandle, accelerator, callback);
+    }
+
+    /**
+     * @param id {number}
+     */
+    unregisterShortcut(id) {
+        Window_unregister_shortcut(this.#windowHandle, id);
+    }
+
+    /**
+     * @param id {number}
+     * @param enabled {boolean}
+     */
+    setShortcutEnabled(id, enabled) {
+        Window_set_shortcut_enabled(this.#windowHandle, id, enabled);
+    }
+
+    /**
+     *
+     * @param message {string | Element}
+     * @param options {AlertOptions}
+     */
+    showAlert(message, options = {}) {
+        options = options || {};
+        this.showConfirm(message, {
+            ...options,
+            hideCancel: true,
+        }).finally(() => {
+            options?.callback && options.callback();
+        });
+    }
+
+    /**
+     *
      * @param message {string | Element}
      * @param options {ConfirmOptions}
      * @returns {Promise<boolean>}
      */
-    showConfirm(message, options = {}) {
-        options = options || {};
-        function createBtn(label) {
-            const btnLabel = new LabelElement();
-            btnLabel.text = label;
-            const btn = new ButtonElement();
-            btn.style = {
-                minWidth: '4em',
-                flexDirection: 'row',
-                justifyContent: 'center',
-            }
-            btn.addChild(btnLabel);
-            return btn;
-        }
-        return new Promise((resolve) => {
-            if (!(message instanceof Element)) {
-                const label = new LabelElement();
-                label.text = message;
-                label.style = {
-                    padding: '2em',
-                }
-                message = label;
-            }
+    showConfirm(message, options = {}) {
+        options = options || {};
+        function createBtn(label) {
+            const btnLabel = new LabelElement();
+            btnLabel.text = label;
+            const btn = new ButtonElement();
+            btn.style = {
+                minWidth: '4em',
+                flexDirection: 'row',
+                justifyContent: 'center',
+            }
+            btn.addChild(btnLabel);
+            return btn;
+        }
+        return new Promise((resolve) => {
+            if (!(message instanceof Element)) {
+                const label = new LabelElement();
+                label.text = message;
+                label.style = {
+                    padding: '2em',
+                }
+                message = label;
+            }
+
+            const footer = new ContainerElement();
+            footer.style = {
+                flexDirection: 'row',
+                justifyContent: 'center',
+                padding: '10px',
+                gap: '2em',
+            }
+            const btn = createBtn(options?.confirmBtnText ?? "OK");
+            footer.addChild(btn);
+
+            let cancelBtn;
+            if (!options.hideCancel) {
+                cancelBtn = createBtn(options?.cancelBtnText ?? "Cancel");
+                footer.addChild(cancelBtn);
+            }
+
+            const wrapper = new ContainerElement();
+            wrapper.style = {
+                minWidth: 200,
+                alignItems: 'center',
+            }
+            wrapper.addChild(message);
+            wrapper.addChild(footer);
+            const dialog = this.showDialog(wrapper, options?.title);
+            btn.bindClick(() => {
+                resolve(true);
+                dialog.close();
+            });
+            cancelBtn?.bindClick(() => {
+                resolve(false);
+                dialog.close();
+            });
+        })
+    }
+
+    /**
+     *
+     * @param content {Element}
+     * @param title {string}
+     * @returns {{close(): void}}
+     */
+    showDialog(content, title) {
+        if (Window.supportMultipleWindows()) {
+            const window = new Window({
+                resizable: false,
+                preferredRenderers: "SoftBuffer",
+                minimizable: false,
+                closable: false,
+            });
+            window.title = title ?? this.title ?? "";
+            window.body.addChild(content);
+            window.setModal(this);
+            window.bindResize((e) => {
+                const parentPos = this.outerPosition;
+                const parentSize = this.innerSize;
+                const x = parentPos.x + (parentSize.width - e.detail.width) / 2.0;
+                const y = parentPos.y + (parentSize.height - e.detail.height) / 2.0;
+                window.outerPosition = {x, y};
+            });
+            return {
+                close() {
+                    window.close();
+                }
+            }
+        } else {
+            const wrapper = new DialogElement();
+            if (title) {
+                const titleEl = new DialogTitleElement();
+                const titleLabelEl = new LabelElement();
+                titleLabelEl.text = title ?? window.title ?? "";
+                titleEl.addChild(titleLabelEl);
+                wrapper.addChild(titleEl);
+            }
+            wrapper.addChild(content);
+            const page = this.createPage(wrapper, NaN, NaN);
+            return {
+                close() {
+                    page.close();
+                }
+            }
+        }
+    }
+
+    /**
+     *
+     * @param title {string}
+     */
+    set title(title) {
+        Window_set_title(this.#windowHandle, title);
+    }
+
+    /**
+     *
+     * @returns {string}
+     */
+    get title() {
+        return Window_get_title(this.#windowHandle);
+    }
+
+    /**
+     *
+     * @returns {{width: number, height: number}}
+     */
+    get monitorSize() {
+        const [width, height] = Window_get_monitor_size(this.#windowHandle);
+        return {
+            width,
+            height
+        }
+    }
+
+    /**
+     *
+     * @param size {Size}
+     */
+    resize(size) {
+        Window_resize(this.#windowHandle, size);
+    }
+
+    /**
+     * Move window to the center of monitor
+     * @param index {number} index of monitor in `deft.screen.getMonitors()`
+     */
+    moveToMonitor(index) {
+        Window_move_to_monitor(this.#windowHandle, index);
+    }
+
+    drag() {
+        Window_drag(this.#windowHandle);
+    }
+
+    /**
+     *
+     * @returns {boolean}
+     */
+    get minimized() {
+        return Window_is_minimized(this.#windowHandle);
+    }
+
+    /**
+     *
+     * @param minimized {boolean}
+     */
+    set minimized(minimized) {
+        Window_set_minimized(this.#windowHandle, minimized);
+    }
+
+    /**
+     *
+     * @returns {boolean}
+     */
+    get maximized() {
+        return Window_is_maximized(this.#windowHandle);
+    }
+
+    /**
+     *
+     * @param maximized {boolean}
+     */
+    set maximized(maximized) {
+        Window_set_maximized(this.#windowHandle, maximized);
+    }
+
+    /**
+     *
+     * @param owner {Window}
+     */
+    setModal(owner) {
+        Window_set_modal(this.#windowHandle, owner.#windowHandle)
+    }
+
+    /**
+     *
+     * @param value {{x: number, y: number}}
+     */
+    set outerPosition(value) {
+        Window_set_outer_position(this.handle, value.x, value.y);
+    }
+
+    /**
+     *
+     * @returns {{x: number, y: number}}
+     */
+    get outerPosition() {
+        const [x, y] = Window_get_outer_position(this.handle);
+        return {x, y}
+    }
+
+    close() {
+        Window_close(this.#windowHandle);
+    }
+
+    /**
+     * Close window after animation finished, the window is closed anyway after timeout
+     * @param animation {() => Promise<void>}
+     * @param timeout {number} in milliseconds
+     */
+    closeWithAnimation(animation, timeout = 1000) {
+        if (!Window_allow_close(this.#windowHandle)) {
+            return;
+        }
+        this.#runCloseAnimation(animation, timeout);
+    }
+
+    /**
+     * Play animation before window is closed by user or close(), e.g. fading out
+     * @param animation {(() => Promise<void>) | null} null to remove animation
+     * @param timeout {number} in milliseconds
+     */
+    setCloseAnimation(animation, timeout = 1000) {
+        if (this.#closeAnimationListener) {
+            this.removeEventListener("close", this.#closeAnimationListener);
+            this.#closeAnimationListener = null;
+        }
+        if (animation) {
+            this.#closeAnimationListener = () => {
+                this.#runCloseAnimation(animation, timeout);
+            };
+            this.addEventListener("close", this.#closeAnimationListener);
+        }
+    }
+
+    #runCloseAnimation(animation, timeout) {
+        const handle = this.#windowHandle;
+        Window_defer_close(handle, timeout);
+        Promise.resolve().then(animation).catch(error => {
+            console.error("close animation error", error);
+        }).finally(() => {
+            try {
+                Window_force_close(handle);
+            } catch (error) {
+                // Closed by timeout already
+            }
+        });
+    }
+
+    /**
+     *
+     * @param visible {boolean}
+     */
+    set visible(visible) {
+        Window_set_visible(this.#windowHandle, visible);
+    }
+
+    /**
+     *
+     * @returns {boolean}
+     */
+    get visible() {
+        return Window_is_visible(this.#windowHandle);
+    }
+
+    requestFullscreen() {
+        Window_request_fullscreen(this.#windowHandle);
+    }
+
+    exitFullscreen() {
+        Window_exit_fullscreen(this.#windowHandle);
+    }
+
+    /**
+     * Release pointer locked by element.requestPointerLock
+     */
+    exitPointerLock() {
+        Window_exit_pointer_lock(this.#windowHandle);
+    }
+
+    get fullscreen() {
+        return Window_is_fullscreen(this.#windowHandle);
+    }
+
+    /**
+     * Kiosk mode shows window in fullscreen on top of other windows, confines cursor in it and
+     * ignores closing by users, e.g. Alt+F4 or close shortcuts. Kiosk mode entered with passcode
+     * could only be exited with the same passcode, an error is thrown if passcode mismatches.
+     * @param enabled {boolean}
+     * @param passcode {string | undefined}
+     */
+    setKiosk(enabled, passcode) {
+        Window_set_kiosk(this.#windowHandle, enabled, passcode);
+    }
+
+    /**
+     * @returns {boolean}
+     */
+    isKiosk() {
+        return Window_is_kiosk(this.#windowHandle);
+    }
+
+    /**
+     * Stats of the last painted frame
+     * @returns {RenderStats}
+     */
+    get renderStats() {
+        return Window_get_render_stats(this.#windowHandle);
+    }
+
+    /**
+     * Highlight text matching query and scroll the first match into view, press Escape to clear
+     * @param query {string}
+     * @param options {FindOptions}
+     * @returns {FindResult}
+     */
+    findInPage(query, options) {
+        return Window_find_in_page(this.#windowHandle, query, options || {});
+    }
+
+    /**
+     * Attached element whose `id` attribute is id
+     * @param id {string}
+     * @returns {Element | null}
+     */
+    getElementById(id) {
+        return Element.fromHandle(Window_get_element_by_id(this.#windowHandle, id));
+    }
+
+    /**
+     * Attached element whose `data-testid` attribute is testId, for ui automation
+     * @param testId {string}
+     * @returns {Element | null}
+     */
+    getElementByTestId(testId) {
+        return Element.fromHandle(Window_get_element_by_test_id(this.#windowHandle, testId));
+    }
+
+    /**
+     * Feed synthetic input through the same handlers as real input, e.g.
+     * `{type: "click", x: 10, y: 20}` or `{type: "keydown", key: "a", ctrlKey: true}`.
+     * Modifier keys pressed by injected keydown are held until injected keyup
+     * @param input {InjectedInput}
+     */
+    injectInput(input) {
+        Window_inject_input(this.#windowHandle, input);
+    }
+
+    /**
+     * Record input events of window to file as json lines, which could be replayed to reproduce bugs
+     * @param path {string}
+     */
+    startInputRecording(path) {
+        Window_start_input_recording(this.#windowHandle, path);
+    }
+
+    stopInputRecording() {
+        Window_stop_input_recording(this.#windowHandle);
+    }
+
+    /**
+     * Inject recorded inputs with original intervals divided by speed. Timers are frozen and advanced
+     * by recorded intervals unless deterministicTimers is false, only one replay could freeze timers
+     * at a time
+     * @param path {string}
+     * @param options {ReplayOptions}
+     */
+    replayInput(path, options) {
+        Window_replay_input(this.#windowHandle, path, options);
+    }
+
+    stopInputReplay() {
+        Window_stop_input_replay(this.#windowHandle);
+    }
+
+    /**
+     * Fired when all inputs of a replay are injected
+     * @param callback {(event: IVoidEvent) => void}
+     */
+    bindInputReplayEnd(callback) {
+        this.bindEvent("inputreplayend", callback);
+    }
 
-            const footer = new ContainerElement();
-            footer.style = {
-                flexDirection: 'row',
-                justifyContent: 'center',
-                padding: '10px',
-                gap: '2em',
-            }
-            const btn = createBtn(options?.confirmBtnText ?? "OK");
-            footer.addChild(btn);
+    /**
+     * Move to next match of findInPage
+     * @returns {FindResult}
+     */
+    findNext() {
+        return Window_find_next(this.#windowHandle);
+    }
 
-            let cancelBtn;
-            if (!options.hideCancel) {
-                cancelBtn = createBtn(options?.cancelBtnText ?? "Cancel");
-                footer.addChild(cancelBtn);
-            }
+    /**
+     * Move to previous match of findInPage
+     * @returns {FindResult}
+     */
+    findPrevious() {
+        return Window_find_previous(this.#windowHandle);
+    }
 
-            const wrapper = new ContainerElement();
-            wrapper.style = {
-                minWidth: 200,
-                alignItems: 'center',
-            }
-            wrapper.addChild(message);
-            wrapper.addChild(footer);
-            const dialog = this.showDialog(wrapper, options?.title);
-            btn.bindClick(() => {
-                resolve(true);
-                dialog.close();
-            });
-            cancelBtn?.bindClick(() => {
-                resolve(false);
-                dialog.close();
-            });
-        })
+    /**
+     * Clear highlights of findInPage
+     */
+    clearFind() {
+        Window_clear_find(this.#windowHandle);
     }
 
     /**
-     *
-     * @param content {Element}
-     * @param title {string}
-     * @returns {{close(): void}}
+     * Set hit margin of edge resizing for undecorated window
+     * @param margin {number}
      */
-    showDialog(content, title) {
-        if (Window.supportMultipleWindows()) {
-            const window = new Window({
-                resizable: false,
-                preferredRenderers: "SoftBuffer",
-                minimizable: false,
-                closable: false,
-            });
-            window.title = title ?? this.title ?? "";
-            window.body.addChild(content);
-            window.setModal(this);
-            window.bindResize((e) => {
-                const parentPos = this.outerPosition;
-                const parentSize = this.innerSize;
-                const x = parentPos.x + (parentSize.width - e.detail.width) / 2.0;
-                const y = parentPos.y + (parentSize.height - e.detail.height) / 2.0;
-                window.outerPosition = {x, y};
-            });
-            return {
-                close() {
-                    window.close();
-                }
-            }
-        } else {
-            const wrapper = new DialogElement();
-            if (title) {
-                const titleEl = new DialogTitleElement();
-                const titleLabelEl = new LabelElement();
-                titleLabelEl.text = title ?? window.title ?? "";
-                titleEl.addChild(titleLabelEl);
-                wrapper.addChild(titleEl);
-            }
-            wrapper.addChild(content);
-            const page = this.createPage(wrapper, NaN, NaN);
-            return {
-                close() {
-                    page.close();
-                }
-            }
-        }
+    set resizeMargin(margin) {
+        Window_set_resize_margin(this.#windowHandle, margin);
     }
 
     /**
      *
-     * @param title {string}
+     * @param edges {"all" | "corners" | "none"}
      */
-    set title(title) {
-        Window_set_title(this.#windowHandle, title);
+    set resizeEdges(edges) {
+        Window_set_resize_edges(this.#windowHandle, edges);
     }
 
     /**
-     *
-     * @returns {string}
+     * Distance in logical pixels within which window dragged by `drag` snaps to screen edges
+     * or other windows, 0 to disable
+     * @param threshold {number}
      */
-    get title() {
-        return Window_get_title(this.#windowHandle);
+    set snapThreshold(threshold) {
+        Window_set_snap_threshold(this.#windowHandle, threshold);
     }
 
     /**
-     *
-     * @returns {{width: number, height: number}}
+     * @returns {number}
      */
-    get monitorSize() {
-        const [width, height] = Window_get_monitor_size(this.#windowHandle);
-        return {
-            width,
-            height
-        }
+    get snapThreshold() {
+        return Window_get_snap_threshold(this.#windowHandle);
+    }
+
+    /**
+     * Material drawn behind the window, only windows created with transparent, effect or shape
+     * attributes support effects
+     * @param effect {WindowEffect}
+     */
+    set effect(effect) {
+        Window_set_effect(this.#windowHandle, effect);
+    }
+
+    /**
+     * @returns {WindowEffect}
+     */
+    get effect() {
+        return Window_get_effect(this.#windowHandle);
+    }
+
+    /**
+     * Shape of window as svg path in logical pixels, pixels out of it are transparent.
+     * Window is restored to rectangle if shape is null.
+     * @param shape {string | null}
+     */
+    set shape(shape) {
+        Window_set_shape(this.#windowHandle, shape);
+    }
+
+    /**
+     * Fired when dragged window snaps to or is pulled away from screen edges or other windows
+     * @param callback {(event: ISnapEvent) => void}
+     */
+    bindSnap(callback) {
+        this.bindEvent("snap", callback);
     }
 
     /**
      *
-     * @param size {Size}
+     * @param callback {(event: IResizeEvent) => void}
      */
-    resize(size) {
-        Window_resize(this.#windowHandle, size);
+    bindResize(callback) {
+        this.#eventBinder.bindEvent("resize", callback);
     }
 
-    drag() {
-        Window_drag(this.#windowHandle);
+    /**
+     * Storage namespace of the window, specified by storageNamespace attribute
+     * @returns {LocalStorage}
+     */
+    get localStorage() {
+        return new LocalStorage(Window_get_storage_namespace(this.#windowHandle));
     }
 
     /**
      *
-     * @returns {boolean}
+     * @param callback {(event: IStorageEvent) => void}
      */
-    get minimized() {
-        return Window_is_minimized(this.#windowHandle);
+    bindStorage(callback) {
+        this.bindEvent("storage", callback);
     }
 
     /**
      *
-     * @param minimized {boolean}
+     * @param callback {(event: IVoidEvent) => void}
      */
-    set minimized(minimized) {
-        Window_set_minimized(this.#windowHandle, minimized);
+    bindResizeStart(callback) {
+        this.bindEvent("resizestart", callback);
     }
 
     /**
      *
-     * @returns {boolean}
+     * @param callback {(event: IVoidEvent) => void}
      */
-    get maximized() {
-        return Window_is_maximized(this.#windowHandle);
+    bindResizeEnd(callback) {
+        this.bindEvent("resizeend", callback);
     }
 
     /**
      *
-     * @param maximized {boolean}
+     * @param callback {(event: IVoidEvent) => void}
      */
-    set maximized(maximized) {
-        Window_set_maximized(this.#windowHandle, maximized);
+    bindClose(callback) {
+        this.bindEvent("close", callback);
     }
 
     /**
      *
-     * @param owner {Window}
+     * @param callback {(event: IVoidEvent) => void}
      */
-    setModal(owner) {
-        Window_set_modal(this.#windowHandle, owner.#windowHandle)
+    bindFocus(callback) {
+        this.bindEvent("focus", callback);
     }
 
     /**
      *
-     * @param value {{x: number, y: number}}
+     * @param callback {(event: IVoidEvent) => void}
      */
-    set outerPosition(value) {
-        Window_set_outer_position(this.handle, value.x, value.y);
+    bindBlur(callback) {
+        this.bindEvent("blur", callback);
+    }
+
+    /**
+     * Fired when soft keyboard is shown, hidden or resized
+     * @param callback {(event: IImeEvent) => void}
+     */
+    bindIme(callback) {
+        this.bindEvent("ime", callback);
+    }
+
+    /**
+     * Show soft keyboard, e.g. for custom editing surfaces. No-op on desktop platforms
+     */
+    showSoftKeyboard() {
+        Window_show_soft_keyboard(this.#windowHandle);
+    }
+
+    hideSoftKeyboard() {
+        Window_hide_soft_keyboard(this.#windowHandle);
+    }
+
+    /**
+     * Scale layout and rendering of the window, independent of scale factor of system.
+     * Ctrl+= and Ctrl+- change zoom by default, Ctrl+0 resets it.
+     * Zoom is saved in localstorage of the window if enabled
+     * @param factor {number} 1.0 is the default size
+     */
+    setZoom(factor) {
+        Window_set_zoom(this.#windowHandle, factor);
     }
 
     /**
      *
-     * @returns {{x: number, y: number}}
+     * @returns {number}
      */
-    get outerPosition() {
-        const [x, y] = Window_get_outer_position(this.handle);
-        return {x, y}
+    getZoom() {
+        return Window_get_zoom(this.#windowHandle);
     }
 
-    close() {
-        Window_close(this.#windowHandle);
+    /**
+     * Fired when zoom of window changed
+     * @param callback {(event: IZoomEvent) => void}
+     */
+    bindZoom(callback) {
+        this.bindEvent("zoom", callback);
+    }
+
+    /**
+     * Color space of window surface, colors and images with embedded color profiles are
+     * converted to it when drawing. Use "display-p3" on wide gamut monitors
+     * @param colorSpace {"srgb" | "display-p3"}
+     */
+    setColorSpace(colorSpace) {
+        Window_set_color_space(this.#windowHandle, colorSpace);
+    }
+
+    /**
+     * @returns {"srgb" | "display-p3"}
+     */
+    getColorSpace() {
+        return Window_get_color_space(this.#windowHandle);
+    }
+
+    /**
+     * Request hdr surface for viewing hdr images or videos, which requires gl renderer on
+     * windows or macos. Sdr content is drawn at sdr white level
+     * @param enabled {boolean}
+     * @returns {boolean} whether hdr is active
+     */
+    setHdrEnabled(enabled) {
+        return Window_set_hdr_enabled(this.#windowHandle, enabled);
+    }
+
+    /**
+     * @returns {boolean}
+     */
+    isHdr() {
+        return Window_is_hdr(this.#windowHandle);
+    }
+
+    /**
+     * Luminance which sdr white is mapped to on hdr surface, usually "sdr content brightness"
+     * of system display settings
+     * @param nits {number} defaults to 80
+     */
+    setSdrWhiteLevel(nits) {
+        Window_set_sdr_white_level(this.#windowHandle, nits);
+    }
+
+    /**
+     * Name of render backend, e.g. "gl" or "softbuffer"
+     * @returns {string}
+     */
+    getRenderer() {
+        return Window_get_renderer(this.#windowHandle);
+    }
+
+    /**
+     * Render content of window to png image without presenting it, works with headless backend
+     * @returns {Uint8Array}
+     */
+    renderToImage() {
+        return new Uint8Array(Window_render_to_image(this.#windowHandle));
+    }
+
+    /**
+     * Snapshot of layers, element objects, matrices, invalid areas and paint styles, which could be
+     * attached to rendering bug reports
+     * @param options {RenderTreeDumpOptions}
+     * @returns {RenderTreeDump}
+     */
+    dumpRenderTree(options = {}) {
+        return Window_dump_render_tree(this.#windowHandle, options);
+    }
+
+    /**
+     * Fired when render backend changed, e.g. fell back to software rendering after gpu context lost
+     * @param callback {(event: IRendererChangeEvent) => void}
+     */
+    bindRendererChange(callback) {
+        this.bindEvent("rendererchange", callback);
+    }
+
+    /**
+     * Whether window is minimized or fully covered by other windows
+     * @returns {boolean}
+     */
+    isOccluded() {
+        return Window_is_occluded(this.#windowHandle);
+    }
+
+    /**
+     * Stop producing frames while window is occluded, default true
+     * @param value {boolean}
+     */
+    set pauseWhenOccluded(value) {
+        Window_set_pause_when_occluded(this.#windowHandle, value);
+    }
+
+    /**
+     * @returns {boolean}
+     */
+    get pauseWhenOccluded() {
+        return Window_get_pause_when_occluded(this.#windowHandle);
     }
 
     /**
-     *
-     * @param visible {boolean}
+     * Maximum frame rate of window, 0 to follow refresh rate of monitor again
+     * @param fps {number}
      */
-    set visible(visible) {
-        Window_set_visible(this.#windowHandle, visible);
+    set maxFps(fps) {
+        Window_set_max_fps(this.#windowHandle, fps);
     }
 
     /**
-     *
-     * @returns {boolean}
+     * @returns {number} the cap, refresh rate of monitor if not capped
      */
-    get visible() {
-        return Window_is_visible(this.#windowHandle);
+    get maxFps() {
+        return Window_get_max_fps(this.#windowHandle);
     }
 
-    requestFullscreen() {
-        Window_request_fullscreen(this.#windowHandle);
+    /**
+     * Frame rate while window is not focused, 0 to disable throttling, not throttled by default
+     * @param fps {number}
+     */
+    set unfocusedFps(fps) {
+        Window_set_unfocused_fps(this.#windowHandle, fps);
     }
 
-    exitFullscreen() {
-        Window_exit_fullscreen(this.#windowHandle);
+    /**
+     * @returns {number}
+     */
+    get unfocusedFps() {
+        return Window_get_unfocused_fps(this.#windowHandle);
     }
 
-    get fullscreen() {
-        return Window_is_fullscreen(this.#windowHandle);
+    /**
+     * Same as setting `maxFps`
+     * @param fps {number}
+     */
+    setMaxFps(fps) {
+        this.maxFps = fps;
     }
 
     /**
-     *
-     * @param callback {(event: IResizeEvent) => void}
+     * Current frame rate after caps and throttling are applied
+     * @returns {number}
      */
-    bindResize(callback) {
-        this.#eventBinder.bindEvent("resize", callback);
+    getFps() {
+        return Window_get_fps(this.#windowHandle);
     }
 
     /**
-     *
-     * @param callback {(event: IVoidEvent) => void}
+     * Fired when window becomes occluded or visible again, not reported on all platforms
+     * @param callback {(event: IVisibilityChangeEvent) => void}
      */
-    bindClose(callback) {
-        this.bindEvent("close", callback);
+    bindVisibilityChange(callback) {
+        this.bindEvent("visibilitychange", callback);
     }
 
     /**
-     *
-     * @param callback {(event: IVoidEvent) => void}
+     * Theme of window, "light" or "dark" unless overridden by `DEFT_THEME`
+     * @returns {string}
      */
-    bindFocus(callback) {
-        this.bindEvent("focus", callback);
+    get theme() {
+        return Window_get_theme(this.#windowHandle);
     }
 
     /**
-     *
-     * @param callback {(event: IVoidEvent) => void}
+     * Fired when theme of system changed, stylesheets could test it with
+     * `@media (prefers-color-scheme: dark)`
+     * @param callback {(event: IThemeChangeEvent) => void}
      */
-    bindBlur(callback) {
-        this.bindEvent("blur", callback);
+    bindThemeChange(callback) {
+        this.bindEvent("themechange", callback);
     }
 
     bindEvent(type, callback) {
@@ -636,9 +2564,10 @@ export class Window {
      * @typedef {("resize", event)} addEventListener
      * @param type
      * @param callback
+     * @param options {EventListenerOptions | undefined}
      */
-    addEventListener(type, callback) {
-        this.#eventBinder.addEventListener(type, callback);
+    addEventListener(type, callback, options) {
+        this.#eventBinder.addEventListener(type, callback, options);
     }
 
     removeEventListener(type, callback) {
@@ -676,6 +2605,15 @@ export class EventObject {
         this.currentTarget = currentTarget;
     }
 
+    /**
+     * Path from root to target element with `id` and `data-testid` attributes, empty if target
+     * is not an element
+     * @returns {ElementPathEntry[]}
+     */
+    get targetPath() {
+        return this.target instanceof Element ? this.target.getPath() : [];
+    }
+
     stopPropagation() {
         this._propagationCancelled = true;
     }
@@ -742,6 +2680,7 @@ export class EventRegistry {
                 callback && callback(event);
             } catch (error) {
                 console.error(`${type} event handling error, detail=`, detail ,error.message || error);
+                reportException(error);
             }
             return event.result();
         }
@@ -750,6 +2689,30 @@ export class EventRegistry {
     }
 }
 
+/**
+ * Weak reference to js objects held by native side if supported by the engine, so that native
+ * references don't keep the objects alive. Falls back to the object itself without WeakRef, which
+ * keeps it alive until the reference is dropped.
+ * @template T
+ * @param value {T}
+ * @returns {WeakRef<T> | T}
+ */
+function nativeRef(value) {
+    return typeof WeakRef === "function" ? new WeakRef(value) : value;
+}
+
+/**
+ * @template T
+ * @param ref {WeakRef<T> | T}
+ * @returns {T | undefined}
+ */
+function derefNativeRef(ref) {
+    return typeof WeakRef === "function" && ref instanceof WeakRef ? ref.deref() : ref;
+}
+
+/**
+ * @typedef {{once?: boolean, passive?: boolean}} EventListenerOptions
+ */
 export class EventBinder {
     #eventListeners = Object.create(null);
     #target;
@@ -779,11 +2742,21 @@ export class EventBinder {
         this.addEventListener(type, callback);
         this.#eventListeners[type] = callback;
     }
-    addEventListener(type, callback) {
+    /**
+     * Listeners of once option are removed after called once, and passive listeners can't
+     * prevent default behaviors. Listeners are released with the target, the callback must not
+     * keep the target alive.
+     * @param type {string}
+     * @param callback {Function}
+     * @param options {EventListenerOptions | undefined}
+     */
+    addEventListener(type, callback, options) {
+        // Native side holds eventCallback, which must not keep the binder or its owner alive
+        const contextGetter = this.#contextGetter;
         const getJsContext = (target) => {
             try {
-                if (target && this.#contextGetter) {
-                    return this.#contextGetter(target);
+                if (target && contextGetter) {
+                    return contextGetter(target);
                 }
                 return target;
             } catch (error) {
@@ -791,7 +2764,12 @@ export class EventBinder {
             }
         }
 
-        const self = this.#self;
+        const selfRef = nativeRef(this.#self);
+        if (!this.#allEventListeners[type]) {
+            this.#allEventListeners[type] = new Map();
+        }
+        const listeners = this.#allEventListeners[type];
+        let id;
 
         /**
          *
@@ -801,19 +2779,23 @@ export class EventBinder {
          * @private
          */
         function eventCallback(detail, target) {
-            const event = new EventObject(type, detail, getJsContext(target), self);
+            // Native side has removed listeners of once option
+            if (options?.once && listeners.get(callback) === id) {
+                listeners.delete(callback);
+            }
+            const event = new EventObject(type, detail, getJsContext(target), derefNativeRef(selfRef));
             try {
                 callback && callback(event);
             } catch (error) {
                 console.error(`${type} event handling error, detail=`, detail ,error.message || error);
+                reportException(error);
             }
             return event.result();
         }
-        if (!this.#allEventListeners[type]) {
-            this.#allEventListeners[type] = new Map();
-        }
-        const id = this.#addEventListenerApi(this.#target, type, eventCallback);
-        this.#allEventListeners[type].set(callback, id);
+        id = options
+            ? this.#addEventListenerApi(this.#target, type, eventCallback, options)
+            : this.#addEventListenerApi(this.#target, type, eventCallback);
+        listeners.set(callback, id);
         return id;
     }
 
@@ -822,13 +2804,210 @@ export class EventBinder {
          * @type {Map}
          */
         const map = this.#allEventListeners[type];
-        const id = map.get(callback);
+        const id = map?.get(callback);
         if (id) {
             map.delete(callback);
             this.#removeEventListenerApi(this.#target, id);
         }
     }
 
+    removeAllEventListeners() {
+        for (const type in this.#allEventListeners) {
+            for (const id of this.#allEventListeners[type].values()) {
+                this.#removeEventListenerApi(this.#target, id);
+            }
+        }
+        this.#allEventListeners = Object.create(null);
+        this.#eventListeners = Object.create(null);
+    }
+
+}
+
+/**
+ * @typedef {{once?: boolean, owner?: object}} EventEmitterListenerOptions
+ * @typedef {{listener: Function, once: boolean}} EventEmitterEntry
+ */
+/**
+ * Event emitter shared by app code and component libraries.
+ * Listeners subscribed with an owner, e.g. an element or a window, are held by the owner instead
+ * of the emitter, so they are released with the owner even if they reference it.
+ * Owners are tracked by WeakRef; on engines without it they are kept alive until all of their
+ * listeners are removed.
+ */
+export class EventEmitter {
+    /**
+     * @type {Map<string, EventEmitterEntry[]>}
+     */
+    #listeners = new Map();
+    /**
+     * @type {WeakMap<object, Map<string, EventEmitterEntry[]>>}
+     */
+    #ownedListeners = new WeakMap();
+    /**
+     * @type {Set<WeakRef<object> | object>}
+     */
+    #owners = new Set();
+
+    /**
+     * @param type {string}
+     * @param listener {Function} called with arguments of `emit`
+     * @param options {EventEmitterListenerOptions | undefined}
+     * @returns {() => void} function to remove the listener
+     */
+    on(type, listener, options) {
+        if (typeof listener !== "function") {
+            throw new Error("invalid listener");
+        }
+        const owner = options && options.owner;
+        let listeners = this.#listeners;
+        if (owner) {
+            listeners = this.#ownedListeners.get(owner);
+            if (!listeners) {
+                listeners = new Map();
+                this.#ownedListeners.set(owner, listeners);
+                this.#owners.add(nativeRef(owner));
+            }
+        }
+        let entries = listeners.get(type);
+        if (!entries) {
+            entries = [];
+            listeners.set(type, entries);
+        }
+        const entry = {listener, once: !!(options && options.once)};
+        entries.push(entry);
+        // Look up the map of owner again instead of capturing it, so that the returned function
+        // doesn't keep the owner alive through its listeners
+        const ownerRef = owner && nativeRef(owner);
+        return () => {
+            const map = owner
+                ? this.#ownedListeners.get(derefNativeRef(ownerRef))
+                : this.#listeners;
+            this.#removeEntry(map, type, entry);
+        };
+    }
+
+    /**
+     * Listen to the first event of type only
+     * @param type {string}
+     * @param listener {Function}
+     * @param options {EventEmitterListenerOptions | undefined}
+     * @returns {() => void} function to remove the listener
+     */
+    once(type, listener, options) {
+        return this.on(type, listener, {...options, once: true});
+    }
+
+    /**
+     * @param type {string}
+     * @param listener {Function}
+     */
+    off(type, listener) {
+        for (const listeners of this.#allListeners()) {
+            const entries = listeners.get(type);
+            const entry = entries && entries.find(it => it.listener === listener);
+            if (entry) {
+                this.#removeEntry(listeners, type, entry);
+                return;
+            }
+        }
+    }
+
+    /**
+     * Call listeners of type in the order they are added, errors thrown by listeners are reported
+     * without stopping the others
+     * @param type {string}
+     * @param args {any[]}
+     * @returns {boolean} whether any listener is called
+     */
+    emit(type, ...args) {
+        const called = [];
+        for (const listeners of this.#allListeners()) {
+            const entries = listeners.get(type);
+            if (!entries) {
+                continue;
+            }
+            called.push(...entries);
+            const remaining = entries.filter(it => !it.once);
+            if (remaining.length) {
+                listeners.set(type, remaining);
+            } else {
+                listeners.delete(type);
+            }
+        }
+        for (const entry of called) {
+            try {
+                entry.listener(...args);
+            } catch (error) {
+                console.error(`${type} event handling error`, error.message || error);
+                reportException(error);
+            }
+        }
+        return called.length > 0;
+    }
+
+    /**
+     * Remove listeners of type, or all listeners if type is not specified
+     * @param type {string | undefined}
+     */
+    removeAllListeners(type) {
+        for (const listeners of this.#allListeners()) {
+            if (type === undefined) {
+                listeners.clear();
+            } else {
+                listeners.delete(type);
+            }
+        }
+    }
+
+    /**
+     * @param type {string}
+     * @returns {number}
+     */
+    listenerCount(type) {
+        let count = 0;
+        for (const listeners of this.#allListeners()) {
+            const entries = listeners.get(type);
+            count += entries ? entries.length : 0;
+        }
+        return count;
+    }
+
+    /**
+     * @param listeners {Map<string, EventEmitterEntry[]> | undefined}
+     * @param type {string}
+     * @param entry {EventEmitterEntry}
+     */
+    #removeEntry(listeners, type, entry) {
+        const entries = listeners && listeners.get(type);
+        const index = entries ? entries.indexOf(entry) : -1;
+        if (index >= 0) {
+            entries.splice(index, 1);
+            if (!entries.length) {
+                listeners.delete(type);
+            }
+        }
+    }
+
+    /**
+     * Listeners not owned and listeners of owners alive, collected owners are forgotten
+     * @returns {Map<string, EventEmitterEntry[]>[]}
+     */
+    #allListeners() {
+        const result = [this.#listeners];
+        for (const ref of this.#owners) {
+            const owner = derefNativeRef(ref);
+            const listeners = owner && this.#ownedListeners.get(owner);
+            if (!listeners) {
+                this.#owners.delete(ref);
+            } else if (!listeners.size) {
+                this.#owners.delete(ref);
+                this.#ownedListeners.delete(owner);
+            } else {
+                result.push(listeners);
+            }
+        }
+        return result;
+    }
 }
 
 export class SystemTray {
@@ -895,6 +3074,73 @@ export class SystemTray {
     }
 
 }
+const MAX_POOLED_ELEMENTS_PER_TAG = 64;
+const MAX_POOLED_ELEMENTS = 256;
+
+/**
+ * Elements detached by `detachForReuse`, grouped by tag
+ */
+class ElementPool {
+    /**
+     * @type {Map<string, Element[]>}
+     */
+    static #elements = new Map();
+    /**
+     * @type {Map<string, typeof Element>}
+     */
+    static #classes = new Map();
+    static #size = 0;
+
+    /**
+     * @param tag {string}
+     * @param elementClass {typeof Element}
+     */
+    static registerClass(tag, elementClass) {
+        this.#classes.set(tag, elementClass);
+    }
+
+    /**
+     * @param element {Element}
+     * @returns {boolean} false if the pool is full and element is dropped
+     */
+    static release(element) {
+        const tag = Element_get_tag(element.handle);
+        let elements = this.#elements.get(tag);
+        if (!elements) {
+            elements = [];
+            this.#elements.set(tag, elements);
+        }
+        if (elements.includes(element)) {
+            return true;
+        }
+        if (this.#size >= MAX_POOLED_ELEMENTS || elements.length >= MAX_POOLED_ELEMENTS_PER_TAG) {
+            return false;
+        }
+        elements.push(element);
+        this.#size += 1;
+        return true;
+    }
+
+    /**
+     * @param tag {string}
+     * @returns {Element}
+     */
+    static acquire(tag) {
+        tag = tag.toLowerCase();
+        const element = this.#elements.get(tag)?.pop();
+        if (element) {
+            this.#size -= 1;
+            element._resetForReuse();
+            return element;
+        }
+        const elementClass = this.#classes.get(tag);
+        if (!elementClass) {
+            throw new Error(`Unexpected element tag ${tag}`);
+        }
+        return new elementClass();
+    }
+}
+
 export class Element {
     /**
      * @type {ContainerBasedElement}
@@ -926,7 +3172,9 @@ export class Element {
      * @param context {object}
      */
     constructor(el, context) {
-        const myContext = this;
+        // Held weakly so that detached elements are collected with their event listeners, contents
+        // of pages and popups have no parent to hold them and are retained until closed
+        const myContext = nativeRef(this);
         if (typeof el === "string") {
             this.handle = Element_create_by_tag(el, myContext);
         } else {
@@ -943,11 +3191,53 @@ export class Element {
 
     static fromHandle(elementHandle) {
         if (elementHandle) {
-            return Element_get_js_context(elementHandle) || null;
+            return derefNativeRef(Element_get_js_context(elementHandle)) || null;
         }
         return null;
     }
 
+    /**
+     * Take an element detached by `detachForReuse` out of the pool of tag, a new element is
+     * created if the pool is empty
+     * @param tag {string}
+     * @returns {Element}
+     */
+    static createFromPool(tag) {
+        return ElementPool.acquire(tag);
+    }
+
+    /**
+     * Remove element from its parent and keep it in the pool of its tag for reuse by
+     * `Element.createFromPool`, children are removed so that they are not retained by the pool.
+     * Event listeners, styles, classes, attributes and backend state like text are reset when
+     * it's taken out of the pool. Elements are dropped if the pool is full.
+     */
+    detachForReuse() {
+        if (this._parent) {
+            this._parent.removeChild(this);
+        }
+        this._clearForPool();
+        ElementPool.release(this);
+    }
+
+    /**
+     * Release resources retained by an element before it's pooled
+     * @protected
+     */
+    _clearForPool() {
+    }
+
+    /**
+     * Reset state of an element taken out of the pool
+     * @protected
+     */
+    _resetForReuse() {
+        this.#eventBinder.removeAllEventListeners();
+        this.#style = undefined;
+        this.#hoverStyle = undefined;
+        Element_reset_for_reuse(this.handle);
+    }
+
     createEventBinder(target, addEventListenerApi, removeEventListenerApi) {
         if (!removeEventListenerApi) {
             removeEventListenerApi = (_t, listenerId) => {
@@ -1009,39 +3299,135 @@ export class Element {
     }
 
     /**
-     * Make element focusable or not
-     * @param focusable {boolean}
+     * Make element focusable or not
+     * @param focusable {boolean}
+     */
+    set focusable(focusable) {
+        Element_set_focusable(this.handle, focusable);
+    }
+
+    /**
+     * Whether element is focusable
+     * @returns {boolean}
+     */
+    get focusable() {
+        return Element_is_focusable(this.handle);
+    }
+
+    /**
+     * Make element a focus scope, which remembers its last focused descendant and restores it
+     * when the scope is focused again, e.g. after a dialog is closed or a tab is reselected
+     * @param value {boolean}
+     */
+    set focusScope(value) {
+        Element_set_focus_scope(this.handle, value);
+    }
+
+    /**
+     * Whether element is a focus scope
+     * @returns {boolean}
+     */
+    get focusScope() {
+        return Element_is_focus_scope(this.handle);
+    }
+
+    /**
+     * Whether element or one of its descendants is under the mouse cursor
+     * @returns {boolean}
+     */
+    get hover() {
+        return Element_is_hover(this.handle);
+    }
+
+    /**
+     * Get the root of current element
+     * @returns {Element}
+     */
+    get rootElement() {
+        let p = this.getParent();
+        if (p == null) {
+            return this;
+        } else {
+            return p.getRootElement();
+        }
+    }
+
+    /**
+     * Request focus on the current element
+     */
+    focus() {
+        Element_focus(this.handle);
+    }
+
+    /**
+     * Show element covering the whole window, until exitFullscreen is called or Escape is pressed.
+     * The backdrop could be styled with `body[fullscreen-backdrop]` selector
+     */
+    requestFullscreen() {
+        Element_request_fullscreen(this.handle);
+    }
+
+    exitFullscreen() {
+        Element_exit_fullscreen(this.handle);
+    }
+
+    /**
+     *
+     * @returns {boolean}
+     */
+    get fullscreen() {
+        return Element_is_fullscreen(this.handle);
+    }
+
+    /**
+     * Hide cursor and report relative motion of mouse as movementX/movementY of mousemove events,
+     * until exitPointerLock is called, Escape is pressed or window loses focus
+     */
+    requestPointerLock() {
+        Element_request_pointer_lock(this.handle);
+    }
+
+    exitPointerLock() {
+        Element_exit_pointer_lock(this.handle);
+    }
+
+    /**
+     * @returns {boolean}
      */
-    set focusable(focusable) {
-        Element_set_focusable(this.handle, focusable);
+    get pointerLocked() {
+        return Element_is_pointer_locked(this.handle);
     }
 
     /**
-     * Whether element is focusable
-     * @returns {boolean}
+     *
+     * @param callback {(event: IPointerLockChangeEvent) => void}
      */
-    get focusable() {
-        return Element_is_focusable(this.handle);
+    bindPointerLockChange(callback) {
+        this.bindEvent("pointerlockchange", callback);
     }
 
     /**
-     * Get the root of current element
-     * @returns {Element}
+     * Move element into a frameless always-on-top window, e.g. picture-in-picture of a player.
+     * State and event listeners of element are kept, and it is moved back when the window is closed
+     * or popIn is called
+     * @param options {{width?: number, height?: number, title?: string}}
+     * @returns {Window}
      */
-    get rootElement() {
-        let p = this.getParent();
-        if (p == null) {
-            return this;
-        } else {
-            return p.getRootElement();
-        }
+    popOut(options = {}) {
+        const windowHandle = Element_pop_out(this.handle, options);
+        return Window.fromHandle(windowHandle);
+    }
+
+    popIn() {
+        Element_pop_in(this.handle);
     }
 
     /**
-     * Request focus on the current element
+     *
+     * @returns {boolean}
      */
-    focus() {
-        Element_focus(this.handle);
+    get poppedOut() {
+        return Element_is_popped_out(this.handle);
     }
 
     set tooltip(text) {
@@ -1127,6 +3513,78 @@ export class Element {
         return Element_get_scroll_left(this.handle);
     }
 
+    /**
+     * Distance to the top or bottom edge of content within which reachtop or reachbottom is emitted
+     * @param value {number}
+     */
+    set scrollEdgeThreshold(value) {
+        Element_set_scroll_edge_threshold(this.handle, value);
+    }
+
+    /**
+     * Distance to the top or bottom edge of content within which reachtop or reachbottom is emitted
+     * @returns {number}
+     */
+    get scrollEdgeThreshold() {
+        return Element_get_scroll_edge_threshold(this.handle);
+    }
+
+    /**
+     * Save scroll position together with the element at the top edge of viewport
+     * @returns {ScrollState}
+     */
+    saveScrollState() {
+        return Element_save_scroll_state(this.handle);
+    }
+
+    /**
+     * Restore scroll position after the next layout, keeping the saved anchor element at the same position if it still exists
+     * @param state {ScrollState}
+     */
+    restoreScrollState(state) {
+        Element_restore_scroll_state(this.handle, state);
+    }
+
+    /**
+     * Scroll to the given offsets, animated if behavior is "smooth"
+     * @param options {ScrollToOptions}
+     */
+    scrollTo(options) {
+        Element_scroll_to(this.handle, options);
+    }
+
+    /**
+     * Fraction of fling velocity kept every millisecond after touch is released
+     * @param value {"normal" | "fast" | number}
+     */
+    set scrollDecelerationRate(value) {
+        Element_set_scroll_deceleration_rate(this.handle, String(value));
+    }
+
+    /**
+     * Fraction of fling velocity kept every millisecond after touch is released
+     * @returns {number}
+     */
+    get scrollDecelerationRate() {
+        return Element_get_scroll_deceleration_rate(this.handle);
+    }
+
+    /**
+     * Whether content could be dragged or flung beyond the edges and spring back
+     * @param value {boolean}
+     */
+    set scrollBounce(value) {
+        Element_set_scroll_bounce(this.handle, value);
+    }
+
+    /**
+     * Whether content could be dragged or flung beyond the edges and spring back
+     * @returns {boolean}
+     */
+    get scrollBounce() {
+        return Element_get_scroll_bounce(this.handle);
+    }
+
     /**
      * Make element draggable
      * @param value {boolean}
@@ -1175,6 +3633,31 @@ export class Element {
         return Element_get_bounding_client_rect(this.handle);
     }
 
+    /**
+     * Path from root to element with `id` and `data-testid` attributes, for ui automation
+     * @returns {ElementPathEntry[]}
+     */
+    getPath() {
+        return Element_get_path(this.handle);
+    }
+
+    /**
+     * Dump the layout tree of element, including style inputs and computed layout of every node
+     * @returns {LayoutDump}
+     */
+    debugLayout() {
+        return Element_debug_layout(this.handle);
+    }
+
+    /**
+     * Trace which default style, css rule or inline style wins the cascade for a property
+     * @param property {string} e.g. "width" or "background-color"
+     * @returns {StyleTrace}
+     */
+    debugComputedStyle(property) {
+        return Element_debug_computed_style(this.handle, property);
+    }
+
     /**
      * The scrollWidth read-only property is a measurement of the height of an element's content, including content not visible on the screen due to overflow.
      * @returns {number}
@@ -1211,6 +3694,14 @@ export class Element {
         this.bindEvent("boundschange", callback);
     }
 
+    /**
+     *
+     * @param callback {(event: IFullscreenChangeEvent) => void}
+     */
+    bindFullscreenChange(callback) {
+        this.bindEvent("fullscreenchange", callback);
+    }
+
     /**
      *
      * @param callback {(event: IVoidEvent) => void}
@@ -1307,6 +3798,30 @@ export class Element {
         this.#eventBinder.bindEvent("scroll", callback);
     }
 
+    /**
+     *
+     * @param callback {(e: IScrollEvent) => void}
+     */
+    bindScrollEnd(callback) {
+        this.#eventBinder.bindEvent("scrollend", callback);
+    }
+
+    /**
+     *
+     * @param callback {(e: IVoidEvent) => void}
+     */
+    bindReachTop(callback) {
+        this.#eventBinder.bindEvent("reachtop", callback);
+    }
+
+    /**
+     *
+     * @param callback {(e: IVoidEvent) => void}
+     */
+    bindReachBottom(callback) {
+        this.#eventBinder.bindEvent("reachbottom", callback);
+    }
+
     bindMouseWheel(callback) {
         this.#eventBinder.bindEvent("mousewheel", callback);
     }
@@ -1339,6 +3854,30 @@ export class Element {
         this.#eventBinder.bindEvent("touchcancel", callback);
     }
 
+    /**
+     * Two-finger pinch
+     * @param callback {(e: IGestureZoomEvent) => void}
+     */
+    bindGestureZoom(callback) {
+        this.#eventBinder.bindEvent("gesturezoom", callback);
+    }
+
+    /**
+     * Two-finger rotation
+     * @param callback {(e: IGestureRotateEvent) => void}
+     */
+    bindGestureRotate(callback) {
+        this.#eventBinder.bindEvent("gesturerotate", callback);
+    }
+
+    /**
+     * Two fingers moving together
+     * @param callback {(e: IGesturePanEvent) => void}
+     */
+    bindGesturePan(callback) {
+        this.#eventBinder.bindEvent("gesturepan", callback);
+    }
+
     /**
      *
      * @param callback {(e: IDroppedFileEvent) => void}
@@ -1359,6 +3898,31 @@ export class Element {
         this.#eventBinder.bindEvent(type, callback);
     }
 
+    /**
+     * @param type {string}
+     * @param callback {Function}
+     * @param options {EventListenerOptions | undefined} passive listeners can't prevent default
+     * behaviors, e.g. scrolling by wheel
+     */
+    addEventListener(type, callback, options) {
+        this.#eventBinder.addEventListener(type, callback, options);
+    }
+
+    removeEventListener(type, callback) {
+        this.#eventBinder.removeEventListener(type, callback);
+    }
+
+    /**
+     * Dispatch a custom event to this element, listeners receive detail as event.detail
+     * @param type {string}
+     * @param detail {any}
+     * @param options {{bubbles?: boolean}}
+     * @returns {boolean} false if any listener called preventDefault
+     */
+    dispatchEvent(type, detail, options) {
+        return Element_dispatch_event(this.handle, type, detail, options || {});
+    }
+
     /**
      *
      * @param value {boolean}
@@ -1446,6 +4010,22 @@ export class LabelElement extends Element {
         Label_set_text(this.handle, text);
     }
 
+    /**
+     * Whether some lines are hidden by -deft-line-clamp
+     * @returns {boolean}
+     */
+    isTruncated() {
+        return Label_is_truncated(this.handle);
+    }
+
+    /**
+     *
+     * @param callback {(e: ITruncationChangeEvent) => void}
+     */
+    bindTruncationChange(callback) {
+        this.bindEvent("truncationchange", callback);
+    }
+
 }
 
 export class CheckboxElement extends Element {
@@ -1652,8 +4232,15 @@ export class SelectElement extends Element {
 
 /**
  * @typedef {{
- *   type: "text",
- *   text: string,
+ *   type: "text" | "link" | "ruby" | "image" | "embed",
+ *   text ?: string,
+ *   href ?: string,
+ *   annotation ?: string,
+ *   src ?: string,
+ *   width ?: number,
+ *   height ?: number,
+ *   verticalAlign ?: "baseline" | "top" | "middle" | "bottom",
+ *   element ?: Element,
  *   weight ?: string,
  *   textDecorationLine ?: string,
  *   fontFamilies ?: string[],
@@ -1663,16 +4250,61 @@ export class SelectElement extends Element {
  * }} TextUnit
  */
 export class RichTextElement extends Element {
+    #embeds = [];
+
     constructor() {
         super(VT_RICH_TEXT);
     }
 
+    /**
+     * Attach elements of embed units as children and replace them with their ids
+     * @param units {TextUnit[]}
+     */
+    #resolveEmbeds(units) {
+        return units.map(u => {
+            if (u.type !== "embed") {
+                return u;
+            }
+            const element = u.element;
+            if (element._parent !== this) {
+                if (element._parent) {
+                    element._parent.removeChild(element);
+                }
+                element._parent = this;
+                Element_add_child(this.handle, element.handle, -1);
+                this.#embeds.push(element);
+            }
+            return {...u, element: element.eid};
+        });
+    }
+
+    /**
+     * Detach an element embedded by embed units, lines referring it should be updated too
+     * @param child {Element}
+     */
+    removeChild(child) {
+        const index = this.#embeds.indexOf(child);
+        if (index >= 0) {
+            child._parent = null;
+            Element_remove_child_element(this.handle, child.handle);
+            this.#embeds.splice(index, 1);
+        }
+    }
+
     /**
      *
      * @param units {TextUnit[]}
      */
     addLine(units) {
-        RichText_add_line(this.handle, units);
+        RichText_add_line(this.handle, this.#resolveEmbeds(units));
+    }
+
+    /**
+     * Whether some lines are hidden by -deft-line-clamp
+     * @returns {boolean}
+     */
+    isTruncated() {
+        return RichText_is_truncated(this.handle);
     }
 
     /**
@@ -1681,7 +4313,7 @@ export class RichTextElement extends Element {
      * @param units {TextUnit[]}
      */
     insertLine(index, units) {
-        RichText_insert_line(this.handle, index, units);
+        RichText_insert_line(this.handle, index, this.#resolveEmbeds(units));
     }
 
 
@@ -1699,7 +4331,7 @@ export class RichTextElement extends Element {
      * @param units {TextUnit[]}
      */
     updateLine(index, units) {
-        RichText_update_line(this.handle, index, units);
+        RichText_update_line(this.handle, index, this.#resolveEmbeds(units));
     }
 
     clear() {
@@ -1723,6 +4355,22 @@ export class RichTextElement extends Element {
         return RichText_get_selection_text(this.handle);
     }
 
+    /**
+     *
+     * @param callback {(e: ILinkClickEvent) => void}
+     */
+    bindLinkClick(callback) {
+        this.bindEvent("linkclick", callback);
+    }
+
+    /**
+     *
+     * @param callback {(e: ILinkHoverEvent) => void}
+     */
+    bindLinkHover(callback) {
+        this.bindEvent("linkhover", callback);
+    }
+
 }
 
 export class ImageElement extends Element {
@@ -1734,6 +4382,130 @@ export class ImageElement extends Element {
     }
 }
 
+/**
+ * Element showing frames submitted by native code, e.g. video decoders or game engines
+ */
+export class ExternalTextureElement extends Element {
+    constructor() {
+        super(VT_EXTERNAL_TEXTURE);
+    }
+
+    /**
+     * Id passed to native code for submitting frames
+     * @returns {number}
+     */
+    get textureId() {
+        return ExternalTexture_get_texture_id(this.handle);
+    }
+
+    /**
+     * Drop current frame
+     */
+    clear() {
+        ExternalTexture_clear(this.handle);
+    }
+}
+
+/**
+ * Element playing video decoded by ffmpeg, requires feature "video" and ffmpeg executables in PATH.
+ * The audio track is not played.
+ */
+export class VideoElement extends Element {
+    constructor() {
+        super(VT_VIDEO);
+    }
+
+    /**
+     * @param src {string} file path or url supported by ffmpeg
+     */
+    set src(src) {
+        Video_set_src(this.handle, src);
+    }
+
+    /**
+     * @returns {string}
+     */
+    get src() {
+        return Video_get_src(this.handle);
+    }
+
+    play() {
+        Video_play(this.handle);
+    }
+
+    pause() {
+        Video_pause(this.handle);
+    }
+
+    /**
+     * @param time {number} seconds
+     */
+    seek(time) {
+        Video_seek(this.handle, time);
+    }
+
+    /**
+     * Current position in seconds
+     * @returns {number}
+     */
+    get currentTime() {
+        return Video_get_current_time(this.handle);
+    }
+
+    /**
+     * @param time {number} seconds
+     */
+    set currentTime(time) {
+        this.seek(time);
+    }
+
+    /**
+     * Duration in seconds, 0 before metadata is loaded
+     * @returns {number}
+     */
+    get duration() {
+        return Video_get_duration(this.handle);
+    }
+
+    /**
+     * @returns {boolean}
+     */
+    get paused() {
+        return Video_is_paused(this.handle);
+    }
+
+    /**
+     * @returns {boolean}
+     */
+    get ended() {
+        return Video_is_ended(this.handle);
+    }
+
+    /**
+     * Restart from the beginning when ended
+     * @param value {boolean}
+     */
+    set loop(value) {
+        Video_set_loop(this.handle, value);
+    }
+
+    /**
+     *
+     * @param callback {(e: ITimeUpdateEvent) => void}
+     */
+    bindTimeUpdate(callback) {
+        this.bindEvent("timeupdate", callback);
+    }
+
+    /**
+     *
+     * @param callback {(e: IVoidEvent) => void}
+     */
+    bindEnded(callback) {
+        this.bindEvent("ended", callback);
+    }
+}
+
 export class TextInputElement extends Element {
 
     constructor() {
@@ -1776,6 +4548,54 @@ export class TextInputElement extends Element {
         return TextInput_get_type(this.handle);
     }
 
+    /**
+     *
+     * @param value {string} e.g. "username", "current-password", "one-time-code"
+     */
+    set autocomplete(value) {
+        this.setAttribute("autocomplete", value);
+    }
+
+    /**
+     *
+     * @returns {string}
+     */
+    get autocomplete() {
+        return this.getAttribute("autocomplete") || "";
+    }
+
+    /**
+     * Input mask, `#` accepts a digit, `A` a letter, `*` a letter or digit, others are literals
+     * @param value {string} e.g. "(###) ###-####"
+     */
+    set mask(value) {
+        this.setAttribute("mask", value);
+    }
+
+    /**
+     *
+     * @returns {string}
+     */
+    get mask() {
+        return this.getAttribute("mask") || "";
+    }
+
+    /**
+     * Text without literals of mask
+     * @returns {string}
+     */
+    get unmaskedText() {
+        return TextInput_get_unmasked_text(this.handle);
+    }
+
+    /**
+     * Custom items appended to the built-in editing context menu
+     * @param menu {Menu | null}
+     */
+    set contextMenu(menu) {
+        TextInput_set_context_menu(this.handle, menu ? menu.handle : null);
+    }
+
     /**
      *
      * @returns {string}
@@ -1817,6 +4637,14 @@ export class TextInputElement extends Element {
         this.bindEvent("caretchange", callback);
     }
 
+    /**
+     *
+     * @param callback {(e: IMaskValidateEvent) => void}
+     */
+    bindMaskValidate(callback) {
+        this.bindEvent("maskvalidate", callback);
+    }
+
 }
 
 export class TextEditElement extends Element {
@@ -1877,6 +4705,14 @@ export class TextEditElement extends Element {
         TextEdit_set_caret_by_char_offset(this.handle, charOffset);
     }
 
+    /**
+     * Custom items appended to the built-in editing context menu
+     * @param menu {Menu | null}
+     */
+    set contextMenu(menu) {
+        TextEdit_set_context_menu(this.handle, menu ? menu.handle : null);
+    }
+
     /**
      *
      * @returns {string}
@@ -1920,6 +4756,137 @@ export class TextEditElement extends Element {
 
 }
 
+/**
+ * Editor of text with bold, italic, underline, links and inline images
+ */
+export class RichTextEditElement extends Element {
+    constructor() {
+        super(VT_RICH_TEXT_EDIT);
+    }
+
+    /**
+     * Content in delta format, lines are separated by "\n" in text inserts
+     * @returns {RichContent}
+     */
+    get content() {
+        return RichTextEdit_get_content(this.handle);
+    }
+
+    /**
+     * Replace content and clear edit history
+     * @param content {RichContent}
+     */
+    set content(content) {
+        RichTextEdit_set_content(this.handle, content);
+    }
+
+    /**
+     * Plain text, images are replaced by object replacement characters
+     * @returns {string}
+     */
+    get text() {
+        return RichTextEdit_get_text(this.handle);
+    }
+
+    /**
+     *
+     * @returns {string | undefined}
+     */
+    get selectionText() {
+        return RichTextEdit_get_selection_text(this.handle);
+    }
+
+    /**
+     * Attributes shared by selected text, or attributes of text typed at caret
+     * @returns {RichTextAttributes}
+     */
+    getFormat() {
+        return RichTextEdit_get_format(this.handle);
+    }
+
+    /**
+     * Format selected text, or text typed next if nothing is selected. An empty link removes links
+     * @param format {RichTextFormat}
+     */
+    format(format) {
+        RichTextEdit_format(this.handle, format);
+    }
+
+    /**
+     * Replace selection with an inline image
+     * @param image {RichTextImage}
+     */
+    insertImage(image) {
+        RichTextEdit_insert_image(this.handle, image);
+    }
+
+    /**
+     *
+     * @param start {number}
+     * @param end {number}
+     */
+    setSelectionByCharOffset(start, end) {
+        RichTextEdit_set_selection_by_char_offset(this.handle, start, end);
+    }
+
+    /**
+     *
+     * @returns {number}
+     */
+    get maxHistory() {
+        return RichTextEdit_get_max_history(this.handle);
+    }
+
+    /**
+     *
+     * @param maxHistory {number}
+     */
+    set maxHistory(maxHistory) {
+        RichTextEdit_set_max_history(this.handle, maxHistory);
+    }
+
+    undo() {
+        RichTextEdit_undo(this.handle);
+    }
+
+    redo() {
+        RichTextEdit_redo(this.handle);
+    }
+
+    /**
+     *
+     * @returns {boolean}
+     */
+    get disabled() {
+        return Element_is_disabled(this.handle);
+    }
+
+    /**
+     *
+     * @param value {boolean}
+     */
+    set disabled(value) {
+        Element_set_disabled(this.handle, value);
+    }
+
+    /**
+     *
+     * @param callback {(e: ITextEvent) => void}
+     */
+    bindTextChange(callback) {
+        this.bindEvent("textchange", callback);
+    }
+
+    /**
+     *
+     * @param callback {(e: ILinkClickEvent) => void}
+     */
+    bindLinkClick(callback) {
+        this.bindEvent("linkclick", callback);
+    }
+
+}
+
 class ContainerBasedElement extends Element {
     #children = [];
     
@@ -1984,7 +4951,7 @@ class ContainerBasedElement extends Element {
         const index = this.#children.indexOf(child);
         if (index >= 0) {
             child._parent = null;
-            Element_remove_child(this.handle, index);
+            Element_remove_child_element(this.handle, child.handle);
             this.#children.splice(index, 1);
         } else {
             console.log("remove child failed")
@@ -1999,6 +4966,13 @@ class ContainerBasedElement extends Element {
         return this.#children.slice();
     }
 
+    _clearForPool() {
+        super._clearForPool();
+        for (const child of this.#children.slice()) {
+            this.removeChild(child);
+        }
+    }
+
 }
 
 export class ButtonElement extends ContainerBasedElement {
@@ -2049,6 +5023,8 @@ export class BodyElement extends ContainerBasedElement {
 }
 
 export class ScrollElement extends ContainerBasedElement {
+    #refreshIndicator = null;
+
     constructor() {
         super(VT_SCROLL);
     }
@@ -2075,6 +5051,63 @@ export class ScrollElement extends ContainerBasedElement {
         Element_scroll_by(this.handle, value);
     }
 
+    /**
+     * Enable pulling content down at the top edge to refresh, by touch or trackpad
+     * @param value {boolean}
+     */
+    set pullToRefresh(value) {
+        Scroll_set_pull_to_refresh(this.handle, value);
+    }
+
+    /**
+     * Set the element revealed above content when pulling, it is added as a child and positioned absolutely
+     * @param indicator {Element}
+     */
+    set refreshIndicator(indicator) {
+        if (this.#refreshIndicator) {
+            this.removeChild(this.#refreshIndicator);
+        }
+        this.#refreshIndicator = indicator;
+        this.addChild(indicator);
+        Scroll_set_refresh_indicator(this.handle, indicator.handle);
+    }
+
+    /**
+     * @returns {Element | null}
+     */
+    get refreshIndicator() {
+        return this.#refreshIndicator;
+    }
+
+    /**
+     * Whether the refresh indicator is kept visible for refreshing
+     * @returns {boolean}
+     */
+    get refreshing() {
+        return Scroll_is_refreshing(this.handle);
+    }
+
+    /**
+     * Collapse the refresh indicator
+     */
+    finishRefresh() {
+        Scroll_finish_refresh(this.handle);
+    }
+
+    /**
+     * The indicator is collapsed when the Promise returned by callback is settled
+     * @param callback {(e: IVoidEvent) => (Promise<any> | void)}
+     */
+    bindRefresh(callback) {
+        this.bindEvent("refresh", async (e) => {
+            try {
+                await callback(e);
+            } finally {
+                e.currentTarget.finishRefresh();
+            }
+        });
+    }
+
 }
 
 export class RadioGroupElement extends ContainerBasedElement {
@@ -2083,6 +5116,191 @@ export class RadioGroupElement extends ContainerBasedElement {
     }
 }
 
+/**
+ * Records canvas operations of custom element, commands are executed by the renderer later
+ */
+export class RenderContext {
+    /**
+     * @type {any[][]}
+     */
+    commands = [];
+    /**
+     * @type {number}
+     */
+    width;
+    /**
+     * @type {number}
+     */
+    height;
+
+    /**
+     *
+     * @param width {number}
+     * @param height {number}
+     */
+    constructor(width, height) {
+        this.width = width;
+        this.height = height;
+    }
+
+    /**
+     *
+     * @param color {string}
+     */
+    set fillStyle(color) {
+        this.commands.push(["fillStyle", color]);
+    }
+
+    /**
+     *
+     * @param color {string}
+     */
+    set strokeStyle(color) {
+        this.commands.push(["strokeStyle", color]);
+    }
+
+    /**
+     *
+     * @param width {number}
+     */
+    set lineWidth(width) {
+        this.commands.push(["lineWidth", width]);
+    }
+
+    fillRect(x, y, width, height) {
+        this.commands.push(["fillRect", x, y, width, height]);
+    }
+
+    strokeRect(x, y, width, height) {
+        this.commands.push(["strokeRect", x, y, width, height]);
+    }
+
+    fillCircle(cx, cy, radius) {
+        this.commands.push(["fillCircle", cx, cy, radius]);
+    }
+
+    strokeCircle(cx, cy, radius) {
+        this.commands.push(["strokeCircle", cx, cy, radius]);
+    }
+
+    line(x1, y1, x2, y2) {
+        this.commands.push(["line", x1, y1, x2, y2]);
+    }
+
+    /**
+     *
+     * @param svgPath {string} svg path data, e.g. "M0 0 L10 10 Z"
+     */
+    fillPath(svgPath) {
+        this.commands.push(["fillPath", svgPath]);
+    }
+
+    /**
+     *
+     * @param svgPath {string} svg path data, e.g. "M0 0 L10 10 Z"
+     */
+    strokePath(svgPath) {
+        this.commands.push(["strokePath", svgPath]);
+    }
+
+    save() {
+        this.commands.push(["save"]);
+    }
+
+    restore() {
+        this.commands.push(["restore"]);
+    }
+
+    translate(x, y) {
+        this.commands.push(["translate", x, y]);
+    }
+
+    /**
+     *
+     * @param degrees {number}
+     */
+    rotate(degrees) {
+        this.commands.push(["rotate", degrees]);
+    }
+
+    scale(x, y) {
+        this.commands.push(["scale", x, y]);
+    }
+
+    clipRect(x, y, width, height) {
+        this.commands.push(["clipRect", x, y, width, height]);
+    }
+}
+
+/**
+ * @typedef {{
+ *     onRender?: (ctx: RenderContext, element: Element) => void,
+ *     onEvent?: (type: string, event: IEvent<any>, element: Element) => void,
+ *     events?: string[],
+ *     measure?: (maxWidth: number, maxHeight: number, element: Element) => {width: number, height: number},
+ * }} CustomElementDefinition
+ */
+const CUSTOM_ELEMENT_DEFAULT_EVENTS = [
+    "click", "mousedown", "mouseup", "mousemove", "mouseenter", "mouseleave",
+    "keydown", "keyup", "focus", "blur", "touchstart", "touchmove", "touchend",
+    "gesturezoom", "gesturerotate", "gesturepan",
+];
+
+/**
+ * Define an element rendered by js callbacks
+ * @param tag {string}
+ * @param definition {CustomElementDefinition}
+ * @returns {typeof ContainerBasedElement} class to create the element
+ */
+export function registerElement(tag, definition) {
+    const render = definition.onRender ? (elementRef, width, height) => {
+        const ctx = new RenderContext(width, height);
+        definition.onRender(ctx, derefNativeRef(elementRef));
+        return ctx.commands;
+    } : null;
+    const measure = definition.measure ? (elementRef, maxWidth, maxHeight) => {
+        const {width, height} = definition.measure(maxWidth, maxHeight, derefNativeRef(elementRef));
+        return [width, height];
+    } : null;
+    custom_element_register(tag, render, measure);
+
+    class CustomElement extends ContainerBasedElement {
+        constructor() {
+            super(tag);
+            this.#bindDefinitionEvents();
+        }
+
+        #bindDefinitionEvents() {
+            if (definition.onEvent) {
+                for (const type of definition.events || CUSTOM_ELEMENT_DEFAULT_EVENTS) {
+                    this.addEventListener(type, e => definition.onEvent(type, e, e.currentTarget));
+                }
+            }
+        }
+
+        _resetForReuse() {
+            super._resetForReuse();
+            this.#bindDefinitionEvents();
+        }
+
+        /**
+         * Call onRender again in next frame
+         */
+        requestRender() {
+            custom_element_request_render(this.handle);
+        }
+
+        /**
+         * Call measure again in next layout
+         */
+        requestLayout() {
+            custom_element_request_layout(this.handle);
+        }
+    }
+    ElementPool.registerClass(tag.toLowerCase(), CustomElement);
+    return CustomElement;
+}
+
 export class WebSocket {
 
     client;
@@ -2217,123 +5435,593 @@ export class WebSocket {
 
 }
 
-export class Worker {
+export class Worker {
+
+    #worker
+
+    /**
+     * @type EventBinder
+     */
+    #eventBinder;
+
+    /**
+     *
+     * @param source {number | string}
+     */
+    constructor(source) {
+        this.#worker = typeof source === "string" ? Worker_create(source) : Worker_bind(source);
+        this.#eventBinder = new EventBinder(
+            this.#worker,
+            Worker_bind_js_event_listener,
+            Worker_remove_js_event_listener,
+            this
+        );
+    }
+
+    postMessage(data) {
+        Worker_post_message(this.#worker, JSON.stringify(data));
+    }
+
+    bindMessage(callback) {
+        this.#eventBinder.bindEvent('message', e => {
+            e.data = JSON.parse(e.detail.data);
+            callback(e);
+        });
+    }
+
+}
+
+export class WorkerContext {
+    #workerContext;
+    /**
+     * @type {EventBinder}
+     */
+    #eventBinder;
+    constructor() {
+        this.#workerContext = WorkerContext_get();
+        this.#eventBinder = new EventBinder(
+            this.#workerContext,
+            WorkerContext_bind_js_event_listener,
+            WorkerContext_remove_js_event_listener,
+            this
+        )
+    }
+    postMessage(data) {
+        WorkerContext_post_message(this.#workerContext, JSON.stringify(data));
+    }
+    bindMessage(callback) {
+        this.#eventBinder.bindEvent('message', e => {
+            e.data = JSON.parse(e.detail.data);
+            callback(e);
+        });
+    }
+
+    static create() {
+        if (globalThis.WorkerContext_get) {
+            return new WorkerContext();
+        }
+        return null;
+    }
+}
+
+export class SqliteConn {
+    #conn;
+    constructor(conn) {
+        this.#conn = conn;
+    }
+
+    /**
+     *
+     * @param sql {string}
+     * @param params {*[]}
+     * @returns {Promise<number>}
+     */
+    async execute(sql, params= []) {
+        return await SqliteConn_execute(this.#conn, sql, params);
+    }
+
+    /**
+     *
+     * @param sql {string}
+     * @param params {*[]}
+     * @returns {Promise<Object[]>}
+     */
+    async query(sql, params = []) {
+        const [columnNames, rows] = await SqliteConn_query(this.#conn, sql, params);
+        return rows.map(it => {
+            const map = {};
+            for (let i = 0; i < columnNames.length; i++) {
+                map[columnNames[i]] = it[i];
+            }
+            return map;
+        });
+    }
+
+}
+
+export class Sqlite {
+
+    /**
+     *
+     * @param path {string}
+     * @returns {Promise<SqliteConn>}
+     */
+    static async open(path) {
+        const conn = SqliteConn_create();
+        await SqliteConn_open(conn, path);
+        return new SqliteConn(conn);
+    }
+
+}
+
+/**
+ * @typedef {number | string} ObjectKey
+ * @typedef {{lower?: ObjectKey, upper?: ObjectKey, lowerOpen?: boolean, upperOpen?: boolean}} ObjectKeyRangeDef
+ * @typedef {{offset?: number, limit?: number, direction?: "next" | "prev"}} ObjectQueryOptions
+ */
+
+export class ObjectKeyRange {
+
+    /**
+     *
+     * @param key {ObjectKey}
+     * @returns {ObjectKeyRangeDef}
+     */
+    static only(key) {
+        return {lower: key, upper: key};
+    }
+
+    /**
+     *
+     * @param lower {ObjectKey}
+     * @param open {boolean}
+     * @returns {ObjectKeyRangeDef}
+     */
+    static lowerBound(lower, open = false) {
+        return {lower, lowerOpen: open};
+    }
+
+    /**
+     *
+     * @param upper {ObjectKey}
+     * @param open {boolean}
+     * @returns {ObjectKeyRangeDef}
+     */
+    static upperBound(upper, open = false) {
+        return {upper, upperOpen: open};
+    }
+
+    /**
+     *
+     * @param lower {ObjectKey}
+     * @param upper {ObjectKey}
+     * @param lowerOpen {boolean}
+     * @param upperOpen {boolean}
+     * @returns {ObjectKeyRangeDef}
+     */
+    static bound(lower, upper, lowerOpen = false, upperOpen = false) {
+        return {lower, upper, lowerOpen, upperOpen};
+    }
+}
+
+function getValueByKeyPath(value, keyPath) {
+    let result = value;
+    for (const p of keyPath.split(".")) {
+        if (result === null || result === undefined) {
+            return undefined;
+        }
+        result = result[p];
+    }
+    return result;
+}
+
+class ObjectStoreIndex {
+    #store;
+    #name;
+
+    constructor(store, name) {
+        this.#store = store;
+        this.#name = name;
+    }
+
+    /**
+     *
+     * @param key {ObjectKey}
+     * @returns {Promise<any>}
+     */
+    async get(key) {
+        const list = await this.getAll(ObjectKeyRange.only(key), {limit: 1});
+        return list.length ? list[0] : undefined;
+    }
+
+    /**
+     *
+     * @param range {ObjectKeyRangeDef}
+     * @param options {ObjectQueryOptions}
+     * @returns {Promise<any[]>}
+     */
+    async getAll(range = {}, options = {}) {
+        return this.#store._query(this.#name, range, options);
+    }
+
+    /**
+     *
+     * @param range {ObjectKeyRangeDef}
+     * @returns {Promise<number>}
+     */
+    async count(range = {}) {
+        return this.#store._count(this.#name, range);
+    }
+
+    /**
+     *
+     * @param range {ObjectKeyRangeDef}
+     * @param direction {"next" | "prev"}
+     * @returns {AsyncGenerator<{key: ObjectKey, value: any}>}
+     */
+    openCursor(range = {}, direction = "next") {
+        return this.#store._openCursor(this.#name, range, direction);
+    }
+}
+
+export class ObjectStore {
+    #db;
+    #name;
+    #schema;
+    #transaction;
+
+    constructor(db, name, schema, transaction = null) {
+        this.#db = db;
+        this.#name = name;
+        this.#schema = schema;
+        this.#transaction = transaction;
+    }
+
+    get name() {
+        return this.#name;
+    }
+
+    get keyPath() {
+        return this.#schema.keyPath;
+    }
+
+    get indexNames() {
+        return Object.keys(this.#schema.indexes);
+    }
+
+    /**
+     *
+     * @param key {ObjectKey}
+     * @returns {Promise<any>}
+     */
+    async get(key) {
+        const value = await ObjectStoreDb_get(this.#db, this.#name, key);
+        return value === null ? undefined : this.#decode(key, value);
+    }
+
+    /**
+     *
+     * @param range {ObjectKeyRangeDef}
+     * @param options {ObjectQueryOptions}
+     * @returns {Promise<any[]>}
+     */
+    async getAll(range = {}, options = {}) {
+        return this._query(null, range, options);
+    }
+
+    /**
+     *
+     * @param range {ObjectKeyRangeDef}
+     * @returns {Promise<number>}
+     */
+    async count(range = {}) {
+        return this._count(null, range);
+    }
+
+    /**
+     *
+     * @param range {ObjectKeyRangeDef}
+     * @param direction {"next" | "prev"}
+     * @returns {AsyncGenerator<{key: ObjectKey, value: any}>}
+     */
+    openCursor(range = {}, direction = "next") {
+        return this._openCursor(null, range, direction);
+    }
+
+    /**
+     *
+     * @param name {string}
+     * @returns {ObjectStoreIndex}
+     */
+    index(name) {
+        if (!this.#schema.indexes[name]) {
+            throw new Error(`No such index: ${name}`);
+        }
+        return new ObjectStoreIndex(this, name);
+    }
+
+    /**
+     * Insert or replace a record, return the key
+     * @param value {any}
+     * @param key {ObjectKey}
+     * @returns {Promise<ObjectKey>}
+     */
+    async put(value, key = undefined) {
+        return this.#write(this.#buildWriteOp("put", value, key));
+    }
+
+    /**
+     * Insert a record, fail if the key exists
+     * @param value {any}
+     * @param key {ObjectKey}
+     * @returns {Promise<ObjectKey>}
+     */
+    async add(value, key = undefined) {
+        return this.#write(this.#buildWriteOp("add", value, key));
+    }
+
+    /**
+     *
+     * @param key {ObjectKey}
+     * @returns {Promise<void>}
+     */
+    async delete(key) {
+        await this.#write({op: "delete", store: this.#name, key});
+    }
+
+    /**
+     *
+     * @returns {Promise<void>}
+     */
+    async clear() {
+        await this.#write({op: "clear", store: this.#name});
+    }
+
+    async _query(index, range, options) {
+        const rows = await ObjectStoreDb_query(this.#db, this.#name, {index, ...range, ...options});
+        return rows.map(([key, value]) => this.#decode(key, value));
+    }
+
+    async _count(index, range) {
+        return await ObjectStoreDb_count(this.#db, this.#name, {index, ...range});
+    }
+
+    async* _openCursor(index, range, direction) {
+        const batchSize = 100;
+        let offset = 0;
+        for (;;) {
+            const rows = await ObjectStoreDb_query(this.#db, this.#name, {
+                index, ...range, direction, offset, limit: batchSize,
+            });
+            for (const [key, value] of rows) {
+                yield {key, value: this.#decode(key, value)};
+            }
+            if (rows.length < batchSize) {
+                break;
+            }
+            offset += rows.length;
+        }
+    }
+
+    #write(op) {
+        if (this.#transaction) {
+            this.#transaction._addOp(op);
+            return op.key;
+        }
+        return ObjectStoreDb_commit(this.#db, [op]).then(keys => keys[0]);
+    }
+
+    #buildWriteOp(op, value, key) {
+        const {keyPath, autoIncrement, indexes} = this.#schema;
+        if (keyPath) {
+            if (key !== undefined) {
+                throw new Error("key should not be provided when keyPath is specified");
+            }
+            key = getValueByKeyPath(value, keyPath);
+        }
+        if (key === undefined && !autoIncrement) {
+            throw new Error("key is required");
+        }
+        if (key !== undefined && typeof key !== "number" && typeof key !== "string") {
+            throw new Error("invalid key type");
+        }
+        const indexEntries = [];
+        for (const name in indexes) {
+            const indexKey = getValueByKeyPath(value, indexes[name].keyPath);
+            if (typeof indexKey === "number" || typeof indexKey === "string") {
+                indexEntries.push({name, key: indexKey});
+            }
+        }
+        return {op, store: this.#name, key, value: JSON.stringify(value), indexes: indexEntries};
+    }
+
+    #decode(key, value) {
+        const result = JSON.parse(value);
+        const {keyPath} = this.#schema;
+        if (keyPath && !keyPath.includes(".") && result && typeof result === "object" && result[keyPath] === undefined) {
+            result[keyPath] = key;
+        }
+        return result;
+    }
+}
+
+export class ObjectStoreTransaction {
+    #db;
+    #database;
+    #ops = [];
+    #committed = false;
 
-    #worker
+    constructor(db, database) {
+        this.#db = db;
+        this.#database = database;
+    }
 
     /**
-     * @type EventBinder
+     * Writes of the returned store are buffered until commit, reads are executed immediately
+     * @param name {string}
+     * @returns {ObjectStore}
      */
-    #eventBinder;
+    objectStore(name) {
+        return new ObjectStore(this.#db, name, this.#database._getSchema(name), this);
+    }
+
+    _addOp(op) {
+        if (this.#committed) {
+            throw new Error("transaction has been committed");
+        }
+        this.#ops.push(op);
+    }
 
     /**
-     *
-     * @param source {number | string}
+     * Execute all buffered writes atomically, return keys of written records
+     * @returns {Promise<ObjectKey[]>}
      */
-    constructor(source) {
-        this.#worker = typeof source === "string" ? Worker_create(source) : Worker_bind(source);
-        this.#eventBinder = new EventBinder(
-            this.#worker,
-            Worker_bind_js_event_listener,
-            Worker_remove_js_event_listener,
-            this
-        );
+    async commit() {
+        this.#committed = true;
+        return await ObjectStoreDb_commit(this.#db, this.#ops);
     }
 
-    postMessage(data) {
-        Worker_post_message(this.#worker, JSON.stringify(data));
+    abort() {
+        this.#committed = true;
+        this.#ops = [];
     }
+}
 
-    bindMessage(callback) {
-        this.#eventBinder.bindEvent('message', e => {
-            e.data = JSON.parse(e.detail.data);
-            callback(e);
-        });
+export class ObjectStoreDatabase {
+    #db;
+    #schema = {};
+
+    constructor(db) {
+        this.#db = db;
     }
 
-}
+    /**
+     * Open a database, in-memory database is used if path is empty
+     * @param path {string}
+     * @returns {Promise<ObjectStoreDatabase>}
+     */
+    static async open(path = "") {
+        const db = ObjectStoreDb_create();
+        await ObjectStoreDb_open(db, path);
+        const database = new ObjectStoreDatabase(db);
+        await database.#reloadSchema();
+        return database;
+    }
 
-export class WorkerContext {
-    #workerContext;
     /**
-     * @type {EventBinder}
+     *
+     * @returns {string[]}
      */
-    #eventBinder;
-    constructor() {
-        this.#workerContext = WorkerContext_get();
-        this.#eventBinder = new EventBinder(
-            this.#workerContext,
-            WorkerContext_bind_js_event_listener,
-            WorkerContext_remove_js_event_listener,
-            this
-        )
+    get objectStoreNames() {
+        return Object.keys(this.#schema);
     }
-    postMessage(data) {
-        WorkerContext_post_message(this.#workerContext, JSON.stringify(data));
+
+    /**
+     *
+     * @param name {string}
+     * @param options {{keyPath?: string, autoIncrement?: boolean}}
+     * @returns {Promise<ObjectStore>}
+     */
+    async createObjectStore(name, options = {}) {
+        await ObjectStoreDb_create_store(this.#db, name, options.keyPath || null, !!options.autoIncrement);
+        await this.#reloadSchema();
+        return this.objectStore(name);
     }
-    bindMessage(callback) {
-        this.#eventBinder.bindEvent('message', e => {
-            e.data = JSON.parse(e.detail.data);
-            callback(e);
-        });
+
+    /**
+     *
+     * @param name {string}
+     * @returns {Promise<void>}
+     */
+    async deleteObjectStore(name) {
+        await ObjectStoreDb_delete_store(this.#db, name);
+        await this.#reloadSchema();
     }
 
-    static create() {
-        if (globalThis.WorkerContext_get) {
-            return new WorkerContext();
+    /**
+     * Create an index and build entries for existing records
+     * @param storeName {string}
+     * @param name {string}
+     * @param keyPath {string}
+     * @param options {{unique?: boolean}}
+     * @returns {Promise<void>}
+     */
+    async createIndex(storeName, name, keyPath, options = {}) {
+        await ObjectStoreDb_create_index(this.#db, storeName, name, keyPath, !!options.unique);
+        await this.#reloadSchema();
+        const tx = this.transaction();
+        const store = tx.objectStore(storeName);
+        const hasKeyPath = !!store.keyPath;
+        for await (const {key, value} of this.objectStore(storeName).openCursor()) {
+            await store.put(value, hasKeyPath ? undefined : key);
         }
-        return null;
+        await tx.commit();
     }
-}
 
-export class SqliteConn {
-    #conn;
-    constructor(conn) {
-        this.#conn = conn;
+    /**
+     *
+     * @param storeName {string}
+     * @param name {string}
+     * @returns {Promise<void>}
+     */
+    async deleteIndex(storeName, name) {
+        await ObjectStoreDb_delete_index(this.#db, storeName, name);
+        await this.#reloadSchema();
     }
 
     /**
      *
-     * @param sql {string}
-     * @param params {*[]}
-     * @returns {Promise<number>}
+     * @param name {string}
+     * @returns {ObjectStore}
      */
-    async execute(sql, params= []) {
-        return await SqliteConn_execute(this.#conn, sql, params);
+    objectStore(name) {
+        return new ObjectStore(this.#db, name, this._getSchema(name));
     }
 
     /**
      *
-     * @param sql {string}
-     * @param params {*[]}
-     * @returns {Promise<Object[]>}
+     * @returns {ObjectStoreTransaction}
      */
-    async query(sql, params = []) {
-        const [columnNames, rows] = await SqliteConn_query(this.#conn, sql, params);
-        return rows.map(it => {
-            const map = {};
-            for (let i = 0; i < columnNames.length; i++) {
-                map[columnNames[i]] = it[i];
-            }
-            return map;
-        });
+    transaction() {
+        return new ObjectStoreTransaction(this.#db, this);
     }
 
-}
-
-export class Sqlite {
-
     /**
-     *
-     * @param path {string}
-     * @returns {Promise<SqliteConn>}
+     * Run callback with a transaction and commit it, the transaction is aborted if callback throws
+     * @param callback {(tx: ObjectStoreTransaction) => Promise<void>}
+     * @returns {Promise<ObjectKey[]>}
      */
-    static async open(path) {
-        const conn = SqliteConn_create();
-        await SqliteConn_open(conn, path);
-        return new SqliteConn(conn);
+    async runTransaction(callback) {
+        const tx = this.transaction();
+        try {
+            await callback(tx);
+        } catch (error) {
+            tx.abort();
+            throw error;
+        }
+        return await tx.commit();
     }
 
+    _getSchema(name) {
+        const schema = this.#schema[name];
+        if (!schema) {
+            throw new Error(`No such object store: ${name}`);
+        }
+        return schema;
+    }
+
+    async #reloadSchema() {
+        const list = await ObjectStoreDb_get_schema(this.#db);
+        const schema = {};
+        for (const [name, keyPath, autoIncrement, indexList] of list) {
+            const indexes = {};
+            for (const [indexName, indexKeyPath, unique] of indexList) {
+                indexes[indexName] = {keyPath: indexKeyPath, unique};
+            }
+            schema[name] = {keyPath, autoIncrement, indexes};
+        }
+        this.#schema = schema;
+    }
 }
 
 function collectCircleRefInfo(value, visited, circleRefList, level) {
@@ -2353,6 +6041,19 @@ function collectCircleRefInfo(value, visited, circleRefList, level) {
     }
 }
 
+/**
+ * Pause in attached debugger if built with feature "debugger"
+ * @param error {any}
+ */
+function reportException(error) {
+    if (globalThis.debugger_report_exception) {
+        const isError = error instanceof Error;
+        const name = isError ? error.name : "Error";
+        const message = isError ? error.message : String(error);
+        debugger_report_exception(name, message, (isError && error.stack) || "");
+    }
+}
+
 function log(...values) {
     values.forEach((value, index) => {
         const visited = [];
@@ -2415,19 +6116,115 @@ globalThis.console = {
     error: log,
 }
 
-/**
- *
- * @type {LocalStorage}
- */
-const localStorage = {
+export class LocalStorage {
+    #namespace;
+
+    /**
+     *
+     * @param namespace {string}
+     */
+    constructor(namespace = "") {
+        this.#namespace = namespace;
+    }
+
+    /**
+     * Get storage of another namespace, the namespace should be enabled by engine.enable_localstorage_with_namespace
+     * @param namespace {string}
+     * @returns {LocalStorage}
+     */
+    static namespace(namespace) {
+        return new LocalStorage(namespace);
+    }
+
+    /**
+     *
+     * @param key {string}
+     * @returns {string | null}
+     */
     getItem(key) {
-        return localstorage_get(key)
-    },
+        return localstorage_get_item(this.#namespace, key)
+    }
+
+    /**
+     * Throw QuotaExceededError if quota of the namespace exceeded
+     * @param key {string}
+     * @param value {string}
+     */
     setItem(key, value) {
-        localstorage_set(key, value);
+        localstorage_set_item(this.#namespace, key, value);
+    }
+
+    /**
+     *
+     * @param key {string}
+     */
+    removeItem(key) {
+        localstorage_remove_item(this.#namespace, key);
+    }
+
+    /**
+     *
+     * @returns {string[]}
+     */
+    keys() {
+        return localstorage_keys(this.#namespace);
+    }
+
+    /**
+     * Used bytes of keys and values
+     * @returns {number}
+     */
+    get usage() {
+        return localstorage_usage(this.#namespace);
+    }
+}
+
+export class LargeStorage {
+    #namespace;
+
+    /**
+     *
+     * @param namespace {string}
+     */
+    constructor(namespace = "") {
+        this.#namespace = namespace;
+    }
+
+    /**
+     *
+     * @param key {string}
+     * @returns {Promise<string | null>}
+     */
+    async getItem(key) {
+        return await largestorage_get(this.#namespace, key);
+    }
+
+    /**
+     *
+     * @param key {string}
+     * @param value {string}
+     * @returns {Promise<void>}
+     */
+    async setItem(key, value) {
+        await largestorage_set(this.#namespace, key, value);
+    }
+
+    /**
+     *
+     * @param key {string}
+     * @returns {Promise<void>}
+     */
+    async removeItem(key) {
+        await largestorage_remove(this.#namespace, key);
     }
 }
-globalThis.localStorage = localStorage;
+
+globalThis.localStorage = new LocalStorage();
+globalThis.LocalStorage = LocalStorage;
+if (globalThis.largestorage_get) {
+    globalThis.largeStorage = new LargeStorage();
+    globalThis.LargeStorage = LargeStorage;
+}
 
 export const workerContext = WorkerContext.create();
 if (workerContext) {
@@ -2436,16 +6233,53 @@ if (workerContext) {
 
 export class FetchResponse {
     _resp;
+    #served;
 
-    constructor(resp, status) {
+    /**
+     * @param resp native response, null if served by a protocol handler
+     * @param status {number}
+     * @param served {{headers: Record<string, string>, body: ArrayBuffer} | undefined} response served by a protocol handler
+     */
+    constructor(resp, status, served = undefined) {
         this._resp = resp;
+        this.#served = served;
         this.status = status;
         this.ok = this.status >= 200 && this.status < 300;
     }
 
+    /**
+     * @returns {Promise<Record<string, string>>} lower case names to values
+     */
+    async getHeaders() {
+        if (this.#served) {
+            return this.#served.headers;
+        }
+        const headers = {};
+        for (const {name, value} of await fetch_response_headers(this._resp)) {
+            headers[name.toLowerCase()] = value;
+        }
+        return headers;
+    }
+
+    async text() {
+        if (this.#served) {
+            return decodeURIComponent(escape(Bytes_toBinaryString(this.#served.body)));
+        }
+        return await fetch_response_body_string(this._resp);
+    }
+
+    /**
+     * @returns {Promise<ArrayBuffer>}
+     */
+    async arrayBuffer() {
+        if (this.#served) {
+            return this.#served.body;
+        }
+        return await fetch_response_body_bytes(this._resp);
+    }
+
     async json() {
-        const body = await fetch_response_body_string(this._resp);
-        return JSON.parse(body);
+        return JSON.parse(await this.text());
     }
 
 }
@@ -2457,6 +6291,10 @@ export class FetchResponse {
  * @returns {Promise<FetchResponse>}
  */
 async function fetch(url, options) {
+    const served = await Protocol.fetch(url, options);
+    if (served) {
+        return served;
+    }
     const resp = await fetch_create(url, options);
     let status = await fetch_response_status(resp);
     return new FetchResponse(resp, status);
@@ -2467,8 +6305,41 @@ globalThis.fetch = fetch;
 globalThis.navigator = new Navigator();
 globalThis.process = new Process();
 globalThis.process.setPromiseRejectionTracker(error => {
+    metrics_increment("deft.js.exceptions", 1);
     console.error('uncaught promise error', error);
+    reportException(error);
 });
+globalThis.metrics = new Metrics();
+globalThis.performance = new Performance();
+globalThis.AsyncStream_create = id => new AsyncStream(id);
+globalThis.Bytes_toArrayBuffer = binary => {
+    const bytes = new Uint8Array(binary.length);
+    for (let i = 0; i < binary.length; i++) {
+        bytes[i] = binary.charCodeAt(i);
+    }
+    return bytes.buffer;
+};
+globalThis.Bytes_toBinaryString = buffer => {
+    let bytes;
+    if (buffer instanceof ArrayBuffer) {
+        bytes = new Uint8Array(buffer);
+    } else if (ArrayBuffer.isView(buffer)) {
+        bytes = new Uint8Array(buffer.buffer, buffer.byteOffset, buffer.byteLength);
+    } else {
+        throw new TypeError("ArrayBuffer or typed array expected");
+    }
+    let binary = "";
+    // Avoid exceeding max arguments of apply
+    for (let i = 0; i < bytes.length; i += 8192) {
+        binary += String.fromCharCode.apply(null, bytes.subarray(i, i + 8192));
+    }
+    return binary;
+};
+if (globalThis.fs_read_dir) {
+    globalThis.fs = new FileSystem();
+    globalThis.CancelToken = CancelToken;
+}
+globalThis.autofill = new Autofill();
 globalThis.fileDialog = new FileDialog();
 globalThis.Worker = Worker;
 globalThis.WorkerContext = WorkerContext;
@@ -2477,7 +6348,29 @@ if (globalThis.SystemTray_create) {
     globalThis.SystemTray = SystemTray;
 }
 globalThis.Menu = Menu;
+globalThis.NativeMenu = NativeMenu;
 globalThis.StandardMenuItem = StandardMenuItem;
+for (const [tag, elementClass] of [
+    [VT_CONTAINER, ContainerElement],
+    [VT_SCROLL, ScrollElement],
+    [VT_LABEL, LabelElement],
+    [VT_TEXT_INPUT, TextInputElement],
+    [VT_TEXT_EDIT, TextEditElement],
+    [VT_BUTTON, ButtonElement],
+    [VT_IMAGE, ImageElement],
+    [VT_EXTERNAL_TEXTURE, ExternalTextureElement],
+    [VT_VIDEO, VideoElement],
+    [VT_RICH_TEXT, RichTextElement],
+    [VT_RICH_TEXT_EDIT, RichTextEditElement],
+    [VT_CHECKBOX, CheckboxElement],
+    [VT_RADIO, RadioElement],
+    [VT_RADIO_GROUP, RadioGroupElement],
+    [VT_SELECT, SelectElement],
+    [VT_DIALOG, DialogElement],
+    [VT_DIALOG_TITLE, DialogTitleElement],
+]) {
+    ElementPool.registerClass(tag, elementClass);
+}
 globalThis.Element = Element;
 globalThis.ContainerElement = ContainerElement;
 globalThis.ScrollElement = ScrollElement;
@@ -2486,19 +6379,168 @@ globalThis.TextInputElement = TextInputElement;
 globalThis.TextEditElement = TextEditElement;
 globalThis.ButtonElement = ButtonElement;
 globalThis.ImageElement  = ImageElement;
+globalThis.ExternalTextureElement = ExternalTextureElement;
+globalThis.VideoElement = VideoElement;
 globalThis.RichTextElement = RichTextElement;
+globalThis.RichTextEditElement = RichTextEditElement;
 globalThis.CheckboxElement = CheckboxElement;
 globalThis.RadioElement = RadioElement;
 globalThis.RadioGroupElement = RadioGroupElement;
 globalThis.SelectElement = SelectElement;
+globalThis.RenderContext = RenderContext;
+globalThis.deft = {
+    registerElement,
+    EventEmitter,
+    debug: new RenderDebug(),
+    a11y: new A11y(),
+    systemMetrics: new SystemMetrics(),
+    screen: new Screen(),
+    network: new Network(),
+    downloads: new DownloadManager(),
+    protocol: new Protocol(),
+    /**
+     * Whether app is started with `--safe-mode`, i.e. software rendering without animations
+     */
+    safeMode: app_is_safe_mode(),
+    /**
+     * Whether app is running without visible windows, e.g. started by `bootstrap_headless` or with `DEFT_HEADLESS=1`
+     */
+    headless: app_is_headless(),
+    /**
+     * Version of deft
+     */
+    version: app_version(),
+    /**
+     * Native features compiled in, e.g. "websocket", "audio", "sqlite", "tray"
+     */
+    features: new Set(app_features()),
+    /**
+     * Register shortcut working in any window of app, throws if the shortcut is registered already
+     * @param accelerator {string} e.g. "Ctrl+Shift+P", "CmdOrCtrl+S", "Alt+F4"
+     * @param callback {() => void}
+     * @returns {number} id of shortcut
+     */
+    registerShortcut: (accelerator, callback) => app_register_shortcut(accelerator, callback),
+    unregisterShortcut: (id) => app_unregister_shortcut(id),
+    setShortcutEnabled: (id, enabled) => app_set_shortcut_enabled(id, enabled),
+    /**
+     * Font families tried in order when chars are missing in the fonts of text, e.g. CJK and emoji fonts
+     * @returns {string[]}
+     */
+    getFallbackFonts: () => typeface_get_fallback_fonts(),
+    /**
+     * Replace the fallback font list, only text laid out afterwards is affected
+     * @param families {string[]} an empty list restores the default list of the platform
+     */
+    setFallbackFonts: (families) => typeface_set_fallback_fonts(families),
+    /**
+     * Collect garbage of js heap now, which is otherwise done in idle periods between frames
+     * @returns {number} duration in milliseconds
+     */
+    gc: () => gc_run(),
+    /**
+     * @returns {GcStats}
+     */
+    getGcStats: () => gc_get_stats(),
+    /**
+     * Whether to collect garbage automatically in idle periods between frames, enabled by default
+     * @param enabled {boolean}
+     */
+    setIdleGcEnabled: (enabled) => gc_set_idle_enabled(enabled),
+    /**
+     * Called on memory pressure after image and layer caches are trimmed and garbage is collected,
+     * release caches of app here
+     * @param handler {(() => void) | null}
+     */
+    setMemoryPressureHandler: (handler) => gc_set_memory_pressure_handler(handler),
+    /**
+     * Trim caches and collect garbage as if memory pressure is reported by system
+     */
+    trimMemory: () => gc_trim_memory(),
+    /**
+     * Run callback with selector matching and window updates deferred, they are flushed once
+     * when the callback returns. Changes made by js are also batched until the current task ends.
+     * @template T
+     * @param callback {() => T}
+     * @returns {T}
+     */
+    batch(callback) {
+        batch_begin();
+        try {
+            return callback();
+        } finally {
+            batch_end();
+        }
+    },
+    /**
+     * Throw if a native feature is not compiled in
+     * @param feature {string}
+     */
+    require(feature) {
+        if (!this.features.has(feature)) {
+            throw new Error(`deft feature "${feature}" is not enabled, rebuild with cargo feature "${feature}"`);
+        }
+    },
+    config: {
+        load: (defaults, name) => Config.load(defaults, name),
+    },
+};
+if (globalThis.debugger_start) {
+    /**
+     * Start debug server speaking Debug Adapter Protocol, attach to it from VS Code with the
+     * returned token in the `token` field of the attach configuration. It only pauses on uncaught
+     * exceptions and evaluates expressions in the global scope.
+     * @param address {string | undefined} defaults to "127.0.0.1:4711"
+     * @returns {string} token of the debug session
+     */
+    globalThis.deft.startDebugger = (address) => debugger_start(address);
+}
+if (globalThis.devtools_start) {
+    /**
+     * Start devtools server, then open the returned url with a session token in a browser to
+     * inspect the app
+     * @param address {string} e.g. "127.0.0.1:9222"
+     * @returns {string} url like http://{address}/?token={token}
+     */
+    globalThis.deft.startDevtools = (address) => devtools_start(address);
+}
+if (globalThis.inspector_start) {
+    /**
+     * Start element inspector server, then open the returned url in a browser or connect to
+     * ws://{address}/?token={token} from other tools to inspect and edit styles of elements
+     * @param address {string} e.g. "127.0.0.1:9223"
+     * @returns {string} url like http://{address}/?token={token}
+     */
+    globalThis.deft.startInspector = (address) => inspector_start(address);
+}
+if (globalThis.iap_is_supported) {
+    globalThis.deft.iap = new InAppPurchase();
+}
+if (globalThis.oauth_authorize) {
+    globalThis.deft.oauth = new OAuth();
+}
 globalThis.Audio = Audio;
 globalThis.WebSocket = WebSocket;
 globalThis.Sqlite = Sqlite;
+if (globalThis.ObjectStoreDb_create) {
+    globalThis.ObjectStoreDatabase = ObjectStoreDatabase;
+    globalThis.ObjectKeyRange = ObjectKeyRange;
+}
 
 globalThis.setTimeout = globalThis.timer_set_timeout;
 globalThis.clearTimeout = globalThis.timer_clear_timeout;
 globalThis.setInterval = globalThis.timer_set_interval;
 globalThis.clearInterval = globalThis.timer_clear_interval;
+globalThis.requestIdleCallback = (callback, options = {}) => {
+    return timer_request_idle_callback((didTimeout, timeRemaining) => {
+        const end = Date.now() + timeRemaining;
+        callback({
+            didTimeout,
+            timeRemaining: () => Math.max(0, end - Date.now()),
+        });
+    }, options.timeout);
+};
+globalThis.cancelIdleCallback = globalThis.timer_cancel_idle_callback;
 globalThis.KEY_MOD_CTRL = 0x1;
 globalThis.KEY_MOD_ALT = 0x1 << 1;
 globalThis.KEY_MOD_META = 0x1 << 2;
@@ -2517,14 +6559,26 @@ globalThis.KEY_MOD_SHIFT = 0x1 << 3;
 
 /**
  * @typedef {IEvent<BoundsChangeDetail>} IBoundsChangeEvent
+ * @typedef {IEvent<FullscreenChangeDetail>} IFullscreenChangeEvent
+ * @typedef {IEvent<PointerLockChangeDetail>} IPointerLockChangeEvent
  * @typedef {IEvent<void>} IVoidEvent
  * @typedef {IEvent<CaretDetail>} ICaretEvent
+ * @typedef {IEvent<MaskValidateDetail>} IMaskValidateEvent
+ * @typedef {IEvent<TruncationChangeDetail>} ITruncationChangeEvent
+ * @typedef {IEvent<LinkClickDetail>} ILinkClickEvent
+ * @typedef {IEvent<TimeUpdateDetail>} ITimeUpdateEvent
+ * @typedef {IEvent<LinkHoverDetail>} ILinkHoverEvent
  * @typedef {IEvent<MouseDetail>} IMouseEvent
  * @typedef {IEvent<KeyDetail>} IKeyEvent
  * @typedef {IEvent<MouseWheelDetail>} IMouseWheelEvent
  * @typedef {IEvent<TextDetail>} ITextEvent
  * @typedef {IEvent<TouchDetail>} ITouchEvent
+ * @typedef {IEvent<GestureZoomDetail>} IGestureZoomEvent
+ * @typedef {IEvent<GestureRotateDetail>} IGestureRotateEvent
+ * @typedef {IEvent<GesturePanDetail>} IGesturePanEvent
  * @typedef {IEvent<ScrollDetail>} IScrollEvent
  * @typedef {IEvent<string>} IDroppedFileEvent
  * @typedef {IEvent<string>} IHoveredFileEvent
+ * @typedef {IEvent<StorageDetail>} IStorageEvent
+ * @typedef {{code: number, cancel: () => void}} BeforeQuitEvent
  */
\ No newline at end of file
@@ -25,11 +25,25 @@ static EVENT_MANAGER: LazyLock<EventManager> = LazyLock::new(|| {
 
     MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
         // println!("tray menu event {:?}", event);
-        EVENT_MANAGER.emit_menu_click(&event.id.0);
+        if !EVENT_MANAGER.emit_menu_click(&event.id.0) {
+            if let Some(handler) = &*UNHANDLED_MENU_EVENT_HANDLER.lock().unwrap() {
+                handler(&event.id.0);
+            }
+        }
     }));
     EventManager::default()
 });
 
+/// Called with ids of menu events not belonging to trays, e.g. menus of windows
+static UNHANDLED_MENU_EVENT_HANDLER: Mutex<Option<Box<dyn Fn(&str) + Send>>> = Mutex::new(None);
+
+/// The menu event handler is shared by all menus, others should receive their events by
+/// this instead of replacing the handler
+pub fn set_unhandled_menu_event_handler(handler: Box<dyn Fn(&str) + Send>) {
+    LazyLock::force(&EVENT_MANAGER);
+    *UNHANDLED_MENU_EVENT_HANDLER.lock().unwrap() = Some(handler);
+}
+
 pub struct GenericTray {
     tray_icon: TrayIcon,
     menu_click_callback: Arc<Mutex<Box<dyn FnMut(String) + Send>>>,
@@ -72,10 +86,13 @@ impl EventManager {
         }
     }
 
-    pub fn emit_menu_click(&self, menu_id: &str) {
+    pub fn emit_menu_click(&self, menu_id: &str) -> bool {
         let mut click_callbacks = self.menu_click_callbacks.lock().unwrap();
         if let Some(callback) = click_callbacks.get_mut(menu_id) {
             callback();
+            true
+        } else {
+            false
         }
     }
 }
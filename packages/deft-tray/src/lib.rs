@@ -11,6 +11,8 @@ use serde::{Deserialize, Serialize};
 pub use crate::linux_tray::LinuxTray as Tray;
 #[cfg(any(target_os = "windows", target_os = "macos"))]
 pub use crate::generic_tray::GenericTray as Tray;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub use crate::generic_tray::set_unhandled_menu_event_handler;
 #[cfg(not(any(linux_pc, target_os = "windows", target_os = "macos")))]
 pub use crate::no_tray::NoTray as Tray;
 
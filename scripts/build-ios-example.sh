@@ -0,0 +1,7 @@
+#!/bin/bash
+set -ue
+
+# Static lib is only needed by the Xcode project, so it is not a crate type of the example
+TARGET=${1:-aarch64-apple-ios}
+
+cargo rustc --target $TARGET --release --example mobile_demo --crate-type staticlib
@@ -1,10 +1,15 @@
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
-use crate::layer::{ILayer, Layer};
+use crate::layer::{ILayer, Layer, LayerSurface};
+use crate::pool::{self, SurfacePoolRef};
 
 pub trait IRenderContext {
     fn create_layer(&mut self, width: usize, height: usize) -> Option<Box<dyn ILayer>>;
     fn flush(&mut self);
+    /// Pool of idle layer surfaces, layers are not reused if None
+    fn surface_pool(&self) -> Option<SurfacePoolRef> {
+        None
+    }
 }
 
 pub struct UserContext {
@@ -63,9 +68,32 @@ impl<'a> RenderContext<'a> {
         Self { context: Box::new(context), user_context }
     }
 
+    /// Create a layer with surface of at least the given size, idle surfaces are reused if
+    /// the context has a surface pool
     pub fn create_layer(&mut self, width: usize, height: usize) -> Option<Layer> {
-        let layer = self.context.create_layer(width, height)?;
-        Some(Layer::new(layer))
+        let pool = match self.context.surface_pool() {
+            Some(pool) => pool,
+            None => {
+                let layer = self.context.create_layer(width, height)?;
+                return Some(Layer::new(layer));
+            }
+        };
+        let (width, height) = pool::surface_size(width, height);
+        let surface = match pool {
+            SurfacePoolRef::Raster(pool) => {
+                let layer = pool.lock().unwrap().take_or_create(width, height);
+                LayerSurface::Raster(layer, pool)
+            }
+            SurfacePoolRef::Gpu(pool) => {
+                let pooled = pool.lock().unwrap().take(width, height);
+                let layer = match pooled {
+                    Some(layer) => layer,
+                    None => self.context.create_layer(width, height)?,
+                };
+                LayerSurface::Gpu(layer, pool)
+            }
+        };
+        Some(Layer::new_pooled(surface, width, height))
     }
 
     pub fn flush(&mut self) {
@@ -1,13 +1,17 @@
 use crate::context::IRenderContext;
 use crate::gl::layer::GlLayer;
 use crate::layer::ILayer;
+use crate::pool::{SurfacePool, SurfacePoolRef};
 use skia_safe::gpu::surfaces::wrap_backend_texture;
 use skia_safe::gpu::{Mipmapped, Protected, Renderable, SurfaceOrigin};
 use skia_safe::{gpu, AlphaType, ColorType, Image};
+use std::sync::{Arc, Mutex};
 
 #[derive(Clone)]
 pub struct GlRenderContext {
     pub gr_context: gpu::DirectContext,
+    /// Textures are bound to the context, so they are only reused by its window
+    pub surface_pool: Arc<Mutex<SurfacePool>>,
 }
 
 impl IRenderContext for GlRenderContext {
@@ -53,4 +57,8 @@ impl IRenderContext for GlRenderContext {
     fn flush(&mut self) {
         self.gr_context.flush_and_submit();
     }
+
+    fn surface_pool(&self) -> Option<SurfacePoolRef> {
+        Some(SurfacePoolRef::Gpu(self.surface_pool.clone()))
+    }
 }
@@ -1,6 +1,8 @@
+use std::collections::VecDeque;
 use std::ffi::{CString};
 use std::num::NonZeroU32;
 use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::channel;
 use std::{thread};
 use ::gl::GetIntegerv;
@@ -8,11 +10,12 @@ use gl::types::{GLint};
 use glutin::config::{Config};
 use glutin::context::PossiblyCurrentContext;
 use glutin::display::{Display};
+use glutin::error::ErrorKind;
 use glutin::prelude::*;
 use glutin::surface::{WindowSurface};
 use log::info;
 use measure_time::print_time;
-use skia_safe::{ColorType, gpu, Surface};
+use skia_safe::{ColorSpace, ColorType, gpu, Surface};
 use skia_safe::gpu::{backend_render_targets, SurfaceOrigin};
 use skia_safe::gpu::gl::FramebufferInfo;
 #[cfg(glx_backend)]
@@ -20,11 +23,13 @@ use winit::platform::x11;
 use winit::window::Window;
 use crate::context::{RenderContext, UserContext};
 use crate::gl::context::GlRenderContext;
-use crate::renderer::Renderer;
+use crate::pool::SurfacePool;
+use crate::renderer::{Renderer, MAX_FRAMES_IN_FLIGHT};
 
 enum RenderMsg {
     Updated,
     Resize(u32, u32),
+    SetColorSpace(ColorSpace),
 }
 
 pub struct GlRenderer {
@@ -38,6 +43,7 @@ struct SurfaceParams {
     num_samples: usize,
     stencil_size: usize,
     frame_buffer_info: FramebufferInfo,
+    hdr: bool,
 }
 
 struct GlContext {
@@ -58,6 +64,7 @@ impl GlRenderer {
         gl_surface: glutin::surface::Surface<WindowSurface>,
         context: PossiblyCurrentContext,
         gl_config: &Config,
+        hdr: bool,
     ) -> Option<Self> {
         unsafe {
             gl::load_with(|s| {
@@ -80,9 +87,14 @@ impl GlRenderer {
                 GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut fboid);
 
 
+                let format = if hdr {
+                    gpu::gl::Format::RGBA16F
+                } else {
+                    gpu::gl::Format::RGBA8
+                };
                 FramebufferInfo {
                     fboid: fboid.try_into().unwrap(),
-                    format: gpu::gl::Format::RGBA8.into(),
+                    format: format.into(),
                     ..Default::default()
                 }
             };
@@ -95,6 +107,13 @@ impl GlRenderer {
                 num_samples,
                 stencil_size,
                 frame_buffer_info: fb_info,
+                hdr,
+            };
+            // scRGB, i.e. linear srgb whose 1.0 is sdr white and larger values are brighter
+            let color_space = if hdr {
+                ColorSpace::new_srgb_linear()
+            } else {
+                ColorSpace::new_srgb()
             };
 
             let size = window.inner_size();
@@ -104,11 +123,20 @@ impl GlRenderer {
             );
             let surface = {
                 // let context = context.lock().unwrap();
-                Self::create_surface(size.0, size.1, &mut gr_context, &surface_params)
+                Self::create_surface(
+                    size.0,
+                    size.1,
+                    &mut gr_context,
+                    &surface_params,
+                    color_space.clone(),
+                )
             };
 
             let context = context.make_not_current().ok()?.treat_as_possibly_current();
-            let render_context = GlRenderContext { gr_context };
+            let render_context = GlRenderContext {
+                gr_context,
+                surface_pool: Arc::new(Mutex::new(SurfacePool::new())),
+            };
             let context =  GlContext {
                 surface,
                 gl_surface,
@@ -117,12 +145,14 @@ impl GlRenderer {
                 user_context: Some(UserContext::new()),
             };
             let context = Arc::new(Mutex::new(context));
-            let drawer = Arc::new(Mutex::new(None));
+            let drawer = Arc::new(Mutex::new(VecDeque::new()));
 
             let render_context_wrapper = RenderContextWrapper {
                 context,
                 surface_params,
                 drawer,
+                color_space: Arc::new(Mutex::new(color_space)),
+                context_lost: Arc::new(AtomicBool::new(false)),
             };
             let (sender, receiver) = channel();
             {
@@ -141,6 +171,9 @@ impl GlRenderer {
                             RenderMsg::Resize(width, height) => {
                                 render_context_wrapper.resize(width, height);
                             }
+                            RenderMsg::SetColorSpace(color_space) => {
+                                render_context_wrapper.set_color_space(color_space);
+                            }
                         }
                     }
                     info!("GlRenderer thread stopped");
@@ -160,11 +193,28 @@ impl GlRenderer {
         self.sender.send(RenderMsg::Resize(width, height)).unwrap();
     }
 
+    /// Ignored for hdr surfaces, which could represent colors of any gamut
+    pub fn set_color_space(&self, color_space: ColorSpace) {
+        if self.is_hdr() {
+            return;
+        }
+        self.sender.send(RenderMsg::SetColorSpace(color_space)).unwrap();
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.render_context_wrapper.surface_params.hdr
+    }
+
+    pub fn is_context_lost(&self) -> bool {
+        self.render_context_wrapper.context_lost.load(Ordering::Relaxed)
+    }
+
     fn create_surface(
         width: i32,
         height: i32,
         gr_context: &mut gpu::DirectContext,
         surface_params: &SurfaceParams,
+        color_space: ColorSpace,
     ) -> Surface {
         let num_samples = surface_params.num_samples;
         let stencil_size = surface_params.stencil_size;
@@ -173,13 +223,18 @@ impl GlRenderer {
 
         let backend_render_target =
             backend_render_targets::make_gl(size, num_samples, stencil_size, fb_info);
+        let color_type = if surface_params.hdr {
+            ColorType::RGBAF16
+        } else {
+            ColorType::RGBA8888
+        };
 
         gpu::surfaces::wrap_backend_render_target(
             gr_context,
             &backend_render_target,
             SurfaceOrigin::BottomLeft,
-            ColorType::RGBA8888,
-            None,
+            color_type,
+            Some(color_space),
             None,
         )
             .expect("Could not create skia surface")
@@ -194,8 +249,12 @@ struct RenderTask {
 #[derive(Clone)]
 pub struct RenderContextWrapper {
     context: Arc<Mutex<GlContext>>,
-    drawer: Arc<Mutex<Option<RenderTask>>>,
+    /// Frames waiting for rasterization, the one being rasterized is not included
+    drawer: Arc<Mutex<VecDeque<RenderTask>>>,
     surface_params: SurfaceParams,
+    color_space: Arc<Mutex<ColorSpace>>,
+    /// Set when gpu reset or swapping buffers failed, e.g. driver crashed or device removed
+    context_lost: Arc<AtomicBool>,
 }
 
 impl RenderContextWrapper {
@@ -214,6 +273,7 @@ impl RenderContextWrapper {
             height as i32,
             &mut context.render_context.gr_context,
             &sf_params,
+            self.color_space.lock().unwrap().clone(),
         );
         context.surface = sf;
         /* First resize the opengl drawable */
@@ -225,16 +285,37 @@ impl RenderContextWrapper {
         );
     }
 
+    pub fn set_color_space(&self, color_space: ColorSpace) {
+        let mut context = self.context.lock().unwrap();
+        let (width, height) = (context.surface.width(), context.surface.height());
+        *self.color_space.lock().unwrap() = color_space.clone();
+        context.surface = GlRenderer::create_surface(
+            width,
+            height,
+            &mut context.render_context.gr_context,
+            &self.surface_params,
+            color_space,
+        );
+    }
+
     pub fn render(&self,  drawer: Renderer, callback: Box<dyn FnOnce(bool) + Send + 'static>) {
         // print_time!("replace drawer");
         let mut drawer_mg = self.drawer.lock().unwrap();
-        if let Some(task) = drawer_mg.take() {
+        while drawer_mg.len() >= MAX_FRAMES_IN_FLIGHT {
+            let task = drawer_mg.pop_front().unwrap();
             (task.callback)(false);
         }
-        drawer_mg.replace(RenderTask { task: drawer, callback });
+        drawer_mg.push_back(RenderTask { task: drawer, callback });
     }
 
     fn update(&self) {
+        if self.context_lost.load(Ordering::Relaxed) {
+            let tasks = self.drawer.lock().unwrap().drain(..).collect::<Vec<_>>();
+            for task in tasks {
+                (task.callback)(false);
+            }
+            return;
+        }
         // print_time!("gpu render time");
         let mut context = {
             // print_time!("lock time");
@@ -247,7 +328,7 @@ impl RenderContextWrapper {
             // print_time!("draw time");
             let drawer = {
                 let mut drawer_arc = self.drawer.lock().unwrap();
-                drawer_arc.take()
+                drawer_arc.pop_front()
             };
             if let Some(drawer) = drawer {
                 let mut user_context = context.user_context.take().unwrap();
@@ -265,11 +346,22 @@ impl RenderContextWrapper {
             // measure_time::print_time!("submit time");
             context.render_context.gr_context.flush_and_submit();
         }
+        if context.render_context.gr_context.abandoned() {
+            log::error!("GL context lost");
+            self.context_lost.store(true, Ordering::Relaxed);
+            callback(false);
+            return;
+        }
 
         {
             // measure_time::print_time!("swap buffers time");
             if let Err(err) = context.gl_surface.swap_buffers(&context.context) {
                 log::error!("Failed to swap buffers after render: {}", err);
+                if err.error_kind() == ErrorKind::ContextLost {
+                    self.context_lost.store(true, Ordering::Relaxed);
+                    callback(false);
+                    return;
+                }
             }
         }
         callback(true);
@@ -1,5 +1,5 @@
 use std::num::NonZeroU32;
-use glutin::config::{Config, ConfigSurfaceTypes, ConfigTemplate, ConfigTemplateBuilder};
+use glutin::config::{ColorBufferType, Config, ConfigSurfaceTypes, ConfigTemplate, ConfigTemplateBuilder};
 use glutin::context::{ContextApi, ContextAttributesBuilder, NotCurrentContext};
 use glutin::display::{Display, DisplayApiPreference};
 use glutin::surface::{SurfaceAttributesBuilder, WindowSurface};
@@ -7,6 +7,7 @@ use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandl
 use winit::event_loop::ActiveEventLoop;
 #[cfg(glx_backend)]
 use winit::platform::x11;
+use skia_safe::ColorSpace;
 use winit::window::{Window};
 
 use glutin::prelude::*;
@@ -79,9 +80,16 @@ impl SurfaceState {
         }
     }
 
-    /// Create template to find OpenGL config.
-    fn config_template(raw_window_handle: RawWindowHandle) -> ConfigTemplate {
+    /// Create template to find OpenGL config, hdr requires half float color buffer and
+    /// transparent windows require alpha channel composited by the system.
+    #[cfg_attr(cgl_backend, allow(unused_variables))]
+    fn config_template(
+        raw_window_handle: RawWindowHandle,
+        hdr: bool,
+        transparent: bool,
+    ) -> ConfigTemplate {
         let builder = ConfigTemplateBuilder::new()
+            .with_float_pixels(hdr)
             .prefer_hardware_accelerated(None)
             .with_depth_size(0)
             .with_stencil_size(0)
@@ -90,42 +98,81 @@ impl SurfaceState {
 
         #[cfg(cgl_backend)]
         let builder = builder.with_transparency(true).with_multisampling(8);
+        #[cfg(not(cgl_backend))]
+        let builder = builder.with_transparency(transparent);
+
+        if hdr {
+            builder.with_alpha_size(16).with_buffer_type(ColorBufferType::Rgb {
+                r_size: 16,
+                g_size: 16,
+                b_size: 16,
+            })
+        } else {
+            builder
+        }
+        .build()
+    }
 
-        builder.build()
+    /// Float pixel formats of window surfaces are only composited as hdr on windows and macos
+    fn is_hdr_supported() -> bool {
+        cfg!(any(target_os = "windows", target_os = "macos"))
     }
 
 
-    pub fn new(event_loop: &ActiveEventLoop, window: Window) -> Option<SurfaceState> {
+    /// Create surface with float pixels if `hdr` is true, falls back to sdr if not supported.
+    /// Surface of transparent window keeps per-pixel alpha if any config supports it.
+    pub fn new(
+        event_loop: &ActiveEventLoop,
+        window: Window,
+        hdr: bool,
+        transparent: bool,
+    ) -> Option<SurfaceState> {
         let raw_display_handle = event_loop.raw_display_handle().ok()?;
         let raw_window_handle = window.raw_window_handle().ok()?;
 
         let glutin_display = Self::ensure_glutin_display(raw_display_handle, &window);
         // Lazily initialize, egl, wgl, glx etc
 
-        let template = Self::config_template(raw_window_handle);
-        let config = unsafe {
-            glutin_display
-                .find_configs(template)
-                .ok()?
-                .reduce(|accum, config| {
-                    // Find the config with the minimum number of samples.
-                    //
-                    // In general if you're not sure what you want in template you can request or
-                    // don't want to require multisampling for example, you can search for a
-                    // specific option you want afterwards.
-                    //
-                    // XXX however on macOS you can request only one config, so you should do
-                    // a search with the help of `find_configs` and adjusting your template.
-                    if config.num_samples() < accum.num_samples() {
-                        config
-                    } else if config.stencil_size() < accum.stencil_size() {
-                        config
-                    } else if config.depth_size() < accum.depth_size() {
-                        config
-                    } else {
-                        accum
-                    }
-                })?
+        let hdr_config = if hdr && Self::is_hdr_supported() {
+            let template = Self::config_template(raw_window_handle, true, transparent);
+            unsafe { glutin_display.find_configs(template).ok() }
+                .and_then(|mut configs| configs.find(|c| c.float_pixels()))
+        } else {
+            None
+        };
+        let hdr = hdr_config.is_some();
+        let find_config = |transparent: bool| {
+            let template = Self::config_template(raw_window_handle, false, transparent);
+            unsafe {
+                glutin_display
+                    .find_configs(template)
+                    .ok()?
+                    .reduce(|accum, config| {
+                        // Find the config with the minimum number of samples.
+                        //
+                        // In general if you're not sure what you want in template you can request or
+                        // don't want to require multisampling for example, you can search for a
+                        // specific option you want afterwards.
+                        //
+                        // XXX however on macOS you can request only one config, so you should do
+                        // a search with the help of `find_configs` and adjusting your template.
+                        if config.num_samples() < accum.num_samples() {
+                            config
+                        } else if config.stencil_size() < accum.stencil_size() {
+                            config
+                        } else if config.depth_size() < accum.depth_size() {
+                            config
+                        } else {
+                            accum
+                        }
+                    })
+            }
+        };
+        let config = match hdr_config {
+            Some(config) => config,
+            // Opaque configs are used if the platform has no config with alpha composited
+            None if transparent => find_config(true).or_else(|| find_config(false))?,
+            None => find_config(false)?,
         };
         println!("Picked a config with {} samples", config.num_samples());
 
@@ -149,7 +196,7 @@ impl SurfaceState {
         let context = not_current_context
             .make_current(&surface)
             .expect("Failed to make GL context current");
-        let render = GlRenderer::new(&glutin_display, &window, surface, context, &config)?;
+        let render = GlRenderer::new(&glutin_display, &window, surface, context, &config, hdr)?;
 
         Some(SurfaceState { window, glutin_display, render })
     }
@@ -167,4 +214,16 @@ impl RenderBackend for SurfaceState {
     fn resize(&mut self, width: u32, height: u32) {
         self.render.resize(&self.window(), width, height);
     }
+
+    fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.render.set_color_space(color_space);
+    }
+
+    fn is_hdr(&self) -> bool {
+        self.render.is_hdr()
+    }
+
+    fn is_context_lost(&self) -> bool {
+        self.render.is_context_lost()
+    }
 }
\ No newline at end of file
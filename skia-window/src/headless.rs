@@ -0,0 +1,41 @@
+use crate::context::{IRenderContext, RenderContext, UserContext};
+use crate::layer::ILayer;
+use crate::paint::Image;
+use crate::pool::{soft_pool, SurfacePoolRef};
+use crate::renderer::Renderer;
+use crate::soft::layer::SoftLayer;
+use skia_safe::{surfaces, ColorSpace, ImageInfo};
+
+/// Render context of offscreen rendering, layers are rasterized by cpu
+struct OffscreenContext;
+
+impl IRenderContext for OffscreenContext {
+    fn create_layer(&mut self, width: usize, height: usize) -> Option<Box<dyn ILayer>> {
+        Some(Box::new(SoftLayer::new(width as u32, height as u32)))
+    }
+
+    fn flush(&mut self) {
+        // Do nothing
+    }
+
+    fn surface_pool(&self) -> Option<SurfacePoolRef> {
+        Some(soft_pool())
+    }
+}
+
+/// Render to a raster surface of the size in physical pixels instead of a window, which works
+/// without display and gpu, e.g. for screenshots and tests
+pub fn render_to_image(
+    width: u32,
+    height: u32,
+    color_space: Option<ColorSpace>,
+    renderer: Renderer,
+) -> Option<Image> {
+    let info = ImageInfo::new_n32_premul((width as i32, height as i32), color_space);
+    let mut surface = surfaces::raster(&info, None, None)?;
+    let mut context = OffscreenContext;
+    let mut user_context = UserContext::new();
+    let mut ctx = RenderContext::new(&mut context, &mut user_context);
+    renderer.render(surface.canvas(), &mut ctx);
+    Some(surface.image_snapshot())
+}
@@ -1,4 +1,6 @@
 use crate::paint::{Canvas, Image};
+use crate::pool::{self, RasterSurfacePool, SurfacePool};
+use std::sync::{Arc, Mutex};
 
 pub trait ILayer {
     fn canvas(&mut self) -> &Canvas;
@@ -6,19 +8,63 @@ pub trait ILayer {
 }
 
 pub struct Layer {
-    layer: Box<dyn ILayer>,
+    surface: Option<LayerSurface>,
+    /// Surface size the layer is returned to its pool with when dropped
+    size: (usize, usize),
+}
+
+/// Surface of a layer and the pool it is returned to
+pub(crate) enum LayerSurface {
+    Unpooled(Box<dyn ILayer>),
+    Raster(Box<dyn ILayer + Send>, Arc<Mutex<RasterSurfacePool>>),
+    Gpu(Box<dyn ILayer>, Arc<Mutex<SurfacePool>>),
 }
 
 impl Layer {
     pub fn new(layer: Box<dyn ILayer>) -> Self {
-        Layer { layer }
+        Layer {
+            surface: Some(LayerSurface::Unpooled(layer)),
+            size: (0, 0),
+        }
+    }
+
+    pub(crate) fn new_pooled(surface: LayerSurface, width: usize, height: usize) -> Self {
+        pool::on_layer_created(width, height);
+        Layer {
+            surface: Some(surface),
+            size: (width, height),
+        }
     }
 
     pub fn canvas(&mut self) -> &Canvas {
-        self.layer.canvas()
+        self.layer().canvas()
     }
 
     pub fn as_image(&mut self) -> Image {
-        self.layer.as_image()
+        self.layer().as_image()
+    }
+
+    fn layer(&mut self) -> &mut dyn ILayer {
+        match self.surface.as_mut().unwrap() {
+            LayerSurface::Unpooled(layer) | LayerSurface::Gpu(layer, _) => layer.as_mut(),
+            LayerSurface::Raster(layer, _) => layer.as_mut(),
+        }
+    }
+}
+
+impl Drop for Layer {
+    fn drop(&mut self) {
+        let (width, height) = self.size;
+        match self.surface.take() {
+            Some(LayerSurface::Raster(layer, pool)) => {
+                pool::on_layer_dropped(width, height);
+                pool.lock().unwrap().put(width, height, layer);
+            }
+            Some(LayerSurface::Gpu(layer, pool)) => {
+                pool::on_layer_dropped(width, height);
+                pool.lock().unwrap().put(width, height, layer);
+            }
+            _ => {}
+        }
     }
 }
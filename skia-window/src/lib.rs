@@ -5,6 +5,9 @@ pub mod skia_window;
 mod surface;
 pub mod layer;
 pub mod context;
+pub mod headless;
+pub mod pool;
+pub mod texture;
 #[cfg(feature = "gl")]
 mod gl;
 pub mod renderer;
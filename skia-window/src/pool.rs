@@ -0,0 +1,206 @@
+use crate::layer::ILayer;
+use crate::soft::layer::SoftLayer;
+use skia_safe::Color;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// Default limit of bytes held by layer surfaces of all windows
+pub const DEFAULT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Surface sizes are rounded up to multiples of it, so layers of similar sizes share surfaces
+const SIZE_BUCKET: usize = 64;
+
+static MEMORY_BUDGET: AtomicUsize = AtomicUsize::new(DEFAULT_MEMORY_BUDGET);
+static TRIM_GENERATION: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static POOLED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static HITS: AtomicUsize = AtomicUsize::new(0);
+static MISSES: AtomicUsize = AtomicUsize::new(0);
+
+/// Raster surfaces are not bound to a context, so they are shared by all windows
+static SOFT_POOL: LazyLock<Arc<Mutex<RasterSurfacePool>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(RasterSurfacePool::new())));
+
+/// Pool of raster surfaces, which could be used by render threads of all windows
+pub type RasterSurfacePool = SurfacePool<dyn ILayer + Send>;
+
+/// Pool layers of a render context are taken from and returned to
+#[derive(Clone)]
+pub enum SurfacePoolRef {
+    /// Raster surfaces shared by all windows, layers are created by the pool
+    Raster(Arc<Mutex<RasterSurfacePool>>),
+    /// Surfaces bound to a gpu context, only used on its render thread
+    Gpu(Arc<Mutex<SurfacePool>>),
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SurfacePoolStats {
+    pub budget_bytes: usize,
+    /// Bytes of surfaces used by layers
+    pub live_bytes: usize,
+    /// Bytes of idle surfaces kept for reuse
+    pub pooled_bytes: usize,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// Limit bytes of layer surfaces, idle surfaces are released when exceeded
+pub fn set_memory_budget(bytes: usize) {
+    MEMORY_BUDGET.store(bytes, Ordering::Relaxed);
+}
+
+pub fn memory_budget() -> usize {
+    MEMORY_BUDGET.load(Ordering::Relaxed)
+}
+
+/// Release all idle surfaces, e.g. on memory pressure. Surfaces of gpu contexts are released
+/// on their render threads when next layer is created.
+pub fn trim() {
+    TRIM_GENERATION.fetch_add(1, Ordering::Relaxed);
+    SOFT_POOL.lock().unwrap().trim_if_requested();
+}
+
+pub fn stats() -> SurfacePoolStats {
+    SurfacePoolStats {
+        budget_bytes: memory_budget(),
+        live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+        pooled_bytes: POOLED_BYTES.load(Ordering::Relaxed),
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    }
+}
+
+pub(crate) fn soft_pool() -> SurfacePoolRef {
+    SurfacePoolRef::Raster(SOFT_POOL.clone())
+}
+
+/// Size of surface created for a layer of the given size
+pub fn surface_size(width: usize, height: usize) -> (usize, usize) {
+    (bucket_size(width), bucket_size(height))
+}
+
+pub(crate) fn on_layer_created(width: usize, height: usize) {
+    LIVE_BYTES.fetch_add(surface_bytes(width, height), Ordering::Relaxed);
+}
+
+pub(crate) fn on_layer_dropped(width: usize, height: usize) {
+    LIVE_BYTES.fetch_sub(surface_bytes(width, height), Ordering::Relaxed);
+}
+
+fn bucket_size(size: usize) -> usize {
+    size.max(1).div_ceil(SIZE_BUCKET) * SIZE_BUCKET
+}
+
+fn surface_bytes(width: usize, height: usize) -> usize {
+    width * height * 4
+}
+
+struct PooledLayer<L: ?Sized> {
+    width: usize,
+    height: usize,
+    layer: Box<L>,
+}
+
+/// Idle layer surfaces, most recently released last
+pub struct SurfacePool<L: ?Sized + ILayer = dyn ILayer> {
+    layers: Vec<PooledLayer<L>>,
+    trim_generation: usize,
+}
+
+impl RasterSurfacePool {
+    /// Take an idle surface of the exact surface size or create a new one
+    pub fn take_or_create(&mut self, width: usize, height: usize) -> Box<dyn ILayer + Send> {
+        self.take(width, height)
+            .unwrap_or_else(|| Box::new(SoftLayer::new(width as u32, height as u32)))
+    }
+}
+
+impl<L: ?Sized + ILayer> SurfacePool<L> {
+    pub fn new() -> Self {
+        Self {
+            layers: Vec::new(),
+            trim_generation: TRIM_GENERATION.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Take an idle surface of the exact surface size, cleared and with canvas state reset
+    pub fn take(&mut self, width: usize, height: usize) -> Option<Box<L>> {
+        self.trim_if_requested();
+        let idx = self
+            .layers
+            .iter()
+            .rposition(|l| l.width == width && l.height == height);
+        let idx = match idx {
+            Some(idx) => idx,
+            None => {
+                MISSES.fetch_add(1, Ordering::Relaxed);
+                self.make_room(surface_bytes(width, height));
+                return None;
+            }
+        };
+        HITS.fetch_add(1, Ordering::Relaxed);
+        let mut pooled = self.layers.remove(idx);
+        POOLED_BYTES.fetch_sub(surface_bytes(width, height), Ordering::Relaxed);
+        let canvas = pooled.layer.canvas();
+        canvas.restore_to_count(1);
+        canvas.reset_matrix();
+        canvas.clear(Color::TRANSPARENT);
+        Some(pooled.layer)
+    }
+
+    /// Keep surface of a dropped layer for reuse unless budget is exceeded
+    pub fn put(&mut self, width: usize, height: usize, layer: Box<L>) {
+        self.trim_if_requested();
+        let bytes = surface_bytes(width, height);
+        let used = LIVE_BYTES.load(Ordering::Relaxed) + POOLED_BYTES.load(Ordering::Relaxed);
+        if used + bytes > memory_budget() {
+            return;
+        }
+        POOLED_BYTES.fetch_add(bytes, Ordering::Relaxed);
+        self.layers.push(PooledLayer {
+            width,
+            height,
+            layer,
+        });
+    }
+
+    /// Release least recently used surfaces until `bytes` more fit in budget
+    fn make_room(&mut self, bytes: usize) {
+        let budget = memory_budget();
+        while !self.layers.is_empty() {
+            let used = LIVE_BYTES.load(Ordering::Relaxed) + POOLED_BYTES.load(Ordering::Relaxed);
+            if used + bytes <= budget {
+                break;
+            }
+            let pooled = self.layers.remove(0);
+            POOLED_BYTES.fetch_sub(
+                surface_bytes(pooled.width, pooled.height),
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    fn trim_if_requested(&mut self) {
+        let generation = TRIM_GENERATION.load(Ordering::Relaxed);
+        if self.trim_generation == generation {
+            return;
+        }
+        self.trim_generation = generation;
+        self.clear();
+    }
+
+    fn clear(&mut self) {
+        for pooled in self.layers.drain(..) {
+            POOLED_BYTES.fetch_sub(
+                surface_bytes(pooled.width, pooled.height),
+                Ordering::Relaxed,
+            );
+        }
+    }
+}
+
+impl<L: ?Sized + ILayer> Drop for SurfacePool<L> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
@@ -1,6 +1,10 @@
 use crate::context::RenderContext;
 use crate::paint::Canvas;
 
+/// Frames submitted but not presented yet, i.e. one rasterizing on render thread and one
+/// queued, while the next one is built on ui thread. Older queued frames are dropped when full.
+pub const MAX_FRAMES_IN_FLIGHT: usize = 2;
+
 pub struct Renderer {
     renderer: Box<dyn FnOnce(&Canvas, &mut RenderContext) + Send + 'static>,
 }
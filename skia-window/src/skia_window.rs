@@ -1,19 +1,24 @@
 use std::collections::HashSet;
 use std::ops::Deref;
 
+use crate::paint::Image;
 use crate::renderer::Renderer;
 use crate::soft::SoftSurface;
 use crate::surface::RenderBackend;
+use skia_safe::{named_gamut, named_transfer_fn, ColorSpace};
 use winit::event_loop::ActiveEventLoop;
 use winit::window::{Window, WindowAttributes};
 
 pub struct SkiaWindow {
     surface_state: Box<dyn RenderBackend>,
+    backend_type: RenderBackendType,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum RenderBackendType {
     SoftBuffer,
+    /// Render into memory without presenting, it is never selected unless requested explicitly
+    Headless,
     #[cfg(feature = "gl")]
     GL,
     #[cfg(feature = "gl")]
@@ -35,9 +40,42 @@ impl RenderBackendType {
         list
     }
 
+    /// Whether rendering is done by cpu, which is not affected by gpu context loss
+    pub fn is_software(&self) -> bool {
+        matches!(self, Self::SoftBuffer | Self::Headless)
+    }
+
+    /// Whether hdr surface could be created, see `SkiaWindow::new_with_hdr`
+    pub fn supports_hdr(&self) -> bool {
+        #[cfg(feature = "gl")]
+        if *self == Self::GL {
+            return cfg!(any(target_os = "windows", target_os = "macos"));
+        }
+        false
+    }
+
+    /// Whether alpha of transparent windows is composited by the system, softbuffer ignores it
+    pub fn supports_transparency(&self) -> bool {
+        *self != Self::SoftBuffer
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SoftBuffer => "softbuffer",
+            Self::Headless => "headless",
+            #[cfg(feature = "gl")]
+            Self::GL => "gl",
+            #[cfg(feature = "gl")]
+            Self::SoftGL => "softgl",
+            #[cfg(feature = "webgl")]
+            Self::WebGL => "webgl",
+        }
+    }
+
     pub fn from_str(backend_type_str: &str) -> Option<Self> {
         match backend_type_str.to_lowercase().as_str() {
             "softbuffer" => Some(RenderBackendType::SoftBuffer),
+            "headless" => Some(RenderBackendType::Headless),
             #[cfg(feature = "gl")]
             "softgl" => Some(RenderBackendType::SoftGL),
             #[cfg(feature = "gl")]
@@ -77,13 +115,60 @@ impl RenderBackendType {
     }
 }
 
+/// Color space of window surface, colors and tagged images are converted to it when drawing
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum SurfaceColorSpace {
+    #[default]
+    Srgb,
+    /// Wide gamut of most recent monitors, colors look oversaturated on srgb monitors
+    DisplayP3,
+}
+
+impl SurfaceColorSpace {
+    pub fn from_str(color_space_str: &str) -> Option<Self> {
+        match color_space_str.to_lowercase().as_str() {
+            "srgb" => Some(Self::Srgb),
+            "display-p3" | "p3" => Some(Self::DisplayP3),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Srgb => "srgb",
+            Self::DisplayP3 => "display-p3",
+        }
+    }
+
+    pub fn to_skia(&self) -> ColorSpace {
+        match self {
+            Self::Srgb => ColorSpace::new_srgb(),
+            Self::DisplayP3 => {
+                ColorSpace::new_rgb(&named_transfer_fn::SRGB, &named_gamut::DISPLAY_P3)
+            }
+        }
+    }
+}
+
 impl SkiaWindow {
-    #[allow(unreachable_code)]
     pub fn new(
         event_loop: &ActiveEventLoop,
         attributes: WindowAttributes,
         backend: RenderBackendType,
     ) -> Option<Self> {
+        Self::new_with_hdr(event_loop, attributes, backend, false)
+    }
+
+    /// Same as `new` but request a hdr surface if `hdr` is true, which is only supported by gl
+    /// backend on windows and macos. Check `is_hdr` for whether it's created.
+    #[allow(unreachable_code, unused_variables)]
+    pub fn new_with_hdr(
+        event_loop: &ActiveEventLoop,
+        attributes: WindowAttributes,
+        backend: RenderBackendType,
+        hdr: bool,
+    ) -> Option<Self> {
+        let transparent = attributes.transparent;
         let surface_state: Box<dyn RenderBackend> = match backend {
             RenderBackendType::SoftBuffer => {
                 #[cfg(target_os = "emscripten")]
@@ -91,18 +176,31 @@ impl SkiaWindow {
                 #[cfg(not(target_os = "emscripten"))]
                 {
                     use crate::soft::softbuffer_surface_presenter::SoftBufferSurfacePresenter;
-                    let window = event_loop.create_window(attributes).ok()?;
+                    // Alpha channel is ignored by softbuffer, keep the window opaque instead of
+                    // showing garbage behind it
+                    let window = event_loop
+                        .create_window(attributes.with_transparent(false))
+                        .ok()?;
                     let presenter = SoftBufferSurfacePresenter::new(window);
                     let soft_surface = SoftSurface::new(event_loop, presenter);
                     Box::new(soft_surface)
                 }
             }
+            RenderBackendType::Headless => {
+                use crate::soft::headless_presenter::HeadlessPresenter;
+                // Frames are never presented, the window is only a source of events
+                let window = event_loop
+                    .create_window(attributes.with_visible(false))
+                    .ok()?;
+                let soft_surface = SoftSurface::new(event_loop, HeadlessPresenter::new(window));
+                Box::new(soft_surface)
+            }
             #[cfg(feature = "gl")]
             RenderBackendType::SoftGL => {
                 let window = event_loop.create_window(attributes).ok()?;
                 let soft_surface = SoftSurface::new(
                     event_loop,
-                    crate::soft::gl_presenter::GlPresenter::new(event_loop, window)?,
+                    crate::soft::gl_presenter::GlPresenter::new(event_loop, window, transparent)?,
                 );
                 Box::new(soft_surface)
             }
@@ -111,7 +209,12 @@ impl SkiaWindow {
                 #[cfg(target_env = "ohos")]
                 return None;
                 let window = event_loop.create_window(attributes).ok()?;
-                Box::new(crate::gl::SurfaceState::new(event_loop, window)?)
+                Box::new(crate::gl::SurfaceState::new(
+                    event_loop,
+                    window,
+                    hdr,
+                    transparent,
+                )?)
             }
             #[cfg(target_os = "emscripten")]
             RenderBackendType::WebGL => {
@@ -119,7 +222,27 @@ impl SkiaWindow {
                 Box::new(crate::webgl::WebGLRenderer::new(event_loop, window)?)
             }
         };
-        Some(Self { surface_state })
+        Some(Self {
+            surface_state,
+            backend_type: backend,
+        })
+    }
+
+    pub fn set_color_space(&mut self, color_space: SurfaceColorSpace) {
+        self.surface_state.set_color_space(color_space.to_skia());
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.surface_state.is_hdr()
+    }
+
+    pub fn backend_type(&self) -> RenderBackendType {
+        self.backend_type
+    }
+
+    /// Whether gpu context is lost, the window should be recreated with another backend then
+    pub fn is_context_lost(&self) -> bool {
+        self.surface_state.is_context_lost()
     }
 
     pub fn resize_surface(&mut self, width: u32, height: u32) {
@@ -130,6 +253,11 @@ impl SkiaWindow {
     pub fn scale_factor(&self) -> f64 {
         self.winit_window().scale_factor()
     }
+
+    /// Last rendered frame of headless backend, None for backends presenting to display
+    pub fn last_frame(&self) -> Option<Image> {
+        self.surface_state.last_frame()
+    }
 }
 
 impl Deref for SkiaWindow {
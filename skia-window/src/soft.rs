@@ -1,7 +1,8 @@
 mod context;
 #[cfg(feature = "gl")]
 pub mod gl_presenter;
-mod layer;
+pub mod headless_presenter;
+pub(crate) mod layer;
 mod soft_renderer;
 pub mod soft_surface;
 pub mod softbuffer_surface_presenter;
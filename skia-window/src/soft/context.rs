@@ -1,5 +1,6 @@
 use crate::context::{IRenderContext, UserContext};
 use crate::layer::ILayer;
+use crate::pool::{soft_pool, SurfacePoolRef};
 use crate::soft::layer::SoftLayer;
 use crate::soft::surface_presenter::SurfacePresenter;
 
@@ -26,4 +27,8 @@ impl IRenderContext for SoftRenderContext {
     fn flush(&mut self) {
         // Do nothing
     }
+
+    fn surface_pool(&self) -> Option<SurfacePoolRef> {
+        Some(soft_pool())
+    }
 }
@@ -1,7 +1,7 @@
 use crate::gl::SurfaceState;
 use crate::soft::surface_presenter::SurfacePresenter;
 use crate::surface::RenderBackend;
-use skia_safe::{Canvas};
+use skia_safe::{Canvas, ColorSpace};
 use winit::event_loop::ActiveEventLoop;
 use winit::window::Window;
 use crate::renderer::Renderer;
@@ -13,11 +13,12 @@ pub struct GlPresenter {
 }
 
 impl GlPresenter {
-    pub fn new(event_loop: &ActiveEventLoop, window: Window) -> Option<GlPresenter> {
+    /// Pixels are drawn straight into the gl surface, so transparent windows keep their alpha
+    pub fn new(event_loop: &ActiveEventLoop, window: Window, transparent: bool) -> Option<GlPresenter> {
         let size = window.inner_size();
         let width = size.width;
         let height = size.height;
-        let surface_state = SurfaceState::new(event_loop, window)?;
+        let surface_state = SurfaceState::new(event_loop, window, false, transparent)?;
         Some(Self {
             surface_state,
             width,
@@ -47,4 +48,12 @@ impl SurfacePresenter for GlPresenter {
     fn size(&self) -> (u32, u32) {
         (self.width, self.height)
     }
+
+    fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.surface_state.set_color_space(color_space);
+    }
+
+    fn is_context_lost(&self) -> bool {
+        self.surface_state.is_context_lost()
+    }
 }
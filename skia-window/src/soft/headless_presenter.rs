@@ -0,0 +1,69 @@
+use crate::paint::{Canvas, Image};
+use crate::soft::surface_presenter::SurfacePresenter;
+use skia_safe::{surfaces, ColorSpace, ImageInfo};
+use winit::window::Window;
+
+/// Presenter keeping frames in memory instead of showing them, so that windows could be
+/// rendered on virtual displays without gpu or shared memory support
+pub struct HeadlessPresenter {
+    window: Window,
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    last_frame: Option<Image>,
+}
+
+impl HeadlessPresenter {
+    pub fn new(window: Window) -> Self {
+        let size = window.inner_size();
+        Self {
+            window,
+            width: size.width,
+            height: size.height,
+            color_space: ColorSpace::new_srgb(),
+            last_frame: None,
+        }
+    }
+}
+
+impl SurfacePresenter for HeadlessPresenter {
+    fn window(&self) -> &Window {
+        &self.window
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    fn render(
+        &mut self,
+        renderer: Box<dyn FnOnce(&Canvas) + Send>,
+        callback: Box<dyn FnOnce(bool) + Send + 'static>,
+    ) {
+        let size = (self.width.max(1) as i32, self.height.max(1) as i32);
+        let info = ImageInfo::new_n32_premul(size, Some(self.color_space.clone()));
+        let mut surface = match surfaces::raster(&info, None, None) {
+            Some(s) => s,
+            None => {
+                callback(false);
+                return;
+            }
+        };
+        renderer(surface.canvas());
+        self.last_frame = Some(surface.image_snapshot());
+        callback(true);
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+    }
+
+    fn last_frame(&self) -> Option<Image> {
+        self.last_frame.clone()
+    }
+}
@@ -6,6 +6,10 @@ pub struct SoftLayer {
     renderer: SoftRenderer,
 }
 
+// SAFETY: raster surfaces own their pixels and are not bound to a thread or gpu context, so they
+// could be moved to render threads of other windows through the shared raster pool
+unsafe impl Send for SoftLayer {}
+
 impl SoftLayer {
     pub fn new(width: u32, height: u32) -> SoftLayer {
         let renderer = SoftRenderer::new(width as i32, height as i32);
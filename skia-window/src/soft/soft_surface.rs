@@ -1,9 +1,10 @@
 use std::ops::DerefMut;
+use skia_safe::ColorSpace;
 use winit::event_loop::ActiveEventLoop;
 use winit::window::{Window};
 use crate::context::{RenderContext};
 use crate::mrc::Mrc;
-use crate::paint::Canvas;
+use crate::paint::{Canvas, Image};
 use crate::renderer::Renderer;
 use crate::soft::context::SoftRenderContext;
 use crate::soft::surface_presenter::SurfacePresenter;
@@ -59,4 +60,16 @@ impl RenderBackend for SoftSurface {
         self.width = width;
         self.height = height;
     }
+
+    fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.context.surface_presenter.set_color_space(color_space);
+    }
+
+    fn is_context_lost(&self) -> bool {
+        self.context.surface_presenter.is_context_lost()
+    }
+
+    fn last_frame(&self) -> Option<Image> {
+        self.context.surface_presenter.last_frame()
+    }
 }
\ No newline at end of file
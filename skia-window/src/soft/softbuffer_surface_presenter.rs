@@ -1,10 +1,11 @@
 use crate::paint::Canvas;
+use crate::renderer::MAX_FRAMES_IN_FLIGHT;
 use crate::soft::surface_presenter::SurfacePresenter;
 use skia_safe::{AlphaType, ColorSpace, ColorType, ImageInfo};
 use softbuffer::{Context, Surface};
+use std::collections::VecDeque;
 use std::num::{NonZeroU32};
-use std::sync::mpsc::Sender;
-use std::sync::{mpsc, Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
 use std::{slice, thread};
 use winit::window::Window;
 
@@ -12,6 +13,7 @@ struct WinSurface {
     surface: Surface<Arc<Window>, Arc<Window>>,
     width: u32,
     height: u32,
+    color_space: ColorSpace,
 }
 
 struct RenderTask {
@@ -22,11 +24,19 @@ struct RenderTask {
 
 unsafe impl Send for RenderTask {}
 
+/// Frames waiting for the render thread, the one being rendered is not included
+#[derive(Default)]
+struct RenderQueue {
+    tasks: VecDeque<RenderTask>,
+    closed: bool,
+}
+
 impl RenderTask {
     pub fn run(self) {
         let mut win_surface = self.surface.lock().unwrap();
         let width = win_surface.width;
         let height = win_surface.height;
+        let color_space = win_surface.color_space.clone();
         let _ = win_surface.surface.resize(
             NonZeroU32::new(width).unwrap(),
             NonZeroU32::new(height).unwrap(),
@@ -43,7 +53,7 @@ impl RenderTask {
             (width as i32, height as i32),
             color_type,
             AlphaType::Premul,
-            Some(ColorSpace::new_srgb()),
+            Some(color_space),
         );
         let buf_ptr = buffer.as_mut_ptr() as *mut u8;
         let len = buffer.len() * 4;
@@ -65,7 +75,7 @@ impl RenderTask {
 pub struct SoftBufferSurfacePresenter {
     window: Arc<Window>,
     win_surface: Arc<Mutex<WinSurface>>,
-    sender: Sender<RenderTask>,
+    queue: Arc<(Mutex<RenderQueue>, Condvar)>,
 }
 
 impl SoftBufferSurfacePresenter {
@@ -78,22 +88,31 @@ impl SoftBufferSurfacePresenter {
             NonZeroU32::new(size.width).unwrap(),
             NonZeroU32::new(size.height).unwrap(),
         );
-        let (sender, receiver) = mpsc::channel::<RenderTask>();
+        let queue = Arc::new((Mutex::new(RenderQueue::default()), Condvar::new()));
+        let render_queue = queue.clone();
         thread::spawn(move || loop {
-            if let Ok(task) = receiver.recv() {
-                task.run();
-            } else {
-                break;
-            }
+            let task = {
+                let (tasks, cond) = &*render_queue;
+                let mut tasks = tasks.lock().unwrap();
+                while tasks.tasks.is_empty() && !tasks.closed {
+                    tasks = cond.wait(tasks).unwrap();
+                }
+                match tasks.tasks.pop_front() {
+                    Some(task) => task,
+                    None => break,
+                }
+            };
+            task.run();
         });
         Self {
             window,
             win_surface: Arc::new(Mutex::new(WinSurface {
                 width: size.width,
                 height: size.height,
+                color_space: ColorSpace::new_srgb(),
                 surface,
             })),
-            sender,
+            queue,
         }
     }
 }
@@ -108,6 +127,10 @@ impl SurfacePresenter for SoftBufferSurfacePresenter {
         win_surface.height = height;
     }
 
+    fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.win_surface.lock().unwrap().color_space = color_space;
+    }
+
     fn render(
         &mut self,
         renderer: Box<dyn FnOnce(&Canvas) + Send>,
@@ -118,7 +141,14 @@ impl SurfacePresenter for SoftBufferSurfacePresenter {
             renderer,
             callback,
         };
-        let _ = self.sender.send(render_task);
+        let (tasks, cond) = &*self.queue;
+        let mut tasks = tasks.lock().unwrap();
+        while tasks.tasks.len() >= MAX_FRAMES_IN_FLIGHT {
+            let task = tasks.tasks.pop_front().unwrap();
+            (task.callback)(false);
+        }
+        tasks.tasks.push_back(render_task);
+        cond.notify_one();
     }
 
     fn size(&self) -> (u32, u32) {
@@ -126,3 +156,11 @@ impl SurfacePresenter for SoftBufferSurfacePresenter {
         (surface.width, surface.height)
     }
 }
+
+impl Drop for SoftBufferSurfacePresenter {
+    fn drop(&mut self) {
+        let (tasks, cond) = &*self.queue;
+        tasks.lock().unwrap().closed = true;
+        cond.notify_one();
+    }
+}
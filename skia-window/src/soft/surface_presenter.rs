@@ -1,4 +1,5 @@
-use crate::paint::Canvas;
+use crate::paint::{Canvas, Image};
+use skia_safe::ColorSpace;
 use winit::window::Window;
 
 pub trait SurfacePresenter {
@@ -6,4 +7,12 @@ pub trait SurfacePresenter {
     fn resize(&mut self, width: u32, height: u32);
     fn render(&mut self, renderer: Box<dyn FnOnce(&Canvas) + Send>, callback: Box<dyn FnOnce(bool) + Send + 'static>);
     fn size(&self) -> (u32, u32);
+    fn set_color_space(&mut self, _color_space: ColorSpace) {}
+    fn is_context_lost(&self) -> bool {
+        false
+    }
+    /// Last rendered frame, only kept by presenters without display
+    fn last_frame(&self) -> Option<Image> {
+        None
+    }
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
+use skia_safe::ColorSpace;
 use winit::window::Window;
+use crate::paint::Image;
 use crate::renderer::Renderer;
 
 pub trait RenderBackend {
@@ -11,4 +13,22 @@ pub trait RenderBackend {
     );
 
     fn resize(&mut self, width: u32, height: u32);
+
+    /// Color space of surface, drawn colors and images are converted to it
+    fn set_color_space(&mut self, _color_space: ColorSpace) {}
+
+    /// Whether surface is float scRGB, whose values above 1.0 are brighter than sdr white
+    fn is_hdr(&self) -> bool {
+        false
+    }
+
+    /// Whether gpu context is lost and nothing could be rendered any more
+    fn is_context_lost(&self) -> bool {
+        false
+    }
+
+    /// Last rendered frame, only kept by backends without display
+    fn last_frame(&self) -> Option<Image> {
+        None
+    }
 }
\ No newline at end of file
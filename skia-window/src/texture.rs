@@ -0,0 +1,49 @@
+use skia_safe::{Canvas, Image};
+
+/// Texture created by native code in the gl context shared with window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlTextureInfo {
+    pub id: u32,
+    /// e.g. GL_TEXTURE_2D
+    pub target: u32,
+    /// Sized internal format, e.g. GL_RGBA8
+    pub format: u32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Wrap a gl texture as image without copying, None if canvas is not backed by gl.
+/// The texture must stay alive until the frame is flushed.
+#[cfg(feature = "gl")]
+pub fn borrow_gl_texture(canvas: &Canvas, info: &GlTextureInfo) -> Option<Image> {
+    use skia_safe::gpu::gl::TextureInfo;
+    use skia_safe::gpu::{backend_textures, Mipmapped, SurfaceOrigin};
+    use skia_safe::{AlphaType, ColorType};
+
+    let mut context = canvas.direct_context()?;
+    let texture_info = TextureInfo {
+        format: info.format,
+        ..TextureInfo::from_target_and_id(info.target, info.id)
+    };
+    let backend_texture = unsafe {
+        backend_textures::make_gl(
+            (info.width, info.height),
+            Mipmapped::No,
+            texture_info,
+            "external",
+        )
+    };
+    Image::from_texture(
+        &mut context,
+        &backend_texture,
+        SurfaceOrigin::TopLeft,
+        ColorType::RGBA8888,
+        AlphaType::Premul,
+        None,
+    )
+}
+
+#[cfg(not(feature = "gl"))]
+pub fn borrow_gl_texture(_canvas: &Canvas, _info: &GlTextureInfo) -> Option<Image> {
+    None
+}
@@ -0,0 +1,217 @@
+use crate as deft;
+use crate::app::AppEvent;
+use crate::js::JsError;
+use crate::safe_mode::is_safe_mode;
+use crate::stylesheet::refresh_windows_style;
+use crate::{js_deserialize, js_serialize, send_app_event};
+use deft_macros::js_methods;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::{env, thread};
+
+thread_local! {
+    static PREFERENCES: RefCell<A11yPreferences> = RefCell::new(A11yPreferences::initial());
+    /// Detected preferences are ignored once preferences are overridden
+    static OVERRIDDEN: Cell<bool> = Cell::new(false);
+}
+
+/// Accessibility preferences of the system
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct A11yPreferences {
+    /// Animations should be skipped
+    pub reduce_motion: bool,
+    pub high_contrast: bool,
+    /// Scale factor of text chosen by user, 1.0 is the default size
+    pub text_scale: f32,
+}
+
+js_serialize!(A11yPreferences);
+js_deserialize!(A11yPreferences);
+
+impl Default for A11yPreferences {
+    fn default() -> Self {
+        Self {
+            reduce_motion: false,
+            high_contrast: false,
+            text_scale: 1.0,
+        }
+    }
+}
+
+impl A11yPreferences {
+    /// Read preferences of system, `DEFT_REDUCE_MOTION`, `DEFT_HIGH_CONTRAST` and
+    /// `DEFT_TEXT_SCALE` environment variables take precedence.
+    /// Motion is always reduced in safe mode. This may spawn processes like gsettings.
+    pub fn detect() -> Self {
+        Self::with_overrides(Self::system())
+    }
+
+    /// Preferences used until detection in background finishes
+    fn initial() -> Self {
+        let result = thread::Builder::new()
+            .name("a11y-preferences".to_string())
+            .spawn(|| {
+                let prefs = Self::detect();
+                let apply = AppEvent::Callback(Box::new(move || {
+                    if !OVERRIDDEN.get() {
+                        update_preferences(prefs);
+                    }
+                }));
+                if let Err(e) = send_app_event(apply) {
+                    error!("Failed to send accessibility preferences: {}", e);
+                }
+            });
+        if let Err(e) = result {
+            error!("Failed to detect accessibility preferences: {}", e);
+        }
+        Self::with_overrides(Self::default())
+    }
+
+    fn with_overrides(mut prefs: Self) -> Self {
+        if let Some(v) = env_flag("DEFT_REDUCE_MOTION") {
+            prefs.reduce_motion = v;
+        }
+        if let Some(v) = env_flag("DEFT_HIGH_CONTRAST") {
+            prefs.high_contrast = v;
+        }
+        if let Some(v) = env::var("DEFT_TEXT_SCALE").ok().and_then(|v| v.parse().ok()) {
+            prefs.text_scale = v;
+        }
+        if is_safe_mode() {
+            prefs.reduce_motion = true;
+        }
+        prefs
+    }
+
+    #[cfg(windows_platform)]
+    fn system() -> Self {
+        use std::ffi::c_void;
+        use windows::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
+        use windows::Win32::UI::WindowsAndMessaging::{
+            SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SPI_GETHIGHCONTRAST,
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+        };
+        let mut prefs = Self::default();
+        let no_update = SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0);
+        unsafe {
+            let mut animation: i32 = 1;
+            let ptr = &mut animation as *mut _ as *mut c_void;
+            if SystemParametersInfoW(SPI_GETCLIENTAREAANIMATION, 0, Some(ptr), no_update).is_ok() {
+                prefs.reduce_motion = animation == 0;
+            }
+            let mut hc = HIGHCONTRASTW {
+                cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+                ..Default::default()
+            };
+            let size = hc.cbSize;
+            let ptr = &mut hc as *mut _ as *mut c_void;
+            if SystemParametersInfoW(SPI_GETHIGHCONTRAST, size, Some(ptr), no_update).is_ok() {
+                prefs.high_contrast = (hc.dwFlags & HCF_HIGHCONTRASTON).0 != 0;
+            }
+        }
+        prefs
+    }
+
+    #[cfg(macos_platform)]
+    fn system() -> Self {
+        let read_flag = |key: &str| {
+            let output = std::process::Command::new("defaults")
+                .args(["read", "com.apple.universalaccess", key])
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim() == "1")
+        };
+        let mut prefs = Self::default();
+        if let Some(v) = read_flag("reduceMotion") {
+            prefs.reduce_motion = v;
+        }
+        if let Some(v) = read_flag("increaseContrast") {
+            prefs.high_contrast = v;
+        }
+        prefs
+    }
+
+    #[cfg(linux_platform)]
+    fn system() -> Self {
+        let read_setting = |schema: &str, key: &str| {
+            let output = std::process::Command::new("gsettings")
+                .args(["get", schema, key])
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        };
+        let mut prefs = Self::default();
+        if let Some(v) = read_setting("org.gnome.desktop.interface", "enable-animations") {
+            prefs.reduce_motion = v == "false";
+        }
+        if let Some(v) = read_setting("org.gnome.desktop.a11y.interface", "high-contrast") {
+            prefs.high_contrast = v == "true";
+        }
+        let scale = read_setting("org.gnome.desktop.interface", "text-scaling-factor");
+        if let Some(v) = scale.and_then(|v| v.parse().ok()) {
+            prefs.text_scale = v;
+        }
+        prefs
+    }
+
+    /// Preferences are not detected on mobile platforms, they could be set with `set_preferences`
+    #[cfg(not(desktop_platform))]
+    fn system() -> Self {
+        Self::default()
+    }
+}
+
+fn env_flag(name: &str) -> Option<bool> {
+    let value = env::var(name).ok()?;
+    Some(!matches!(value.as_str(), "" | "0" | "false"))
+}
+
+pub fn get_a11y_preferences() -> A11yPreferences {
+    PREFERENCES.with_borrow(|p| p.clone())
+}
+
+/// Override preferences, styles depending on them are refreshed
+pub fn set_a11y_preferences(preferences: A11yPreferences) {
+    OVERRIDDEN.set(true);
+    update_preferences(preferences);
+}
+
+fn update_preferences(preferences: A11yPreferences) {
+    let changed = PREFERENCES.with_borrow_mut(|p| {
+        let changed = *p != preferences;
+        *p = preferences;
+        changed
+    });
+    if changed {
+        refresh_windows_style();
+    }
+}
+
+pub fn is_reduce_motion() -> bool {
+    PREFERENCES.with_borrow(|p| p.reduce_motion)
+}
+
+#[allow(nonstandard_style)]
+pub struct a11y {}
+
+#[js_methods]
+impl a11y {
+    #[js_func]
+    pub fn get_preferences() -> Result<A11yPreferences, JsError> {
+        Ok(get_a11y_preferences())
+    }
+
+    #[js_func]
+    pub fn set_preferences(preferences: A11yPreferences) -> Result<(), JsError> {
+        set_a11y_preferences(preferences);
+        Ok(())
+    }
+}
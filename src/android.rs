@@ -1,9 +1,10 @@
 use crate::app::{AppEvent, InsetType};
 use crate::base::Rect;
 use crate::send_app_event;
-use jni::objects::{JClass, JString, JValue};
+use anyhow::{anyhow, Error};
+use jni::objects::{JClass, JObject, JString, JValue, JValueOwned};
 use jni::sys::{jboolean, jfloat, jint, jlong};
-use jni::JNIEnv;
+use jni::{JNIEnv, JavaVM};
 use log::debug;
 use std::sync::OnceLock;
 use winit::platform::android::activity::AndroidApp;
@@ -15,6 +16,50 @@ pub fn init_android_app(app: &AndroidApp) {
     ANDROID_APP.get_or_init(move || app);
 }
 
+/// Run callback with the activity of app on an attached jni env
+pub fn with_activity<R>(
+    callback: impl FnOnce(&mut JNIEnv, &JObject) -> Result<R, Error>,
+) -> Result<R, Error> {
+    let app = ANDROID_APP
+        .get()
+        .ok_or_else(|| anyhow!("android app not initialized"))?;
+    let vm = unsafe { JavaVM::from_raw(app.vm_as_ptr() as _)? };
+    let activity = unsafe { JObject::from_raw(app.activity_as_ptr() as _) };
+    let mut env = vm.attach_current_thread()?;
+    callback(&mut env, &activity)
+}
+
+/// Check whether the activity of app implements an optional method
+pub fn has_activity_method(env: &mut JNIEnv, activity: &JObject, name: &str, sig: &str) -> bool {
+    let found = env
+        .get_object_class(activity)
+        .and_then(|class| env.get_method_id(&class, name, sig))
+        .is_ok();
+    // NoSuchMethodError is thrown when not found
+    let _ = env.exception_clear();
+    found
+}
+
+/// Call an optional method implemented by the activity of app, missing methods and java
+/// exceptions are returned as errors instead of left pending
+pub fn call_activity_method<'local>(
+    env: &mut JNIEnv<'local>,
+    activity: &JObject,
+    name: &str,
+    sig: &str,
+    args: &[JValue],
+) -> Result<JValueOwned<'local>, Error> {
+    if !has_activity_method(env, activity, name, sig) {
+        return Err(anyhow!("{} is not implemented by the activity", name));
+    }
+    let result = env.call_method(activity, name, sig, args);
+    if env.exception_check()? {
+        env.exception_describe()?;
+        env.exception_clear()?;
+    }
+    result.map_err(|e| anyhow!("failed to call {}: {}", name, e))
+}
+
 #[no_mangle]
 pub extern "system" fn Java_deft_DeftActivity_send<'local>(
     mut env: JNIEnv<'local>,
@@ -91,3 +136,68 @@ pub fn clipboard_write_text(content: &str) -> Result<(), jni::errors::Error> {
     .v()?;
     Ok(())
 }
+
+/// Transport of the active network with internet capability, None if offline
+pub fn active_network_transport() -> Result<Option<&'static str>, jni::errors::Error> {
+    use jni::objects::JObject;
+    use jni::JavaVM;
+    // Constants of android.net.NetworkCapabilities
+    const NET_CAPABILITY_INTERNET: i32 = 12;
+    const TRANSPORTS: [(i32, &str); 4] =
+        [(1, "wifi"), (0, "cellular"), (3, "ethernet"), (4, "vpn")];
+    let app = ANDROID_APP.get().unwrap();
+    let vm = unsafe { JavaVM::from_raw(app.vm_as_ptr() as _)? };
+    let activity = unsafe { JObject::from_raw(app.activity_as_ptr() as _) };
+    let mut env = vm.attach_current_thread()?;
+    let service_name = env.new_string("connectivity")?;
+    let manager = env
+        .call_method(
+            &activity,
+            "getSystemService",
+            "(Ljava/lang/String;)Ljava/lang/Object;",
+            &[JValue::Object(&service_name)],
+        )?
+        .l()?;
+    let network = env
+        .call_method(&manager, "getActiveNetwork", "()Landroid/net/Network;", &[])?
+        .l()?;
+    if network.is_null() {
+        return Ok(None);
+    }
+    let capabilities = env
+        .call_method(
+            &manager,
+            "getNetworkCapabilities",
+            "(Landroid/net/Network;)Landroid/net/NetworkCapabilities;",
+            &[JValue::Object(&network)],
+        )?
+        .l()?;
+    if capabilities.is_null() {
+        return Ok(None);
+    }
+    let has_internet = env
+        .call_method(
+            &capabilities,
+            "hasCapability",
+            "(I)Z",
+            &[JValue::Int(NET_CAPABILITY_INTERNET)],
+        )?
+        .z()?;
+    if !has_internet {
+        return Ok(None);
+    }
+    for (transport, name) in TRANSPORTS {
+        let has_transport = env
+            .call_method(
+                &capabilities,
+                "hasTransport",
+                "(I)Z",
+                &[JValue::Int(transport)],
+            )?
+            .z()?;
+        if has_transport {
+            return Ok(Some(name));
+        }
+    }
+    Ok(Some("unknown"))
+}
@@ -1,6 +1,7 @@
 pub mod actor;
 pub mod css_actor;
 
+use crate::a11y::is_reduce_motion;
 use crate::animation::actor::AnimationActor;
 use crate::base::Callback;
 use crate::mrc::Mrc;
@@ -15,7 +16,7 @@ use ordered_float::OrderedFloat;
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap};
 use std::ops::Bound::{Excluded, Included};
-use std::time::{Instant, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use yoga::StyleUnit;
 
 macro_rules! interpolate_values {
@@ -206,6 +207,42 @@ pub trait FrameController {
     fn request_next_frame(&mut self, callback: Box<dyn FnOnce()>);
 }
 
+/// How often an animation applies frames, nothing is painted for skipped frames
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameSkipPolicy {
+    /// Apply every frame
+    None,
+    /// Apply one of every n frames
+    EveryNth(u32),
+    /// Apply at most n frames per second
+    MaxFps(f32),
+}
+
+impl FrameSkipPolicy {
+    /// Policy of `-deft-animation-frame-rate`, 0 to apply every frame
+    pub fn from_max_fps(fps: f32) -> Self {
+        if fps > 0.0 {
+            Self::MaxFps(fps)
+        } else {
+            Self::None
+        }
+    }
+
+    /// Whether current frame should be applied, `since_applied` is number of frames and time
+    /// passed since the last applied frame
+    pub fn should_apply(&self, since_applied: Option<(u32, Duration)>) -> bool {
+        let Some((frames, elapsed)) = since_applied else {
+            return true;
+        };
+        match self {
+            Self::None => true,
+            Self::EveryNth(n) => frames >= *n,
+            // Frames of window are not evenly spaced, allow a little jitter
+            Self::MaxFps(fps) => elapsed.as_secs_f32() >= 0.9 / fps,
+        }
+    }
+}
+
 pub struct AnimationState {
     actor: Box<dyn AnimationActor>,
     start_time: Instant,
@@ -213,6 +250,9 @@ pub struct AnimationState {
     iteration_count: f32,
     frame_controller: Box<dyn FrameController>,
     stopped: bool,
+    frame_skip_policy: FrameSkipPolicy,
+    last_applied_time: Option<Instant>,
+    skipped_frames: u32,
 }
 
 pub struct AnimationInstance {
@@ -312,12 +352,19 @@ impl AnimationInstance {
             iteration_count,
             frame_controller,
             stopped: false,
+            frame_skip_policy: FrameSkipPolicy::None,
+            last_applied_time: None,
+            skipped_frames: 0,
         };
         Self {
             state: Mrc::new(state),
         }
     }
 
+    pub fn set_frame_skip_policy(&mut self, policy: FrameSkipPolicy) {
+        self.state.frame_skip_policy = policy;
+    }
+
     pub fn run(&mut self) {
         let mut state = self.state.clone();
         self.state
@@ -335,16 +382,30 @@ impl AnimationInstance {
     }
 
     fn render_frame(mut state: Mrc<AnimationState>) {
+        if is_reduce_motion() && !state.stopped {
+            // Snap to the end state without intermediate frames
+            let mut is_ended = true;
+            state.actor.apply_animation(1.0, &mut is_ended);
+            state.actor.stop();
+            return;
+        }
         let elapsed = state.start_time.elapsed().as_nanos() as f32;
         let position = elapsed / state.duration;
         let mut is_ended = false;
+        let since_applied = state
+            .last_applied_time
+            .map(|t| (state.skipped_frames + 1, t.elapsed()));
         if position >= state.iteration_count || state.stopped {
             state.actor.stop();
             is_ended = true;
-        } else {
+        } else if state.frame_skip_policy.should_apply(since_applied) {
+            state.last_applied_time = Some(Instant::now());
+            state.skipped_frames = 0;
             state
                 .actor
                 .apply_animation(position - position as usize as f32, &mut is_ended);
+        } else {
+            state.skipped_frames += 1;
         };
         if !is_ended {
             let s = state.clone();
@@ -420,3 +481,21 @@ impl FrameController for SimpleFrameController {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::animation::FrameSkipPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn test_frame_skip_policy() {
+        let frame = Duration::from_millis(16);
+        assert!(FrameSkipPolicy::MaxFps(10.0).should_apply(None));
+        assert!(FrameSkipPolicy::None.should_apply(Some((1, frame))));
+        assert!(!FrameSkipPolicy::EveryNth(3).should_apply(Some((2, frame * 2))));
+        assert!(FrameSkipPolicy::EveryNth(3).should_apply(Some((3, frame * 3))));
+        assert!(!FrameSkipPolicy::MaxFps(30.0).should_apply(Some((1, frame))));
+        assert!(FrameSkipPolicy::MaxFps(30.0).should_apply(Some((2, frame * 2))));
+        assert_eq!(FrameSkipPolicy::None, FrameSkipPolicy::from_max_fps(0.0));
+    }
+}
@@ -1,13 +1,19 @@
 use crate::base::{Rect, ResultWaiter};
+use crate::element::init_base_components;
 use crate::event_loop::{
     init_event_loop_proxy, run_event_loop_task, run_with_event_loop, AppEventProxy,
+    AppEventWakeUp, EventPriority,
 };
 use crate::ext::ext_localstorage::localstorage;
-use crate::ext::ext_window::WINDOWS;
+use crate::ext::ext_process::process;
+use crate::ext::ext_window::{handle_device_event, handle_window_event, WindowAttrs, WINDOWS};
+use crate::ext::service::Service;
 use crate::js::js_engine::JsEngine;
 use crate::js::js_event_loop::{js_init_event_loop, JsEvent, JsEventLoopClosedError};
 use crate::js::loader::JsModuleLoader;
+use crate::loader::StaticModuleLoader;
 use crate::mrc::Mrc;
+use crate::some_or_return;
 use crate::window::{
     window_check_update, window_input, window_on_render_idle, window_send_key, window_update_inset,
 };
@@ -18,7 +24,9 @@ use jni::objects::JValue;
 use jni::sys::{jboolean, jlong};
 use log::debug;
 use measure_time::debug_time;
+use std::cell::RefCell;
 use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use winit::application::ApplicationHandler;
 use winit::event::{DeviceEvent, DeviceId, WindowEvent};
@@ -40,6 +48,9 @@ pub enum InsetType {
     StatusBar,
     Ime,
     Navigation,
+    /// Unsafe area on the left, e.g. the notch of phones in landscape
+    SafeLeft,
+    SafeRight,
 }
 
 impl InsetType {
@@ -67,6 +78,22 @@ pub enum AppEvent {
     RenderIdle(i32),
 }
 
+impl AppEvent {
+    pub fn priority(&self) -> EventPriority {
+        match self {
+            AppEvent::CommitInput(..)
+            | AppEvent::NamedKeyInput(..)
+            | AppEvent::ShowSoftInput(_)
+            | AppEvent::HideSoftInput(_) => EventPriority::Input,
+            AppEvent::Update(_) | AppEvent::RenderIdle(_) => EventPriority::Animation,
+            AppEvent::BindWindow(_)
+            | AppEvent::Callback(_)
+            | AppEvent::JsEvent(_)
+            | AppEvent::SetInset(..) => EventPriority::Normal,
+        }
+    }
+}
+
 impl Debug for AppEvent {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         //TODO impl debug
@@ -82,70 +109,158 @@ pub trait IApp {
         let _ = js_engine;
     }
     /// Create js module loader
-    fn create_module_loader(&mut self) -> Box<dyn JsModuleLoader + Send + Sync + 'static>;
+    fn create_module_loader(&mut self) -> Box<dyn JsModuleLoader + Send + Sync + 'static> {
+        Box::new(StaticModuleLoader::new())
+    }
+    /// App started callback, windows could be created with `deft::view` here without any js code.
+    /// It's called without holding the lock of `App::app_impl`.
+    fn on_start(&mut self) {}
+    /// Whether to execute js entry module after started, return false for pure rust ui.
+    fn execute_js_main(&self) -> bool {
+        true
+    }
+    /// Whether to create js engine, pure rust ui skips it. Defaults to `execute_js_main`.
+    fn use_js_engine(&self) -> bool {
+        self.execute_js_main()
+    }
 }
 
+/// Stands in for app impl while its callbacks run without holding the lock
+struct StartingApp;
+
+impl IApp for StartingApp {}
+
 pub struct WinitApp {
-    pub js_engine: Mrc<JsEngine>,
+    /// None for pure rust ui, see `IApp::use_js_engine`
+    pub js_engine: Option<Mrc<JsEngine>>,
+    app: App,
+    event_loop_proxy: AppEventProxy,
+}
+
+/// Window created by engine on start, see `App::with_main_window`
+pub struct MainWindowTemplate {
+    pub attrs: WindowAttrs,
+    pub initial_module: String,
 }
 
 #[derive(Clone)]
 pub struct App {
     pub app_impl: Arc<Mutex<Box<dyn IApp + Send + Sync>>>,
+    pub main_window: Option<Arc<MainWindowTemplate>>,
 }
 
 impl App {
     pub fn new<A: IApp + Send + Sync + 'static>(app: A) -> Self {
         Self {
             app_impl: Arc::new(Mutex::new(Box::new(app))),
+            main_window: None,
         }
     }
+
+    /// Create main window on start and execute `initial_module` instead of the entry module,
+    /// `onCreate` exported by the module is called with the window.
+    pub fn with_main_window(mut self, attrs: WindowAttrs, initial_module: &str) -> Self {
+        self.main_window = Some(Arc::new(MainWindowTemplate {
+            attrs,
+            initial_module: initial_module.to_string(),
+        }));
+        self
+    }
+    /// Call callback with app impl taken out of the lock, so that apis called by the callback
+    /// are free to lock it again
+    fn with_app_unlocked<R, F: FnOnce(&mut Box<dyn IApp + Send + Sync>) -> R>(&self, f: F) -> R {
+        let placeholder: Box<dyn IApp + Send + Sync> = Box::new(StartingApp);
+        let mut app_impl = std::mem::replace(&mut *self.app_impl.lock().unwrap(), placeholder);
+        let result = f(&mut app_impl);
+        *self.app_impl.lock().unwrap() = app_impl;
+        result
+    }
 }
 
 impl WinitApp {
     pub fn new(app: App, event_loop_proxy: AppEventProxy) -> Self {
-        JsEngine::init(app.clone());
-        let mut js_engine = JsEngine::get();
-        js_engine.init_api();
+        let use_js_engine = app.app_impl.lock().unwrap().use_js_engine();
+        let js_engine = if use_js_engine {
+            JsEngine::init(app.clone());
+            let mut js_engine = JsEngine::get();
+            js_engine.init_api();
+            Some(js_engine)
+        } else {
+            init_base_components();
+            None
+        };
         init_event_loop_proxy(event_loop_proxy.clone());
+        let js_event_loop_proxy = event_loop_proxy.clone();
         let _ = js_init_event_loop(move |js_event| {
-            let _ = event_loop_proxy
+            let _ = js_event_loop_proxy
                 .send_event(AppEvent::JsEvent(js_event))
                 .map_err(|_| JsEventLoopClosedError {});
             Ok(())
         });
-        {
+        if let Some(js_engine) = &js_engine {
+            let mut js_engine = js_engine.clone();
             let mut app = app.app_impl.lock().unwrap();
             app.init_js_engine(&mut js_engine);
         }
-        Self { js_engine }
+        Self {
+            js_engine,
+            app,
+            event_loop_proxy,
+        }
     }
 
     fn execute_pending_jobs(&mut self) {
-        self.js_engine.execute_pending_jobs();
+        if let Some(js_engine) = &self.js_engine {
+            js_engine.execute_pending_jobs();
+        }
     }
 }
 
-impl ApplicationHandler<AppEventPayload> for WinitApp {
+impl ApplicationHandler<AppEventWakeUp> for WinitApp {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         run_event_loop_task(event_loop, move || {
             let uninitialized = WINDOWS.with(|m| m.borrow().is_empty());
             if uninitialized {
                 debug_time!("js init time");
-                self.js_engine.execute_main();
+                let app = self.app.clone();
+                let execute_js_main = app.with_app_unlocked(|app| {
+                    app.on_start();
+                    app.execute_js_main()
+                });
+                match (&mut self.js_engine, &app.main_window) {
+                    (Some(js_engine), Some(template)) if execute_js_main => {
+                        if let Err(e) = js_engine.execute_main_window(template) {
+                            log::error!("failed to create main window: {:?}", e);
+                        }
+                    }
+                    (Some(js_engine), None) if execute_js_main => js_engine.execute_main(),
+                    _ => {}
+                }
                 self.execute_pending_jobs();
             } else {
+                // Surfaces are kept on ios when app goes to background
+                #[cfg(not(ios_platform))]
                 WINDOWS.with_borrow_mut(|m| {
                     m.iter_mut().for_each(|(_, f)| {
                         if let Ok(mut f) = f.upgrade_mut() {
                             f.resume();
                         }
                     })
-                })
+                });
+                process::handle_lifecycle_change(true);
+                self.execute_pending_jobs();
             }
         });
     }
-    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: AppEventPayload) {
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        run_event_loop_task(event_loop, move || {
+            process::handle_lifecycle_change(false);
+            self.execute_pending_jobs();
+        });
+    }
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, _event: AppEventWakeUp) {
+        let event = some_or_return!(self.event_loop_proxy.take_next_event());
         run_event_loop_task(event_loop, move || {
             match event.event {
                 AppEvent::BindWindow(id) => {
@@ -207,7 +322,7 @@ impl ApplicationHandler<AppEventPayload> for WinitApp {
     ) {
         // debug!("onWindowEvent: {:?}, {:?}", &window_id, event);
         run_event_loop_task(event_loop, move || {
-            self.js_engine.handle_window_event(window_id, event);
+            handle_window_event(window_id, event);
             self.execute_pending_jobs();
         });
     }
@@ -219,20 +334,72 @@ impl ApplicationHandler<AppEventPayload> for WinitApp {
         event: DeviceEvent,
     ) {
         // debug!("onDeviceEvent: {:?}", event);
+        let _ = device_id;
+        run_event_loop_task(event_loop, move || {
+            handle_device_event(event);
+            self.execute_pending_jobs();
+        });
+    }
+
+    /// Sent on memory pressure, e.g. onTrimMemory on android
+    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {
+        debug!("memory warning received, trimming caches");
         run_event_loop_task(event_loop, move || {
-            self.js_engine.handle_device_event(device_id, event);
+            crate::gc::handle_memory_pressure();
             self.execute_pending_jobs();
         });
     }
 }
 
-pub fn exit_app(_code: i32) -> Result<(), Error> {
+/// Whether the event loop is owned by host, see `embed`
+static EMBEDDED: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_embedded() {
+    EMBEDDED.store(true, Ordering::Relaxed);
+}
+
+thread_local! {
+    static SHUTDOWN_HOOKS: RefCell<Vec<(i32, Box<dyn FnOnce()>)>> = RefCell::new(Vec::new());
+}
+
+/// Register a hook called when app is shutting down, hooks with smaller order are called first.
+/// All hooks are called before workers, websockets, audios and localstorage are torn down.
+pub fn add_shutdown_hook<F: FnOnce() + 'static>(order: i32, hook: F) {
+    SHUTDOWN_HOOKS.with_borrow_mut(|hooks| {
+        hooks.push((order, Box::new(hook)));
+    });
+}
+
+/// Request to quit app, js before-quit handlers are called first and may cancel quitting
+pub fn exit_app(code: i32) -> Result<(), Error> {
+    if !process::handle_before_quit(code) {
+        shutdown_app(code);
+    }
+    Ok(())
+}
+
+/// Tear down and stop the event loop without calling before-quit handlers. When embedded, only
+/// deft state is torn down and the event loop is left to host.
+pub fn shutdown_app(_code: i32) {
     //TODO use code from parameter
+    let mut hooks = SHUTDOWN_HOOKS.with_borrow_mut(|hooks| std::mem::take(hooks));
+    hooks.sort_by_key(|(order, _)| *order);
+    for (_, hook) in hooks {
+        hook();
+    }
+    Service::stop_all();
+    #[cfg(feature = "websocket")]
+    crate::ext::ext_websocket::close_all_connections();
+    #[cfg(feature = "audio")]
+    crate::ext::ext_audio::stop_all_audios();
     localstorage::cleanup().unwrap();
+    crate::metrics::shutdown();
+    if EMBEDDED.load(Ordering::Relaxed) {
+        return;
+    }
     run_with_event_loop(|el| {
         el.exit();
     });
-    Ok(())
 }
 
 #[cfg(target_os = "android")]
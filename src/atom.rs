@@ -0,0 +1,164 @@
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+thread_local! {
+    static ATOMS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// Interned string for tag names, classes, attribute keys and style prop names. Atoms of equal
+/// strings share one allocation, so that they are compared and hashed by pointer. Atoms are
+/// interned per thread and removed once no longer referenced.
+#[derive(Clone)]
+pub struct Atom(Rc<str>);
+
+impl Atom {
+    pub fn new(value: &str) -> Self {
+        ATOMS.with_borrow_mut(|atoms| {
+            if let Some(atom) = atoms.get(value) {
+                return Self(atom.clone());
+            }
+            let atom: Rc<str> = Rc::from(value);
+            atoms.insert(atom.clone());
+            Self(atom)
+        })
+    }
+
+    /// Atom of value if it is interned, nothing could equal value otherwise
+    pub fn lookup(value: &str) -> Option<Self> {
+        ATOMS.with_borrow(|atoms| atoms.get(value).map(|atom| Self(atom.clone())))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for Atom {
+    fn drop(&mut self) {
+        // The other reference is held by the table
+        if Rc::strong_count(&self.0) == 2 {
+            let _ = ATOMS.try_with(|atoms| {
+                if let Ok(mut atoms) = atoms.try_borrow_mut() {
+                    atoms.remove(&*self.0);
+                }
+            });
+        }
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Atom {}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Atom {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for Atom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Rc::as_ptr(&self.0) as *const u8 as usize).hash(state);
+    }
+}
+
+impl Deref for Atom {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Atom {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Atom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&*self.0, f)
+    }
+}
+
+impl Debug for Atom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Default for Atom {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl From<&str> for Atom {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&String> for Atom {
+    fn from(value: &String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Atom {
+    fn from(value: String) -> Self {
+        Self::new(&value)
+    }
+}
+
+impl Serialize for Atom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::atom::Atom;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_atom() {
+        let a = Atom::new("button");
+        let b = Atom::from("button".to_string());
+        assert_eq!(a, b);
+        assert_ne!(a, Atom::new("label"));
+        assert_eq!(a, "button");
+        assert_eq!("button", &*a);
+        let set: HashSet<Atom> = [a.clone(), b].into_iter().collect();
+        assert_eq!(1, set.len());
+        assert!(set.contains(&Atom::new("button")));
+    }
+
+    #[test]
+    fn test_release() {
+        assert!(Atom::lookup("atom-release-test").is_none());
+        let a = Atom::new("atom-release-test");
+        let b = Atom::lookup("atom-release-test").unwrap();
+        drop(a);
+        assert_eq!(b, "atom-release-test");
+        drop(b);
+        assert!(Atom::lookup("atom-release-test").is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use crate as deft;
+use crate::js::JsError;
+use deft_macros::js_methods;
+use log::error;
+use quick_js::JsValue;
+use std::cell::RefCell;
+use winit::window::ImePurpose;
+
+thread_local! {
+    static PROVIDER: RefCell<Option<Box<dyn AutofillProvider>>> = RefCell::new(None);
+}
+
+/// Value of `autocomplete` attribute of text input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutofillHint {
+    Off,
+    Username,
+    Email,
+    CurrentPassword,
+    NewPassword,
+    OneTimeCode,
+    Other(String),
+}
+
+impl AutofillHint {
+    pub fn parse(value: &str) -> Option<Self> {
+        // Only the last token is the field name, e.g. "section-login username"
+        let name = value.split_whitespace().last()?.to_lowercase();
+        let hint = match name.as_str() {
+            "off" => AutofillHint::Off,
+            "username" => AutofillHint::Username,
+            "email" => AutofillHint::Email,
+            "current-password" => AutofillHint::CurrentPassword,
+            "new-password" => AutofillHint::NewPassword,
+            "one-time-code" => AutofillHint::OneTimeCode,
+            _ => AutofillHint::Other(name),
+        };
+        Some(hint)
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            AutofillHint::Off => "off",
+            AutofillHint::Username => "username",
+            AutofillHint::Email => "email",
+            AutofillHint::CurrentPassword => "current-password",
+            AutofillHint::NewPassword => "new-password",
+            AutofillHint::OneTimeCode => "one-time-code",
+            AutofillHint::Other(name) => name,
+        }
+    }
+
+    pub fn is_password(&self) -> bool {
+        matches!(self, AutofillHint::CurrentPassword | AutofillHint::NewPassword)
+    }
+
+    /// Hint passed to platform input method, so that system autofill could be offered
+    pub fn ime_purpose(&self) -> ImePurpose {
+        if self.is_password() {
+            ImePurpose::Password
+        } else {
+            ImePurpose::Normal
+        }
+    }
+}
+
+/// Source of autofill values on desktop, e.g. a password manager
+pub trait AutofillProvider {
+    /// Return value to fill into an empty input with hint when it gains focus
+    fn fill(&mut self, hint: &AutofillHint) -> Option<String>;
+}
+
+pub fn set_autofill_provider<P: AutofillProvider + 'static>(provider: P) {
+    PROVIDER.with_borrow_mut(|p| *p = Some(Box::new(provider)));
+}
+
+pub fn clear_autofill_provider() {
+    PROVIDER.with_borrow_mut(|p| *p = None);
+}
+
+pub fn request_autofill(hint: &AutofillHint) -> Option<String> {
+    if *hint == AutofillHint::Off {
+        return None;
+    }
+    // Provider is taken out so that it is able to access inputs while filling
+    let mut provider = PROVIDER.with_borrow_mut(|p| p.take())?;
+    let value = provider.fill(hint);
+    PROVIDER.with_borrow_mut(|p| {
+        if p.is_none() {
+            *p = Some(provider);
+        }
+    });
+    value
+}
+
+struct JsAutofillProvider {
+    callback: JsValue,
+}
+
+impl AutofillProvider for JsAutofillProvider {
+    fn fill(&mut self, hint: &AutofillHint) -> Option<String> {
+        let args = vec![JsValue::String(hint.name().to_string())];
+        match self.callback.call_as_function(args) {
+            Ok(JsValue::String(s)) => Some(s),
+            Ok(_) => None,
+            Err(e) => {
+                error!("autofill provider error: {:?}", e);
+                None
+            }
+        }
+    }
+}
+
+#[allow(nonstandard_style)]
+pub struct autofill {}
+
+#[js_methods]
+impl autofill {
+    /// Callback is called with autocomplete hint and returns a string or null
+    #[js_func]
+    pub fn set_provider(callback: JsValue) -> Result<(), JsError> {
+        match callback {
+            JsValue::Null | JsValue::Undefined => clear_autofill_provider(),
+            callback => set_autofill_provider(JsAutofillProvider { callback }),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::autofill::AutofillHint;
+
+    #[test]
+    fn test_parse_hint() {
+        assert_eq!(AutofillHint::parse("username"), Some(AutofillHint::Username));
+        assert_eq!(
+            AutofillHint::parse("section-login Current-Password"),
+            Some(AutofillHint::CurrentPassword)
+        );
+        assert_eq!(
+            AutofillHint::parse("tel"),
+            Some(AutofillHint::Other("tel".to_string()))
+        );
+        assert_eq!(AutofillHint::parse("  "), None);
+    }
+}
@@ -250,6 +250,9 @@ pub struct MouseDetail {
     pub window_y: f32,
     pub screen_x: f32,
     pub screen_y: f32,
+    /// Relative motion of mouse since last mousemove event, only reported in pointer lock mode
+    pub movement_x: f32,
+    pub movement_y: f32,
 }
 
 #[derive(Debug, Copy, Clone, Serialize)]
@@ -414,12 +417,25 @@ pub trait EventListener<T, E> {
     fn handle_event(&mut self, event: &mut T, ctx: &mut EventContext<E>);
 }
 
+/// Options of event listeners added by js
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct EventListenerOptions {
+    /// Remove the listener after it is called once
+    pub once: bool,
+    /// Default behaviors are not prevented by the listener
+    pub passive: bool,
+}
+
+js_deserialize!(EventListenerOptions);
+
 pub struct EventRegistration<E> {
     listeners: HashMap<String, Vec<(u32, Box<EventHandler<E>>)>>,
     next_listener_id: u32,
     typed_listeners:
         HashMap<TypeId, Vec<(u32, Box<dyn FnMut(&mut event::Event, &mut EventContext<E>)>)>>,
     listener_types: HashMap<u32, TypeId>,
+    listener_options: HashMap<u32, EventListenerOptions>,
 }
 
 impl<E> EventRegistration<E> {
@@ -429,6 +445,7 @@ impl<E> EventRegistration<E> {
             listeners: HashMap::new(),
             typed_listeners: HashMap::new(),
             listener_types: HashMap::new(),
+            listener_options: HashMap::new(),
         }
     }
 
@@ -461,7 +478,19 @@ impl<E> EventRegistration<E> {
         id
     }
 
+    pub fn set_listener_options(&mut self, id: u32, options: EventListenerOptions) {
+        if !self.listener_types.contains_key(&id) {
+            return;
+        }
+        if options == EventListenerOptions::default() {
+            self.listener_options.remove(&id);
+        } else {
+            self.listener_options.insert(id, options);
+        }
+    }
+
     pub fn unregister_event_listener(&mut self, id: u32) {
+        self.listener_options.remove(&id);
         let event_type_id = some_or_return!(self.listener_types.remove(&id));
         if let Some(listeners) = self.typed_listeners.get_mut(&event_type_id) {
             listeners.retain(|(i, _)| *i != id);
@@ -479,6 +508,7 @@ impl<E> EventRegistration<E> {
         event: &mut event::Event,
         ctx: &mut EventContext<E>,
     ) {
+        let mut expired = Vec::new();
         if let Some(listeners) = self.typed_listeners.get_mut(&event_type_id) {
             if event_type_id != event.event_type_id() {
                 log::error!(
@@ -487,10 +517,21 @@ impl<E> EventRegistration<E> {
                     event.event_type_id()
                 );
             }
-            for it in listeners {
-                (it.1)(event, ctx);
+            for (id, listener) in listeners {
+                let options = self.listener_options.get(id).copied().unwrap_or_default();
+                let prevent_default = ctx.prevent_default;
+                listener(event, ctx);
+                if options.passive {
+                    ctx.prevent_default = prevent_default;
+                }
+                if options.once {
+                    expired.push(*id);
+                }
             }
         }
+        for id in expired {
+            self.unregister_event_listener(id);
+        }
     }
 
     pub fn add_event_listener(&mut self, event_type: &str, handler: Box<EventHandler<E>>) -> u32 {
@@ -751,7 +792,7 @@ unsafe impl<P> Sync for UnsafeFnMut<P> {}
 
 #[cfg(test)]
 mod tests {
-    use crate::base::{EventContext, EventListener, EventRegistration};
+    use crate::base::{EventContext, EventListener, EventListenerOptions, EventRegistration};
     use log::debug;
     use std::cell::RefCell;
     use std::rc::Rc;
@@ -782,4 +823,34 @@ mod tests {
 
         assert_eq!(1, *value.borrow());
     }
+
+    #[test]
+    fn test_listener_options() {
+        struct MyEvent;
+        struct PreventingListener {
+            calls: Rc<RefCell<i32>>,
+        }
+        impl EventListener<MyEvent, ()> for PreventingListener {
+            fn handle_event(&mut self, _event: &mut MyEvent, ctx: &mut EventContext<()>) {
+                *self.calls.borrow_mut() += 1;
+                ctx.prevent_default = true;
+            }
+        }
+        let calls = Rc::new(RefCell::new(0));
+        let mut er: EventRegistration<()> = EventRegistration::new();
+        let id = er.register_event_listener(PreventingListener {
+            calls: calls.clone(),
+        });
+        let options = EventListenerOptions {
+            once: true,
+            passive: true,
+        };
+        er.set_listener_options(id, options);
+
+        let mut ctx = EventContext::new(());
+        er.emit(MyEvent, &mut ctx);
+        assert!(!ctx.prevent_default);
+        er.emit(MyEvent, &mut EventContext::new(()));
+        assert_eq!(1, *calls.borrow());
+    }
 }
@@ -0,0 +1,175 @@
+use crate as deft;
+use crate::app::AppEvent;
+use crate::element::Element;
+use crate::send_app_event;
+use deft_macros::js_func;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+thread_local! {
+    static BATCH: RefCell<BatchState> = RefCell::new(BatchState::default());
+}
+
+#[derive(Default)]
+struct BatchState {
+    /// Depth of nested batches started by `begin`
+    depth: u32,
+    /// Whether selector matching is deferred until the running event loop task ends
+    auto: bool,
+    /// Elements whose subtrees should match selectors again, keyed by element id
+    select_style: HashMap<u32, Element>,
+    /// Windows waiting for an update event
+    windows: HashSet<i32>,
+}
+
+/// Run callback with selector matching and window updates deferred, they are flushed once when
+/// the callback returns
+pub fn batch<R>(callback: impl FnOnce() -> R) -> R {
+    begin();
+    let result = callback();
+    end();
+    result
+}
+
+pub fn begin() {
+    BATCH.with_borrow_mut(|b| b.depth += 1);
+}
+
+/// End a batch, pending changes are flushed when the outermost batch ends
+pub fn end() {
+    let depth = BATCH.with_borrow_mut(|b| {
+        b.depth = b.depth.saturating_sub(1);
+        b.depth
+    });
+    if depth == 0 {
+        flush();
+    }
+}
+
+pub fn is_batching() -> bool {
+    BATCH.with_borrow(|b| b.depth > 0)
+}
+
+/// Run an event loop task with selector matching deferred, changes are flushed when the task
+/// ends so that code running outside of tasks always sees up-to-date styles
+pub(crate) fn auto_batch<R>(callback: impl FnOnce() -> R) -> R {
+    let nested = BATCH.with_borrow_mut(|b| mem::replace(&mut b.auto, true));
+    let result = callback();
+    if !nested {
+        BATCH.with_borrow_mut(|b| b.auto = false);
+        flush_auto();
+    }
+    result
+}
+
+/// Flush changes deferred by auto batching, changes in explicit batches are kept
+pub(crate) fn flush_auto() {
+    if !is_batching() {
+        flush_styles();
+    }
+}
+
+/// Returns false if selectors should be matched immediately
+pub(crate) fn defer_select_style(element: &Element) -> bool {
+    BATCH.with_borrow_mut(|b| {
+        if b.depth == 0 && !b.auto {
+            return false;
+        }
+        b.select_style.insert(element.get_eid(), element.clone());
+        true
+    })
+}
+
+/// Returns false if update event of window should be sent immediately
+pub(crate) fn defer_window_update(window_id: i32) -> bool {
+    BATCH.with_borrow_mut(|b| {
+        if b.depth == 0 {
+            return false;
+        }
+        b.windows.insert(window_id);
+        true
+    })
+}
+
+/// Match selectors for elements changed in batch, styles must be up to date before painting
+pub(crate) fn flush_styles() {
+    loop {
+        let elements = BATCH.with_borrow_mut(|b| mem::take(&mut b.select_style));
+        if elements.is_empty() {
+            break;
+        }
+        for element in elements.values() {
+            // Subtrees of pending ancestors are matched anyway
+            if !has_pending_ancestor(element, &elements) {
+                element.clone().update_select_style_recurse();
+            }
+        }
+    }
+}
+
+fn flush() {
+    flush_styles();
+    let windows = BATCH.with_borrow_mut(|b| mem::take(&mut b.windows));
+    for window_id in windows {
+        let _ = send_app_event(AppEvent::Update(window_id));
+    }
+}
+
+fn has_pending_ancestor(element: &Element, pending: &HashMap<u32, Element>) -> bool {
+    let mut parent = element.get_parent();
+    while let Some(p) = parent {
+        if pending.contains_key(&p.get_eid()) {
+            return true;
+        }
+        parent = p.get_parent();
+    }
+    false
+}
+
+#[js_func]
+pub fn batch_begin() {
+    begin();
+}
+
+#[js_func]
+pub fn batch_end() {
+    end();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batch::{auto_batch, batch, BATCH};
+    use crate::element::container::Container;
+    use crate::element::{Element, CSS_MANAGER};
+
+    fn is_pending(element: &Element) -> bool {
+        BATCH.with_borrow(|b| b.select_style.contains_key(&element.get_eid()))
+    }
+
+    #[test]
+    fn test_batch() {
+        CSS_MANAGER.with_borrow_mut(|cm| cm.add(".a { color: red }").unwrap());
+        let mut element = Element::create(Container::create);
+        batch(|| {
+            element.set_class("a".to_string());
+            assert!(is_pending(&element));
+            batch(|| element.set_class("".to_string()));
+            assert!(is_pending(&element));
+        });
+        assert!(!is_pending(&element));
+    }
+
+    #[test]
+    fn test_auto_batch() {
+        CSS_MANAGER.with_borrow_mut(|cm| cm.add(".b { color: red }").unwrap());
+        let mut element = Element::create(Container::create);
+        auto_batch(|| {
+            element.set_class("b".to_string());
+            assert!(is_pending(&element));
+        });
+        assert!(!is_pending(&element));
+        element.set_class("".to_string());
+        assert!(!is_pending(&element));
+    }
+}
@@ -0,0 +1,262 @@
+use skia_safe::{named_gamut, named_transfer_fn, ColorSpace};
+
+/// Max difference of primaries to be treated as the same gamut
+const PRIMARY_TOLERANCE: f32 = 0.01;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Gamut {
+    Srgb,
+    DisplayP3,
+    AdobeRgb,
+    Rec2020,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TransferFn {
+    Srgb,
+    TwoDotTwo,
+    Linear,
+    /// Perceptual quantizer of hdr content
+    Pq,
+    /// Hybrid log-gamma of hdr content
+    Hlg,
+}
+
+/// Xyz of red, green and blue primaries adapted to D50, same as named gamuts of skia
+const GAMUT_PRIMARIES: [(Gamut, [[f32; 3]; 3]); 4] = [
+    (
+        Gamut::Srgb,
+        [
+            [0.436066, 0.222488, 0.013916],
+            [0.385147, 0.716873, 0.097076],
+            [0.143066, 0.060608, 0.714096],
+        ],
+    ),
+    (
+        Gamut::DisplayP3,
+        [
+            [0.515102, 0.241182, -0.001049],
+            [0.291965, 0.692236, 0.041882],
+            [0.157153, 0.066583, 0.784130],
+        ],
+    ),
+    (
+        Gamut::AdobeRgb,
+        [
+            [0.609741, 0.311111, 0.019470],
+            [0.205276, 0.625671, 0.060867],
+            [0.149185, 0.063217, 0.744568],
+        ],
+    ),
+    (
+        Gamut::Rec2020,
+        [
+            [0.673459, 0.279033, -0.001931],
+            [0.165661, 0.675338, 0.029979],
+            [0.125100, 0.045629, 0.797162],
+        ],
+    ),
+];
+
+/// Color profile embedded in images, only matrix/TRC rgb profiles of well-known gamuts
+/// and cicp tags are recognized, others are treated as srgb.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ColorProfile {
+    pub gamut: Gamut,
+    pub transfer_fn: TransferFn,
+}
+
+impl ColorProfile {
+    pub fn from_icc(icc: &[u8]) -> Option<Self> {
+        if icc.get(16..20)? != b"RGB " {
+            return None;
+        }
+        if let Some(profile) = find_tag(icc, b"cicp").and_then(read_cicp) {
+            return Some(profile);
+        }
+        let primaries = [
+            read_xyz(find_tag(icc, b"rXYZ")?)?,
+            read_xyz(find_tag(icc, b"gXYZ")?)?,
+            read_xyz(find_tag(icc, b"bXYZ")?)?,
+        ];
+        let gamut = GAMUT_PRIMARIES.iter().find_map(|(gamut, expected)| {
+            let matched = expected.iter().zip(primaries.iter()).all(|(e, p)| {
+                e.iter()
+                    .zip(p.iter())
+                    .all(|(e, p)| (e - p).abs() < PRIMARY_TOLERANCE)
+            });
+            matched.then_some(*gamut)
+        })?;
+        let transfer_fn = read_transfer_fn(find_tag(icc, b"rTRC")?)?;
+        Some(Self { gamut, transfer_fn })
+    }
+
+    pub fn to_color_space(&self) -> ColorSpace {
+        let gamut = match self.gamut {
+            Gamut::Srgb => named_gamut::SRGB,
+            Gamut::DisplayP3 => named_gamut::DISPLAY_P3,
+            Gamut::AdobeRgb => named_gamut::ADOBE_RGB,
+            Gamut::Rec2020 => named_gamut::REC2020,
+        };
+        let transfer_fn = match self.transfer_fn {
+            TransferFn::Srgb => named_transfer_fn::SRGB,
+            TransferFn::TwoDotTwo => named_transfer_fn::TWO_DOT_TWO,
+            TransferFn::Linear => named_transfer_fn::LINEAR,
+            TransferFn::Pq => named_transfer_fn::PQ,
+            TransferFn::Hlg => named_transfer_fn::HLG,
+        };
+        ColorSpace::new_rgb(&transfer_fn, &gamut)
+    }
+}
+
+/// Color space of image with embedded icc profile, srgb if missing or not recognized
+pub fn icc_to_color_space(icc: Option<&[u8]>) -> ColorSpace {
+    icc.and_then(ColorProfile::from_icc)
+        .map(|p| p.to_color_space())
+        .unwrap_or_else(ColorSpace::new_srgb)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_s15_fixed16(data: &[u8], offset: usize) -> Option<f32> {
+    Some(read_u32(data, offset)? as i32 as f32 / 65536.0)
+}
+
+fn find_tag<'a>(icc: &'a [u8], signature: &[u8; 4]) -> Option<&'a [u8]> {
+    let count = read_u32(icc, 128)? as usize;
+    (0..count).find_map(|i| {
+        let entry = 132 + i * 12;
+        if icc.get(entry..entry + 4)? != signature {
+            return None;
+        }
+        let offset = read_u32(icc, entry + 4)? as usize;
+        let size = read_u32(icc, entry + 8)? as usize;
+        icc.get(offset..offset.checked_add(size)?)
+    })
+}
+
+fn read_xyz(tag: &[u8]) -> Option<[f32; 3]> {
+    if tag.get(0..4)? != b"XYZ " {
+        return None;
+    }
+    Some([
+        read_s15_fixed16(tag, 8)?,
+        read_s15_fixed16(tag, 12)?,
+        read_s15_fixed16(tag, 16)?,
+    ])
+}
+
+/// Coding-independent code points of ITU-T H.273, used by hdr images
+fn read_cicp(tag: &[u8]) -> Option<ColorProfile> {
+    if tag.get(0..4)? != b"cicp" {
+        return None;
+    }
+    let gamut = match *tag.get(8)? {
+        1 => Gamut::Srgb,
+        9 => Gamut::Rec2020,
+        12 => Gamut::DisplayP3,
+        _ => return None,
+    };
+    let transfer_fn = match *tag.get(9)? {
+        1 | 6 | 13 => TransferFn::Srgb,
+        8 => TransferFn::Linear,
+        16 => TransferFn::Pq,
+        18 => TransferFn::Hlg,
+        _ => return None,
+    };
+    Some(ColorProfile { gamut, transfer_fn })
+}
+
+fn read_transfer_fn(tag: &[u8]) -> Option<TransferFn> {
+    let gamma = match tag.get(0..4)? {
+        b"curv" => match read_u32(tag, 8)? {
+            0 => 1.0,
+            1 => u16::from_be_bytes(tag.get(12..14)?.try_into().ok()?) as f32 / 256.0,
+            // Sampled curves are mostly srgb
+            _ => return Some(TransferFn::Srgb),
+        },
+        b"para" => {
+            let function_type = u16::from_be_bytes(tag.get(8..10)?.try_into().ok()?);
+            if function_type != 0 {
+                // Gamma with linear segment near black
+                return Some(TransferFn::Srgb);
+            }
+            read_s15_fixed16(tag, 12)?
+        }
+        _ => return None,
+    };
+    if (gamma - 1.0).abs() < 0.01 {
+        Some(TransferFn::Linear)
+    } else if (gamma - 2.2).abs() < 0.05 {
+        Some(TransferFn::TwoDotTwo)
+    } else {
+        Some(TransferFn::Srgb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color_profile::{ColorProfile, Gamut, TransferFn, GAMUT_PRIMARIES};
+
+    const UNKNOWN_PRIMARIES: [[f32; 3]; 3] = [[0.5, 0.5, 0.5]; 3];
+
+    fn fixed(v: f32) -> [u8; 4] {
+        ((v * 65536.0).round() as i32).to_be_bytes()
+    }
+
+    fn build_icc(primaries: &[[f32; 3]; 3], trc: &[u8], cicp: Option<[u8; 4]>) -> Vec<u8> {
+        let mut tags: Vec<(&[u8; 4], Vec<u8>)> = Vec::new();
+        for (sig, xyz) in [b"rXYZ", b"gXYZ", b"bXYZ"].into_iter().zip(primaries) {
+            let mut data = b"XYZ \0\0\0\0".to_vec();
+            xyz.iter().for_each(|v| data.extend_from_slice(&fixed(*v)));
+            tags.push((sig, data));
+        }
+        tags.push((b"rTRC", trc.to_vec()));
+        if let Some(cicp) = cicp {
+            let mut data = b"cicp\0\0\0\0".to_vec();
+            data.extend_from_slice(&cicp);
+            tags.push((b"cicp", data));
+        }
+        let mut icc = vec![0u8; 128];
+        icc[16..20].copy_from_slice(b"RGB ");
+        icc.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+        let mut offset = 132 + tags.len() * 12;
+        for (sig, data) in &tags {
+            icc.extend_from_slice(*sig);
+            icc.extend_from_slice(&(offset as u32).to_be_bytes());
+            icc.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            offset += data.len();
+        }
+        for (_, data) in &tags {
+            icc.extend_from_slice(data);
+        }
+        icc
+    }
+
+    #[test]
+    fn test_parse_icc() {
+        let p3 = &GAMUT_PRIMARIES[1].1;
+        let para = b"para\0\0\0\0\0\x03\0\0\0\x02\x66\x66";
+        let profile = ColorProfile::from_icc(&build_icc(p3, para, None)).unwrap();
+        assert_eq!(Gamut::DisplayP3, profile.gamut);
+        assert_eq!(TransferFn::Srgb, profile.transfer_fn);
+
+        let adobe = &GAMUT_PRIMARIES[2].1;
+        let curv = b"curv\0\0\0\0\0\0\0\x01\x02\x33";
+        let profile = ColorProfile::from_icc(&build_icc(adobe, curv, None)).unwrap();
+        assert_eq!(Gamut::AdobeRgb, profile.gamut);
+        assert_eq!(TransferFn::TwoDotTwo, profile.transfer_fn);
+
+        let icc = build_icc(&UNKNOWN_PRIMARIES, curv, None);
+        assert_eq!(None, ColorProfile::from_icc(&icc));
+
+        // Rec2020 with PQ
+        let icc = build_icc(&UNKNOWN_PRIMARIES, curv, Some([9, 16, 0, 1]));
+        let profile = ColorProfile::from_icc(&icc).unwrap();
+        assert_eq!(Gamut::Rec2020, profile.gamut);
+        assert_eq!(TransferFn::Pq, profile.transfer_fn);
+    }
+}
@@ -0,0 +1,259 @@
+use crate as deft;
+use crate::data_dir::get_data_path;
+use crate::js::JsError;
+use crate::timer::{set_interval, TimerHandle};
+use deft_macros::js_methods;
+use log::error;
+use quick_js::JsValue;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Interval to check whether config files are edited by others
+const WATCH_INTERVAL: u64 = 1000;
+
+pub const DEFAULT_CONFIG_NAME: &str = "config";
+
+type ChangeListener = Box<dyn Fn(&str, &[String])>;
+
+thread_local! {
+    static CONFIGS: RefCell<HashMap<String, Config>> = RefCell::new(HashMap::new());
+    static LISTENERS: RefCell<Vec<ChangeListener>> = RefCell::new(Vec::new());
+    static WATCH_TIMER: RefCell<Option<TimerHandle>> = RefCell::new(None);
+}
+
+/// Json config file in data dir, missing keys are filled with defaults
+struct Config {
+    path: PathBuf,
+    defaults: Map<String, Value>,
+    values: Map<String, Value>,
+    /// Modified time after last read or write, used to detect external edits
+    modified: Option<SystemTime>,
+}
+
+impl Config {
+    fn read(path: PathBuf, defaults: Map<String, Value>) -> Self {
+        let mut config = Self {
+            path,
+            values: defaults.clone(),
+            defaults,
+            modified: None,
+        };
+        config.reload();
+        config
+    }
+
+    /// Read file again, return keys with changed values
+    fn reload(&mut self) -> Vec<String> {
+        self.modified = modified_time(&self.path);
+        let stored = match fs::read_to_string(&self.path) {
+            Ok(content) => match serde_json::from_str::<Map<String, Value>>(&content) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("invalid config file {:?}: {}", self.path, e);
+                    return Vec::new();
+                }
+            },
+            Err(_) => Map::new(),
+        };
+        let values = merge_defaults(&self.defaults, stored);
+        let changed = changed_keys(&self.values, &values);
+        self.values = values;
+        changed
+    }
+
+    /// Write to a temporary file then rename it, so the file is never half written
+    fn save(&mut self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(&self.values)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &self.path)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+}
+
+fn modified_time(path: &PathBuf) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn merge_defaults(
+    defaults: &Map<String, Value>,
+    mut values: Map<String, Value>,
+) -> Map<String, Value> {
+    for (k, v) in defaults {
+        if !values.contains_key(k) {
+            values.insert(k.clone(), v.clone());
+        }
+    }
+    values
+}
+
+fn changed_keys(old: &Map<String, Value>, new: &Map<String, Value>) -> Vec<String> {
+    let mut keys: Vec<String> = new
+        .iter()
+        .filter(|(k, v)| old.get(*k) != Some(*v))
+        .map(|(k, _)| k.clone())
+        .collect();
+    keys.extend(old.keys().filter(|k| !new.contains_key(*k)).cloned());
+    keys
+}
+
+fn config_path(name: &str) -> PathBuf {
+    get_data_path(&format!("{}.json", name))
+}
+
+/// Load config named `name` from data dir, reloading it if loaded already.
+/// Returns all values, keys missing in file take values of defaults.
+pub fn load_config(name: &str, defaults: Map<String, Value>) -> Map<String, Value> {
+    let config = Config::read(config_path(name), defaults);
+    let values = config.values.clone();
+    CONFIGS.with_borrow_mut(|m| m.insert(name.to_string(), config));
+    start_watching();
+    values
+}
+
+pub fn get_config_value(name: &str, key: &str) -> Option<Value> {
+    CONFIGS.with_borrow(|m| m.get(name)?.values.get(key).cloned())
+}
+
+/// Set value and persist config, listeners are notified if value changed
+pub fn set_config_value(name: &str, key: &str, value: Value) -> io::Result<()> {
+    let changed = CONFIGS.with_borrow_mut(|m| {
+        let config = m
+            .get_mut(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "config not loaded"))?;
+        if config.values.get(key) == Some(&value) {
+            return Ok(false);
+        }
+        config.values.insert(key.to_string(), value);
+        config.save()?;
+        Ok::<bool, io::Error>(true)
+    })?;
+    if changed {
+        notify_change(name, &[key.to_string()]);
+    }
+    Ok(())
+}
+
+/// Typed getter, returns None if missing or not convertible
+pub fn get_config<T: DeserializeOwned>(name: &str, key: &str) -> Option<T> {
+    serde_json::from_value(get_config_value(name, key)?).ok()
+}
+
+/// Typed setter
+pub fn set_config<T: Serialize>(name: &str, key: &str, value: T) -> io::Result<()> {
+    set_config_value(name, key, serde_json::to_value(value)?)
+}
+
+/// Listen changes of any config, called with name of config and changed keys
+pub fn add_config_change_listener<F: Fn(&str, &[String]) + 'static>(listener: F) {
+    LISTENERS.with_borrow_mut(|l| l.push(Box::new(listener)));
+}
+
+fn notify_change(name: &str, keys: &[String]) {
+    // Listeners are taken out so that they could access configs
+    let listeners = LISTENERS.with_borrow_mut(|l| std::mem::take(l));
+    for listener in &listeners {
+        listener(name, keys);
+    }
+    LISTENERS.with_borrow_mut(|l| {
+        let mut added = std::mem::replace(l, listeners);
+        l.append(&mut added);
+    });
+}
+
+fn start_watching() {
+    WATCH_TIMER.with_borrow_mut(|timer| {
+        if timer.is_none() {
+            *timer = Some(set_interval(check_external_changes, WATCH_INTERVAL));
+        }
+    });
+}
+
+fn check_external_changes() {
+    let changes = CONFIGS.with_borrow_mut(|m| {
+        let mut changes = Vec::new();
+        for (name, config) in m.iter_mut() {
+            if modified_time(&config.path) != config.modified {
+                let keys = config.reload();
+                if !keys.is_empty() {
+                    changes.push((name.clone(), keys));
+                }
+            }
+        }
+        changes
+    });
+    for (name, keys) in changes {
+        notify_change(&name, &keys);
+    }
+}
+
+#[allow(nonstandard_style)]
+pub struct config {}
+
+/// Values are passed to js as json strings to keep their types
+#[js_methods]
+impl config {
+    #[js_func]
+    pub fn load(name: String, defaults: String) -> Result<String, JsError> {
+        let defaults: Map<String, Value> = serde_json::from_str(&defaults)?;
+        let values = load_config(&name, defaults);
+        Ok(serde_json::to_string(&values)?)
+    }
+
+    #[js_func]
+    pub fn get(name: String, key: String) -> Result<Option<String>, JsError> {
+        match get_config_value(&name, &key) {
+            Some(v) => Ok(Some(serde_json::to_string(&v)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[js_func]
+    pub fn set(name: String, key: String, value: String) -> Result<(), JsError> {
+        let value: Value = serde_json::from_str(&value)?;
+        set_config_value(&name, &key, value)?;
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn add_change_listener(listener: JsValue) {
+        add_config_change_listener(move |name, keys| {
+            let keys = keys.iter().map(|k| JsValue::String(k.clone())).collect();
+            let args = vec![JsValue::String(name.to_string()), JsValue::Array(keys)];
+            if let Err(e) = listener.call_as_function(args) {
+                error!("config change listener error: {:?}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{changed_keys, merge_defaults};
+    use serde_json::{json, Map, Value};
+
+    fn to_map(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_merge_defaults() {
+        let defaults = to_map(json!({"theme": "light", "fontSize": 14}));
+        let stored = to_map(json!({"theme": "dark"}));
+        let values = merge_defaults(&defaults, stored);
+        assert_eq!(to_map(json!({"theme": "dark", "fontSize": 14})), values);
+        let changed = changed_keys(&defaults, &values);
+        assert_eq!(vec!["theme".to_string()], changed);
+    }
+}
@@ -7,6 +7,9 @@ pub const ENV_KEY: &str = "APP_DATA_PATH";
 pub fn get_data_path(name: &str) -> PathBuf {
     let data_root = if let Ok(v) = env::var(ENV_KEY) {
         PathBuf::from_str(&v).unwrap()
+    } else if cfg!(emscripten_platform) {
+        // In-memory filesystem of emscripten, localstorage is kept in the page instead
+        PathBuf::from("/data")
     } else {
         let my_path = env::current_exe().unwrap();
         my_path.parent().unwrap().parent().unwrap().join("data")
@@ -0,0 +1,245 @@
+use crate as deft;
+use crate::inspector;
+use crate::js::JsError;
+use crate::metrics;
+use crate::metrics::{MetricRecord, MetricsExporter};
+use anyhow::anyhow;
+use deft_macros::js_methods;
+use log::{debug, error, info};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message, WebSocket};
+
+const CLIENT_HTML: &str = include_str!("devtools/client.html");
+const MAX_BUFFERED_LOGS: usize = 1000;
+const MAX_BUFFERED_RECORDS: usize = 10000;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+static STATE: LazyLock<Mutex<DevtoolsState>> = LazyLock::new(|| {
+    Mutex::new(DevtoolsState {
+        logs: VecDeque::new(),
+        pending_log: String::new(),
+        timeline: VecDeque::new(),
+        clients: Vec::new(),
+    })
+});
+
+struct DevtoolsState {
+    logs: VecDeque<Value>,
+    /// Console output not terminated by newline yet
+    pending_log: String,
+    timeline: VecDeque<Value>,
+    /// Senders of events to connected clients
+    clients: Vec<Sender<String>>,
+}
+
+impl DevtoolsState {
+    fn broadcast(&mut self, event: &str, params: Value) {
+        let msg = json!({ "event": event, "params": params }).to_string();
+        self.clients.retain(|c| c.send(msg.clone()).is_ok());
+    }
+}
+
+/// Start devtools server in background, returns url of the page like
+/// `http://<address>/?token=<token>`, open it in a browser to inspect the app
+pub fn start_server(address: &str) -> Result<String, anyhow::Error> {
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return Err(anyhow!("devtools server already started"));
+    }
+    let started =
+        inspector::generate_token().and_then(|token| Ok((TcpListener::bind(address)?, token)));
+    let (listener, token) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            STARTED.store(false, Ordering::Relaxed);
+            return Err(e);
+        }
+    };
+    let url = format!("http://{}/?token={}", address, token);
+    info!("devtools listening on {}", url);
+    metrics::add_exporter(TimelineExporter {});
+    thread::Builder::new()
+        .name("devtools".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let token = token.clone();
+                        let _ = thread::Builder::new()
+                            .name("devtools-client".to_string())
+                            .spawn(move || handle_connection(stream, &token));
+                    }
+                    Err(e) => error!("devtools accept error: {:?}", e),
+                }
+            }
+        })?;
+    Ok(url)
+}
+
+pub fn is_started() -> bool {
+    STARTED.load(Ordering::Relaxed)
+}
+
+/// Collect console output, lines are sent to clients as console events
+pub fn write_console(text: &str) {
+    if !is_started() {
+        return;
+    }
+    let mut state = STATE.lock().unwrap();
+    state.pending_log.push_str(text);
+    while let Some(idx) = state.pending_log.find('\n') {
+        let line: String = state.pending_log.drain(..=idx).collect();
+        let entry = json!({
+            "message": line.trim_end_matches('\n'),
+            "time": now_millis(),
+        });
+        if state.logs.len() >= MAX_BUFFERED_LOGS {
+            state.logs.pop_front();
+        }
+        state.logs.push_back(entry.clone());
+        state.broadcast("console", entry);
+    }
+}
+
+struct TimelineExporter {}
+
+impl MetricsExporter for TimelineExporter {
+    fn export(&mut self, records: &[MetricRecord]) {
+        let records: Vec<Value> = records.iter().map(record_to_json).collect();
+        let mut state = STATE.lock().unwrap();
+        for r in &records {
+            if state.timeline.len() >= MAX_BUFFERED_RECORDS {
+                state.timeline.pop_front();
+            }
+            state.timeline.push_back(r.clone());
+        }
+        state.broadcast("timeline", Value::Array(records));
+    }
+}
+
+fn record_to_json(record: &MetricRecord) -> Value {
+    serde_json::to_value(record).unwrap_or(Value::Null)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn handle_connection(stream: TcpStream, token: &str) {
+    let mut buf = [0u8; 2048];
+    let len = match stream.peek(&mut buf) {
+        Ok(len) => len,
+        Err(e) => {
+            debug!("devtools connection error: {:?}", e);
+            return;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..len]);
+    if head.to_lowercase().contains("upgrade: websocket") {
+        handle_websocket(stream, token);
+    } else if inspector::is_authorized_http(&head, token) {
+        inspector::serve_html(stream, CLIENT_HTML);
+    } else {
+        inspector::reject_http(stream);
+    }
+}
+
+fn handle_websocket(stream: TcpStream, token: &str) {
+    let mut ws = match inspector::accept_websocket(stream, token) {
+        Ok(ws) => ws,
+        Err(e) => {
+            debug!("devtools handshake error: {:?}", e);
+            return;
+        }
+    };
+    // Read with timeout so that events could be sent between reads
+    if let Err(e) = ws.get_ref().set_read_timeout(Some(POLL_INTERVAL)) {
+        error!("devtools set timeout error: {:?}", e);
+        return;
+    }
+    let (sender, receiver) = channel();
+    STATE.lock().unwrap().clients.push(sender);
+    loop {
+        if !flush_events(&mut ws, &receiver) {
+            break;
+        }
+        match ws.read() {
+            Ok(Message::Text(text)) => {
+                let response = handle_message(&text);
+                if ws.send(Message::Text(response)).is_err() {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(WsError::Io(e))
+                if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {}
+            Err(e) => {
+                debug!("devtools client closed: {:?}", e);
+                break;
+            }
+        }
+    }
+}
+
+fn flush_events(ws: &mut WebSocket<TcpStream>, receiver: &Receiver<String>) -> bool {
+    while let Ok(msg) = receiver.try_recv() {
+        if ws.send(Message::Text(msg)).is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Handle a request like `{"id": 1, "method": "getElementTree", "params": {}}`
+fn handle_message(text: &str) -> String {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(e) => return json!({ "error": format!("invalid request: {}", e) }).to_string(),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "getConsoleLogs" => {
+            let state = STATE.lock().unwrap();
+            Ok(json!({ "logs": state.logs.iter().collect::<Vec<_>>() }))
+        }
+        "getTimeline" => {
+            let state = STATE.lock().unwrap();
+            Ok(json!({ "records": state.timeline.iter().collect::<Vec<_>>() }))
+        }
+        // Methods of elements are handled by inspector
+        _ => inspector::handle_method(method, params)
+            .unwrap_or_else(|| Err(format!("unknown method: {}", method))),
+    };
+    match result {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(error) => json!({ "id": id, "error": error }),
+    }
+    .to_string()
+}
+
+#[allow(nonstandard_style)]
+pub struct devtools {}
+
+#[js_methods]
+impl devtools {
+    /// Start devtools server, e.g. "127.0.0.1:9222", returns url of the page with token
+    #[js_func]
+    pub fn start(address: String) -> Result<String, JsError> {
+        Ok(start_server(&address)?)
+    }
+}
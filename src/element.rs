@@ -11,24 +11,33 @@ use bitflags::bitflags;
 use deft_macros::{js_methods, mrc_object};
 use quick_js::JsValue;
 use serde::{Deserialize, Serialize};
-use winit::window::{Cursor, CursorIcon};
-use yoga::{Direction, StyleUnit};
+use winit::window::{Cursor, CursorIcon, ImePurpose};
+use yoga::{Direction, MeasureMode, PositionType, Size, StyleUnit};
 
+use crate::atom::Atom;
 use crate::base::{
-    BoxJsEventListenerFactory, EventContext, EventListener, EventRegistration, JsEvent, Rect,
+    BoxJsEventListenerFactory, EventContext, EventListener, EventListenerOptions,
+    EventRegistration, JsEvent, Rect,
 };
+use crate::batch;
 use crate::element::button::Button;
 use crate::element::container::Container;
+use crate::element::external_texture::ExternalTexture;
 use crate::element::image::Image;
 use crate::element::scroll::{Scroll, ScrollBarStrategy};
 use crate::event::{
     BlurEventListener, BoundsChangeEvent, BoundsChangeEventListener, ClickEventListener,
-    ContextMenuEventListener, DragOverEventListener, DragStartEventListener, DropEventListener,
-    DroppedFileEventListener, Event, FocusEventListener, FocusShiftEventListener,
-    HoveredFileEventListener, KeyDownEventListener, KeyUpEventListener, MouseDownEvent,
-    MouseDownEventListener, MouseEnterEvent, MouseEnterEventListener, MouseLeaveEvent,
-    MouseLeaveEventListener, MouseMoveEventListener, MouseUpEventListener, MouseWheelEventListener,
-    ScrollEvent, ScrollEventListener, TextChangeEventListener, TextUpdateEventListener,
+    ContextMenuEventListener, CustomEvent, CustomEventListener, DragOverEventListener,
+    DragStartEventListener, DropEventListener, DroppedFileEventListener, Event, FocusEventListener, FocusShiftEventListener,
+    FullscreenChangeEventListener, GesturePanEventListener, GestureRotateEventListener,
+    GestureZoomEventListener, HoveredFileEventListener, KeyDownEventListener,
+    KeyUpEventListener, MouseDownEvent,
+    MouseDownEventListener, MouseEnterEventListener, MouseLeaveEventListener,
+    MouseMoveEventListener, MouseUpEventListener, MouseWheelEventListener,
+    PointerLockChangeEventListener, ReachBottomEvent,
+    ReachBottomEventListener, ReachTopEvent, ReachTopEventListener, ScrollEndEvent,
+    ScrollEndEventListener, ScrollEvent, ScrollEventListener, TextChangeEventListener,
+    TextUpdateEventListener,
     TouchCancelEventListener, TouchEndEventListener, TouchMoveEventListener, TouchStartEvent,
     TouchStartEventListener,
 };
@@ -37,10 +46,12 @@ use crate::mrc::Mrc;
 use crate::number::DeNan;
 use crate::resource_table::ResourceTable;
 use crate::style::{FixedStyleProp, ResolvedStyleProp, StyleNode, StylePropKey, StylePropVal};
+use crate::window::element_index::{element_path, ElementIndex, ElementPathEntry};
+use crate::window::pop_out::{self, PopOutOptions};
 use crate::window::{Window, WindowHandle};
 use crate::{
     base, bind_js_event_listener, js_auto_upgrade, js_deserialize, js_serialize, js_value,
-    ok_or_return,
+    ok_or_return, some_or_continue,
 };
 
 pub mod body;
@@ -48,32 +59,41 @@ pub mod button;
 pub mod checkbox;
 pub mod common;
 pub mod container;
+pub mod custom;
+pub mod debug;
 mod edit_history;
+pub mod external_texture;
 mod font_manager;
 pub mod image;
 pub mod label;
 pub mod paragraph;
 pub mod radio;
 pub mod richtext;
+pub mod richtextedit;
 pub mod scroll;
 pub mod select;
 pub mod text;
 pub mod textedit;
 pub mod textinput;
 pub mod util;
+#[cfg(feature = "video")]
+pub mod video;
 
 use crate as deft;
 use crate::computed::ComputedValue;
 use crate::element::body::Body;
 use crate::element::checkbox::Checkbox;
-use crate::element::common::scrollable::Scrollable;
+use crate::element::debug::{LayoutDump, StyleTrace};
+use crate::element::common::fling::ScrollPhysics;
+use crate::element::common::scrollable::{ScrollState, Scrollable, SMOOTH_SCROLL_DURATION};
 use crate::element::label::Label;
 use crate::element::radio::{Radio, RadioGroup};
 use crate::element::richtext::RichText;
+use crate::element::richtextedit::RichTextEdit;
 use crate::element::select::Select;
 use crate::element::textedit::TextEdit;
 use crate::element::textinput::TextInput;
-use crate::element::util::is_form_event;
+use crate::element::util::{is_form_event, parse_generated_content};
 use crate::event::event_emitter::EventEmitter;
 use crate::js::JsError;
 use crate::paint::MatrixCalculator;
@@ -81,7 +101,8 @@ use crate::render::RenderFn;
 use crate::state::StateMutRef;
 use crate::style::border_path::BorderPath;
 use crate::style::css_manager::CssManager;
-use crate::style::length::LengthContext;
+use crate::style::length::{Length, LengthContext, LengthOrPercent};
+use crate::style::list_style::ListStyleType;
 use crate::style::style_vars::StyleVars;
 use crate::style::styles::Styles;
 use crate::style_list::StyleList;
@@ -108,6 +129,11 @@ struct ElementJsContext {
     context: JsValue,
 }
 
+/// Strong reference to the js wrapper of an element shown without a js parent
+struct ElementJsRoot {
+    context: JsValue,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScrollByOption {
     x: f32,
@@ -116,14 +142,36 @@ pub struct ScrollByOption {
 js_serialize!(ScrollByOption);
 js_deserialize!(ScrollByOption);
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrollToOption {
+    left: Option<f32>,
+    top: Option<f32>,
+    /// "smooth" to animate, otherwise jump
+    behavior: Option<String>,
+}
+js_deserialize!(ScrollToOption);
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct DispatchEventOption {
+    bubbles: Option<bool>,
+}
+js_deserialize!(DispatchEventOption);
+
 //TODO rename
 pub trait ViewEvent {
     fn allow_bubbles(&self) -> bool;
 }
 
 pub fn register_component<T: ElementBackend>(tag: &str) {
+    register_component_creator(tag, |ele| Box::new(T::create(ele)));
+}
+
+pub fn register_component_creator<F: FnMut(&mut Element) -> Box<dyn ElementBackend> + 'static>(
+    tag: &str,
+    creator: F,
+) {
     let tag = tag.to_string();
-    let bc: BackendCreator = Box::new(move |ele| Box::new(T::create(ele)));
+    let bc: BackendCreator = Box::new(creator);
     ELEMENT_CREATORS.with_borrow_mut(move |map| {
         map.insert(tag, bc);
     })
@@ -136,15 +184,19 @@ pub fn init_base_components() {
     register_component::<Checkbox>("checkbox");
     register_component::<Radio>("radio");
     register_component::<Image>("image");
+    register_component::<ExternalTexture>("external-texture");
     register_component::<Label>("label");
     register_component::<TextInput>("text-input");
     register_component::<TextEdit>("text-edit");
     register_component::<Body>("body");
     register_component::<RadioGroup>("radio-group");
     register_component::<RichText>("rich-text");
+    register_component::<RichTextEdit>("rich-text-edit");
     register_component::<Select>("select");
     register_component::<Container>("dialog");
     register_component::<Container>("dialog-title");
+    #[cfg(feature = "video")]
+    register_component::<video::Video>("video");
 }
 
 #[js_methods]
@@ -158,6 +210,28 @@ impl Element {
         let ele_weak = ele.inner.as_weak();
         // let bk = backend(ele_cp);
         ele.backend = Mrc::new(backend_creator(&mut ele));
+        if ele.backend.is_measurable() {
+            ele.style
+                .yoga_node
+                .set_measure_func(ele.as_weak(), |el, params| {
+                    if let Ok(el) = el.upgrade() {
+                        let max_width = match params.width_mode {
+                            MeasureMode::Undefined => f32::INFINITY,
+                            _ => params.width,
+                        };
+                        let max_height = match params.height_mode {
+                            MeasureMode::Undefined => f32::INFINITY,
+                            _ => params.height,
+                        };
+                        let (width, height) = el.get_backend().measure(max_width, max_height);
+                        return Size { width, height };
+                    }
+                    Size {
+                        width: 0.0,
+                        height: 0.0,
+                    }
+                });
+        }
         ele.style.on_changed = Some(Box::new(move |key| {
             if let Ok(mut inner) = ele_weak.upgrade() {
                 inner.backend.handle_style_changed(key);
@@ -168,15 +242,26 @@ impl Element {
             let el = ele.as_weak();
             ele.scrollable.horizontal_bar.set_scroll_callback(move |_| {
                 let mut el = ok_or_return!(el.upgrade());
-                el.mark_dirty(false);
+                el.mark_scroll_dirty();
                 el.emit_scroll_event();
+                el.schedule_scroll_end_event();
             });
         }
         {
             let el = ele.as_weak();
             ele.scrollable.vertical_bar.set_scroll_callback(move |_| {
                 let mut el = ok_or_return!(el.upgrade());
-                el.mark_dirty(false);
+                el.mark_scroll_dirty();
+                el.emit_scroll_event();
+                el.emit_reach_edge_events();
+                el.schedule_scroll_end_event();
+            });
+        }
+        {
+            let el = ele.as_weak();
+            ele.scrollable.set_overscroll_callback(move || {
+                let mut el = ok_or_return!(el.upgrade());
+                el.mark_scroll_dirty();
                 el.emit_scroll_event();
             });
         }
@@ -204,13 +289,34 @@ impl Element {
         classes.join(" ")
     }
 
+    #[js_func]
+    pub fn get_tag(&self) -> String {
+        self.tag.to_string()
+    }
+
+    /// Reset inline styles, classes, attributes, scroll offsets and backend state of an element
+    /// recycled by `detachForReuse`, so that it looks like a newly created element of its tag
+    #[js_func]
+    pub fn reset_for_reuse(&mut self) {
+        self.update_style(JsValue::Object(HashMap::new()), true);
+        self.set_hover_style(JsValue::Object(HashMap::new()));
+        self.set_class(String::new());
+        let keys: Vec<Atom> = self.attributes.keys().cloned().collect();
+        for key in keys {
+            self.remove_attribute(key.to_string());
+        }
+        self.set_scroll_left(0.0);
+        self.set_scroll_top(0.0);
+        self.get_backend_mut().reset();
+    }
+
     #[js_func]
     pub fn set_class(&mut self, class: String) {
         let old_classes = mem::take(&mut self.classes);
         for c in class.split(" ") {
             let c = c.trim();
             if !c.is_empty() {
-                self.classes.insert(c.to_string());
+                self.classes.insert(Atom::new(c));
             }
         }
         let need_update = CSS_MANAGER.with_borrow_mut(|cm| {
@@ -225,17 +331,23 @@ impl Element {
                     .is_some()
         });
         if need_update {
-            self.select_style_recurse();
+            self.request_select_style_recurse();
         }
     }
 
     #[js_func]
     pub fn get_attribute(&self, key: String) -> Option<String> {
-        self.attributes.get(&key).map(|it| it.to_string())
+        self.get_attribute_value(&key).map(|it| it.to_string())
+    }
+
+    /// Value of attribute without interning key
+    pub fn get_attribute_value(&self, key: &str) -> Option<&String> {
+        Atom::lookup(key).and_then(|key| self.attributes.get(&key))
     }
 
     #[js_func]
     pub fn set_attribute(&mut self, key: String, value: String) {
+        let key = Atom::from(key);
         let need_update_style = CSS_MANAGER.with_borrow(|cm| cm.contains_attr(&key));
         let mut backend = self.backend.clone();
         let mut is_new = false;
@@ -244,27 +356,59 @@ impl Element {
             String::new()
         });
         if is_new || v != &value {
-            *v = value;
+            let old_value = mem::replace(v, value);
             backend.on_attribute_changed(&key, Some(&v));
+            if ElementIndex::is_indexed_attribute(&key) {
+                let new_value = v.clone();
+                self.update_element_index(&key, (!is_new).then_some(old_value), Some(new_value));
+            }
             if need_update_style {
-                self.select_style_recurse();
+                self.request_select_style_recurse();
+            }
+            if self.has_generated_content() {
+                self.update_generated_content();
             }
         }
     }
 
     #[js_func]
     pub fn remove_attribute(&mut self, key: String) {
+        let key = Atom::from(key);
         let need_update_style = CSS_MANAGER.with_borrow(|cm| cm.contains_attr(&key));
-        self.attributes.remove(&key);
+        let old_value = self.attributes.remove(&key);
         self.backend.on_attribute_changed(&key, None);
+        if old_value.is_some() && ElementIndex::is_indexed_attribute(&key) {
+            self.update_element_index(&key, old_value, None);
+        }
         if need_update_style {
-            self.select_style_recurse();
+            self.request_select_style_recurse();
+        }
+        if self.has_generated_content() {
+            self.update_generated_content();
         }
     }
 
+    fn update_element_index(
+        &self,
+        key: &str,
+        old_value: Option<String>,
+        new_value: Option<String>,
+    ) {
+        let el = self.clone();
+        self.with_window(|mut w| {
+            w.on_indexed_attribute_changed(&el, key, old_value.as_deref(), new_value.as_deref())
+        });
+    }
+
+    /// Path from root to element with `id` and `data-testid` attributes, for ui automation
+    #[js_func]
+    pub fn get_path(&self) -> Vec<ElementPathEntry> {
+        element_path(self)
+    }
+
     #[js_func]
     pub fn is_disabled(&self) -> bool {
-        self.is_form_element && self.attributes.contains_key("disabled")
+        self.is_form_element && self.get_attribute_value("disabled").is_some()
     }
 
     #[js_func]
@@ -335,6 +479,17 @@ impl Element {
         self.resource_table.put(ElementJsContext { context });
     }
 
+    /// Keep the js wrapper alive while the element is shown as content of a page or popup, as
+    /// only a weak reference is held by js context. Released when the page or window is closed.
+    #[js_func]
+    pub fn retain_js_context(&mut self, context: JsValue) {
+        self.resource_table.put(ElementJsRoot { context });
+    }
+
+    pub(crate) fn release_js_context(&mut self) {
+        self.resource_table.remove::<ElementJsRoot>();
+    }
+
     #[js_func]
     pub fn get_js_context(&self) -> Result<JsValue, Error> {
         let e = self
@@ -356,6 +511,19 @@ impl Element {
         Ok(())
     }
 
+    /// Remove child by identity, returns false if it's not a child. Children moved out of this
+    /// element by fullscreen or pop out are closed there instead of being moved back.
+    #[js_func]
+    pub fn remove_child_element(&mut self, child: Element) -> bool {
+        if let Some(position) = self.get_child_position(&child) {
+            self.remove_child_view(position);
+            return true;
+        }
+        pop_out::discard(&child, self);
+        self.with_window(|mut w| w.discard_fullscreen_element(&child, self));
+        false
+    }
+
     #[js_func]
     pub fn remove_child(&mut self, position: u32) -> Result<(), Error> {
         self.remove_child_view(position);
@@ -385,6 +553,19 @@ impl Element {
         &mut self,
         event_type: String,
         listener: JsValue,
+        options: Option<EventListenerOptions>,
+    ) -> Result<u32, JsError> {
+        let id = self.bind_js_event_listener(event_type, listener)?;
+        if let Some(options) = options {
+            self.event_registration.set_listener_options(id, options);
+        }
+        Ok(id)
+    }
+
+    fn bind_js_event_listener(
+        &mut self,
+        event_type: String,
+        listener: JsValue,
     ) -> Result<u32, JsError> {
         let id = bind_js_event_listener!(
             self, event_type.as_str(), listener.clone();
@@ -403,17 +584,25 @@ impl Element {
             "touchmove" => TouchMoveEventListener,
             "touchend" => TouchEndEventListener,
             "touchcancel" => TouchCancelEventListener,
+            "gesturezoom" => GestureZoomEventListener,
+            "gesturerotate" => GestureRotateEventListener,
+            "gesturepan" => GesturePanEventListener,
             "focus" => FocusEventListener,
             "blur" => BlurEventListener,
             "focusshift" => FocusShiftEventListener,
             "textchange" => TextChangeEventListener,
             "scroll" => ScrollEventListener,
+            "scrollend" => ScrollEndEventListener,
+            "reachtop" => ReachTopEventListener,
+            "reachbottom" => ReachBottomEventListener,
             "dragstart" => DragStartEventListener,
             "dragover" => DragOverEventListener,
             "drop" => DropEventListener,
             "boundschange" => BoundsChangeEventListener,
             "droppedfile" => DroppedFileEventListener,
             "hoveredfile" => HoveredFileEventListener,
+            "fullscreenchange" => FullscreenChangeEventListener,
+            "pointerlockchange" => PointerLockChangeEventListener,
         );
         if id.is_none() {
             if let Some(factory) = self.js_event_listener_factory.get_mut(&event_type) {
@@ -429,10 +618,40 @@ impl Element {
                 }
             }
         }
-        let id = id.ok_or_else(|| JsError::new(format!("unknown event_type:{}", event_type)))?;
+        let id = match id {
+            Some(id) => id,
+            None => self.register_event_listener(CustomEventListener::from_js_value(
+                &event_type,
+                listener,
+            )),
+        };
         Ok(id)
     }
 
+    /// Dispatch a user-defined event synchronously, return false if default prevented
+    #[js_func]
+    pub fn dispatch_event(
+        &mut self,
+        name: String,
+        detail: JsValue,
+        option: Option<DispatchEventOption>,
+    ) -> bool {
+        let option = option.unwrap_or_default();
+        let event = CustomEvent {
+            name,
+            detail,
+            bubbles: option.bubbles.unwrap_or(false),
+        };
+        let mut ctx = EventContext::new(self.as_weak());
+        ctx.allow_bubbles = event.allow_bubbles();
+        self.handle_event(
+            TypeId::of::<CustomEvent>(),
+            &mut Event::new(event),
+            &mut ctx,
+        );
+        !ctx.prevent_default
+    }
+
     #[js_func]
     pub fn focus(&mut self) {
         self.with_window(|mut w| {
@@ -440,6 +659,77 @@ impl Element {
         });
     }
 
+    /// Show element covering the whole window until exit_fullscreen is called or Escape
+    /// is pressed
+    #[js_func]
+    pub fn request_fullscreen(&mut self) -> Result<(), JsError> {
+        let window = self
+            .get_window()
+            .ok_or_else(|| JsError::from_str("element is not in a window"))?;
+        let mut window = window.upgrade_mut()?;
+        window.request_element_fullscreen(self.clone())
+    }
+
+    #[js_func]
+    pub fn exit_fullscreen(&mut self) {
+        self.with_window(|mut w| {
+            if w.is_element_fullscreen(self) {
+                w.exit_element_fullscreen();
+            }
+        });
+    }
+
+    #[js_func]
+    pub fn is_fullscreen(&self) -> bool {
+        let mut fullscreen = false;
+        self.with_window(|w| fullscreen = w.is_element_fullscreen(self));
+        fullscreen
+    }
+
+    /// Hide cursor and report relative motion of mouse as movement of mousemove events, until
+    /// exit_pointer_lock is called, Escape is pressed or window loses focus
+    #[js_func]
+    pub fn request_pointer_lock(&mut self) -> Result<(), JsError> {
+        let window = self
+            .get_window()
+            .ok_or_else(|| JsError::from_str("element is not in a window"))?;
+        let mut window = window.upgrade_mut()?;
+        window.request_pointer_lock(self.clone())
+    }
+
+    #[js_func]
+    pub fn exit_pointer_lock(&mut self) {
+        self.with_window(|mut w| {
+            if w.is_pointer_locked(self) {
+                w.exit_pointer_lock();
+            }
+        });
+    }
+
+    #[js_func]
+    pub fn is_pointer_locked(&self) -> bool {
+        let mut locked = false;
+        self.with_window(|w| locked = w.is_pointer_locked(self));
+        locked
+    }
+
+    /// Move element into a frameless always-on-top window, e.g. picture-in-picture of a player.
+    /// The element is moved back when the window is closed or pop_in is called.
+    #[js_func]
+    pub fn pop_out(&mut self, options: PopOutOptions) -> Result<WindowHandle, JsError> {
+        pop_out::pop_out(self, options)
+    }
+
+    #[js_func]
+    pub fn pop_in(&mut self) {
+        pop_out::pop_in(self);
+    }
+
+    #[js_func]
+    pub fn is_popped_out(&self) -> bool {
+        pop_out::get_pop_out_window(self).is_some()
+    }
+
     #[js_func]
     pub fn set_tooltip(&mut self, tooltip: String) {
         self.tooltip = tooltip;
@@ -473,6 +763,52 @@ impl Element {
         }
     }
 
+    /// Scroll to the given offsets, animated if behavior is "smooth"
+    #[js_func]
+    pub fn scroll_to(&mut self, option: ScrollToOption) {
+        let (scroll_left, scroll_top) = self.scrollable.scroll_offset();
+        let left = option.left.unwrap_or(scroll_left);
+        let top = option.top.unwrap_or(scroll_top);
+        if option.behavior.as_deref() == Some("smooth") {
+            let me = self.clone();
+            self.scrollable
+                .animate_scroll_to(&me, left, top, SMOOTH_SCROLL_DURATION);
+        } else {
+            self.set_scroll_left(left);
+            self.set_scroll_top(top);
+        }
+    }
+
+    /// Fraction of fling velocity kept every millisecond, "normal", "fast" or a number in (0, 1)
+    #[js_func]
+    pub fn set_scroll_deceleration_rate(&mut self, value: String) -> Result<(), JsError> {
+        let rate = ScrollPhysics::parse_deceleration_rate(&value)
+            .ok_or_else(|| JsError::from_str("invalid deceleration rate"))?;
+        let physics = self.scrollable.physics();
+        self.scrollable.set_physics(ScrollPhysics {
+            deceleration_rate: rate,
+            ..physics
+        });
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn get_scroll_deceleration_rate(&self) -> f32 {
+        self.scrollable.physics().deceleration_rate
+    }
+
+    /// Whether content could be dragged or flung beyond the edges and spring back
+    #[js_func]
+    pub fn set_scroll_bounce(&mut self, bounce: bool) {
+        let physics = self.scrollable.physics();
+        self.scrollable.set_physics(ScrollPhysics { bounce, ..physics });
+    }
+
+    #[js_func]
+    pub fn get_scroll_bounce(&self) -> bool {
+        self.scrollable.physics().bounce
+    }
+
     pub fn get_max_scroll_left(&self) -> f32 {
         let content_bounds = self.get_content_bounds();
         let width = content_bounds.width;
@@ -503,6 +839,19 @@ impl Element {
         self.scrollable.vertical_bar.set_scroll_offset(value);
     }
 
+    /// Save scroll position together with the element at the top edge of viewport
+    #[js_func]
+    pub fn save_scroll_state(&self) -> ScrollState {
+        self.scrollable.save_state(self)
+    }
+
+    /// Restore scroll position after the next layout, relative to the saved anchor if it exists
+    #[js_func]
+    pub fn restore_scroll_state(&mut self, state: ScrollState) {
+        self.scrollable.restore_state(state);
+        self.mark_dirty(true);
+    }
+
     #[js_func]
     pub fn get_scroll_height(&self) -> f32 {
         self.get_real_content_size().1
@@ -521,6 +870,45 @@ impl Element {
         });
     }
 
+    /// Distance to the top or bottom edge within which reachtop or reachbottom is emitted
+    #[js_func]
+    pub fn set_scroll_edge_threshold(&mut self, threshold: f32) {
+        self.scrollable.set_edge_threshold(threshold);
+        self.emit_reach_edge_events();
+    }
+
+    #[js_func]
+    pub fn get_scroll_edge_threshold(&self) -> f32 {
+        self.scrollable.edge_threshold()
+    }
+
+    fn emit_reach_edge_events(&mut self) {
+        let (top_reached, bottom_reached) = self.scrollable.update_edges();
+        if top_reached {
+            self.emit(ReachTopEvent);
+        }
+        if bottom_reached {
+            self.emit(ReachBottomEvent);
+        }
+    }
+
+    fn schedule_scroll_end_event(&mut self) {
+        let weak = self.as_weak();
+        self.scrollable.schedule_scroll_end(move || {
+            let mut el = ok_or_return!(weak.upgrade());
+            // Scrolling may continue after a pause of dragging
+            if el.scrollable.is_dragging() {
+                el.schedule_scroll_end_event();
+                return;
+            }
+            let (scroll_left, scroll_top) = el.scrollable.scroll_offset();
+            el.emit(ScrollEndEvent {
+                scroll_top,
+                scroll_left,
+            });
+        });
+    }
+
     pub fn get_backend_as<T>(&self) -> &T {
         unsafe {
             // &*(self as *const dyn Any as *const T)
@@ -550,7 +938,7 @@ impl Element {
     fn set_parent_internal(&mut self, parent: ElementParent) {
         self.parent = parent;
         self.applied_style = Styles::new();
-        self.select_style_recurse();
+        self.request_select_style_recurse();
         self.mark_style_dirty();
     }
 
@@ -583,7 +971,7 @@ impl Element {
     pub fn get_window(&self) -> Option<WindowHandle> {
         if let Some(p) = self.get_parent() {
             return p.get_window();
-        } else if let ElementParent::Window(ww) = &self.parent {
+        } else if let ElementParent::Window(ww) | ElementParent::Page(ww) = &self.parent {
             return Some(ww.clone());
         }
         None
@@ -621,6 +1009,51 @@ impl Element {
         self.auto_focus
     }
 
+    /// Mark element as a focus scope, which remembers its last focused descendant and
+    /// restores it when focus moves to the scope again.
+    #[js_func]
+    pub fn set_focus_scope(&mut self, focus_scope: bool) {
+        self.focus_scope = focus_scope;
+        if !focus_scope {
+            self.last_focused = None;
+        }
+    }
+
+    #[js_func]
+    pub fn is_focus_scope(&self) -> bool {
+        self.focus_scope
+    }
+
+    /// The remembered descendant of focus scope if it is still a focusable descendant
+    pub(crate) fn get_focus_restore_target(&self) -> Option<Element> {
+        if !self.focus_scope {
+            return None;
+        }
+        let target = self.last_focused.as_ref()?.upgrade().ok()?;
+        if !target.is_focusable() {
+            return None;
+        }
+        let mut p = target.get_parent();
+        while let Some(e) = p {
+            if &e == self {
+                return Some(target);
+            }
+            p = e.get_parent();
+        }
+        None
+    }
+
+    /// Record focused element in all focus scopes containing it
+    pub(crate) fn remember_focus_in_scopes(&self) {
+        let mut p = self.get_parent();
+        while let Some(mut e) = p {
+            if e.focus_scope {
+                e.last_focused = Some(self.as_weak());
+            }
+            p = e.get_parent();
+        }
+    }
+
     fn compute_length(&self, length: StyleUnit, parent_length: Option<f32>) -> Option<f32> {
         if let StyleUnit::Point(p) = length {
             Some(p.0)
@@ -654,7 +1087,7 @@ impl Element {
     pub fn get_real_content_size(&self) -> (f32, f32) {
         let mut content_width = 0.0;
         let mut content_height = 0.0;
-        for c in self.get_children() {
+        for c in self.get_layout_children() {
             let cb = c.get_bounds();
             content_width = f32::max(content_width, cb.right());
             content_height = f32::max(content_height, cb.bottom());
@@ -679,6 +1112,30 @@ impl Element {
         )
     }
 
+    /// Scroll ancestors so that rect relative to the border box of element becomes visible
+    pub fn scroll_rect_into_view(&self, rect: &base::Rect) {
+        let mut rect = *rect;
+        let mut el = self.clone();
+        while let Some(p) = el.get_parent() {
+            let b = el.get_bounds();
+            let (bt, _, _, bl) = p.get_border_width();
+            let content_rect =
+                base::Rect::new(b.x + rect.x - bl, b.y + rect.y - bt, rect.width, rect.height);
+            let mut scrollable = p.scrollable.clone();
+            if scrollable.is_scrollable() {
+                scrollable.scroll_into_view(&content_rect);
+            }
+            let (sl, st) = scrollable.scroll_offset();
+            rect = base::Rect::new(
+                content_rect.x - sl + bl,
+                content_rect.y - st + bt,
+                rect.width,
+                rect.height,
+            );
+            el = p;
+        }
+    }
+
     /// bounds relative to root node
     pub fn get_origin_bounds(&self) -> base::Rect {
         let b = self.get_bounds();
@@ -701,15 +1158,16 @@ impl Element {
                 p.get_eid()
             );
         }
-        let pos = {
-            let layout = &mut self.style;
-            let pos = position.unwrap_or_else(|| layout.child_count());
-            layout.insert_child(&mut child.style, pos);
-            pos
-        };
+        let count = self.children.len() as u32;
+        let pos = position.map_or(count, |p| p.min(count));
+        // Generated ::marker and ::before boxes are laid out before children
+        let layout_pos = pos + self.get_leading_generated_count();
+        self.style.insert_child(&mut child.style, layout_pos);
         self.mark_dirty(true);
         child.set_parent_internal(ElementParent::Element(self.as_weak()));
         self.children.insert(pos as usize, child.clone());
+        let attached = child.clone();
+        self.with_window(|mut w| w.on_element_attached(&attached));
         child.process_auto_focus();
     }
 
@@ -734,23 +1192,59 @@ impl Element {
     }
 
     pub fn remove_child_view(&mut self, position: u32) {
+        if position as usize >= self.children.len() {
+            return;
+        }
         let mut c = self.children.remove(position as usize);
         c.set_parent_internal(ElementParent::None);
         let mut ele = self.clone();
         let layout = &mut ele.style;
         layout.remove_child(&mut c.style);
         ele.mark_dirty(true);
+        // Counters of following siblings may change
+        ele.mark_children_style_dirty();
         if let Some(window) = self.get_window() {
             if let Ok(mut f) = window.upgrade_mut() {
-                f.on_element_removed(&c);
+                f.on_element_removed(self, &c);
             }
         }
     }
 
+    pub(crate) fn get_child_position(&self, child: &Element) -> Option<u32> {
+        let idx = self.children.iter().position(|c| c == child)?;
+        Some(idx as u32)
+    }
+
+    pub(crate) fn get_child_count(&self) -> u32 {
+        self.children.len() as u32
+    }
+
+    /// Count of generated ::marker and ::before boxes, which are laid out before children
+    fn get_leading_generated_count(&self) -> u32 {
+        self.marker_element.is_some() as u32 + self.before_element.is_some() as u32
+    }
+
+    fn has_generated_content(&self) -> bool {
+        self.marker_element.is_some()
+            || self.before_element.is_some()
+            || self.after_element.is_some()
+    }
+
+    /// Children excluding generated ::marker, ::before and ::after boxes
     pub fn get_children(&self) -> Vec<Element> {
         self.children.clone()
     }
 
+    /// Children in layout order, including generated ::marker, ::before and ::after boxes
+    pub(crate) fn get_layout_children(&self) -> Vec<Element> {
+        let mut children = Vec::with_capacity(self.children.len() + 3);
+        children.extend(self.marker_element.clone());
+        children.extend(self.before_element.clone());
+        children.extend(self.children.iter().cloned());
+        children.extend(self.after_element.clone());
+        children
+    }
+
     pub fn calculate_layout(&mut self, available_width: f32, available_height: f32) {
         // mark all children dirty so that custom measure function could be call
         // self.mark_all_layout_dirty();
@@ -758,6 +1252,8 @@ impl Element {
         if self.style.has_shadow() {
             let mut me = self.clone();
             self.scrollable.update_layout(&mut me);
+            // Content size may change without scrolling, e.g. more items are loaded
+            self.emit_reach_edge_events();
         } else {
             self.before_layout_recurse();
             self.style
@@ -831,11 +1327,42 @@ impl Element {
         }
     }
 
+    /// Update hover state, return true if changed. Css rules with :hover are not re-matched here,
+    /// see `Window::update_hover_path`
+    pub(crate) fn set_hover(&mut self, hover: bool) -> bool {
+        if self.hover == hover {
+            return false;
+        }
+        self.hover = hover;
+        if self.style_list.has_hover_style() {
+            self.mark_style_dirty();
+        }
+        true
+    }
+
+    #[js_func]
+    pub fn is_hover(&self) -> bool {
+        self.hover
+    }
+
     #[js_func]
     pub fn get_bounding_client_rect(&self) -> base::Rect {
         self.get_origin_bounds()
     }
 
+    /// Dump yoga node tree with style inputs and computed layout
+    #[js_func]
+    pub fn debug_layout(&self) -> LayoutDump {
+        debug::dump_layout(self)
+    }
+
+    /// Trace declarations of property in cascade order
+    #[js_func]
+    pub fn debug_computed_style(&self, property: String) -> Result<StyleTrace, Error> {
+        debug::trace_style(self, &property)
+            .ok_or_else(|| anyhow!("Unknown style property {}", property))
+    }
+
     //TODO remove
     fn calculate_changed_style<'a>(
         old_style_map: &'a HashMap<StylePropKey, FixedStyleProp>,
@@ -893,7 +1420,7 @@ impl Element {
 
     pub(crate) fn resolve_style_vars_recurse(&mut self, parent_vars: &StyleVars) {
         let new_vars = self.style_list.resolve_variables(&parent_vars);
-        for mut c in self.get_children() {
+        for mut c in self.get_layout_children() {
             c.resolve_style_vars_recurse(&new_vars);
         }
     }
@@ -916,7 +1443,7 @@ impl Element {
         let mut ctx = ctx.clone();
         ctx.font_size = px;
 
-        for mut c in self.get_children() {
+        for mut c in self.get_layout_children() {
             c.compute_font_size_recurse(&ctx);
         }
     }
@@ -930,10 +1457,14 @@ impl Element {
             false
         };
         if is_children_dirty || changed {
-            let mut children = self.get_children();
+            let mut children = self.get_layout_children();
             for c in &mut children {
                 c.apply_style_update(changed, length_ctx);
             }
+            // Counters depend on preceding siblings
+            for c in &mut children {
+                c.refresh_generated_text();
+            }
         }
         self.dirty_flags.remove(StyleDirtyFlags::ChildrenDirty);
         self.dirty_flags.remove(StyleDirtyFlags::SelfDirty);
@@ -978,6 +1509,14 @@ impl Element {
         let changed_styles =
             styles.compute_changed_style(&self.applied_style, |k| self.style.get_default_value(k));
         let mut changed = !changed_styles.is_empty();
+        let mut generated_content_changed = changed_styles.iter().any(|p| {
+            matches!(
+                p.key(),
+                StylePropKey::ListStyleType
+                    | StylePropKey::CounterReset
+                    | StylePropKey::CounterIncrement
+            )
+        });
         for sp in changed_styles {
             let (repaint, need_layout) = self.style.set_resolved_style_prop(sp, length_ctx);
             if need_layout || repaint {
@@ -1006,6 +1545,9 @@ impl Element {
                 changed_pe_styles_map.insert(k.clone(), changed_pe_styles);
             }
         }
+        generated_content_changed |= ["marker", "before", "after"]
+            .iter()
+            .any(|k| changed_pe_styles_map.contains_key(*k));
         if !changed_pe_styles_map.is_empty() {
             self.accept_pseudo_element_styles(changed_pe_styles_map);
             changed = true;
@@ -1014,9 +1556,211 @@ impl Element {
         // println!("changed list: {} {:?}", self.id, changed_list);
         self.applied_style = styles;
         self.applied_pseudo_element_styles = pseudo_element_styles;
+        if generated_content_changed {
+            self.update_generated_content();
+        }
         changed
     }
 
+    /// Create, update or remove generated boxes of ::marker, ::before and ::after
+    fn update_generated_content(&mut self) {
+        if !self.backend.accept_generated_content() {
+            return;
+        }
+        let marker = self.resolve_marker();
+        let mut marker_element = self.marker_element.take();
+        self.update_generated_element(&mut marker_element, marker, Some(0));
+        self.marker_element = marker_element;
+
+        let before = self.resolve_generated_content("before");
+        let mut before_element = self.before_element.take();
+        let position = self.marker_element.is_some() as u32;
+        self.update_generated_element(&mut before_element, before, Some(position));
+        self.before_element = before_element;
+
+        let after = self.resolve_generated_content("after");
+        let mut after_element = self.after_element.take();
+        self.update_generated_element(&mut after_element, after, None);
+        self.after_element = after_element;
+    }
+
+    /// Update text of generated boxes, which may depend on counters of preceding siblings
+    fn refresh_generated_text(&mut self) {
+        let generated = [
+            (self.marker_element.clone(), "marker"),
+            (self.before_element.clone(), "before"),
+            (self.after_element.clone(), "after"),
+        ];
+        for (element, pseudo) in generated {
+            let mut el = some_or_continue!(element);
+            let content = if pseudo == "marker" {
+                self.resolve_marker()
+            } else {
+                self.resolve_generated_content(pseudo)
+            };
+            if let Some((text, _)) = content {
+                el.get_backend_mut_as::<Label>().set_text(text);
+            }
+        }
+    }
+
+    fn resolve_generated_content(&self, pseudo: &str) -> Option<(String, Vec<FixedStyleProp>)> {
+        let styles = self.applied_pseudo_element_styles.get(pseudo)?;
+        let content = match styles.get(&StylePropKey::Content) {
+            Some(ResolvedStyleProp::Content(c)) => c,
+            _ => return None,
+        };
+        let text = self.parse_content(content)?;
+        let style_props = styles
+            .values()
+            .filter(|p| p.key() != StylePropKey::Content)
+            .map(|p| p.to_unresolved())
+            .collect();
+        Some((text, style_props))
+    }
+
+    /// Marker of list item, which is placed outside of the element at the start of first line
+    fn resolve_marker(&self) -> Option<(String, Vec<FixedStyleProp>)> {
+        if self.style.list_style_type == ListStyleType::None {
+            return None;
+        }
+        let top = LengthOrPercent::Length(Length::PX(0.0));
+        let mut style_props = vec![
+            FixedStyleProp::Position(StylePropVal::Custom(PositionType::Absolute)),
+            FixedStyleProp::Top(StylePropVal::Custom(top)),
+            FixedStyleProp::Right(StylePropVal::Custom(LengthOrPercent::Percent(100.0))),
+        ];
+        let mut content = None;
+        if let Some(styles) = self.applied_pseudo_element_styles.get("marker") {
+            for p in styles.values() {
+                match p {
+                    ResolvedStyleProp::Content(c) => content = Some(c.as_str()),
+                    _ => style_props.push(p.to_unresolved()),
+                }
+            }
+        }
+        let text = match content {
+            Some(c) => self.parse_content(c)?,
+            None => {
+                let value = self.get_counters("list-item").last().copied().unwrap_or(0);
+                self.style.list_style_type.format_marker(value)
+            }
+        };
+        Some((text, style_props))
+    }
+
+    fn parse_content(&self, content: &str) -> Option<String> {
+        parse_generated_content(
+            content,
+            |k| self.get_attribute_value(k).cloned(),
+            |k| self.get_counters(k),
+        )
+    }
+
+    /// Values of nested counters with the given name, the innermost one is at last.
+    ///
+    /// Only resets and increments of the element itself, its preceding siblings and ancestors
+    /// (and their preceding siblings) are counted, descendants of preceding siblings are ignored.
+    /// `list-item` is implicitly incremented by list items and reset by their parent.
+    pub fn get_counters(&self, name: &str) -> Vec<i32> {
+        self.get_counters_before(name, false)
+    }
+
+    /// Values of counters before the element if `exclude_self` is true, used to get outer
+    /// counters of nested scopes
+    fn get_counters_before(&self, name: &str, exclude_self: bool) -> Vec<i32> {
+        let mut value = 0;
+        let mut skip = exclude_self;
+        let mut node = self.clone();
+        loop {
+            let mut current = Some(node.clone());
+            while let Some(e) = current {
+                if !skip {
+                    value += e.get_counter_increment(name);
+                    if let Some(reset) = e.get_counter_reset(name) {
+                        let mut values = e.get_counters_before(name, true);
+                        values.push(reset + value);
+                        return values;
+                    }
+                }
+                skip = false;
+                current = e.get_previous_sibling();
+            }
+            match node.get_parent() {
+                Some(p) => node = p,
+                None => {
+                    return if exclude_self && value == 0 {
+                        Vec::new()
+                    } else {
+                        vec![value]
+                    };
+                }
+            }
+        }
+    }
+
+    fn get_counter_reset(&self, name: &str) -> Option<i32> {
+        if let Some(v) = self.style.counter_reset.get(name, 0) {
+            return Some(v);
+        }
+        let is_list = name == "list-item"
+            && self
+                .children
+                .iter()
+                .any(|c| c.style.list_style_type != ListStyleType::None);
+        is_list.then_some(0)
+    }
+
+    fn get_counter_increment(&self, name: &str) -> i32 {
+        if let Some(v) = self.style.counter_increment.get(name, 1) {
+            v
+        } else if name == "list-item" && self.style.list_style_type != ListStyleType::None {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn get_previous_sibling(&self) -> Option<Element> {
+        let parent = self.get_parent()?;
+        let idx = parent.children.iter().position(|c| c == self)?;
+        parent.children.get(idx.checked_sub(1)?).cloned()
+    }
+
+    fn update_generated_element(
+        &mut self,
+        element: &mut Option<Element>,
+        content: Option<(String, Vec<FixedStyleProp>)>,
+        position: Option<u32>,
+    ) {
+        match content {
+            Some((text, style_props)) => {
+                let mut el = match element.take() {
+                    Some(el) => el,
+                    None => {
+                        let mut el = Element::create(Label::create);
+                        // Generated boxes are laid out and painted, but never exposed as children
+                        let pos = position.unwrap_or_else(|| self.style.child_count());
+                        self.style.insert_child(&mut el.style, pos);
+                        el.set_parent_internal(ElementParent::Element(self.as_weak()));
+                        self.mark_dirty(true);
+                        el
+                    }
+                };
+                el.get_backend_mut_as::<Label>().set_text(text);
+                el.set_style_props(style_props);
+                *element = Some(el);
+            }
+            None => {
+                if let Some(mut el) = element.take() {
+                    el.set_parent_internal(ElementParent::None);
+                    self.style.remove_child(&mut el.style);
+                    self.mark_dirty(true);
+                }
+            }
+        }
+    }
+
     fn accept_pseudo_element_styles(&mut self, styles: HashMap<String, Vec<ResolvedStyleProp>>) {
         self.scrollable.accept_css_style(&styles);
         self.backend.accept_pseudo_element_styles(styles);
@@ -1075,26 +1819,6 @@ impl Element {
             ctx.propagation_cancelled = true;
             return;
         }
-        if event_type_id == TypeId::of::<MouseEnterEvent>() {
-            self.hover = true;
-            //TODO optimize performance
-            if !self.parent.is_element() {
-                self.update_select_style_recurse();
-            }
-            if self.style_list.has_hover_style() {
-                self.mark_style_dirty();
-            }
-        } else if event_type_id == TypeId::of::<MouseLeaveEvent>() {
-            self.hover = false;
-            //TODO optimize performance
-            //FIXME style may not be updated if event stop propagates?
-            if !self.parent.is_element() {
-                self.update_select_style_recurse();
-            }
-            if self.style_list.has_hover_style() {
-                self.mark_style_dirty();
-            }
-        }
         let me = self.clone();
         if !self.scrollable.on_event(&event, ctx, &me) {
             let backend = self.get_backend_mut();
@@ -1120,10 +1844,15 @@ impl Element {
                 }
             }
         }
-        if !self.backend.execute_default_behavior(event, ctx) {
-            if let Some(mut p) = self.get_parent() {
-                p.handle_default_behavior(event, ctx);
-            }
+        if self.backend.execute_default_behavior(event, ctx) {
+            return;
+        }
+        let me = self.clone();
+        if self.scrollable.execute_default_behavior(event, &me) {
+            return;
+        }
+        if let Some(mut p) = self.get_parent() {
+            p.handle_default_behavior(event, ctx);
         }
     }
 
@@ -1154,6 +1883,13 @@ impl Element {
         }
     }
 
+    /// Repaint after scroll offset changed, layers are moved without rebuilding render tree
+    /// if nothing else changed
+    pub fn mark_scroll_dirty(&mut self) {
+        let el = self.clone();
+        self.with_window(|mut w| w.invalid_scroll(el));
+    }
+
     fn request_invalid(&mut self, element: &Element) {
         if let Some(mut p) = self.get_parent() {
             p.request_invalid(element);
@@ -1170,7 +1906,7 @@ impl Element {
 
     pub fn mark_all_layout_dirty(&mut self) {
         self.mark_dirty(true);
-        for mut c in self.get_children() {
+        for mut c in self.get_layout_children() {
             c.mark_all_layout_dirty();
         }
     }
@@ -1194,13 +1930,13 @@ impl Element {
 
     pub fn before_layout_recurse(&mut self) {
         self.backend.before_layout();
-        for c in &mut self.children {
+        for mut c in self.get_layout_children() {
             c.before_layout_recurse();
         }
     }
     pub fn before_render_recurse(&mut self) {
         self.scrollable.execute_auto_scroll_callback();
-        for c in &mut self.children {
+        for mut c in self.get_layout_children() {
             c.before_render_recurse();
         }
     }
@@ -1225,7 +1961,7 @@ impl Element {
             if self.style.has_shadow() {
                 self.calculate_layout(origin_bounds.width, origin_bounds.height);
             } else {
-                for child in &mut self.get_children() {
+                for child in &mut self.get_layout_children() {
                     child.on_layout_update();
                 }
             }
@@ -1292,15 +2028,22 @@ impl Element {
     }
 
     pub fn set_tag(&mut self, tag: String) {
-        self.tag = tag;
+        self.tag = Atom::from(tag.to_ascii_lowercase());
     }
 
     fn select_style_recurse(&mut self) {
         self.select_style();
-        for mut child in self.get_children() {
+        for mut child in self.get_layout_children() {
             child.select_style_recurse();
         }
     }
+
+    /// Match selectors of element and its descendants, deferred to the end of batch if batching
+    fn request_select_style_recurse(&mut self) {
+        if !batch::defer_select_style(self) {
+            self.select_style_recurse();
+        }
+    }
 }
 
 impl ElementWeak {
@@ -1358,16 +2101,23 @@ pub struct Element {
     pub(crate) animation_style_props: HashMap<StylePropKey, FixedStyleProp>,
     pub(crate) hover: bool,
     auto_focus: bool,
+    focus_scope: bool,
+    /// Last focused descendant when element is a focus scope
+    last_focused: Option<ElementWeak>,
     dirty_flags: StyleDirtyFlags,
     element_type: ElementType,
 
-    applied_style: Styles,
+    pub(crate) applied_style: Styles,
     applied_pseudo_element_styles: HashMap<String, Styles>,
+    /// Generated boxes of ::marker, ::before and ::after
+    marker_element: Option<Element>,
+    before_element: Option<Element>,
+    after_element: Option<Element>,
     // animation_instance: Option<AnimationInstance>,
     draggable: bool,
     cursor: Cursor,
     rect: base::Rect,
-    resource_table: ResourceTable,
+    pub(crate) resource_table: ResourceTable,
     children_decoration: (f32, f32, f32, f32),
 
     //TODO rename
@@ -1376,12 +2126,14 @@ pub struct Element {
     border_path: BorderPath,
     style_list: StyleList,
     focusable: bool,
-    pub(crate) classes: HashSet<String>,
-    pub(crate) attributes: HashMap<String, String>,
+    pub(crate) classes: HashSet<Atom>,
+    pub(crate) attributes: HashMap<Atom, String>,
     pub scrollable: Scrollable,
-    pub tag: String,
+    pub tag: Atom,
     pub(crate) is_form_element: bool,
     pub allow_ime: bool,
+    /// Hint for input method when focused
+    pub ime_purpose: ImePurpose,
     js_event_listener_factory: HashMap<String, BoxJsEventListenerFactory<ElementWeak>>,
     pub(crate) tooltip: String,
 }
@@ -1425,15 +2177,21 @@ impl ElementData {
             border_path: BorderPath::new(0.0, 0.0, [0.0; 4], [0.0; 4]),
             style_list: StyleList::new(),
             auto_focus: false,
+            focus_scope: false,
+            last_focused: None,
             focusable: false,
             dirty_flags: StyleDirtyFlags::empty(),
             classes: HashSet::new(),
             attributes: HashMap::new(),
             applied_pseudo_element_styles: HashMap::new(),
+            marker_element: None,
+            before_element: None,
+            after_element: None,
             scrollable,
-            tag: "".to_string(),
+            tag: Atom::new(""),
             is_form_element: false,
             allow_ime: false,
+            ime_purpose: ImePurpose::Normal,
             js_event_listener_factory: HashMap::new(),
             tooltip: String::new(),
         }
@@ -1517,6 +2275,33 @@ pub trait ElementBackend: 'static {
         }
     }
 
+    /// Reset state of an element recycled by `detachForReuse`, e.g. text or source
+    fn reset(&mut self) {
+        if let Some(base) = self.get_base_mut() {
+            base.reset();
+        }
+    }
+
+    /// Return true to lay out the element as a leaf sized by `measure`, children are ignored by layout
+    fn is_measurable(&self) -> bool {
+        false
+    }
+
+    /// Intrinsic size of element, max size is infinity if unconstrained
+    fn measure(&self, max_width: f32, max_height: f32) -> (f32, f32) {
+        let _ = (max_width, max_height);
+        (0.0, 0.0)
+    }
+
+    /// Return true if ::before and ::after boxes could be inserted as children
+    fn accept_generated_content(&mut self) -> bool {
+        if let Some(base) = self.get_base_mut() {
+            base.accept_generated_content()
+        } else {
+            false
+        }
+    }
+
     fn backend_type_id(&self) -> TypeId {
         self.type_id()
     }
@@ -1,13 +1,62 @@
-use crate::element::{Element, ElementBackend};
+use crate::base::EventContext;
+use crate::element::{Element, ElementBackend, ElementWeak};
+use crate::event::{Event, KeyDownEvent, KeyEventDetail};
+use crate::ok_or_return;
+use crate::some_or_return;
+use crate::window::step_zoom;
 
-pub struct Body {}
+pub struct Body {
+    element: ElementWeak,
+}
+
+/// Ctrl+= and Ctrl+- zoom in or out the window, Ctrl+0 resets zoom.
+/// Return the new zoom if the key is a zoom shortcut.
+pub fn zoom_by_shortcut(detail: &KeyEventDetail, zoom: f32) -> Option<f32> {
+    if !detail.ctrl_key || detail.alt_key || detail.meta_key {
+        return None;
+    }
+    match detail.key_str.as_deref()? {
+        "=" | "+" => Some(step_zoom(zoom, true)),
+        "-" => Some(step_zoom(zoom, false)),
+        "0" => Some(1.0),
+        _ => None,
+    }
+}
+
+impl Body {
+    fn handle_zoom_key(&self, e: &KeyDownEvent) -> bool {
+        let element = ok_or_return!(self.element.upgrade(), false);
+        let window = some_or_return!(element.get_window(), false);
+        let mut window = ok_or_return!(window.upgrade_mut(), false);
+        let zoom = some_or_return!(zoom_by_shortcut(&e.0, window.get_zoom()), false);
+        window.set_zoom(zoom);
+        true
+    }
+}
 
 impl ElementBackend for Body {
-    fn create(_element: &mut Element) -> Self {
-        Self {}
+    fn create(element: &mut Element) -> Self {
+        Self {
+            element: element.as_weak(),
+        }
     }
 
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         None
     }
+
+    fn accept_generated_content(&mut self) -> bool {
+        true
+    }
+
+    fn execute_default_behavior(
+        &mut self,
+        event: &mut Event,
+        _ctx: &mut EventContext<ElementWeak>,
+    ) -> bool {
+        if let Some(e) = KeyDownEvent::cast(event) {
+            return self.handle_zoom_key(e);
+        }
+        false
+    }
 }
@@ -6,11 +6,12 @@ use crate::element::label::Label;
 use crate::element::{Element, ElementBackend, ElementWeak};
 use crate::event::{ClickEvent, Event};
 use crate::ok_or_return;
+use crate::style::display::Display;
 use crate::style::length::LengthOrPercent;
 use crate::style::{FixedStyleProp, ResolvedStyleProp, StylePropVal};
 use deft_macros::{element_backend, event, js_methods};
 use std::collections::HashMap;
-use yoga::{Align, Display, FlexDirection};
+use yoga::{Align, FlexDirection};
 
 #[event]
 pub struct ChangeEvent {}
@@ -117,6 +118,11 @@ impl ElementBackend for Checkbox {
         inst
     }
 
+    fn reset(&mut self) {
+        self.set_label(String::new());
+        self.base.reset();
+    }
+
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         Some(&mut self.base)
     }
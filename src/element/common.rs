@@ -1,5 +1,7 @@
 pub mod editable;
+pub mod fling;
 pub mod image_object;
+pub mod input_mask;
 pub mod scrollable;
 mod svg_object;
 
@@ -71,6 +73,11 @@ impl ScrollBar {
         self.scroll_length > self.length
     }
 
+    /// Visible length of scroll area
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
     pub fn get_max_scroll_offset(&self) -> f32 {
         (self.scroll_length - self.length).max(0.0)
     }
@@ -126,6 +133,11 @@ impl ScrollBar {
         self.scroll_offset
     }
 
+    /// Whether the thumb is being dragged
+    pub fn is_dragging(&self) -> bool {
+        self.scroll_begin_info.is_some()
+    }
+
     pub fn set_scroll_offset(&mut self, scroll_offset: f32) {
         self.update_scroll_offset(scroll_offset);
     }
@@ -2,12 +2,15 @@ use crate as deft;
 use crate::app::AppEvent;
 use crate::base::{Callback, EventContext, Rect};
 use crate::canvas_util::CanvasHelper;
+use crate::element::common::input_mask::InputMask;
 use crate::element::edit_history::{EditDetail, EditHistory};
 use crate::element::util::is_form_event;
+use crate::element::body::zoom_by_shortcut;
 use crate::element::{Element, ElementBackend, ElementWeak};
-use crate::event::{BlurEvent, BoundsChangeEvent, CaretChangeEvent, Event, FocusEvent, KeyDownEvent, KeyEventDetail, MouseDownEvent, MouseLeaveEvent, PreeditEvent, ScrollEvent, TextChangeEvent, TextInputEvent, TextUpdateEvent, KEY_MOD_CTRL, KEY_MOD_SHIFT};
+use crate::event::{BlurEvent, BoundsChangeEvent, CaretChangeEvent, Event, FocusEvent, KeyDownEvent, KeyEventDetail, MaskValidateEvent, MouseDownEvent, MouseLeaveEvent, PreeditEvent, ScrollEvent, TextChangeEvent, TextInputEvent, TextUpdateEvent, KEY_MOD_CMD_OR_CTRL, KEY_MOD_SHIFT};
 use crate::event_loop::create_event_loop_proxy;
 use crate::js::{FromJsValue, ToJsValue};
+use crate::menu::Menu;
 use crate::number::DeNan;
 use crate::render::RenderFn;
 use crate::string::StringUtils;
@@ -15,7 +18,7 @@ use crate::style::{ResolvedStyleProp, StylePropKey};
 use crate::text::textbox::{TextBox, TextCoord, TextElement, TextUnit};
 use crate::text::TextAlign;
 use crate::timer::TimerHandle;
-use crate::winit::dpi::{LogicalPosition, LogicalSize, Size};
+use crate::window::find::find_in_text_box;
 use crate::{ok_or_return, some_or_return, timer};
 use deft_macros::{element_backend, js_methods};
 use quick_js::{JsValue, ValueError};
@@ -77,6 +80,11 @@ pub struct Editable {
     line_height: Option<f32>,
     auto_height: bool,
     layout_dirty: bool,
+    mask: Option<InputMask>,
+    /// Whether composition text of input method is shown
+    preediting: bool,
+    /// Items appended to the built-in context menu
+    context_menu: Option<Menu>,
 }
 
 #[js_methods]
@@ -97,6 +105,7 @@ impl Editable {
                 self.paragraph.add_line(Self::build_line(ln.to_string()));
             }
             self.update_caret_value(TextCoord::new((0, 0)), false);
+            self.apply_mask();
         }
         self.element.mark_dirty(true);
     }
@@ -187,6 +196,57 @@ impl Editable {
         }
     }
     
+    /// Set input mask of single line text, current text is reformatted
+    pub fn set_mask(&mut self, mask: Option<InputMask>) {
+        self.mask = mask;
+        if self.mask.is_some() {
+            self.apply_mask();
+            self.emit_mask_validate();
+        }
+    }
+
+    pub fn get_mask(&self) -> Option<&InputMask> {
+        self.mask.as_ref()
+    }
+
+    /// Text without literals of mask, or the whole text if no mask is set
+    pub fn get_unmasked_text(&self) -> String {
+        let text = self.get_text();
+        match &self.mask {
+            Some(mask) => mask.unmask(&text),
+            None => text,
+        }
+    }
+
+    fn apply_mask(&mut self) {
+        let text = self.paragraph.get_text();
+        let caret = self.paragraph.get_caret();
+        let (formatted, new_caret) = some_or_return!(&self.mask).format(&text, caret.1);
+        if formatted != text {
+            self.paragraph.clear();
+            self.paragraph.add_line(Self::build_line(formatted));
+            self.update_caret_value(TextCoord::new((0, new_caret)), false);
+        } else if new_caret != caret.1 {
+            self.update_caret_value(TextCoord::new((0, new_caret)), false);
+        }
+    }
+
+    fn emit_mask_validate(&mut self) {
+        let mask = some_or_return!(&self.mask);
+        let value = self.paragraph.get_text();
+        let event = MaskValidateEvent {
+            raw_value: mask.unmask(&value),
+            complete: mask.is_complete(&value),
+            value,
+        };
+        self.element.emit(event);
+    }
+
+    /// Set custom items appended to the built-in context menu
+    pub fn set_context_menu(&mut self, menu: Option<Menu>) {
+        self.context_menu = menu;
+    }
+
     pub fn set_max_history(&mut self, max_history: usize) {
         self.edit_history.set_max_history(max_history);
     }
@@ -211,17 +271,8 @@ impl Editable {
         let el = self.element.upgrade_mut().ok()?;
         let win = el.get_window()?;
         let win = win.upgrade_mut().ok()?;
-        //TOOD use transformed position
-        let el_offset = el.get_origin_bounds();
-        let x = (el_offset.x + pos.x) as f64;
-        let y = (el_offset.y + pos.bottom()) as f64;
-        win.window.set_ime_cursor_area(
-            crate::winit::dpi::Position::Logical(LogicalPosition { x, y }),
-            Size::Logical(LogicalSize {
-                width: 1.0,
-                height: 1.0,
-            }),
-        );
+        // Candidate window is placed below the caret area by the platform
+        win.set_ime_cursor_area(&el, pos);
         Some(())
     }
 
@@ -268,11 +319,19 @@ impl Editable {
             let me = self.as_weak();
             p.scrollable.set_autoscroll_callback(move || {
                 let me = me.upgrade().ok()?;
-                me.get_caret_pixels_position()
+                // Caret position is relative to editable, which is offset by padding of parent
+                let bounds = me.element.upgrade().ok()?.get_bounds();
+                let caret = me.get_caret_pixels_position()?;
+                Some(caret.translate(bounds.x, bounds.y))
             });
         }
     }
 
+    /// Extend selection to point while selecting by mouse, returns false if not selecting
+    pub(crate) fn update_selecting(&mut self, x: f32, y: f32) -> bool {
+        self.paragraph.update_selecting_by_pixel_coord((x, y))
+    }
+
     fn emit_caret_change(&mut self) {
         let element = ok_or_return!(self.element.upgrade_mut());
         let origin_bounds = element.get_origin_bounds();
@@ -388,6 +447,12 @@ impl Editable {
                     NamedKey::ArrowDown => {
                         self.move_caret_vertical(false);
                     }
+                    NamedKey::Home => {
+                        self.paragraph.move_caret_to_line_edge(false, false);
+                    }
+                    NamedKey::End => {
+                        self.paragraph.move_caret_to_line_edge(true, false);
+                    }
                     NamedKey::Space => {
                         self.handle_input(" ");
                     }
@@ -401,10 +466,16 @@ impl Editable {
                 self.handle_input(&text);
             }
         } else if event.modifiers == KEY_MOD_SHIFT {
-            if let Some(text) = &event.key_str {
-                self.handle_input(&text);
+            match &event.named_key {
+                Some(NamedKey::Home) => self.paragraph.move_caret_to_line_edge(false, true),
+                Some(NamedKey::End) => self.paragraph.move_caret_to_line_edge(true, true),
+                _ => {
+                    if let Some(text) = &event.key_str {
+                        self.handle_input(&text);
+                    }
+                }
             }
-        } else if event.modifiers == KEY_MOD_CTRL {
+        } else if event.modifiers == KEY_MOD_CMD_OR_CTRL {
             if let Some(text) = &event.key_str {
                 match text.as_str() {
                     #[cfg(feature = "clipboard")]
@@ -420,7 +491,7 @@ impl Editable {
                     _ => {}
                 }
             }
-        } else if event.modifiers == KEY_MOD_CTRL | KEY_MOD_SHIFT {
+        } else if event.modifiers == KEY_MOD_CMD_OR_CTRL | KEY_MOD_SHIFT {
             if let Some(text) = &event.key_str {
                 match text.to_lowercase().as_str() {
                     "z" => {
@@ -562,6 +633,8 @@ impl Editable {
         if record_history {
             self.edit_history.record_input(start_caret, delete_detail, insert_detail);
         }
+        // Pasted or typed text is normalized by mask
+        self.apply_mask();
 
         // emit text update
         let text = self.paragraph.get_text().to_string();
@@ -571,6 +644,7 @@ impl Editable {
 
         // emit text change
         self.element.emit(TextChangeEvent { value: text });
+        self.emit_mask_validate();
 
         self.element.mark_dirty(true);
     }
@@ -642,6 +716,7 @@ impl Editable {
         } else if let Some(_e) = BlurEvent::cast(event) {
             self.handle_blur();
         } else if let Some(e) = TextInputEvent::cast(event) {
+            self.preediting = false;
             self.insert_text(e.0.as_str(), self.paragraph.get_caret(), true);
         } else if let Some(_e) = ScrollEvent::cast(event) {
             //TODO update later?
@@ -663,7 +738,11 @@ impl Editable {
             }
         } else if let Some(e) = KeyDownEvent::cast(event) {
             self.handle_key_down(&e.0);
+            // Keys are consumed by editor, e.g. arrow keys should not scroll ancestors,
+            // except zoom shortcuts handled by body
+            return zoom_by_shortcut(&e.0, 1.0).is_none();
         } else if let Some(e) = PreeditEvent::cast(event) {
+            self.preediting = !e.content.is_empty();
             self.handle_input(&e.content);
             if !e.content.is_empty() {
                 let end_caret = self.paragraph.get_caret();
@@ -686,64 +765,47 @@ impl Editable {
         false
     }
 
-    fn show_menu(&self, x: f32, y: f32) {
-        use crate::menu::{Menu, MenuItem, StandardMenuItem};
+    fn show_menu(&mut self, x: f32, y: f32) {
+        use crate::menu::{Menu, MenuItem};
+        // Editing actions should not be applied to uncommitted composition text
+        self.cancel_preedit();
         let mut menu = Menu::new();
+        let can_undo = self.edit_history.can_undo();
+        let can_redo = self.edit_history.can_redo();
+        menu.add_item(self.create_menu_item("Undo", "CmdOrCtrl+Z", can_undo, |me| me.undo()));
+        menu.add_item(self.create_menu_item("Redo", "CmdOrCtrl+Shift+Z", can_redo, |me| me.redo()));
+        menu.add_item(MenuItem::Separator);
         #[cfg(feature = "clipboard")]
         {
-            let (cut_menu, copy_menu) = {
-                let text_for_copy = self.get_text_for_copy();
-                let is_empty = text_for_copy.is_empty();
-                let me_weak = self.as_weak();
-                let mut copy_item = StandardMenuItem::new("Copy", move || {
-                    if let Ok(me) = me_weak.upgrade_mut() {
-                        me.copy();
-                    }
-                });
-                copy_item.set_disabled(is_empty);
-
-                let me_weak = self.as_weak();
-                let mut cut_item = StandardMenuItem::new("Cut", move || {
-                    if let Ok(mut me) = me_weak.upgrade_mut() {
-                        me.cut();
-                    }
-                });
-                cut_item.set_disabled(is_empty);
-                (cut_item, copy_item)
-            };
-            let paste_menu = {
-                let content = crate::ext::ext_clipboard::Clipboard::read_text()
-                    .ok()
-                    .unwrap_or_else(String::new);
-                let has_content = !content.is_empty();
-                let me_weak = self.as_weak();
-                let mut item = StandardMenuItem::new("Paste", move || {
-                    if let Ok(mut me) = me_weak.upgrade_mut() {
-                        me.paste();
-                    }
-                });
-                item.set_disabled(!has_content);
-                item
-            };
-            menu.add_item(MenuItem::Standard(cut_menu));
-            menu.add_item(MenuItem::Standard(copy_menu));
-            menu.add_item(MenuItem::Standard(paste_menu));
+            let has_selection = !self.get_text_for_copy().is_empty();
+            let has_content = crate::ext::ext_clipboard::Clipboard::read_text()
+                .map(|c| !c.is_empty())
+                .unwrap_or(false);
+            menu.add_item(
+                self.create_menu_item("Cut", "CmdOrCtrl+X", has_selection, |me| me.cut()),
+            );
+            menu.add_item(
+                self.create_menu_item("Copy", "CmdOrCtrl+C", has_selection, |me| me.copy()),
+            );
+            menu.add_item(
+                self.create_menu_item("Paste", "CmdOrCtrl+V", has_content, |me| me.paste()),
+            );
             menu.add_item(MenuItem::Separator);
         }
-        let select_all_menu = {
-            let me_weak = self.as_weak();
-            let content = self.paragraph.get_text();
-            let allow_select_all =
-                !content.is_empty() && Some(content) != self.paragraph.get_selection_text();
-            let mut item = StandardMenuItem::new("Select All", move || {
-                if let Ok(mut me) = me_weak.upgrade_mut() {
-                    me.paragraph.select_all();
-                }
-            });
-            item.set_disabled(!allow_select_all);
-            item
-        };
-        menu.add_item(MenuItem::Standard(select_all_menu));
+        let content = self.paragraph.get_text();
+        let allow_select_all =
+            !content.is_empty() && Some(content) != self.paragraph.get_selection_text();
+        menu.add_item(
+            self.create_menu_item("Select All", "CmdOrCtrl+A", allow_select_all, |me| {
+                me.select_all()
+            }),
+        );
+        if let Some(custom_menu) = &self.context_menu {
+            if !custom_menu.is_empty() {
+                menu.add_item(MenuItem::Separator);
+                menu.extend(custom_menu);
+            }
+        }
 
         if let Ok(e) = self.element.upgrade_mut() {
             if let Some(w) = e.get_window() {
@@ -753,6 +815,54 @@ impl Editable {
             }
         }
     }
+
+    fn create_menu_item<F: FnMut(&mut Editable) + 'static>(
+        &self,
+        label: &str,
+        accelerator: &str,
+        enabled: bool,
+        mut action: F,
+    ) -> crate::menu::MenuItem {
+        use crate::menu::{MenuItem, StandardMenuItem};
+        let me_weak = self.as_weak();
+        let mut item = StandardMenuItem::new(label, move || {
+            if let Ok(mut me) = me_weak.upgrade_mut() {
+                action(&mut me);
+            }
+        });
+        item.set_disabled(!enabled);
+        item.set_accelerator(Some(accelerator.to_string()));
+        MenuItem::Standard(item)
+    }
+
+    /// Remove uncommitted composition text and discard composition of input method
+    fn cancel_preedit(&mut self) {
+        if !self.preediting {
+            return;
+        }
+        self.preediting = false;
+        // Composition text is selected, see PreeditEvent handling
+        self.insert_text("", self.paragraph.get_caret(), false);
+        let element = ok_or_return!(self.element.upgrade());
+        element.with_window(|w| {
+            w.window.set_ime_allowed(false);
+            w.window.set_ime_allowed(true);
+        });
+    }
+
+    /// Bounds of text matching query, relative to the border box of element. Passwords are
+    /// never matched.
+    pub fn find_text(&mut self, query: &str, case_sensitive: bool) -> Vec<Rect> {
+        if self.input_type == InputType::Password {
+            return Vec::new();
+        }
+        let element = ok_or_return!(self.element.upgrade(), Vec::new());
+        let (bt, _, _, bl) = element.get_border_width();
+        find_in_text_box(&mut self.paragraph, query, case_sensitive)
+            .into_iter()
+            .map(|r| Rect::new(r.x + bl, r.y + bt, r.width, r.height))
+            .collect()
+    }
 }
 
 impl ElementBackend for Editable {
@@ -809,6 +919,9 @@ impl ElementBackend for Editable {
             line_height: None,
             auto_height: true,
             layout_dirty: true,
+            mask: None,
+            preediting: false,
+            context_menu: None,
         }
         .to_ref();
         inst.set_multiple_line(false);
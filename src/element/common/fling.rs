@@ -0,0 +1,352 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Samples older than this relative to the latest one are ignored, in milliseconds
+const VELOCITY_WINDOW: f32 = 100.0;
+/// Fling stops once the speed falls below this, in pixels per millisecond
+pub const MIN_FLING_VELOCITY: f32 = 0.05;
+/// Speed of flings is capped to this, in pixels per millisecond
+const MAX_FLING_VELOCITY: f32 = 8.0;
+/// Angular frequency of the spring pulling overscrolled content back, per millisecond
+const SPRING_FREQUENCY: f32 = 0.015;
+/// Overscroll smaller than this is snapped to the edge when settling
+const SETTLE_DISTANCE: f32 = 0.5;
+/// Resistance of rubber band when content is dragged beyond the edge
+const RUBBER_BAND_COEFFICIENT: f32 = 0.55;
+/// Time step of simulation in milliseconds
+const STEP: f32 = 1.0;
+/// Frames delayed more than this are simulated as if they were on time, in milliseconds
+const MAX_FRAME_INTERVAL: f32 = 100.0;
+
+pub const DECELERATION_RATE_NORMAL: f32 = 0.998;
+pub const DECELERATION_RATE_FAST: f32 = 0.99;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollPhysics {
+    /// Fraction of velocity kept after each millisecond of fling
+    pub deceleration_rate: f32,
+    /// Whether content moves beyond the edges and springs back
+    pub bounce: bool,
+}
+
+impl Default for ScrollPhysics {
+    fn default() -> Self {
+        Self {
+            deceleration_rate: DECELERATION_RATE_NORMAL,
+            bounce: false,
+        }
+    }
+}
+
+impl ScrollPhysics {
+    /// Parse deceleration rate by name ("normal" or "fast") or number
+    pub fn parse_deceleration_rate(value: &str) -> Option<f32> {
+        let rate = match value {
+            "normal" => DECELERATION_RATE_NORMAL,
+            "fast" => DECELERATION_RATE_FAST,
+            v => v.parse::<f32>().ok()?,
+        };
+        Self::is_valid_deceleration_rate(rate).then_some(rate)
+    }
+
+    pub fn is_valid_deceleration_rate(rate: f32) -> bool {
+        rate > 0.0 && rate < 1.0
+    }
+}
+
+/// Estimate velocity of a pointer from its recent positions
+pub struct VelocityTracker {
+    origin: Instant,
+    /// (time in milliseconds, x, y)
+    samples: VecDeque<(f32, f32, f32)>,
+}
+
+impl VelocityTracker {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.origin = Instant::now();
+        self.samples.clear();
+    }
+
+    pub fn add(&mut self, x: f32, y: f32) {
+        self.add_sample(self.now(), x, y);
+    }
+
+    pub fn add_sample(&mut self, time: f32, x: f32, y: f32) {
+        self.samples.push_back((time, x, y));
+        while let Some((t, _, _)) = self.samples.front() {
+            if time - *t <= VELOCITY_WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Current velocity in pixels per millisecond
+    pub fn velocity(&self) -> (f32, f32) {
+        self.velocity_at(self.now())
+    }
+
+    /// Velocity at `time` fitted by least squares, which is zero if the pointer has rested
+    pub fn velocity_at(&self, time: f32) -> (f32, f32) {
+        let samples: Vec<_> = self
+            .samples
+            .iter()
+            .filter(|(t, _, _)| time - *t <= VELOCITY_WINDOW)
+            .collect();
+        if samples.len() < 2 {
+            return (0.0, 0.0);
+        }
+        let n = samples.len() as f32;
+        let (mut st, mut sx, mut sy) = (0.0, 0.0, 0.0);
+        for (t, x, y) in &samples {
+            st += t;
+            sx += x;
+            sy += y;
+        }
+        let (mt, mx, my) = (st / n, sx / n, sy / n);
+        let (mut stt, mut stx, mut sty) = (0.0, 0.0, 0.0);
+        for (t, x, y) in &samples {
+            let dt = t - mt;
+            stt += dt * dt;
+            stx += dt * (x - mx);
+            sty += dt * (y - my);
+        }
+        if stt <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let clamp = |v: f32| v.clamp(-MAX_FLING_VELOCITY, MAX_FLING_VELOCITY);
+        (clamp(stx / stt), clamp(sty / stt))
+    }
+
+    fn now(&self) -> f32 {
+        self.origin.elapsed().as_secs_f32() * 1000.0
+    }
+}
+
+/// Displacement of content dragged `overscroll` beyond the edge of a viewport of `dimension`
+pub fn rubber_band(overscroll: f32, dimension: f32) -> f32 {
+    if dimension <= 0.0 {
+        return 0.0;
+    }
+    let c = RUBBER_BAND_COEFFICIENT;
+    let distance = (1.0 - 1.0 / (overscroll.abs() * c / dimension + 1.0)) * dimension;
+    distance.copysign(overscroll)
+}
+
+/// Overscroll that displaces content by `displacement`, inverse of `rubber_band`
+pub fn rubber_band_inverse(displacement: f32, dimension: f32) -> f32 {
+    if dimension <= 0.0 {
+        return 0.0;
+    }
+    let ratio = (displacement.abs() / dimension).min(0.99);
+    let overscroll = (1.0 / (1.0 - ratio) - 1.0) * dimension / RUBBER_BAND_COEFFICIENT;
+    overscroll.copysign(displacement)
+}
+
+/// Motion of scroll offset along one axis after a fling, the offset exceeds `[0, max]` only if
+/// bouncing is enabled
+#[derive(Debug, Clone)]
+pub struct FlingAxis {
+    position: f32,
+    /// Pixels per millisecond
+    velocity: f32,
+    max: f32,
+    physics: ScrollPhysics,
+    settled: bool,
+}
+
+impl FlingAxis {
+    pub fn new(position: f32, velocity: f32, max: f32, physics: ScrollPhysics) -> Self {
+        let mut axis = Self {
+            position,
+            velocity,
+            max: max.max(0.0),
+            physics,
+            settled: false,
+        };
+        if !physics.bounce {
+            axis.position = position.clamp(0.0, axis.max);
+        }
+        axis.settled = axis.is_at_rest();
+        axis
+    }
+
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.settled
+    }
+
+    /// Distance beyond the nearest edge, negative beyond the start edge
+    pub fn overscroll(&self) -> f32 {
+        if self.position < 0.0 {
+            self.position
+        } else if self.position > self.max {
+            self.position - self.max
+        } else {
+            0.0
+        }
+    }
+
+    /// Advance by `elapsed` milliseconds, returns false once settled
+    pub fn step(&mut self, elapsed: f32) -> bool {
+        let mut remaining = elapsed.clamp(0.0, MAX_FRAME_INTERVAL);
+        while !self.settled && remaining > 0.0 {
+            let dt = remaining.min(STEP);
+            remaining -= dt;
+            self.step_once(dt);
+            if self.is_at_rest() {
+                self.settle();
+            }
+        }
+        !self.settled
+    }
+
+    /// Offset where the fling ends
+    pub fn rest_position(&self) -> f32 {
+        if self.settled {
+            return self.position;
+        }
+        if self.overscroll() != 0.0 {
+            return self.position.clamp(0.0, self.max);
+        }
+        // Integral of velocity decaying by rate every millisecond
+        let distance = -self.velocity / self.physics.deceleration_rate.ln();
+        (self.position + distance).clamp(0.0, self.max)
+    }
+
+    pub fn finish(&mut self) {
+        self.position = self.rest_position();
+        self.velocity = 0.0;
+        self.settled = true;
+    }
+
+    fn step_once(&mut self, dt: f32) {
+        let overscroll = self.overscroll();
+        if overscroll != 0.0 {
+            // Critically damped spring towards the edge
+            let w = SPRING_FREQUENCY;
+            let acceleration = -w * w * overscroll - 2.0 * w * self.velocity;
+            self.velocity += acceleration * dt;
+            let new_position = self.position + self.velocity * dt;
+            // Do not pass through the edge
+            let edge = if overscroll < 0.0 { 0.0 } else { self.max };
+            if (new_position - edge).signum() != overscroll.signum() {
+                self.position = edge;
+                self.velocity = 0.0;
+            } else {
+                self.position = new_position;
+            }
+            return;
+        }
+        self.velocity *= self.physics.deceleration_rate.powf(dt);
+        let new_position = self.position + self.velocity * dt;
+        if !self.physics.bounce {
+            let clamped = new_position.clamp(0.0, self.max);
+            if clamped != new_position {
+                self.velocity = 0.0;
+            }
+            self.position = clamped;
+        } else {
+            self.position = new_position;
+        }
+    }
+
+    fn is_at_rest(&self) -> bool {
+        let overscroll = self.overscroll();
+        if overscroll != 0.0 {
+            overscroll.abs() < SETTLE_DISTANCE && self.velocity.abs() < MIN_FLING_VELOCITY
+        } else {
+            self.velocity.abs() < MIN_FLING_VELOCITY
+        }
+    }
+
+    fn settle(&mut self) {
+        self.position = self.position.clamp(0.0, self.max);
+        self.velocity = 0.0;
+        self.settled = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::common::fling::{
+        rubber_band, rubber_band_inverse, FlingAxis, ScrollPhysics, VelocityTracker,
+        DECELERATION_RATE_FAST,
+    };
+
+    #[test]
+    fn test_velocity_tracker() {
+        let mut tracker = VelocityTracker::new();
+        assert_eq!(tracker.velocity_at(0.0), (0.0, 0.0));
+        // Stale samples are dropped
+        tracker.add_sample(0.0, 500.0, 0.0);
+        for i in 0..10 {
+            let t = 300.0 + i as f32 * 10.0;
+            tracker.add_sample(t, t * 0.5, -t);
+        }
+        let (vx, vy) = tracker.velocity_at(390.0);
+        assert!((vx - 0.5).abs() < 1e-3);
+        assert!((vy + 1.0).abs() < 1e-3);
+        // Released after resting
+        assert_eq!(tracker.velocity_at(600.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_fling() {
+        let physics = ScrollPhysics::default();
+        let mut axis = FlingAxis::new(100.0, 2.0, 10000.0, physics);
+        let rest = axis.rest_position();
+        let mut frames = 0;
+        while axis.step(16.0) {
+            frames += 1;
+            assert!(frames < 1000);
+        }
+        assert!((axis.position() - rest).abs() < 30.0);
+        assert!(axis.position() > 100.0);
+
+        // Stop at the edge without bouncing
+        let mut axis = FlingAxis::new(100.0, -2.0, 10000.0, physics);
+        axis.step(100.0);
+        assert_eq!(axis.overscroll(), 0.0);
+        while axis.step(16.0) {}
+        assert_eq!(axis.position(), 0.0);
+
+        let physics = ScrollPhysics {
+            deceleration_rate: DECELERATION_RATE_FAST,
+            bounce: true,
+        };
+        let mut axis = FlingAxis::new(100.0, -2.0, 10000.0, physics);
+        let mut min_overscroll = 0.0f32;
+        while axis.step(16.0) {
+            min_overscroll = min_overscroll.min(axis.overscroll());
+        }
+        assert!(min_overscroll < 0.0);
+        assert_eq!(axis.position(), 0.0);
+    }
+
+    #[test]
+    fn test_rubber_band() {
+        assert_eq!(rubber_band(0.0, 500.0), 0.0);
+        let d = rubber_band(100.0, 500.0);
+        assert!(d > 0.0 && d < 100.0);
+        assert_eq!(rubber_band(-100.0, 500.0), -d);
+        assert!(rubber_band(100000.0, 500.0) < 500.0);
+        assert!((rubber_band_inverse(d, 500.0) - 100.0).abs() < 1e-2);
+        assert!((rubber_band_inverse(-d, 500.0) + 100.0).abs() < 1e-2);
+        assert_eq!(
+            ScrollPhysics::parse_deceleration_rate("fast"),
+            Some(DECELERATION_RATE_FAST)
+        );
+        assert_eq!(ScrollPhysics::parse_deceleration_rate("1.5"), None);
+    }
+}
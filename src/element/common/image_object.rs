@@ -1,5 +1,6 @@
 use crate::element::common::svg_object::SvgObject;
-use crate::img_manager::{dyn_image_to_skia_image, IMG_MANAGER};
+use crate::img_manager::{decode_image, IMG_MANAGER};
+use crate::protocol::{self, ProtocolRequest};
 use crate::render::RenderFn;
 use crate::resource::Resource;
 use anyhow::Error;
@@ -7,7 +8,7 @@ use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 use image::{EncodableLayout, ImageReader};
 use log::error;
-use skia_safe::Color;
+use skia_safe::{surfaces, Color};
 use std::io::Cursor;
 
 #[derive(Clone)]
@@ -83,6 +84,25 @@ impl ImageObject {
         self.img.get_size()
     }
 
+    /// Image which could be drawn without the object, svg is rasterized at `scale`
+    pub fn to_raster_image(&self, scale: f32) -> Option<skia_safe::Image> {
+        match &self.img {
+            ImageSrc::Img(img) => Some(img.clone()),
+            ImageSrc::Svg(dom) => {
+                let (width, height) = dom.container_size();
+                let size = (
+                    (width * scale).ceil() as i32,
+                    (height * scale).ceil() as i32,
+                );
+                let mut surface = surfaces::raster_n32_premul(size)?;
+                dom.set_color(self.color);
+                dom.render(surface.canvas(), scale);
+                Some(surface.image_snapshot())
+            }
+            ImageSrc::None => None,
+        }
+    }
+
     pub fn render(&self) -> RenderFn {
         let (width, height) = self.container_size;
         let (img_width, img_height) = self.img.get_size();
@@ -107,6 +127,25 @@ impl ImageObject {
     }
 
     fn load(src: &str) -> ImageSrc {
+        if let Some(result) = protocol::load_sync(&ProtocolRequest::get(src)) {
+            return match result {
+                Ok(response) => {
+                    let is_svg = match response.content_type() {
+                        Some(mime) => mime.starts_with("image/svg"),
+                        None => src.ends_with(".svg"),
+                    };
+                    if is_svg {
+                        Self::load_svg_from_data(&response.body)
+                    } else {
+                        Self::load_image_from_data(&response.body)
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to load image {}: {:?}", src, e);
+                    ImageSrc::None
+                }
+            };
+        }
         if let Some(data_url) = src.strip_prefix("data:") {
             if let Some((mime, data)) = Self::parse_data_url(data_url) {
                 if mime.starts_with("image/svg") {
@@ -151,11 +190,8 @@ impl ImageObject {
     }
 
     fn load_image_from_data(data: &Vec<u8>) -> ImageSrc {
-        match ImageReader::new(Cursor::new(data)).decode() {
-            Ok(img) => {
-                let sk_img = dyn_image_to_skia_image(&img);
-                ImageSrc::Img(sk_img)
-            }
+        match decode_image(ImageReader::new(Cursor::new(data))) {
+            Ok(sk_img) => ImageSrc::Img(sk_img),
             Err(e) => {
                 error!("Failed to load image: {:?}", e);
                 ImageSrc::None
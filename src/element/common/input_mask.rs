@@ -0,0 +1,135 @@
+/// Slot of input mask, `#` accepts a digit, `A` a letter and `*` a letter or digit.
+/// Other characters are literals, a mask character could be escaped by `\` to be a literal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MaskSlot {
+    Digit,
+    Letter,
+    Alphanumeric,
+    Literal(char),
+}
+
+impl MaskSlot {
+    fn accepts(&self, c: char) -> bool {
+        match self {
+            MaskSlot::Digit => c.is_ascii_digit(),
+            MaskSlot::Letter => c.is_alphabetic(),
+            MaskSlot::Alphanumeric => c.is_alphanumeric(),
+            MaskSlot::Literal(_) => false,
+        }
+    }
+}
+
+/// Input mask like `(###) ###-####` or `####-##-##`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputMask {
+    source: String,
+    slots: Vec<MaskSlot>,
+}
+
+impl InputMask {
+    pub fn parse(mask: &str) -> Option<Self> {
+        let mut slots = Vec::new();
+        let mut chars = mask.chars();
+        while let Some(c) = chars.next() {
+            let slot = match c {
+                '#' => MaskSlot::Digit,
+                'A' => MaskSlot::Letter,
+                '*' => MaskSlot::Alphanumeric,
+                '\\' => MaskSlot::Literal(chars.next()?),
+                c => MaskSlot::Literal(c),
+            };
+            slots.push(slot);
+        }
+        if slots.iter().all(|s| matches!(s, MaskSlot::Literal(_))) {
+            return None;
+        }
+        Some(Self {
+            source: mask.to_string(),
+            slots,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Format text with the mask, returns formatted text and the new char offset of caret.
+    /// Literals typed or pasted by user are kept if they match the mask, characters not
+    /// accepted by the mask are dropped and literals are inserted before accepted characters,
+    /// so caret skips over them.
+    pub fn format(&self, text: &str, caret: usize) -> (String, usize) {
+        let mut result = String::new();
+        let mut result_len = 0;
+        let mut new_caret = None;
+        let mut slot_idx = 0;
+        for (i, c) in text.chars().enumerate() {
+            if i == caret {
+                new_caret = Some(result_len);
+            }
+            if slot_idx >= self.slots.len() {
+                break;
+            }
+            if self.slots[slot_idx] == MaskSlot::Literal(c) {
+                result.push(c);
+                result_len += 1;
+                slot_idx += 1;
+                continue;
+            }
+            let next_input = match self.slots[slot_idx..]
+                .iter()
+                .position(|s| !matches!(s, MaskSlot::Literal(_)))
+            {
+                Some(p) => slot_idx + p,
+                None => break,
+            };
+            if !self.slots[next_input].accepts(c) {
+                continue;
+            }
+            for slot in &self.slots[slot_idx..next_input] {
+                if let MaskSlot::Literal(l) = slot {
+                    result.push(*l);
+                    result_len += 1;
+                }
+            }
+            result.push(c);
+            result_len += 1;
+            slot_idx = next_input + 1;
+        }
+        (result, new_caret.unwrap_or(result_len))
+    }
+
+    /// Characters filled in input slots of formatted text
+    pub fn unmask(&self, text: &str) -> String {
+        text.chars()
+            .zip(self.slots.iter())
+            .filter(|(_, s)| !matches!(s, MaskSlot::Literal(_)))
+            .map(|(c, _)| c)
+            .collect()
+    }
+
+    /// Whether all slots of the mask are filled
+    pub fn is_complete(&self, text: &str) -> bool {
+        let (formatted, _) = self.format(text, 0);
+        formatted.chars().count() == self.slots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::common::input_mask::InputMask;
+
+    #[test]
+    fn test_format() {
+        let mask = InputMask::parse("(###) ###-####").unwrap();
+        assert_eq!(mask.format("5", 1), ("(5".to_string(), 2));
+        assert_eq!(mask.format("(5551", 5), ("(555) 1".to_string(), 7));
+        assert_eq!(mask.format("555-123-4567", 12), ("(555) 123-4567".to_string(), 14));
+        assert_eq!(mask.format("(555) x1", 7), ("(555) 1".to_string(), 6));
+        assert_eq!(mask.unmask("(555) 123-4567"), "5551234567");
+        assert!(mask.is_complete("(555) 123-4567"));
+        assert!(!mask.is_complete("(555) 123"));
+        assert!(InputMask::parse("--").is_none());
+        let escaped = InputMask::parse("\\#A").unwrap();
+        assert_eq!(escaped.format("b", 1), ("#b".to_string(), 2));
+    }
+}
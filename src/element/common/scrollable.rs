@@ -2,36 +2,103 @@ use crate as deft;
 use crate::animation::actor::AnimationActor;
 use crate::animation::{AnimationInstance, WindowAnimationController};
 use crate::base::{EventContext, Rect};
+use crate::element::common::fling::{
+    rubber_band, rubber_band_inverse, FlingAxis, ScrollPhysics, VelocityTracker, MIN_FLING_VELOCITY,
+};
 use crate::element::common::ScrollBar;
 use crate::element::scroll::Scroll;
 use crate::element::{Element, ElementWeak};
-use crate::event::{Event, TouchCancelEvent, TouchEndEvent, TouchMoveEvent, TouchStartEvent};
+use crate::event::{
+    Event, KeyDownEvent, KeyEventDetail, TouchCancelEvent, TouchEndEvent, TouchMoveEvent,
+    TouchStartEvent, WheelEvent,
+};
 use crate::number::DeNan;
 use crate::render::RenderFn;
 use crate::style::ResolvedStyleProp;
-use crate::{is_mobile_platform, some_or_return};
+use crate::timer::{set_timeout, TimerHandle};
+use crate::{is_mobile_platform, js_deserialize, js_serialize, ok_or_return, some_or_return};
 use bezier_rs::{Bezier, TValue};
 use deft_macros::mrc_object;
 use log::debug;
+use serde::{Deserialize, Serialize};
 use std::cell::Cell;
 use std::collections::HashMap;
-use std::time::Instant;
+use winit::keyboard::NamedKey;
 use yoga::Direction::LTR;
 
+/// Distance of arrow keys
+const KEYBOARD_LINE_DISTANCE: f32 = 40.0;
+/// Duration of animated keyboard scrolling in milliseconds
+const KEYBOARD_SCROLL_DURATION: f32 = 150.0;
+/// Duration of `scrollTo` with smooth behavior in milliseconds
+pub const SMOOTH_SCROLL_DURATION: f32 = 300.0;
+/// Upper bound of fling duration in milliseconds, flings settle long before it
+const FLING_MAX_DURATION: f32 = 10000.0;
+/// Delay after the last scroll offset change before scrolling is considered ended in milliseconds
+const SCROLL_END_DELAY: u64 = 150;
+/// Ratio of indicator movement to overscroll distance when pulling to refresh
+const PULL_RESISTANCE: f32 = 0.5;
+/// Pull distance to trigger refresh if there is no indicator
+const DEFAULT_REFRESH_THRESHOLD: f32 = 60.0;
+/// Duration of settling or collapsing the refresh indicator in milliseconds
+const PULL_ANIMATION_DURATION: f32 = 200.0;
+
 thread_local! {
     static CONSUMED_EVENT_ID: Cell<u64> = Cell::new(0);
 }
 
+/// Saved scroll position, which is restored relative to the anchor if it still exists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollState {
+    pub scroll_left: f32,
+    pub scroll_top: f32,
+    /// Id of the element at the top edge of viewport
+    pub anchor: Option<u32>,
+    /// Distance from the top edge of viewport to the top of anchor
+    pub anchor_offset: f32,
+}
+js_serialize!(ScrollState);
+js_deserialize!(ScrollState);
+
 #[mrc_object]
 pub struct Scrollable {
     pub vertical_bar: ScrollBar,
     pub horizontal_bar: ScrollBar,
-    momentum_info: Option<crate::element::scroll::MomentumInfo>,
-    momentum_animation_instance: Option<AnimationInstance>,
+    /// Velocity of touch dragging, which the content keeps when released
+    velocity_tracker: VelocityTracker,
+    physics: ScrollPhysics,
+    /// Distance the content is dragged or flung beyond the (left, top) or (right, bottom) edges
+    overscroll: (f32, f32),
+    overscroll_callback: Box<dyn FnMut()>,
+    /// Fling, keyboard or smooth scroll animation
+    scroll_animation_instance: Option<AnimationInstance>,
+    /// Increased when scroll animation is replaced, so that the stale one does not apply its end
+    scroll_animation_id: u64,
     vertical_move_begin: Option<(f32, f32)>,
     /// (mouse_offset, scroll_offset)
     horizontal_move_begin: Option<(f32, f32)>,
     auto_scroll_callback: Option<Box<dyn FnOnce() -> Option<Rect>>>,
+    /// State to restore after the next layout
+    pending_state: Option<ScrollState>,
+    /// Distance to the top or bottom edge within which the edge is considered reached
+    edge_threshold: f32,
+    /// Whether (top, bottom) edges are reached
+    edges_reached: (bool, bool),
+    scroll_end_timer: Option<TimerHandle>,
+    pull_to_refresh: bool,
+    /// Element revealed above content when pulling
+    refresh_indicator: Option<ElementWeak>,
+    /// Distance the content is pulled down beyond the top edge
+    pull_distance: f32,
+    refreshing: bool,
+    pull_callback: Box<dyn FnMut()>,
+    refresh_callback: Box<dyn FnMut()>,
+    /// Release trackpad pulling once wheel events stop
+    pull_release_timer: Option<TimerHandle>,
+    pull_animation_instance: Option<AnimationInstance>,
+    /// Increased when pull animation is replaced, so that the stale one does not apply its end
+    pull_animation_id: u64,
 }
 
 impl Scrollable {
@@ -41,11 +108,28 @@ impl Scrollable {
         ScrollableData {
             vertical_bar,
             horizontal_bar,
-            momentum_animation_instance: None,
-            momentum_info: None,
+            scroll_animation_instance: None,
+            scroll_animation_id: 0,
+            velocity_tracker: VelocityTracker::new(),
+            physics: ScrollPhysics::default(),
+            overscroll: (0.0, 0.0),
+            overscroll_callback: Box::new(|| {}),
             vertical_move_begin: None,
             horizontal_move_begin: None,
             auto_scroll_callback: None,
+            pending_state: None,
+            edge_threshold: 0.0,
+            edges_reached: (true, false),
+            scroll_end_timer: None,
+            pull_to_refresh: false,
+            refresh_indicator: None,
+            pull_distance: 0.0,
+            refreshing: false,
+            pull_callback: Box::new(|| {}),
+            refresh_callback: Box::new(|| {}),
+            pull_release_timer: None,
+            pull_animation_instance: None,
+            pull_animation_id: 0,
         }
         .to_ref()
     }
@@ -72,12 +156,62 @@ impl Scrollable {
         RenderFn::merge(vec![vertical_bar, horizontal_bar])
     }
 
+    /// Offset of content, which is beyond the edges when pulled to refresh or bouncing
     pub fn scroll_offset(&self) -> (f32, f32) {
-        let offset_y = self.vertical_bar.scroll_offset();
-        let offset_x = self.horizontal_bar.scroll_offset();
+        let offset_y = self.vertical_bar.scroll_offset() - self.pull_distance + self.overscroll.1;
+        let offset_x = self.horizontal_bar.scroll_offset() + self.overscroll.0;
         (offset_x, offset_y)
     }
 
+    pub fn set_physics(&mut self, physics: ScrollPhysics) {
+        self.physics = physics;
+        if !physics.bounce {
+            self.set_overscroll((0.0, 0.0));
+        }
+    }
+
+    pub fn physics(&self) -> ScrollPhysics {
+        self.physics
+    }
+
+    /// Called when the content is moved beyond the edges by bouncing
+    pub fn set_overscroll_callback<F: FnMut() + 'static>(&mut self, callback: F) {
+        self.overscroll_callback = Box::new(callback);
+    }
+
+    fn set_overscroll(&mut self, overscroll: (f32, f32)) {
+        if self.overscroll != overscroll {
+            self.overscroll = overscroll;
+            (self.overscroll_callback)();
+        }
+    }
+
+    /// Whether the content could be moved beyond the edges along (x, y) axes
+    fn can_bounce(&self) -> (bool, bool) {
+        let bounce = self.physics.bounce;
+        (
+            bounce && self.horizontal_bar.is_scrollable(),
+            bounce && self.vertical_bar.is_scrollable(),
+        )
+    }
+
+    /// Set scroll offsets which may be beyond the edges, the exceeded part is kept as overscroll
+    fn set_unclamped_offset(&mut self, left: f32, top: f32) {
+        self.horizontal_bar.update_scroll_offset(left);
+        self.vertical_bar.update_scroll_offset(top);
+        let (bounce_x, bounce_y) = self.can_bounce();
+        let mut overscroll_x = left - self.horizontal_bar.scroll_offset();
+        let mut overscroll_y = top - self.vertical_bar.scroll_offset();
+        if !bounce_x {
+            overscroll_x = 0.0;
+        }
+        // The top edge is pulled to refresh instead
+        if !bounce_y || (self.pull_to_refresh && overscroll_y < 0.0) {
+            overscroll_y = 0.0;
+        }
+        self.set_overscroll((overscroll_x, overscroll_y));
+    }
+
     pub fn set_autoscroll_callback<F: FnOnce() -> Option<Rect> + 'static>(
         &mut self,
         autoscroll_callback: F,
@@ -96,9 +230,20 @@ impl Scrollable {
         element: &Element,
     ) -> bool {
         let event_id = ctx.get_id();
-        if !self.is_scrollable() || CONSUMED_EVENT_ID.get() == event_id {
+        if !(self.is_scrollable() || self.pull_to_refresh) || CONSUMED_EVENT_ID.get() == event_id {
             return false;
         }
+        if let Some(e) = WheelEvent::cast(event) {
+            // Wheel takes over flinging content
+            if self.scroll_animation_instance.is_some() {
+                self.stop_scroll_animation();
+                self.set_overscroll((0.0, 0.0));
+            }
+            if self.pull_to_refresh && self.pull_by_wheel(e, element) {
+                CONSUMED_EVENT_ID.set(event_id);
+                return true;
+            }
+        }
         let accepted =
             self.vertical_bar.on_event(&event, ctx) || self.horizontal_bar.on_event(&event, ctx);
         if accepted {
@@ -113,15 +258,14 @@ impl Scrollable {
                         None => return false,
                         Some(v) => v,
                     };
+                // Catch the content where it is, including the part beyond the edges
+                self.stop_scroll_animation();
                 self.begin_scroll_x(-window_x);
                 self.begin_scroll_y(-window_y);
                 debug!("touch start: pos {:?}", (window_x, window_y));
-                self.momentum_info = Some(crate::element::scroll::MomentumInfo {
-                    start_time: Instant::now(),
-                    start_left: self.horizontal_bar.scroll_offset,
-                    start_top: self.vertical_bar.scroll_offset,
-                });
-                self.momentum_animation_instance = None;
+                self.velocity_tracker.reset();
+                self.velocity_tracker.add(-window_x, -window_y);
+                self.stop_pull_animation();
                 CONSUMED_EVENT_ID.set(event_id);
                 return false;
             } else if let Some(e) = TouchMoveEvent::cast(event) {
@@ -135,72 +279,432 @@ impl Scrollable {
                     };
                 self.update_scroll_x(-window_x);
                 self.update_scroll_y(-window_y);
-                let left = self.horizontal_bar.scroll_offset;
-                let top = self.vertical_bar.scroll_offset;
+                self.velocity_tracker.add(-window_x, -window_y);
                 // debug!("touch updated: {:?}", (window_x, window_y));
-                if let Some(momentum_info) = &mut self.momentum_info {
-                    if momentum_info.start_time.elapsed().as_millis() as f32
-                        > crate::element::scroll::MOMENTUM_DURATION
-                    {
-                        momentum_info.start_time = Instant::now();
-                        momentum_info.start_left = left;
-                        momentum_info.start_top = top;
-                    }
-                }
                 CONSUMED_EVENT_ID.set(event_id);
                 return false;
             } else if let Some(e) = TouchEndEvent::cast(event) {
-                debug!("touch end: {:?} {}", e.0, self.momentum_info.is_some());
-                if let Some(momentum_info) = &self.momentum_info {
-                    let duration =
-                        momentum_info.start_time.elapsed().as_nanos() as f32 / 1000_000.0;
-                    let horizontal_distance = self.scroll_offset().0 - momentum_info.start_left;
-                    let vertical_distance = self.scroll_offset().1 - momentum_info.start_top;
-                    let max_distance = f32::max(horizontal_distance.abs(), vertical_distance.abs());
-                    debug!("touch end: info{:?}", (duration, vertical_distance));
-                    if duration < crate::element::scroll::MOMENTUM_DURATION
-                        && max_distance > crate::element::scroll::MOMENTUM_DISTANCE
-                    {
-                        let horizontal_speed =
-                            crate::element::scroll::calculate_speed(horizontal_distance, duration);
-                        let vertical_speed =
-                            crate::element::scroll::calculate_speed(vertical_distance, duration);
-                        debug!("speed: {} {}", horizontal_speed, vertical_speed);
-                        let (old_left, old_top) = self.scroll_offset();
-                        let left_dist = horizontal_speed / 0.003;
-                        let top_dist = vertical_speed / 0.003;
-                        debug!(
-                            "scroll params: {} {} {} {} {}",
-                            old_left, old_top, left_dist, top_dist, duration
-                        );
-                        let actor = ScrollAnimationActor::new(
-                            self.clone(),
-                            old_left,
-                            old_top,
-                            left_dist,
-                            top_dist,
-                        );
-                        let window = some_or_return!(element.get_window(), false);
-                        let fc = WindowAnimationController::new(window);
-                        let mut ai =
-                            AnimationInstance::new(actor, 1000.0 * 1000000.0, 1.0, Box::new(fc));
-                        ai.run();
-                        self.momentum_animation_instance = Some(ai);
-                    }
-                }
-                self.momentum_info = None;
+                debug!("touch end: {:?}", e.0);
+                let is_touch_scrolling = self.is_touch_scrolling();
                 self.end_scroll();
+                if is_touch_scrolling {
+                    let (velocity_x, velocity_y) = self.velocity_tracker.velocity();
+                    debug!("touch end: velocity {:?}", (velocity_x, velocity_y));
+                    self.fling(element, velocity_x, velocity_y);
+                }
+                self.release_pull(element);
                 CONSUMED_EVENT_ID.set(event_id);
                 return false;
             } else if let Some(_e) = TouchCancelEvent::cast(event) {
+                let is_touch_scrolling = self.is_touch_scrolling();
                 self.end_scroll();
-                self.momentum_info = None;
+                // Spring back without momentum
+                if is_touch_scrolling {
+                    self.fling(element, 0.0, 0.0);
+                }
+                self.release_pull(element);
                 return false;
             }
         }
         accepted
     }
 
+    /// Scroll by keyboard, return false if the key is not handled or already scrolled to the edge
+    /// so that ancestors could scroll instead
+    pub fn execute_default_behavior(&mut self, event: &Event, element: &Element) -> bool {
+        match KeyDownEvent::cast(event) {
+            Some(e) => self.handle_key_down(&e.0, element),
+            None => false,
+        }
+    }
+
+    fn handle_key_down(&mut self, detail: &KeyEventDetail, element: &Element) -> bool {
+        if !self.is_scrollable() || detail.ctrl_key || detail.alt_key || detail.meta_key {
+            return false;
+        }
+        let named_key = some_or_return!(&detail.named_key, false);
+        let line = KEYBOARD_LINE_DISTANCE;
+        // Keep a line visible when scrolling by page
+        let page = (self.vertical_bar.length() - line).max(line);
+        let (left, top) = self.scroll_offset();
+        let max_left = self.horizontal_bar.get_max_scroll_offset();
+        let max_top = self.vertical_bar.get_max_scroll_offset();
+        let (new_left, new_top) = match named_key {
+            NamedKey::ArrowUp => (left, top - line),
+            NamedKey::ArrowDown => (left, top + line),
+            NamedKey::ArrowLeft => (left - line, top),
+            NamedKey::ArrowRight => (left + line, top),
+            NamedKey::PageUp => (left, top - page),
+            NamedKey::PageDown => (left, top + page),
+            NamedKey::Home => (left, 0.0),
+            NamedKey::End => (left, max_top),
+            NamedKey::Space if detail.shift_key => (left, top - page),
+            NamedKey::Space => (left, top + page),
+            _ => return false,
+        };
+        let new_left = new_left.clamp(0.0, max_left);
+        let new_top = new_top.clamp(0.0, max_top);
+        if new_left == left && new_top == top {
+            return false;
+        }
+        self.animate_scroll_to(element, new_left, new_top, KEYBOARD_SCROLL_DURATION);
+        true
+    }
+
+    /// Scroll to (left, top) in `duration` milliseconds, replacing the running fling or scroll
+    /// animation
+    pub fn animate_scroll_to(&mut self, element: &Element, left: f32, top: f32, duration: f32) {
+        self.stop_scroll_animation();
+        self.set_overscroll((0.0, 0.0));
+        let left = left.clamp(0.0, self.horizontal_bar.get_max_scroll_offset());
+        let top = top.clamp(0.0, self.vertical_bar.get_max_scroll_offset());
+        let (old_left, old_top) = self.scroll_offset();
+        let window = match element.get_window() {
+            Some(w) => w,
+            None => {
+                self.horizontal_bar.set_scroll_offset(left);
+                self.vertical_bar.set_scroll_offset(top);
+                return;
+            }
+        };
+        let (left_dist, top_dist) = (left - old_left, top - old_top);
+        let actor = ScrollAnimationActor::new(self.clone(), old_left, old_top, left_dist, top_dist);
+        let fc = WindowAnimationController::new(window);
+        let duration = duration * 1000000.0;
+        let mut ai = AnimationInstance::new(actor, duration, 1.0, Box::new(fc));
+        ai.run();
+        self.scroll_animation_instance = Some(ai);
+    }
+
+    /// Keep scrolling with velocity in pixels per millisecond after touch is released, the
+    /// content springs back if it is beyond the edges
+    fn fling(&mut self, element: &Element, velocity_x: f32, velocity_y: f32) {
+        let (bounce_x, bounce_y) = self.can_bounce();
+        let axis = |bar: &ScrollBar, overscroll: f32, velocity: f32, bounce: bool| {
+            // Flinging along an axis that could not scroll makes nothing move
+            let velocity = if bar.is_scrollable() && velocity.abs() >= MIN_FLING_VELOCITY {
+                velocity
+            } else {
+                0.0
+            };
+            let physics = ScrollPhysics {
+                bounce,
+                ..self.physics
+            };
+            let position = bar.scroll_offset() + overscroll;
+            FlingAxis::new(position, velocity, bar.get_max_scroll_offset(), physics)
+        };
+        let x = axis(
+            &self.horizontal_bar,
+            self.overscroll.0,
+            velocity_x,
+            bounce_x,
+        );
+        let y = axis(&self.vertical_bar, self.overscroll.1, velocity_y, bounce_y);
+        if x.is_settled() && y.is_settled() {
+            self.set_unclamped_offset(x.position(), y.position());
+            return;
+        }
+        self.stop_scroll_animation();
+        let window = match element.get_window() {
+            Some(w) => w,
+            None => {
+                self.set_unclamped_offset(x.rest_position(), y.rest_position());
+                return;
+            }
+        };
+        let actor = FlingAnimationActor {
+            scrollable: self.clone(),
+            id: self.scroll_animation_id,
+            x,
+            y,
+            elapsed: 0.0,
+        };
+        let fc = WindowAnimationController::new(window);
+        let duration = FLING_MAX_DURATION * 1000000.0;
+        let mut ai = AnimationInstance::new(actor, duration, 1.0, Box::new(fc));
+        ai.run();
+        self.scroll_animation_instance = Some(ai);
+    }
+
+    fn stop_scroll_animation(&mut self) {
+        self.scroll_animation_id += 1;
+        self.scroll_animation_instance = None;
+    }
+
+    pub fn set_edge_threshold(&mut self, threshold: f32) {
+        self.edge_threshold = threshold.max(0.0);
+    }
+
+    pub fn edge_threshold(&self) -> f32 {
+        self.edge_threshold
+    }
+
+    /// Update whether the viewport is near the top or bottom edge of content, returns whether
+    /// (top, bottom) edges are newly reached
+    pub fn update_edges(&mut self) -> (bool, bool) {
+        let bar = &self.vertical_bar;
+        // Nothing to reach before content is laid out
+        if bar.length() <= 0.0 {
+            return (false, false);
+        }
+        let offset = bar.scroll_offset();
+        let reached = (
+            offset <= self.edge_threshold,
+            bar.get_max_scroll_offset() - offset <= self.edge_threshold,
+        );
+        let (old_top, old_bottom) = self.edges_reached;
+        self.edges_reached = reached;
+        (reached.0 && !old_top, reached.1 && !old_bottom)
+    }
+
+    /// Call `callback` once scroll offset stays unchanged for a while, the previous callback
+    /// is cancelled
+    pub fn schedule_scroll_end<F: FnOnce() + 'static>(&mut self, callback: F) {
+        self.scroll_end_timer = Some(set_timeout(callback, SCROLL_END_DELAY));
+    }
+
+    /// Whether scrolling is driven by a touch or a dragged scroll bar thumb
+    pub fn is_dragging(&self) -> bool {
+        self.is_touch_scrolling()
+            || self.vertical_bar.is_dragging()
+            || self.horizontal_bar.is_dragging()
+    }
+
+    fn is_touch_scrolling(&self) -> bool {
+        self.vertical_move_begin.is_some() || self.horizontal_move_begin.is_some()
+    }
+
+    pub fn set_pull_to_refresh(&mut self, enabled: bool) {
+        self.pull_to_refresh = enabled;
+        if !enabled {
+            self.refreshing = false;
+            self.stop_pull_animation();
+            self.set_pull_distance(0.0);
+        }
+    }
+
+    pub fn set_refresh_indicator(&mut self, indicator: Option<ElementWeak>) {
+        self.refresh_indicator = indicator;
+    }
+
+    /// Called when pull distance changes
+    pub fn set_pull_callback<F: FnMut() + 'static>(&mut self, callback: F) {
+        self.pull_callback = Box::new(callback);
+    }
+
+    /// Called when the indicator is pulled beyond the threshold and released
+    pub fn set_refresh_callback<F: FnMut() + 'static>(&mut self, callback: F) {
+        self.refresh_callback = Box::new(callback);
+    }
+
+    pub fn is_refreshing(&self) -> bool {
+        self.refreshing
+    }
+
+    /// Collapse the indicator after refreshing is done
+    pub fn finish_refresh(&mut self, element: &Element) {
+        if self.refreshing {
+            self.refreshing = false;
+            self.animate_pull_to(element, 0.0);
+        }
+    }
+
+    /// Pull distance to trigger refresh, which is the height of indicator
+    fn refresh_threshold(&self) -> f32 {
+        self.refresh_indicator
+            .as_ref()
+            .and_then(|i| i.upgrade().ok())
+            .map(|i| i.get_bounds().height)
+            .filter(|h| *h > 0.0)
+            .unwrap_or(DEFAULT_REFRESH_THRESHOLD)
+    }
+
+    /// Pull distance where overscroll starts, the indicator is kept visible while refreshing
+    fn pull_base(&self) -> f32 {
+        if self.refreshing {
+            self.refresh_threshold()
+        } else {
+            0.0
+        }
+    }
+
+    fn set_pull_distance(&mut self, distance: f32) {
+        if self.pull_distance != distance {
+            self.pull_distance = distance;
+            (self.pull_callback)();
+        }
+    }
+
+    /// Pull by trackpad when content is at the top, returns true if the event is consumed
+    fn pull_by_wheel(&mut self, e: &WheelEvent, element: &Element) -> bool {
+        // Only trackpads report pixel deltas
+        if e.delta_mode != WheelEvent::DELTA_MODE_PIXEL || self.vertical_bar.scroll_offset() > 0.0 {
+            return false;
+        }
+        let base = self.pull_base();
+        let overscroll = self.pull_distance - base;
+        if e.delta_y <= 0.0 && overscroll <= 0.0 {
+            return false;
+        }
+        self.stop_pull_animation();
+        self.set_pull_distance(base + (overscroll + e.delta_y * PULL_RESISTANCE).max(0.0));
+        let weak = element.as_weak();
+        let mut me = self.clone();
+        let timer = set_timeout(
+            move || {
+                let element = ok_or_return!(weak.upgrade());
+                me.release_pull(&element);
+            },
+            SCROLL_END_DELAY,
+        );
+        self.pull_release_timer = Some(timer);
+        true
+    }
+
+    /// Start refreshing if pulled beyond the threshold, then settle the indicator
+    fn release_pull(&mut self, element: &Element) {
+        if !self.pull_to_refresh || self.pull_distance <= 0.0 {
+            return;
+        }
+        let threshold = self.refresh_threshold();
+        if !self.refreshing && self.pull_distance >= threshold {
+            self.refreshing = true;
+            (self.refresh_callback)();
+        }
+        let target = if self.refreshing { threshold } else { 0.0 };
+        self.animate_pull_to(element, target);
+    }
+
+    fn stop_pull_animation(&mut self) {
+        self.pull_animation_id += 1;
+        self.pull_animation_instance = None;
+    }
+
+    fn animate_pull_to(&mut self, element: &Element, target: f32) {
+        self.stop_pull_animation();
+        let window = match element.get_window() {
+            Some(w) => w,
+            None => {
+                self.set_pull_distance(target);
+                return;
+            }
+        };
+        let actor = PullAnimationActor {
+            scrollable: self.clone(),
+            id: self.pull_animation_id,
+            from: self.pull_distance,
+            to: target,
+        };
+        let fc = WindowAnimationController::new(window);
+        let duration = PULL_ANIMATION_DURATION * 1000000.0;
+        let mut ai = AnimationInstance::new(actor, duration, 1.0, Box::new(fc));
+        ai.run();
+        self.pull_animation_instance = Some(ai);
+    }
+
+    pub fn save_state(&self, element: &Element) -> ScrollState {
+        let (scroll_left, scroll_top) = self.scroll_offset();
+        let viewport_top = scroll_top + element.get_border_width().0;
+        let anchor = Self::find_anchor(element, 0.0, viewport_top);
+        ScrollState {
+            scroll_left,
+            scroll_top,
+            anchor: anchor.as_ref().map(|(a, _)| a.get_eid()),
+            anchor_offset: anchor.map(|(_, top)| top - viewport_top).unwrap_or(0.0),
+        }
+    }
+
+    /// Restore state after the next layout, when the content may have been rebuilt
+    pub fn restore_state(&mut self, state: ScrollState) {
+        self.pending_state = Some(state);
+    }
+
+    fn apply_pending_state(&mut self, element: &Element) {
+        let state = some_or_return!(self.pending_state.take());
+        let anchor_top = state
+            .anchor
+            .and_then(|eid| Self::find_descendant(element, eid))
+            .and_then(|anchor| Self::get_anchor_top(&anchor, element));
+        let scroll_top = match anchor_top {
+            Some(top) => top - element.get_border_width().0 - state.anchor_offset,
+            None => state.scroll_top,
+        };
+        self.horizontal_bar.set_scroll_offset(state.scroll_left);
+        self.vertical_bar.set_scroll_offset(scroll_top);
+    }
+
+    /// Select the element at the top edge of viewport, whose position is kept on layout changes
+    fn select_anchor(&self, element: &Element) -> Option<(Element, f32)> {
+        let scroll_top = self.vertical_bar.scroll_offset();
+        // Content sticks to the top if not scrolled
+        if scroll_top <= 0.0 || self.pending_state.is_some() {
+            return None;
+        }
+        Self::find_anchor(element, 0.0, scroll_top + element.get_border_width().0)
+    }
+
+    /// Scroll by the distance the anchor moved, so that the visible content does not jump
+    fn adjust_for_anchor(&mut self, element: &Element, anchor: Option<(Element, f32)>) {
+        let (anchor, old_top) = some_or_return!(anchor);
+        let new_top = some_or_return!(Self::get_anchor_top(&anchor, element));
+        let delta = new_top - old_top;
+        if delta == 0.0 {
+            return;
+        }
+        let scroll_top = self.vertical_bar.scroll_offset();
+        self.vertical_bar.set_scroll_offset(scroll_top + delta);
+        if let Some((_, begin_top)) = &mut self.vertical_move_begin {
+            *begin_top += delta;
+        }
+    }
+
+    /// Find the deepest element crossing or below the viewport top, returns the element and its
+    /// top relative to the border box of the scroll container
+    fn find_anchor(parent: &Element, parent_top: f32, viewport_top: f32) -> Option<(Element, f32)> {
+        for child in parent.get_children() {
+            let bounds = child.get_bounds();
+            let top = parent_top + bounds.y;
+            if bounds.height <= 0.0 || top + bounds.height <= viewport_top {
+                continue;
+            }
+            // Children of nested scroll containers move with their own scroll offset
+            if top < viewport_top && !child.style.has_shadow() {
+                if let Some(anchor) = Self::find_anchor(&child, top, viewport_top) {
+                    return Some(anchor);
+                }
+            }
+            return Some((child, top));
+        }
+        None
+    }
+
+    /// Top of anchor relative to the border box of scroll container, None if it is detached
+    fn get_anchor_top(anchor: &Element, container: &Element) -> Option<f32> {
+        let mut top = 0.0;
+        let mut el = anchor.clone();
+        loop {
+            let parent = el.get_parent()?;
+            top += el.get_bounds().y;
+            if &parent == container {
+                return Some(top);
+            }
+            el = parent;
+        }
+    }
+
+    fn find_descendant(element: &Element, eid: u32) -> Option<Element> {
+        for child in element.get_children() {
+            if child.get_eid() == eid {
+                return Some(child);
+            }
+            if let Some(el) = Self::find_descendant(&child, eid) {
+                return Some(el);
+            }
+        }
+        None
+    }
+
     pub fn is_mouse_over_bar(&self, x: f32, y: f32) -> bool {
         self.vertical_bar.is_mouse_over(x, y) || self.horizontal_bar.is_mouse_over(x, y)
     }
@@ -262,6 +766,7 @@ impl Scrollable {
 
     fn do_layout_content(&mut self, element: &mut Element, bounds_width: f32, bounds_height: f32) {
         // print_time!("scroll layout content time");
+        let anchor = self.select_anchor(element);
         self.layout_content(element, bounds_width, bounds_height);
 
         // let (mut body_width, body_height) = self.get_body_view_size(bounds_width, bounds_height);
@@ -299,6 +804,9 @@ impl Scrollable {
         self.horizontal_bar
             .set_length(bounds_width, real_content_width, bounds_height);
 
+        self.adjust_for_anchor(element, anchor);
+        self.apply_pending_state(element);
+
         let vbw = self.vertical_bar.visible_thickness();
         let hbw = self.horizontal_bar.visible_thickness();
         element.set_child_decoration((0.0, vbw, hbw, 0.0));
@@ -316,7 +824,7 @@ impl Scrollable {
             .style
             .calculate_shadow_layout(width, layout_height, LTR);
 
-        for child in &mut element.get_children().clone() {
+        for child in &mut element.get_layout_children().clone() {
             //TODO remove?
             child.on_layout_update();
         }
@@ -335,18 +843,25 @@ impl Scrollable {
     }
 
     fn begin_scroll_y(&mut self, y: f32) {
-        self.vertical_move_begin = Some((y, self.vertical_bar.scroll_offset));
+        let overscroll = rubber_band_inverse(self.overscroll.1, self.vertical_bar.length());
+        self.vertical_move_begin = Some((y, self.vertical_bar.scroll_offset + overscroll));
     }
 
     fn begin_scroll_x(&mut self, x: f32) {
-        self.horizontal_move_begin = Some((x, self.horizontal_bar.scroll_offset));
+        let overscroll = rubber_band_inverse(self.overscroll.0, self.horizontal_bar.length());
+        self.horizontal_move_begin = Some((x, self.horizontal_bar.scroll_offset + overscroll));
     }
 
     fn update_scroll_y(&mut self, y: f32) {
         if let Some((begin_y, begin_top)) = self.vertical_move_begin {
             let mouse_move_distance = y - begin_y;
             let distance = mouse_move_distance;
-            self.vertical_bar.update_scroll_offset(begin_top + distance);
+            if self.pull_to_refresh {
+                let overscroll = (-(begin_top + distance)).max(0.0);
+                self.set_pull_distance(self.pull_base() + overscroll * PULL_RESISTANCE);
+            }
+            let top = self.resist_overscroll(&self.vertical_bar, begin_top + distance);
+            self.set_unclamped_offset(self.horizontal_bar.scroll_offset + self.overscroll.0, top);
         }
     }
 
@@ -354,11 +869,17 @@ impl Scrollable {
         if let Some((begin_x, begin_left)) = self.horizontal_move_begin {
             let mouse_move_distance = x - begin_x;
             let distance = mouse_move_distance;
-            self.horizontal_bar
-                .update_scroll_offset(begin_left + distance)
+            let left = self.resist_overscroll(&self.horizontal_bar, begin_left + distance);
+            self.set_unclamped_offset(left, self.vertical_bar.scroll_offset + self.overscroll.1);
         }
     }
 
+    /// Dragged offset with the part beyond the edges reduced by rubber band
+    fn resist_overscroll(&self, bar: &ScrollBar, offset: f32) -> f32 {
+        let clamped = offset.clamp(0.0, bar.get_max_scroll_offset());
+        clamped + rubber_band(offset - clamped, bar.length())
+    }
+
     fn end_scroll(&mut self) {
         self.vertical_move_begin = None;
         self.horizontal_move_begin = None;
@@ -395,6 +916,67 @@ impl ScrollAnimationActor {
     }
 }
 
+struct PullAnimationActor {
+    scrollable: Scrollable,
+    id: u64,
+    from: f32,
+    to: f32,
+}
+
+impl AnimationActor for PullAnimationActor {
+    fn apply_animation(&mut self, position: f32, _stop: &mut bool) {
+        // Ease out
+        let progress = 1.0 - (1.0 - position) * (1.0 - position);
+        let distance = self.from + (self.to - self.from) * progress;
+        self.scrollable.set_pull_distance(distance);
+    }
+
+    fn stop(&mut self) {
+        if self.scrollable.pull_animation_id == self.id {
+            self.scrollable.set_pull_distance(self.to);
+        }
+    }
+}
+
+struct FlingAnimationActor {
+    scrollable: Scrollable,
+    id: u64,
+    x: FlingAxis,
+    y: FlingAxis,
+    /// Milliseconds simulated so far
+    elapsed: f32,
+}
+
+impl FlingAnimationActor {
+    fn apply_position(&mut self) {
+        let (left, top) = (self.x.position(), self.y.position());
+        self.scrollable.set_unclamped_offset(left, top);
+    }
+}
+
+impl AnimationActor for FlingAnimationActor {
+    fn apply_animation(&mut self, position: f32, stop: &mut bool) {
+        let time = position * FLING_MAX_DURATION;
+        let dt = time - self.elapsed;
+        self.elapsed = time;
+        let moving_x = self.x.step(dt);
+        let moving_y = self.y.step(dt);
+        self.apply_position();
+        if !moving_x && !moving_y {
+            *stop = true;
+        }
+    }
+
+    fn stop(&mut self) {
+        // Interrupted flings stop where they are
+        if self.scrollable.scroll_animation_id == self.id {
+            self.x.finish();
+            self.y.finish();
+            self.apply_position();
+        }
+    }
+}
+
 impl AnimationActor for ScrollAnimationActor {
     fn apply_animation(&mut self, position: f32, stop: &mut bool) {
         let mut left_stopped = self.left_dist == 0.0;
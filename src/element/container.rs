@@ -11,4 +11,8 @@ impl ElementBackend for Container {
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         None
     }
+
+    fn accept_generated_content(&mut self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,319 @@
+use crate as deft;
+use crate::element::{register_component_creator, Element, ElementBackend, ElementWeak};
+use crate::js::js_value_util::JsValueHelper;
+use crate::js::JsError;
+use crate::render::RenderFn;
+use crate::style::color::parse_color;
+use crate::{ok_or_return, some_or_return};
+use deft_macros::js_methods;
+use log::error;
+use quick_js::JsValue;
+use skia_safe::{Canvas, ClipOp, Color, Paint, PaintStyle, Path, Rect};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    static DEFINITIONS: RefCell<HashMap<String, CustomElementDefinition>> = RefCell::new(HashMap::new());
+}
+
+struct CustomElementDefinition {
+    /// Called with (element, width, height), returns a list of draw commands
+    render: Option<JsValue>,
+    /// Called with (element, max_width, max_height), returns [width, height]
+    measure: Option<JsValue>,
+}
+
+/// Element defined by js, see `deft.registerElement`
+pub struct CustomElement {
+    element: ElementWeak,
+    tag: String,
+    measure: Option<JsValue>,
+}
+
+#[allow(nonstandard_style)]
+pub struct custom_element {}
+
+#[js_methods]
+impl custom_element {
+    #[js_func]
+    pub fn register(tag: String, render: JsValue, measure: JsValue) -> Result<(), JsError> {
+        let tag = tag.to_lowercase();
+        if DEFINITIONS.with_borrow(|d| d.contains_key(&tag)) {
+            return Err(JsError::new(format!("element already registered: {}", tag)));
+        }
+        let definition = CustomElementDefinition {
+            render: Self::to_callback(render),
+            measure: Self::to_callback(measure),
+        };
+        DEFINITIONS.with_borrow_mut(|d| {
+            d.insert(tag.clone(), definition);
+        });
+        let creator_tag = tag.clone();
+        register_component_creator(&tag, move |ele| {
+            Box::new(CustomElement::new(ele, creator_tag.clone()))
+        });
+        Ok(())
+    }
+
+    /// Measure callback will be called again in next layout
+    #[js_func]
+    pub fn request_layout(mut element: Element) {
+        element.mark_dirty(true);
+    }
+
+    /// Render callback will be called again in next frame
+    #[js_func]
+    pub fn request_render(mut element: Element) {
+        element.mark_dirty(false);
+    }
+
+    fn to_callback(value: JsValue) -> Option<JsValue> {
+        match value {
+            JsValue::Null | JsValue::Undefined => None,
+            v => Some(v),
+        }
+    }
+}
+
+impl CustomElement {
+    fn new(element: &mut Element, tag: String) -> Self {
+        let measure = DEFINITIONS.with_borrow(|d| d.get(&tag).and_then(|d| d.measure.clone()));
+        Self {
+            element: element.as_weak(),
+            tag,
+            measure,
+        }
+    }
+}
+
+impl ElementBackend for CustomElement {
+    fn create(element: &mut Element) -> Self {
+        Self::new(element, element.tag.to_string())
+    }
+
+    fn is_measurable(&self) -> bool {
+        self.measure.is_some()
+    }
+
+    fn measure(&self, max_width: f32, max_height: f32) -> (f32, f32) {
+        let measure = some_or_return!(&self.measure, (0.0, 0.0));
+        let element = ok_or_return!(self.element.upgrade(), (0.0, 0.0));
+        let context = element.get_js_context().unwrap_or(JsValue::Undefined);
+        let args = vec![
+            context,
+            JsValue::Float(max_width as f64),
+            JsValue::Float(max_height as f64),
+        ];
+        match measure.call_as_function(args) {
+            Ok(JsValue::Array(size)) if size.len() == 2 => {
+                let width = size[0].as_number().unwrap_or(0.0) as f32;
+                let height = size[1].as_number().unwrap_or(0.0) as f32;
+                (width, height)
+            }
+            Ok(v) => {
+                error!("invalid measure result of custom element {}: {:?}", self.tag, v);
+                (0.0, 0.0)
+            }
+            Err(e) => {
+                error!("failed to measure custom element {}: {:?}", self.tag, e);
+                (0.0, 0.0)
+            }
+        }
+    }
+
+    fn render(&mut self) -> RenderFn {
+        let element = ok_or_return!(self.element.upgrade(), RenderFn::empty());
+        let render = DEFINITIONS.with_borrow(|d| d.get(&self.tag).and_then(|d| d.render.clone()));
+        let render = some_or_return!(render, RenderFn::empty());
+        let bounds = element.get_bounds();
+        let (top, right, bottom, left) = element.get_border_width();
+        let width = bounds.width - left - right;
+        let height = bounds.height - top - bottom;
+        let context = element.get_js_context().unwrap_or(JsValue::Undefined);
+        let args = vec![context, JsValue::Float(width as f64), JsValue::Float(height as f64)];
+        let commands = match render.call_as_function(args) {
+            Ok(JsValue::Array(list)) => DrawCommand::parse_list(&list),
+            Ok(_) => Vec::new(),
+            Err(e) => {
+                error!("failed to render custom element {}: {:?}", element.tag, e);
+                Vec::new()
+            }
+        };
+        RenderFn::new(move |painter| {
+            let mut state = DrawState::new();
+            let canvas = painter.canvas;
+            canvas.save();
+            for cmd in &commands {
+                cmd.draw(canvas, &mut state);
+            }
+            canvas.restore();
+        })
+    }
+}
+
+/// Canvas operation recorded by js render callback, each command is an array like `["fillRect", x, y, w, h]`
+enum DrawCommand {
+    FillStyle(Color),
+    StrokeStyle(Color),
+    LineWidth(f32),
+    FillRect(Rect),
+    StrokeRect(Rect),
+    FillCircle(f32, f32, f32),
+    StrokeCircle(f32, f32, f32),
+    Line(f32, f32, f32, f32),
+    /// Svg path data
+    FillPath(String),
+    StrokePath(String),
+    Save,
+    Restore,
+    Translate(f32, f32),
+    Rotate(f32),
+    Scale(f32, f32),
+    ClipRect(Rect),
+}
+
+struct DrawState {
+    fill: Paint,
+    stroke: Paint,
+}
+
+impl DrawState {
+    fn new() -> Self {
+        let mut fill = Paint::default();
+        fill.set_anti_alias(true);
+        fill.set_style(PaintStyle::Fill);
+        fill.set_color(Color::BLACK);
+        let mut stroke = fill.clone();
+        stroke.set_style(PaintStyle::Stroke);
+        stroke.set_stroke_width(1.0);
+        Self { fill, stroke }
+    }
+}
+
+impl DrawCommand {
+    fn parse_list(list: &Vec<JsValue>) -> Vec<DrawCommand> {
+        let mut result = Vec::with_capacity(list.len());
+        for item in list {
+            match item {
+                JsValue::Array(args) => match Self::parse(args) {
+                    Some(cmd) => result.push(cmd),
+                    None => error!("invalid draw command: {:?}", args),
+                },
+                _ => error!("invalid draw command: {:?}", item),
+            }
+        }
+        result
+    }
+
+    fn parse(args: &Vec<JsValue>) -> Option<DrawCommand> {
+        let name = match args.first()? {
+            JsValue::String(s) => s.as_str(),
+            _ => return None,
+        };
+        let num = |i: usize| args.get(i).and_then(|v| v.as_number()).map(|v| v as f32);
+        let str = |i: usize| match args.get(i) {
+            Some(JsValue::String(s)) => Some(s.as_str()),
+            _ => None,
+        };
+        let rect = || Some(Rect::from_xywh(num(1)?, num(2)?, num(3)?, num(4)?));
+        let cmd = match name {
+            "fillStyle" => DrawCommand::FillStyle(parse_color(str(1)?)?),
+            "strokeStyle" => DrawCommand::StrokeStyle(parse_color(str(1)?)?),
+            "lineWidth" => DrawCommand::LineWidth(num(1)?),
+            "fillRect" => DrawCommand::FillRect(rect()?),
+            "strokeRect" => DrawCommand::StrokeRect(rect()?),
+            "fillCircle" => DrawCommand::FillCircle(num(1)?, num(2)?, num(3)?),
+            "strokeCircle" => DrawCommand::StrokeCircle(num(1)?, num(2)?, num(3)?),
+            "line" => DrawCommand::Line(num(1)?, num(2)?, num(3)?, num(4)?),
+            "fillPath" => DrawCommand::FillPath(str(1)?.to_string()),
+            "strokePath" => DrawCommand::StrokePath(str(1)?.to_string()),
+            "save" => DrawCommand::Save,
+            "restore" => DrawCommand::Restore,
+            "translate" => DrawCommand::Translate(num(1)?, num(2)?),
+            "rotate" => DrawCommand::Rotate(num(1)?),
+            "scale" => DrawCommand::Scale(num(1)?, num(2)?),
+            "clipRect" => DrawCommand::ClipRect(rect()?),
+            _ => return None,
+        };
+        Some(cmd)
+    }
+
+    fn draw(&self, canvas: &Canvas, state: &mut DrawState) {
+        match self {
+            DrawCommand::FillStyle(c) => {
+                state.fill.set_color(*c);
+            }
+            DrawCommand::StrokeStyle(c) => {
+                state.stroke.set_color(*c);
+            }
+            DrawCommand::LineWidth(w) => {
+                state.stroke.set_stroke_width(*w);
+            }
+            DrawCommand::FillRect(r) => {
+                canvas.draw_rect(r, &state.fill);
+            }
+            DrawCommand::StrokeRect(r) => {
+                canvas.draw_rect(r, &state.stroke);
+            }
+            DrawCommand::FillCircle(x, y, r) => {
+                canvas.draw_circle((*x, *y), *r, &state.fill);
+            }
+            DrawCommand::StrokeCircle(x, y, r) => {
+                canvas.draw_circle((*x, *y), *r, &state.stroke);
+            }
+            DrawCommand::Line(x1, y1, x2, y2) => {
+                canvas.draw_line((*x1, *y1), (*x2, *y2), &state.stroke);
+            }
+            DrawCommand::FillPath(p) => {
+                if let Some(path) = Path::from_svg(p) {
+                    canvas.draw_path(&path, &state.fill);
+                }
+            }
+            DrawCommand::StrokePath(p) => {
+                if let Some(path) = Path::from_svg(p) {
+                    canvas.draw_path(&path, &state.stroke);
+                }
+            }
+            DrawCommand::Save => {
+                canvas.save();
+            }
+            DrawCommand::Restore => {
+                canvas.restore();
+            }
+            DrawCommand::Translate(x, y) => {
+                canvas.translate((*x, *y));
+            }
+            DrawCommand::Rotate(deg) => {
+                canvas.rotate(*deg, None);
+            }
+            DrawCommand::Scale(x, y) => {
+                canvas.scale((*x, *y));
+            }
+            DrawCommand::ClipRect(r) => {
+                canvas.clip_rect(r, ClipOp::Intersect, true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::custom::DrawCommand;
+    use quick_js::JsValue;
+
+    #[test]
+    fn test_parse_draw_command() {
+        let args = vec![
+            JsValue::String("fillRect".to_string()),
+            JsValue::Int(1),
+            JsValue::Float(2.5),
+            JsValue::Int(10),
+            JsValue::Int(20),
+        ];
+        assert!(matches!(DrawCommand::parse(&args), Some(DrawCommand::FillRect(_))));
+        let args = vec![JsValue::String("fillRect".to_string()), JsValue::Int(1)];
+        assert!(DrawCommand::parse(&args).is_none());
+        let args = vec![JsValue::String("unknown".to_string())];
+        assert!(DrawCommand::parse(&args).is_none());
+    }
+}
@@ -0,0 +1,204 @@
+use crate as deft;
+use crate::element::{Element, ElementType, CSS_MANAGER};
+use crate::js_serialize;
+use crate::number::DeNan;
+use crate::style::StylePropKey;
+use crate::style_list::{declared_values, StyleList};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use yoga::StyleUnit;
+
+/// Yoga node of element with style inputs and computed layout
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutDump {
+    pub id: u32,
+    pub tag: String,
+    /// Style inputs of yoga node, undefined values are omitted
+    pub style: BTreeMap<String, String>,
+    pub layout: LayoutBox,
+    pub children: Vec<LayoutDump>,
+}
+
+js_serialize!(LayoutDump);
+
+/// Computed layout relative to parent, edges are (top, right, bottom, left)
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutBox {
+    pub left: f32,
+    pub top: f32,
+    pub width: f32,
+    pub height: f32,
+    pub margin: [f32; 4],
+    pub border: [f32; 4],
+    pub padding: [f32; 4],
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CascadeEntry {
+    /// One of "default", "rule", "inline", "hover" and "animation"
+    pub origin: String,
+    /// Selector of stylesheet rule
+    pub selector: Option<String>,
+    pub value: String,
+}
+
+/// Declarations of a style property in cascade order, the last one wins
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StyleTrace {
+    pub property: String,
+    /// Applied value after cascade and inheritance
+    pub value: String,
+    pub winner: Option<CascadeEntry>,
+    pub cascade: Vec<CascadeEntry>,
+}
+
+js_serialize!(StyleTrace);
+
+pub fn dump_layout(element: &Element) -> LayoutDump {
+    let bounds = element.get_bounds();
+    let yn = &element.style.yoga_node._yn;
+    let (pt, pr, pb, pl) = element.style.get_padding();
+    let layout = LayoutBox {
+        left: bounds.x,
+        top: bounds.y,
+        width: bounds.width,
+        height: bounds.height,
+        margin: [
+            yn.get_layout_margin_top().de_nan(0.0),
+            yn.get_layout_margin_right().de_nan(0.0),
+            yn.get_layout_margin_bottom().de_nan(0.0),
+            yn.get_layout_margin_left().de_nan(0.0),
+        ],
+        border: [
+            yn.get_layout_border_top().de_nan(0.0),
+            yn.get_layout_border_right().de_nan(0.0),
+            yn.get_layout_border_bottom().de_nan(0.0),
+            yn.get_layout_border_left().de_nan(0.0),
+        ],
+        padding: [pt, pr, pb, pl],
+    };
+    LayoutDump {
+        id: element.get_eid(),
+        tag: element.tag.to_string(),
+        style: node_style_inputs(element),
+        layout,
+        children: element
+            .get_layout_children()
+            .iter()
+            .map(dump_layout)
+            .collect(),
+    }
+}
+
+fn node_style_inputs(element: &Element) -> BTreeMap<String, String> {
+    let n = &element.style.yoga_node;
+    let mut style = BTreeMap::new();
+    let units = [
+        ("width", &n.width),
+        ("height", &n.height),
+        ("minWidth", &n.min_width),
+        ("minHeight", &n.min_height),
+        ("maxWidth", &n.max_width),
+        ("maxHeight", &n.max_height),
+        ("flexBasis", &n.flex_basis),
+        ("marginTop", &n.margin_top),
+        ("marginRight", &n.margin_right),
+        ("marginBottom", &n.margin_bottom),
+        ("marginLeft", &n.margin_left),
+        ("paddingTop", &n.padding_top),
+        ("paddingRight", &n.padding_right),
+        ("paddingBottom", &n.padding_bottom),
+        ("paddingLeft", &n.padding_left),
+        ("top", &n.top),
+        ("right", &n.right),
+        ("bottom", &n.bottom),
+        ("left", &n.left),
+    ];
+    for (name, unit) in units {
+        if let Some(v) = style_unit_to_string(unit) {
+            style.insert(name.to_string(), v);
+        }
+    }
+    let others = [
+        ("display", format!("{:?}", n.display)),
+        ("position", format!("{:?}", n.position_type)),
+        ("flexDirection", format!("{:?}", n.flex_direction)),
+        ("flexWrap", format!("{:?}", n.flex_wrap)),
+        ("justifyContent", format!("{:?}", n.justify_content)),
+        ("alignItems", format!("{:?}", n.align_items)),
+        ("alignContent", format!("{:?}", n.align_content)),
+        ("alignSelf", format!("{:?}", n.align_self)),
+        ("overflow", format!("{:?}", n.overflow)),
+        ("direction", format!("{:?}", n.direction)),
+        ("flex", n.flex.to_string()),
+        ("flexGrow", n.flex_grow.to_string()),
+        ("flexShrink", n.flex_shrink.to_string()),
+        ("columnGap", n.column_gap.to_string()),
+        ("rowGap", n.row_gap.to_string()),
+        ("borderTop", n.border_top.to_string()),
+        ("borderRight", n.border_right.to_string()),
+        ("borderBottom", n.border_bottom.to_string()),
+        ("borderLeft", n.border_left.to_string()),
+    ];
+    for (name, value) in others {
+        style.insert(name.to_string(), value);
+    }
+    style
+}
+
+fn style_unit_to_string(unit: &StyleUnit) -> Option<String> {
+    match unit {
+        StyleUnit::UndefinedValue => None,
+        StyleUnit::Point(v) => Some(format!("{}px", v.0)),
+        StyleUnit::Percent(v) => Some(format!("{}%", v.0)),
+        StyleUnit::Auto => Some("auto".to_string()),
+    }
+}
+
+/// Trace declarations of property, returns None if property is unknown
+pub fn trace_style(element: &Element, property: &str) -> Option<StyleTrace> {
+    let key = StylePropKey::parse(&property.replace('-', ""))?;
+    let mut cascade = Vec::new();
+    let mut push = |origin: &str, selector: Option<String>, values: Vec<String>| {
+        for value in values {
+            cascade.push(CascadeEntry {
+                origin: origin.to_string(),
+                selector: selector.clone(),
+                value,
+            });
+        }
+    };
+    push("default", None, element.style_list.trace_default(key));
+    if element.element_type == ElementType::Widget {
+        let rules = CSS_MANAGER.with_borrow(|cm| cm.trace_rules(element));
+        for (selector, declarations) in rules {
+            let (props, _) = StyleList::parse_style(&declarations);
+            push("rule", Some(selector), declared_values(&props, key));
+        }
+    }
+    push("inline", None, element.style_list.trace_inline(key));
+    if element.hover {
+        push("hover", None, element.style_list.trace_hover(key));
+    }
+    if let Some(p) = element.animation_style_props.get(&key) {
+        push("animation", None, vec![p.to_style_string()]);
+    }
+    let value = match element.applied_style.get(&key) {
+        Some(v) => v.to_unresolved().to_style_string(),
+        None => element
+            .style
+            .get_default_value(key)
+            .to_unresolved()
+            .to_style_string(),
+    };
+    Some(StyleTrace {
+        property: key.name().to_string(),
+        value,
+        winner: cascade.last().cloned(),
+        cascade,
+    })
+}
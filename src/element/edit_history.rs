@@ -1,26 +1,50 @@
 use crate::some_or_return;
 use crate::text::textbox::TextCoord;
 
+/// Content deleted or inserted by an edit, consecutive edits are merged into one
+pub trait EditContent: Clone {
+    /// Append content inserted right after this one
+    fn append(&mut self, other: &Self);
+    /// Prepend content deleted right before this one
+    fn prepend(&mut self, other: &Self);
+    fn starts_with_whitespace(&self) -> bool;
+}
+
+impl EditContent for String {
+    fn append(&mut self, other: &Self) {
+        self.push_str(other);
+    }
+
+    fn prepend(&mut self, other: &Self) {
+        self.insert_str(0, other);
+    }
+
+    fn starts_with_whitespace(&self) -> bool {
+        let first = some_or_return!(self.chars().next(), false);
+        ['\t', '\r', '\n', ' '].contains(&first)
+    }
+}
+
 #[derive(Clone, Debug)]
-pub struct EditDetail {
-    pub content: String,
+pub struct EditDetail<C = String> {
+    pub content: C,
     pub end: TextCoord,
 }
 
 #[derive(Clone, Debug)]
-pub struct EditOp {
+pub struct EditOp<C = String> {
     pub caret: TextCoord,
-    pub delete: Option<EditDetail>,
-    pub insert: Option<EditDetail>,
+    pub delete: Option<EditDetail<C>>,
+    pub insert: Option<EditDetail<C>>,
 }
 
-pub struct EditHistory {
+pub struct EditHistory<C = String> {
     max_history: usize,
-    history: Vec<EditOp>,
+    history: Vec<EditOp<C>>,
     history_ptr: usize,
 }
 
-impl EditHistory {
+impl<C: EditContent> EditHistory<C> {
     pub fn new(max_history: usize) -> Self {
         EditHistory {
             history: Vec::new(),
@@ -40,8 +64,8 @@ impl EditHistory {
     pub fn record_input(
         &mut self,
         caret: TextCoord,
-        delete_detail: Option<EditDetail>,
-        insert_detail: Option<EditDetail>,
+        delete_detail: Option<EditDetail<C>>,
+        insert_detail: Option<EditDetail<C>>,
     ) {
         if self.max_history == 0 || (delete_detail.is_none() && insert_detail.is_none()) {
             return;
@@ -55,7 +79,15 @@ impl EditHistory {
         }
     }
 
-    pub fn undo(&mut self) -> Option<EditOp> {
+    pub fn can_undo(&self) -> bool {
+        self.history_ptr > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history_ptr < self.history.len()
+    }
+
+    pub fn undo(&mut self) -> Option<EditOp<C>> {
         if self.history_ptr == 0 {
             return None;
         }
@@ -64,7 +96,7 @@ impl EditHistory {
         Some(prev_op.clone())
     }
 
-    pub fn redo(&mut self) -> Option<EditOp> {
+    pub fn redo(&mut self) -> Option<EditOp<C>> {
         if self.history.is_empty() || self.history_ptr >= self.history.len() {
             return None;
         }
@@ -76,8 +108,8 @@ impl EditHistory {
     fn merge_input(
         &mut self,
         caret: TextCoord,
-        delete_detail: &Option<EditDetail>,
-        insert_detail: &Option<EditDetail>,
+        delete_detail: &Option<EditDetail<C>>,
+        insert_detail: &Option<EditDetail<C>>,
     ) -> bool {
         if self.history_ptr == 0 || self.history_ptr != self.history.len() {
             return false;
@@ -90,10 +122,8 @@ impl EditHistory {
             &mut last_op.insert,
         ) {
             (None, Some(insert_detail), None, Some(last_insert)) => {
-                if last_insert.end == caret
-                    && !Self::starts_with_whitespace(&insert_detail.content)
-                {
-                    last_insert.content.push_str(&insert_detail.content);
+                if last_insert.end == caret && !insert_detail.content.starts_with_whitespace() {
+                    last_insert.content.append(&insert_detail.content);
                     last_insert.end = insert_detail.end;
                     true
                 } else {
@@ -102,7 +132,7 @@ impl EditHistory {
             }
             (Some(delete_detail), None, Some(last_delete), None) => {
                 if delete_detail.end == last_op.caret {
-                    last_delete.content = format!("{}{}", delete_detail.content, last_delete.content);
+                    last_delete.content.prepend(&delete_detail.content);
                     last_op.caret = caret;
                     true
                 } else {
@@ -113,7 +143,7 @@ impl EditHistory {
         }
     }
 
-    fn push_op(&mut self, op: EditOp) {
+    fn push_op(&mut self, op: EditOp<C>) {
         let expected_len = self.history_ptr;
         while self.history.len() > expected_len {
             self.history.pop().unwrap();
@@ -126,9 +156,4 @@ impl EditHistory {
         self.history_ptr += 1;
     }
 
-    fn starts_with_whitespace(content: &str) -> bool {
-        let first = some_or_return!(content.chars().next(), false);
-        ['\t', '\r', '\n', ' '].contains(&first)
-    }
-
 }
@@ -0,0 +1,235 @@
+use crate as deft;
+use crate::base::Rect;
+use crate::element::{Element, ElementBackend, ElementWeak};
+use crate::event_loop::{create_event_loop_fn_mut, EventLoopFnMutCallback};
+use crate::render::RenderFn;
+use crate::{ok_or_return, some_or_return};
+use deft_macros::{element_backend, js_methods};
+use skia_safe::{
+    images, AlphaType, ColorType, Data, FilterMode, Image, ImageInfo, Paint, Rect as SkRect,
+    SamplingOptions,
+};
+use skia_window::texture::borrow_gl_texture;
+pub use skia_window::texture::GlTextureInfo;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use yoga::Size;
+
+static EXTERNAL_TEXTURES: LazyLock<Mutex<HashMap<u32, Weak<TextureSlot>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Frame submitted by native code
+pub enum TextureFrame {
+    /// Premultiplied RGBA8888 pixels
+    Pixels {
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    },
+    /// Texture in the gl context of window, not drawn by software renderer
+    GlTexture(GlTextureInfo),
+}
+
+impl TextureFrame {
+    fn size(&self) -> (f32, f32) {
+        match self {
+            TextureFrame::Pixels { width, height, .. } => (*width as f32, *height as f32),
+            TextureFrame::GlTexture(info) => (info.width as f32, info.height as f32),
+        }
+    }
+}
+
+/// Shared with render functions, which run on the render thread; Image is thread-safe and
+/// gl textures are only referenced by id, so this is Send and Sync without unsafe impls
+enum FrameSrc {
+    None,
+    Image(Image),
+    GlTexture(GlTextureInfo),
+}
+
+struct TextureSlot {
+    pending: Mutex<Option<TextureFrame>>,
+    on_frame: Mutex<EventLoopFnMutCallback<()>>,
+}
+
+/// Thread-safe handle for submitting frames to an external-texture element
+#[derive(Clone)]
+pub struct ExternalTextureHandle {
+    slot: Weak<TextureSlot>,
+}
+
+impl ExternalTextureHandle {
+    /// Submit a frame, the element is repainted once on the main thread no matter how many
+    /// frames are submitted in between. Returns false if the element has been dropped.
+    pub fn submit(&self, frame: TextureFrame) -> bool {
+        let slot = match self.slot.upgrade() {
+            Some(slot) => slot,
+            None => return false,
+        };
+        let scheduled = slot.pending.lock().unwrap().replace(frame).is_some();
+        if !scheduled {
+            slot.on_frame.lock().unwrap().call(());
+        }
+        true
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.slot.strong_count() > 0
+    }
+}
+
+/// Find the texture handle of an external-texture element by its element id
+pub fn get_external_texture(element_id: u32) -> Option<ExternalTextureHandle> {
+    let mut textures = EXTERNAL_TEXTURES.lock().unwrap();
+    textures.retain(|_, slot| slot.strong_count() > 0);
+    let slot = textures.get(&element_id)?;
+    Some(ExternalTextureHandle { slot: slot.clone() })
+}
+
+#[element_backend]
+pub struct ExternalTexture {
+    element: ElementWeak,
+    slot: Arc<TextureSlot>,
+    src: Arc<FrameSrc>,
+    frame_size: (f32, f32),
+    container_size: (f32, f32),
+}
+
+#[js_methods]
+impl ExternalTexture {
+    /// Id to pass to native code for looking up the texture handle
+    #[js_func]
+    pub fn get_texture_id(&self) -> u32 {
+        ok_or_return!(self.element.upgrade(), 0).get_eid()
+    }
+
+    /// Drop current frame
+    #[js_func]
+    pub fn clear(&mut self) {
+        self.slot.pending.lock().unwrap().take();
+        self.src = Arc::new(FrameSrc::None);
+        self.update_frame_size((0.0, 0.0));
+    }
+
+    pub fn handle(&self) -> ExternalTextureHandle {
+        ExternalTextureHandle {
+            slot: Arc::downgrade(&self.slot),
+        }
+    }
+
+    fn take_pending_frame(&mut self) {
+        let frame = match self.slot.pending.lock().unwrap().take() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let size = frame.size();
+        let src = match frame {
+            TextureFrame::Pixels {
+                width,
+                height,
+                data,
+            } => {
+                let info = ImageInfo::new(
+                    (width as i32, height as i32),
+                    ColorType::RGBA8888,
+                    AlphaType::Premul,
+                    None,
+                );
+                let data = Data::new_copy(&data);
+                match images::raster_from_data(&info, data, width as usize * 4) {
+                    Some(img) => FrameSrc::Image(img),
+                    None => {
+                        log::error!("invalid texture frame: {}x{}", width, height);
+                        FrameSrc::None
+                    }
+                }
+            }
+            TextureFrame::GlTexture(info) => FrameSrc::GlTexture(info),
+        };
+        self.src = Arc::new(src);
+        self.update_frame_size(size);
+    }
+
+    fn update_frame_size(&mut self, size: (f32, f32)) {
+        let layout_dirty = self.frame_size != size;
+        self.frame_size = size;
+        self.element.mark_dirty(layout_dirty);
+    }
+}
+
+impl ElementBackend for ExternalTexture {
+    fn create(element: &mut Element) -> Self {
+        let element_weak = element.as_weak();
+        let on_frame = {
+            let element_weak = element_weak.clone();
+            create_event_loop_fn_mut(move |()| {
+                let element = ok_or_return!(element_weak.upgrade());
+                element
+                    .get_backend_as::<ExternalTexture>()
+                    .clone()
+                    .take_pending_frame();
+            })
+        };
+        let slot = Arc::new(TextureSlot {
+            pending: Mutex::new(None),
+            on_frame: Mutex::new(on_frame),
+        });
+        EXTERNAL_TEXTURES
+            .lock()
+            .unwrap()
+            .insert(element.get_eid(), Arc::downgrade(&slot));
+        let texture = ExternalTextureData {
+            element: element_weak,
+            slot,
+            src: Arc::new(FrameSrc::None),
+            frame_size: (0.0, 0.0),
+            container_size: (0.0, 0.0),
+        }
+        .to_ref();
+        element
+            .style
+            .yoga_node
+            .set_measure_func(texture.as_weak(), |texture, _params| {
+                if let Ok(texture) = texture.upgrade() {
+                    let (width, height) = texture.frame_size;
+                    return Size { width, height };
+                }
+                Size {
+                    width: 0.0,
+                    height: 0.0,
+                }
+            });
+        texture
+    }
+
+    fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
+        None
+    }
+
+    fn render(&mut self) -> RenderFn {
+        let src = self.src.clone();
+        let (width, height) = self.container_size;
+        RenderFn::new(move |painter| {
+            let canvas = painter.canvas;
+            let img = match src.as_ref() {
+                FrameSrc::Image(img) => img.clone(),
+                FrameSrc::GlTexture(info) => some_or_return!(borrow_gl_texture(canvas, info)),
+                FrameSrc::None => return,
+            };
+            let dst = SkRect::from_wh(width, height);
+            let mut options = SamplingOptions::default();
+            options.filter = FilterMode::Linear;
+            canvas.draw_image_rect_with_sampling_options(
+                &img,
+                None,
+                dst,
+                options,
+                &Paint::default(),
+            );
+        })
+    }
+
+    fn handle_origin_bounds_change(&mut self, bounds: &Rect) {
+        self.container_size = (bounds.width, bounds.height);
+    }
+}
@@ -1,8 +1,9 @@
 use crate as deft;
+use crate::font::fallback::{get_fallback_fonts, script_fallback_families, Script};
 use crate::font::family::{FontFamilies, FontFamily};
 use crate::font::Font;
+use crate::some_or_continue;
 use crate::style::font::FontStyle;
-use crate::text::textbox::DEFAULT_FALLBACK_FONTS;
 use deft_macros::mrc_object;
 use font_kit::family_name::FamilyName;
 use font_kit::handle::Handle;
@@ -61,9 +62,10 @@ impl FontManager {
         style: &skia_safe::FontStyle,
     ) -> Vec<Font> {
         let mut result = Vec::new();
+        let fallback_fonts = get_fallback_fonts();
         let mut family_names = family_names.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
-        for name in DEFAULT_FALLBACK_FONTS.split(",") {
-            family_names.push(name);
+        for name in &fallback_fonts {
+            family_names.push(name.as_str());
         }
         for name in family_names {
             if let Some(font) = self.get_by_family_name(name.as_ref(), &style) {
@@ -73,6 +75,33 @@ impl FontManager {
         result
     }
 
+    /// Find font containing the char in the fallback fonts and fonts of the script,
+    /// fonts with color glyphs are preferred for emoji
+    pub fn fallback_for_char(
+        &self,
+        ch: char,
+        script: Script,
+        style: &skia_safe::FontStyle,
+    ) -> Option<Font> {
+        let fallback_fonts = get_fallback_fonts();
+        let names = fallback_fonts
+            .iter()
+            .map(|s| s.as_str())
+            .chain(script_fallback_families(script).iter().copied());
+        let mut result = None;
+        for name in names {
+            let font = some_or_continue!(self.get_by_family_name(name, style));
+            if font.glyph_for_char(ch).unwrap_or(0) == 0 {
+                continue;
+            }
+            if script != Script::Emoji || font.has_color_glyphs() {
+                return Some(font);
+            }
+            result.get_or_insert(font);
+        }
+        result
+    }
+
     pub fn all_font_families(&self) -> FontFamilies {
         if let Ok(fonts) = self.source.all_families() {
             let list = fonts.iter().map(|it| FontFamily::new(it)).collect();
@@ -52,6 +52,12 @@ impl ElementBackend for Image {
         img
     }
 
+    fn reset(&mut self) {
+        self.src.clear();
+        self.img = ImageObject::none();
+        self.element.mark_dirty(true);
+    }
+
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         None
     }
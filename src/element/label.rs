@@ -1,10 +1,12 @@
 use crate as deft;
 use crate::base::Rect;
 use crate::element::{Element, ElementBackend, ElementWeak};
-use crate::event::TextUpdateEvent;
+use crate::event::{TextUpdateEvent, TruncationChangeEvent};
 use crate::mrc::Mrc;
 use crate::ok_or_return;
+use crate::window::find::find_in_text_box;
 use crate::render::RenderFn;
+use crate::style::line_clamp::LineClamp;
 use crate::style::StylePropKey;
 use crate::text::textbox::{TextBox, TextElement, TextUnit};
 use crate::text::TextAlign;
@@ -30,6 +32,7 @@ pub struct Label {
 struct LabelState {
     text_box: TextBox,
     layout_calculated: bool,
+    truncated: bool,
 }
 
 #[js_methods]
@@ -55,6 +58,22 @@ impl Label {
         self.text.clone()
     }
 
+    #[js_func]
+    pub fn is_truncated(&self) -> bool {
+        self.state.text_box.is_truncated()
+    }
+
+    /// Bounds of text matching query, relative to the border box of element
+    pub fn find_text(&mut self, query: &str, case_sensitive: bool) -> Vec<Rect> {
+        let element = ok_or_return!(self.element.upgrade(), Vec::new());
+        let (bt, _, _, bl) = element.get_border_width();
+        let (pt, _, _, pl) = element.get_padding();
+        find_in_text_box(&mut self.state.text_box, query, case_sensitive)
+            .into_iter()
+            .map(|r| Rect::new(r.x + bl + pl, r.y + bt + pt, r.width, r.height))
+            .collect()
+    }
+
     fn mark_dirty(&mut self, layout_dirty: bool) {
         self.element.mark_dirty(layout_dirty);
     }
@@ -79,6 +98,7 @@ impl ElementBackend for Label {
         let state = LabelState {
             text_box: TextBox::new(),
             layout_calculated: false,
+            truncated: false,
         };
         let label = LabelData {
             text,
@@ -86,6 +106,7 @@ impl ElementBackend for Label {
             element: ele.as_weak(),
         }
         .to_ref();
+        ele.register_js_event::<TruncationChangeEvent>("truncationchange");
         ele.style
             .yoga_node
             .set_measure_func(label.state.clone(), |state, params| {
@@ -101,6 +122,10 @@ impl ElementBackend for Label {
         label
     }
 
+    fn reset(&mut self) {
+        self.set_text(String::new());
+    }
+
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         None
     }
@@ -139,6 +164,25 @@ impl ElementBackend for Label {
                 self.state.text_box.set_line_height(line_height);
                 self.mark_dirty(true);
             }
+            StylePropKey::DeftTextStroke => {
+                let width = element.style.text_stroke_width;
+                let color = element.style.text_stroke_color;
+                self.state.text_box.set_text_stroke(width, color);
+                self.mark_dirty(false);
+            }
+            StylePropKey::DeftTextFill => {
+                let text_fill = element.style.text_fill.clone();
+                self.state.text_box.set_text_fill(text_fill);
+                self.mark_dirty(false);
+            }
+            StylePropKey::DeftLineClamp => {
+                let line_clamp = match element.style.line_clamp {
+                    LineClamp::None => None,
+                    LineClamp::Lines(lines, mode) => Some((lines, mode)),
+                };
+                self.state.text_box.set_line_clamp(line_clamp);
+                self.mark_dirty(true);
+            }
             _ => {}
         }
     }
@@ -163,5 +207,10 @@ impl ElementBackend for Label {
             self.state.text_box.layout();
             self.state.layout_calculated = true;
         }
+        let truncated = self.state.text_box.is_truncated();
+        if self.state.truncated != truncated {
+            self.state.truncated = truncated;
+            self.element.emit(TruncationChangeEvent { truncated });
+        }
     }
 }
@@ -19,6 +19,7 @@ use crate::render::RenderFn;
 use crate::string::StringUtils;
 use crate::style::color::parse_optional_color_str;
 use crate::style::font::FontStyle;
+use crate::style::ruby::RubyPosition;
 use crate::style::{PropValueParse, StylePropKey};
 use crate::text::textbox::{TextCoord, TextUnit};
 use crate::text::{TextAlign, TextDecoration, TextStyle};
@@ -46,6 +47,9 @@ pub struct ParagraphParams {
     pub font_families: FontFamilies,
     pub font_weight: Weight,
     pub font_style: FontStyle,
+    /// Font size of ruby annotations relative to the base text
+    pub ruby_size: f32,
+    pub ruby_position: RubyPosition,
 }
 
 pub fn parse_optional_weight(value: Option<&String>) -> Option<Weight> {
@@ -1,11 +1,14 @@
 use crate::element::font_manager::FontManager;
 use crate::element::paragraph::ParagraphParams;
 use crate::element::text::simple_text_paragraph::{
-    chars_to_glyphs_vec, SimpleTextParagraph, TextBlock,
+    chars_to_glyphs_vec, Placeholder, Ruby, SimpleTextParagraph, TextBlock,
+    OBJECT_REPLACEMENT_CHARACTER,
 };
+use crate::font::fallback::{fallback_script, is_emoji_component, Script};
 use crate::font::Font;
 use crate::mrc::Mrc;
 use crate::some_or_continue;
+use crate::some_or_return;
 use crate::string::StringUtils;
 use crate::text::TextStyle;
 use log::warn;
@@ -20,7 +23,7 @@ pub struct SimpleParagraphBuilder {
     styles: Vec<TextStyle>,
     text_blocks: Vec<TextBlock>,
     font_manager: FontManager,
-    fallback_cache: Mrc<HashMap<char, Option<Font>>>,
+    fallback_cache: Mrc<HashMap<(char, Script), Option<Font>>>,
 }
 
 impl SimpleParagraphBuilder {
@@ -44,18 +47,72 @@ impl SimpleParagraphBuilder {
     pub fn add_text(&mut self, text: impl Into<String>) {
         let text = text.into();
         let style = self.styles.last().unwrap().clone();
+        let font_families_names = self.font_families_names(&style);
+        let mut text_blocks = self.resolve_font(&font_families_names, &style, &text);
+        // debug!("text_blocks: {:?} {:?}", &text, &text_blocks);
+        self.text_blocks.append(&mut text_blocks);
+    }
+
+    /// Add a box which flows inline with text, it is aligned with the font of current style
+    pub fn add_placeholder(&mut self, placeholder: Placeholder) {
+        let style = self.styles.last().unwrap().clone();
+        let font_families_names = self.font_families_names(&style);
+        let fonts = self
+            .font_manager
+            .match_best(&font_families_names, style.font_style());
+        let font = match fonts.into_iter().next() {
+            Some(f) => f,
+            None => {
+                warn!("No matching font found for {:?}", &font_families_names);
+                return;
+            }
+        };
+        self.text_blocks.push(TextBlock {
+            text: OBJECT_REPLACEMENT_CHARACTER.to_string(),
+            style,
+            font,
+            placeholder: Some(placeholder),
+            ruby: None,
+        });
+    }
+
+    /// Add base text with annotation rendered over or under it, they are never broken into lines
+    pub fn add_ruby(&mut self, base: impl Into<String>, annotation: impl Into<String>) {
+        let (base, annotation) = (base.into(), annotation.into());
+        let style = self.styles.last().unwrap().clone();
+        let mut annotation_style = style.clone();
+        annotation_style.set_font_size(style.font_size() * self.paragraph_params.ruby_size);
+        let font_families_names = self.font_families_names(&style);
+        // The whole text is laid out with the font of its first char
+        let base_blocks = self.resolve_font(&font_families_names, &style, &base);
+        let annotation_blocks =
+            self.resolve_font(&font_families_names, &annotation_style, &annotation);
+        let base_block = some_or_return!(base_blocks.into_iter().next());
+        let annotation_block = some_or_return!(annotation_blocks.into_iter().next());
+        let ruby = Ruby {
+            annotation: TextBlock {
+                text: annotation,
+                ..annotation_block
+            },
+            position: self.paragraph_params.ruby_position,
+        };
+        self.text_blocks.push(TextBlock {
+            text: base,
+            ruby: Some(Box::new(ruby)),
+            ..base_block
+        });
+    }
+
+    fn font_families_names<'a>(&'a self, style: &'a TextStyle) -> Vec<&'a str> {
         let font_families = style
             .font_families()
             .as_ref()
             .unwrap_or(&self.paragraph_params.font_families);
-        let font_families_names = font_families
+        font_families
             .as_slice()
             .iter()
             .map(|it| it.name())
-            .collect::<Vec<_>>();
-        let mut text_blocks = self.resolve_font(&font_families_names, &style, &text);
-        // debug!("text_blocks: {:?} {:?}", &text, &text_blocks);
-        self.text_blocks.append(&mut text_blocks);
+            .collect()
     }
 
     fn resolve_font(
@@ -75,37 +132,42 @@ impl SimpleParagraphBuilder {
         let chars = text.chars().collect::<Vec<_>>();
         let (mut resolved_typefaces, _unresolved_count) = Self::do_resolve_font(&chars, &fonts);
         for i in 0..chars.len() {
-            if resolved_typefaces[i] != -1 {
-                continue;
-            }
             let ch = chars[i];
-            if ch == '\n' {
+            if ch == '\n' || is_emoji_component(ch) {
                 continue;
             }
-            let cached_tf = match self.fallback_cache.get(&ch) {
+            let script = fallback_script(&chars, i);
+            if resolved_typefaces[i] != -1 {
+                // Emoji should be colored even if a font of text has monochrome glyph of it
+                let font = &fonts[resolved_typefaces[i] as usize];
+                if script != Script::Emoji || font.has_color_glyphs() {
+                    continue;
+                }
+            }
+            let cached_tf = match self.fallback_cache.get(&(ch, script)) {
                 Some(tf) => tf,
                 None => {
-                    //TODO fix locale
-                    // let tf = self.font_collection.default_fallback_char(chars[i] as Unichar, style.font_style(), "en");
-                    let tf = None;
-                    self.fallback_cache.clone().insert(ch, tf);
-                    self.fallback_cache.get(&ch).unwrap()
+                    let tf = self
+                        .font_manager
+                        .fallback_for_char(ch, script, style.font_style());
+                    self.fallback_cache.clone().insert((ch, script), tf);
+                    self.fallback_cache.get(&(ch, script)).unwrap()
                 }
             };
 
             let tf = some_or_continue!(cached_tf);
-            //TODO fix equal check
-            /*
-            for tf_idx in 0..fonts.len() {
-                if fonts[tf_idx] == tf {
-                    resolved_typefaces[i] = tf_idx as i32;
-                    break;
+            match fonts.iter().position(|f| f == tf) {
+                Some(tf_idx) => resolved_typefaces[i] = tf_idx as i32,
+                None => {
+                    fonts.push(tf.clone());
+                    resolved_typefaces[i] = (fonts.len() - 1) as i32;
                 }
             }
-             */
-            if resolved_typefaces[i] == -1 {
-                fonts.push(tf.clone());
-                resolved_typefaces[i] = (fonts.len() - 1) as i32;
+        }
+        // Joiners, variation selectors and modifiers are kept in the font of the emoji
+        for i in 1..chars.len() {
+            if is_emoji_component(chars[i]) && resolved_typefaces[i - 1] != -1 {
+                resolved_typefaces[i] = resolved_typefaces[i - 1];
             }
         }
         for i in 0..resolved_typefaces.len() {
@@ -128,6 +190,8 @@ impl SimpleParagraphBuilder {
                 text: text.substring(begin, end - begin).to_string(),
                 style: style.clone(),
                 font,
+                placeholder: None,
+                ruby: None,
             });
         }
         text_blocks
@@ -163,6 +227,7 @@ mod tests {
     use crate::element::paragraph::{ParagraphParams, ZERO_WIDTH_WHITESPACE};
     use crate::font::family::{FontFamilies, FontFamily};
     use crate::style::font::FontStyle;
+    use crate::style::ruby::{RubyPosition, DEFAULT_RUBY_SIZE};
     use measure_time::print_time;
     use skia_safe::font_style::Weight;
 
@@ -179,6 +244,8 @@ mod tests {
                 font_weight: Weight::NORMAL,
                 font_style: FontStyle::Normal,
                 mask_char: None,
+                ruby_size: DEFAULT_RUBY_SIZE,
+                ruby_position: RubyPosition::Over,
             };
             let mut pb = SimpleParagraphBuilder::new(&params);
             let str = include_str!("../../../Cargo.lock");
@@ -202,6 +269,8 @@ mod tests {
             font_weight: Weight::NORMAL,
             font_style: FontStyle::Normal,
             mask_char: None,
+            ruby_size: DEFAULT_RUBY_SIZE,
+            ruby_position: RubyPosition::Over,
         };
         let mut pb = SimpleParagraphBuilder::new(&params);
         pb.add_text(format!("{}{}", "12", ZERO_WIDTH_WHITESPACE));
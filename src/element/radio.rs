@@ -5,12 +5,13 @@ use crate::element::image::Image;
 use crate::element::label::Label;
 use crate::element::{Element, ElementBackend, ElementWeak};
 use crate::event::{ClickEvent, Event};
+use crate::style::display::Display;
 use crate::style::length::LengthOrPercent;
 use crate::style::{FixedStyleProp, ResolvedStyleProp, StylePropVal};
 use crate::{ok_or_return, some_or_return};
 use deft_macros::{element_backend, event, js_methods};
 use std::collections::HashMap;
-use yoga::{Align, Display, FlexDirection};
+use yoga::{Align, FlexDirection};
 
 #[element_backend]
 pub struct Radio {
@@ -145,6 +146,11 @@ impl ElementBackend for Radio {
         inst
     }
 
+    fn reset(&mut self) {
+        self.set_label(String::new());
+        self.base.reset();
+    }
+
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         Some(&mut self.base)
     }
@@ -1,22 +1,42 @@
 use crate as deft;
-use crate::base::EventContext;
+use crate::base::{EventContext, Rect};
 use crate::element::{Element, ElementBackend, ElementWeak};
-use crate::event::Event;
-use crate::ok_or_return;
+use crate::event::{
+    BlurEvent, ClickEvent, Event, KeyDownEvent, LinkClickEvent, LinkHoverEvent, MouseLeaveEvent,
+    MouseMoveEvent, KEY_MOD_SHIFT,
+};
 use crate::render::RenderFn;
+use crate::style::line_clamp::LineClamp;
 use crate::style::StylePropKey;
-use crate::text::textbox::{TextBox, TextCoord, TextElement};
+use crate::text::textbox::{LinkPosition, TextBox, TextCoord, TextElement};
+use crate::window::find::find_in_text_box;
+use crate::{ok_or_return, some_or_continue};
 use deft_macros::{element_backend, js_methods};
-use yoga::Size;
+use ordered_float::OrderedFloat;
+use skia_safe::{Paint, PaintStyle};
+use winit::keyboard::NamedKey;
+use winit::window::{Cursor, CursorIcon};
+use yoga::{Direction, Edge, Size, StyleUnit};
 
 #[element_backend]
 pub struct RichText {
     element: ElementWeak,
     text_box: TextBox,
+    hover_link: Option<LinkPosition>,
+    /// Link activated by Enter key, moved by Tab and Shift+Tab
+    focused_link: Option<LinkPosition>,
+    /// Cursor of element before hovering a link
+    saved_cursor: Option<Cursor>,
+    layout_calculated: bool,
 }
 
 #[js_methods]
 impl RichText {
+    #[js_func]
+    pub fn is_truncated(&self) -> bool {
+        self.text_box.is_truncated()
+    }
+
     #[js_func]
     pub fn add_line(&mut self, units: Vec<TextElement>) {
         self.text_box.add_line(units);
@@ -37,6 +57,16 @@ impl RichText {
         self.text_box.update_line(index, units);
     }
 
+    /// Bounds of text matching query, relative to the border box of element
+    pub fn find_text(&mut self, query: &str, case_sensitive: bool) -> Vec<Rect> {
+        let element = ok_or_return!(self.element.upgrade(), Vec::new());
+        let (bt, _, _, bl) = element.get_border_width();
+        find_in_text_box(&mut self.text_box, query, case_sensitive)
+            .into_iter()
+            .map(|r| Rect::new(r.x + bl, r.y + bt, r.width, r.height))
+            .collect()
+    }
+
     #[js_func]
     pub fn clear(&mut self) {
         self.text_box.clear();
@@ -57,10 +87,78 @@ impl RichText {
         self.text_box.get_selection_text()
     }
 
+    fn update_hover_link(&mut self, link: Option<LinkPosition>) {
+        if self.hover_link == link {
+            return;
+        }
+        self.hover_link = link;
+        let mut element = ok_or_return!(self.element.upgrade());
+        if link.is_some() {
+            if self.saved_cursor.is_none() {
+                self.saved_cursor = Some(element.get_cursor());
+            }
+            element.set_cursor(Cursor::Icon(CursorIcon::Pointer));
+        } else if let Some(cursor) = self.saved_cursor.take() {
+            element.set_cursor(cursor);
+        }
+        let href = link.and_then(|l| Some(self.text_box.get_link(l)?.href.clone()));
+        self.element.emit(LinkHoverEvent { href });
+    }
+
+    fn activate_link(&mut self, link: LinkPosition) {
+        if let Some(l) = self.text_box.get_link(link) {
+            let href = l.href.clone();
+            self.element.emit(LinkClickEvent { href });
+        }
+    }
+
+    fn move_focused_link(&mut self, backward: bool) -> bool {
+        let next = self.text_box.get_next_link(self.focused_link, backward);
+        if next.is_some() {
+            self.focused_link = next;
+            self.element.mark_dirty(false);
+        }
+        next.is_some()
+    }
+
     fn layout(&mut self, width: f32) {
         //TODO twice layout occurs here?
+        self.measure_embeds();
         self.text_box.set_layout_width(width);
         self.text_box.layout();
+        self.layout_calculated = true;
+    }
+
+    /// Layout embedded children independently, their sizes are used by text layout
+    fn measure_embeds(&mut self) {
+        let element = ok_or_return!(self.element.upgrade());
+        let embeds = self.text_box.get_embeds();
+        for mut child in element.get_children() {
+            let eid = child.get_eid();
+            if !embeds.contains(&eid) {
+                continue;
+            }
+            child.before_layout_recurse();
+            child
+                .style
+                .calculate_layout(f32::NAN, f32::NAN, Direction::LTR);
+            let bounds = child.get_bounds();
+            self.text_box
+                .set_embed_size(eid, (bounds.width, bounds.height));
+        }
+    }
+
+    /// Move embedded children to the boxes reserved by text layout
+    fn position_embeds(&mut self) {
+        let element = ok_or_return!(self.element.upgrade());
+        let children = element.get_children();
+        for (eid, rect) in self.text_box.get_embed_bounds() {
+            let mut child = some_or_continue!(children.iter().find(|c| c.get_eid() == eid)).clone();
+            let node = &mut child.style.yoga_node._yn;
+            node.set_position(Edge::Left, StyleUnit::Point(OrderedFloat(rect.x)));
+            node.set_position(Edge::Top, StyleUnit::Point(OrderedFloat(rect.y)));
+            node.calculate_layout(f32::NAN, f32::NAN, Direction::LTR);
+        }
     }
 }
 
@@ -81,8 +179,14 @@ impl ElementBackend for RichText {
         let this = RichTextData {
             element: element.as_weak(),
             text_box,
+            hover_link: None,
+            focused_link: None,
+            saved_cursor: None,
+            layout_calculated: false,
         }
         .to_ref();
+        element.register_js_event::<LinkClickEvent>("linkclick");
+        element.register_js_event::<LinkHoverEvent>("linkhover");
         element
             .style
             .yoga_node
@@ -102,6 +206,10 @@ impl ElementBackend for RichText {
         this
     }
 
+    fn reset(&mut self) {
+        self.clear();
+    }
+
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         None
     }
@@ -128,15 +236,99 @@ impl ElementBackend for RichText {
             StylePropKey::LineHeight => {
                 self.text_box.set_line_height(element.style.line_height);
             }
+            StylePropKey::DeftTextStroke => {
+                let style = &element.style;
+                self.text_box
+                    .set_text_stroke(style.text_stroke_width, style.text_stroke_color);
+            }
+            StylePropKey::DeftTextFill => {
+                self.text_box.set_text_fill(element.style.text_fill.clone());
+            }
+            StylePropKey::DeftLineClamp => {
+                let line_clamp = match element.style.line_clamp {
+                    LineClamp::None => None,
+                    LineClamp::Lines(lines, mode) => Some((lines, mode)),
+                };
+                self.text_box.set_line_clamp(line_clamp);
+            }
+            StylePropKey::RubyPosition => {
+                self.text_box.set_ruby_position(element.style.ruby_position);
+            }
+            StylePropKey::DeftRubySize => {
+                self.text_box.set_ruby_size(element.style.ruby_size);
+            }
             _ => {}
         }
     }
 
+    fn before_layout(&mut self) {
+        self.layout_calculated = false;
+    }
+
+    fn handle_origin_bounds_change(&mut self, bounds: &Rect) {
+        if !self.layout_calculated {
+            self.layout(bounds.width);
+        }
+        self.position_embeds();
+    }
+
     fn render(&mut self) -> RenderFn {
-        self.text_box.render()
+        let text_renderer = self.text_box.render();
+        let focus_rects = match self.focused_link {
+            Some(link) => self.text_box.get_link_rects(link),
+            None => Vec::new(),
+        };
+        if focus_rects.is_empty() {
+            return text_renderer;
+        }
+        let mut focus_paint = Paint::default();
+        focus_paint.set_style(PaintStyle::Stroke);
+        focus_paint.set_stroke_width(1.0);
+        focus_paint.set_color(self.text_box.get_paragraph_params().color);
+        RenderFn::new(move |painter| {
+            painter.canvas.save();
+            text_renderer.run(painter);
+            painter.canvas.restore();
+            for r in &focus_rects {
+                painter.canvas.draw_rect(r.to_skia_rect(), &focus_paint);
+            }
+        })
     }
 
     fn on_event(&mut self, event: &mut Event, ctx: &mut EventContext<ElementWeak>) {
+        if let Some(e) = MouseMoveEvent::cast(event) {
+            let link = self.text_box.get_link_at((e.0.offset_x, e.0.offset_y));
+            self.update_hover_link(link);
+        } else if MouseLeaveEvent::is(event) {
+            self.update_hover_link(None);
+        } else if let Some(e) = ClickEvent::cast(event) {
+            if self.text_box.get_selection().is_empty() {
+                if let Some(link) = self.text_box.get_link_at((e.0.offset_x, e.0.offset_y)) {
+                    self.focused_link = Some(link);
+                    self.activate_link(link);
+                }
+            }
+        } else if let Some(e) = KeyDownEvent::cast(event) {
+            let shift = e.0.modifiers == KEY_MOD_SHIFT;
+            match e.0.named_key {
+                Some(NamedKey::Tab) if e.0.modifiers == 0 || shift => {
+                    if self.move_focused_link(shift) {
+                        return;
+                    }
+                }
+                Some(NamedKey::Enter) if e.0.modifiers == 0 => {
+                    if let Some(link) = self.focused_link {
+                        self.activate_link(link);
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        } else if BlurEvent::is(event) {
+            if self.focused_link.take().is_some() {
+                self.element.mark_dirty(false);
+            }
+        }
         self.text_box.on_event(&event, ctx, 0.0, 0.0);
     }
 }
@@ -0,0 +1,609 @@
+pub mod document;
+
+use crate as deft;
+use crate::app::AppEvent;
+use crate::base::{EventContext, Rect};
+use crate::canvas_util::CanvasHelper;
+use crate::element::body::zoom_by_shortcut;
+use crate::element::edit_history::{EditDetail, EditHistory};
+use crate::element::richtextedit::document::{
+    RichContent, RichDocument, RichFormat, RichImage, RunAttributes,
+};
+use crate::element::util::is_form_event;
+use crate::element::{Element, ElementBackend, ElementWeak};
+use crate::event::{
+    BlurEvent, ClickEvent, Event, FocusEvent, KeyDownEvent, KeyEventDetail, LinkClickEvent,
+    PreeditEvent, TextChangeEvent, TextInputEvent, KEY_MOD_CTRL, KEY_MOD_SHIFT,
+};
+use crate::event_loop::create_event_loop_proxy;
+use crate::render::RenderFn;
+use crate::string::StringUtils;
+use crate::style::StylePropKey;
+use crate::text::textbox::{TextBox, TextCoord};
+use crate::timer::TimerHandle;
+use crate::{ok_or_return, some_or_return, timer};
+use deft_macros::{element_backend, js_methods};
+use skia_safe::Paint;
+use std::cell::Cell;
+use std::rc::Rc;
+use winit::keyboard::NamedKey;
+use yoga::Size;
+
+/// Editor of text with inline formatting, links and images
+#[element_backend]
+pub struct RichTextEdit {
+    element: ElementWeak,
+    document: RichDocument,
+    text_box: TextBox,
+    edit_history: EditHistory<RichDocument>,
+    /// Attributes of text typed at the coord, set by formatting a collapsed selection
+    typing_attributes: Option<(TextCoord, RunAttributes)>,
+    caret_visible: Rc<Cell<bool>>,
+    caret_timer_handle: Option<TimerHandle>,
+    focusing: bool,
+    disabled: bool,
+    layout_calculated: bool,
+}
+
+#[js_methods]
+impl RichTextEdit {
+    #[js_func]
+    pub fn get_content(&self) -> RichContent {
+        self.document.to_content()
+    }
+
+    /// Replace content, history is cleared
+    #[js_func]
+    pub fn set_content(&mut self, content: RichContent) {
+        self.document = RichDocument::from_content(&content);
+        self.text_box.clear();
+        for row in 0..self.document.line_count() {
+            self.text_box.add_line(self.document.line_elements(row));
+        }
+        self.edit_history = EditHistory::new(self.edit_history.get_max_history());
+        self.typing_attributes = None;
+        self.text_box.select(TextCoord(0, 0), TextCoord(0, 0));
+        self.text_box.update_caret_value(TextCoord(0, 0), false);
+        self.element.mark_dirty(true);
+    }
+
+    /// Plain text, images are replaced by object replacement characters
+    #[js_func]
+    pub fn get_text(&self) -> String {
+        self.document.get_text()
+    }
+
+    #[js_func]
+    pub fn get_selection_text(&self) -> Option<String> {
+        self.text_box.get_selection_text()
+    }
+
+    /// Attributes shared by selected text, or attributes of text typed at caret
+    #[js_func]
+    pub fn get_format(&self) -> RunAttributes {
+        let (start, end) = self.text_box.get_selection().normalize();
+        if start == end {
+            self.typing_attributes(start)
+        } else {
+            self.document.common_attributes(start, end)
+        }
+    }
+
+    /// Format selected text, or text typed next if nothing is selected
+    #[js_func]
+    pub fn format(&mut self, format: RichFormat) {
+        let (start, end) = self.text_box.get_selection().normalize();
+        if start == end {
+            let mut attrs = self.typing_attributes(start);
+            format.apply(&mut attrs);
+            self.typing_attributes = Some((start, attrs));
+            return;
+        }
+        let mut formatted = self.document.slice(start, end);
+        formatted.format(TextCoord(0, 0), formatted.end(), &format);
+        self.replace(start, end, formatted, true);
+        self.text_box.select(start, end);
+    }
+
+    /// Replace selection with an inline image
+    #[js_func]
+    pub fn insert_image(&mut self, image: RichImage) {
+        let (start, end) = self.text_box.get_selection().normalize();
+        self.replace(start, end, RichDocument::from_image(image), true);
+    }
+
+    #[js_func]
+    pub fn set_selection_by_char_offset(&mut self, start: usize, end: usize) {
+        let start = some_or_return!(self.text_box.get_text_coord_by_char_offset(start));
+        let end = some_or_return!(self.text_box.get_text_coord_by_char_offset(end));
+        self.text_box.select(start, end);
+        self.text_box.update_caret_value(end, false);
+    }
+
+    #[js_func]
+    pub fn set_max_history(&mut self, max_history: usize) {
+        self.edit_history.set_max_history(max_history);
+    }
+
+    #[js_func]
+    pub fn get_max_history(&self) -> usize {
+        self.edit_history.get_max_history()
+    }
+
+    #[js_func]
+    pub fn undo(&mut self) {
+        if let Some(op) = self.edit_history.undo() {
+            let end = op.insert.map(|it| it.end).unwrap_or(op.caret);
+            let content = op.delete.map(|it| it.content).unwrap_or_default();
+            self.replace(op.caret, end, content, false);
+        }
+    }
+
+    #[js_func]
+    pub fn redo(&mut self) {
+        if let Some(op) = self.edit_history.redo() {
+            let end = op.delete.map(|it| it.end).unwrap_or(op.caret);
+            let content = op.insert.map(|it| it.content).unwrap_or_default();
+            self.replace(op.caret, end, content, false);
+        }
+    }
+
+    fn typing_attributes(&self, caret: TextCoord) -> RunAttributes {
+        match &self.typing_attributes {
+            Some((coord, attrs)) if *coord == caret => attrs.clone(),
+            _ => self.document.typing_attributes_at(caret),
+        }
+    }
+
+    /// Replace content in range with fragment, and move caret to the end of fragment
+    fn replace(
+        &mut self,
+        start: TextCoord,
+        end: TextCoord,
+        fragment: RichDocument,
+        record_history: bool,
+    ) -> TextCoord {
+        let (start, end) = (start.min(end), start.max(end));
+        let deleted = self.document.delete(start, end);
+        let inserted = fragment.clone();
+        let new_end = self.document.insert(start, fragment);
+        self.sync_lines(start.0, end.0 - start.0 + 1, new_end.0 - start.0 + 1);
+        self.typing_attributes = None;
+        self.text_box.select(new_end, new_end);
+        self.text_box.update_caret_value(new_end, false);
+        if record_history {
+            let delete_detail = (!deleted.is_empty()).then(|| EditDetail {
+                content: deleted,
+                end,
+            });
+            let insert_detail = (!inserted.is_empty()).then(|| EditDetail {
+                content: inserted,
+                end: new_end,
+            });
+            self.edit_history
+                .record_input(start, delete_detail, insert_detail);
+        }
+        self.element.emit(TextChangeEvent {
+            value: self.document.get_text(),
+        });
+        self.element.mark_dirty(true);
+        new_end
+    }
+
+    /// Replace `old_count` lines of text box from row with lines of document
+    fn sync_lines(&mut self, row: usize, old_count: usize, new_count: usize) {
+        for _ in new_count..old_count {
+            self.text_box.delete_line(row + new_count);
+        }
+        for i in 0..new_count {
+            let units = self.document.line_elements(row + i);
+            if i < old_count {
+                self.text_box.update_line(row + i, units);
+            } else {
+                self.text_box.insert_line(row + i, units);
+            }
+        }
+    }
+
+    fn handle_input(&mut self, input: &str) {
+        let (start, end) = self.text_box.get_selection().normalize();
+        let caret = self.text_box.get_caret();
+        let (start, end) = if start == end {
+            (caret, caret)
+        } else {
+            (start, end)
+        };
+        let attrs = self.typing_attributes(start);
+        self.replace(start, end, RichDocument::from_text(input, &attrs), true);
+    }
+
+    fn delete_backward(&mut self) {
+        if self.text_box.get_selection().is_empty() {
+            let end = self.text_box.get_caret();
+            let start = some_or_return!(self.text_box.calculate_caret(-1));
+            self.text_box.select(start, end);
+        }
+        self.handle_input("");
+    }
+
+    fn delete_forward(&mut self) {
+        if self.text_box.get_selection().is_empty() {
+            let start = self.text_box.get_caret();
+            let end = some_or_return!(self.text_box.calculate_caret(1));
+            self.text_box.select(start, end);
+        }
+        self.handle_input("");
+    }
+
+    fn toggle_format(
+        &mut self,
+        update: fn(&mut RichFormat, bool),
+        current: fn(&RunAttributes) -> bool,
+    ) {
+        let mut format = RichFormat::default();
+        update(&mut format, !current(&self.get_format()));
+        self.format(format);
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn copy(&self) {
+        use clipboard::{ClipboardContext, ClipboardProvider};
+        let text = some_or_return!(self.text_box.get_selection_text());
+        if !text.is_empty() {
+            let mut ctx: ClipboardContext = ClipboardProvider::new().unwrap();
+            ctx.set_contents(text).unwrap();
+        }
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn cut(&mut self) {
+        self.copy();
+        self.handle_input("");
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn paste(&mut self) {
+        use clipboard::{ClipboardContext, ClipboardProvider};
+        let mut ctx: ClipboardContext = ClipboardProvider::new().unwrap();
+        if let Ok(text) = ctx.get_contents() {
+            self.handle_input(&text);
+        }
+    }
+
+    fn handle_key_down(&mut self, event: &KeyEventDetail) {
+        if event.modifiers == 0 || event.modifiers == KEY_MOD_SHIFT {
+            match &event.named_key {
+                Some(NamedKey::Backspace) => self.delete_backward(),
+                Some(NamedKey::Delete) => self.delete_forward(),
+                Some(NamedKey::Enter) => self.handle_input("\n"),
+                Some(NamedKey::ArrowLeft) => self.text_box.move_caret(-1),
+                Some(NamedKey::ArrowRight) => self.text_box.move_caret(1),
+                Some(NamedKey::ArrowUp) => self.text_box.move_caret_vertical(true),
+                Some(NamedKey::ArrowDown) => self.text_box.move_caret_vertical(false),
+                Some(NamedKey::Home) => self
+                    .text_box
+                    .move_caret_to_line_edge(false, event.modifiers == KEY_MOD_SHIFT),
+                Some(NamedKey::End) => self
+                    .text_box
+                    .move_caret_to_line_edge(true, event.modifiers == KEY_MOD_SHIFT),
+                Some(NamedKey::Space) => self.handle_input(" "),
+                Some(NamedKey::Tab) => self.handle_input("\t"),
+                Some(_) => {}
+                None => {
+                    if let Some(text) = &event.key_str {
+                        self.handle_input(text);
+                    }
+                }
+            }
+        } else if event.modifiers == KEY_MOD_CTRL {
+            if let Some(text) = &event.key_str {
+                match text.as_str() {
+                    #[cfg(feature = "clipboard")]
+                    "c" => self.copy(),
+                    #[cfg(feature = "clipboard")]
+                    "x" => self.cut(),
+                    #[cfg(feature = "clipboard")]
+                    "v" => self.paste(),
+                    "a" => self.text_box.select_all(),
+                    "z" => self.undo(),
+                    "b" => self.toggle_format(|f, v| f.bold = Some(v), |a| a.bold),
+                    "i" => self.toggle_format(|f, v| f.italic = Some(v), |a| a.italic),
+                    "u" => self.toggle_format(|f, v| f.underline = Some(v), |a| a.underline),
+                    _ => {}
+                }
+            }
+        } else if event.modifiers == KEY_MOD_CTRL | KEY_MOD_SHIFT {
+            if let Some(text) = &event.key_str {
+                if text.to_lowercase() == "z" {
+                    self.redo();
+                }
+            }
+        }
+    }
+
+    fn handle_preedit(&mut self, content: &str, offset: Option<usize>) {
+        self.handle_input(content);
+        if content.is_empty() {
+            return;
+        }
+        // Composition text is selected, so that it is replaced by next preedit or commit
+        let end_caret = self.text_box.get_caret();
+        let content_chars_count = content.chars_count() as isize;
+        if let Some(start_caret) = self.text_box.calculate_caret(-content_chars_count) {
+            self.text_box.select(start_caret, end_caret);
+            if let Some(offset) = offset {
+                let char_offset = content[0..offset].chars_count() as isize;
+                if char_offset < content_chars_count {
+                    self.text_box.move_caret(char_offset - content_chars_count);
+                }
+            }
+        }
+    }
+
+    fn update_ime(&mut self) -> Option<()> {
+        let caret = self.text_box.get_caret_rect()?;
+        let el = self.element.upgrade_mut().ok()?;
+        let win = el.get_window()?;
+        let win = win.upgrade_mut().ok()?;
+        win.set_ime_cursor_area(&el, Rect::new(caret.x, caret.y, 1.0, caret.height));
+        Some(())
+    }
+
+    fn handle_focus(&mut self) {
+        let _ = self.update_ime();
+        self.focusing = true;
+        self.caret_visible.set(true);
+        self.caret_timer_handle = Some({
+            let caret_visible = self.caret_visible.clone();
+            let mut context = self.element.clone();
+            timer::set_interval(
+                move || {
+                    caret_visible.set(!caret_visible.get());
+                    context.mark_dirty(false);
+                },
+                500,
+            )
+        });
+        self.show_soft_input(true);
+    }
+
+    fn handle_blur(&mut self) {
+        self.focusing = false;
+        self.caret_timer_handle = None;
+        self.caret_visible.set(false);
+        self.show_soft_input(false);
+    }
+
+    fn show_soft_input(&mut self, visible: bool) {
+        let mut element = ok_or_return!(self.element.upgrade_mut());
+        element.mark_dirty(false);
+        if let Some(window) = element.get_window() {
+            if let Ok(f) = window.upgrade_mut() {
+                let event = if visible {
+                    AppEvent::ShowSoftInput(f.get_id())
+                } else {
+                    AppEvent::HideSoftInput(f.get_id())
+                };
+                create_event_loop_proxy().send_event(event).unwrap();
+            }
+        }
+    }
+
+    fn layout(&mut self, width: f32) {
+        self.text_box.set_layout_width(width);
+        self.text_box.layout();
+        self.layout_calculated = true;
+    }
+}
+
+impl ElementBackend for RichTextEdit {
+    fn create(element: &mut Element) -> Self
+    where
+        Self: Sized,
+    {
+        element.allow_ime = true;
+        element.set_focusable(true);
+        element.is_form_element = true;
+        let document = RichDocument::new();
+        let mut text_box = TextBox::new();
+        text_box.add_line(document.line_elements(0));
+        {
+            let mut el = element.as_weak();
+            text_box.set_repaint_callback(move || el.mark_dirty(false));
+        }
+        {
+            let mut el = element.as_weak();
+            text_box.set_layout_callback(move || el.mark_dirty(true));
+        }
+        let mut this = RichTextEditData {
+            element: element.as_weak(),
+            document,
+            text_box,
+            edit_history: EditHistory::new(100),
+            typing_attributes: None,
+            caret_visible: Rc::new(Cell::new(false)),
+            caret_timer_handle: None,
+            focusing: false,
+            disabled: false,
+            layout_calculated: false,
+        }
+        .to_ref();
+        {
+            let weak = this.as_weak();
+            this.text_box.set_caret_change_callback(move || {
+                let mut me = ok_or_return!(weak.upgrade());
+                let _ = me.update_ime();
+                me.element.mark_dirty(false);
+            });
+        }
+        element.register_js_event::<LinkClickEvent>("linkclick");
+        element
+            .style
+            .yoga_node
+            .set_measure_func(this.as_weak(), |edit_weak, params| {
+                if let Ok(mut edit) = edit_weak.upgrade() {
+                    edit.layout(params.width);
+                    return Size {
+                        width: edit.text_box.max_intrinsic_width(),
+                        height: edit.text_box.height(),
+                    };
+                }
+                Size {
+                    width: 0.0,
+                    height: 0.0,
+                }
+            });
+        this
+    }
+
+    fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
+        None
+    }
+
+    fn handle_style_changed(&mut self, key: StylePropKey) {
+        let element = ok_or_return!(self.element.upgrade());
+        match key {
+            StylePropKey::Color => {
+                self.text_box.set_color(element.style.color);
+            }
+            StylePropKey::FontSize => {
+                self.text_box.set_font_size(element.style.font_size);
+            }
+            StylePropKey::FontFamily => {
+                self.text_box
+                    .set_font_families(element.style.font_family.clone());
+            }
+            StylePropKey::FontWeight => {
+                self.text_box.set_font_weight(element.style.font_weight);
+            }
+            StylePropKey::FontStyle => {
+                self.text_box.set_font_style(element.style.font_style);
+            }
+            StylePropKey::LineHeight => {
+                self.text_box.set_line_height(element.style.line_height);
+            }
+            _ => {}
+        }
+    }
+
+    fn before_layout(&mut self) {
+        self.layout_calculated = false;
+    }
+
+    fn handle_origin_bounds_change(&mut self, bounds: &Rect) {
+        if !self.layout_calculated {
+            self.layout(bounds.width);
+        }
+    }
+
+    fn render(&mut self) -> RenderFn {
+        let element = ok_or_return!(self.element.upgrade(), RenderFn::empty());
+        let text_renderer = self.text_box.render();
+        let caret = match self.focusing && self.caret_visible.get() {
+            true => self.text_box.get_caret_rect(),
+            false => None,
+        };
+        let mut paint = Paint::default();
+        paint.set_color(element.style.color);
+        paint.set_stroke_width(2.0);
+        RenderFn::new(move |painter| {
+            let canvas = painter.canvas;
+            canvas.session(|_| {
+                text_renderer.run(painter);
+            });
+            if let Some(caret) = caret {
+                canvas.draw_line((caret.x, caret.y), (caret.x, caret.bottom()), &paint);
+            }
+        })
+    }
+
+    fn on_event(&mut self, event: &mut Event, ctx: &mut EventContext<ElementWeak>) {
+        if self.disabled && is_form_event(event) {
+            ctx.propagation_cancelled = true;
+            return;
+        }
+        if let Some(e) = ClickEvent::cast(event) {
+            // Caret is moved by text box as well, listeners decide whether to follow the link
+            if let Some(link) = self.text_box.get_link_at((e.0.offset_x, e.0.offset_y)) {
+                if let Some(l) = self.text_box.get_link(link) {
+                    let href = l.href.clone();
+                    self.element.emit(LinkClickEvent { href });
+                }
+            }
+        }
+        if self.text_box.on_event(event, ctx, 0.0, 0.0) {
+            return;
+        }
+        if FocusEvent::is(event) {
+            self.handle_focus();
+        } else if BlurEvent::is(event) {
+            self.handle_blur();
+        } else if let Some(e) = TextInputEvent::cast(event) {
+            self.handle_input(e.0.as_str());
+        }
+    }
+
+    fn execute_default_behavior(
+        &mut self,
+        event: &mut Event,
+        _ctx: &mut EventContext<ElementWeak>,
+    ) -> bool {
+        if let Some(e) = KeyDownEvent::cast(event) {
+            self.handle_key_down(&e.0);
+            // Keys are consumed by editor, except zoom shortcuts handled by body
+            return zoom_by_shortcut(&e.0, 1.0).is_none();
+        } else if let Some(e) = PreeditEvent::cast(event) {
+            let (content, offset) = (e.content.clone(), e.offset);
+            self.handle_preedit(&content, offset);
+        }
+        false
+    }
+
+    fn on_attribute_changed(&mut self, key: &str, value: Option<&str>) {
+        match key {
+            "disabled" => self.disabled = value.is_some(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::richtextedit::document::{RichFormat, RichImage};
+    use crate::element::richtextedit::RichTextEdit;
+    use crate::element::{Element, ElementBackend};
+    use crate::text::textbox::TextCoord;
+
+    #[test]
+    fn test_edit_content() {
+        let mut el = Element::create(RichTextEdit::create);
+        let edit = el.get_backend_mut_as::<RichTextEdit>();
+        edit.handle_input("hello world");
+        edit.text_box.select(TextCoord(0, 6), TextCoord(0, 11));
+        edit.format(RichFormat {
+            bold: Some(true),
+            ..Default::default()
+        });
+        assert!(edit.get_format().bold);
+        edit.text_box.select(TextCoord(0, 11), TextCoord(0, 11));
+        edit.insert_image(RichImage {
+            src: "a.png".to_string(),
+            width: Some(10.0),
+            height: Some(10.0),
+        });
+        assert_eq!(edit.get_text(), "hello world\u{FFFC}");
+        let content = edit.get_content();
+        assert_eq!(content.ops.len(), 3);
+
+        edit.undo();
+        assert_eq!(edit.get_content().ops.len(), 2);
+        edit.undo();
+        assert_eq!(edit.get_content().ops.len(), 1);
+        edit.redo();
+        assert_eq!(edit.get_content().ops.len(), 2);
+
+        edit.set_content(content.clone());
+        assert_eq!(edit.get_content(), content);
+        assert_eq!(edit.text_box.get_lines().len(), 1);
+    }
+}
@@ -0,0 +1,583 @@
+use crate as deft;
+use crate::element::edit_history::EditContent;
+use crate::element::text::simple_text_paragraph::OBJECT_REPLACEMENT_CHARACTER;
+use crate::string::StringUtils;
+use crate::text::textbox::{TextCoord, TextElement, TextImage, TextLink, TextUnit};
+use crate::{js_deserialize, js_serialize};
+use serde::{Deserialize, Serialize};
+use std::mem;
+
+/// Inline formatting of a text run
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RunAttributes {
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub bold: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub italic: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub underline: bool,
+    /// Href of link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+js_serialize!(RunAttributes);
+
+impl RunAttributes {
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Changes of formatting, unspecified attributes are kept. An empty link removes the link.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RichFormat {
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub link: Option<String>,
+}
+
+js_deserialize!(RichFormat);
+
+impl RichFormat {
+    pub fn apply(&self, attributes: &mut RunAttributes) {
+        if let Some(bold) = self.bold {
+            attributes.bold = bold;
+        }
+        if let Some(italic) = self.italic {
+            attributes.italic = italic;
+        }
+        if let Some(underline) = self.underline {
+            attributes.underline = underline;
+        }
+        if let Some(link) = &self.link {
+            attributes.link = if link.is_empty() {
+                None
+            } else {
+                Some(link.clone())
+            };
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RichImage {
+    #[serde(rename = "image")]
+    pub src: String,
+    /// Defaults to natural width, or scaled by height if only height is specified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<f32>,
+}
+
+js_deserialize!(RichImage);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Run {
+    Text(String, RunAttributes),
+    Image(RichImage),
+}
+
+impl Run {
+    fn atom_count(&self) -> usize {
+        match self {
+            Run::Text(text, _) => text.chars_count(),
+            Run::Image(_) => 1,
+        }
+    }
+
+    /// Split run at atom offset
+    fn split(self, offset: usize) -> (Option<Run>, Option<Run>) {
+        let count = self.atom_count();
+        if offset == 0 {
+            return (None, Some(self));
+        } else if offset >= count {
+            return (Some(self), None);
+        }
+        match self {
+            Run::Text(text, attrs) => {
+                let left = text.substring(0, offset).to_string();
+                let right = text.substring(offset, count - offset).to_string();
+                (
+                    Some(Run::Text(left, attrs.clone())),
+                    Some(Run::Text(right, attrs)),
+                )
+            }
+            image => (Some(image), None),
+        }
+    }
+
+    fn to_text_element(&self) -> TextElement {
+        match self {
+            Run::Text(text, attrs) => {
+                let unit = TextUnit {
+                    text: text.clone(),
+                    font_families: None,
+                    font_size: None,
+                    color: None,
+                    text_decoration_line: attrs.underline.then(|| "underline".to_string()),
+                    weight: attrs.bold.then(|| "bold".to_string()),
+                    background_color: None,
+                    style: attrs.italic.then(|| "italic".to_string()),
+                };
+                match &attrs.link {
+                    Some(href) => TextElement::Link(TextLink {
+                        unit,
+                        href: href.clone(),
+                    }),
+                    None => TextElement::Text(unit),
+                }
+            }
+            Run::Image(image) => TextElement::Image(TextImage {
+                src: image.src.clone(),
+                width: image.width,
+                height: image.height,
+                vertical_align: None,
+            }),
+        }
+    }
+}
+
+/// Inserted content of a delta op
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum DeltaInsert {
+    Text(String),
+    Image(RichImage),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaOp {
+    pub insert: DeltaInsert,
+    #[serde(default, skip_serializing_if = "RunAttributes::is_plain")]
+    pub attributes: RunAttributes,
+}
+
+/// Content of rich text editor in delta format, e.g.
+/// `{ops: [{insert: "Hello "}, {insert: "world", attributes: {bold: true}}, {insert: {image: "a.png"}}]}`.
+/// Lines are separated by "\n" in text inserts.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RichContent {
+    pub ops: Vec<DeltaOp>,
+}
+
+js_serialize!(RichContent);
+js_deserialize!(RichContent);
+
+/// Document model of rich text editor, lines of text and image runs. Columns of text coords
+/// are counted in chars, an image takes one column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichDocument {
+    /// Never empty, adjacent text runs of a line have different attributes
+    lines: Vec<Vec<Run>>,
+}
+
+impl Default for RichDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RichDocument {
+    pub fn new() -> Self {
+        Self {
+            lines: vec![Vec::new()],
+        }
+    }
+
+    pub fn from_text(text: &str, attributes: &RunAttributes) -> Self {
+        let mut doc = Self::new();
+        doc.push_text(text, attributes);
+        doc
+    }
+
+    pub fn from_image(image: RichImage) -> Self {
+        Self {
+            lines: vec![vec![Run::Image(image)]],
+        }
+    }
+
+    pub fn from_content(content: &RichContent) -> Self {
+        let mut doc = Self::new();
+        for op in &content.ops {
+            match &op.insert {
+                DeltaInsert::Text(text) => doc.push_text(text, &op.attributes),
+                DeltaInsert::Image(image) => doc.push_run(Run::Image(image.clone())),
+            }
+        }
+        doc
+    }
+
+    pub fn to_content(&self) -> RichContent {
+        let mut ops: Vec<DeltaOp> = Vec::new();
+        for (row, line) in self.lines.iter().enumerate() {
+            if row > 0 {
+                push_text_op(&mut ops, "\n", &RunAttributes::default());
+            }
+            for run in line {
+                match run {
+                    Run::Text(text, attrs) => push_text_op(&mut ops, text, attrs),
+                    Run::Image(image) => ops.push(DeltaOp {
+                        insert: DeltaInsert::Image(image.clone()),
+                        attributes: RunAttributes::default(),
+                    }),
+                }
+            }
+        }
+        RichContent { ops }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.len() == 1 && self.lines[0].is_empty()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn line_atom_count(&self, row: usize) -> usize {
+        match self.lines.get(row) {
+            Some(line) => line.iter().map(|r| r.atom_count()).sum(),
+            None => 0,
+        }
+    }
+
+    pub fn end(&self) -> TextCoord {
+        let row = self.lines.len() - 1;
+        TextCoord(row, self.line_atom_count(row))
+    }
+
+    /// Plain text, images are replaced by object replacement characters
+    pub fn get_text(&self) -> String {
+        let lines: Vec<String> = self
+            .lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|run| match run {
+                        Run::Text(text, _) => text.as_str(),
+                        Run::Image(_) => OBJECT_REPLACEMENT_CHARACTER,
+                    })
+                    .collect()
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    pub fn line_elements(&self, row: usize) -> Vec<TextElement> {
+        match self.lines.get(row) {
+            Some(line) => line.iter().map(|r| r.to_text_element()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Attributes of the atom before coord, or after coord at the start of line
+    pub fn attributes_at(&self, coord: TextCoord) -> RunAttributes {
+        let col = if coord.1 > 0 { coord.1 - 1 } else { 0 };
+        match self.run_at(coord.0, col) {
+            Some(Run::Text(_, attrs)) => attrs.clone(),
+            _ => RunAttributes::default(),
+        }
+    }
+
+    /// Attributes of text typed at coord, links are only extended inside themselves
+    pub fn typing_attributes_at(&self, coord: TextCoord) -> RunAttributes {
+        let mut attrs = self.attributes_at(coord);
+        if attrs.link.is_some() {
+            let next = match self.run_at(coord.0, coord.1) {
+                Some(Run::Text(_, next)) if coord.1 > 0 => next.link.clone(),
+                _ => None,
+            };
+            if next != attrs.link {
+                attrs.link = None;
+            }
+        }
+        attrs
+    }
+
+    /// Attributes shared by all text in range, or attributes at start if range is empty
+    pub fn common_attributes(&self, start: TextCoord, end: TextCoord) -> RunAttributes {
+        if start >= end {
+            return self.attributes_at(start);
+        }
+        let fragment = self.slice(start, end);
+        let mut result: Option<RunAttributes> = None;
+        for run in fragment.lines.iter().flatten() {
+            if let Run::Text(_, attrs) = run {
+                match &mut result {
+                    None => result = Some(attrs.clone()),
+                    Some(r) => {
+                        r.bold &= attrs.bold;
+                        r.italic &= attrs.italic;
+                        r.underline &= attrs.underline;
+                        if r.link != attrs.link {
+                            r.link = None;
+                        }
+                    }
+                }
+            }
+        }
+        result.unwrap_or_default()
+    }
+
+    /// Copy of content in range
+    pub fn slice(&self, start: TextCoord, end: TextCoord) -> RichDocument {
+        let mut doc = self.clone();
+        doc.delete(start, end)
+    }
+
+    /// Remove content in range and return it
+    pub fn delete(&mut self, start: TextCoord, end: TextCoord) -> RichDocument {
+        let (start, end) = (self.clamp(start.min(end)), self.clamp(start.max(end)));
+        let tail = self.split_off(end);
+        let middle = self.split_off(start);
+        self.append(tail);
+        middle
+    }
+
+    /// Insert fragment at coord and return the end coord of inserted content
+    pub fn insert(&mut self, coord: TextCoord, fragment: RichDocument) -> TextCoord {
+        let coord = self.clamp(coord);
+        let tail = self.split_off(coord);
+        self.append(fragment);
+        let end = self.end();
+        self.append(tail);
+        end
+    }
+
+    /// Apply format to range and return the replaced content
+    pub fn format(
+        &mut self,
+        start: TextCoord,
+        end: TextCoord,
+        format: &RichFormat,
+    ) -> RichDocument {
+        let mut fragment = self.delete(start, end);
+        let old = fragment.clone();
+        for run in fragment.lines.iter_mut().flatten() {
+            if let Run::Text(_, attrs) = run {
+                format.apply(attrs);
+            }
+        }
+        fragment.normalize();
+        self.insert(start.min(end), fragment);
+        old
+    }
+
+    fn clamp(&self, coord: TextCoord) -> TextCoord {
+        let row = coord.0.min(self.lines.len() - 1);
+        TextCoord(row, coord.1.min(self.line_atom_count(row)))
+    }
+
+    fn run_at(&self, row: usize, col: usize) -> Option<&Run> {
+        let mut offset = 0;
+        for run in self.lines.get(row)? {
+            let count = run.atom_count();
+            if col < offset + count {
+                return Some(run);
+            }
+            offset += count;
+        }
+        None
+    }
+
+    /// Split document at coord, content after coord is returned
+    fn split_off(&mut self, coord: TextCoord) -> RichDocument {
+        let TextCoord(row, col) = coord;
+        let mut lines = self.lines.split_off(row + 1);
+        let line = mem::take(&mut self.lines[row]);
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        let mut offset = 0;
+        for run in line {
+            let count = run.atom_count();
+            let (l, r) = run.split(col.saturating_sub(offset));
+            left.extend(l);
+            right.extend(r);
+            offset += count;
+        }
+        self.lines[row] = left;
+        lines.insert(0, right);
+        RichDocument { lines }
+    }
+
+    /// Append document, its first line is joined to the last line
+    fn append(&mut self, other: RichDocument) {
+        let mut lines = other.lines.into_iter();
+        if let Some(first) = lines.next() {
+            for run in first {
+                self.push_run(run);
+            }
+        }
+        self.lines.extend(lines);
+    }
+
+    fn push_text(&mut self, text: &str, attributes: &RunAttributes) {
+        for (i, ln) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.lines.push(Vec::new());
+            }
+            let ln = ln.trim_end_matches('\r');
+            if !ln.is_empty() {
+                self.push_run(Run::Text(ln.to_string(), attributes.clone()));
+            }
+        }
+    }
+
+    fn push_run(&mut self, run: Run) {
+        let line = self.lines.last_mut().unwrap();
+        if let (Some(Run::Text(last, last_attrs)), Run::Text(text, attrs)) = (line.last_mut(), &run)
+        {
+            if last_attrs == attrs {
+                last.push_str(text);
+                return;
+            }
+        }
+        if run.atom_count() > 0 {
+            line.push(run);
+        }
+    }
+
+    fn normalize(&mut self) {
+        for line in &mut self.lines {
+            let runs = mem::take(line);
+            let mut doc = RichDocument {
+                lines: vec![Vec::new()],
+            };
+            for run in runs {
+                doc.push_run(run);
+            }
+            *line = doc.lines.pop().unwrap();
+        }
+    }
+}
+
+impl EditContent for RichDocument {
+    fn append(&mut self, other: &Self) {
+        RichDocument::append(self, other.clone());
+    }
+
+    fn prepend(&mut self, other: &Self) {
+        self.insert(TextCoord(0, 0), other.clone());
+    }
+
+    fn starts_with_whitespace(&self) -> bool {
+        match self.lines[0].first() {
+            Some(Run::Text(text, _)) => text.starts_with(['\t', ' ']),
+            Some(Run::Image(_)) => false,
+            None => self.lines.len() > 1,
+        }
+    }
+}
+
+fn push_text_op(ops: &mut Vec<DeltaOp>, text: &str, attributes: &RunAttributes) {
+    if let Some(DeltaOp {
+        insert: DeltaInsert::Text(last),
+        attributes: last_attrs,
+    }) = ops.last_mut()
+    {
+        if last_attrs == attributes {
+            last.push_str(text);
+            return;
+        }
+    }
+    ops.push(DeltaOp {
+        insert: DeltaInsert::Text(text.to_string()),
+        attributes: attributes.clone(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::richtextedit::document::{
+        RichContent, RichDocument, RichFormat, RichImage, RunAttributes,
+    };
+    use crate::text::textbox::TextCoord;
+
+    fn bold() -> RunAttributes {
+        RunAttributes {
+            bold: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut doc = RichDocument::from_text("hello\nworld", &RunAttributes::default());
+        let end = doc.insert(TextCoord(0, 5), RichDocument::from_text(" big", &bold()));
+        assert_eq!(end, TextCoord(0, 9));
+        assert_eq!(doc.get_text(), "hello big\nworld");
+        assert_eq!(doc.attributes_at(TextCoord(0, 9)), bold());
+
+        let deleted = doc.delete(TextCoord(0, 6), TextCoord(1, 2));
+        assert_eq!(deleted.get_text(), "big\nwo");
+        assert_eq!(doc.get_text(), "hello rld");
+        assert_eq!(doc.line_count(), 1);
+
+        doc.insert(TextCoord(0, 6), deleted);
+        assert_eq!(doc.get_text(), "hello big\nworld");
+        assert_eq!(doc.line_elements(0).len(), 2);
+
+        let end = doc.insert(
+            TextCoord(1, 0),
+            RichDocument::from_image(RichImage {
+                src: "a.png".to_string(),
+                width: None,
+                height: None,
+            }),
+        );
+        assert_eq!(end, TextCoord(1, 1));
+        assert_eq!(doc.line_atom_count(1), 6);
+    }
+
+    #[test]
+    fn test_format() {
+        let mut doc = RichDocument::from_text("hello world", &RunAttributes::default());
+        let format = RichFormat {
+            italic: Some(true),
+            link: Some("https://example.com".to_string()),
+            ..Default::default()
+        };
+        let old = doc.format(TextCoord(0, 6), TextCoord(0, 11), &format);
+        assert_eq!(old.get_text(), "world");
+        let attrs = doc.common_attributes(TextCoord(0, 6), TextCoord(0, 11));
+        assert!(attrs.italic && !attrs.bold);
+        assert_eq!(attrs.link.as_deref(), Some("https://example.com"));
+        // Typing after a link does not extend it
+        assert!(doc.typing_attributes_at(TextCoord(0, 11)).link.is_none());
+        assert!(doc.typing_attributes_at(TextCoord(0, 8)).link.is_some());
+        assert!(
+            !doc.common_attributes(TextCoord(0, 0), TextCoord(0, 11))
+                .italic
+        );
+    }
+
+    #[test]
+    fn test_content() {
+        let mut doc = RichDocument::from_text("a\nb", &RunAttributes::default());
+        doc.format(
+            TextCoord(1, 0),
+            TextCoord(1, 1),
+            &RichFormat {
+                bold: Some(true),
+                ..Default::default()
+            },
+        );
+        let content = doc.to_content();
+        assert_eq!(content.ops.len(), 2);
+        assert_eq!(content.ops[1].attributes, bold());
+        let parsed = RichDocument::from_content(&content);
+        assert_eq!(parsed, doc);
+        assert!(RichDocument::from_content(&RichContent::default()).is_empty());
+    }
+}
@@ -4,10 +4,11 @@ use crate::base::EventContext;
 use crate::element::container::Container;
 use crate::element::scroll::ScrollBarStrategy::{Always, Auto, Never};
 use crate::element::{Element, ElementBackend, ElementWeak};
-use crate::event::{CaretChangeEvent, Event};
+use crate::event::{CaretChangeEvent, Event, RefreshEvent};
 use crate::js::FromJsValue;
 use crate::render::RenderFn;
-use crate::style::ResolvedStyleProp;
+use crate::style::length::{Length, LengthOrPercent};
+use crate::style::{FixedStyleProp, ResolvedStyleProp, StylePropVal};
 use crate::{backend_as_api, ok_or_return};
 use bezier_rs::{Bezier, TValue};
 use deft_macros::{element_backend, js_methods};
@@ -16,7 +17,7 @@ use quick_js::{JsValue, ValueError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Instant;
-use yoga::{MeasureMode, NodeRef, Size};
+use yoga::{MeasureMode, NodeRef, PositionType, Size};
 
 pub const MOMENTUM_DURATION: f32 = 200.0;
 pub const MOMENTUM_DISTANCE: f32 = 16.0;
@@ -109,6 +110,42 @@ impl Scroll {
     //     Ok(())
     // }
 
+    /// Enable pulling content down at the top edge to emit `refresh` event
+    #[js_func]
+    pub fn set_pull_to_refresh(&mut self, enabled: bool) {
+        let mut element = ok_or_return!(self.element.upgrade());
+        element.scrollable.set_pull_to_refresh(enabled);
+    }
+
+    /// Use a child element as the indicator, which is placed above content and revealed by pulling
+    #[js_func]
+    pub fn set_refresh_indicator(&mut self, mut indicator: Element) {
+        let mut element = ok_or_return!(self.element.upgrade());
+        let zero = LengthOrPercent::Length(Length::PX(0.0));
+        indicator.set_style_props(vec![
+            FixedStyleProp::Position(StylePropVal::Custom(PositionType::Absolute)),
+            FixedStyleProp::Left(StylePropVal::Custom(zero)),
+            FixedStyleProp::Right(StylePropVal::Custom(zero)),
+            FixedStyleProp::Bottom(StylePropVal::Custom(LengthOrPercent::Percent(100.0))),
+        ]);
+        element
+            .scrollable
+            .set_refresh_indicator(Some(indicator.as_weak()));
+    }
+
+    /// Collapse the refresh indicator
+    #[js_func]
+    pub fn finish_refresh(&mut self) {
+        let element = ok_or_return!(self.element.upgrade());
+        element.scrollable.clone().finish_refresh(&element);
+    }
+
+    #[js_func]
+    pub fn is_refreshing(&self) -> bool {
+        let element = ok_or_return!(self.element.upgrade(), false);
+        element.scrollable.is_refreshing()
+    }
+
     fn mark_layout_dirty(&mut self) {
         let auto_height = self.auto_height;
         self.element.mark_dirty(auto_height);
@@ -165,6 +202,20 @@ impl ElementBackend for Scroll {
         // ele.create_shadow();
         ele.need_snapshot = true;
         let base = Container::create(ele);
+        ele.register_js_event::<RefreshEvent>("refresh");
+        {
+            let weak = ele.as_weak();
+            ele.scrollable.set_pull_callback(move || {
+                let mut el = ok_or_return!(weak.upgrade());
+                el.mark_dirty(false);
+            });
+        }
+        {
+            let weak = ele.as_weak();
+            ele.scrollable.set_refresh_callback(move || {
+                weak.emit(RefreshEvent);
+            });
+        }
 
         let inst = ScrollData {
             // scroll_bar_size: if is_mobile_platform { 4.0 } else { 14.0 },
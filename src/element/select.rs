@@ -139,6 +139,14 @@ impl ElementBackend for Select {
         }
         .to_ref()
     }
+    fn reset(&mut self) {
+        self.options.clear();
+        self.value.clear();
+        self.label.set_text(String::new());
+        self.placeholder.clear();
+        self.element_weak.mark_dirty(true);
+    }
+
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         None
     }
@@ -9,6 +9,8 @@ struct RasterizeCacheKey {
     font_cache_key: CacheKey,
     glyph_id: GlyphId,
     font_size: u32,
+    /// Stroke width in 1/64 pixels, zero for filled glyph
+    stroke_width: u32,
 }
 
 pub struct RasterizeCache {
@@ -22,15 +24,26 @@ impl RasterizeCache {
         }
     }
     pub fn get_image(&self, font: &Font, glyph_id: GlyphId, size: f32) -> Option<Image> {
+        self.get_stroke_image(font, glyph_id, size, None)
+    }
+
+    pub fn get_stroke_image(
+        &self,
+        font: &Font,
+        glyph_id: GlyphId,
+        size: f32,
+        stroke_width: Option<f32>,
+    ) -> Option<Image> {
         let key = RasterizeCacheKey {
             font_cache_key: font.as_ref().key,
             glyph_id,
             font_size: size as u32,
+            stroke_width: stroke_width.map(|w| (w * 64.0).round() as u32).unwrap_or(0),
         };
         self.cache
             .clone()
             .entry(key)
-            .or_insert_with(move || font.rasterize_glyph(glyph_id, size))
+            .or_insert_with(move || font.rasterize_glyph_with_stroke(glyph_id, size, stroke_width))
             .clone()
     }
 }
@@ -5,11 +5,12 @@ use crate::paint::Painter;
 use crate::some_or_return;
 use crate::string::StringUtils;
 use crate::style::color::ColorHelper;
+use crate::style::ruby::RubyPosition;
 use crate::text::{calculate_line_char_count, TextStyle};
 use libc::memcpy;
 use log::warn;
 use skia_safe::{
-    scalar, AlphaType, Bitmap, Color, ColorType, FilterMode, ImageInfo, Paint, Point, Rect,
+    scalar, AlphaType, Bitmap, Color, ColorType, FilterMode, Image, ImageInfo, Paint, Point, Rect,
     SamplingOptions,
 };
 use std::ffi::c_void;
@@ -18,10 +19,16 @@ use std::sync::Arc;
 use swash::scale::image::Content;
 use swash::GlyphId;
 
+const ELLIPSIS: char = '\u{2026}';
+
+/// Text of placeholder block, which takes one char offset
+pub const OBJECT_REPLACEMENT_CHARACTER: &str = "\u{FFFC}";
+
 thread_local! {
     static RASTERIZE_CACHE: RasterizeCache = RasterizeCache::new();
 }
 
+#[derive(Clone)]
 struct LineUnit {
     block: TextBlock,
     x: f32,
@@ -43,7 +50,19 @@ impl BoundsWithOffset {
 }
 
 impl LineUnit {
+    fn width(&self) -> f32 {
+        self.get_inner_layout_bounds(false)
+            .last()
+            .map(|b| b.bounds_with_offset().right)
+            .unwrap_or(0.0)
+    }
+
     fn get_inner_layout_bounds(&self, compact: bool) -> Vec<BoundsWithOffset> {
+        if let Some(ph) = &self.block.placeholder {
+            let (ascent, descent) = self.block.placeholder_metrics(ph);
+            let bounds = Rect::new(0.0, -ascent, ph.width, descent);
+            return vec![BoundsWithOffset::new(0.0, bounds)];
+        }
         let glyph_ids = str_to_glyphs_vec(&self.block.font, self.block.text.as_str());
         let mut bounds = Vec::with_capacity(glyph_ids.len());
         let mut widths = Vec::with_capacity(glyph_ids.len());
@@ -84,10 +103,82 @@ impl LineUnit {
         range: Option<(usize, usize)>,
         paint: Option<&Paint>,
     ) {
-        let font = &self.block.font;
-        let font_size = self.block.style.font_size();
+        if let Some(ph) = &self.block.placeholder {
+            if range.map(|(start, end)| start < end).unwrap_or(true) {
+                self.paint_placeholder(painter, origin, ph);
+            }
+            return;
+        }
         let foreground = self.block.style.foreground();
         let paint = paint.unwrap_or(&foreground);
+        self.paint_glyphs(painter, origin, range, paint.color(), None);
+        if range.is_none() {
+            self.paint_ruby(painter, origin, paint.color(), None);
+        }
+    }
+
+    /// Paint annotation of ruby centered over or under the base text
+    fn paint_ruby(
+        &self,
+        painter: &Painter,
+        origin: Point,
+        color: Color,
+        stroke_width: Option<f32>,
+    ) {
+        let ruby = some_or_return!(&self.block.ruby);
+        let annotation = LineUnit {
+            block: ruby.annotation.clone(),
+            x: 0.0,
+            char_offset: 0,
+        };
+        let (base_ascent, base_descent) = self.block.text_metrics();
+        let (ascent, descent) = ruby.annotation.text_metrics();
+        let x = origin.x + (self.width() - annotation.width()) / 2.0;
+        let y = match ruby.position {
+            RubyPosition::Over => origin.y - base_ascent - descent,
+            RubyPosition::Under => origin.y + base_descent + ascent,
+        };
+        annotation.paint_glyphs(painter, Point::new(x, y), None, color, stroke_width);
+    }
+
+    fn paint_placeholder(&self, painter: &Painter, origin: Point, placeholder: &Placeholder) {
+        let img = some_or_return!(&placeholder.image);
+        let (ascent, descent) = self.block.placeholder_metrics(placeholder);
+        let dst = Rect::new(
+            origin.x,
+            origin.y - ascent,
+            origin.x + placeholder.width,
+            origin.y + descent,
+        );
+        let mut options = SamplingOptions::default();
+        options.filter = FilterMode::Linear;
+        painter.canvas.draw_image_rect_with_sampling_options(
+            img,
+            None,
+            dst,
+            options,
+            &Paint::default(),
+        );
+    }
+
+    fn paint_stroke(&self, painter: &Painter, origin: Point, width: f32, color: Color) {
+        if self.block.placeholder.is_some() {
+            return;
+        }
+        self.paint_glyphs(painter, origin, None, color, Some(width));
+        self.paint_ruby(painter, origin, color, Some(width));
+    }
+
+    fn paint_glyphs(
+        &self,
+        painter: &Painter,
+        origin: Point,
+        range: Option<(usize, usize)>,
+        color: Color,
+        stroke_width: Option<f32>,
+    ) {
+        let font = &self.block.font;
+        let font_size = self.block.style.font_size();
         let mut glyphs = str_to_glyphs_vec(&font, self.block.text.as_str());
         let mut layout_bounds = self
             .get_inner_layout_bounds(false)
@@ -115,12 +206,13 @@ impl LineUnit {
         let canvas = painter.canvas;
         canvas.save();
         canvas.scale((1.0 / scale, 1.0 / scale));
-        let color = paint.color();
+        let stroke_width = stroke_width.map(|w| w * scale);
         for idx in range_start..range_end {
             let lb = layout_bounds[idx];
             let glyph = glyphs[idx];
-            let rasterized_img =
-                RASTERIZE_CACHE.with(move |cache| cache.get_image(&font, glyph, font_size * scale));
+            let rasterized_img = RASTERIZE_CACHE.with(move |cache| {
+                cache.get_stroke_image(&font, glyph, font_size * scale, stroke_width)
+            });
             if let Some(img) = rasterized_img {
                 if let Some(bmp) = Self::swash_to_bitmap(&img, color) {
                     let x = img.placement.left;
@@ -189,6 +281,7 @@ impl LineUnit {
     }
 }
 
+#[derive(Clone)]
 struct TextLine {
     units: Vec<LineUnit>,
     line_number: usize,
@@ -209,6 +302,56 @@ impl TextLine {
             char_offset,
         }
     }
+
+    /// Truncate units to fit the width with ellipsis appended
+    fn append_ellipsis(&mut self, width: f32) {
+        let last_unit = some_or_return!(self.units.last());
+        let text = match last_unit.block.font.glyph_for_char(ELLIPSIS) {
+            Some(g) if g != 0 => ELLIPSIS.to_string(),
+            _ => "...".to_string(),
+        };
+        let mut ellipsis = LineUnit {
+            block: TextBlock {
+                text,
+                placeholder: None,
+                ruby: None,
+                ..last_unit.block.clone()
+            },
+            x: 0.0,
+            char_offset: last_unit.char_offset + last_unit.block.text.chars_count(),
+        };
+        let limit = width - ellipsis.width();
+        let mut units = Vec::with_capacity(self.units.len() + 1);
+        for unit in self.units.drain(..) {
+            let bounds = unit.get_inner_layout_bounds(false);
+            let visible_count = bounds
+                .iter()
+                .take_while(|b| unit.x + b.bounds_with_offset().right <= limit)
+                .count();
+            ellipsis.x = match visible_count.checked_sub(1) {
+                Some(i) => unit.x + bounds[i].bounds_with_offset().right,
+                None => unit.x,
+            };
+            if visible_count == bounds.len() {
+                units.push(unit);
+                continue;
+            }
+            if visible_count > 0 {
+                let text = unit.block.text.substring(0, visible_count).to_string();
+                units.push(LineUnit {
+                    block: TextBlock {
+                        text,
+                        ruby: None,
+                        ..unit.block
+                    },
+                    ..unit
+                });
+            }
+            break;
+        }
+        units.push(ellipsis);
+        self.units = units;
+    }
 }
 
 pub struct SimpleTextParagraph {
@@ -233,7 +376,7 @@ impl TextLayout {
             let y = ln.y + ln.baseline;
             for unit in &ln.units {
                 let tb = &unit.block;
-                if tb.style.foreground().color().is_transparent() {
+                if tb.placeholder.is_none() && tb.style.foreground().color().is_transparent() {
                     continue;
                 }
                 let x = unit.x;
@@ -243,6 +386,54 @@ impl TextLayout {
         canvas.restore();
     }
 
+    /// Layout of the first `count` lines, the last one is truncated to fit `ellipsis_width` with
+    /// an ellipsis appended if specified
+    pub fn truncate(&self, count: usize, ellipsis_width: Option<f32>) -> TextLayout {
+        let count = usize::min(count, self.lines.len());
+        let mut lines = self.lines[..count].to_vec();
+        if let (Some(width), Some(last)) = (ellipsis_width, lines.last_mut()) {
+            last.append_ellipsis(width);
+        }
+        TextLayout {
+            max_intrinsic_width: self.max_intrinsic_width,
+            height: self.lines_height(count),
+            lines,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Height of the first `count` lines
+    pub fn lines_height(&self, count: usize) -> f32 {
+        match count.checked_sub(1).and_then(|i| self.lines.get(i)) {
+            Some(ln) => ln.y + ln.height,
+            None => 0.0,
+        }
+    }
+
+    /// Right edge of glyphs in the line
+    pub fn line_width(&self, line: usize) -> f32 {
+        let ln = some_or_return!(self.lines.get(line), 0.0);
+        let unit = some_or_return!(ln.units.last(), 0.0);
+        unit.x + unit.width()
+    }
+
+    /// Paint outlines of glyphs, which are centered on the edges of filled glyphs
+    pub fn paint_stroke(&self, painter: &Painter, p: Point, width: f32, color: Color) {
+        let canvas = painter.canvas;
+        canvas.save();
+        canvas.translate(p);
+        for ln in &self.lines {
+            let y = ln.y + ln.baseline;
+            for unit in &ln.units {
+                unit.paint_stroke(painter, Point::new(unit.x, y), width, color);
+            }
+        }
+        canvas.restore();
+    }
+
     fn get_unit_at_char_offset(&self, char_offset: usize) -> Option<(&TextLine, &LineUnit)> {
         for ln in self.lines.iter().rev() {
             if ln.char_offset > char_offset {
@@ -330,10 +521,81 @@ impl TextLayout {
     }
 }
 
+/// Vertical alignment of placeholder relative to the text around it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PlaceholderAlignment {
+    /// Bottom edge on the baseline
+    #[default]
+    Baseline,
+    /// Top edge aligned with the ascent of text
+    Top,
+    /// Center aligned with the center of text
+    Middle,
+    /// Bottom edge aligned with the descent of text
+    Bottom,
+}
+
+/// Box which flows inline with text, e.g. an image or an embedded element
+#[derive(Clone)]
+pub struct Placeholder {
+    pub width: f32,
+    pub height: f32,
+    pub alignment: PlaceholderAlignment,
+    /// Image painted in the box, embedded elements are painted by themselves
+    pub image: Option<Image>,
+}
+
+/// Annotation rendered over or under the base text, e.g. furigana
+#[derive(Clone)]
+pub struct Ruby {
+    pub annotation: TextBlock,
+    pub position: RubyPosition,
+}
+
+#[derive(Clone)]
 pub struct TextBlock {
     pub text: String,
     pub style: TextStyle,
     pub font: Font,
+    pub placeholder: Option<Placeholder>,
+    /// Ruby of block, the block is laid out as a whole if specified
+    pub ruby: Option<Box<Ruby>>,
+}
+
+impl TextBlock {
+    /// (ascent, descent) of the font, both are positive in common
+    fn text_metrics(&self) -> (f32, f32) {
+        let metrics = self.font.metrics();
+        let scale = self.style.font_size() / metrics.units_per_em as f32;
+        (metrics.ascent * scale, metrics.descent * scale)
+    }
+
+    /// (ascent, descent) of placeholder relative to the baseline
+    fn placeholder_metrics(&self, placeholder: &Placeholder) -> (f32, f32) {
+        let height = placeholder.height;
+        let (text_ascent, text_descent) = self.text_metrics();
+        let ascent = match placeholder.alignment {
+            PlaceholderAlignment::Baseline => height,
+            PlaceholderAlignment::Top => text_ascent,
+            PlaceholderAlignment::Middle => (height + text_ascent - text_descent) / 2.0,
+            PlaceholderAlignment::Bottom => height - text_descent,
+        };
+        (ascent, height - ascent)
+    }
+
+    /// (ascent, descent) of text in line, where the space of `line_height` is shared equally
+    fn line_metrics(&self, line_height: Option<f32>) -> (f32, f32) {
+        let font_metrics = self.font.metrics();
+        let metrics_scale = self.style.font_size() / font_metrics.units_per_em as f32;
+        let text_height =
+            (font_metrics.ascent + font_metrics.descent + font_metrics.leading) * metrics_scale;
+        //TODO fix leading?
+        let text_base_line = font_metrics.ascent * metrics_scale /* + font_metrics.leading */;
+        let line_height = line_height.unwrap_or(text_height);
+        let line_space = line_height - text_height;
+        let text_ascent = text_base_line + line_space / 2.0;
+        (text_ascent, line_height - text_ascent)
+    }
 }
 
 impl SimpleTextParagraph {
@@ -363,6 +625,70 @@ impl SimpleTextParagraph {
         let mut current_line = TextLine::new(0, 0);
 
         for tb in &self.text_blocks {
+            if let Some(ph) = &tb.placeholder {
+                if left > 0.0 && left + ph.width > available_width {
+                    let next_line_number = current_line.line_number + 1;
+                    left = 0.0;
+                    top += current_line.height;
+                    lines.push(current_line);
+                    current_line = TextLine::new(next_line_number, char_offset);
+                    current_line.y = top;
+                }
+                current_line.units.push(LineUnit {
+                    block: tb.clone(),
+                    x: left,
+                    char_offset,
+                });
+                char_offset += 1;
+                left += ph.width;
+                let (ascent, descent) = tb.placeholder_metrics(ph);
+                current_line.baseline = f32::max(current_line.baseline, ascent);
+                current_line.height =
+                    f32::max(current_line.height, current_line.baseline + descent);
+                max_intrinsic_width = f32::max(max_intrinsic_width, left);
+                continue;
+            }
+            if let Some(ruby) = &tb.ruby {
+                let base_unit = LineUnit {
+                    block: tb.clone(),
+                    x: 0.0,
+                    char_offset: 0,
+                };
+                let annotation_unit = LineUnit {
+                    block: ruby.annotation.clone(),
+                    x: 0.0,
+                    char_offset: 0,
+                };
+                let base_width = base_unit.width();
+                let width = f32::max(base_width, annotation_unit.width());
+                if left > 0.0 && left + width > available_width {
+                    let next_line_number = current_line.line_number + 1;
+                    left = 0.0;
+                    top += current_line.height;
+                    lines.push(current_line);
+                    current_line = TextLine::new(next_line_number, char_offset);
+                    current_line.y = top;
+                }
+                current_line.units.push(LineUnit {
+                    x: left + (width - base_width) / 2.0,
+                    char_offset,
+                    ..base_unit
+                });
+                char_offset += tb.text.chars_count();
+                left += width;
+                let (ascent, descent) = tb.line_metrics(self.line_height);
+                let (annotation_ascent, annotation_descent) = ruby.annotation.text_metrics();
+                let annotation_height = annotation_ascent + annotation_descent;
+                let (ascent, descent) = match ruby.position {
+                    RubyPosition::Over => (ascent + annotation_height, descent),
+                    RubyPosition::Under => (ascent, descent + annotation_height),
+                };
+                current_line.baseline = f32::max(current_line.baseline, ascent);
+                current_line.height =
+                    f32::max(current_line.height, current_line.baseline + descent);
+                max_intrinsic_width = f32::max(max_intrinsic_width, left);
+                continue;
+            }
             let glyphs = str_to_glyphs_vec(&tb.font, &tb.text);
             let char_count = glyphs.len();
             if char_count == 0 {
@@ -392,8 +718,7 @@ impl SimpleTextParagraph {
                 x_pos[i + 1] = x_pos[i] + widths[i];
             }
 
-            let font_metrics = tb.font.metrics();
-            let metrics_scale = tb.style.font_size() / font_metrics.units_per_em as f32;
+            let (text_ascent, text_descent) = tb.line_metrics(self.line_height);
             let mut consumed_char_count = 0;
             while consumed_char_count < char_count {
                 let mut cc = calculate_line_char_count(
@@ -417,6 +742,8 @@ impl SimpleTextParagraph {
                         text: tb.text.substring(consumed_char_count, cc).to_string(),
                         style: tb.style.clone(),
                         font: tb.font.clone(),
+                        placeholder: None,
+                        ruby: None,
                     },
                     x: left,
                     char_offset,
@@ -425,16 +752,10 @@ impl SimpleTextParagraph {
                 left += x_pos[consumed_char_count + cc - 1] - x_pos[consumed_char_count]
                     + widths[consumed_char_count + cc - 1];
                 consumed_char_count += cc;
-                let text_height =
-                    (font_metrics.ascent + font_metrics.descent + font_metrics.leading)
-                        * metrics_scale;
-                //TODO fix leading?
-                let text_base_line = font_metrics.ascent * metrics_scale /* + font_metrics.leading */;
-                let line_height = self.line_height.unwrap_or(text_height);
-                let line_space = line_height - text_height;
-                current_line.baseline =
-                    f32::max(current_line.baseline, text_base_line + line_space / 2.0);
-                current_line.height = f32::max(current_line.height, line_height);
+                current_line.baseline = f32::max(current_line.baseline, text_ascent);
+                // Keep descent of text inside the line when baseline is lowered by placeholders
+                current_line.height =
+                    f32::max(current_line.height, current_line.baseline + text_descent);
                 max_intrinsic_width = f32::max(max_intrinsic_width, left);
             }
         }
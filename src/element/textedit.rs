@@ -3,6 +3,7 @@ use crate::base::EventContext;
 use crate::element::common::editable::Editable;
 use crate::element::{Element, ElementBackend, ElementWeak};
 use crate::event::Event;
+use crate::menu::Menu;
 use crate::style::length::{Length, LengthOrPercent};
 use crate::style::{FixedStyleProp, ResolvedStyleProp, StylePropVal};
 use deft_macros::{element_backend, js_methods};
@@ -56,6 +57,12 @@ impl TextEdit {
     pub fn set_caret_by_char_offset(&mut self, char_offset: usize) {
         self.editable.set_caret_by_char_offset(char_offset);
     }
+
+    /// Set custom items appended to the built-in context menu
+    #[js_func]
+    pub fn set_context_menu(&mut self, menu: Option<Menu>) {
+        self.editable.set_context_menu(menu);
+    }
 }
 
 impl ElementBackend for TextEdit {
@@ -86,6 +93,11 @@ impl ElementBackend for TextEdit {
         .to_ref()
     }
 
+    fn reset(&mut self) {
+        self.set_text(String::new());
+        self.set_placeholder(String::new());
+    }
+
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         None
     }
@@ -1,20 +1,36 @@
 use crate as deft;
-use crate::base::EventContext;
+use crate::base::{Callback, EventContext};
 use crate::element::common::editable::{Editable, InputType};
+use crate::element::common::input_mask::InputMask;
 use crate::element::{Element, ElementBackend, ElementWeak};
-use crate::event::Event;
-use crate::ok_or_return;
+use crate::autofill::{request_autofill, AutofillHint};
+use crate::menu::Menu;
+use crate::event::{
+    Event, FocusEvent, MaskValidateEvent, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
+    TextChangeEvent,
+};
+use crate::{ok_or_return, some_or_return};
 use crate::style::length::LengthOrPercent;
 use crate::style::{FixedStyleProp, ResolvedStyleProp, StylePropVal};
 use deft_macros::{element_backend, js_methods};
 use std::collections::HashMap;
+use winit::window::ImePurpose;
 use yoga::FlexDirection;
 
+/// Distance to left or right edge within which drag selecting scrolls text
+const AUTOSCROLL_EDGE: f32 = 16.0;
+/// Max distance scrolled per frame when drag selecting
+const AUTOSCROLL_MAX_STEP: f32 = 16.0;
+
 #[element_backend]
 pub struct TextInput {
     element: ElementWeak,
     editable_element: Element,
     editable: Editable,
+    autofill_hint: Option<AutofillHint>,
+    /// Pointer offset of drag selecting, relative to border box
+    selecting_pointer: Option<(f32, f32)>,
+    autoscroll_scheduled: bool,
 }
 
 #[js_methods]
@@ -53,6 +69,101 @@ impl TextInput {
     pub fn get_type(&self) -> InputType {
         self.editable.get_type()
     }
+
+    /// Text without literals of mask
+    #[js_func]
+    pub fn get_unmasked_text(&self) -> String {
+        self.editable.get_unmasked_text()
+    }
+
+    /// Set custom items appended to the built-in context menu
+    #[js_func]
+    pub fn set_context_menu(&mut self, menu: Option<Menu>) {
+        self.editable.set_context_menu(menu);
+    }
+
+    fn update_autofill_hint(&mut self, value: Option<&str>) {
+        let mut el = ok_or_return!(self.element.upgrade());
+        self.autofill_hint = value.and_then(AutofillHint::parse);
+        el.ime_purpose = match &self.autofill_hint {
+            Some(hint) => hint.ime_purpose(),
+            None => ImePurpose::Normal,
+        };
+    }
+
+    /// Offset from border box of input to editable, scroll offset of text is included
+    fn editable_offset(&self) -> (f32, f32) {
+        let el = ok_or_return!(self.element.upgrade(), (0.0, 0.0));
+        let (scroll_left, scroll_top) = el.scrollable.scroll_offset();
+        let eb = self.editable_element.get_bounds();
+        (scroll_left - eb.x, scroll_top - eb.y)
+    }
+
+    fn track_selecting(&mut self, event: &Event) {
+        if let Some(e) = MouseDownEvent::cast(event) {
+            if e.0.button == 1 {
+                self.selecting_pointer = Some((e.0.offset_x, e.0.offset_y));
+            }
+        } else if let Some(e) = MouseMoveEvent::cast(event) {
+            if self.selecting_pointer.is_some() {
+                self.selecting_pointer = Some((e.0.offset_x, e.0.offset_y));
+                self.schedule_autoscroll();
+            }
+        } else if MouseUpEvent::is(event) {
+            self.selecting_pointer = None;
+        }
+    }
+
+    fn schedule_autoscroll(&mut self) {
+        if self.autoscroll_scheduled {
+            return;
+        }
+        let el = ok_or_return!(self.element.upgrade());
+        self.autoscroll_scheduled = true;
+        let me = self.as_weak();
+        el.with_window(|mut w| {
+            w.request_next_frame_callback(Callback::new(move || {
+                if let Ok(mut me) = me.upgrade() {
+                    me.autoscroll_tick();
+                }
+            }));
+        });
+    }
+
+    /// Scroll text when pointer is dragged near or beyond edges, runs once per frame
+    fn autoscroll_tick(&mut self) {
+        self.autoscroll_scheduled = false;
+        let (x, y) = some_or_return!(self.selecting_pointer);
+        let mut el = ok_or_return!(self.element.upgrade());
+        let width = el.get_bounds().width;
+        let step = if x < AUTOSCROLL_EDGE {
+            x - AUTOSCROLL_EDGE
+        } else if x > width - AUTOSCROLL_EDGE {
+            x - (width - AUTOSCROLL_EDGE)
+        } else {
+            return;
+        };
+        let step = step.clamp(-AUTOSCROLL_MAX_STEP, AUTOSCROLL_MAX_STEP);
+        let old_scroll_left = el.get_scroll_left();
+        el.set_scroll_left(old_scroll_left + step);
+        if el.get_scroll_left() == old_scroll_left {
+            return;
+        }
+        let (offset_x, offset_y) = self.editable_offset();
+        self.editable.update_selecting(x + offset_x, y + offset_y);
+        self.schedule_autoscroll();
+    }
+
+    fn autofill(&mut self) {
+        let hint = some_or_return!(&self.autofill_hint);
+        if !self.editable.get_text().is_empty() {
+            return;
+        }
+        if let Some(value) = request_autofill(hint) {
+            self.editable.set_text(value.clone());
+            self.element.emit(TextChangeEvent { value });
+        }
+    }
 }
 
 impl ElementBackend for TextInput {
@@ -61,6 +172,7 @@ impl ElementBackend for TextInput {
         Self: Sized,
     {
         element.allow_ime = true;
+        element.register_js_event::<MaskValidateEvent>("maskvalidate");
         element.scrollable.vertical_bar.set_thickness(0.0);
         element.scrollable.horizontal_bar.set_thickness(0.0);
         element.set_style_props(vec![FixedStyleProp::FlexDirection(StylePropVal::Custom(
@@ -83,10 +195,18 @@ impl ElementBackend for TextInput {
             element: element.as_weak(),
             editable_element: editable.clone(),
             editable: backend,
+            autofill_hint: None,
+            selecting_pointer: None,
+            autoscroll_scheduled: false,
         }
         .to_ref()
     }
 
+    fn reset(&mut self) {
+        self.set_text(String::new());
+        self.set_placeholder(String::new());
+    }
+
     fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
         None
     }
@@ -94,8 +214,12 @@ impl ElementBackend for TextInput {
     fn on_event(&mut self, event: &mut Event, ctx: &mut EventContext<ElementWeak>) {
         // let el = ok_or_return!(self.element.upgrade());
         if ctx.target == self.element {
-            let eb = self.editable_element.get_bounds();
-            self.editable.handle_event(event, ctx, (-eb.x, -eb.y));
+            if FocusEvent::is(event) {
+                self.autofill();
+            }
+            self.track_selecting(event);
+            let offset = self.editable_offset();
+            self.editable.handle_event(event, ctx, offset);
         }
     }
 
@@ -113,4 +237,12 @@ impl ElementBackend for TextInput {
     fn accept_pseudo_element_styles(&mut self, styles: HashMap<String, Vec<ResolvedStyleProp>>) {
         self.editable.accept_pseudo_element_styles(styles);
     }
+
+    fn on_attribute_changed(&mut self, key: &str, value: Option<&str>) {
+        if key == "autocomplete" {
+            self.update_autofill_hint(value);
+        } else if key == "mask" {
+            self.editable.set_mask(value.and_then(InputMask::parse));
+        }
+    }
 }
@@ -2,6 +2,8 @@ use crate::element::Element;
 use crate::event::{
     ClickEvent, Event, KeyDownEvent, KeyUpEvent, MouseDownEvent, MouseUpEvent, TextInputEvent,
 };
+use crate::style::list_style::ListStyleType;
+use crate::style::PropValueParse;
 
 pub fn is_form_event(event: &Event) -> bool {
     ClickEvent::is(event)
@@ -19,3 +21,122 @@ pub fn get_tree_level(element: &Element) -> usize {
         0
     }
 }
+
+/// Parse `content` of ::before and ::after, e.g. `"*"`, `attr(title)`, `"(" attr(count) ")"`
+/// or `counters(section, ".") " "`. `get_counters` returns values of nested counters with the
+/// innermost one at last.
+///
+/// Return None if no box should be generated
+pub fn parse_generated_content<F: Fn(&str) -> Option<String>, G: Fn(&str) -> Vec<i32>>(
+    content: &str,
+    get_attribute: F,
+    get_counters: G,
+) -> Option<String> {
+    let content = content.trim();
+    if content.is_empty() || content == "none" || content == "normal" {
+        return None;
+    }
+    let mut result = String::new();
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let mut escaped = false;
+                for sc in chars.by_ref() {
+                    if escaped {
+                        result.push(sc);
+                        escaped = false;
+                    } else if sc == '\\' {
+                        escaped = true;
+                    } else if sc == c {
+                        break;
+                    } else {
+                        result.push(sc);
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            _ => {
+                let mut func = String::from(c);
+                for fc in chars.by_ref() {
+                    func.push(fc);
+                    if fc == ')' {
+                        break;
+                    }
+                }
+                let func = func.strip_suffix(')')?;
+                if let Some(name) = func.strip_prefix("attr(") {
+                    result.push_str(&get_attribute(name.trim()).unwrap_or_default());
+                } else if let Some(args) = func.strip_prefix("counters(") {
+                    let (name, args) = args.split_once(',')?;
+                    let (separator, style) = split_quoted(args.trim())?;
+                    let style = parse_counter_style(style)?;
+                    let mut values = get_counters(name.trim());
+                    if values.is_empty() {
+                        values.push(0);
+                    }
+                    let values: Vec<String> =
+                        values.iter().map(|v| style.format_counter(*v)).collect();
+                    result.push_str(&values.join(&separator));
+                } else if let Some(args) = func.strip_prefix("counter(") {
+                    let (name, style) = args.split_once(',').unwrap_or((args, ""));
+                    let style = parse_counter_style(style)?;
+                    let value = get_counters(name.trim()).last().copied().unwrap_or(0);
+                    result.push_str(&style.format_counter(value));
+                } else {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(result)
+}
+
+/// Split leading quoted string and the rest args, e.g. `", ", lower-roman`
+fn split_quoted(value: &str) -> Option<(String, &str)> {
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let end = value[1..].find(quote)? + 1;
+    let rest = value[end + 1..].trim();
+    let rest = rest.strip_prefix(',').unwrap_or(rest);
+    Some((value[1..end].to_string(), rest))
+}
+
+fn parse_counter_style(value: &str) -> Option<ListStyleType> {
+    let value = value.trim();
+    if value.is_empty() {
+        Some(ListStyleType::Decimal)
+    } else {
+        ListStyleType::parse_prop_value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::util::parse_generated_content;
+
+    #[test]
+    fn test_parse_generated_content() {
+        let attr = |k: &str| match k {
+            "count" => Some("3".to_string()),
+            _ => None,
+        };
+        let counters = |k: &str| match k {
+            "section" => vec![2, 4],
+            _ => Vec::new(),
+        };
+        let parse = |content: &str| parse_generated_content(content, attr, counters);
+        assert_eq!(parse("none"), None);
+        assert_eq!(parse("\"\""), Some("".to_string()));
+        assert_eq!(parse("'a\\'b'"), Some("a'b".to_string()));
+        assert_eq!(parse("\"(\" attr(count) \")\""), Some("(3)".to_string()));
+        assert_eq!(parse("attr(missing)"), Some("".to_string()));
+        assert_eq!(parse("counter(section) \".\""), Some("4.".to_string()));
+        assert_eq!(
+            parse("counter(section, upper-roman)"),
+            Some("IV".to_string())
+        );
+        assert_eq!(parse("counters(section, \".\")"), Some("2.4".to_string()));
+        assert_eq!(parse("counter(missing)"), Some("0".to_string()));
+        assert_eq!(parse("unknown(x)"), None);
+    }
+}
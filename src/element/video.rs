@@ -0,0 +1,555 @@
+use crate as deft;
+use crate::base::Rect;
+use crate::element::{Element, ElementBackend, ElementWeak};
+use crate::event::{EndedEvent, TimeUpdateEvent};
+use crate::event_loop::{create_event_loop_fn_mut, EventLoopFnMutCallback};
+use crate::render::RenderFn;
+use crate::{ok_or_return, some_or_return};
+use deft_macros::{element_backend, js_methods};
+use log::error;
+use serde_json::Value;
+use skia_safe::{
+    images, AlphaType, ColorType, Data, FilterMode, Image, ImageInfo, Paint, Rect as SkRect,
+    SamplingOptions,
+};
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use yoga::Size;
+
+const DEFAULT_FRAME_RATE: f64 = 30.0;
+/// Min interval of timeupdate events in seconds
+const TIME_UPDATE_INTERVAL: f64 = 0.25;
+
+/// Executables used for decoding, could be overridden by DEFT_FFMPEG and DEFT_FFPROBE
+fn ffmpeg_command(name: &str, env_key: &str) -> Command {
+    let program = std::env::var(env_key).unwrap_or(name.to_string());
+    let mut command = Command::new(program);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+/// Input argument of ffmpeg and ffprobe, local paths are prefixed with the file protocol so that
+/// they are never parsed as options
+fn ffmpeg_input(src: &str) -> String {
+    let is_url = src.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    if is_url {
+        src.to_string()
+    } else {
+        format!("file:{}", src)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct VideoMetadata {
+    width: u32,
+    height: u32,
+    /// Seconds, 0 if unknown
+    duration: f64,
+    frame_rate: f64,
+}
+
+impl VideoMetadata {
+    fn probe(src: &str) -> Result<Self, String> {
+        let output = ffmpeg_command("ffprobe", "DEFT_FFPROBE")
+            .args([
+                "-v",
+                "error",
+                "-select_streams",
+                "v:0",
+                "-show_entries",
+                "stream=width,height,avg_frame_rate,r_frame_rate:format=duration",
+                "-of",
+                "json",
+                &ffmpeg_input(src),
+            ])
+            .output()
+            .map_err(|e| format!("failed to run ffprobe: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| format!("no video stream found in {}", src))
+    }
+
+    fn parse(probe_output: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(probe_output).ok()?;
+        let stream = value.get("streams")?.get(0)?;
+        let width = stream.get("width")?.as_u64()? as u32;
+        let height = stream.get("height")?.as_u64()? as u32;
+        let frame_rate = ["avg_frame_rate", "r_frame_rate"]
+            .iter()
+            .find_map(|k| parse_frame_rate(stream.get(k)?.as_str()?))
+            .unwrap_or(DEFAULT_FRAME_RATE);
+        let duration = value
+            .get("format")
+            .and_then(|f| f.get("duration")?.as_str()?.parse().ok())
+            .unwrap_or(0.0);
+        Some(Self {
+            width,
+            height,
+            duration,
+            frame_rate,
+        })
+    }
+}
+
+/// Parse frame rates like "30000/1001" or "25"
+fn parse_frame_rate(value: &str) -> Option<f64> {
+    let rate = match value.split_once('/') {
+        Some((num, den)) => num.parse::<f64>().ok()? / den.parse::<f64>().ok()?,
+        None => value.parse().ok()?,
+    };
+    (rate.is_finite() && rate > 0.0).then_some(rate)
+}
+
+/// Decoded rgba pixels, converted to image on the main thread
+struct VideoFrame {
+    time: f64,
+    pixels: Vec<u8>,
+}
+
+/// State shared between a decoder thread and the main thread
+#[derive(Default)]
+struct DecoderShared {
+    metadata: Mutex<Option<VideoMetadata>>,
+    frame: Mutex<Option<VideoFrame>>,
+    error: Mutex<Option<String>>,
+    ended: AtomicBool,
+    stopped: AtomicBool,
+    process: Mutex<Option<Child>>,
+}
+
+struct Decoder {
+    shared: Arc<DecoderShared>,
+}
+
+impl Decoder {
+    /// Decode from `start_time`, only the first frame is decoded unless playing
+    fn start(
+        src: String,
+        start_time: f64,
+        playing: bool,
+        notify: EventLoopFnMutCallback<Arc<DecoderShared>>,
+    ) -> Self {
+        let shared = Arc::new(DecoderShared::default());
+        let thread_shared = shared.clone();
+        let _ = thread::Builder::new()
+            .name("video-decoder".to_string())
+            .spawn(move || {
+                let mut notify = notify;
+                if let Err(e) = Self::run(&src, start_time, playing, &thread_shared, &mut notify) {
+                    if !thread_shared.stopped.load(Ordering::Relaxed) {
+                        thread_shared.error.lock().unwrap().replace(e);
+                        notify.call(thread_shared.clone());
+                    }
+                }
+            });
+        Self { shared }
+    }
+
+    fn run(
+        src: &str,
+        start_time: f64,
+        playing: bool,
+        shared: &Arc<DecoderShared>,
+        notify: &mut EventLoopFnMutCallback<Arc<DecoderShared>>,
+    ) -> Result<(), String> {
+        let metadata = VideoMetadata::probe(src)?;
+        shared.metadata.lock().unwrap().replace(metadata);
+        notify.call(shared.clone());
+
+        let mut command = ffmpeg_command("ffmpeg", "DEFT_FFMPEG");
+        command.args([
+            "-v",
+            "error",
+            "-ss",
+            &format!("{:.3}", start_time),
+            "-i",
+            &ffmpeg_input(src),
+        ]);
+        if !playing {
+            command.args(["-frames:v", "1"]);
+        }
+        let mut child = command
+            .args(["-an", "-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("failed to run ffmpeg: {}", e))?;
+        let mut stdout = some_or_return!(child.stdout.take(), Err("no output".to_string()));
+        shared.process.lock().unwrap().replace(child);
+        if shared.stopped.load(Ordering::Relaxed) {
+            Self::kill(shared);
+            return Ok(());
+        }
+
+        let row_bytes = metadata.width as usize * 4;
+        let mut buf = vec![0u8; row_bytes * metadata.height as usize];
+        let begin = Instant::now();
+        let mut index = 0u64;
+        loop {
+            if stdout.read_exact(&mut buf).is_err() {
+                break;
+            }
+            if shared.stopped.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let offset = index as f64 / metadata.frame_rate;
+            let due = begin + Duration::from_secs_f64(offset);
+            let now = Instant::now();
+            if due > now {
+                thread::sleep(due - now);
+            }
+            let frame = VideoFrame {
+                time: start_time + offset,
+                pixels: buf.clone(),
+            };
+            let scheduled = shared.frame.lock().unwrap().replace(frame).is_some();
+            if !scheduled {
+                notify.call(shared.clone());
+            }
+            index += 1;
+        }
+        if let Some(mut child) = shared.process.lock().unwrap().take() {
+            let _ = child.wait();
+        }
+        if playing && !shared.stopped.load(Ordering::Relaxed) {
+            shared.ended.store(true, Ordering::Relaxed);
+            notify.call(shared.clone());
+        }
+        Ok(())
+    }
+
+    fn kill(shared: &DecoderShared) {
+        if let Some(mut child) = shared.process.lock().unwrap().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    fn stop(&self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+        Self::kill(&self.shared);
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Video element decoding frames with ffmpeg, the audio track is not played
+#[element_backend]
+pub struct Video {
+    element: ElementWeak,
+    src: String,
+    decoder: Option<Decoder>,
+    metadata: VideoMetadata,
+    image: Option<Image>,
+    current_time: f64,
+    last_time_update: f64,
+    paused: bool,
+    ended: bool,
+    auto_loop: bool,
+    container_size: (f32, f32),
+}
+
+#[js_methods]
+impl Video {
+    #[js_func]
+    pub fn set_src(&mut self, src: String) {
+        self.src = src;
+        self.image = None;
+        self.metadata = VideoMetadata::default();
+        self.current_time = 0.0;
+        self.last_time_update = 0.0;
+        self.ended = false;
+        self.restart_decoder();
+        self.element.mark_dirty(true);
+    }
+
+    #[js_func]
+    pub fn get_src(&self) -> String {
+        self.src.clone()
+    }
+
+    #[js_func]
+    pub fn play(&mut self) {
+        if !self.paused {
+            return;
+        }
+        if self.ended {
+            self.current_time = 0.0;
+            self.ended = false;
+        }
+        self.paused = false;
+        self.restart_decoder();
+    }
+
+    #[js_func]
+    pub fn pause(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.paused = true;
+        if let Some(decoder) = self.decoder.take() {
+            decoder.stop();
+        }
+    }
+
+    /// Seek to time in seconds
+    #[js_func]
+    pub fn seek(&mut self, time: f64) {
+        let time = if self.metadata.duration > 0.0 {
+            time.clamp(0.0, self.metadata.duration)
+        } else {
+            time.max(0.0)
+        };
+        self.current_time = time;
+        self.ended = false;
+        self.restart_decoder();
+        self.emit_time_update();
+    }
+
+    #[js_func]
+    pub fn get_current_time(&self) -> f64 {
+        self.current_time
+    }
+
+    /// Duration in seconds, 0 before metadata is loaded
+    #[js_func]
+    pub fn get_duration(&self) -> f64 {
+        self.metadata.duration
+    }
+
+    #[js_func]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    #[js_func]
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Restart from the beginning when ended
+    #[js_func]
+    pub fn set_loop(&mut self, value: bool) {
+        self.auto_loop = value;
+    }
+
+    fn restart_decoder(&mut self) {
+        if let Some(decoder) = self.decoder.take() {
+            decoder.stop();
+        }
+        if self.src.is_empty() {
+            return;
+        }
+        let element = self.element.clone();
+        let notify = create_event_loop_fn_mut(move |shared: Arc<DecoderShared>| {
+            let element = ok_or_return!(element.upgrade());
+            element
+                .get_backend_as::<Video>()
+                .clone()
+                .handle_decoder_update(&shared);
+        });
+        self.decoder = Some(Decoder::start(
+            self.src.clone(),
+            self.current_time,
+            !self.paused,
+            notify,
+        ));
+    }
+
+    fn handle_decoder_update(&mut self, shared: &Arc<DecoderShared>) {
+        let decoder = some_or_return!(&self.decoder);
+        if !Arc::ptr_eq(&decoder.shared, shared) {
+            return;
+        }
+        if let Some(metadata) = shared.metadata.lock().unwrap().take() {
+            let layout_dirty =
+                (metadata.width, metadata.height) != (self.metadata.width, self.metadata.height);
+            self.metadata = metadata;
+            self.element.mark_dirty(layout_dirty);
+        }
+        if let Some(e) = shared.error.lock().unwrap().take() {
+            error!("Failed to decode video {}: {}", self.src, e);
+        }
+        if let Some(frame) = shared.frame.lock().unwrap().take() {
+            let info = ImageInfo::new(
+                (self.metadata.width as i32, self.metadata.height as i32),
+                ColorType::RGBA8888,
+                AlphaType::Unpremul,
+                None,
+            );
+            let row_bytes = self.metadata.width as usize * 4;
+            let image = images::raster_from_data(&info, Data::new_copy(&frame.pixels), row_bytes);
+            if image.is_none() {
+                error!("Invalid frame of video {}", self.src);
+            }
+            self.image = image;
+            self.current_time = frame.time;
+            self.element.mark_dirty(false);
+            if self.current_time - self.last_time_update >= TIME_UPDATE_INTERVAL {
+                self.emit_time_update();
+            }
+        }
+        if shared.ended.load(Ordering::Relaxed) {
+            self.decoder = None;
+            if self.metadata.duration > 0.0 {
+                self.current_time = self.metadata.duration;
+            }
+            self.emit_time_update();
+            if self.auto_loop {
+                self.current_time = 0.0;
+                self.restart_decoder();
+            } else {
+                self.paused = true;
+                self.ended = true;
+                self.element.emit(EndedEvent);
+            }
+        }
+    }
+
+    fn emit_time_update(&mut self) {
+        self.last_time_update = self.current_time;
+        self.element.emit(TimeUpdateEvent {
+            current_time: self.current_time,
+            duration: self.metadata.duration,
+        });
+    }
+}
+
+/// Rect of content scaled to fit in container, keeping aspect ratio
+fn fit_rect(content: (f32, f32), container: (f32, f32)) -> SkRect {
+    let (cw, ch) = content;
+    let (width, height) = container;
+    if cw <= 0.0 || ch <= 0.0 {
+        return SkRect::from_wh(width, height);
+    }
+    let scale = f32::min(width / cw, height / ch);
+    let (w, h) = (cw * scale, ch * scale);
+    SkRect::from_xywh((width - w) / 2.0, (height - h) / 2.0, w, h)
+}
+
+impl ElementBackend for Video {
+    fn create(element: &mut Element) -> Self {
+        element.register_js_event::<TimeUpdateEvent>("timeupdate");
+        element.register_js_event::<EndedEvent>("ended");
+        let video = VideoData {
+            element: element.as_weak(),
+            src: String::new(),
+            decoder: None,
+            metadata: VideoMetadata::default(),
+            image: None,
+            current_time: 0.0,
+            last_time_update: 0.0,
+            paused: true,
+            ended: false,
+            auto_loop: false,
+            container_size: (0.0, 0.0),
+        }
+        .to_ref();
+        element
+            .style
+            .yoga_node
+            .set_measure_func(video.as_weak(), |video, _params| {
+                if let Ok(video) = video.upgrade() {
+                    return Size {
+                        width: video.metadata.width as f32,
+                        height: video.metadata.height as f32,
+                    };
+                }
+                Size {
+                    width: 0.0,
+                    height: 0.0,
+                }
+            });
+        video
+    }
+
+    fn reset(&mut self) {
+        self.pause();
+        self.auto_loop = false;
+        self.set_src(String::new());
+    }
+
+    fn get_base_mut(&mut self) -> Option<&mut dyn ElementBackend> {
+        None
+    }
+
+    fn render(&mut self) -> RenderFn {
+        let image = some_or_return!(self.image.clone(), RenderFn::empty());
+        let dst = fit_rect(
+            (image.width() as f32, image.height() as f32),
+            self.container_size,
+        );
+        RenderFn::new(move |painter| {
+            let mut options = SamplingOptions::default();
+            options.filter = FilterMode::Linear;
+            painter.canvas.draw_image_rect_with_sampling_options(
+                &image,
+                None,
+                dst,
+                options,
+                &Paint::default(),
+            );
+        })
+    }
+
+    fn handle_origin_bounds_change(&mut self, bounds: &Rect) {
+        self.container_size = (bounds.width, bounds.height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::video::{ffmpeg_input, fit_rect, parse_frame_rate, VideoMetadata};
+
+    #[test]
+    fn test_parse_metadata() {
+        let output = r#"{
+            "streams": [{"width": 1920, "height": 1080, "avg_frame_rate": "30000/1001", "r_frame_rate": "30/1"}],
+            "format": {"duration": "12.500000"}
+        }"#;
+        let metadata = VideoMetadata::parse(output).unwrap();
+        assert_eq!((1920, 1080), (metadata.width, metadata.height));
+        assert_eq!(12.5, metadata.duration);
+        assert!((metadata.frame_rate - 29.97).abs() < 0.01);
+        assert_eq!(None, parse_frame_rate("0/0"));
+        assert!(VideoMetadata::parse(r#"{"streams": []}"#).is_none());
+    }
+
+    #[test]
+    fn test_ffmpeg_input() {
+        assert_eq!("file:-version", ffmpeg_input("-version"));
+        assert_eq!("file:/tmp/a.mp4", ffmpeg_input("/tmp/a.mp4"));
+        assert_eq!("https://a.com/a.mp4", ffmpeg_input("https://a.com/a.mp4"));
+        assert_eq!("file:-i://a", ffmpeg_input("-i://a"));
+    }
+
+    #[test]
+    fn test_fit_rect() {
+        let rect = fit_rect((1920.0, 1080.0), (960.0, 1080.0));
+        assert_eq!(
+            (0.0, 270.0, 960.0, 540.0),
+            (rect.x(), rect.y(), rect.width(), rect.height())
+        );
+    }
+}
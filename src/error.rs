@@ -1,37 +1,159 @@
 use crate::js::JsError;
 use crate::mrc::UpgradeError;
 use std::error::Error;
-use std::fmt::{Display, Formatter};
+use std::fmt::{Debug, Display, Formatter};
+use std::io;
 
-#[derive(Debug)]
-pub enum DeftError {
-    InvalidState,
-    InvalidParameter,
-    Internal(String),
+/// Category of error, exposed to js as `code` of errors. Extension modules could define their
+/// own kinds, e.g. `pub const DEVICE_LOST: ErrorKind = ErrorKind::new("ERR_DEVICE_LOST");`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorKind {
+    code: &'static str,
 }
 
-impl Display for DeftError {
+impl ErrorKind {
+    pub const INVALID_STATE: ErrorKind = ErrorKind::new("ERR_INVALID_STATE");
+    pub const INVALID_PARAMETER: ErrorKind = ErrorKind::new("ERR_INVALID_PARAMETER");
+    pub const NOT_FOUND: ErrorKind = ErrorKind::new("ERR_NOT_FOUND");
+    pub const NOT_SUPPORTED: ErrorKind = ErrorKind::new("ERR_NOT_SUPPORTED");
+    pub const IO: ErrorKind = ErrorKind::new("ERR_IO");
+    pub const INTERNAL: ErrorKind = ErrorKind::new("ERR_INTERNAL");
+
+    pub const fn new(code: &'static str) -> Self {
+        Self { code }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+}
+
+/// Error with kind, message and the error causing it
+pub struct DeftError {
+    kind: ErrorKind,
+    message: String,
+    cause: Option<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+impl DeftError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    pub fn invalid_state() -> Self {
+        Self::new(ErrorKind::INVALID_STATE, "invalid state")
+    }
+
+    pub fn invalid_parameter() -> Self {
+        Self::new(ErrorKind::INVALID_PARAMETER, "invalid parameter")
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::INTERNAL, message)
+    }
+
+    pub fn with_cause(mut self, cause: impl Into<Box<dyn Error + Send + Sync + 'static>>) -> Self {
+        self.cause = Some(cause.into());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Convert to js error keeping code and causes
+    pub fn to_js_error(&self) -> JsError {
+        let mut error = JsError::new(self.message.clone()).with_code(self.kind.code);
+        if let Some(cause) = self.source() {
+            error = error.with_cause(JsError::from_error_chain(cause));
+        }
+        error
+    }
+}
+
+impl Debug for DeftError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            DeftError::InvalidState => f.write_str("invalid state"),
-            DeftError::InvalidParameter => f.write_str("invalid parameter"),
-            DeftError::Internal(message) => f.write_str(message),
+        let mut s = f.debug_struct("DeftError");
+        s.field("code", &self.kind.code);
+        s.field("message", &self.message);
+        if let Some(cause) = &self.cause {
+            s.field("cause", cause);
         }
+        s.finish()
+    }
+}
+
+impl Display for DeftError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
     }
 }
 
-impl Error for DeftError {}
+impl Error for DeftError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause
+            .as_ref()
+            .map(|e| e.as_ref() as &(dyn Error + 'static))
+    }
+}
 
 impl From<UpgradeError> for DeftError {
     fn from(_value: UpgradeError) -> Self {
-        DeftError::InvalidState
+        DeftError::invalid_state()
     }
 }
 
 impl From<JsError> for DeftError {
     fn from(value: JsError) -> Self {
-        DeftError::Internal(value.to_string())
+        DeftError::internal(value.to_string())
+    }
+}
+
+impl From<io::Error> for DeftError {
+    fn from(value: io::Error) -> Self {
+        let kind = match value.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NOT_FOUND,
+            io::ErrorKind::Unsupported => ErrorKind::NOT_SUPPORTED,
+            _ => ErrorKind::IO,
+        };
+        DeftError::new(kind, value.to_string()).with_cause(value)
     }
 }
 
 pub type DeftResult<T> = Result<T, DeftError>;
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{DeftError, ErrorKind};
+    use std::io;
+
+    const CUSTOM: ErrorKind = ErrorKind::new("ERR_CUSTOM");
+
+    #[test]
+    fn test_to_js_error() {
+        let io_error = io::Error::new(io::ErrorKind::NotFound, "no such file");
+        let error = DeftError::new(CUSTOM, "failed to load").with_cause(DeftError::from(io_error));
+        assert_eq!(error.code(), "ERR_CUSTOM");
+        let js_error = error.to_js_error();
+        assert_eq!(js_error.code(), Some("ERR_CUSTOM"));
+        assert_eq!(js_error.to_string(), "failed to load");
+        let cause = js_error.cause().unwrap();
+        assert_eq!(cause.code(), Some("ERR_NOT_FOUND"));
+        assert_eq!(cause.to_string(), "no such file");
+        let root_cause = cause.cause().unwrap();
+        assert_eq!(root_cause.code(), None);
+        assert!(root_cause.cause().is_none());
+    }
+}
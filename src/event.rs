@@ -2,8 +2,12 @@ pub mod event_emitter;
 
 use crate as deft;
 use crate::base;
-use crate::base::{MouseDetail, Rect, TouchDetail};
+use crate::base::{EventContext, EventListener, MouseDetail, Rect, TouchDetail};
+use crate::element::{ElementWeak, ViewEvent};
+use crate::js::js_value_util::EventResult;
+use crate::js::{FromJsValue, ToJsValue};
 use deft_macros::event;
+use quick_js::JsValue;
 use serde::{Deserialize, Serialize};
 use std::any::{Any, TypeId};
 use winit::keyboard::{ModifiersState, NamedKey};
@@ -38,6 +42,12 @@ pub const KEY_MOD_CTRL: u32 = 0x1;
 pub const KEY_MOD_ALT: u32 = 0x1 << 1;
 pub const KEY_MOD_META: u32 = 0x1 << 2;
 pub const KEY_MOD_SHIFT: u32 = 0x1 << 3;
+/// Modifier of common shortcuts like copy and paste, Meta on macos and Ctrl on other platforms
+pub const KEY_MOD_CMD_OR_CTRL: u32 = if cfg!(target_os = "macos") {
+    KEY_MOD_META
+} else {
+    KEY_MOD_CTRL
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -789,6 +799,43 @@ pub struct TouchEndEvent(pub TouchDetail);
 #[event]
 pub struct TouchCancelEvent(pub TouchDetail);
 
+/// Two-finger pinch, offset and window position are of the center of fingers
+#[event]
+pub struct GestureZoomEvent {
+    /// Scale relative to the distance of fingers when gesture started
+    pub scale: f32,
+    /// Scale relative to last event
+    pub delta_scale: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub window_x: f32,
+    pub window_y: f32,
+}
+
+/// Two-finger rotation in radians, clockwise is positive
+#[event]
+pub struct GestureRotateEvent {
+    pub rotation: f32,
+    pub delta_rotation: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub window_x: f32,
+    pub window_y: f32,
+}
+
+/// Two fingers moving together
+#[event]
+pub struct GesturePanEvent {
+    pub translation_x: f32,
+    pub translation_y: f32,
+    pub delta_x: f32,
+    pub delta_y: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub window_x: f32,
+    pub window_y: f32,
+}
+
 #[event]
 pub struct FocusEvent;
 
@@ -824,6 +871,25 @@ pub struct ScrollEvent {
     pub scroll_left: f32,
 }
 
+/// Scroll offset stays unchanged for a while after scrolling
+#[event]
+pub struct ScrollEndEvent {
+    pub scroll_top: f32,
+    pub scroll_left: f32,
+}
+
+/// Viewport is scrolled into the edge threshold of content top
+#[event]
+pub struct ReachTopEvent;
+
+/// Viewport is scrolled into the edge threshold of content bottom
+#[event]
+pub struct ReachBottomEvent;
+
+/// Scroll container is pulled beyond the refresh threshold and released
+#[event]
+pub struct RefreshEvent;
+
 #[event]
 pub struct DragStartEvent;
 
@@ -844,6 +910,18 @@ pub struct BoundsChangeEvent {
     pub origin_bounds: base::Rect,
 }
 
+/// Element entered or exited fullscreen
+#[event]
+pub struct FullscreenChangeEvent {
+    pub fullscreen: bool,
+}
+
+/// Pointer of element locked or released
+#[event]
+pub struct PointerLockChangeEvent {
+    pub locked: bool,
+}
+
 #[event]
 pub struct CaretChangeEvent {
     pub row: usize,
@@ -851,3 +929,98 @@ pub struct CaretChangeEvent {
     pub origin_bounds: Rect,
     pub bounds: Rect,
 }
+
+/// Emitted after text of masked input changed
+#[event]
+pub struct MaskValidateEvent {
+    pub value: String,
+    /// Characters filled in input slots of mask
+    pub raw_value: String,
+    pub complete: bool,
+}
+
+/// Emitted when a link of rich text is clicked or activated by keyboard
+#[event]
+pub struct LinkClickEvent {
+    pub href: String,
+}
+
+/// Emitted when mouse enters or leaves a link of rich text, href is None when leaving
+#[event]
+pub struct LinkHoverEvent {
+    pub href: Option<String>,
+}
+
+/// Emitted periodically while a video is playing, and after seeking. Times are in seconds
+#[event]
+pub struct TimeUpdateEvent {
+    pub current_time: f64,
+    /// 0 if unknown
+    pub duration: f64,
+}
+
+/// Emitted when a video reaches its end
+#[event]
+pub struct EndedEvent;
+
+/// Emitted when text gets truncated by line clamp or shown fully again
+#[event]
+pub struct TruncationChangeEvent {
+    pub truncated: bool,
+}
+
+/// User-defined event dispatched from js, listeners are matched by event name
+pub struct CustomEvent {
+    pub name: String,
+    pub detail: JsValue,
+    pub bubbles: bool,
+}
+
+impl ViewEvent for CustomEvent {
+    fn allow_bubbles(&self) -> bool {
+        self.bubbles
+    }
+}
+
+pub struct CustomEventListener {
+    name: String,
+    listener: Box<dyn FnMut(&mut CustomEvent, &mut EventContext<ElementWeak>)>,
+}
+
+impl CustomEventListener {
+    pub fn new<F: FnMut(&mut CustomEvent, &mut EventContext<ElementWeak>) + 'static>(
+        name: &str,
+        listener: F,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            listener: Box::new(listener),
+        }
+    }
+
+    pub fn from_js_value(name: &str, value: JsValue) -> Self {
+        Self::new(name, move |e, ctx| {
+            if let Ok(target) = ctx.target.clone().to_js_value() {
+                let callback_result = value.call_as_function(vec![e.detail.clone(), target]);
+                if let Ok(cb_result) = callback_result {
+                    if let Ok(res) = EventResult::from_js_value(cb_result) {
+                        if res.propagation_cancelled {
+                            ctx.propagation_cancelled = true;
+                        }
+                        if res.prevent_default {
+                            ctx.prevent_default = true;
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl EventListener<CustomEvent, ElementWeak> for CustomEventListener {
+    fn handle_event(&mut self, event: &mut CustomEvent, ctx: &mut EventContext<ElementWeak>) {
+        if event.name == self.name {
+            (self.listener)(event, ctx);
+        }
+    }
+}
@@ -1,8 +1,12 @@
 use crate::app::{AppEvent, AppEventPayload};
 use crate::base::{ResultWaiter, UnsafeFnMut, UnsafeFnOnce};
+use crate::batch;
+use crate::metrics;
 use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
 use std::ptr::null_mut;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use winit::event_loop::{ActiveEventLoop, EventLoopClosed, EventLoopProxy};
 
 thread_local! {
@@ -10,27 +14,96 @@ thread_local! {
     pub static STATIC_EVENT_LOOP_PROXY: RefCell<Option<AppEventProxy>> = RefCell::new(None);
 }
 
+/// Lanes of app events, events in lanes of higher priority are handled first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventPriority {
+    Input = 0,
+    Animation = 1,
+    Normal = 2,
+    Idle = 3,
+}
+
+const LANE_COUNT: usize = 4;
+
+/// Sent to winit event loop for every queued app event, the event of highest priority
+/// is taken when it is received.
+#[derive(Debug)]
+pub struct AppEventWakeUp;
+
+#[derive(Default)]
+struct EventLanes {
+    lanes: [VecDeque<AppEventPayload>; LANE_COUNT],
+    /// Result waiters of queued update events by window id, duplicate updates are coalesced
+    pending_updates: HashMap<i32, ResultWaiter<()>>,
+}
+
 #[derive(Clone)]
 pub struct AppEventProxy {
-    proxy: EventLoopProxy<AppEventPayload>,
+    waker: Arc<dyn Fn() -> bool + Send + Sync>,
+    lanes: Arc<Mutex<EventLanes>>,
 }
 
 impl AppEventProxy {
-    pub fn new(proxy: EventLoopProxy<AppEventPayload>) -> AppEventProxy {
-        Self { proxy }
+    pub fn new(proxy: EventLoopProxy<AppEventWakeUp>) -> AppEventProxy {
+        Self::with_waker(move || proxy.send_event(AppEventWakeUp).is_ok())
+    }
+
+    /// Create proxy for an event loop owned by host, `waker` should make the host deliver an
+    /// `AppEventWakeUp` to deft and return false if the event loop is closed.
+    pub fn with_waker<F: Fn() -> bool + Send + Sync + 'static>(waker: F) -> AppEventProxy {
+        Self {
+            waker: Arc::new(waker),
+            lanes: Arc::new(Mutex::new(EventLanes::default())),
+        }
     }
 
     pub fn send_event(
         &self,
         event: AppEvent,
-    ) -> Result<ResultWaiter<()>, EventLoopClosed<AppEventPayload>> {
-        let result_waiter = ResultWaiter::new();
-        self.proxy.send_event(AppEventPayload {
-            event,
-            result_waiter: result_waiter.clone(),
-        })?;
+    ) -> Result<ResultWaiter<()>, EventLoopClosed<AppEventWakeUp>> {
+        let priority = event.priority();
+        self.send_event_with_priority(event, priority)
+    }
+
+    pub fn send_event_with_priority(
+        &self,
+        event: AppEvent,
+        priority: EventPriority,
+    ) -> Result<ResultWaiter<()>, EventLoopClosed<AppEventWakeUp>> {
+        let result_waiter = {
+            let mut lanes = self.lanes.lock().unwrap();
+            if let AppEvent::Update(window_id) = &event {
+                if let Some(waiter) = lanes.pending_updates.get(window_id) {
+                    return Ok(waiter.clone());
+                }
+            }
+            let result_waiter = ResultWaiter::new();
+            if let AppEvent::Update(window_id) = &event {
+                lanes
+                    .pending_updates
+                    .insert(*window_id, result_waiter.clone());
+            }
+            lanes.lanes[priority as usize].push_back(AppEventPayload {
+                event,
+                result_waiter: result_waiter.clone(),
+            });
+            result_waiter
+        };
+        if !(self.waker)() {
+            return Err(EventLoopClosed(AppEventWakeUp));
+        }
         Ok(result_waiter)
     }
+
+    /// Take the queued event of highest priority
+    pub fn take_next_event(&self) -> Option<AppEventPayload> {
+        let mut lanes = self.lanes.lock().unwrap();
+        let payload = lanes.lanes.iter_mut().find_map(|lane| lane.pop_front())?;
+        if let AppEvent::Update(window_id) = &payload.event {
+            lanes.pending_updates.remove(window_id);
+        }
+        Some(payload)
+    }
 }
 
 pub struct EventLoopCallback {
@@ -99,7 +172,13 @@ pub fn create_event_loop_fn_mut<P: Send + Sync, F: FnMut(P) + 'static>(
 
 pub fn run_event_loop_task<F: FnOnce()>(event_loop: &ActiveEventLoop, callback: F) {
     ACTIVE_EVENT_LOOP.set(event_loop as *const ActiveEventLoop);
-    callback();
+    let start = Instant::now();
+    batch::auto_batch(callback);
+    let elapsed = start.elapsed().as_millis() as u64;
+    if elapsed > metrics::LONG_TASK_MILLIS {
+        metrics::increment(metrics::LONG_TASKS, 1.0);
+        metrics::timing(metrics::LONG_TASK_TIME, elapsed as f64);
+    }
     ACTIVE_EVENT_LOOP.set(null_mut());
 }
 
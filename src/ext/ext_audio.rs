@@ -97,6 +97,13 @@ fn registry_playing(audio: &Audio) {
     })
 }
 
+pub fn stop_all_audios() {
+    let audios: Vec<Audio> = PLAYING_MAP.with_borrow(|m| m.values().cloned().collect());
+    for audio in audios {
+        let _ = audio.stop();
+    }
+}
+
 fn unregistry_playing(audio: &Audio) {
     let id = audio.id;
     PLAYING_MAP.with_borrow_mut(move |m| {
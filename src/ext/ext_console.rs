@@ -8,6 +8,8 @@ impl Console {
     #[js_func]
     pub fn print(text: String) {
         print!("{}", text);
+        #[cfg(feature = "devtools")]
+        crate::devtools::write_console(&text);
         #[cfg(target_env = "ohos")]
         ohos_hilog_binding::hilog_info!("{}", text);
     }
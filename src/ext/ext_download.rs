@@ -0,0 +1,674 @@
+use crate as deft;
+use crate::data_dir::get_data_path;
+use crate::event_loop::{create_event_loop_fn_mut, EventLoopFnMutCallback};
+use crate::js::js_engine::JsEngine;
+use crate::js::{JsPo, ToJsValue};
+use crate::network::http_client_builder;
+use crate::{js_serialize, some_or_return};
+use anyhow::{anyhow, Error};
+use deft_macros::js_methods;
+use log::error;
+use quick_js::JsValue;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+
+const DEFAULT_MAX_CONCURRENT: usize = 3;
+/// Minimal interval between progress events of a download
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+const METADATA_FILE: &str = "downloads.json";
+
+thread_local! {
+    static MANAGER: RefCell<Option<DownloadManager>> = RefCell::new(None);
+    static HANDLER: RefCell<Option<JsValue>> = RefCell::new(None);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadState {
+    Queued,
+    Downloading,
+    Paused,
+    Completed,
+    Failed,
+    Canceled,
+}
+
+impl DownloadState {
+    fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            DownloadState::Completed | DownloadState::Failed | DownloadState::Canceled
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Download {
+    pub id: u32,
+    pub url: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub state: DownloadState,
+    pub received: u64,
+    pub total: Option<u64>,
+    /// Bytes per second, zero unless downloading
+    #[serde(default)]
+    pub speed: f64,
+    pub error: Option<String>,
+    /// Validator of the partial file, resuming restarts from scratch if the resource changed
+    pub etag: Option<String>,
+}
+
+js_serialize!(Download);
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadSummary {
+    pub queued: usize,
+    pub downloading: usize,
+    pub paused: usize,
+    pub completed: usize,
+    pub failed: usize,
+    /// Bytes received of unfinished downloads
+    pub received: u64,
+    /// Bytes to receive of unfinished downloads, None if any size is unknown
+    pub total: Option<u64>,
+    pub speed: f64,
+}
+
+js_serialize!(DownloadSummary);
+
+impl DownloadSummary {
+    fn of<'a, I: IntoIterator<Item = &'a Download>>(downloads: I) -> Self {
+        let mut summary = Self {
+            total: Some(0),
+            ..Default::default()
+        };
+        for d in downloads {
+            match d.state {
+                DownloadState::Queued => summary.queued += 1,
+                DownloadState::Downloading => summary.downloading += 1,
+                DownloadState::Paused => summary.paused += 1,
+                DownloadState::Completed => summary.completed += 1,
+                DownloadState::Failed => summary.failed += 1,
+                DownloadState::Canceled => {}
+            }
+            if !d.state.is_finished() {
+                summary.received += d.received;
+                summary.total = summary.total.zip(d.total).map(|(a, b)| a + b);
+                summary.speed += d.speed;
+            }
+        }
+        summary
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DownloadOptions {
+    pub headers: Option<HashMap<String, String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadEvent {
+    r#type: &'static str,
+    download: Download,
+    summary: DownloadSummary,
+}
+
+js_serialize!(DownloadEvent);
+
+enum TaskReport {
+    Started {
+        received: u64,
+        total: Option<u64>,
+        etag: Option<String>,
+    },
+    Progress {
+        received: u64,
+        speed: f64,
+    },
+    Completed,
+    /// Stopped because of pause or cancel
+    Stopped,
+    Failed(String),
+}
+
+struct DownloadManager {
+    downloads: BTreeMap<u32, Download>,
+    next_id: u32,
+    max_concurrent: usize,
+    /// Stop flags of running tasks
+    running: HashMap<u32, Arc<AtomicBool>>,
+    /// Events dispatched after the manager is released, handlers may call the manager again
+    events: Vec<DownloadEvent>,
+    reporter: EventLoopFnMutCallback<(u32, TaskReport)>,
+}
+
+impl DownloadManager {
+    fn load() -> Self {
+        let mut downloads = read_metadata();
+        for d in downloads.values_mut() {
+            if !d.state.is_finished() {
+                d.received = fs::metadata(part_path(&d.path))
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+            }
+            // Interrupted by exiting, continue when the manager is used again
+            if d.state == DownloadState::Downloading {
+                d.state = DownloadState::Queued;
+            }
+        }
+        let next_id = downloads.keys().last().map(|id| id + 1).unwrap_or(1);
+        let reporter = create_event_loop_fn_mut(|(id, report): (u32, TaskReport)| {
+            with_manager(|m| m.handle_report(id, report));
+        });
+        Self {
+            downloads,
+            next_id,
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            running: HashMap::new(),
+            events: Vec::new(),
+            reporter,
+        }
+    }
+
+    fn add(&mut self, url: String, path: String, headers: HashMap<String, String>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let download = Download {
+            id,
+            url,
+            path,
+            headers,
+            state: DownloadState::Queued,
+            received: 0,
+            total: None,
+            speed: 0.0,
+            error: None,
+            etag: None,
+        };
+        self.downloads.insert(id, download);
+        self.state_changed(id);
+        self.schedule();
+        id
+    }
+
+    fn get_mut(&mut self, id: u32) -> Result<&mut Download, Error> {
+        self.downloads
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("download not found: {}", id))
+    }
+
+    fn pause(&mut self, id: u32) -> Result<(), Error> {
+        let download = self.get_mut(id)?;
+        if !matches!(
+            download.state,
+            DownloadState::Queued | DownloadState::Downloading
+        ) {
+            return Ok(());
+        }
+        download.state = DownloadState::Paused;
+        download.speed = 0.0;
+        self.stop_task(id);
+        self.state_changed(id);
+        self.schedule();
+        Ok(())
+    }
+
+    fn resume(&mut self, id: u32) -> Result<(), Error> {
+        let download = self.get_mut(id)?;
+        if !matches!(
+            download.state,
+            DownloadState::Paused | DownloadState::Failed
+        ) {
+            return Ok(());
+        }
+        download.state = DownloadState::Queued;
+        download.error = None;
+        self.state_changed(id);
+        self.schedule();
+        Ok(())
+    }
+
+    fn cancel(&mut self, id: u32) -> Result<(), Error> {
+        let download = self.get_mut(id)?;
+        if download.state.is_finished() {
+            return Ok(());
+        }
+        download.state = DownloadState::Canceled;
+        download.speed = 0.0;
+        let path = download.path.clone();
+        // Running task removes the partial file after it stopped writing
+        if !self.stop_task(id) {
+            let _ = fs::remove_file(part_path(&path));
+        }
+        self.state_changed(id);
+        self.schedule();
+        Ok(())
+    }
+
+    /// Remove a finished download from the list, the downloaded file is kept
+    fn remove(&mut self, id: u32) -> Result<(), Error> {
+        let download = self.get_mut(id)?;
+        if !download.state.is_finished() {
+            return Err(anyhow!("download is not finished: {}", id));
+        }
+        self.downloads.remove(&id);
+        self.save();
+        Ok(())
+    }
+
+    fn set_max_concurrent(&mut self, max: usize) {
+        self.max_concurrent = max.max(1);
+        self.schedule();
+    }
+
+    /// Return true if the task is running
+    fn stop_task(&mut self, id: u32) -> bool {
+        match self.running.get(&id) {
+            Some(stopped) => {
+                stopped.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Start queued downloads in order until the concurrency limit is reached
+    fn schedule(&mut self) {
+        while self.running.len() < self.max_concurrent {
+            let next = self
+                .downloads
+                .values_mut()
+                .find(|d| d.state == DownloadState::Queued && !self.running.contains_key(&d.id));
+            let download = some_or_return!(next);
+            download.state = DownloadState::Downloading;
+            let download = download.clone();
+            let stopped = Arc::new(AtomicBool::new(false));
+            self.running.insert(download.id, stopped.clone());
+            let mut reporter = self.reporter.clone();
+            JsEngine::get().runtime_handle().spawn(async move {
+                let id = download.id;
+                let report = match run_task(download, stopped, reporter.clone()).await {
+                    Ok(report) => report,
+                    Err(e) => TaskReport::Failed(e.to_string()),
+                };
+                reporter.call((id, report));
+            });
+            // Start event is emitted when response received
+            self.save();
+        }
+    }
+
+    fn handle_report(&mut self, id: u32, report: TaskReport) {
+        let finished = matches!(
+            report,
+            TaskReport::Completed | TaskReport::Stopped | TaskReport::Failed(_)
+        );
+        if finished {
+            self.running.remove(&id);
+        }
+        let download = some_or_return!(self.downloads.get_mut(&id));
+        match report {
+            TaskReport::Started {
+                received,
+                total,
+                etag,
+            } => {
+                download.received = received;
+                download.total = total;
+                download.etag = etag;
+                self.state_changed(id);
+            }
+            TaskReport::Progress { received, speed } => {
+                if download.state == DownloadState::Downloading {
+                    download.received = received;
+                    download.speed = speed;
+                    self.emit("progress", id);
+                }
+            }
+            TaskReport::Completed => {
+                download.state = DownloadState::Completed;
+                download.speed = 0.0;
+                download.received = download.total.unwrap_or(download.received);
+                self.state_changed(id);
+            }
+            TaskReport::Stopped => {
+                // State has been changed by pause or cancel, resumed before stopped if queued
+                if download.state == DownloadState::Canceled {
+                    let _ = fs::remove_file(part_path(&download.path));
+                }
+            }
+            TaskReport::Failed(e) => {
+                download.state = DownloadState::Failed;
+                download.speed = 0.0;
+                download.error = Some(e);
+                self.state_changed(id);
+            }
+        }
+        if finished {
+            self.schedule();
+        }
+    }
+
+    fn state_changed(&mut self, id: u32) {
+        self.save();
+        self.emit("statechange", id);
+    }
+
+    fn emit(&mut self, r#type: &'static str, id: u32) {
+        let download = some_or_return!(self.downloads.get(&id)).clone();
+        self.events.push(DownloadEvent {
+            r#type,
+            download,
+            summary: DownloadSummary::of(self.downloads.values()),
+        });
+    }
+
+    fn save(&self) {
+        if let Err(e) = write_metadata(&self.downloads) {
+            error!("Failed to save download metadata: {:?}", e);
+        }
+    }
+}
+
+fn with_manager<R, F: FnOnce(&mut DownloadManager) -> R>(f: F) -> R {
+    let (result, events) = MANAGER.with_borrow_mut(|m| {
+        let manager = m.get_or_insert_with(DownloadManager::load);
+        let result = f(manager);
+        (result, std::mem::take(&mut manager.events))
+    });
+    for event in events {
+        dispatch_event(event);
+    }
+    result
+}
+
+fn dispatch_event(event: DownloadEvent) {
+    let handler = some_or_return!(HANDLER.with_borrow(|h| h.clone()));
+    let event = match event.to_js_value() {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to serialize download event: {:?}", e);
+            return;
+        }
+    };
+    if let Err(e) = handler.call_as_function(vec![event]) {
+        error!("Failed to call download event handler: {:?}", e);
+    }
+}
+
+fn part_path(path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.part", path))
+}
+
+fn metadata_path() -> PathBuf {
+    get_data_path(METADATA_FILE)
+}
+
+fn read_metadata() -> BTreeMap<u32, Download> {
+    let content = match fs::read_to_string(metadata_path()) {
+        Ok(c) => c,
+        Err(_) => return BTreeMap::new(),
+    };
+    match serde_json::from_str::<Vec<Download>>(&content) {
+        Ok(list) => list.into_iter().map(|d| (d.id, d)).collect(),
+        Err(e) => {
+            error!("invalid download metadata: {}", e);
+            BTreeMap::new()
+        }
+    }
+}
+
+fn write_metadata(downloads: &BTreeMap<u32, Download>) -> Result<(), Error> {
+    let path = metadata_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let list: Vec<&Download> = downloads.values().collect();
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_string_pretty(&list)?)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+/// Parse total size from `Content-Range: bytes 100-199/1000`
+fn parse_content_range_total(value: &str) -> Option<u64> {
+    let (_, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    total.trim().parse().ok()
+}
+
+async fn run_task(
+    download: Download,
+    stopped: Arc<AtomicBool>,
+    mut reporter: EventLoopFnMutCallback<(u32, TaskReport)>,
+) -> Result<TaskReport, Error> {
+    let id = download.id;
+    let part = part_path(&download.path);
+    let mut offset = tokio::fs::metadata(&part)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    let mut headers = HeaderMap::new();
+    for (k, v) in &download.headers {
+        headers.insert(HeaderName::from_str(k)?, HeaderValue::from_str(v)?);
+    }
+    if offset > 0 {
+        headers.insert(
+            "range",
+            HeaderValue::from_str(&format!("bytes={}-", offset))?,
+        );
+        if let Some(etag) = &download.etag {
+            headers.insert("if-range", HeaderValue::from_str(etag)?);
+        }
+    }
+    let client = http_client_builder()?.build()?;
+    let mut rsp = client.get(&download.url).headers(headers).send().await?;
+    let rsp_headers = rsp.headers();
+    let (append, total) = if rsp.status() == StatusCode::PARTIAL_CONTENT {
+        let total = rsp_headers
+            .get("content-range")
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_content_range_total);
+        (true, total)
+    } else if rsp.status().is_success() {
+        // Server ignored range or the resource changed, start over
+        offset = 0;
+        (false, rsp.content_length())
+    } else {
+        return Err(anyhow!("unexpected status: {}", rsp.status()));
+    };
+    let etag = rsp_headers
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    reporter.call((
+        id,
+        TaskReport::Started {
+            received: offset,
+            total,
+            etag,
+        },
+    ));
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&part)
+        .await?;
+    let mut received = offset;
+    let mut last_report = Instant::now();
+    let mut last_received = received;
+    while let Some(chunk) = rsp.chunk().await? {
+        if stopped.load(Ordering::Relaxed) {
+            file.flush().await?;
+            return Ok(TaskReport::Stopped);
+        }
+        file.write_all(&chunk).await?;
+        received += chunk.len() as u64;
+        let elapsed = last_report.elapsed();
+        if elapsed >= PROGRESS_INTERVAL {
+            let speed = (received - last_received) as f64 / elapsed.as_secs_f64();
+            reporter.call((id, TaskReport::Progress { received, speed }));
+            last_report = Instant::now();
+            last_received = received;
+        }
+    }
+    file.flush().await?;
+    drop(file);
+    if stopped.load(Ordering::Relaxed) {
+        return Ok(TaskReport::Stopped);
+    }
+    tokio::fs::rename(&part, &download.path).await?;
+    Ok(TaskReport::Completed)
+}
+
+#[allow(nonstandard_style)]
+pub struct download;
+
+#[js_methods]
+impl download {
+    /// Queue a download saving to path, data is written to `{path}.part` until completed
+    #[js_func]
+    pub fn add(
+        url: String,
+        path: String,
+        options: Option<JsPo<DownloadOptions>>,
+    ) -> Result<u32, Error> {
+        let headers = options.and_then(|o| o.take().headers).unwrap_or_default();
+        for (k, v) in &headers {
+            HeaderName::from_str(k)?;
+            HeaderValue::from_str(v)?;
+        }
+        Ok(with_manager(|m| m.add(url, path, headers)))
+    }
+
+    #[js_func]
+    pub fn pause(id: u32) -> Result<(), Error> {
+        with_manager(|m| m.pause(id))
+    }
+
+    #[js_func]
+    pub fn resume(id: u32) -> Result<(), Error> {
+        with_manager(|m| m.resume(id))
+    }
+
+    /// Stop the download and delete its partial file
+    #[js_func]
+    pub fn cancel(id: u32) -> Result<(), Error> {
+        with_manager(|m| m.cancel(id))
+    }
+
+    #[js_func]
+    pub fn remove(id: u32) -> Result<(), Error> {
+        with_manager(|m| m.remove(id))
+    }
+
+    #[js_func]
+    pub fn get(id: u32) -> Option<Download> {
+        with_manager(|m| m.downloads.get(&id).cloned())
+    }
+
+    /// All downloads including the ones restored from last run
+    #[js_func]
+    pub fn list() -> Vec<Download> {
+        with_manager(|m| m.downloads.values().cloned().collect())
+    }
+
+    #[js_func]
+    pub fn summary() -> DownloadSummary {
+        with_manager(|m| DownloadSummary::of(m.downloads.values()))
+    }
+
+    /// Running downloads are not stopped when lowering the limit
+    #[js_func]
+    pub fn set_max_concurrent(max: usize) {
+        with_manager(|m| m.set_max_concurrent(max));
+    }
+
+    /// Handler is called with `{type, download, summary}` when a download changes
+    #[js_func]
+    pub fn set_handler(handler: JsValue) {
+        let handler = match handler {
+            JsValue::Null | JsValue::Undefined => None,
+            h => Some(h),
+        };
+        HANDLER.set(handler);
+        // Restored downloads start once someone is listening
+        with_manager(|m| m.schedule());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::ext_download::{
+        parse_content_range_total, Download, DownloadState, DownloadSummary,
+    };
+    use std::collections::HashMap;
+
+    fn create_download(
+        id: u32,
+        state: DownloadState,
+        received: u64,
+        total: Option<u64>,
+    ) -> Download {
+        Download {
+            id,
+            url: format!("https://example.com/{}", id),
+            path: format!("/tmp/{}", id),
+            headers: HashMap::new(),
+            state,
+            received,
+            total,
+            speed: 0.0,
+            error: None,
+            etag: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_content_range_total() {
+        assert_eq!(Some(1000), parse_content_range_total("bytes 100-999/1000"));
+        assert_eq!(None, parse_content_range_total("bytes 100-999/*"));
+        assert_eq!(None, parse_content_range_total("items 1-2/3"));
+    }
+
+    #[test]
+    fn test_summary() {
+        let downloads = vec![
+            create_download(1, DownloadState::Downloading, 10, Some(100)),
+            create_download(2, DownloadState::Paused, 20, Some(50)),
+            create_download(3, DownloadState::Completed, 30, Some(30)),
+            create_download(4, DownloadState::Canceled, 5, None),
+        ];
+        let summary = DownloadSummary::of(&downloads);
+        assert_eq!(
+            (1, 1, 1),
+            (summary.downloading, summary.paused, summary.completed)
+        );
+        assert_eq!(30, summary.received);
+        assert_eq!(Some(150), summary.total);
+
+        let downloads = vec![
+            create_download(1, DownloadState::Queued, 0, None),
+            create_download(2, DownloadState::Paused, 20, Some(50)),
+        ];
+        assert_eq!(None, DownloadSummary::of(&downloads).total);
+    }
+}
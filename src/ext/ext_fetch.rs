@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use crate::js::JsPo;
+use crate::js::{JsBytes, JsPo};
+use crate::network::http_client_builder;
 use crate::{js_serialize, js_value};
 use anyhow::{anyhow, Error};
 use deft_macros::js_methods;
@@ -47,7 +48,7 @@ impl fetch {
         url: String,
         options: Option<JsPo<FetchOptions>>,
     ) -> Result<FetchResponse, Error> {
-        let mut client_builder = reqwest::Client::builder();
+        let mut client_builder = http_client_builder()?;
         let mut method = Method::GET;
         let mut headers = HeaderMap::new();
         let mut body = None;
@@ -110,6 +111,16 @@ impl fetch {
         Ok(headers)
     }
 
+    #[js_func]
+    pub async fn response_body_bytes(response: FetchResponse) -> Result<JsBytes, Error> {
+        let mut rsp = response.response.lock().await;
+        let mut result = Vec::new();
+        while let Some(c) = rsp.chunk().await? {
+            result.extend_from_slice(&c);
+        }
+        Ok(JsBytes(result))
+    }
+
     #[js_func]
     pub async fn response_body_string(response: FetchResponse) -> Result<String, Error> {
         let mut rsp = response.response.lock().await;
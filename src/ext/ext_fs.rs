@@ -1,14 +1,25 @@
 use crate as deft;
-use crate::js::JsPo;
-use deft_macros::js_func;
+use crate::js::{JsBytes, JsPo};
+use crate::js_value;
+use deft_macros::{js_func, js_methods};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::future::Future;
 use std::io;
+use std::io::SeekFrom;
 #[cfg(unix)]
 use std::os::unix::fs::MetadataExt;
 #[cfg(windows)]
 use std::os::windows::fs::MetadataExt;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, LazyLock, Mutex as StdMutex};
+use std::time::UNIX_EPOCH;
 use tokio::fs;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{Mutex, Notify};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Stat {
@@ -76,3 +87,266 @@ pub async fn fs_remove_dir(path: String) -> io::Result<()> {
 pub async fn fs_remove_dir_all(path: String) -> io::Result<()> {
     fs::remove_dir_all(&path).await
 }
+
+static NEXT_CANCEL_TOKEN_ID: AtomicU32 = AtomicU32::new(1);
+
+static CANCEL_TOKENS: LazyLock<StdMutex<HashMap<u32, Arc<CancelState>>>> =
+    LazyLock::new(|| StdMutex::new(HashMap::new()));
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DirEntry {
+    name: String,
+    size: u64,
+    is_dir: bool,
+    is_file: bool,
+    /// Milliseconds since unix epoch
+    modified: Option<f64>,
+}
+
+/// Create token to cancel fs operations, release it with `fs_release_cancel_token`
+#[js_func]
+pub fn fs_create_cancel_token() -> u32 {
+    let id = NEXT_CANCEL_TOKEN_ID.fetch_add(1, Ordering::Relaxed);
+    CANCEL_TOKENS
+        .lock()
+        .unwrap()
+        .insert(id, Arc::new(CancelState::default()));
+    id
+}
+
+/// Cancel all pending operations using the token, they are rejected with an interrupted error
+#[js_func]
+pub fn fs_cancel(token: u32) {
+    if let Some(state) = CANCEL_TOKENS.lock().unwrap().get(&token) {
+        state.cancelled.store(true, Ordering::Relaxed);
+        state.notify.notify_waiters();
+    }
+}
+
+#[js_func]
+pub fn fs_release_cancel_token(token: u32) {
+    CANCEL_TOKENS.lock().unwrap().remove(&token);
+}
+
+async fn cancellable<T, F: Future<Output = io::Result<T>>>(
+    token: Option<u32>,
+    future: F,
+) -> io::Result<T> {
+    let state = token.and_then(|t| CANCEL_TOKENS.lock().unwrap().get(&t).cloned());
+    let state = match state {
+        Some(s) => s,
+        None => return future.await,
+    };
+    let cancelled = || io::Error::new(io::ErrorKind::Interrupted, "cancelled");
+    // Created before checking the flag so that cancelling in between is not missed
+    let notified = state.notify.notified();
+    if state.cancelled.load(Ordering::Relaxed) {
+        return Err(cancelled());
+    }
+    tokio::select! {
+        r = future => r,
+        _ = notified => Err(cancelled()),
+    }
+}
+
+fn modified_millis(meta: &Metadata) -> Option<f64> {
+    let modified = meta.modified().ok()?;
+    let d = modified.duration_since(UNIX_EPOCH).ok()?;
+    Some(d.as_millis() as f64)
+}
+
+/// List entries of dir with their metadata
+#[js_func]
+pub async fn fs_read_dir_entries(
+    path: String,
+    cancel: Option<u32>,
+) -> io::Result<Vec<JsPo<DirEntry>>> {
+    cancellable(cancel, async move {
+        let mut dirs = fs::read_dir(&path).await?;
+        let mut result = Vec::new();
+        while let Some(entry) = dirs.next_entry().await? {
+            let meta = entry.metadata().await?;
+            result.push(JsPo::new(DirEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                size: meta.len(),
+                is_dir: meta.is_dir(),
+                is_file: meta.is_file(),
+                modified: modified_millis(&meta),
+            }));
+        }
+        Ok(result)
+    })
+    .await
+}
+
+async fn copy_all(src: PathBuf, dest: PathBuf) -> io::Result<()> {
+    let mut pending = vec![(src, dest)];
+    while let Some((src, dest)) = pending.pop() {
+        let meta = fs::symlink_metadata(&src).await?;
+        if meta.is_dir() {
+            fs::create_dir_all(&dest).await?;
+            let mut entries = fs::read_dir(&src).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                pending.push((entry.path(), dest.join(entry.file_name())));
+            }
+        } else {
+            fs::copy(&src, &dest).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn remove_all(path: &PathBuf) -> io::Result<()> {
+    if fs::symlink_metadata(path).await?.is_dir() {
+        fs::remove_dir_all(path).await
+    } else {
+        fs::remove_file(path).await
+    }
+}
+
+/// Copy file or dir recursively
+#[js_func]
+pub async fn fs_copy(src: String, dest: String, cancel: Option<u32>) -> io::Result<()> {
+    cancellable(cancel, copy_all(PathBuf::from(src), PathBuf::from(dest))).await
+}
+
+/// Move file or dir, falls back to copying and removing only when moving across file systems
+#[js_func]
+pub async fn fs_move(src: String, dest: String, cancel: Option<u32>) -> io::Result<()> {
+    cancellable(cancel, async move {
+        match fs::rename(&src, &dest).await {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+            result => return result,
+        }
+        let src = PathBuf::from(src);
+        copy_all(src.clone(), PathBuf::from(dest)).await?;
+        remove_all(&src).await
+    })
+    .await
+}
+
+/// Remove file or dir recursively
+#[js_func]
+pub async fn fs_remove(path: String, cancel: Option<u32>) -> io::Result<()> {
+    cancellable(cancel, async move { remove_all(&PathBuf::from(path)).await }).await
+}
+
+#[derive(Clone)]
+pub struct FileHandle {
+    file: Arc<Mutex<Option<File>>>,
+}
+
+js_value!(FileHandle);
+
+fn opened_file(file: &mut Option<File>) -> io::Result<&mut File> {
+    file.as_mut()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "file closed"))
+}
+
+#[js_methods]
+impl FileHandle {
+    /// Open file with mode of `r`, `r+`, `w`, `w+`, `wx`, `a` or `a+`
+    #[js_func]
+    pub async fn open(path: String, mode: String, cancel: Option<u32>) -> io::Result<FileHandle> {
+        let mut options = OpenOptions::new();
+        match mode.as_str() {
+            "r" => options.read(true),
+            "r+" => options.read(true).write(true),
+            "w" => options.write(true).create(true).truncate(true),
+            "w+" => options.read(true).write(true).create(true).truncate(true),
+            "wx" => options.write(true).create_new(true),
+            "a" => options.append(true).create(true),
+            "a+" => options.read(true).append(true).create(true),
+            m => {
+                let msg = format!("invalid mode: {}", m);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+            }
+        };
+        let file = cancellable(cancel, options.open(path)).await?;
+        Ok(FileHandle {
+            file: Arc::new(Mutex::new(Some(file))),
+        })
+    }
+
+    /// Read at most `length` bytes from current position, empty result means end of file
+    #[js_func]
+    pub async fn read(
+        handle: FileHandle,
+        length: u32,
+        cancel: Option<u32>,
+    ) -> io::Result<JsBytes> {
+        cancellable(cancel, async move {
+            let mut file = handle.file.lock().await;
+            let mut buf = vec![0; length as usize];
+            let size = opened_file(&mut file)?.read(&mut buf).await?;
+            buf.truncate(size);
+            Ok(JsBytes(buf))
+        })
+        .await
+    }
+
+    /// Write all bytes at current position
+    #[js_func]
+    pub async fn write(
+        handle: FileHandle,
+        data: JsBytes,
+        cancel: Option<u32>,
+    ) -> io::Result<usize> {
+        cancellable(cancel, async move {
+            let mut file = handle.file.lock().await;
+            opened_file(&mut file)?.write_all(&data.0).await?;
+            Ok(data.0.len())
+        })
+        .await
+    }
+
+    /// Move position, whence is one of `start`, `current` and `end`.
+    /// Returns the new position from start.
+    #[js_func]
+    pub async fn seek(handle: FileHandle, offset: i64, whence: String) -> io::Result<u64> {
+        let pos = match whence.as_str() {
+            "start" => SeekFrom::Start(offset.max(0) as u64),
+            "current" => SeekFrom::Current(offset),
+            "end" => SeekFrom::End(offset),
+            w => {
+                let msg = format!("invalid whence: {}", w);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+            }
+        };
+        let mut file = handle.file.lock().await;
+        opened_file(&mut file)?.seek(pos).await
+    }
+
+    #[js_func]
+    pub async fn stat(handle: FileHandle) -> io::Result<JsPo<Stat>> {
+        let mut file = handle.file.lock().await;
+        let meta = opened_file(&mut file)?.metadata().await?;
+        Ok(JsPo::new(Stat {
+            size: meta.len(),
+            is_dir: meta.is_dir(),
+            is_file: meta.is_file(),
+        }))
+    }
+
+    #[js_func]
+    pub async fn truncate(handle: FileHandle, length: u64) -> io::Result<()> {
+        let mut file = handle.file.lock().await;
+        opened_file(&mut file)?.set_len(length).await
+    }
+
+    /// Flush and close file, later operations on the handle fail
+    #[js_func]
+    pub async fn close(handle: FileHandle) -> io::Result<()> {
+        let file = handle.file.lock().await.take();
+        if let Some(mut file) = file {
+            file.flush().await?;
+        }
+        Ok(())
+    }
+}
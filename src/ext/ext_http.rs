@@ -1,4 +1,5 @@
 use crate as deft;
+use crate::network::http_client_builder;
 use crate::{js_deserialize, js_serialize};
 use anyhow::Error;
 use deft_macros::js_methods;
@@ -35,7 +36,7 @@ js_deserialize!(UploadOptions);
 impl http {
     #[js_func]
     pub async fn request(url: String) -> Result<HttpResponse, Error> {
-        let rsp = reqwest::get(url).await?;
+        let rsp = http_client_builder()?.build()?.get(url).send().await?;
         let status = rsp.status().as_u16();
         let body = rsp.text().await?;
         Ok(HttpResponse { status, body })
@@ -58,7 +59,7 @@ impl http {
         }
         form = form.part(options.field.clone(), stream);
 
-        let client = reqwest::Client::new();
+        let client = http_client_builder()?.build()?;
         let rsp = client
             .post(url)
             .headers(headers)
@@ -0,0 +1,540 @@
+use crate as deft;
+use crate::event_loop::{create_event_loop_fn_mut, EventLoopFnMutCallback};
+use crate::js::ToJsValue;
+use crate::{js_deserialize, js_serialize, some_or_return};
+use anyhow::{anyhow, Error};
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use deft_macros::js_methods;
+use log::error;
+use quick_js::JsValue;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// request id => sender of result json
+static PENDING_REQUESTS: LazyLock<Mutex<HashMap<u64, oneshot::Sender<Result<String, String>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Notify purchases updated outside of requests, e.g. pending purchases completed
+static UPDATE_CALLBACK: Mutex<Option<EventLoopFnMutCallback<String>>> = Mutex::new(None);
+
+thread_local! {
+    static UPDATE_HANDLER: RefCell<Option<JsValue>> = RefCell::new(None);
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Product {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    /// Localized price, e.g. "$0.99"
+    pub price: String,
+    pub price_micros: i64,
+    pub currency: String,
+}
+
+js_serialize!(Product);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Purchase {
+    pub product_id: String,
+    /// Order id of google play or transaction id of app store
+    pub transaction_id: String,
+    /// Milliseconds since epoch
+    pub purchase_time: i64,
+    /// "purchased", "pending" or "restored"
+    pub state: String,
+    /// Purchase token of google play or JWS representation of the StoreKit 2 transaction of
+    /// app store, for verification
+    pub receipt: String,
+}
+
+js_serialize!(Purchase);
+js_deserialize!(Purchase);
+
+/// Send request to the store bridge of host, the result is delivered by `complete_request`.
+///
+/// On android the activity should implement `void iapRequest(long id, String method, String args)`
+/// with Play Billing and call `DeftActivity.iapResult0(id, success, json)`, on ios the app should
+/// export `deft_iap_request` with StoreKit and call `deft_iap_result`. Methods are
+/// `queryProducts`, `purchase`, `restore` and `finish`, args and results are json.
+/// The bridge is optional, requests fail when the host does not implement it.
+async fn request<T: DeserializeOwned>(method: &str, args: serde_json::Value) -> Result<T, Error> {
+    let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = oneshot::channel();
+    PENDING_REQUESTS.lock().unwrap().insert(id, sender);
+    if let Err(e) = platform::send_request(id, method, &args.to_string()) {
+        PENDING_REQUESTS.lock().unwrap().remove(&id);
+        return Err(e);
+    }
+    let result = receiver
+        .await
+        .map_err(|_| anyhow!("store request dropped"))?
+        .map_err(|e| anyhow!("store request failed: {}", e))?;
+    Ok(serde_json::from_str(&result)?)
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn complete_request(id: u64, result: Result<String, String>) {
+    let sender = some_or_return!(PENDING_REQUESTS.lock().unwrap().remove(&id));
+    let _ = sender.send(result);
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn notify_purchases_updated(purchases: String) {
+    if let Some(callback) = UPDATE_CALLBACK.lock().unwrap().as_mut() {
+        callback.call(purchases);
+    }
+}
+
+fn call_update_handler(purchases: &str) {
+    let handler = some_or_return!(UPDATE_HANDLER.with_borrow(|h| h.clone()));
+    let purchases = match serde_json::from_str::<Vec<Purchase>>(purchases) {
+        Ok(p) => p,
+        Err(e) => {
+            error!("Invalid purchases from store: {}", e);
+            return;
+        }
+    };
+    let result = purchases
+        .to_js_value()
+        .map_err(|e| anyhow!("{:?}", e))
+        .and_then(|p| {
+            handler
+                .call_as_function(vec![p])
+                .map_err(|e| anyhow!("{:?}", e))
+        });
+    if let Err(e) = result {
+        error!("Failed to call purchase update handler: {}", e);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyRequest<'a> {
+    platform: &'a str,
+    product_id: &'a str,
+    transaction_id: &'a str,
+    receipt: &'a str,
+}
+
+/// Decode payload of a JWS without verifying its signature, which is only safe for JWS
+/// fetched from the store over TLS
+fn decode_jws_payload(jws: &str) -> Result<serde_json::Value, Error> {
+    let mut parts = jws.split('.');
+    let payload = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(_), Some(payload), Some(_), None) => payload,
+        _ => return Err(anyhow!("invalid jws")),
+    };
+    let payload = BASE64_URL_SAFE_NO_PAD.decode(payload.trim_end_matches('='))?;
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+/// Percent-encode a value to be used as a single segment of url path
+fn encode_path_segment(value: &str) -> Result<String, Error> {
+    // dot segments are resolved by url parsers even when encoded
+    if value.is_empty() || value == "." || value == ".." {
+        return Err(anyhow!("invalid path segment: {:?}", value));
+    }
+    let mut result = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            result.push(b as char);
+        } else {
+            result.push_str(&format!("%{:02X}", b));
+        }
+    }
+    Ok(result)
+}
+
+/// Check decoded transaction of app store server api is for the expected app and product, and is
+/// not revoked or expired
+fn check_app_store_transaction(
+    transaction: &serde_json::Value,
+    bundle_id: &str,
+    product_id: &str,
+    now_millis: i64,
+) -> Result<(), Error> {
+    if transaction["bundleId"].as_str() != Some(bundle_id) {
+        return Err(anyhow!("transaction is not for app {}", bundle_id));
+    }
+    if transaction["productId"].as_str() != Some(product_id) {
+        return Err(anyhow!("transaction is not for product {}", product_id));
+    }
+    if let Some(date) = transaction["revocationDate"].as_i64() {
+        return Err(anyhow!("transaction is revoked at {}", date));
+    }
+    match transaction["expiresDate"].as_i64() {
+        Some(date) if date <= now_millis => Err(anyhow!("transaction is expired at {}", date)),
+        _ => Ok(()),
+    }
+}
+
+/// Check purchase of android publisher api is for the expected product and is completed
+fn check_play_purchase(purchase: &serde_json::Value, product_id: &str) -> Result<(), Error> {
+    // productId is omitted by some versions of the api, the path already selects the product
+    if let Some(id) = purchase["productId"].as_str() {
+        if id != product_id {
+            return Err(anyhow!("purchase is not for product {}", product_id));
+        }
+    }
+    // 0 is purchased, 1 is canceled and 2 is pending
+    match purchase["purchaseState"].as_i64() {
+        Some(0) => Ok(()),
+        state => Err(anyhow!("purchase is not completed, state: {:?}", state)),
+    }
+}
+
+/// Get transaction of app store with the App Store Server API, for servers of apps. Resolved with
+/// the decoded payload of signed transaction info. `token` is a JWT signed with an in-app purchase
+/// key of the app. Transactions not found on production are retried on the sandbox. Transactions
+/// of other apps or products than `bundle_id` and `product_id` are rejected.
+pub async fn verify_app_store_transaction(
+    transaction_id: &str,
+    bundle_id: &str,
+    product_id: &str,
+    token: &str,
+) -> Result<serde_json::Value, Error> {
+    const PRODUCTION_URL: &str = "https://api.storekit.itunes.apple.com";
+    const SANDBOX_URL: &str = "https://api.storekit-sandbox.itunes.apple.com";
+    let transaction_id = encode_path_segment(transaction_id)?;
+    let client = crate::network::http_client_builder()?.build()?;
+    let mut rsp = None;
+    for base_url in [PRODUCTION_URL, SANDBOX_URL] {
+        let url = format!("{}/inApps/v1/transactions/{}", base_url, transaction_id);
+        let r = client.get(url).bearer_auth(token).send().await?;
+        if r.status() != reqwest::StatusCode::NOT_FOUND {
+            rsp = Some(r);
+            break;
+        }
+    }
+    let rsp = rsp.ok_or_else(|| anyhow!("transaction not found"))?;
+    if !rsp.status().is_success() {
+        return Err(anyhow!("failed to get transaction: {}", rsp.status()));
+    }
+    let result: serde_json::Value = serde_json::from_str(&rsp.text().await?)?;
+    let signed = result["signedTransactionInfo"]
+        .as_str()
+        .ok_or_else(|| anyhow!("signed transaction info missing"))?;
+    let transaction = decode_jws_payload(signed)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+    check_app_store_transaction(&transaction, bundle_id, product_id, now)?;
+    Ok(transaction)
+}
+
+/// Get purchase of google play with the android publisher api, for servers of apps.
+/// `access_token` is an oauth token of a service account with access to the app.
+pub async fn verify_play_purchase(
+    package_name: &str,
+    product_id: &str,
+    token: &str,
+    access_token: &str,
+) -> Result<serde_json::Value, Error> {
+    let url = format!(
+        "https://androidpublisher.googleapis.com/androidpublisher/v3/applications/{}/purchases/products/{}/tokens/{}",
+        encode_path_segment(package_name)?,
+        encode_path_segment(product_id)?,
+        encode_path_segment(token)?
+    );
+    let client = crate::network::http_client_builder()?.build()?;
+    let rsp = client.get(url).bearer_auth(access_token).send().await?;
+    if !rsp.status().is_success() {
+        return Err(anyhow!("failed to get purchase: {}", rsp.status()));
+    }
+    let result: serde_json::Value = serde_json::from_str(&rsp.text().await?)?;
+    check_play_purchase(&result, product_id)?;
+    Ok(result)
+}
+
+#[allow(nonstandard_style)]
+pub struct iap;
+
+#[js_methods]
+impl iap {
+    /// Whether the host app implements the store bridge
+    #[js_func]
+    pub fn is_supported() -> bool {
+        platform::is_supported()
+    }
+
+    #[js_func]
+    pub async fn query_products(ids: Vec<String>) -> Result<Vec<Product>, Error> {
+        request("queryProducts", serde_json::json!({ "ids": ids })).await
+    }
+
+    /// Resolved when the store completes or defers the purchase, pending purchases are
+    /// delivered to the update handler when completed
+    #[js_func]
+    pub async fn purchase(product_id: String) -> Result<Purchase, Error> {
+        request("purchase", serde_json::json!({ "productId": product_id })).await
+    }
+
+    #[js_func]
+    pub async fn restore() -> Result<Vec<Purchase>, Error> {
+        request("restore", serde_json::json!({})).await
+    }
+
+    /// Acknowledge or consume a purchase after content is delivered, unfinished purchases are
+    /// refunded by google play and delivered again by app store
+    #[js_func]
+    pub async fn finish(purchase: Purchase, consume: bool) -> Result<(), Error> {
+        let args = serde_json::json!({ "purchase": purchase, "consume": consume });
+        let _: serde_json::Value = request("finish", args).await?;
+        Ok(())
+    }
+
+    /// Post purchase to the verification server of app, resolved with its json response.
+    /// The server should verify with the store, e.g. by `verify_app_store_transaction`.
+    #[js_func]
+    pub async fn verify(url: String, purchase: Purchase) -> Result<String, Error> {
+        let body = VerifyRequest {
+            platform: crate::platform::PLATFORM_NAME,
+            product_id: &purchase.product_id,
+            transaction_id: &purchase.transaction_id,
+            receipt: &purchase.receipt,
+        };
+        let client = crate::network::http_client_builder()?.build()?;
+        let rsp = client
+            .post(url)
+            .header("content-type", "application/json")
+            .body(serde_json::to_string(&body)?)
+            .send()
+            .await?;
+        if !rsp.status().is_success() {
+            return Err(anyhow!("verification failed: {}", rsp.status()));
+        }
+        Ok(rsp.text().await?)
+    }
+
+    /// Handler is called with purchases updated outside of purchase requests
+    #[js_func]
+    pub fn set_update_handler(handler: JsValue) {
+        let handler = match handler {
+            JsValue::Null | JsValue::Undefined => None,
+            h => Some(h),
+        };
+        let mut callback = UPDATE_CALLBACK.lock().unwrap();
+        if handler.is_some() && callback.is_none() {
+            callback.replace(create_event_loop_fn_mut(|purchases: String| {
+                call_update_handler(&purchases);
+            }));
+        }
+        UPDATE_HANDLER.set(handler);
+    }
+}
+
+#[cfg(target_os = "android")]
+mod platform {
+    use crate::android::{call_activity_method, has_activity_method, with_activity};
+    use crate::ext::ext_iap::{complete_request, notify_purchases_updated};
+    use anyhow::Error;
+    use jni::objects::{JClass, JString, JValue};
+    use jni::sys::{jboolean, jlong};
+    use jni::JNIEnv;
+    use log::error;
+
+    const REQUEST_METHOD: &str = "iapRequest";
+    const REQUEST_SIG: &str = "(JLjava/lang/String;Ljava/lang/String;)V";
+
+    pub fn is_supported() -> bool {
+        with_activity(|env, activity| {
+            Ok(has_activity_method(
+                env,
+                activity,
+                REQUEST_METHOD,
+                REQUEST_SIG,
+            ))
+        })
+        .unwrap_or(false)
+    }
+
+    pub fn send_request(id: u64, method: &str, args: &str) -> Result<(), Error> {
+        with_activity(|env, activity| {
+            let method = env.new_string(method)?;
+            let args = env.new_string(args)?;
+            let args = [
+                JValue::Long(id as i64),
+                JValue::Object(&method),
+                JValue::Object(&args),
+            ];
+            call_activity_method(env, activity, REQUEST_METHOD, REQUEST_SIG, &args)?.v()?;
+            Ok(())
+        })
+    }
+
+    fn get_string(env: &mut JNIEnv, value: &JString) -> String {
+        match env.get_string(value) {
+            Ok(s) => s.into(),
+            Err(e) => {
+                error!("Failed to get java string: {}", e);
+                String::new()
+            }
+        }
+    }
+
+    #[no_mangle]
+    pub extern "system" fn Java_deft_DeftActivity_iapResult0<'local>(
+        mut env: JNIEnv<'local>,
+        _class: JClass<'local>,
+        id: jlong,
+        success: jboolean,
+        result: JString<'local>,
+    ) {
+        let result = get_string(&mut env, &result);
+        let result = if success != 0 {
+            Ok(result)
+        } else {
+            Err(result)
+        };
+        complete_request(id as u64, result);
+    }
+
+    #[no_mangle]
+    pub extern "system" fn Java_deft_DeftActivity_iapPurchasesUpdated0<'local>(
+        mut env: JNIEnv<'local>,
+        _class: JClass<'local>,
+        purchases: JString<'local>,
+    ) {
+        notify_purchases_updated(get_string(&mut env, &purchases));
+    }
+}
+
+#[cfg(target_os = "ios")]
+mod platform {
+    use crate::ext::ext_iap::{complete_request, notify_purchases_updated};
+    use crate::platform::find_host_symbol;
+    use anyhow::{anyhow, Error};
+    use std::ffi::{c_char, CStr, CString};
+
+    /// `deft_iap_request` implemented by the app with StoreKit
+    type RequestFn = unsafe extern "C" fn(id: u64, method: *const c_char, args: *const c_char);
+
+    fn request_fn() -> Option<RequestFn> {
+        let ptr = find_host_symbol(c"deft_iap_request")?;
+        Some(unsafe { std::mem::transmute::<*mut std::ffi::c_void, RequestFn>(ptr) })
+    }
+
+    pub fn is_supported() -> bool {
+        request_fn().is_some()
+    }
+
+    pub fn send_request(id: u64, method: &str, args: &str) -> Result<(), Error> {
+        let request = request_fn()
+            .ok_or_else(|| anyhow!("deft_iap_request is not implemented by the app"))?;
+        let method = CString::new(method)?;
+        let args = CString::new(args)?;
+        unsafe { request(id, method.as_ptr(), args.as_ptr()) };
+        Ok(())
+    }
+
+    unsafe fn to_string(value: *const c_char) -> String {
+        if value.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(value).to_string_lossy().to_string()
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn deft_iap_result(id: u64, success: bool, result: *const c_char) {
+        let result = to_string(result);
+        complete_request(id, if success { Ok(result) } else { Err(result) });
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn deft_iap_purchases_updated(purchases: *const c_char) {
+        notify_purchases_updated(to_string(purchases));
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+mod platform {
+    use anyhow::{anyhow, Error};
+
+    pub fn is_supported() -> bool {
+        false
+    }
+
+    pub fn send_request(_id: u64, _method: &str, _args: &str) -> Result<(), Error> {
+        Err(anyhow!("in-app purchase is not supported on this platform"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::ext_iap::{
+        check_app_store_transaction, check_play_purchase, decode_jws_payload, encode_path_segment,
+        Purchase,
+    };
+    use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+    use base64::Engine;
+
+    #[test]
+    fn test_decode_jws_payload() {
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(r#"{"transactionId":"1000","price":990}"#);
+        let jws = format!("eyJhbGciOiJFUzI1NiJ9.{}.c2ln", payload);
+        let transaction = decode_jws_payload(&jws).unwrap();
+        assert_eq!(transaction["transactionId"], "1000");
+        assert_eq!(transaction["price"], 990);
+        assert!(decode_jws_payload("a.b").is_err());
+        assert!(decode_jws_payload("a.!!.c").is_err());
+    }
+
+    #[test]
+    fn test_check_transaction_state() {
+        let now = 1_700_000_000_000;
+        let check = |json: &str| {
+            let mut transaction: serde_json::Value = serde_json::from_str(json).unwrap();
+            if transaction.get("bundleId").is_none() {
+                transaction["bundleId"] = "com.example.app".into();
+            }
+            if transaction.get("productId").is_none() {
+                transaction["productId"] = "coins".into();
+            }
+            check_app_store_transaction(&transaction, "com.example.app", "coins", now)
+        };
+        assert!(check(r#"{"transactionId":"1"}"#).is_ok());
+        assert!(check(r#"{"expiresDate":1800000000000}"#).is_ok());
+        assert!(check(r#"{"expiresDate":1600000000000}"#).is_err());
+        assert!(check(r#"{"revocationDate":1600000000000}"#).is_err());
+        assert!(check(r#"{"bundleId":"com.example.other"}"#).is_err());
+        assert!(check(r#"{"productId":"gems"}"#).is_err());
+        assert!(check(r#"{"bundleId":null}"#).is_err());
+
+        let check = |json: &str| check_play_purchase(&serde_json::from_str(json).unwrap(), "coins");
+        assert!(check(r#"{"purchaseState":0}"#).is_ok());
+        assert!(check(r#"{"purchaseState":0,"productId":"coins"}"#).is_ok());
+        assert!(check(r#"{"purchaseState":0,"productId":"gems"}"#).is_err());
+        assert!(check(r#"{"purchaseState":2}"#).is_err());
+        assert!(check(r#"{}"#).is_err());
+    }
+
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(encode_path_segment("2000000123").unwrap(), "2000000123");
+        assert_eq!(encode_path_segment("a.B-c_d~").unwrap(), "a.B-c_d~");
+        assert_eq!(encode_path_segment("../1?x=#").unwrap(), "..%2F1%3Fx%3D%23");
+        assert_eq!(encode_path_segment("é").unwrap(), "%C3%A9");
+        assert!(encode_path_segment("").is_err());
+        assert!(encode_path_segment("..").is_err());
+    }
+
+    #[test]
+    fn test_parse_purchase() {
+        let purchases: Vec<Purchase> = serde_json::from_str(
+            r#"[{"productId":"coins","transactionId":"GPA.1","purchaseTime":1,"state":"pending","receipt":"token"}]"#,
+        )
+        .unwrap();
+        assert_eq!(purchases[0].product_id, "coins");
+        assert_eq!(purchases[0].state, "pending");
+    }
+}
@@ -0,0 +1,105 @@
+use crate as deft;
+use crate::js::js_engine::JsEngine;
+use crate::js::JsError;
+use crate::task_executor::TaskExecutor;
+use deft_macros::js_methods;
+use quick_js::JsValue;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+thread_local! {
+    static STORAGES: RefCell<HashMap<String, TaskExecutor<Session>>> = RefCell::new(HashMap::new());
+}
+
+struct Session {
+    conn: Result<Connection, String>,
+}
+
+/// Async key-value storage for bigger values, backed by sqlite
+#[allow(nonstandard_style)]
+pub struct largestorage {}
+
+#[js_methods]
+impl largestorage {
+    /// Open an in-memory database if path is None
+    pub fn init_namespace(namespace: &str, path: Option<PathBuf>) {
+        let executor = TaskExecutor::new(move || {
+            let conn = match path {
+                Some(path) => Connection::open(path),
+                None => Connection::open_in_memory(),
+            };
+            let conn = conn
+                .and_then(|c| {
+                    c.execute(
+                        "CREATE TABLE IF NOT EXISTS kv (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+                        (),
+                    )?;
+                    Ok(c)
+                })
+                .map_err(|e| format!("failed to open large storage, {}", e));
+            Session { conn }
+        });
+        STORAGES.with_borrow_mut(|m| m.insert(namespace.to_string(), executor));
+    }
+
+    #[js_func]
+    pub fn get(namespace: String, key: String) -> Result<JsValue, JsError> {
+        let executor = Self::get_executor(&namespace)?;
+        let (promise, resolver) = JsEngine::get().create_promise();
+        executor.run(move |sess| {
+            let r = sess.conn.as_ref().map_err(|e| e.clone()).and_then(|conn| {
+                conn.query_row("SELECT value FROM kv WHERE key = ?1", params![key], |r| {
+                    r.get::<_, String>(0)
+                })
+                .optional()
+                .map(|v| v.map(JsValue::String).unwrap_or(JsValue::Null))
+                .map_err(|e| format!("failed to read large storage, {}", e))
+            });
+            resolver.settle(r);
+        });
+        Ok(promise)
+    }
+
+    #[js_func]
+    pub fn set(namespace: String, key: String, value: String) -> Result<JsValue, JsError> {
+        let executor = Self::get_executor(&namespace)?;
+        let (promise, resolver) = JsEngine::get().create_promise();
+        executor.run(move |sess| {
+            let r = sess.conn.as_ref().map_err(|e| e.clone()).and_then(|conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO kv (key, value) VALUES (?1, ?2)",
+                    params![key, value],
+                )
+                .map(|_| JsValue::Undefined)
+                .map_err(|e| format!("failed to write large storage, {}", e))
+            });
+            resolver.settle(r);
+        });
+        Ok(promise)
+    }
+
+    #[js_func]
+    pub fn remove(namespace: String, key: String) -> Result<JsValue, JsError> {
+        let executor = Self::get_executor(&namespace)?;
+        let (promise, resolver) = JsEngine::get().create_promise();
+        executor.run(move |sess| {
+            let r = sess.conn.as_ref().map_err(|e| e.clone()).and_then(|conn| {
+                conn.execute("DELETE FROM kv WHERE key = ?1", params![key])
+                    .map(|_| JsValue::Undefined)
+                    .map_err(|e| format!("failed to write large storage, {}", e))
+            });
+            resolver.settle(r);
+        });
+        Ok(promise)
+    }
+
+    fn get_executor(namespace: &str) -> Result<TaskExecutor<Session>, JsError> {
+        STORAGES.with_borrow(|m| {
+            m.get(namespace)
+                .cloned()
+                .ok_or_else(|| JsError::from_str("large storage is not enabled"))
+        })
+    }
+}
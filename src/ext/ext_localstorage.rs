@@ -1,11 +1,13 @@
 use crate as deft;
+use crate::event_loop::create_event_loop_callback;
+use crate::ext::ext_window::WINDOWS;
 use crate::js::JsError;
+use crate::window::WindowStorageEvent;
 use anyhow::{anyhow, Error};
 use deft_macros::js_methods;
 use log::debug;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::ops::Deref;
 use std::path::PathBuf;
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
@@ -13,8 +15,18 @@ use std::thread;
 use std::thread::JoinHandle;
 use std::time::Duration;
 
+pub const DEFAULT_NAMESPACE: &str = "";
+
 thread_local! {
-    static DB: RefCell<Option<KVStorage>> = RefCell::new(None);
+    static DB: RefCell<HashMap<String, KVStorage>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Default, Clone)]
+pub struct StorageOptions {
+    /// Max bytes of keys and values, unlimited if None
+    pub quota: Option<usize>,
+    /// Keep data in memory only, nothing is written to disk
+    pub private: bool,
 }
 
 #[allow(nonstandard_style)]
@@ -23,57 +35,140 @@ pub struct localstorage {}
 #[js_methods]
 impl localstorage {
     pub fn init(path: PathBuf) {
+        Self::init_namespace(DEFAULT_NAMESPACE, Some(path), StorageOptions::default());
+    }
+
+    /// Path is ignored for private storage, and on web where localStorage of the page is used
+    pub fn init_namespace(namespace: &str, path: Option<PathBuf>, options: StorageOptions) {
+        let storage = match path {
+            #[cfg(not(emscripten_platform))]
+            Some(path) if !options.private => KVStorage::new(path, options.quota),
+            #[cfg(emscripten_platform)]
+            Some(_) if !options.private => KVStorage::new_web(namespace, options.quota),
+            _ => KVStorage::new_in_memory(options.quota),
+        };
         DB.with_borrow_mut(move |db| {
-            *db = Some(KVStorage::new(path));
+            db.insert(namespace.to_string(), storage);
         });
     }
 
     #[js_func]
     pub fn set(key: String, value: String) -> Result<(), JsError> {
-        let db = Self::get_storage()?;
-        db.set(key, value);
-        Ok(())
+        Self::set_item(DEFAULT_NAMESPACE.to_string(), key, value)
     }
 
     #[js_func]
     pub fn get(key: String) -> Result<Option<String>, JsError> {
-        let db = Self::get_storage()?;
+        Self::get_item(DEFAULT_NAMESPACE.to_string(), key)
+    }
+
+    #[js_func]
+    pub fn set_item(namespace: String, key: String, value: String) -> Result<(), JsError> {
+        let db = Self::get_storage(&namespace)?;
+        let old_value = db.set(key.clone(), Some(value.clone()))?;
+        notify_storage_changed(namespace, key, old_value, Some(value));
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn get_item(namespace: String, key: String) -> Result<Option<String>, JsError> {
+        let db = Self::get_storage(&namespace)?;
         Ok(db.get(key))
     }
 
+    #[js_func]
+    pub fn remove_item(namespace: String, key: String) -> Result<(), JsError> {
+        let db = Self::get_storage(&namespace)?;
+        let old_value = db.set(key.clone(), None)?;
+        if old_value.is_some() {
+            notify_storage_changed(namespace, key, old_value, None);
+        }
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn keys(namespace: String) -> Result<Vec<String>, JsError> {
+        let db = Self::get_storage(&namespace)?;
+        Ok(db.keys())
+    }
+
+    #[js_func]
+    pub fn usage(namespace: String) -> Result<usize, JsError> {
+        let db = Self::get_storage(&namespace)?;
+        Ok(db.usage())
+    }
+
     pub fn cleanup() -> Result<(), JsError> {
-        if let Ok(db) = Self::get_storage() {
+        let storages: Vec<KVStorage> = DB.with_borrow(|db| db.values().cloned().collect());
+        for db in storages {
             db.cleanup();
         }
         Ok(())
     }
 
-    fn get_storage() -> Result<KVStorage, JsError> {
-        DB.with(|db| {
-            if let Some(db) = db.borrow().deref() {
+    fn get_storage(namespace: &str) -> Result<KVStorage, JsError> {
+        DB.with_borrow(|db| {
+            if let Some(db) = db.get(namespace) {
                 Ok(db.clone())
-            } else {
+            } else if namespace == DEFAULT_NAMESPACE {
                 Err(JsError::from_str("localstorage is not enabled"))
+            } else {
+                Err(JsError::new(format!(
+                    "localstorage namespace is not enabled: {}",
+                    namespace
+                )))
             }
         })
     }
 }
 
+fn notify_storage_changed(
+    namespace: String,
+    key: String,
+    old_value: Option<String>,
+    new_value: Option<String>,
+) {
+    create_event_loop_callback(move || {
+        let windows: Vec<_> = WINDOWS.with_borrow(|m| m.values().cloned().collect());
+        for w in windows {
+            if let Ok(mut w) = w.upgrade_mut() {
+                w.emit(WindowStorageEvent {
+                    namespace: namespace.clone(),
+                    key: key.clone(),
+                    old_value: old_value.clone(),
+                    new_value: new_value.clone(),
+                });
+            }
+        }
+    })
+    .call();
+}
+
 enum KVMsg {
-    Write((String, String)),
+    /// Value is None if removed
+    Write((String, Option<String>)),
     Cleanup,
 }
 
+struct KVData {
+    entries: HashMap<String, String>,
+    usage: usize,
+}
+
 #[derive(Clone)]
 struct KVStorage {
-    path: PathBuf,
-    data: Arc<Mutex<HashMap<String, String>>>,
-    sender: Sender<KVMsg>,
+    path: Option<PathBuf>,
+    data: Arc<Mutex<KVData>>,
+    quota: Option<usize>,
+    sender: Option<Sender<KVMsg>>,
     write_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Key prefix in window.localStorage, where entries are written through
+    #[cfg(emscripten_platform)]
+    web_prefix: Option<String>,
 }
 
 impl KVStorage {
-    fn new(dir: PathBuf) -> Self {
+    fn new(dir: PathBuf, quota: Option<usize>) -> Self {
         let db = Self::open_db(&dir).expect("failed to open localstorage");
         let mut data = HashMap::new();
         for e in db.iter() {
@@ -110,7 +205,10 @@ impl KVStorage {
                 if !list.is_empty() {
                     let db = Self::open_db(&dir).expect("failed to open localstorage");
                     for (k, v) in list.iter() {
-                        db.insert(k, v.as_bytes()).unwrap();
+                        match v {
+                            Some(v) => db.insert(k, v.as_bytes()).unwrap(),
+                            None => db.remove(k).unwrap(),
+                        };
                     }
                     db.flush().expect("failed to flush localstorage");
                     debug!("localstorage flushed");
@@ -123,26 +221,93 @@ impl KVStorage {
         };
 
         Self {
-            path: dir,
-            data: Arc::new(Mutex::new(data)),
-            sender,
+            path: Some(dir),
+            data: Arc::new(Mutex::new(KVData::new(data))),
+            quota,
+            sender: Some(sender),
             write_handle: Arc::new(Mutex::new(Some(write_handle))),
+            #[cfg(emscripten_platform)]
+            web_prefix: None,
+        }
+    }
+
+    fn new_in_memory(quota: Option<usize>) -> Self {
+        Self {
+            path: None,
+            data: Arc::new(Mutex::new(KVData::new(HashMap::new()))),
+            quota,
+            sender: None,
+            write_handle: Arc::new(Mutex::new(None)),
+            #[cfg(emscripten_platform)]
+            web_prefix: None,
         }
     }
 
-    fn set(&self, key: String, value: String) {
+    /// Backed by localStorage of the page, keys are prefixed with namespace
+    #[cfg(emscripten_platform)]
+    fn new_web(namespace: &str, quota: Option<usize>) -> Self {
+        let web_prefix = format!("deft:{}:", namespace);
+        let entries = crate::platform::web_storage_entries(&web_prefix);
+        Self {
+            path: None,
+            data: Arc::new(Mutex::new(KVData::new(entries))),
+            quota,
+            sender: None,
+            write_handle: Arc::new(Mutex::new(None)),
+            web_prefix: Some(web_prefix),
+        }
+    }
+
+    /// Return old value
+    fn set(&self, key: String, value: Option<String>) -> Result<Option<String>, JsError> {
         let mut data = self.data.lock().expect("failed to lock localstorage");
-        data.insert(key.clone(), value.clone());
-        self.sender.send(KVMsg::Write((key, value))).unwrap();
+        let old_size = data.entries.get(&key).map(|v| key.len() + v.len());
+        let new_size = value.as_ref().map(|v| key.len() + v.len());
+        let usage = data.usage - old_size.unwrap_or(0) + new_size.unwrap_or(0);
+        if let Some(quota) = self.quota {
+            if new_size > old_size && usage > quota {
+                return Err(JsError::new(format!(
+                    "QuotaExceededError: localstorage quota exceeded, quota={}, required={}",
+                    quota, usage
+                )));
+            }
+        }
+        data.usage = usage;
+        let old_value = match &value {
+            Some(v) => data.entries.insert(key.clone(), v.clone()),
+            None => data.entries.remove(&key),
+        };
+        #[cfg(emscripten_platform)]
+        if let Some(prefix) = &self.web_prefix {
+            crate::platform::web_storage_set(&format!("{}{}", prefix, key), value.as_deref());
+        }
+        if let Some(sender) = &self.sender {
+            sender.send(KVMsg::Write((key, value))).unwrap();
+        }
+        Ok(old_value)
     }
 
     fn get(&self, key: String) -> Option<String> {
         let data = self.data.lock().expect("failed to lock localstorage");
-        data.get(&key).cloned()
+        data.entries.get(&key).cloned()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let data = self.data.lock().expect("failed to lock localstorage");
+        data.entries.keys().cloned().collect()
+    }
+
+    fn usage(&self) -> usize {
+        let data = self.data.lock().expect("failed to lock localstorage");
+        data.usage
     }
 
     fn cleanup(&self) {
-        self.sender.send(KVMsg::Cleanup).unwrap();
+        let sender = match &self.sender {
+            Some(sender) => sender,
+            None => return,
+        };
+        sender.send(KVMsg::Cleanup).unwrap();
         let write_handle = self.write_handle.clone();
         let mut write_handle = write_handle.lock().unwrap();
         if let Some(handle) = write_handle.take() {
@@ -160,3 +325,26 @@ impl KVStorage {
         Err(anyhow!("failed to open localstorage"))
     }
 }
+
+impl KVData {
+    fn new(entries: HashMap<String, String>) -> Self {
+        let usage = entries.iter().map(|(k, v)| k.len() + v.len()).sum();
+        Self { entries, usage }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::ext_localstorage::KVStorage;
+
+    #[test]
+    fn test_quota() {
+        let storage = KVStorage::new_in_memory(Some(10));
+        assert!(storage.set("a".to_string(), Some("1234".to_string())).is_ok());
+        assert!(storage.set("b".to_string(), Some("123456".to_string())).is_err());
+        assert_eq!(storage.usage(), 5);
+        assert!(storage.set("a".to_string(), None).is_ok());
+        assert!(storage.set("b".to_string(), Some("123456".to_string())).is_ok());
+        assert_eq!(storage.get("b".to_string()), Some("123456".to_string()));
+    }
+}
@@ -0,0 +1,599 @@
+use crate as deft;
+use crate::base::{Event, EventHandler, EventRegistration};
+use crate::menu::StandardMenuItem;
+use crate::window::{Window, WindowHandle};
+use crate::{js_deserialize, js_value, ok_or_return};
+use anyhow::{anyhow, Error};
+use deft_macros::{js_methods, mrc_object};
+use log::debug;
+use quick_js::JsValue;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+thread_local! {
+    static NEXT_MENU_ID: Cell<u32> = Cell::new(1);
+    /// Menus built with native items, native events are dispatched to them by id
+    static NATIVE_MENUS: RefCell<HashMap<u32, MenuWeak>> = RefCell::new(HashMap::new());
+    /// window id => menubar
+    static MENUBARS: RefCell<HashMap<i32, Menu>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuKind {
+    Standard,
+    Checkmark,
+    Separator,
+    Submenu,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuItemSpec {
+    /// Passed to menuselect listeners
+    pub id: Option<String>,
+    /// "standard", "checkmark", "separator" or "submenu", inferred from other fields if missing
+    pub kind: Option<String>,
+    pub label: Option<String>,
+    /// e.g. "CmdOrCtrl+Shift+S"
+    pub accelerator: Option<String>,
+    pub enabled: Option<bool>,
+    pub checked: Option<bool>,
+    pub items: Option<Vec<MenuItemSpec>>,
+}
+
+js_deserialize!(MenuItemSpec);
+
+impl MenuItemSpec {
+    pub fn kind(&self) -> Result<MenuKind, Error> {
+        let kind = match self.kind.as_deref() {
+            Some("standard") => MenuKind::Standard,
+            Some("checkmark") => MenuKind::Checkmark,
+            Some("separator") => MenuKind::Separator,
+            Some("submenu") => MenuKind::Submenu,
+            Some(k) => return Err(anyhow!("invalid menu kind: {}", k)),
+            None if self.items.is_some() => MenuKind::Submenu,
+            None if self.checked.is_some() => MenuKind::Checkmark,
+            None => MenuKind::Standard,
+        };
+        Ok(kind)
+    }
+
+    fn children(&self) -> &[MenuItemSpec] {
+        self.items.as_deref().unwrap_or_default()
+    }
+}
+
+/// Find item by id in nested items
+fn find_item<'a>(items: &'a mut [MenuItemSpec], id: &str) -> Option<&'a mut MenuItemSpec> {
+    for item in items {
+        if item.id.as_deref() == Some(id) {
+            return Some(item);
+        }
+        if let Some(found) = item.items.as_mut().and_then(|c| find_item(c, id)) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn validate_items(items: &[MenuItemSpec]) -> Result<(), Error> {
+    for item in items {
+        if item.kind()? != MenuKind::Submenu && item.items.is_some() {
+            return Err(anyhow!("only submenu could contain items"));
+        }
+        validate_items(item.children())?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuSelectDetail {
+    pub id: Option<String>,
+    /// New state of checkmark items
+    pub checked: Option<bool>,
+}
+
+#[mrc_object]
+pub struct Menu {
+    id: u32,
+    items: Vec<MenuItemSpec>,
+    event_registration: EventRegistration<Menu>,
+    /// None if native menus are not supported, element popups are used instead
+    native: Option<native::NativeMenu>,
+}
+
+js_value!(Menu);
+
+#[js_methods]
+impl Menu {
+    /// Create a menu shown by native widgets if supported
+    #[js_func]
+    pub fn create(items: Vec<MenuItemSpec>) -> Result<Menu, Error> {
+        validate_items(&items)?;
+        let id = NEXT_MENU_ID.get();
+        NEXT_MENU_ID.set(id + 1);
+        let native = match native::NativeMenu::new(id, &items) {
+            Ok(native) => Some(native),
+            Err(e) => {
+                debug!("native menu unavailable: {}", e);
+                None
+            }
+        };
+        let menu = MenuData {
+            id,
+            items,
+            event_registration: EventRegistration::new(),
+            native,
+        }
+        .to_ref();
+        if menu.native.is_some() {
+            NATIVE_MENUS.with_borrow_mut(|m| {
+                m.retain(|_, w| w.upgrade().is_ok());
+                m.insert(id, menu.as_weak());
+            });
+        }
+        Ok(menu)
+    }
+
+    /// Show as context menu at position relative to window
+    #[js_func]
+    pub fn popup(&self, window: WindowHandle, x: f32, y: f32) -> Result<(), Error> {
+        let window = window.upgrade_mut()?;
+        if let Some(native) = &self.native {
+            return native.popup(&window, x, y);
+        }
+        window.popup_menu(self.build_element_menu(&self.items), x, y);
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn set_checked(&mut self, id: String, checked: bool) -> Result<(), Error> {
+        let item = find_item(&mut self.items, &id).ok_or_else(|| anyhow!("no item: {}", id))?;
+        item.checked = Some(checked);
+        if let Some(native) = &self.native {
+            native.set_checked(&id, checked);
+        }
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn set_enabled(&mut self, id: String, enabled: bool) -> Result<(), Error> {
+        let item = find_item(&mut self.items, &id).ok_or_else(|| anyhow!("no item: {}", id))?;
+        item.enabled = Some(enabled);
+        if let Some(native) = &self.native {
+            native.set_enabled(&id, enabled);
+        }
+        Ok(())
+    }
+
+    pub fn add_event_listener(
+        &mut self,
+        event_type: String,
+        handler: Box<EventHandler<Menu>>,
+    ) -> u32 {
+        self.event_registration
+            .add_event_listener(&event_type, handler)
+    }
+
+    #[js_func]
+    pub fn remove_event_listener(&mut self, event_type: String, id: i32) {
+        self.event_registration
+            .remove_event_listener(&event_type, id as u32);
+    }
+
+    #[js_func]
+    pub fn bind_event(&mut self, event_name: String, callback: JsValue) -> u32 {
+        self.event_registration
+            .add_js_event_listener(&event_name, callback) as u32
+    }
+
+    fn emit_select(&mut self, detail: MenuSelectDetail) {
+        let mut event = Event::new("menuselect", detail, self.clone());
+        self.event_registration.emit_event(&mut event);
+    }
+
+    /// Build menu rendered by elements for platforms without native menus
+    fn build_element_menu(&self, items: &[MenuItemSpec]) -> crate::menu::Menu {
+        let mut menu = crate::menu::Menu::new();
+        for item in items {
+            let label = item.label.clone().unwrap_or_default();
+            match item.kind().unwrap_or(MenuKind::Standard) {
+                MenuKind::Separator => menu.add_separator(),
+                MenuKind::Submenu => {
+                    menu.add_submenu(&label, self.build_element_menu(item.children()));
+                }
+                kind => {
+                    let weak = self.as_weak();
+                    let id = item.id.clone();
+                    let checkable = kind == MenuKind::Checkmark;
+                    let mut standard_item = StandardMenuItem::new(&label, move || {
+                        let mut menu = ok_or_return!(weak.upgrade());
+                        let mut checked = None;
+                        if let Some(item) = checkable
+                            .then_some(id.as_deref())
+                            .flatten()
+                            .and_then(|id| find_item(&mut menu.items, id))
+                        {
+                            let value = !item.checked.unwrap_or(false);
+                            item.checked = Some(value);
+                            checked = Some(value);
+                        }
+                        menu.emit_select(MenuSelectDetail {
+                            id: id.clone(),
+                            checked,
+                        });
+                    });
+                    standard_item.disabled = !item.enabled.unwrap_or(true);
+                    standard_item.accelerator = item.accelerator.clone();
+                    if checkable {
+                        standard_item.checked = Some(item.checked.unwrap_or(false));
+                    }
+                    menu.add_standard_item(standard_item);
+                }
+            }
+        }
+        menu
+    }
+}
+
+/// Called on the main thread with id of native menu item
+#[cfg(all(feature = "menu", any(target_os = "windows", target_os = "macos")))]
+fn dispatch_native_event(native_id: &str) {
+    use crate::some_or_return;
+    let menu_id = some_or_return!(native::parse_menu_id(native_id));
+    let menu = NATIVE_MENUS.with_borrow(|m| m.get(&menu_id).and_then(|w| w.upgrade().ok()));
+    let mut menu = some_or_return!(menu);
+    let resolved = menu.native.as_ref().and_then(|n| n.resolve(native_id));
+    let (id, checked) = some_or_return!(resolved);
+    if let (Some(id), Some(checked)) = (&id, checked) {
+        if let Some(item) = find_item(&mut menu.items, id) {
+            item.checked = Some(checked);
+        }
+    }
+    menu.emit_select(MenuSelectDetail { id, checked });
+}
+
+/// Show menu as native menubar of window, or remove the menubar if menu is None.
+/// On macos the menubar is shared by the app, top level items should be submenus.
+/// Linux is not supported since windows are not created by gtk, menubars should be rendered
+/// with elements there.
+pub fn set_window_menu(window: &Window, menu: Option<Menu>) -> Result<(), Error> {
+    let window_id = window.get_id();
+    if let Some(old) = MENUBARS.with_borrow_mut(|m| m.remove(&window_id)) {
+        if let Some(native) = &old.native {
+            native.detach(window)?;
+        }
+    }
+    let menu = match menu {
+        Some(menu) => menu,
+        None => return Ok(()),
+    };
+    let native = menu.native.as_ref().ok_or_else(|| {
+        anyhow!("native menubar is not supported on this platform, render it with elements instead")
+    })?;
+    native.attach(window)?;
+    MENUBARS.with_borrow_mut(|m| m.insert(window_id, menu));
+    Ok(())
+}
+
+/// Detach and release the menubar of a closing window
+pub fn remove_window_menu(window: &Window) {
+    if let Err(e) = set_window_menu(window, None) {
+        debug!("failed to detach menubar: {}", e);
+    }
+}
+
+/// Translate accelerators of menubars, called by the event loop with each message
+#[cfg(all(feature = "menu", target_os = "windows"))]
+pub fn translate_accelerator(msg: *const std::ffi::c_void) -> bool {
+    MENUBARS.with_borrow(|m| {
+        m.values()
+            .filter_map(|menu| menu.native.as_ref())
+            .any(|native| native.translate_accelerator(msg))
+    })
+}
+
+#[cfg(all(feature = "menu", any(target_os = "windows", target_os = "macos")))]
+mod native {
+    use crate::event_loop::create_event_loop_fn_mut;
+    use crate::ext::ext_menu::{dispatch_native_event, MenuItemSpec, MenuKind};
+    use crate::window::Window;
+    use anyhow::{anyhow, Error};
+    use muda::accelerator::Accelerator;
+    use muda::dpi::{LogicalPosition, Position};
+    #[cfg(not(feature = "tray"))]
+    use muda::MenuEvent;
+    use muda::{CheckMenuItem, ContextMenu, IsMenuItem, MenuItem, PredefinedMenuItem, Submenu};
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    #[cfg(target_os = "windows")]
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::{Mutex, Once};
+
+    const ID_PREFIX: &str = "deft-menu:";
+
+    static INSTALL_EVENT_HANDLER: Once = Once::new();
+
+    enum NativeItem {
+        Standard(MenuItem),
+        Checkmark(CheckMenuItem),
+        Submenu(Submenu),
+    }
+
+    pub struct NativeMenu {
+        menu: muda::Menu,
+        /// native id => (item id, item)
+        items: HashMap<String, (Option<String>, NativeItem)>,
+        /// Window showing this menu as menubar
+        #[cfg(target_os = "windows")]
+        hwnd: Cell<isize>,
+    }
+
+    pub fn parse_menu_id(native_id: &str) -> Option<u32> {
+        let (menu_id, _) = native_id.strip_prefix(ID_PREFIX)?.split_once(':')?;
+        menu_id.parse().ok()
+    }
+
+    fn install_event_handler() {
+        INSTALL_EVENT_HANDLER.call_once(|| {
+            let callback = Mutex::new(create_event_loop_fn_mut(|id: String| {
+                dispatch_native_event(&id);
+            }));
+            let handler = move |id: &str| {
+                if id.starts_with(ID_PREFIX) {
+                    callback.lock().unwrap().call(id.to_string());
+                }
+            };
+            #[cfg(feature = "tray")]
+            deft_tray::set_unhandled_menu_event_handler(Box::new(handler));
+            #[cfg(not(feature = "tray"))]
+            MenuEvent::set_event_handler(Some(move |e: MenuEvent| handler(&e.id.0)));
+        });
+    }
+
+    fn parse_accelerator(accelerator: &Option<String>) -> Result<Option<Accelerator>, Error> {
+        let parse = |a: &str| {
+            Accelerator::from_str(a).map_err(|e| anyhow!("invalid accelerator {}: {}", a, e))
+        };
+        accelerator.as_deref().map(parse).transpose()
+    }
+
+    impl NativeMenu {
+        pub fn new(menu_id: u32, items: &[MenuItemSpec]) -> Result<Self, Error> {
+            install_event_handler();
+            let mut native = Self {
+                menu: muda::Menu::new(),
+                items: HashMap::new(),
+                #[cfg(target_os = "windows")]
+                hwnd: Cell::new(0),
+            };
+            let built = native.build_items(menu_id, items)?;
+            let refs: Vec<&dyn IsMenuItem> = built.iter().map(|b| b.as_ref()).collect();
+            native.menu.append_items(&refs)?;
+            Ok(native)
+        }
+
+        fn build_items(
+            &mut self,
+            menu_id: u32,
+            items: &[MenuItemSpec],
+        ) -> Result<Vec<Box<dyn IsMenuItem>>, Error> {
+            let mut result: Vec<Box<dyn IsMenuItem>> = Vec::new();
+            for item in items {
+                let kind = item.kind()?;
+                if kind == MenuKind::Separator {
+                    result.push(Box::new(PredefinedMenuItem::separator()));
+                    continue;
+                }
+                let native_id = format!("{}{}:{}", ID_PREFIX, menu_id, self.items.len());
+                let label = item.label.clone().unwrap_or_default();
+                let enabled = item.enabled.unwrap_or(true);
+                let accelerator = parse_accelerator(&item.accelerator)?;
+                let native_item = match kind {
+                    MenuKind::Checkmark => {
+                        let checked = item.checked.unwrap_or(false);
+                        let it = CheckMenuItem::with_id(
+                            native_id.clone(),
+                            label,
+                            enabled,
+                            checked,
+                            accelerator,
+                        );
+                        result.push(Box::new(it.clone()));
+                        NativeItem::Checkmark(it)
+                    }
+                    MenuKind::Submenu => {
+                        let it = Submenu::with_id(native_id.clone(), label, enabled);
+                        // Reserve the id before children are numbered
+                        self.items
+                            .insert(native_id.clone(), (None, NativeItem::Submenu(it.clone())));
+                        let children = self.build_items(menu_id, item.children())?;
+                        let refs: Vec<&dyn IsMenuItem> =
+                            children.iter().map(|b| b.as_ref()).collect();
+                        it.append_items(&refs)?;
+                        result.push(Box::new(it.clone()));
+                        NativeItem::Submenu(it)
+                    }
+                    _ => {
+                        let it = MenuItem::with_id(native_id.clone(), label, enabled, accelerator);
+                        result.push(Box::new(it.clone()));
+                        NativeItem::Standard(it)
+                    }
+                };
+                self.items.insert(native_id, (item.id.clone(), native_item));
+            }
+            Ok(result)
+        }
+
+        fn find(&self, id: &str) -> Option<&NativeItem> {
+            self.items
+                .values()
+                .find(|(item_id, _)| item_id.as_deref() == Some(id))
+                .map(|(_, item)| item)
+        }
+
+        /// Return item id and checked state of native item
+        pub fn resolve(&self, native_id: &str) -> Option<(Option<String>, Option<bool>)> {
+            let (id, item) = self.items.get(native_id)?;
+            let checked = match item {
+                NativeItem::Checkmark(it) => Some(it.is_checked()),
+                _ => None,
+            };
+            Some((id.clone(), checked))
+        }
+
+        pub fn set_checked(&self, id: &str, checked: bool) {
+            if let Some(NativeItem::Checkmark(it)) = self.find(id) {
+                it.set_checked(checked);
+            }
+        }
+
+        pub fn set_enabled(&self, id: &str, enabled: bool) {
+            match self.find(id) {
+                Some(NativeItem::Standard(it)) => it.set_enabled(enabled),
+                Some(NativeItem::Checkmark(it)) => it.set_enabled(enabled),
+                Some(NativeItem::Submenu(it)) => it.set_enabled(enabled),
+                None => {}
+            }
+        }
+
+        pub fn popup(&self, window: &Window, x: f32, y: f32) -> Result<(), Error> {
+            let position = Some(Position::Logical(LogicalPosition::new(x as f64, y as f64)));
+            match window.window.raw_window_handle()? {
+                #[cfg(target_os = "windows")]
+                RawWindowHandle::Win32(h) => unsafe {
+                    self.menu.show_context_menu_for_hwnd(h.hwnd.get(), position);
+                },
+                #[cfg(target_os = "macos")]
+                RawWindowHandle::AppKit(h) => unsafe {
+                    self.menu
+                        .show_context_menu_for_nsview(h.ns_view.as_ptr(), position);
+                },
+                _ => return Err(anyhow!("unsupported window handle")),
+            }
+            Ok(())
+        }
+
+        pub fn attach(&self, window: &Window) -> Result<(), Error> {
+            #[cfg(target_os = "windows")]
+            if let RawWindowHandle::Win32(h) = window.window.raw_window_handle()? {
+                unsafe { self.menu.init_for_hwnd(h.hwnd.get())? };
+                self.hwnd.set(h.hwnd.get());
+            }
+            #[cfg(target_os = "macos")]
+            {
+                let _ = window;
+                self.menu.init_for_nsapp();
+            }
+            Ok(())
+        }
+
+        pub fn detach(&self, window: &Window) -> Result<(), Error> {
+            #[cfg(target_os = "windows")]
+            if let RawWindowHandle::Win32(h) = window.window.raw_window_handle()? {
+                unsafe { self.menu.remove_for_hwnd(h.hwnd.get())? };
+                self.hwnd.set(0);
+            }
+            #[cfg(target_os = "macos")]
+            {
+                let _ = window;
+                self.menu.remove_for_nsapp();
+            }
+            Ok(())
+        }
+
+        #[cfg(target_os = "windows")]
+        pub fn translate_accelerator(&self, msg: *const std::ffi::c_void) -> bool {
+            use windows::Win32::UI::WindowsAndMessaging::{TranslateAcceleratorW, HACCEL, MSG};
+            let msg = msg as *const MSG;
+            unsafe {
+                let hwnd = (*msg).hwnd;
+                if hwnd.0 as isize != self.hwnd.get() {
+                    return false;
+                }
+                TranslateAcceleratorW(hwnd, HACCEL(self.menu.haccel() as _), msg) != 0
+            }
+        }
+    }
+}
+
+#[cfg(not(all(feature = "menu", any(target_os = "windows", target_os = "macos"))))]
+mod native {
+    use crate::ext::ext_menu::MenuItemSpec;
+    use crate::window::Window;
+    use anyhow::{anyhow, Error};
+
+    /// Placeholder of platforms without native menus, never created
+    pub struct NativeMenu;
+
+    impl NativeMenu {
+        pub fn new(_menu_id: u32, _items: &[MenuItemSpec]) -> Result<Self, Error> {
+            Err(anyhow!("native menus are not supported"))
+        }
+
+        pub fn set_checked(&self, _id: &str, _checked: bool) {}
+
+        pub fn set_enabled(&self, _id: &str, _enabled: bool) {}
+
+        pub fn popup(&self, _window: &Window, _x: f32, _y: f32) -> Result<(), Error> {
+            Err(anyhow!("native menus are not supported"))
+        }
+
+        pub fn attach(&self, _window: &Window) -> Result<(), Error> {
+            Err(anyhow!("native menus are not supported"))
+        }
+
+        pub fn detach(&self, _window: &Window) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::ext_menu::{find_item, validate_items, MenuItemSpec, MenuKind};
+
+    fn item(id: &str) -> MenuItemSpec {
+        MenuItemSpec {
+            id: Some(id.to_string()),
+            label: Some(id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_kind_and_find() {
+        let mut items = vec![
+            item("open"),
+            MenuItemSpec {
+                items: Some(vec![
+                    MenuItemSpec {
+                        checked: Some(true),
+                        ..item("wrap")
+                    },
+                    MenuItemSpec {
+                        kind: Some("separator".to_string()),
+                        ..Default::default()
+                    },
+                ]),
+                ..item("view")
+            },
+        ];
+        assert!(validate_items(&items).is_ok());
+        assert_eq!(MenuKind::Standard, items[0].kind().unwrap());
+        assert_eq!(MenuKind::Submenu, items[1].kind().unwrap());
+        let wrap = find_item(&mut items, "wrap").unwrap();
+        assert_eq!(MenuKind::Checkmark, wrap.kind().unwrap());
+        assert!(find_item(&mut items, "missing").is_none());
+
+        let invalid = vec![MenuItemSpec {
+            kind: Some("standard".to_string()),
+            items: Some(vec![]),
+            ..Default::default()
+        }];
+        assert!(validate_items(&invalid).is_err());
+    }
+}
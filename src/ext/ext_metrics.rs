@@ -0,0 +1,56 @@
+use crate as deft;
+use crate::js::JsError;
+use crate::metrics::{LogExporter, StatsdExporter};
+use deft_macros::js_methods;
+
+#[allow(nonstandard_style)]
+pub struct metrics {}
+
+#[js_methods]
+impl metrics {
+    #[js_func]
+    pub fn increment(name: String, value: f64) {
+        crate::metrics::increment(&name, value);
+    }
+
+    #[js_func]
+    pub fn timing(name: String, millis: f64) {
+        crate::metrics::timing(&name, millis);
+    }
+
+    #[js_func]
+    pub fn is_enabled() -> bool {
+        crate::metrics::is_enabled()
+    }
+
+    #[js_func]
+    pub fn flush() {
+        crate::metrics::flush();
+    }
+
+    #[js_func]
+    pub fn add_log_exporter() {
+        crate::metrics::add_exporter(LogExporter);
+    }
+
+    #[js_func]
+    pub fn add_statsd_exporter(address: String, prefix: String) -> Result<(), JsError> {
+        let exporter = StatsdExporter::new(&address, &prefix)?;
+        crate::metrics::add_exporter(exporter);
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn add_http_exporter(url: String) -> Result<(), JsError> {
+        #[cfg(feature = "http")]
+        {
+            crate::metrics::add_exporter(crate::metrics::HttpBatchExporter::new(&url));
+            Ok(())
+        }
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = url;
+            Err(JsError::from_str("http feature is not enabled"))
+        }
+    }
+}
@@ -0,0 +1,695 @@
+use crate as deft;
+use crate::network::http_client_builder;
+use crate::{js_deserialize, js_serialize, some_or_return};
+use anyhow::{anyhow, Error};
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use deft_macros::js_methods;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const DEFAULT_LOOPBACK_PATH: &str = "/callback";
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+const LOOPBACK_READ_TIMEOUT_SECS: u64 = 10;
+const LOOPBACK_PAGE: &str = "<!DOCTYPE html><html><body>\
+    <p>Authorization finished, you can close this page and return to the app.</p>\
+    </body></html>";
+
+/// state => sender of redirect params, for redirects to custom schemes
+static PENDING_REDIRECTS: LazyLock<
+    Mutex<HashMap<String, oneshot::Sender<HashMap<String, String>>>>,
+> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthConfig {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub client_id: String,
+    /// Only for confidential clients, native apps should rely on PKCE instead
+    pub client_secret: Option<String>,
+    pub scopes: Option<Vec<String>>,
+    /// Loopback uri like `http://127.0.0.1:8765/callback` or custom scheme uri like
+    /// `com.example.app:/oauth`, defaults to a loopback uri with a free port
+    pub redirect_uri: Option<String>,
+    /// Extra params of the authorization request, e.g. `prompt` or `login_hint`
+    pub params: Option<HashMap<String, String>>,
+    /// Store refresh token in secure storage under this account, on android the activity should
+    /// implement `secureStorageGet`, `secureStorageSet` and `secureStorageDelete`
+    pub account: Option<String>,
+    /// Seconds to wait for the redirect, default is 300
+    pub timeout: Option<u64>,
+}
+
+js_deserialize!(OAuthConfig);
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: Option<String>,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+    id_token: Option<String>,
+    scope: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub token_type: Option<String>,
+    /// Milliseconds since epoch
+    pub expires_at: Option<u64>,
+    pub refresh_token: Option<String>,
+    pub id_token: Option<String>,
+    pub scope: Option<String>,
+}
+
+js_serialize!(OAuthTokens);
+
+impl OAuthTokens {
+    fn from_response(response: TokenResponse) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Self {
+            access_token: response.access_token,
+            token_type: response.token_type,
+            expires_at: response.expires_in.map(|s| now + s * 1000),
+            refresh_token: response.refresh_token,
+            id_token: response.id_token,
+            scope: response.scope,
+        }
+    }
+}
+
+fn random_token() -> Result<String, Error> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow!("failed to generate random: {}", e))?;
+    Ok(BASE64_URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// S256 code challenge of PKCE
+fn code_challenge(verifier: &str) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn is_loopback(uri: &Url) -> bool {
+    uri.scheme() == "http"
+        && matches!(
+            uri.host_str(),
+            Some("127.0.0.1") | Some("[::1]") | Some("localhost")
+        )
+}
+
+fn authorization_url(
+    config: &OAuthConfig,
+    redirect_uri: &str,
+    state: &str,
+    challenge: &str,
+) -> Result<Url, Error> {
+    let mut url = Url::parse(&config.authorization_endpoint)?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &config.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("state", state)
+            .append_pair("code_challenge", challenge)
+            .append_pair("code_challenge_method", "S256");
+        if let Some(scopes) = &config.scopes {
+            query.append_pair("scope", &scopes.join(" "));
+        }
+        for (k, v) in config.params.iter().flatten() {
+            query.append_pair(k, v);
+        }
+    }
+    Ok(url)
+}
+
+/// Get authorization code from params of the redirect
+fn authorization_code(params: &HashMap<String, String>, state: &str) -> Result<String, Error> {
+    if let Some(error) = params.get("error") {
+        let description = params.get("error_description").map(|s| s.as_str());
+        return Err(anyhow!(
+            "authorization failed: {} {}",
+            error,
+            description.unwrap_or_default()
+        ));
+    }
+    if params.get("state").map(|s| s.as_str()) != Some(state) {
+        return Err(anyhow!("state of redirect mismatched"));
+    }
+    params
+        .get("code")
+        .cloned()
+        .ok_or_else(|| anyhow!("no code in redirect"))
+}
+
+fn query_params(url: &Url) -> HashMap<String, String> {
+    url.query_pairs()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Complete the pending authorization waiting for a custom scheme redirect, returns false if no
+/// authorization is waiting for it. Apps handling deep links themselves should forward the
+/// redirect urls here.
+pub fn handle_redirect(url: &str) -> bool {
+    let url = some_or_return!(Url::parse(url).ok(), false);
+    let params = query_params(&url);
+    let state = some_or_return!(params.get("state"), false);
+    let sender = some_or_return!(PENDING_REDIRECTS.lock().unwrap().remove(state), false);
+    sender.send(params).is_ok()
+}
+
+/// Read the request target of a connection to the loopback listener
+async fn read_request_url(stream: &mut TcpStream) -> Result<Option<Url>, Error> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_HEAD {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let target = head.lines().next().and_then(|l| l.split(' ').nth(1));
+    Ok(target.and_then(|t| Url::parse(&format!("http://127.0.0.1{}", t)).ok()))
+}
+
+/// Receive the redirect with a loopback listener, other requests like favicon get 404. Redirects
+/// with other state are rejected and the listener keeps waiting until `timeout`, so that other
+/// local processes can not complete the authorization.
+async fn receive_loopback_redirect(
+    listener: TcpListener,
+    path: &str,
+    state: &str,
+    timeout: Duration,
+) -> Result<HashMap<String, String>, Error> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let (mut stream, _) = tokio::time::timeout_at(deadline, listener.accept())
+            .await
+            .map_err(|_| anyhow!("authorization timed out"))??;
+        let read_timeout = Duration::from_secs(LOOPBACK_READ_TIMEOUT_SECS);
+        let url = match tokio::time::timeout(read_timeout, read_request_url(&mut stream)).await {
+            Ok(Ok(url)) => url,
+            _ => continue,
+        };
+        let params = url.as_ref().filter(|u| u.path() == path).map(query_params);
+        let matched = params
+            .as_ref()
+            .is_some_and(|p| p.get("state").map(|s| s.as_str()) == Some(state));
+        let response = if matched {
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+                Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                LOOPBACK_PAGE.len(),
+                LOOPBACK_PAGE
+            )
+        } else {
+            let status = if params.is_some() {
+                "400 Bad Request"
+            } else {
+                "404 Not Found"
+            };
+            format!(
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+        };
+        // The browser failing to show the page does not fail the authorization
+        let _ = tokio::time::timeout(read_timeout, stream.write_all(response.as_bytes())).await;
+        if let (true, Some(params)) = (matched, params) {
+            return Ok(params);
+        }
+    }
+}
+
+async fn request_token(
+    config: &OAuthConfig,
+    mut form: Vec<(&str, &str)>,
+) -> Result<OAuthTokens, Error> {
+    form.push(("client_id", config.client_id.as_str()));
+    if let Some(secret) = &config.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+    let client = http_client_builder()?.build()?;
+    let rsp = client
+        .post(&config.token_endpoint)
+        .header("accept", "application/json")
+        .form(&form)
+        .send()
+        .await?;
+    let status = rsp.status();
+    let body = rsp.text().await?;
+    if !status.is_success() {
+        return Err(anyhow!("token request failed: {}, {}", status, body));
+    }
+    let tokens = OAuthTokens::from_response(serde_json::from_str(&body)?);
+    if let (Some(account), Some(refresh_token)) = (&config.account, &tokens.refresh_token) {
+        secure_store::set(account, refresh_token)?;
+    }
+    Ok(tokens)
+}
+
+/// Run authorization code flow with PKCE in the system browser and exchange the code for tokens
+pub async fn authorize(config: &OAuthConfig) -> Result<OAuthTokens, Error> {
+    let verifier = random_token()?;
+    let state = random_token()?;
+    let timeout = Duration::from_secs(config.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let redirect_uri = match &config.redirect_uri {
+        Some(uri) => Some(Url::parse(uri)?),
+        None => None,
+    };
+    let (redirect_uri, params) = match redirect_uri {
+        Some(uri) if !is_loopback(&uri) => {
+            let (sender, receiver) = oneshot::channel();
+            PENDING_REDIRECTS
+                .lock()
+                .unwrap()
+                .insert(state.clone(), sender);
+            let url = authorization_url(config, uri.as_str(), &state, &code_challenge(&verifier));
+            let result = match url.and_then(|url| platform::open_browser(url.as_str())) {
+                Ok(_) => tokio::time::timeout(timeout, receiver)
+                    .await
+                    .map_err(|_| anyhow!("authorization timed out"))
+                    .and_then(|r| r.map_err(|_| anyhow!("authorization canceled"))),
+                Err(e) => Err(e),
+            };
+            PENDING_REDIRECTS.lock().unwrap().remove(&state);
+            (uri, result?)
+        }
+        uri => {
+            // Keep the host registered by app, e.g. localhost
+            let host = uri
+                .as_ref()
+                .and_then(|u| u.host_str())
+                .unwrap_or("127.0.0.1");
+            let bind_host = if host == "[::1]" { "::1" } else { "127.0.0.1" };
+            let port = uri.as_ref().and_then(|u| u.port()).unwrap_or(0);
+            let listener = TcpListener::bind((bind_host, port)).await?;
+            let port = listener.local_addr()?.port();
+            let path = match &uri {
+                Some(u) if u.path() != "/" => u.path().to_string(),
+                _ => DEFAULT_LOOPBACK_PATH.to_string(),
+            };
+            let uri = Url::parse(&format!("http://{}:{}{}", host, port, path))?;
+            let url = authorization_url(config, uri.as_str(), &state, &code_challenge(&verifier))?;
+            platform::open_browser(url.as_str())?;
+            let params = receive_loopback_redirect(listener, &path, &state, timeout).await?;
+            (uri, params)
+        }
+    };
+    let code = authorization_code(&params, &state)?;
+    let form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("code_verifier", verifier.as_str()),
+    ];
+    request_token(config, form).await
+}
+
+/// Refresh access token, using the refresh token stored for the account if not given
+pub async fn refresh(
+    config: &OAuthConfig,
+    refresh_token: Option<String>,
+) -> Result<OAuthTokens, Error> {
+    let refresh_token = match (refresh_token, &config.account) {
+        (Some(token), _) => token,
+        (None, Some(account)) => {
+            secure_store::get(account)?.ok_or_else(|| anyhow!("no refresh token stored"))?
+        }
+        (None, None) => return Err(anyhow!("no refresh token")),
+    };
+    let form = vec![
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token.as_str()),
+    ];
+    let mut tokens = request_token(config, form).await?;
+    // Servers not rotating refresh tokens omit them
+    tokens.refresh_token.get_or_insert(refresh_token);
+    Ok(tokens)
+}
+
+#[allow(nonstandard_style)]
+pub struct oauth;
+
+#[js_methods]
+impl oauth {
+    #[js_func]
+    pub async fn authorize(config: OAuthConfig) -> Result<OAuthTokens, Error> {
+        authorize(&config).await
+    }
+
+    #[js_func]
+    pub async fn refresh(
+        config: OAuthConfig,
+        refresh_token: Option<String>,
+    ) -> Result<OAuthTokens, Error> {
+        refresh(&config, refresh_token).await
+    }
+
+    #[js_func]
+    pub fn get_refresh_token(account: String) -> Result<Option<String>, Error> {
+        secure_store::get(&account)
+    }
+
+    /// Delete the refresh token stored for the account
+    #[js_func]
+    pub fn sign_out(account: String) -> Result<(), Error> {
+        secure_store::delete(&account)
+    }
+
+    #[js_func]
+    pub fn handle_redirect(url: String) -> bool {
+        handle_redirect(&url)
+    }
+}
+
+/// Name of the app for entries of secure storage
+fn service_name() -> String {
+    let exe = std::env::current_exe().ok();
+    let name = exe
+        .as_ref()
+        .and_then(|p| p.file_stem())
+        .and_then(|s| s.to_str());
+    format!("{}.oauth", name.unwrap_or("deft"))
+}
+
+#[cfg(any(desktop_platform, ios_platform))]
+mod secure_store {
+    use crate::ext::ext_oauth::service_name;
+    use anyhow::Error;
+    use keyring::Entry;
+
+    pub fn get(account: &str) -> Result<Option<String>, Error> {
+        match Entry::new(&service_name(), account)?.get_password() {
+            Ok(token) => Ok(Some(token)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn set(account: &str, token: &str) -> Result<(), Error> {
+        Ok(Entry::new(&service_name(), account)?.set_password(token)?)
+    }
+
+    pub fn delete(account: &str) -> Result<(), Error> {
+        match Entry::new(&service_name(), account)?.delete_credential() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Tokens are kept by the activity, which should implement
+/// `String secureStorageGet(String key)`, `void secureStorageSet(String key, String value)` and
+/// `void secureStorageDelete(String key)` with EncryptedSharedPreferences or values encrypted by
+/// a key of Android Keystore. Storing fails if the activity does not implement them.
+#[cfg(target_os = "android")]
+mod secure_store {
+    use crate::android::{call_activity_method, with_activity};
+    use crate::ext::ext_oauth::service_name;
+    use anyhow::Error;
+    use jni::objects::{JString, JValue};
+
+    fn key(account: &str) -> String {
+        format!("{}/{}", service_name(), account)
+    }
+
+    pub fn get(account: &str) -> Result<Option<String>, Error> {
+        with_activity(|env, activity| {
+            let key = env.new_string(key(account))?;
+            let sig = "(Ljava/lang/String;)Ljava/lang/String;";
+            let args = [JValue::Object(&key)];
+            let value = call_activity_method(env, activity, "secureStorageGet", sig, &args)?.l()?;
+            if value.is_null() {
+                return Ok(None);
+            }
+            let value: String = env.get_string(&JString::from(value))?.into();
+            Ok(Some(value))
+        })
+    }
+
+    pub fn set(account: &str, token: &str) -> Result<(), Error> {
+        with_activity(|env, activity| {
+            let key = env.new_string(key(account))?;
+            let token = env.new_string(token)?;
+            let sig = "(Ljava/lang/String;Ljava/lang/String;)V";
+            let args = [JValue::Object(&key), JValue::Object(&token)];
+            call_activity_method(env, activity, "secureStorageSet", sig, &args)?.v()?;
+            Ok(())
+        })
+    }
+
+    pub fn delete(account: &str) -> Result<(), Error> {
+        with_activity(|env, activity| {
+            let key = env.new_string(key(account))?;
+            let sig = "(Ljava/lang/String;)V";
+            let args = [JValue::Object(&key)];
+            call_activity_method(env, activity, "secureStorageDelete", sig, &args)?.v()?;
+            Ok(())
+        })
+    }
+}
+
+/// Tokens are never written in plaintext, storing fails without a secure storage
+#[cfg(not(any(desktop_platform, ios_platform, target_os = "android")))]
+mod secure_store {
+    use anyhow::{anyhow, Error};
+
+    pub fn get(_account: &str) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    pub fn set(_account: &str, _token: &str) -> Result<(), Error> {
+        Err(anyhow!("secure storage is not supported on this platform"))
+    }
+
+    pub fn delete(_account: &str) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(desktop_platform)]
+mod platform {
+    use anyhow::{anyhow, Error};
+    use std::process::Command;
+
+    pub fn open_browser(url: &str) -> Result<(), Error> {
+        let mut cmd = if cfg!(target_os = "windows") {
+            let mut cmd = Command::new("rundll32");
+            cmd.arg("url.dll,FileProtocolHandler");
+            cmd
+        } else if cfg!(target_os = "macos") {
+            Command::new("open")
+        } else {
+            Command::new("xdg-open")
+        };
+        cmd.arg(url)
+            .spawn()
+            .map_err(|e| anyhow!("failed to open browser: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "android")]
+mod platform {
+    use crate::android::ANDROID_APP;
+    use crate::ext::ext_oauth::handle_redirect;
+    use anyhow::Error;
+    use jni::objects::{JClass, JObject, JString, JValue};
+    use jni::{JNIEnv, JavaVM};
+    use log::error;
+
+    pub fn open_browser(url: &str) -> Result<(), Error> {
+        let app = ANDROID_APP.get().unwrap();
+        let vm = unsafe { JavaVM::from_raw(app.vm_as_ptr() as _)? };
+        let activity = unsafe { JObject::from_raw(app.activity_as_ptr() as _) };
+        let mut env = vm.attach_current_thread()?;
+        let url = env.new_string(url)?;
+        let uri = env
+            .call_static_method(
+                "android/net/Uri",
+                "parse",
+                "(Ljava/lang/String;)Landroid/net/Uri;",
+                &[JValue::Object(&url)],
+            )?
+            .l()?;
+        let action = env.new_string("android.intent.action.VIEW")?;
+        let intent = env.new_object(
+            "android/content/Intent",
+            "(Ljava/lang/String;Landroid/net/Uri;)V",
+            &[JValue::Object(&action), JValue::Object(&uri)],
+        )?;
+        env.call_method(
+            &activity,
+            "startActivity",
+            "(Landroid/content/Intent;)V",
+            &[JValue::Object(&intent)],
+        )?
+        .v()?;
+        Ok(())
+    }
+
+    /// Called by the activity with data of intents matching the redirect scheme
+    #[no_mangle]
+    pub extern "system" fn Java_deft_DeftActivity_oauthRedirect0<'local>(
+        mut env: JNIEnv<'local>,
+        _class: JClass<'local>,
+        url: JString<'local>,
+    ) {
+        match env.get_string(&url) {
+            Ok(url) => {
+                handle_redirect(&String::from(url));
+            }
+            Err(e) => error!("Failed to get redirect url: {}", e),
+        }
+    }
+}
+
+#[cfg(target_os = "ios")]
+mod platform {
+    use crate::ext::ext_oauth::handle_redirect;
+    use crate::platform::find_host_symbol;
+    use anyhow::{anyhow, Error};
+    use std::ffi::{c_char, CStr, CString};
+
+    /// `deft_oauth_open` implemented by the app, e.g. with `ASWebAuthenticationSession` or
+    /// `UIApplication.open`
+    type OpenFn = unsafe extern "C" fn(url: *const c_char);
+
+    pub fn open_browser(url: &str) -> Result<(), Error> {
+        let ptr = find_host_symbol(c"deft_oauth_open")
+            .ok_or_else(|| anyhow!("deft_oauth_open is not implemented by the app"))?;
+        let open = unsafe { std::mem::transmute::<*mut std::ffi::c_void, OpenFn>(ptr) };
+        let url = CString::new(url)?;
+        unsafe { open(url.as_ptr()) };
+        Ok(())
+    }
+
+    /// Called by the app with urls opened by the redirect scheme
+    #[no_mangle]
+    pub unsafe extern "C" fn deft_oauth_redirect(url: *const c_char) -> bool {
+        if url.is_null() {
+            return false;
+        }
+        handle_redirect(&CStr::from_ptr(url).to_string_lossy())
+    }
+}
+
+#[cfg(not(any(desktop_platform, target_os = "android", target_os = "ios")))]
+mod platform {
+    use anyhow::{anyhow, Error};
+
+    pub fn open_browser(_url: &str) -> Result<(), Error> {
+        Err(anyhow!("opening browser is not supported on this platform"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::ext_oauth::{
+        authorization_code, authorization_url, code_challenge, receive_loopback_redirect,
+        OAuthConfig,
+    };
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn test_code_challenge() {
+        assert_eq!(
+            "5Uukp0KwgIk3evNWtBP8NrOlzIAM-FfP9aoerpFYOoA",
+            code_challenge("dBjftJeZ4CVP-mB92K3uhbUJU1p1r_wW1gFWFOEjXk")
+        );
+    }
+
+    #[test]
+    fn test_authorization_url() {
+        let config = OAuthConfig {
+            authorization_endpoint: "https://example.com/authorize?tenant=a".to_string(),
+            token_endpoint: "https://example.com/token".to_string(),
+            client_id: "app".to_string(),
+            client_secret: None,
+            scopes: Some(vec!["openid".to_string(), "email".to_string()]),
+            redirect_uri: None,
+            params: None,
+            account: None,
+            timeout: None,
+        };
+        let url = authorization_url(&config, "http://127.0.0.1:8765/callback", "s", "c").unwrap();
+        assert_eq!(
+            "https://example.com/authorize?tenant=a&response_type=code&client_id=app\
+            &redirect_uri=http%3A%2F%2F127.0.0.1%3A8765%2Fcallback&state=s&code_challenge=c\
+            &code_challenge_method=S256&scope=openid+email",
+            url.as_str()
+        );
+    }
+
+    #[test]
+    fn test_authorization_code() {
+        let params = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        let ok = params(&[("code", "abc"), ("state", "s")]);
+        assert_eq!("abc", authorization_code(&ok, "s").unwrap());
+        assert!(authorization_code(&ok, "other").is_err());
+        let denied = params(&[("error", "access_denied"), ("state", "s")]);
+        assert!(authorization_code(&denied, "s").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_loopback_redirect_state() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let mut statuses = Vec::new();
+            for target in [
+                "/callback?code=x&state=other",
+                "/favicon.ico",
+                "/callback?code=y&state=s",
+            ] {
+                let mut stream = TcpStream::connect(addr).await.unwrap();
+                let request = format!("GET {} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n", target);
+                stream.write_all(request.as_bytes()).await.unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).await.unwrap();
+                statuses.push(response.split(' ').nth(1).unwrap().to_string());
+            }
+            statuses
+        });
+        let timeout = Duration::from_secs(10);
+        let params = receive_loopback_redirect(listener, "/callback", "s", timeout)
+            .await
+            .unwrap();
+        assert_eq!("y", params["code"]);
+        assert_eq!(vec!["400", "404", "200"], client.await.unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let timeout = Duration::from_millis(50);
+        assert!(
+            receive_loopback_redirect(listener, "/callback", "s", timeout)
+                .await
+                .is_err()
+        );
+    }
+}
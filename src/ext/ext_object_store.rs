@@ -0,0 +1,581 @@
+use crate as deft;
+use crate::js::js_engine::JsEngine;
+use crate::js::{JsError, JsPo};
+use crate::js_value;
+use crate::task_executor::TaskExecutor;
+use deft_macros::{js_methods, mrc_object};
+use quick_js::JsValue;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS deft_stores (
+    name TEXT PRIMARY KEY,
+    key_path TEXT,
+    auto_increment INTEGER NOT NULL,
+    next_key INTEGER NOT NULL DEFAULT 1
+);
+CREATE TABLE IF NOT EXISTS deft_indexes (
+    store TEXT NOT NULL,
+    name TEXT NOT NULL,
+    key_path TEXT NOT NULL,
+    is_unique INTEGER NOT NULL,
+    PRIMARY KEY (store, name)
+);
+CREATE TABLE IF NOT EXISTS deft_records (
+    store TEXT NOT NULL,
+    key NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (store, key)
+);
+CREATE TABLE IF NOT EXISTS deft_index_entries (
+    store TEXT NOT NULL,
+    idx TEXT NOT NULL,
+    index_key NOT NULL,
+    key NOT NULL,
+    PRIMARY KEY (store, idx, index_key, key)
+);
+CREATE INDEX IF NOT EXISTS deft_index_entries_key ON deft_index_entries (store, key);
+";
+
+/// Key of record or index, numbers are sorted before strings
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum ObjectKey {
+    Number(f64),
+    Text(String),
+}
+
+impl ObjectKey {
+    fn to_sql_value(&self) -> Value {
+        match self {
+            ObjectKey::Number(n) => {
+                if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+                    Value::Integer(*n as i64)
+                } else {
+                    Value::Real(*n)
+                }
+            }
+            ObjectKey::Text(s) => Value::Text(s.clone()),
+        }
+    }
+
+    fn from_sql_value(value: ValueRef) -> Result<Self, String> {
+        match value {
+            ValueRef::Integer(i) => Ok(ObjectKey::Number(i as f64)),
+            ValueRef::Real(f) => Ok(ObjectKey::Number(f)),
+            ValueRef::Text(t) => Ok(ObjectKey::Text(String::from_utf8_lossy(t).to_string())),
+            _ => Err("invalid key type".to_string()),
+        }
+    }
+
+    fn to_js_value(self) -> JsValue {
+        match self {
+            ObjectKey::Number(n) => JsValue::Float(n),
+            ObjectKey::Text(s) => JsValue::String(s),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IndexEntry {
+    pub name: String,
+    pub key: ObjectKey,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteOp {
+    /// "put", "add", "delete" or "clear"
+    pub op: String,
+    pub store: String,
+    pub key: Option<ObjectKey>,
+    /// Serialized value
+    pub value: Option<String>,
+    pub indexes: Option<Vec<IndexEntry>>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryOptions {
+    pub index: Option<String>,
+    pub lower: Option<ObjectKey>,
+    pub upper: Option<ObjectKey>,
+    pub lower_open: Option<bool>,
+    pub upper_open: Option<bool>,
+    /// "next" or "prev"
+    pub direction: Option<String>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+#[mrc_object]
+pub struct ObjectStoreDb {
+    task_executor: TaskExecutor<Session>,
+}
+
+struct Session {
+    conn: Option<Connection>,
+}
+
+impl Session {
+    fn ensure_opened_mut(&mut self) -> Result<&mut Connection, String> {
+        match &mut self.conn {
+            None => Err("No connection".to_string()),
+            Some(conn) => Ok(conn),
+        }
+    }
+}
+
+js_value!(ObjectStoreDb);
+
+#[js_methods]
+impl ObjectStoreDb {
+    #[js_func]
+    pub fn create() -> Result<Self, JsError> {
+        let task_executor = TaskExecutor::new(move || Session { conn: None });
+        Ok(ObjectStoreDbData { task_executor }.to_ref())
+    }
+
+    /// Open an in-memory database if path is empty
+    #[js_func]
+    pub fn open(&self, path: String) -> Result<JsValue, JsError> {
+        self.run(move |sess| {
+            let conn = if path.is_empty() {
+                Connection::open_in_memory()
+            } else {
+                Connection::open(path)
+            };
+            let conn = conn.map_err(|e| format!("Failed to open object store, {}", e))?;
+            conn.execute_batch(SCHEMA)
+                .map_err(|e| format!("Failed to init object store, {}", e))?;
+            sess.conn = Some(conn);
+            Ok(JsValue::Undefined)
+        })
+    }
+
+    /// Return [[storeName, keyPath, autoIncrement, [[indexName, keyPath, unique]]]]
+    #[js_func]
+    pub fn get_schema(&self) -> Result<JsValue, JsError> {
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            get_schema(conn).map_err(|e| format!("Failed to read schema, {}", e))
+        })
+    }
+
+    #[js_func]
+    pub fn create_store(
+        &self,
+        name: String,
+        key_path: Option<String>,
+        auto_increment: bool,
+    ) -> Result<JsValue, JsError> {
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            conn.execute(
+                "INSERT INTO deft_stores (name, key_path, auto_increment) VALUES (?1, ?2, ?3)",
+                params![name, key_path, auto_increment],
+            )
+            .map_err(|e| format!("Failed to create store {}, {}", name, e))?;
+            Ok(JsValue::Undefined)
+        })
+    }
+
+    #[js_func]
+    pub fn delete_store(&self, name: String) -> Result<JsValue, JsError> {
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            for table in ["deft_index_entries", "deft_records", "deft_indexes"] {
+                tx.execute(&format!("DELETE FROM {} WHERE store = ?1", table), [&name])
+                    .map_err(|e| e.to_string())?;
+            }
+            tx.execute("DELETE FROM deft_stores WHERE name = ?1", [&name])
+                .map_err(|e| e.to_string())?;
+            tx.commit().map_err(|e| e.to_string())?;
+            Ok(JsValue::Undefined)
+        })
+    }
+
+    /// Index entries of existing records should be written by put operations after creating
+    #[js_func]
+    pub fn create_index(
+        &self,
+        store: String,
+        name: String,
+        key_path: String,
+        unique: bool,
+    ) -> Result<JsValue, JsError> {
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            conn.execute(
+                "INSERT INTO deft_indexes (store, name, key_path, is_unique) VALUES (?1, ?2, ?3, ?4)",
+                params![store, name, key_path, unique],
+            )
+            .map_err(|e| format!("Failed to create index {}, {}", name, e))?;
+            Ok(JsValue::Undefined)
+        })
+    }
+
+    #[js_func]
+    pub fn delete_index(&self, store: String, name: String) -> Result<JsValue, JsError> {
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            tx.execute(
+                "DELETE FROM deft_index_entries WHERE store = ?1 AND idx = ?2",
+                [&store, &name],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.execute(
+                "DELETE FROM deft_indexes WHERE store = ?1 AND name = ?2",
+                [&store, &name],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.commit().map_err(|e| e.to_string())?;
+            Ok(JsValue::Undefined)
+        })
+    }
+
+    #[js_func]
+    pub fn get(&self, store: String, key: JsPo<ObjectKey>) -> Result<JsValue, JsError> {
+        let key = key.take();
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            let value = conn
+                .query_row(
+                    "SELECT value FROM deft_records WHERE store = ?1 AND key = ?2",
+                    params![store, key.to_sql_value()],
+                    |r| r.get::<_, String>(0),
+                )
+                .optional()
+                .map_err(|e| format!("Failed to get record, {}", e))?;
+            Ok(value.map(JsValue::String).unwrap_or(JsValue::Null))
+        })
+    }
+
+    /// Return [[key, value]]
+    #[js_func]
+    pub fn query(&self, store: String, options: JsPo<QueryOptions>) -> Result<JsValue, JsError> {
+        let options = options.take();
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            query(conn, &store, &options, false).map_err(|e| format!("Failed to query, {}", e))
+        })
+    }
+
+    #[js_func]
+    pub fn count(&self, store: String, options: JsPo<QueryOptions>) -> Result<JsValue, JsError> {
+        let options = options.take();
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            query(conn, &store, &options, true).map_err(|e| format!("Failed to count, {}", e))
+        })
+    }
+
+    /// Execute write operations atomically, return keys of written records
+    #[js_func]
+    pub fn commit(&self, ops: JsPo<Vec<WriteOp>>) -> Result<JsValue, JsError> {
+        let ops = ops.take();
+        self.run(move |sess| {
+            let conn = sess.ensure_opened_mut()?;
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            let mut keys = Vec::with_capacity(ops.len());
+            for op in ops {
+                let key = execute_write_op(&tx, op)?;
+                keys.push(key.map(|k| k.to_js_value()).unwrap_or(JsValue::Null));
+            }
+            tx.commit()
+                .map_err(|e| format!("Failed to commit transaction, {}", e))?;
+            Ok(JsValue::Array(keys))
+        })
+    }
+
+    fn run<F>(&self, task: F) -> Result<JsValue, JsError>
+    where
+        F: Send + FnOnce(&mut Session) -> Result<JsValue, String> + 'static,
+    {
+        let (promise, resolver) = JsEngine::get().create_promise();
+        self.task_executor
+            .run(move |session| resolver.settle(task(session)));
+        Ok(promise)
+    }
+}
+
+fn get_schema(conn: &Connection) -> Result<JsValue, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT name, key_path, auto_increment FROM deft_stores")?;
+    let stores = stmt
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, Option<String>>(1)?,
+                r.get::<_, bool>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut index_stmt =
+        conn.prepare("SELECT name, key_path, is_unique FROM deft_indexes WHERE store = ?1")?;
+    let mut result = Vec::with_capacity(stores.len());
+    for (name, key_path, auto_increment) in stores {
+        let indexes = index_stmt
+            .query_map([&name], |r| {
+                Ok(JsValue::Array(vec![
+                    JsValue::String(r.get(0)?),
+                    JsValue::String(r.get(1)?),
+                    JsValue::Bool(r.get(2)?),
+                ]))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        result.push(JsValue::Array(vec![
+            JsValue::String(name),
+            key_path.map(JsValue::String).unwrap_or(JsValue::Null),
+            JsValue::Bool(auto_increment),
+            JsValue::Array(indexes),
+        ]));
+    }
+    Ok(JsValue::Array(result))
+}
+
+fn query(
+    conn: &Connection,
+    store: &str,
+    options: &QueryOptions,
+    count: bool,
+) -> Result<JsValue, rusqlite::Error> {
+    let mut params = vec![Value::Text(store.to_string())];
+    let (mut sql, key_column) = if let Some(index) = &options.index {
+        params.push(Value::Text(index.to_string()));
+        let sql = "SELECT r.key, r.value FROM deft_index_entries e \
+            JOIN deft_records r ON r.store = e.store AND r.key = e.key \
+            WHERE e.store = ?1 AND e.idx = ?2"
+            .to_string();
+        (sql, "e.index_key")
+    } else {
+        let sql = "SELECT r.key, r.value FROM deft_records r WHERE r.store = ?1".to_string();
+        (sql, "r.key")
+    };
+    if let Some(lower) = &options.lower {
+        params.push(lower.to_sql_value());
+        let op = if options.lower_open.unwrap_or(false) { ">" } else { ">=" };
+        sql.push_str(&format!(" AND {} {} ?{}", key_column, op, params.len()));
+    }
+    if let Some(upper) = &options.upper {
+        params.push(upper.to_sql_value());
+        let op = if options.upper_open.unwrap_or(false) { "<" } else { "<=" };
+        sql.push_str(&format!(" AND {} {} ?{}", key_column, op, params.len()));
+    }
+    if count {
+        let sql = format!("SELECT COUNT(*) FROM ({})", sql);
+        let count: i64 = conn.query_row(&sql, params_from_iter(params.iter()), |r| r.get(0))?;
+        return Ok(JsValue::Float(count as f64));
+    }
+    let order = match options.direction.as_deref() {
+        Some("prev") => "DESC",
+        _ => "ASC",
+    };
+    sql.push_str(&format!(" ORDER BY {} {}, r.key {}", key_column, order, order));
+    sql.push_str(&format!(
+        " LIMIT {} OFFSET {}",
+        options.limit.map(|l| l as i64).unwrap_or(-1),
+        options.offset.unwrap_or(0)
+    ));
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt
+        .query_map(params_from_iter(params.iter()), |r| {
+            let key = ObjectKey::from_sql_value(r.get_ref(0)?)
+                .map(|k| k.to_js_value())
+                .unwrap_or(JsValue::Null);
+            Ok(JsValue::Array(vec![key, JsValue::String(r.get(1)?)]))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(JsValue::Array(rows))
+}
+
+fn execute_write_op(tx: &Transaction, op: WriteOp) -> Result<Option<ObjectKey>, String> {
+    let store = op.store;
+    let (auto_increment, next_key) = tx
+        .query_row(
+            "SELECT auto_increment, next_key FROM deft_stores WHERE name = ?1",
+            [&store],
+            |r| Ok((r.get::<_, bool>(0)?, r.get::<_, i64>(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No such object store: {}", store))?;
+    match op.op.as_str() {
+        "clear" => {
+            for table in ["deft_index_entries", "deft_records"] {
+                tx.execute(&format!("DELETE FROM {} WHERE store = ?1", table), [&store])
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok(None)
+        }
+        "delete" => {
+            let key = op.key.ok_or("key is required")?.to_sql_value();
+            delete_record(tx, &store, &key)?;
+            Ok(None)
+        }
+        "put" | "add" => {
+            let key = match op.key {
+                Some(key) => key,
+                None if auto_increment => ObjectKey::Number(next_key as f64),
+                None => return Err("key is required".to_string()),
+            };
+            if auto_increment {
+                if let ObjectKey::Number(n) = key {
+                    if n >= next_key as f64 {
+                        tx.execute(
+                            "UPDATE deft_stores SET next_key = ?1 WHERE name = ?2",
+                            params![n.floor() as i64 + 1, store],
+                        )
+                        .map_err(|e| e.to_string())?;
+                    }
+                }
+            }
+            let sql_key = key.to_sql_value();
+            if op.op == "add" {
+                let exists = tx
+                    .query_row(
+                        "SELECT 1 FROM deft_records WHERE store = ?1 AND key = ?2",
+                        params![store, sql_key],
+                        |_| Ok(()),
+                    )
+                    .optional()
+                    .map_err(|e| e.to_string())?;
+                if exists.is_some() {
+                    return Err(format!("ConstraintError: key already exists: {:?}", key));
+                }
+            }
+            delete_record(tx, &store, &sql_key)?;
+            tx.execute(
+                "INSERT INTO deft_records (store, key, value) VALUES (?1, ?2, ?3)",
+                params![store, sql_key, op.value.unwrap_or("null".to_string())],
+            )
+            .map_err(|e| e.to_string())?;
+            for entry in op.indexes.unwrap_or_default() {
+                insert_index_entry(tx, &store, &entry, &sql_key)?;
+            }
+            Ok(Some(key))
+        }
+        _ => Err(format!("unknown operation: {}", op.op)),
+    }
+}
+
+fn delete_record(tx: &Transaction, store: &str, key: &Value) -> Result<(), String> {
+    tx.execute(
+        "DELETE FROM deft_index_entries WHERE store = ?1 AND key = ?2",
+        params![store, key],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM deft_records WHERE store = ?1 AND key = ?2",
+        params![store, key],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn insert_index_entry(
+    tx: &Transaction,
+    store: &str,
+    entry: &IndexEntry,
+    key: &Value,
+) -> Result<(), String> {
+    let unique = tx
+        .query_row(
+            "SELECT is_unique FROM deft_indexes WHERE store = ?1 AND name = ?2",
+            params![store, entry.name],
+            |r| r.get::<_, bool>(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No such index: {}", entry.name))?;
+    let index_key = entry.key.to_sql_value();
+    if unique {
+        let exists = tx
+            .query_row(
+                "SELECT 1 FROM deft_index_entries WHERE store = ?1 AND idx = ?2 AND index_key = ?3",
+                params![store, entry.name, index_key],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if exists.is_some() {
+            return Err(format!(
+                "ConstraintError: duplicated key of unique index {}",
+                entry.name
+            ));
+        }
+    }
+    tx.execute(
+        "INSERT INTO deft_index_entries (store, idx, index_key, key) VALUES (?1, ?2, ?3, ?4)",
+        params![store, entry.name, index_key, key],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn.execute(
+            "INSERT INTO deft_stores (name, key_path, auto_increment) VALUES ('s', NULL, 1)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO deft_indexes (store, name, key_path, is_unique) VALUES ('s', 'age', 'age', 0)",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn put(age: f64) -> WriteOp {
+        WriteOp {
+            op: "put".to_string(),
+            store: "s".to_string(),
+            key: None,
+            value: Some(format!("{{\"age\":{}}}", age)),
+            indexes: Some(vec![IndexEntry {
+                name: "age".to_string(),
+                key: ObjectKey::Number(age),
+            }]),
+        }
+    }
+
+    #[test]
+    fn test_auto_increment_and_index_range() {
+        let mut conn = open();
+        let tx = conn.transaction().unwrap();
+        for age in [30.0, 10.0, 20.0] {
+            execute_write_op(&tx, put(age)).unwrap();
+        }
+        tx.commit().unwrap();
+        let options = QueryOptions {
+            index: Some("age".to_string()),
+            lower: Some(ObjectKey::Number(15.0)),
+            ..Default::default()
+        };
+        match query(&conn, "s", &options, false).unwrap() {
+            JsValue::Array(rows) => {
+                assert_eq!(rows.len(), 2);
+                match &rows[0] {
+                    JsValue::Array(row) => {
+                        assert!(matches!(row[0], JsValue::Float(k) if k == 3.0));
+                        assert!(matches!(&row[1], JsValue::String(v) if v == "{\"age\":20}"));
+                    }
+                    _ => panic!("unexpected row"),
+                }
+            }
+            _ => panic!("unexpected result"),
+        }
+        let count = query(&conn, "s", &QueryOptions::default(), true).unwrap();
+        assert!(matches!(count, JsValue::Float(c) if c == 3.0));
+    }
+}
@@ -1,16 +1,23 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use crate as deft;
-use crate::app::exit_app;
+use crate::app::{exit_app, shutdown_app};
 use crate::is_mobile_platform;
 use crate::js::js_engine::JsEngine;
+use crate::some_or_return;
+use crate::timer::{set_timeout, TimerHandle};
 use deft_macros::js_methods;
-use log::error;
+use log::{error, warn};
 use quick_js::exception::HostPromiseRejectionTracker;
 use quick_js::JsValue;
 use std::env;
 
 thread_local! {
     pub static EXIT_ON_ALL_WINDOWS_CLOSED: Cell<bool> = Cell::new(true);
+    static BEFORE_QUIT_HANDLER: RefCell<Option<JsValue>> = RefCell::new(None);
+    /// Force quitting timer, exists when before-quit handler is running
+    static QUIT_TIMER: RefCell<Option<TimerHandle>> = RefCell::new(None);
+    static QUIT_TIMEOUT: Cell<u64> = Cell::new(5000);
+    static LIFECYCLE_HANDLER: RefCell<Option<JsValue>> = RefCell::new(None);
 }
 
 struct UserPromiseRejectionTracker {
@@ -35,6 +42,84 @@ impl process {
         let _ = exit_app(code);
     }
 
+    /// Handler is called with exit code and should call confirm_quit or cancel_quit later
+    #[js_func]
+    pub fn set_before_quit_handler(handler: JsValue) {
+        let handler = match handler {
+            JsValue::Null | JsValue::Undefined => None,
+            h => Some(h),
+        };
+        BEFORE_QUIT_HANDLER.set(handler);
+    }
+
+    /// Max milliseconds to wait for before-quit handler, app quits when timeout
+    #[js_func]
+    pub fn set_quit_timeout(timeout: u64) {
+        QUIT_TIMEOUT.set(timeout);
+    }
+
+    #[js_func]
+    pub fn confirm_quit(code: i32) {
+        if QUIT_TIMER.with_borrow_mut(|t| t.take()).is_some() {
+            shutdown_app(code);
+        }
+    }
+
+    #[js_func]
+    pub fn cancel_quit() {
+        QUIT_TIMER.with_borrow_mut(|t| t.take());
+    }
+
+    /// Return false if no before-quit handler
+    pub fn handle_before_quit(code: i32) -> bool {
+        let handler = match BEFORE_QUIT_HANDLER.with_borrow(|h| h.clone()) {
+            Some(h) => h,
+            None => return false,
+        };
+        if QUIT_TIMER.with_borrow(|t| t.is_some()) {
+            // Quitting is in progress
+            return true;
+        }
+        let timer = set_timeout(
+            move || {
+                if QUIT_TIMER.with_borrow_mut(|t| t.take()).is_some() {
+                    warn!("before-quit handler timeout, force quitting");
+                    shutdown_app(code);
+                }
+            },
+            QUIT_TIMEOUT.get(),
+        );
+        QUIT_TIMER.with_borrow_mut(|t| t.replace(timer));
+        if let Err(e) = handler.call_as_function(vec![JsValue::Int(code)]) {
+            error!("Failed to call before-quit handler: {:?}", e);
+            Self::confirm_quit(code);
+        }
+        true
+    }
+
+    /// Handler is called with "foreground" or "background"
+    #[js_func]
+    pub fn set_lifecycle_handler(handler: JsValue) {
+        let handler = match handler {
+            JsValue::Null | JsValue::Undefined => None,
+            h => Some(h),
+        };
+        LIFECYCLE_HANDLER.set(handler);
+    }
+
+    /// Called when app comes to foreground or goes to background, only reported on mobile
+    pub fn handle_lifecycle_change(foreground: bool) {
+        let handler = some_or_return!(LIFECYCLE_HANDLER.with_borrow(|h| h.clone()));
+        let state = if foreground {
+            "foreground"
+        } else {
+            "background"
+        };
+        if let Err(e) = handler.call_as_function(vec![JsValue::String(state.to_string())]) {
+            error!("Failed to call lifecycle handler: {:?}", e);
+        }
+    }
+
     #[js_func]
     pub fn set_exit_on_all_windows_closed(value: bool) {
         EXIT_ON_ALL_WINDOWS_CLOSED.set(value);
@@ -0,0 +1,123 @@
+use crate as deft;
+use crate::app::add_shutdown_hook;
+use crate::js::JsError;
+use crate::timer::{set_timeout, TimerHandle};
+use deft_macros::js_methods;
+use log::{debug, error};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LOCK_FILE: &str = "session.lock";
+const STATE_FILE: &str = "recovery.json";
+
+thread_local! {
+    static RECOVERY: RefCell<Option<RecoveryState>> = RefCell::new(None);
+}
+
+struct RecoveryState {
+    dir: PathBuf,
+    interval: u64,
+    /// Saved state of last session if it was not shut down cleanly
+    previous_state: Option<String>,
+    pending_state: Option<String>,
+    flush_timer: Option<TimerHandle>,
+}
+
+/// Crash recovery, app state is saved periodically and delivered to next session after unclean shutdown
+#[allow(nonstandard_style)]
+pub struct recovery {}
+
+#[js_methods]
+impl recovery {
+    /// Enable crash recovery, state set by js is written to dir at most once per interval milliseconds
+    pub fn init(dir: PathBuf, interval: u64) {
+        if let Err(e) = fs::create_dir_all(&dir) {
+            error!("failed to create recovery dir: {:?}", e);
+            return;
+        }
+        let lock_file = dir.join(LOCK_FILE);
+        let state_file = dir.join(STATE_FILE);
+        let previous_state = if lock_file.exists() {
+            debug!("unclean shutdown detected");
+            fs::read_to_string(&state_file).ok()
+        } else {
+            None
+        };
+        if let Err(e) = fs::write(&lock_file, std::process::id().to_string()) {
+            error!("failed to create session lock file: {:?}", e);
+        }
+        RECOVERY.set(Some(RecoveryState {
+            dir,
+            interval,
+            previous_state,
+            pending_state: None,
+            flush_timer: None,
+        }));
+        add_shutdown_hook(0, || {
+            if let Some(state) = RECOVERY.take() {
+                let _ = fs::remove_file(state.dir.join(STATE_FILE));
+                let _ = fs::remove_file(state.dir.join(LOCK_FILE));
+            }
+        });
+    }
+
+    /// Set serialized app state, which is written to disk later
+    #[js_func]
+    pub fn set_state(state: String) -> Result<(), JsError> {
+        RECOVERY.with_borrow_mut(|r| {
+            let r = r
+                .as_mut()
+                .ok_or_else(|| JsError::from_str("crash recovery is not enabled"))?;
+            r.pending_state = Some(state);
+            if r.flush_timer.is_none() {
+                r.flush_timer = Some(set_timeout(Self::flush, r.interval));
+            }
+            Ok(())
+        })
+    }
+
+    /// Return saved state if last session was not shut down cleanly
+    #[js_func]
+    pub fn get_previous_state() -> Option<String> {
+        RECOVERY.with_borrow(|r| r.as_ref().and_then(|r| r.previous_state.clone()))
+    }
+
+    /// Drop saved state of last session and current session
+    #[js_func]
+    pub fn clear() {
+        RECOVERY.with_borrow_mut(|r| {
+            if let Some(r) = r {
+                r.previous_state = None;
+                r.pending_state = None;
+                r.flush_timer = None;
+                let _ = fs::remove_file(r.dir.join(STATE_FILE));
+            }
+        });
+    }
+
+    #[js_func]
+    pub fn is_enabled() -> bool {
+        RECOVERY.with_borrow(|r| r.is_some())
+    }
+
+    fn flush() {
+        let pending = RECOVERY.with_borrow_mut(|r| {
+            let r = r.as_mut()?;
+            r.flush_timer = None;
+            let state = r.pending_state.take()?;
+            Some((r.dir.clone(), state))
+        });
+        if let Some((dir, state)) = pending {
+            if let Err(e) = write_atomically(&dir.join(STATE_FILE), &state) {
+                error!("failed to save recovery state: {:?}", e);
+            }
+        }
+    }
+}
+
+fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
@@ -1,4 +1,5 @@
 use crate as deft;
+use crate::ext::ext_window::WINDOWS;
 use crate::js::JsError;
 use crate::timer::{set_interval, set_timeout, TimerHandle};
 use deft_macros::js_func;
@@ -6,10 +7,25 @@ use log::error;
 use quick_js::JsValue;
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Max time given to idle callbacks at once, as browsers do
+const MAX_IDLE_PERIOD: Duration = Duration::from_millis(50);
+/// Interval to check whether windows are idle while idle callbacks are pending
+const IDLE_CHECK_INTERVAL: u64 = 16;
 
 thread_local! {
     pub static NEXT_TIMER_ID: Cell<i32> = Cell::new(1);
     pub static TIMERS: RefCell<HashMap<i32, TimerHandle>> = RefCell::new(HashMap::new());
+    static IDLE_CALLBACKS: RefCell<Vec<IdleCallback>> = RefCell::new(Vec::new());
+    static IDLE_CHECK_TIMER: RefCell<Option<TimerHandle>> = RefCell::new(None);
+}
+
+struct IdleCallback {
+    id: i32,
+    callback: JsValue,
+    /// Callback is called even if app is busy after this time
+    timeout_time: Option<Instant>,
 }
 
 #[js_func]
@@ -61,3 +77,85 @@ pub fn timer_set_interval(callback: JsValue, interval: i32) -> Result<i32, JsErr
 pub fn timer_clear_interval(id: i32) {
     TIMERS.with_borrow_mut(|m| m.remove(&id));
 }
+
+/// Queue callback to run when no window has pending paint or animation work, the callback
+/// receives whether it is called because of timeout and the remaining time in milliseconds.
+#[js_func]
+pub fn timer_request_idle_callback(
+    callback: JsValue,
+    timeout: Option<i32>,
+) -> Result<i32, JsError> {
+    let id = NEXT_TIMER_ID.get();
+    NEXT_TIMER_ID.set(id + 1);
+    let timeout_time = timeout
+        .filter(|t| *t > 0)
+        .map(|t| Instant::now() + Duration::from_millis(t as u64));
+    IDLE_CALLBACKS.with_borrow_mut(|list| {
+        list.push(IdleCallback {
+            id,
+            callback,
+            timeout_time,
+        })
+    });
+    schedule_idle_check(0);
+    Ok(id)
+}
+
+#[js_func]
+pub fn timer_cancel_idle_callback(id: i32) {
+    IDLE_CALLBACKS.with_borrow_mut(|list| list.retain(|c| c.id != id));
+}
+
+fn schedule_idle_check(delay: u64) {
+    IDLE_CHECK_TIMER.with_borrow_mut(|timer| {
+        if timer.is_none() {
+            *timer = Some(set_timeout(run_idle_callbacks, delay));
+        }
+    });
+}
+
+/// Time left for idle callbacks, None if any window is busy
+pub fn idle_period() -> Option<Duration> {
+    WINDOWS.with_borrow(|m| {
+        let mut period = MAX_IDLE_PERIOD;
+        for w in m.values() {
+            let w = w.upgrade_mut().ok()?;
+            if !w.is_idle() {
+                return None;
+            }
+            period = period.min(w.time_to_next_frame());
+        }
+        Some(period)
+    })
+}
+
+fn run_idle_callbacks() {
+    IDLE_CHECK_TIMER.with_borrow_mut(|timer| timer.take());
+    let now = Instant::now();
+    let deadline = idle_period().map(|p| now + p);
+    let pending = IDLE_CALLBACKS.with_borrow_mut(|list| std::mem::take(list));
+    let mut remaining = Vec::new();
+    for cb in pending {
+        let timed_out = cb.timeout_time.map(|t| t <= now).unwrap_or(false);
+        let time_left = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        let runnable = timed_out || time_left.map(|t| !t.is_zero()).unwrap_or(false);
+        if !runnable {
+            remaining.push(cb);
+            continue;
+        }
+        let time_left = time_left.unwrap_or_default().as_secs_f64() * 1000.0;
+        let args = vec![JsValue::Bool(timed_out), JsValue::Float(time_left)];
+        if let Err(err) = cb.callback.call_as_function(args) {
+            error!("idle callback error:{:?}", err);
+        }
+    }
+    let has_pending = IDLE_CALLBACKS.with_borrow_mut(|list| {
+        // Callbacks requested while running go after the skipped ones
+        remaining.append(list);
+        *list = remaining;
+        !list.is_empty()
+    });
+    if has_pending {
+        schedule_idle_check(IDLE_CHECK_INTERVAL);
+    }
+}
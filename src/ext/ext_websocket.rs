@@ -1,6 +1,7 @@
 use crate as deft;
 use crate::js::{JsError, ToJsValue};
 use crate::js_weak_value;
+use crate::network::connect_websocket;
 use anyhow::Error;
 use deft_macros::{js_methods, mrc_object};
 use futures_util::stream::{SplitSink, SplitStream};
@@ -14,7 +15,7 @@ use std::sync::Arc;
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
 use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
 thread_local! {
     pub static NEXT_ID: RefCell<u64> = RefCell::new(0);
@@ -23,6 +24,12 @@ thread_local! {
 
 type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
+pub fn close_all_connections() {
+    CONNECTIONS.with_borrow_mut(|map| {
+        map.clear();
+    });
+}
+
 #[mrc_object]
 pub struct WsConnection {
     id: u64,
@@ -43,7 +50,7 @@ impl WsConnection {
             *id += 1;
             *id - 1
         });
-        let (socket, _) = connect_async(url)
+        let socket = connect_websocket(&url)
             .await
             .map_err(|e| io::Error::new(ErrorKind::Other, e))?;
         let (writer, reader) = socket.split();
@@ -1,20 +1,34 @@
 use crate as deft;
-use log::{debug, warn};
+use deft_macros::js_methods;
+use log::{debug, error, warn};
+use quick_js::JsValue;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::collections::HashMap;
-use winit::event::WindowEvent;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{DeviceEvent, ElementState, WindowEvent};
+use winit::monitor::MonitorHandle;
 use winit::window::WindowId as WinitWindowId;
 
-use crate::window::WindowHandle;
-use crate::{js_deserialize, js_value};
+use crate::event_loop::run_with_event_loop;
+use crate::js::ToJsValue;
+use crate::timer::{set_timeout, TimerHandle};
+use crate::window::{WindowHandle, WindowType};
+use crate::{js_deserialize, js_serialize, js_value, some_or_return};
 
 thread_local! {
     pub static WINDOWS: RefCell<HashMap<i32, WindowHandle >> = RefCell::new(HashMap::new());
     pub static WINIT_TO_WINDOW: RefCell<HashMap<WinitWindowId, WindowHandle >> = RefCell::new(HashMap::new());
     pub static MODAL_TO_OWNERS: RefCell<HashMap<WinitWindowId, WindowHandle >> = RefCell::new(HashMap::new());
+    static MONITORS: RefCell<Option<Vec<MonitorInfo>>> = RefCell::new(None);
+    static MONITORS_CHANGE_HANDLER: RefCell<Option<JsValue>> = RefCell::new(None);
+    static MONITORS_POLL_TIMER: RefCell<Option<TimerHandle>> = RefCell::new(None);
 }
 
+/// Interval of checking monitors while a change handler is set, winit has no event for
+/// displays being added or removed
+const MONITORS_POLL_INTERVAL: u64 = 2000;
+
 pub const WINDOW_TYPE_NORMAL: &str = "normal";
 pub const WINDOW_TYPE_MENU: &str = "menu";
 
@@ -37,7 +51,7 @@ pub type ElementId = i32;
 
 pub type WindowId = i32;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WindowAttrs {
     pub width: Option<f32>,
@@ -53,17 +67,94 @@ pub struct WindowAttrs {
     pub minimizable: Option<bool>,
     pub maximizable: Option<bool>,
     pub closable: Option<bool>,
+    pub resize_margin: Option<f32>,
+    /// "all", "corners" or "none"
+    pub resize_edges: Option<String>,
+    /// Namespace of localstorage used by the window, see JsEngine::enable_localstorage_with_namespace
+    pub storage_namespace: Option<String>,
+    /// "srgb" or "display-p3", defaults to `DEFT_COLOR_SPACE` environment variable or "srgb"
+    pub color_space: Option<String>,
+    /// Make the background of window transparent so that content behind it shows through,
+    /// backends that can't composite alpha, e.g. softbuffer, are only used if no other works
+    pub transparent: Option<bool>,
+    /// Draw shadow around undecorated window, defaults to true if not transparent
+    pub shadow: Option<bool>,
+    /// Radius of corners of undecorated window in logical pixels
+    pub corner_radius: Option<f32>,
+    /// Material drawn behind the window, one of "blur", "acrylic", "mica", "vibrancy" and "none",
+    /// the window is transparent if specified
+    pub effect: Option<String>,
+    /// Shape of window as svg path in logical pixels, pixels out of it are transparent
+    pub shape: Option<String>,
+    /// Distance in logical pixels within which window dragged by `drag` snaps to screen edges
+    /// or other windows, disabled by default
+    pub snap_threshold: Option<f32>,
+    /// Maximum frame rate, frames are paced by refresh rate of monitor if not capped
+    pub max_fps: Option<f32>,
+    /// Frame rate while window is not focused, not throttled by default
+    pub unfocused_fps: Option<f32>,
 }
 
 js_deserialize!(WindowAttrs);
 
+/// Handle device events, e.g. close menus when pressing outside of them
+pub fn handle_device_event(event: DeviceEvent) {
+    if let DeviceEvent::MouseMotion { delta: (x, y) } = event {
+        let windows: Vec<WindowHandle> = WINDOWS.with_borrow(|m| m.values().cloned().collect());
+        for window in windows {
+            if let Ok(mut window) = window.upgrade_mut() {
+                window.handle_pointer_lock_motion(x, y);
+            }
+        }
+        return;
+    }
+    if let DeviceEvent::Button { state, .. } = event {
+        if state == ElementState::Pressed {
+            let close_windows: Vec<WindowHandle> = WINDOWS.with_borrow(|windows| {
+                windows
+                    .iter()
+                    .filter(|(_, f)| {
+                        f.upgrade_mut()
+                            .ok()
+                            .map(|f| f.window_type == WindowType::Menu)
+                            .unwrap_or(false)
+                    })
+                    .map(|(_, f)| f.clone())
+                    .filter(|w| {
+                        if let Ok(window) = w.upgrade_mut() {
+                            let w_size: PhysicalSize<i32> = window.window.outer_size().cast();
+                            if let Some(ptr_pos) = window.window.pointer_position() {
+                                let is_in_window = ptr_pos.x >= 0
+                                    && ptr_pos.x <= w_size.width
+                                    && ptr_pos.y >= 0
+                                    && ptr_pos.y <= w_size.height;
+                                if !is_in_window {
+                                    return true;
+                                }
+                            }
+                        }
+                        false
+                    })
+                    .collect()
+            });
+            for f in close_windows {
+                if let Ok(mut f) = f.upgrade_mut() {
+                    let _ = f.close();
+                }
+            }
+        }
+    }
+}
+
 pub fn handle_window_event(window_id: WinitWindowId, event: WindowEvent) {
     match &event {
         WindowEvent::Resized(_) => {}
         WindowEvent::Moved(_) => {}
         WindowEvent::Destroyed => {}
         WindowEvent::ModifiersChanged(_) => {}
-        WindowEvent::ScaleFactorChanged { .. } => {}
+        WindowEvent::ScaleFactorChanged { .. } => {
+            refresh_monitors();
+        }
         WindowEvent::ThemeChanged(_) => {}
         WindowEvent::Occluded(_) => {}
         WindowEvent::RedrawRequested => {}
@@ -92,7 +183,7 @@ pub fn handle_window_event(window_id: WinitWindowId, event: WindowEvent) {
     if let Some(window) = &mut window {
         if &WindowEvent::CloseRequested == &event {
             if let Ok(mut f) = window.upgrade_mut() {
-                let _ = f.close();
+                f.handle_close_request();
             }
         } else {
             if let Ok(mut window) = window.upgrade_mut() {
@@ -105,3 +196,151 @@ pub fn handle_window_event(window_id: WinitWindowId, event: WindowEvent) {
 }
 
 js_value!(WindowHandle);
+
+/// Display connected to the system, bounds are in logical pixels of the monitor
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    /// Index of monitor, see `Window.move_to_monitor`
+    pub index: usize,
+    pub name: Option<String>,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub scale_factor: f64,
+    /// Refresh rate in Hz if available
+    pub refresh_rate: Option<f32>,
+    pub primary: bool,
+}
+
+js_serialize!(MonitorInfo);
+
+impl MonitorInfo {
+    fn new(index: usize, monitor: &MonitorHandle, primary: bool) -> Self {
+        let scale_factor = monitor.scale_factor();
+        let position = monitor.position().to_logical::<f32>(scale_factor);
+        let size = monitor.size().to_logical::<f32>(scale_factor);
+        Self {
+            index,
+            name: monitor.name(),
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            scale_factor,
+            refresh_rate: monitor.refresh_rate_millihertz().map(|r| r as f32 / 1000.0),
+            primary,
+        }
+    }
+}
+
+/// Monitors in the order of `available_monitors`
+pub fn list_monitors() -> Vec<MonitorInfo> {
+    run_with_event_loop(|el| {
+        let primary = el.primary_monitor();
+        el.available_monitors()
+            .enumerate()
+            .map(|(i, m)| MonitorInfo::new(i, &m, primary.as_ref() == Some(&m)))
+            .collect()
+    })
+}
+
+/// Read monitors again, returns true and notifies the change handler if they changed
+pub fn refresh_monitors() -> bool {
+    let monitors = list_monitors();
+    let changed = MONITORS.with_borrow_mut(|m| {
+        let changed = m.as_ref().is_some_and(|m| m != &monitors);
+        *m = Some(monitors.clone());
+        changed
+    });
+    if changed {
+        call_monitors_change_handler(monitors);
+    }
+    changed
+}
+
+fn call_monitors_change_handler(monitors: Vec<MonitorInfo>) {
+    let handler = some_or_return!(MONITORS_CHANGE_HANDLER.with_borrow(|h| h.clone()));
+    let result = monitors
+        .to_js_value()
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|m| {
+            handler
+                .call_as_function(vec![m])
+                .map_err(|e| format!("{:?}", e))
+        });
+    if let Err(e) = result {
+        error!("Failed to call monitors change handler: {}", e);
+    }
+}
+
+fn schedule_monitors_poll() {
+    let timer = set_timeout(
+        || {
+            refresh_monitors();
+            if MONITORS_CHANGE_HANDLER.with_borrow(|h| h.is_some()) {
+                schedule_monitors_poll();
+            }
+        },
+        MONITORS_POLL_INTERVAL,
+    );
+    MONITORS_POLL_TIMER.set(Some(timer));
+}
+
+/// Position of window of size centered in monitor, the top-left corner is kept inside the
+/// monitor if the window is larger. All values are physical pixels.
+pub fn center_in_monitor(
+    monitor_position: PhysicalPosition<i32>,
+    monitor_size: PhysicalSize<u32>,
+    window_size: PhysicalSize<u32>,
+) -> PhysicalPosition<i32> {
+    let x = (monitor_size.width as i32 - window_size.width as i32) / 2;
+    let y = (monitor_size.height as i32 - window_size.height as i32) / 2;
+    PhysicalPosition::new(monitor_position.x + x.max(0), monitor_position.y + y.max(0))
+}
+
+#[allow(nonstandard_style)]
+pub struct screen;
+
+#[js_methods]
+impl screen {
+    #[js_func]
+    pub fn get_monitors() -> Vec<MonitorInfo> {
+        let monitors = list_monitors();
+        MONITORS.set(Some(monitors.clone()));
+        monitors
+    }
+
+    /// Handler is called with all monitors when displays are added, removed or changed
+    #[js_func]
+    pub fn set_change_handler(handler: JsValue) {
+        let handler = match handler {
+            JsValue::Null | JsValue::Undefined => None,
+            h => Some(h),
+        };
+        if handler.is_some() {
+            MONITORS.set(Some(list_monitors()));
+            schedule_monitors_poll();
+        } else {
+            MONITORS_POLL_TIMER.set(None);
+        }
+        MONITORS_CHANGE_HANDLER.set(handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::ext_window::center_in_monitor;
+    use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+    #[test]
+    fn test_center_in_monitor() {
+        let origin = PhysicalPosition::new(1920, 0);
+        let monitor = PhysicalSize::new(1280, 1024);
+        let p = center_in_monitor(origin, monitor, PhysicalSize::new(800, 600));
+        assert_eq!(p, PhysicalPosition::new(2160, 212));
+        let p = center_in_monitor(origin, monitor, PhysicalSize::new(2000, 600));
+        assert_eq!(p, PhysicalPosition::new(1920, 212));
+    }
+}
@@ -12,6 +12,8 @@ pub mod ext_clipboard;
 pub mod ext_console;
 #[cfg(feature = "dialog")]
 pub mod ext_dialog;
+#[cfg(feature = "http")]
+pub mod ext_download;
 pub mod ext_env;
 #[cfg(feature = "http")]
 pub mod ext_fetch;
@@ -19,9 +21,20 @@ pub mod ext_fetch;
 pub mod ext_fs;
 #[cfg(feature = "http")]
 pub mod ext_http;
+#[cfg(feature = "iap")]
+pub mod ext_iap;
+#[cfg(feature = "sqlite")]
+pub mod ext_large_storage;
 pub mod ext_localstorage;
+pub mod ext_menu;
+pub mod ext_metrics;
+#[cfg(feature = "oauth")]
+pub mod ext_oauth;
+#[cfg(feature = "sqlite")]
+pub mod ext_object_store;
 pub mod ext_path;
 pub mod ext_process;
+pub mod ext_recovery;
 pub mod ext_shell;
 #[cfg(feature = "sqlite")]
 pub mod ext_sqlite;
@@ -7,6 +7,7 @@ use crate::js::js_event_loop::{js_init_event_loop, JsEvent, JsEventLoopClosedErr
 use crate::js::ToJsValue;
 use log::error;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{SendError, Sender};
 use std::sync::{Arc, LazyLock, Mutex};
 use std::thread;
@@ -29,6 +30,7 @@ pub struct Service {
     id: u32,
     sender: Arc<Mutex<Option<Sender<JsEvent>>>>,
     msg_handlers: Arc<Mutex<IdHashMap<Box<dyn FnMut(crate::ext::ext_worker::MessageData) + Send>>>>,
+    stopped: Arc<AtomicBool>,
 }
 
 impl Service {
@@ -48,6 +50,7 @@ impl Service {
             id,
             sender: Arc::new(Mutex::new(None)),
             msg_handlers: msg_handlers.clone(),
+            stopped: Arc::new(AtomicBool::new(false)),
         };
         {
             let mut services = SERVICES.lock().unwrap();
@@ -64,6 +67,7 @@ impl Service {
             sender_holder.replace(sender.clone());
         }
         let msg_handlers = self.msg_handlers.clone();
+        let stopped = self.stopped.clone();
         let _ = thread::Builder::new()
             .name("js-worker".to_string())
             .spawn(move || {
@@ -114,6 +118,9 @@ impl Service {
                         }
                     }
                     js_engine.execute_pending_jobs();
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
                 }
             });
     }
@@ -122,6 +129,24 @@ impl Service {
         self.id
     }
 
+    /// Stop the event loop of service after pending events handled
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let _ = self.send_event(JsEvent::MacroTask(Box::new(|| {})));
+        let mut services = SERVICES.lock().unwrap();
+        services.services.remove(&self.id);
+    }
+
+    pub fn stop_all() {
+        let services: Vec<Service> = {
+            let services = SERVICES.lock().unwrap();
+            services.services.values().cloned().collect()
+        };
+        for s in services {
+            s.stop();
+        }
+    }
+
     pub fn send_event(&self, event: JsEvent) -> Result<(), SendError<JsEvent>> {
         let sender = self.sender.lock().unwrap();
         if let Some(sender) = sender.as_ref() {
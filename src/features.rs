@@ -0,0 +1,44 @@
+use crate as deft;
+use deft_macros::js_func;
+
+/// Version of deft
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Cargo features of deft enabled at build time
+pub fn enabled_features() -> Vec<&'static str> {
+    let features = [
+        ("websocket", cfg!(feature = "websocket")),
+        ("http", cfg!(feature = "http")),
+        ("tray", cfg!(feature = "tray")),
+        ("menu", cfg!(feature = "menu")),
+        ("clipboard", cfg!(feature = "clipboard")),
+        ("sqlite", cfg!(feature = "sqlite")),
+        ("iap", cfg!(feature = "iap")),
+        ("oauth", cfg!(feature = "oauth")),
+        ("audio", cfg!(feature = "audio")),
+        ("video", cfg!(feature = "video")),
+        ("gl", cfg!(feature = "gl")),
+        ("dialog", cfg!(feature = "dialog")),
+        ("devtools", cfg!(feature = "devtools")),
+        ("inspector", cfg!(feature = "inspector")),
+        ("debugger", cfg!(feature = "debugger")),
+    ];
+    features
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[js_func]
+pub fn app_version() -> String {
+    VERSION.to_string()
+}
+
+#[js_func]
+pub fn app_features() -> Vec<String> {
+    enabled_features()
+        .into_iter()
+        .map(|f| f.to_string())
+        .collect()
+}
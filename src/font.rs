@@ -1,17 +1,28 @@
+pub mod fallback;
 pub mod family;
 
 use crate as deft;
 use crate::mrc::Mrc;
 use deft_macros::mrc_object;
 use memmap2::{Mmap, MmapOptions};
-use skia_safe::Rect;
+use skia_safe::{surfaces, AlphaType, ColorType, ImageInfo, Paint, Rect, TextBlob, Typeface};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs::File;
 use std::ops::Deref;
 use std::path::Path;
-use swash::scale::image::Image;
+use swash::scale::image::{Content, Image};
 use swash::scale::{Render, ScaleContext, Source, StrikeWith};
-use swash::zeno::{Angle, Format, Transform};
-use swash::{Attributes, CacheKey, Charmap, FontRef, GlyphId, Metrics, Style, Weight};
+use swash::zeno::{Angle, Format, Placement, Stroke, Transform};
+use swash::{
+    tag_from_bytes, Attributes, CacheKey, Charmap, FontRef, GlyphId, Metrics, Style, Weight,
+};
+
+thread_local! {
+    /// Skia typefaces of fonts with glyphs unsupported by swash
+    static SKIA_TYPEFACES: RefCell<HashMap<CacheKey, Option<Typeface>>> =
+        RefCell::new(HashMap::new());
+}
 
 enum FontContent {
     Mmap(Mmap),
@@ -33,6 +44,8 @@ pub struct Font {
     data: Mrc<FontContent>,
     // Offset to the table directory
     offset: u32,
+    // Index of the font in collection
+    index: usize,
     // Cache key
     key: CacheKey,
 
@@ -41,6 +54,9 @@ pub struct Font {
     weight: Weight,
 
     style: Style,
+
+    // Whether color glyphs are defined by COLR tables of version 1, which swash can't render
+    colr_v1: bool,
 }
 
 unsafe impl Send for Font {}
@@ -62,14 +78,20 @@ impl Font {
         let weight = font.attributes().weight();
         let style = font.attributes().style();
         let (offset, key) = (font.offset, font.key);
+        let colr_v1 = font
+            .table(tag_from_bytes(b"COLR"))
+            .map(|t| t.len() >= 2 && u16::from_be_bytes([t[0], t[1]]) >= 1)
+            .unwrap_or(false);
         Some(
             FontData {
                 data: Mrc::new(data),
                 offset,
+                index,
                 key,
                 family_name,
                 weight,
                 style,
+                colr_v1,
             }
             .to_ref(),
         )
@@ -80,10 +102,12 @@ impl Font {
         FontData {
             data,
             offset: self.offset,
+            index: self.index,
             key: self.key,
             family_name: self.family_name.clone(),
             weight,
             style,
+            colr_v1: self.colr_v1,
         }
         .to_ref()
     }
@@ -115,22 +139,51 @@ impl Font {
         Some(self.charmap().map(c))
     }
 
+    /// Whether the font has color glyphs, e.g. emoji fonts with CBDT, sbix or COLR tables
+    pub fn has_color_glyphs(&self) -> bool {
+        let font = self.as_ref();
+        [b"CBDT", b"sbix", b"COLR"]
+            .iter()
+            .any(|tag| font.table(tag_from_bytes(tag)).is_some())
+    }
+
     pub fn rasterize_glyph(&self, glyph_id: GlyphId, font_size: f32) -> Option<Image> {
+        self.rasterize_glyph_with_stroke(glyph_id, font_size, None)
+    }
+
+    /// Rasterize the stroked outline of glyph if stroke_width is specified
+    pub fn rasterize_glyph_with_stroke(
+        &self,
+        glyph_id: GlyphId,
+        font_size: f32,
+        stroke_width: Option<f32>,
+    ) -> Option<Image> {
+        if self.colr_v1 && stroke_width.is_none() {
+            if let Some(image) = self.rasterize_with_skia(glyph_id, font_size) {
+                return Some(image);
+            }
+        }
         let mut context = ScaleContext::new();
         let mut scaler = context
             .builder(self.as_ref())
             .size(font_size)
             .hint(true)
             .build();
-        let mut render = Render::new(&[
-            // Color outline with the first palette
-            Source::ColorOutline(0),
-            Source::Bitmap(StrikeWith::BestFit),
-            // Color bitmap with best fit selection mode
-            Source::ColorBitmap(StrikeWith::BestFit),
-            // Standard scalable outline
-            Source::Outline,
-        ]);
+        let sources: &[Source] = if stroke_width.is_some() {
+            // Only outlines could be stroked
+            &[Source::Outline]
+        } else {
+            &[
+                // Color outline with the first palette
+                Source::ColorOutline(0),
+                Source::Bitmap(StrikeWith::BestFit),
+                // Color bitmap with best fit selection mode
+                Source::ColorBitmap(StrikeWith::BestFit),
+                // Standard scalable outline
+                Source::Outline,
+            ]
+        };
+        let mut render = Render::new(sources);
         // Select a subpixel format
         let font_attrs = self.as_ref().attributes();
         if font_attrs.weight() != self.weight {
@@ -150,10 +203,61 @@ impl Font {
             }
         }
 
+        if let Some(width) = stroke_width {
+            render.style(Stroke::new(width));
+        }
+
         // Render the image
         render.format(Format::Alpha).render(&mut scaler, glyph_id)
     }
 
+    /// Rasterize color glyph with skia, which supports COLRv1 glyphs unsupported by swash
+    fn rasterize_with_skia(&self, glyph_id: GlyphId, font_size: f32) -> Option<Image> {
+        let typeface = SKIA_TYPEFACES.with_borrow_mut(|m| {
+            m.entry(self.key)
+                .or_insert_with(|| {
+                    skia_safe::FontMgr::new().new_from_data(self.data.as_ref(), self.index)
+                })
+                .clone()
+        })?;
+        let font = skia_safe::Font::from_typeface(typeface, font_size);
+        let glyphs = [glyph_id];
+        let mut bounds = [Rect::default()];
+        font.get_bounds(&glyphs, &mut bounds, None);
+        let bounds = bounds[0].round_out();
+        if bounds.is_empty() {
+            return None;
+        }
+        let (width, height) = (bounds.width(), bounds.height());
+        let mut surface = surfaces::raster_n32_premul((width, height))?;
+        let blob = TextBlob::from_text(&glyphs[..], &font)?;
+        let origin = (-bounds.left as f32, -bounds.top as f32);
+        surface
+            .canvas()
+            .draw_text_blob(&blob, origin, &Paint::default());
+        let info = ImageInfo::new(
+            (width, height),
+            ColorType::RGBA8888,
+            AlphaType::Unpremul,
+            None,
+        );
+        let mut data = vec![0u8; (width * height * 4) as usize];
+        if !surface.read_pixels(&info, &mut data, width as usize * 4, (0, 0)) {
+            return None;
+        }
+        let mut image = Image::new();
+        image.source = Source::ColorOutline(0);
+        image.content = Content::Color;
+        image.placement = Placement {
+            left: bounds.left,
+            top: -bounds.top,
+            width: width as u32,
+            height: height as u32,
+        };
+        image.data = data;
+        Some(image)
+    }
+
     /// Just for debug
     pub fn name(&self) -> &str {
         &self.family_name
@@ -0,0 +1,262 @@
+use crate::text::textbox::DEFAULT_FALLBACK_FONTS;
+use std::cell::RefCell;
+
+thread_local! {
+    static FALLBACK_FONTS: RefCell<Vec<String>> = RefCell::new(default_fallback_fonts());
+}
+
+/// Scripts which usually need fonts other than the font of latin text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    Emoji,
+    Symbol,
+    Han,
+    Kana,
+    Hangul,
+    Arabic,
+    Hebrew,
+    Thai,
+    Devanagari,
+    Bengali,
+    Tamil,
+    Other,
+}
+
+pub fn script_of(ch: char) -> Script {
+    match ch as u32 {
+        0x0590..=0x05FF | 0xFB1D..=0xFB4F => Script::Hebrew,
+        0x0600..=0x06FF | 0x0750..=0x077F | 0x08A0..=0x08FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => {
+            Script::Arabic
+        }
+        0x0900..=0x097F | 0xA8E0..=0xA8FF => Script::Devanagari,
+        0x0980..=0x09FF => Script::Bengali,
+        0x0B80..=0x0BFF => Script::Tamil,
+        0x0E00..=0x0E7F => Script::Thai,
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xA960..=0xA97F | 0xAC00..=0xD7FF => Script::Hangul,
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F | 0x1B000..=0x1B16F => Script::Kana,
+        0x2E80..=0x2FDF
+        | 0x3000..=0x303F
+        | 0x3190..=0x31EF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xF900..=0xFAFF
+        | 0xFF00..=0xFF65
+        | 0x20000..=0x3134F => Script::Han,
+        0x2190..=0x21FF | 0x2300..=0x23FF | 0x25A0..=0x27BF | 0x2900..=0x2BFF => Script::Symbol,
+        0x1F000..=0x1FAFF => Script::Emoji,
+        _ => Script::Other,
+    }
+}
+
+/// Whether the char is rendered as emoji without variation selector
+pub fn is_emoji_presentation(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x231A..=0x231B
+            | 0x23E9..=0x23EC
+            | 0x23F0
+            | 0x23F3
+            | 0x25FD..=0x25FE
+            | 0x2614..=0x2615
+            | 0x2648..=0x2653
+            | 0x267F
+            | 0x2693
+            | 0x26A1
+            | 0x26AA..=0x26AB
+            | 0x26BD..=0x26BE
+            | 0x26C4..=0x26C5
+            | 0x26CE
+            | 0x26D4
+            | 0x26EA
+            | 0x26F2..=0x26F3
+            | 0x26F5
+            | 0x26FA
+            | 0x26FD
+            | 0x2705
+            | 0x270A..=0x270B
+            | 0x2728
+            | 0x274C
+            | 0x274E
+            | 0x2753..=0x2755
+            | 0x2757
+            | 0x2795..=0x2797
+            | 0x27B0
+            | 0x27BF
+            | 0x2B1B..=0x2B1C
+            | 0x2B50
+            | 0x2B55
+            | 0x1F004
+            | 0x1F0CF
+            | 0x1F18E
+            | 0x1F191..=0x1F19A
+            | 0x1F1E6..=0x1F1FF
+            | 0x1F201
+            | 0x1F21A
+            | 0x1F22F
+            | 0x1F232..=0x1F236
+            | 0x1F238..=0x1F23A
+            | 0x1F250..=0x1F251
+            | 0x1F300..=0x1F64F
+            | 0x1F680..=0x1F6FF
+            | 0x1F7E0..=0x1F7F0
+            | 0x1F90C..=0x1F9FF
+            | 0x1FA70..=0x1FAFF
+    )
+}
+
+/// Chars joining or modifying the preceding emoji, e.g. zero width joiner, variation
+/// selectors, skin tone modifiers, keycap and tags
+pub fn is_emoji_component(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x200D | 0xFE0E | 0xFE0F | 0x20E3 | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F
+    )
+}
+
+/// Script used to find fallback font of the char at index, chars followed by the emoji
+/// variation selector are emoji even if they are text by default
+pub fn fallback_script(chars: &[char], index: usize) -> Script {
+    let ch = chars[index];
+    match chars.get(index + 1) {
+        Some('\u{FE0F}') => Script::Emoji,
+        Some('\u{FE0E}') => script_of(ch),
+        _ if is_emoji_presentation(ch) => Script::Emoji,
+        _ => script_of(ch),
+    }
+}
+
+/// Fallback fonts of the script which are usually installed on the platform
+pub fn script_fallback_families(script: Script) -> &'static [&'static str] {
+    platform::script_fallback_families(script)
+}
+
+/// Families tried in order when chars are missing in the fonts specified by styles
+pub fn get_fallback_fonts() -> Vec<String> {
+    FALLBACK_FONTS.with_borrow(|f| f.clone())
+}
+
+/// Replace the fallback font list, an empty list restores the default list.
+/// Only affects text laid out afterwards.
+pub fn set_fallback_fonts(families: Vec<String>) {
+    let families = if families.is_empty() {
+        default_fallback_fonts()
+    } else {
+        families
+    };
+    FALLBACK_FONTS.set(families);
+}
+
+fn default_fallback_fonts() -> Vec<String> {
+    DEFAULT_FALLBACK_FONTS
+        .split(",")
+        .map(|s| s.to_string())
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use crate::font::fallback::Script;
+
+    pub fn script_fallback_families(script: Script) -> &'static [&'static str] {
+        match script {
+            Script::Emoji => &["Segoe UI Emoji", "Segoe UI Symbol"],
+            Script::Symbol => &["Segoe UI Symbol", "Segoe UI Emoji", "Cambria Math"],
+            Script::Han => &["Microsoft YaHei", "Microsoft JhengHei", "SimSun"],
+            Script::Kana => &["Yu Gothic UI", "Meiryo", "MS Gothic"],
+            Script::Hangul => &["Malgun Gothic", "Gulim"],
+            Script::Arabic => &["Segoe UI", "Tahoma"],
+            Script::Hebrew => &["Segoe UI", "Tahoma"],
+            Script::Thai => &["Leelawadee UI", "Tahoma"],
+            Script::Devanagari | Script::Bengali | Script::Tamil => &["Nirmala UI"],
+            Script::Other => &["Segoe UI", "Arial Unicode MS"],
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod platform {
+    use crate::font::fallback::Script;
+
+    pub fn script_fallback_families(script: Script) -> &'static [&'static str] {
+        match script {
+            Script::Emoji => &["Apple Color Emoji"],
+            Script::Symbol => &["Apple Symbols", "Apple Color Emoji", "Menlo"],
+            Script::Han => &["PingFang SC", "PingFang TC", "Heiti SC", "Hiragino Sans GB"],
+            Script::Kana => &["Hiragino Sans", "Hiragino Kaku Gothic ProN"],
+            Script::Hangul => &["Apple SD Gothic Neo"],
+            Script::Arabic => &["Geeza Pro", "SF Arabic"],
+            Script::Hebrew => &["Arial Hebrew", "SF Hebrew"],
+            Script::Thai => &["Thonburi"],
+            Script::Devanagari => &["Kohinoor Devanagari", "Devanagari Sangam MN"],
+            Script::Bengali => &["Kohinoor Bangla", "Bangla Sangam MN"],
+            Script::Tamil => &["Tamil Sangam MN"],
+            Script::Other => &["Arial Unicode MS", "Lucida Grande"],
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "ios")))]
+mod platform {
+    use crate::font::fallback::Script;
+
+    pub fn script_fallback_families(script: Script) -> &'static [&'static str] {
+        match script {
+            Script::Emoji => &["Noto Color Emoji", "Twemoji", "Noto Emoji"],
+            Script::Symbol => &["Noto Sans Symbols", "Noto Sans Symbols 2", "DejaVu Sans"],
+            Script::Han => &[
+                "Noto Sans CJK SC",
+                "Noto Sans CJK TC",
+                "Source Han Sans SC",
+                "WenQuanYi Micro Hei",
+            ],
+            Script::Kana => &["Noto Sans CJK JP", "Source Han Sans JP", "IPAGothic"],
+            Script::Hangul => &["Noto Sans CJK KR", "Source Han Sans KR", "NanumGothic"],
+            Script::Arabic => &["Noto Sans Arabic", "Noto Naskh Arabic", "DejaVu Sans"],
+            Script::Hebrew => &["Noto Sans Hebrew", "DejaVu Sans"],
+            Script::Thai => &["Noto Sans Thai", "Noto Sans Thai Looped"],
+            Script::Devanagari => &["Noto Sans Devanagari", "Lohit Devanagari"],
+            Script::Bengali => &["Noto Sans Bengali", "Lohit Bengali"],
+            Script::Tamil => &["Noto Sans Tamil", "Lohit Tamil"],
+            Script::Other => &["Noto Sans", "DejaVu Sans"],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::font::fallback::{
+        fallback_script, get_fallback_fonts, is_emoji_component, script_of, set_fallback_fonts,
+        Script,
+    };
+
+    #[test]
+    fn test_script_of() {
+        assert_eq!(Script::Other, script_of('a'));
+        assert_eq!(Script::Han, script_of('中'));
+        assert_eq!(Script::Kana, script_of('カ'));
+        assert_eq!(Script::Hangul, script_of('한'));
+        assert_eq!(Script::Arabic, script_of('ع'));
+        assert_eq!(Script::Emoji, script_of('😀'));
+        assert_eq!(Script::Symbol, script_of('❤'));
+    }
+
+    #[test]
+    fn test_fallback_script() {
+        let chars = "❤️❤😀\u{FE0E}".chars().collect::<Vec<_>>();
+        assert_eq!(Script::Emoji, fallback_script(&chars, 0));
+        assert_eq!(Script::Symbol, fallback_script(&chars, 2));
+        assert_eq!(Script::Emoji, fallback_script(&chars, 3));
+        assert!(is_emoji_component(chars[1]));
+        assert!(is_emoji_component('\u{1F3FB}'));
+        assert!(!is_emoji_component('😀'));
+    }
+
+    #[test]
+    fn test_fallback_fonts() {
+        let default = get_fallback_fonts();
+        set_fallback_fonts(vec!["Test Sans".to_string()]);
+        assert_eq!(vec!["Test Sans".to_string()], get_fallback_fonts());
+        set_fallback_fonts(Vec::new());
+        assert_eq!(default, get_fallback_fonts());
+    }
+}
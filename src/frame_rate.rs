@@ -1,12 +1,14 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 const NANOS_PER_MILLI: u32 = 1_000_000;
 const NANOS_PER_FRAME: u64 = 16_666_666;
+pub const DEFAULT_FPS: f32 = 60.0;
 
 pub struct FrameRateController {
     start_time: Instant,
     current_frame: u64,
     total_frames: u64,
+    nanos_per_frame: u64,
 }
 
 impl FrameRateController {
@@ -15,23 +17,73 @@ impl FrameRateController {
             start_time: Instant::now(),
             current_frame: 0,
             total_frames: 0,
+            nanos_per_frame: NANOS_PER_FRAME,
         }
     }
 
+    pub fn get_fps(&self) -> f32 {
+        (1_000_000_000.0 / self.nanos_per_frame as f64) as f32
+    }
+
+    /// Change frame rate, frame numbers restart from now. Returns whether frame rate is changed.
+    pub fn set_fps(&mut self, fps: f32) -> bool {
+        let nanos_per_frame = (1_000_000_000.0 / fps.max(1.0) as f64) as u64;
+        if nanos_per_frame == self.nanos_per_frame {
+            return false;
+        }
+        self.nanos_per_frame = nanos_per_frame;
+        self.start_time = Instant::now();
+        self.current_frame = 0;
+        true
+    }
+
     /// Advance frame and return time to wait as nanos.
     pub fn next_frame(&mut self) -> u64 {
         let now = Instant::now();
         let mut next_frame_no =
-            now.duration_since(self.start_time).as_nanos() as u64 / NANOS_PER_FRAME + 1;
+            now.duration_since(self.start_time).as_nanos() as u64 / self.nanos_per_frame + 1;
         if next_frame_no == self.current_frame {
             next_frame_no += 1;
         }
         self.total_frames += 1;
-        let next_frame_time_nano = next_frame_no * NANOS_PER_FRAME;
+        let next_frame_time_nano = next_frame_no * self.nanos_per_frame;
         let now_nano = now.duration_since(self.start_time).as_nanos() as u64;
         self.current_frame = next_frame_no;
         next_frame_time_nano - now_nano
     }
+
+    /// Number of frames missed by a frame which took `elapsed` to produce
+    pub fn dropped_frames(&self, elapsed: Duration) -> u64 {
+        elapsed.as_nanos() as u64 / self.nanos_per_frame
+    }
+
+    /// Time left before the next frame should start
+    pub fn time_to_next_frame(&self) -> Duration {
+        let elapsed = Instant::now().duration_since(self.start_time).as_nanos() as u64;
+        let next_frame_nano = (elapsed / self.nanos_per_frame + 1) * self.nanos_per_frame;
+        Duration::from_nanos(next_frame_nano - elapsed)
+    }
+}
+
+/// Frame rate a window is paced at before caps, DEFAULT_FPS is used if the refresh rate of
+/// monitor is unknown
+pub fn target_fps(refresh_rate: Option<f32>) -> f32 {
+    refresh_rate.filter(|fps| *fps > 0.0).unwrap_or(DEFAULT_FPS)
+}
+
+/// Frame rate of a window, `max_fps` caps the target and `unfocused_fps` throttles it further
+/// while the window is not focused
+pub fn effective_fps(
+    target_fps: f32,
+    max_fps: Option<f32>,
+    unfocused_fps: Option<f32>,
+    focused: bool,
+) -> f32 {
+    let fps = max_fps.map_or(target_fps, |max_fps| target_fps.min(max_fps));
+    match unfocused_fps {
+        Some(unfocused_fps) if !focused => fps.min(unfocused_fps),
+        _ => fps,
+    }
 }
 
 #[test]
@@ -40,4 +92,34 @@ pub fn test_next_frame() {
     let time = controller.next_frame();
     assert!(time > 0);
     assert!(time < NANOS_PER_FRAME * 2);
+    assert!(controller.time_to_next_frame().as_nanos() as u64 <= NANOS_PER_FRAME);
+
+    assert!(!controller.set_fps(DEFAULT_FPS));
+    assert!(controller.set_fps(5.0));
+    assert_eq!(5.0, controller.get_fps());
+    let time = controller.next_frame();
+    assert!(time > NANOS_PER_FRAME);
+    assert!(time <= 200_000_000);
+    assert_eq!(0, controller.dropped_frames(Duration::from_millis(150)));
+    assert_eq!(2, controller.dropped_frames(Duration::from_millis(450)));
+}
+
+#[test]
+pub fn test_effective_fps() {
+    assert_eq!(
+        DEFAULT_FPS,
+        effective_fps(DEFAULT_FPS, None, Some(5.0), true)
+    );
+    assert_eq!(5.0, effective_fps(DEFAULT_FPS, None, Some(5.0), false));
+    assert_eq!(30.0, effective_fps(DEFAULT_FPS, Some(30.0), None, false));
+    assert_eq!(2.0, effective_fps(DEFAULT_FPS, Some(2.0), Some(5.0), false));
+    assert_eq!(144.0, effective_fps(144.0, None, None, true));
+    assert_eq!(120.0, effective_fps(144.0, Some(120.0), None, true));
+}
+
+#[test]
+pub fn test_target_fps() {
+    assert_eq!(DEFAULT_FPS, target_fps(None));
+    assert_eq!(DEFAULT_FPS, target_fps(Some(0.0)));
+    assert_eq!(144.0, target_fps(Some(144.0)));
 }
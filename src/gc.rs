@@ -0,0 +1,242 @@
+use crate as deft;
+use crate::ext::ext_timer::idle_period;
+use crate::img_manager::IMG_MANAGER;
+use crate::js::js_engine::JsEngine;
+use crate::text::textbox;
+use crate::timer::{set_timeout, TimerHandle};
+use crate::{js_serialize, metrics, some_or_return};
+use deft_macros::js_methods;
+use log::{debug, error};
+use quick_js::JsValue;
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+/// Delay after a frame is painted before looking for an idle period, in milliseconds
+const IDLE_GC_DELAY: u64 = 200;
+/// Interval to look for an idle period again while windows are busy, in milliseconds
+const IDLE_GC_RETRY_INTERVAL: u64 = 50;
+/// Idle collections are at least this far apart
+const MIN_IDLE_GC_INTERVAL: Duration = Duration::from_secs(2);
+/// Collection runs in any idle period, even a short one, if it has been deferred this long
+const MAX_IDLE_GC_DEFERRAL: Duration = Duration::from_secs(10);
+/// Weight of the latest collection in the estimated duration
+const DURATION_SMOOTHING: f32 = 0.3;
+
+thread_local! {
+    static GC_STATE: RefCell<GcState> = RefCell::new(GcState::new());
+    static IDLE_GC_TIMER: RefCell<Option<TimerHandle>> = RefCell::new(None);
+    static IDLE_GC_ENABLED: Cell<bool> = Cell::new(true);
+    static MEMORY_PRESSURE_HANDLER: RefCell<Option<JsValue>> = RefCell::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GcReason {
+    /// Scheduled between frames
+    Idle,
+    /// Requested by `deft.gc()`
+    Forced,
+    MemoryPressure,
+}
+
+/// Statistics of js garbage collections
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GcStats {
+    pub count: u32,
+    pub idle_count: u32,
+    pub forced_count: u32,
+    pub memory_pressure_count: u32,
+    /// In milliseconds
+    pub total_time: f32,
+    /// In milliseconds
+    pub last_time: f32,
+    /// In milliseconds
+    pub max_time: f32,
+    /// Frames painted since the last collection
+    pub frames_since_last: u32,
+}
+
+js_serialize!(GcStats);
+
+struct GcState {
+    stats: GcStats,
+    last_gc_time: Instant,
+    /// Expected duration of next collection in milliseconds
+    estimated_time: f32,
+}
+
+impl GcState {
+    fn new() -> Self {
+        Self {
+            stats: GcStats::default(),
+            last_gc_time: Instant::now(),
+            estimated_time: 0.0,
+        }
+    }
+
+    /// Whether a collection fits in `idle_period`, which is None if any window is busy
+    fn should_run_idle_gc(&self, now: Instant, idle_period: Option<Duration>) -> bool {
+        let idle_period = some_or_return!(idle_period, false);
+        let since_last = now.saturating_duration_since(self.last_gc_time);
+        if self.stats.frames_since_last == 0 || since_last < MIN_IDLE_GC_INTERVAL {
+            return false;
+        }
+        since_last >= MAX_IDLE_GC_DEFERRAL
+            || idle_period.as_secs_f32() * 1000.0 >= self.estimated_time
+    }
+
+    fn record(&mut self, reason: GcReason, time: f32, now: Instant) {
+        let stats = &mut self.stats;
+        stats.count += 1;
+        match reason {
+            GcReason::Idle => stats.idle_count += 1,
+            GcReason::Forced => stats.forced_count += 1,
+            GcReason::MemoryPressure => stats.memory_pressure_count += 1,
+        }
+        stats.total_time += time;
+        stats.last_time = time;
+        stats.max_time = stats.max_time.max(time);
+        stats.frames_since_last = 0;
+        self.estimated_time = if stats.count == 1 {
+            time
+        } else {
+            self.estimated_time + (time - self.estimated_time) * DURATION_SMOOTHING
+        };
+        self.last_gc_time = now;
+    }
+}
+
+/// Collect garbage of js heap now, returns its duration in milliseconds
+pub fn run_gc(reason: GcReason) -> f32 {
+    let start = Instant::now();
+    if let Some(js_engine) = JsEngine::try_get() {
+        js_engine.js_context.run_gc();
+    }
+    let time = start.elapsed().as_secs_f32() * 1000.0;
+    debug!("js gc ({:?}) took {:.2}ms", reason, time);
+    metrics::timing(metrics::GC_TIME, time as f64);
+    GC_STATE.with_borrow_mut(|s| s.record(reason, time, Instant::now()));
+    time
+}
+
+/// Called when a frame is painted, so that a collection is scheduled once windows are idle
+pub fn on_frame_painted() {
+    GC_STATE.with_borrow_mut(|s| s.stats.frames_since_last += 1);
+    schedule_idle_gc(IDLE_GC_DELAY);
+}
+
+fn schedule_idle_gc(delay: u64) {
+    if !IDLE_GC_ENABLED.get() {
+        return;
+    }
+    IDLE_GC_TIMER.with_borrow_mut(|timer| {
+        if timer.is_none() {
+            *timer = Some(set_timeout(run_idle_gc, delay));
+        }
+    });
+}
+
+fn run_idle_gc() {
+    IDLE_GC_TIMER.with_borrow_mut(|timer| timer.take());
+    let period = idle_period();
+    if GC_STATE.with_borrow(|s| s.should_run_idle_gc(Instant::now(), period)) {
+        run_gc(GcReason::Idle);
+    } else if GC_STATE.with_borrow(|s| s.stats.frames_since_last > 0) {
+        schedule_idle_gc(IDLE_GC_RETRY_INTERVAL);
+    }
+}
+
+/// Release cached images and layer surfaces and collect garbage, then notify js, e.g. on
+/// onTrimMemory of android
+pub fn handle_memory_pressure() {
+    skia_window::pool::trim();
+    IMG_MANAGER.with(|im| im.trim());
+    textbox::clear_inline_image_cache();
+    skia_safe::graphics::purge_all_caches();
+    run_gc(GcReason::MemoryPressure);
+    let handler = some_or_return!(MEMORY_PRESSURE_HANDLER.with_borrow(|h| h.clone()));
+    if let Err(e) = handler.call_as_function(vec![]) {
+        error!("Failed to call memory pressure handler: {:?}", e);
+    }
+}
+
+#[allow(nonstandard_style)]
+pub struct gc;
+
+#[js_methods]
+impl gc {
+    #[js_func]
+    pub fn run() -> f32 {
+        run_gc(GcReason::Forced)
+    }
+
+    #[js_func]
+    pub fn get_stats() -> GcStats {
+        GC_STATE.with_borrow(|s| s.stats.clone())
+    }
+
+    /// Whether to collect garbage automatically in idle periods between frames
+    #[js_func]
+    pub fn set_idle_enabled(enabled: bool) {
+        IDLE_GC_ENABLED.set(enabled);
+        if !enabled {
+            IDLE_GC_TIMER.with_borrow_mut(|timer| timer.take());
+        }
+    }
+
+    /// Handler is called after caches are trimmed and garbage is collected on memory pressure
+    #[js_func]
+    pub fn set_memory_pressure_handler(handler: JsValue) {
+        let handler = match handler {
+            JsValue::Null | JsValue::Undefined => None,
+            h => Some(h),
+        };
+        MEMORY_PRESSURE_HANDLER.set(handler);
+    }
+
+    /// Simulate memory pressure
+    #[js_func]
+    pub fn trim_memory() {
+        handle_memory_pressure();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gc::{GcReason, GcState, MAX_IDLE_GC_DEFERRAL, MIN_IDLE_GC_INTERVAL};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_should_run_idle_gc() {
+        let mut state = GcState::new();
+        let start = Instant::now();
+        state.record(GcReason::Forced, 20.0, start);
+        let idle = Some(Duration::from_millis(50));
+        let after = start + MIN_IDLE_GC_INTERVAL;
+        // Nothing painted since last collection
+        assert!(!state.should_run_idle_gc(after, idle));
+        state.stats.frames_since_last = 1;
+        assert!(!state.should_run_idle_gc(start, idle));
+        assert!(!state.should_run_idle_gc(after, None));
+        assert!(state.should_run_idle_gc(after, idle));
+        // Too short for the estimated duration until deferred for long
+        let short = Some(Duration::from_millis(10));
+        assert!(!state.should_run_idle_gc(after, short));
+        assert!(state.should_run_idle_gc(start + MAX_IDLE_GC_DEFERRAL, short));
+    }
+
+    #[test]
+    fn test_record() {
+        let mut state = GcState::new();
+        let now = Instant::now();
+        state.record(GcReason::Idle, 10.0, now);
+        state.record(GcReason::MemoryPressure, 20.0, now);
+        assert_eq!(state.stats.count, 2);
+        assert_eq!(state.stats.idle_count, 1);
+        assert_eq!(state.stats.memory_pressure_count, 1);
+        assert_eq!(state.stats.max_time, 20.0);
+        assert_eq!(state.stats.last_time, 20.0);
+        assert!(state.estimated_time > 10.0 && state.estimated_time < 20.0);
+    }
+}
@@ -0,0 +1,84 @@
+use crate as deft;
+use deft_macros::js_func;
+use std::env;
+use std::sync::OnceLock;
+
+static HEADLESS: OnceLock<bool> = OnceLock::new();
+
+/// Headless mode renders windows into memory instead of presenting them, so that apps could
+/// run without gpu for screenshot tests. Enabled by `bootstrap_headless` or `DEFT_HEADLESS`
+/// environment variable.
+pub fn is_headless() -> bool {
+    *HEADLESS.get_or_init(|| {
+        env::var("DEFT_HEADLESS")
+            .map(|v| v != "0" && v != "false")
+            .unwrap_or(false)
+    })
+}
+
+/// Enable or disable headless mode, must be called before the first window is created.
+/// Returns false if headless mode is determined already.
+pub fn set_headless(enabled: bool) -> bool {
+    HEADLESS.set(enabled).is_ok()
+}
+
+#[js_func]
+pub fn app_is_headless() -> bool {
+    is_headless()
+}
+
+/// X server without physical display, the server is stopped when dropped
+#[cfg(linux_platform)]
+pub struct VirtualDisplay {
+    process: std::process::Child,
+}
+
+#[cfg(linux_platform)]
+impl VirtualDisplay {
+    /// Start `Xvfb` on a free display number and point `DISPLAY` to it
+    pub fn start(width: u32, height: u32) -> Result<Self, anyhow::Error> {
+        use std::path::Path;
+        use std::process::{Command, Stdio};
+        use std::time::{Duration, Instant};
+        for number in 99..199 {
+            let socket = format!("/tmp/.X11-unix/X{}", number);
+            let lock = format!("/tmp/.X{}-lock", number);
+            if Path::new(&socket).exists() || Path::new(&lock).exists() {
+                continue;
+            }
+            let display = format!(":{}", number);
+            let mut process = Command::new("Xvfb")
+                .arg(&display)
+                .args(["-screen", "0", &format!("{}x{}x24", width, height)])
+                .args(["-nolisten", "tcp"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_secs(5) {
+                if Path::new(&socket).exists() {
+                    env::set_var("DISPLAY", &display);
+                    // Make sure windows are created on the virtual display
+                    env::remove_var("WAYLAND_DISPLAY");
+                    return Ok(Self { process });
+                }
+                // Display number is taken by another server which is starting
+                if process.try_wait()?.is_some() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+        Err(anyhow::anyhow!("failed to start Xvfb"))
+    }
+}
+
+#[cfg(linux_platform)]
+impl Drop for VirtualDisplay {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
@@ -1,5 +1,6 @@
+use crate::color_profile::icc_to_color_space;
 use anyhow::Error;
-use image::{DynamicImage, EncodableLayout, ImageReader};
+use image::{DynamicImage, EncodableLayout, ImageDecoder, ImageReader};
 use libc::memcpy;
 use log::error;
 use skia_safe::{AlphaType, Bitmap, ColorSpace, ColorType, Image, ImageInfo};
@@ -7,6 +8,7 @@ use std::borrow::Borrow;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ffi::c_void;
+use std::io::{BufRead, Seek};
 use std::rc::{Rc, Weak};
 
 thread_local! {
@@ -31,8 +33,7 @@ impl ImgManager {
                 return Ok(ii);
             }
         }
-        let img = ImageReader::open(path)?.decode()?;
-        let sk_img = dyn_image_to_skia_image(&img);
+        let sk_img = decode_image(ImageReader::open(path)?)?;
         let rc = Rc::new(sk_img.clone());
         self.cache
             .borrow_mut()
@@ -40,6 +41,11 @@ impl ImgManager {
         Ok(sk_img)
     }
 
+    /// Remove entries of images which are no longer used
+    pub fn trim(&self) {
+        self.cache.borrow_mut().retain(|_, img| img.strong_count() > 0);
+    }
+
     pub fn get_img(&self, src: &str) -> Option<Image> {
         let img_res = self.load_img(src);
         match img_res {
@@ -52,14 +58,31 @@ impl ImgManager {
     }
 }
 
+/// Decode image tagged with its embedded icc profile, so that colors are converted to color
+/// space of window surface when drawing
+pub fn decode_image<R: BufRead + Seek>(reader: ImageReader<R>) -> Result<Image, Error> {
+    let mut decoder = reader.with_guessed_format()?.into_decoder()?;
+    let icc = decoder.icc_profile().ok().flatten();
+    let img = DynamicImage::from_decoder(decoder)?;
+    let color_space = icc_to_color_space(icc.as_deref());
+    Ok(dyn_image_to_skia_image_with_color_space(&img, color_space))
+}
+
 pub fn dyn_image_to_skia_image(src: &DynamicImage) -> Image {
+    dyn_image_to_skia_image_with_color_space(src, ColorSpace::new_srgb())
+}
+
+pub fn dyn_image_to_skia_image_with_color_space(
+    src: &DynamicImage,
+    color_space: ColorSpace,
+) -> Image {
     let width = src.width() as i32;
     let height = src.height() as i32;
     let image_info = ImageInfo::new(
         (width, height),
         ColorType::RGBA8888,
         AlphaType::Unpremul,
-        ColorSpace::new_srgb(),
+        color_space,
     );
     let mut bm = Bitmap::new();
     let _ = bm.set_info(&image_info, width as usize * 4);
@@ -0,0 +1,131 @@
+use crate::element::{debug, Element};
+use crate::ext::ext_window::WINDOWS;
+use crate::style_list::ParsedStyleProp;
+use quick_js::JsValue;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Methods which access elements, they must be called in main thread
+pub const METHODS: &[&str] = &[
+    "getElementTree",
+    "getComputedStyle",
+    "getLayout",
+    "getStyleTrace",
+    "setStyle",
+];
+
+pub fn call(method: &str, params: &Value) -> Result<Value, String> {
+    match method {
+        "getElementTree" => Ok(get_element_tree()),
+        "getComputedStyle" => get_computed_style(params),
+        "getLayout" => get_layout(params),
+        "getStyleTrace" => get_style_trace(params),
+        "setStyle" => set_style(params),
+        _ => Err(format!("unknown method: {}", method)),
+    }
+}
+
+fn get_element_tree() -> Value {
+    let windows: Vec<Value> = WINDOWS.with_borrow(|m| {
+        m.iter()
+            .filter_map(|(id, w)| {
+                let body = w.upgrade_mut().ok()?.get_body()?;
+                Some(json!({ "id": id, "root": element_to_json(&body) }))
+            })
+            .collect()
+    });
+    json!({ "windows": windows })
+}
+
+fn element_to_json(element: &Element) -> Value {
+    let bounds = element.get_bounding_client_rect();
+    let mut attributes = Map::new();
+    for (k, v) in &element.attributes {
+        attributes.insert(k.to_string(), Value::String(v.clone()));
+    }
+    let children: Vec<Value> = element.get_children().iter().map(element_to_json).collect();
+    json!({
+        "id": element.get_eid(),
+        "tag": element.tag,
+        "class": element.get_class(),
+        "attributes": attributes,
+        "bounds": {
+            "x": bounds.x,
+            "y": bounds.y,
+            "width": bounds.width,
+            "height": bounds.height,
+        },
+        "children": children,
+    })
+}
+
+fn get_computed_style(params: &Value) -> Result<Value, String> {
+    let element = find_element_by_params(params)?;
+    let mut styles = Map::new();
+    for prop in element.applied_style.values() {
+        let value = prop.to_unresolved().to_style_string();
+        styles.insert(prop.key().name().to_string(), Value::String(value));
+    }
+    Ok(json!({ "styles": styles }))
+}
+
+fn get_layout(params: &Value) -> Result<Value, String> {
+    let element = find_element_by_params(params)?;
+    serde_json::to_value(debug::dump_layout(&element)).map_err(|e| e.to_string())
+}
+
+fn get_style_trace(params: &Value) -> Result<Value, String> {
+    let element = find_element_by_params(params)?;
+    let property = get_str_param(params, "property")?;
+    let trace = debug::trace_style(&element, property)
+        .ok_or_else(|| format!("unknown style property: {}", property))?;
+    serde_json::to_value(trace).map_err(|e| e.to_string())
+}
+
+/// Override a property of inline style, e.g. `{"elementId": 3, "property": "color", "value": "red"}`.
+/// Changes take effect in next frame.
+fn set_style(params: &Value) -> Result<Value, String> {
+    let mut element = find_element_by_params(params)?;
+    let property = get_str_param(params, "property")?;
+    let value = get_str_param(params, "value")?;
+    let (props, _) = ParsedStyleProp::parse_all(vec![(property, value)]);
+    // Style variables are always accepted
+    if props.is_empty() && !property.starts_with("--") {
+        return Err(format!("invalid style: {}: {}", property, value));
+    }
+    let style = HashMap::from([(property.to_string(), JsValue::String(value.to_string()))]);
+    element.update_style(JsValue::Object(style), false);
+    Ok(json!({}))
+}
+
+fn get_str_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, String> {
+    params
+        .get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("{} is required", name))
+}
+
+fn find_element_by_params(params: &Value) -> Result<Element, String> {
+    let element_id = params
+        .get("elementId")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| "elementId is required".to_string())?;
+    WINDOWS
+        .with_borrow(|m| {
+            m.values().find_map(|w| {
+                let body = w.upgrade_mut().ok()?.get_body()?;
+                find_element(&body, element_id as u32)
+            })
+        })
+        .ok_or_else(|| format!("element not found: {}", element_id))
+}
+
+fn find_element(element: &Element, id: u32) -> Option<Element> {
+    if element.get_eid() == id {
+        return Some(element.clone());
+    }
+    element
+        .get_children()
+        .iter()
+        .find_map(|c| find_element(c, id))
+}
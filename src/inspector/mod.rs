@@ -0,0 +1,272 @@
+pub mod dom;
+
+use crate as deft;
+use crate::app::AppEvent;
+use crate::base::ResultWaiter;
+use crate::js::JsError;
+use crate::send_app_event;
+use anyhow::anyhow;
+use deft_macros::js_methods;
+use log::{debug, error, info};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::{accept_hdr, Message, WebSocket};
+
+const CLIENT_HTML: &str = include_str!("client.html");
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Start inspector server in background, returns url of the page like
+/// `http://<address>/?token=<token>`. Open it in a browser or connect to
+/// `ws://<address>/?token=<token>` from other tools to inspect elements of the app.
+pub fn start_server(address: &str) -> Result<String, anyhow::Error> {
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return Err(anyhow!("inspector server already started"));
+    }
+    let started = generate_token().and_then(|token| Ok((TcpListener::bind(address)?, token)));
+    let (listener, token) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            STARTED.store(false, Ordering::Relaxed);
+            return Err(e);
+        }
+    };
+    let url = format!("http://{}/?token={}", address, token);
+    info!("inspector listening on {}", url);
+    thread::Builder::new()
+        .name("inspector".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let token = token.clone();
+                        let _ = thread::Builder::new()
+                            .name("inspector-client".to_string())
+                            .spawn(move || handle_connection(stream, &token));
+                    }
+                    Err(e) => error!("inspector accept error: {:?}", e),
+                }
+            }
+        })?;
+    Ok(url)
+}
+
+/// Random token of a server session. Clients should pass it in query of urls, so that web pages
+/// and other users of the machine could not control the app.
+pub(crate) fn generate_token() -> Result<String, anyhow::Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow!("failed to generate token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn query_token(target: &str) -> Option<&str> {
+    let (_, query) = target.split_once('?')?;
+    query.split('&').find_map(|kv| kv.strip_prefix("token="))
+}
+
+/// Browsers send Origin in websocket handshakes, only pages served by the server itself are
+/// allowed. Host must be an ip or localhost, so pages of domains rebound to the server are
+/// rejected.
+fn is_allowed_origin(origin: Option<&str>, host: Option<&str>) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    let Some(host) = host else {
+        return false;
+    };
+    let hostname = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    let is_local_name = hostname == "localhost" || hostname.parse::<IpAddr>().is_ok();
+    is_local_name && origin.eq_ignore_ascii_case(&format!("http://{}", host))
+}
+
+/// Check token in the request line of a plain http request
+pub(crate) fn is_authorized_http(head: &str, token: &str) -> bool {
+    let target = head.lines().next().and_then(|l| l.split(' ').nth(1));
+    target.and_then(query_token) == Some(token)
+}
+
+/// Respond to a plain http request without valid token
+pub(crate) fn reject_http(mut stream: TcpStream) {
+    let _ = stream
+        .write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+}
+
+/// Accept websocket of clients with the session token and an allowed origin
+pub(crate) fn accept_websocket(
+    stream: TcpStream,
+    token: &str,
+) -> Result<WebSocket<TcpStream>, anyhow::Error> {
+    let callback = |request: &Request, response: Response| {
+        let header = |name| request.headers().get(name).and_then(|v| v.to_str().ok());
+        let target = request.uri().to_string();
+        if query_token(&target) == Some(token)
+            && is_allowed_origin(header("origin"), header("host"))
+        {
+            Ok(response)
+        } else {
+            let mut response = ErrorResponse::new(Some("forbidden".to_string()));
+            *response.status_mut() = StatusCode::FORBIDDEN;
+            Err(response)
+        }
+    };
+    accept_hdr(stream, callback).map_err(|e| anyhow!("handshake error: {}", e))
+}
+
+/// Handle a method of elements, returns None if the method is unknown
+pub fn handle_method(method: &str, params: Value) -> Option<Result<Value, String>> {
+    if !dom::METHODS.contains(&method) {
+        return None;
+    }
+    Some(run_in_main_thread(method.to_string(), params))
+}
+
+/// Respond to a plain http request with the html page
+pub(crate) fn serve_html(mut stream: TcpStream, html: &str) {
+    // Drain request head, body is not expected
+    let mut buf = [0u8; 2048];
+    let _ = stream.read(&mut buf);
+    let response = format!(
+        concat!(
+            "HTTP/1.1 200 OK\r\n",
+            "Content-Type: text/html; charset=utf-8\r\n",
+            "Content-Length: {}\r\n",
+            "Connection: close\r\n\r\n{}"
+        ),
+        html.len(),
+        html
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn handle_connection(stream: TcpStream, token: &str) {
+    let mut buf = [0u8; 2048];
+    let len = match stream.peek(&mut buf) {
+        Ok(len) => len,
+        Err(e) => {
+            debug!("inspector connection error: {:?}", e);
+            return;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..len]);
+    if head.to_lowercase().contains("upgrade: websocket") {
+        handle_websocket(stream, token);
+    } else if is_authorized_http(&head, token) {
+        serve_html(stream, CLIENT_HTML);
+    } else {
+        reject_http(stream);
+    }
+}
+
+fn handle_websocket(stream: TcpStream, token: &str) {
+    let mut ws = match accept_websocket(stream, token) {
+        Ok(ws) => ws,
+        Err(e) => {
+            debug!("inspector handshake error: {:?}", e);
+            return;
+        }
+    };
+    loop {
+        match ws.read() {
+            Ok(Message::Text(text)) => {
+                let response = handle_message(&text);
+                if ws.send(Message::Text(response)).is_err() {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(e) => {
+                debug!("inspector client closed: {:?}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// Handle a request like `{"id": 1, "method": "getElementTree", "params": {}}`
+fn handle_message(text: &str) -> String {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(e) => return json!({ "error": format!("invalid request: {}", e) }).to_string(),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let result =
+        handle_method(method, params).unwrap_or_else(|| Err(format!("unknown method: {}", method)));
+    match result {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(error) => json!({ "id": id, "error": error }),
+    }
+    .to_string()
+}
+
+/// Elements could only be accessed in main thread
+fn run_in_main_thread(method: String, params: Value) -> Result<Value, String> {
+    let waiter = ResultWaiter::new();
+    let finisher = waiter.clone();
+    send_app_event(AppEvent::Callback(Box::new(move || {
+        finisher.finish(dom::call(&method, &params));
+    })))
+    .map_err(|e| e.to_string())?;
+    waiter.wait_result(|r| r.clone())
+}
+
+#[allow(nonstandard_style)]
+pub struct inspector {}
+
+#[js_methods]
+impl inspector {
+    /// Start inspector server, e.g. "127.0.0.1:9223", returns url of the page with token
+    #[js_func]
+    pub fn start(address: String) -> Result<String, JsError> {
+        Ok(start_server(&address)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inspector::{is_allowed_origin, is_authorized_http, query_token};
+
+    #[test]
+    fn test_token() {
+        assert_eq!(Some("abc"), query_token("/?token=abc"));
+        assert_eq!(Some("abc"), query_token("/?x=1&token=abc"));
+        assert_eq!(None, query_token("/"));
+        assert!(is_authorized_http(
+            "GET /?token=abc HTTP/1.1\r\nHost: a\r\n",
+            "abc"
+        ));
+        assert!(!is_authorized_http("GET /?token=abd HTTP/1.1\r\n", "abc"));
+        assert!(!is_authorized_http("GET / HTTP/1.1\r\n", "abc"));
+    }
+
+    #[test]
+    fn test_allowed_origin() {
+        let host = Some("127.0.0.1:9223");
+        assert!(is_allowed_origin(None, host));
+        assert!(is_allowed_origin(Some("http://127.0.0.1:9223"), host));
+        assert!(!is_allowed_origin(Some("https://example.com"), host));
+        assert!(is_allowed_origin(
+            Some("http://[::1]:9223"),
+            Some("[::1]:9223")
+        ));
+        assert!(is_allowed_origin(
+            Some("http://localhost:9223"),
+            Some("localhost:9223")
+        ));
+        assert!(!is_allowed_origin(
+            Some("http://evil.com:9223"),
+            Some("evil.com:9223")
+        ));
+        assert!(!is_allowed_origin(Some("http://127.0.0.1:9223"), None));
+    }
+}
@@ -1,25 +1,96 @@
+use crate::error::DeftError;
 use crate::js::js_deserialze::JsDeserializer;
+use crate::js::js_engine::JsEngine;
 use crate::js::js_runtime::{JsContext, JsValueView};
 use crate::js::js_serde::JsValueSerializer;
 use crate::js::js_value_util::JsValueHelper;
 use crate::mrc::Mrc;
 use quick_js::{JsValue, ValueError};
 use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::error::Error;
 use std::fmt::Display;
 use std::ops::{Deref, DerefMut};
 #[derive(Clone, Debug)]
 pub struct JsError {
     message: String,
+    code: Option<String>,
+    cause: Option<Box<JsError>>,
 }
 
 impl JsError {
     pub fn new(message: String) -> Self {
-        Self { message }
+        Self {
+            message,
+            code: None,
+            cause: None,
+        }
     }
     pub fn from_str(message: &str) -> Self {
         Self::new(message.to_string())
     }
+
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    pub fn with_cause(mut self, cause: JsError) -> Self {
+        self.cause = Some(Box::new(cause));
+        self
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn cause(&self) -> Option<&JsError> {
+        self.cause.as_deref()
+    }
+
+    /// Convert error and its sources, codes of DeftErrors in the chain are kept
+    pub fn from_error_chain(error: &(dyn Error + 'static)) -> Self {
+        if let Some(e) = error.downcast_ref::<DeftError>() {
+            return e.to_js_error();
+        }
+        let mut js_error = Self::new(error.to_string());
+        if let Some(source) = error.source() {
+            js_error = js_error.with_cause(Self::from_error_chain(source));
+        }
+        js_error
+    }
+
+    /// Convert error returned by native functions, codes and causes are kept for DeftError,
+    /// anyhow::Error and JsError
+    pub fn from_call_error<E: ToString + 'static>(error: E) -> Self {
+        let any = &error as &dyn Any;
+        if let Some(e) = any.downcast_ref::<JsError>() {
+            e.clone()
+        } else if let Some(e) = any.downcast_ref::<DeftError>() {
+            e.to_js_error()
+        } else if let Some(e) = any.downcast_ref::<anyhow::Error>() {
+            Self::from_error_chain(e.as_ref())
+        } else {
+            Self::new(error.to_string())
+        }
+    }
+
+    /// Create a DeftError object with code and cause, must be called on the js thread
+    pub fn create_js_error(&self) -> JsValue {
+        let code = match &self.code {
+            Some(code) => JsValue::String(code.clone()),
+            None => JsValue::Undefined,
+        };
+        let cause = match &self.cause {
+            Some(cause) => cause.create_js_error(),
+            None => JsValue::Undefined,
+        };
+        let message = JsValue::String(self.message.clone());
+        JsEngine::get()
+            .js_context
+            .call_function("DeftError_create", vec![message, code, cause])
+            .unwrap_or_else(|_| JsValue::String(self.message.clone()))
+    }
 }
 
 impl<E> From<E> for JsError
@@ -28,9 +99,7 @@ where
 {
     #[cold]
     fn from(error: E) -> Self {
-        Self {
-            message: error.to_string(),
-        }
+        Self::from_error_chain(&error)
     }
 }
 
@@ -332,14 +401,11 @@ impl<T: ToJsValue> ToJsCallResult for T {
     }
 }
 
-impl<T: ToJsValue, E: ToString> ToJsCallResult for Result<T, E> {
+impl<T: ToJsValue, E: ToString + 'static> ToJsCallResult for Result<T, E> {
     fn to_js_call_result(self) -> Result<JsValue, JsCallError> {
         match self {
             Ok(v) => v.to_js_call_result(),
-            Err(e) => {
-                let e = JsError::from_str(&e.to_string());
-                Err(JsCallError::ExecutionError(e))
-            }
+            Err(e) => Err(JsCallError::ExecutionError(JsError::from_call_error(e))),
         }
     }
 }
@@ -393,6 +459,53 @@ where
     }
 }
 
+/// Bytes passed to js as ArrayBuffer, ArrayBuffer, typed arrays and arrays of bytes are
+/// accepted from js
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JsBytes(pub Vec<u8>);
+
+impl Serialize for JsBytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsBytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = JsBytes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("bytes")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<JsBytes, E> {
+                Ok(JsBytes(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<JsBytes, E> {
+                Ok(JsBytes(v))
+            }
+        }
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+impl FromJsValue for JsBytes {
+    fn from_js_value(value: JsValue) -> Result<Self, ValueError> {
+        JsBytes::deserialize(JsDeserializer { value })
+            .map_err(|e| ValueError::Internal(e.to_string()))
+    }
+}
+
+impl ToJsValue for JsBytes {
+    fn to_js_value(self) -> Result<JsValue, ValueError> {
+        self.serialize(JsValueSerializer {})
+            .map_err(|e| ValueError::Internal(format!("Failed to serialize bytes: {:?}", e)))
+    }
+}
+
 pub struct JsResource<T> {
     value: T,
 }
@@ -0,0 +1,191 @@
+use crate::base::ResultWaiter;
+use crate::js::js_event_loop::{js_create_event_loop_proxy, js_is_in_event_loop, JsEventLoopProxy};
+use crate::js::{FromJsValue, JsValue, ToJsValue, ValueError};
+use crate::metrics;
+use log::error;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Max calls queued but not executed in js thread yet
+pub const DEFAULT_MAX_PENDING_CALLS: usize = 64;
+
+static NEXT_CALLBACK_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// Js functions referenced by handles, js values never leave js thread
+    static CALLBACKS: RefCell<HashMap<u64, JsValue>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Debug, Clone)]
+pub enum JsCallbackError {
+    /// Js event loop is closed
+    Closed,
+    /// Too many calls are pending
+    Full,
+    /// Callback was released
+    Released,
+    /// Failed to convert arguments or js function threw
+    Js(String),
+}
+
+impl Display for JsCallbackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsCallbackError::Closed => f.write_str("js event loop closed"),
+            JsCallbackError::Full => f.write_str("too many pending js callback calls"),
+            JsCallbackError::Released => f.write_str("js callback released"),
+            JsCallbackError::Js(e) => write!(f, "js callback error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JsCallbackError {}
+
+struct CallbackShared {
+    id: u64,
+    proxy: JsEventLoopProxy,
+    max_pending: usize,
+    pending: Mutex<usize>,
+    pending_changed: Condvar,
+}
+
+impl Drop for CallbackShared {
+    fn drop(&mut self) {
+        let id = self.id;
+        let _ = self.proxy.schedule_macro_task(move || {
+            CALLBACKS.with_borrow_mut(|m| m.remove(&id));
+        });
+    }
+}
+
+/// Handle of js function which could be cloned and called from any thread, calls are
+/// executed in js thread in order.
+///
+/// Arguments are converted to js values in js thread, tuples and vectors are spread as
+/// arguments, e.g. `handle.call((1, "a".to_string()))`.
+#[derive(Clone)]
+pub struct JsCallbackHandle {
+    shared: Arc<CallbackShared>,
+}
+
+impl JsCallbackHandle {
+    /// Create handle of js function, must be called in js thread
+    pub fn new(callback: JsValue) -> Self {
+        Self::with_max_pending(callback, DEFAULT_MAX_PENDING_CALLS)
+    }
+
+    pub fn with_max_pending(callback: JsValue, max_pending: usize) -> Self {
+        let id = NEXT_CALLBACK_ID.fetch_add(1, Ordering::Relaxed);
+        CALLBACKS.with_borrow_mut(|m| m.insert(id, callback));
+        let shared = CallbackShared {
+            id,
+            proxy: js_create_event_loop_proxy(),
+            max_pending: max_pending.max(1),
+            pending: Mutex::new(0),
+            pending_changed: Condvar::new(),
+        };
+        Self {
+            shared: Arc::new(shared),
+        }
+    }
+
+    /// Queue a call, returns `JsCallbackError::Full` immediately if too many calls are pending
+    pub fn try_call<A: ToJsValue + Send + 'static>(
+        &self,
+        args: A,
+    ) -> Result<ResultWaiter<Result<(), JsCallbackError>>, JsCallbackError> {
+        {
+            let mut pending = self.shared.pending.lock().unwrap();
+            if *pending >= self.shared.max_pending {
+                return Err(JsCallbackError::Full);
+            }
+            *pending += 1;
+        }
+        self.schedule(args)
+    }
+
+    /// Queue a call, blocks until the number of pending calls is below the limit.
+    /// Never blocks in js thread since pending calls could not be executed then.
+    pub fn call<A: ToJsValue + Send + 'static>(
+        &self,
+        args: A,
+    ) -> Result<ResultWaiter<Result<(), JsCallbackError>>, JsCallbackError> {
+        {
+            let mut pending = self.shared.pending.lock().unwrap();
+            if !js_is_in_event_loop() {
+                while *pending >= self.shared.max_pending {
+                    pending = self.shared.pending_changed.wait(pending).unwrap();
+                }
+            }
+            *pending += 1;
+        }
+        self.schedule(args)
+    }
+
+    /// Number of calls queued but not executed yet
+    pub fn pending_calls(&self) -> usize {
+        *self.shared.pending.lock().unwrap()
+    }
+
+    fn schedule<A: ToJsValue + Send + 'static>(
+        &self,
+        args: A,
+    ) -> Result<ResultWaiter<Result<(), JsCallbackError>>, JsCallbackError> {
+        let waiter = ResultWaiter::new();
+        let finisher = waiter.clone();
+        let shared = self.shared.clone();
+        // Mutex makes arguments Sync as required by macro tasks
+        let args = Mutex::new(Some(args));
+        let result = self.shared.proxy.schedule_macro_task(move || {
+            let args = args.lock().unwrap().take();
+            let result = args
+                .ok_or(JsCallbackError::Released)
+                .and_then(|args| invoke(shared.id, args));
+            if let Err(e) = &result {
+                error!("{}", e);
+                if let JsCallbackError::Js(_) = e {
+                    metrics::increment(metrics::JS_EXCEPTIONS, 1.0);
+                }
+            }
+            shared.release_pending();
+            finisher.finish(result);
+        });
+        if result.is_err() {
+            self.shared.release_pending();
+            return Err(JsCallbackError::Closed);
+        }
+        Ok(waiter)
+    }
+}
+
+impl CallbackShared {
+    fn release_pending(&self) {
+        let mut pending = self.pending.lock().unwrap();
+        *pending = pending.saturating_sub(1);
+        self.pending_changed.notify_one();
+    }
+}
+
+fn invoke<A: ToJsValue>(id: u64, args: A) -> Result<(), JsCallbackError> {
+    let callback = CALLBACKS.with_borrow(|m| m.get(&id).cloned());
+    let callback = callback.ok_or(JsCallbackError::Released)?;
+    let args = match args.to_js_value() {
+        Ok(JsValue::Array(list)) => list,
+        Ok(JsValue::Undefined) => Vec::new(),
+        Ok(v) => vec![v],
+        Err(e) => return Err(JsCallbackError::Js(format!("{:?}", e))),
+    };
+    callback
+        .call_as_function(args)
+        .map(|_| ())
+        .map_err(|e| JsCallbackError::Js(format!("{:?}", e)))
+}
+
+impl FromJsValue for JsCallbackHandle {
+    fn from_js_value(value: JsValue) -> Result<Self, ValueError> {
+        Ok(Self::new(value))
+    }
+}
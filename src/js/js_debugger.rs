@@ -0,0 +1,502 @@
+use crate as deft;
+use crate::inspector;
+use crate::js::js_engine::JsEngine;
+use crate::js::js_event_loop::{js_create_event_loop_proxy, JsEventLoopProxy};
+use crate::js::JsError;
+use anyhow::anyhow;
+use deft_macros::js_methods;
+use log::{debug, error, info};
+use quick_js::JsValue;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, LazyLock, Mutex};
+use std::thread;
+
+/// Id of the only thread reported to debug client
+const MAIN_THREAD_ID: i64 = 1;
+const EXCEPTION_VARIABLES_REF: i64 = 1;
+const UNCAUGHT_FILTER: &str = "uncaught";
+/// Loopback only, so that other machines could not connect
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:4711";
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+static STATE: LazyLock<(Mutex<DebuggerState>, Condvar)> =
+    LazyLock::new(|| (Mutex::new(DebuggerState::new(None)), Condvar::new()));
+
+struct DebuggerState {
+    /// Writing half of connected client
+    client: Option<TcpStream>,
+    seq: i64,
+    break_on_uncaught: bool,
+    paused: Option<PausedException>,
+    /// Evaluate requests run by the main thread while it is paused
+    evaluations: VecDeque<Value>,
+}
+
+impl DebuggerState {
+    fn new(client: Option<TcpStream>) -> Self {
+        Self {
+            client,
+            seq: 1,
+            break_on_uncaught: true,
+            paused: None,
+            evaluations: VecDeque::new(),
+        }
+    }
+
+    fn send(&mut self, mut message: Value) {
+        let client = match &mut self.client {
+            Some(c) => c,
+            None => return,
+        };
+        message["seq"] = json!(self.seq);
+        self.seq += 1;
+        let body = message.to_string();
+        let data = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        if let Err(e) = client.write_all(data.as_bytes()) {
+            debug!("failed to write to debug client: {:?}", e);
+            self.client = None;
+        }
+    }
+
+    fn send_event(&mut self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn send_response(&mut self, request: &Value, result: Result<Value, String>) {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+        });
+        match result {
+            Ok(body) => {
+                response["success"] = json!(true);
+                response["body"] = body;
+            }
+            Err(message) => {
+                response["success"] = json!(false);
+                response["message"] = json!(message);
+            }
+        }
+        self.send(response);
+    }
+}
+
+struct PausedException {
+    name: String,
+    message: String,
+    stack: String,
+    frames: Vec<StackFrame>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StackFrame {
+    pub name: String,
+    pub file: String,
+    pub line: i64,
+    pub column: i64,
+}
+
+/// Start a Debug Adapter Protocol server, returns token of the session. Debug clients like
+/// VS Code could attach to it by passing the token in arguments of the attach request.
+pub fn start_server(address: &str) -> Result<String, anyhow::Error> {
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return Err(anyhow!("debugger already started"));
+    }
+    let started =
+        inspector::generate_token().and_then(|token| Ok((TcpListener::bind(address)?, token)));
+    let (listener, token) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            STARTED.store(false, Ordering::Relaxed);
+            return Err(e);
+        }
+    };
+    info!("js debugger listening on {}", address);
+    let session_token = token.clone();
+    let proxy = js_create_event_loop_proxy();
+    thread::Builder::new()
+        .name("js-debugger".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    // Only one client is served at a time
+                    Ok(stream) => handle_client(stream, &token, &proxy),
+                    Err(e) => error!("debugger accept error: {:?}", e),
+                }
+            }
+        })?;
+    Ok(session_token)
+}
+
+/// Pause main thread when an uncaught exception is reported and a client is attached,
+/// returns after the client continues or disconnects
+pub fn pause_on_exception(name: &str, message: &str, stack: &str) {
+    if !STARTED.load(Ordering::Relaxed) {
+        return;
+    }
+    let (lock, cvar) = &*STATE;
+    let mut state = lock.lock().unwrap();
+    if state.client.is_none() || !state.break_on_uncaught {
+        return;
+    }
+    state.paused = Some(PausedException {
+        name: name.to_string(),
+        message: message.to_string(),
+        stack: stack.to_string(),
+        frames: parse_stack(stack),
+    });
+    state.send_event(
+        "stopped",
+        json!({
+            "reason": "exception",
+            "description": format!("Paused on {}", name),
+            "text": message,
+            "threadId": MAIN_THREAD_ID,
+            "allThreadsStopped": true,
+        }),
+    );
+    while state.paused.is_some() {
+        if let Some(request) = state.evaluations.pop_front() {
+            // Evaluated without the lock, the expression may report exceptions again
+            drop(state);
+            let result = evaluate(&request);
+            state = lock.lock().unwrap();
+            state.send_response(&request, result);
+            continue;
+        }
+        state = cvar.wait(state).unwrap();
+    }
+}
+
+/// Evaluate expression of request in the global scope, locals of paused frames are not available
+/// as QuickJS has no debugging api
+fn evaluate(request: &Value) -> Result<Value, String> {
+    let expression = request["arguments"]["expression"]
+        .as_str()
+        .ok_or("expression is required")?;
+    let value = JsEngine::get()
+        .js_context
+        .eval(expression)
+        .map_err(|e| e.to_string())?;
+    Ok(json!({ "result": describe_value(&value), "variablesReference": 0 }))
+}
+
+fn describe_value(value: &JsValue) -> String {
+    match value {
+        JsValue::Undefined => "undefined".to_string(),
+        JsValue::Null => "null".to_string(),
+        JsValue::Bool(b) => b.to_string(),
+        JsValue::Int(i) => i.to_string(),
+        JsValue::Float(f) => f.to_string(),
+        JsValue::String(s) => format!("{:?}", s),
+        other => format!("{:?}", other),
+    }
+}
+
+fn resume(state: &mut DebuggerState) {
+    if state.paused.take().is_some() {
+        STATE.1.notify_all();
+    }
+}
+
+fn handle_client(stream: TcpStream, token: &str, proxy: &JsEventLoopProxy) {
+    let writer = match stream.try_clone() {
+        Ok(w) => w,
+        Err(e) => {
+            error!("debugger client error: {:?}", e);
+            return;
+        }
+    };
+    let mut reader = BufReader::new(stream);
+    let (writer, seq) = match authenticate(&mut reader, writer, token) {
+        Some(attached) => attached,
+        None => {
+            debug!("debug client rejected");
+            return;
+        }
+    };
+    {
+        let mut state = STATE.0.lock().unwrap();
+        state.client = Some(writer);
+        state.seq = seq;
+    }
+    while let Some(request) = read_message(&mut reader) {
+        let mut state = STATE.0.lock().unwrap();
+        if request["command"] == "evaluate" {
+            if state.paused.is_some() {
+                state.evaluations.push_back(request);
+                STATE.1.notify_all();
+            } else {
+                let task = move || {
+                    let result = evaluate(&request);
+                    STATE.0.lock().unwrap().send_response(&request, result);
+                };
+                if proxy.schedule_macro_task(task).is_err() {
+                    debug!("event loop is closed, evaluate request dropped");
+                }
+            }
+            continue;
+        }
+        let result = handle_request(&mut state, &request);
+        state.send_response(&request, result);
+        if request["command"] == "disconnect" {
+            break;
+        }
+    }
+    let mut state = STATE.0.lock().unwrap();
+    state.client = None;
+    resume(&mut state);
+}
+
+/// Answer requests until the client attaches with the session token, other requests are rejected
+/// and close the connection. Returns the writer and next seq of the attached client.
+fn authenticate<R: BufRead>(
+    reader: &mut R,
+    writer: TcpStream,
+    token: &str,
+) -> Option<(TcpStream, i64)> {
+    // Not the client of shared state until attached, so it is never paused for exceptions
+    let mut state = DebuggerState::new(Some(writer));
+    loop {
+        let request = read_message(reader)?;
+        match request["command"].as_str().unwrap_or("") {
+            "initialize" => {
+                let result = handle_request(&mut state, &request);
+                state.send_response(&request, result);
+            }
+            "attach" if request["arguments"]["token"].as_str() == Some(token) => {
+                state.send_response(&request, Ok(json!({})));
+                state.send_event("initialized", json!({}));
+                return Some((state.client.take()?, state.seq));
+            }
+            _ => {
+                state.send_response(&request, Err("Invalid debug session token".to_string()));
+                return None;
+            }
+        }
+    }
+}
+
+/// Read a message framed with Content-Length header. Requests of other protocols, e.g. http
+/// requests sent by web pages, are rejected as headers other than those of DAP are not allowed.
+fn read_message<R: BufRead>(reader: &mut R) -> Option<Value> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        let (key, value) = line.split_once(':')?;
+        let key = key.trim();
+        if key.eq_ignore_ascii_case("Content-Length") {
+            content_length = value.trim().parse::<usize>().ok();
+        } else if !key.eq_ignore_ascii_case("Content-Type") {
+            return None;
+        }
+    }
+    let mut body = vec![0u8; content_length?];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+fn handle_request(state: &mut DebuggerState, request: &Value) -> Result<Value, String> {
+    let args = &request["arguments"];
+    match request["command"].as_str().unwrap_or("") {
+        "initialize" => Ok(json!({
+            "supportsConfigurationDoneRequest": true,
+            "supportsExceptionInfoRequest": true,
+            "exceptionBreakpointFilters": [{
+                "filter": UNCAUGHT_FILTER,
+                "label": "Uncaught Exceptions",
+                "default": true,
+            }],
+        })),
+        "configurationDone" => Ok(json!({})),
+        "setExceptionBreakpoints" => {
+            state.break_on_uncaught = args["filters"]
+                .as_array()
+                .map(|f| f.iter().any(|v| v == UNCAUGHT_FILTER))
+                .unwrap_or(false);
+            Ok(json!({}))
+        }
+        "threads" => Ok(json!({ "threads": [{ "id": MAIN_THREAD_ID, "name": "main" }] })),
+        "stackTrace" => {
+            let paused = state.paused.as_ref().ok_or("not paused")?;
+            let frames: Vec<Value> = paused
+                .frames
+                .iter()
+                .enumerate()
+                .map(|(i, f)| {
+                    json!({
+                        "id": i,
+                        "name": f.name,
+                        "source": { "name": f.file, "path": f.file },
+                        "line": f.line,
+                        "column": f.column,
+                    })
+                })
+                .collect();
+            Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+        }
+        "scopes" => Ok(json!({
+            "scopes": [{
+                "name": "Exception",
+                "variablesReference": EXCEPTION_VARIABLES_REF,
+                "expensive": false,
+            }]
+        })),
+        "variables" => {
+            let paused = state.paused.as_ref().ok_or("not paused")?;
+            let variables: Vec<Value> = [
+                ("name", &paused.name),
+                ("message", &paused.message),
+                ("stack", &paused.stack),
+            ]
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": value, "variablesReference": 0 }))
+            .collect();
+            Ok(json!({ "variables": variables }))
+        }
+        "exceptionInfo" => {
+            let paused = state.paused.as_ref().ok_or("not paused")?;
+            Ok(json!({
+                "exceptionId": paused.name,
+                "description": paused.message,
+                "breakMode": "unhandled",
+                "details": { "stackTrace": paused.stack },
+            }))
+        }
+        "continue" => {
+            resume(state);
+            Ok(json!({ "allThreadsContinued": true }))
+        }
+        "disconnect" => {
+            resume(state);
+            Ok(json!({}))
+        }
+        command => Err(format!("Unknown command: {}", command)),
+    }
+}
+
+/// Parse stack of QuickJS errors, lines look like `    at foo (index.js:12:5)`
+pub fn parse_stack(stack: &str) -> Vec<StackFrame> {
+    let mut frames = Vec::new();
+    for line in stack.lines() {
+        let line = match line.trim().strip_prefix("at ") {
+            Some(l) => l,
+            None => continue,
+        };
+        let (name, location) = match line.rfind(" (") {
+            Some(idx) if line.ends_with(')') => (&line[..idx], &line[idx + 2..line.len() - 1]),
+            _ => ("<anonymous>", line),
+        };
+        let mut parts = location.rsplitn(3, ':');
+        let last = parts.next().and_then(|p| p.parse::<i64>().ok());
+        let second = parts.next();
+        let (file, line, column) = match (last, second.and_then(|p| p.parse::<i64>().ok())) {
+            (Some(column), Some(line)) => (parts.next().unwrap_or(""), line, column),
+            (Some(line), None) => match location.rsplit_once(':') {
+                Some((file, _)) => (file, line, 1),
+                None => (location, 0, 0),
+            },
+            _ => (location, 0, 0),
+        };
+        frames.push(StackFrame {
+            name: name.to_string(),
+            file: file.to_string(),
+            line,
+            column,
+        });
+    }
+    frames
+}
+
+#[allow(nonstandard_style)]
+pub struct debugger {}
+
+#[js_methods]
+impl debugger {
+    /// Start debug server, e.g. "127.0.0.1:4711", returns token of the session
+    #[js_func]
+    pub fn start(address: Option<String>) -> Result<String, JsError> {
+        Ok(start_server(address.as_deref().unwrap_or(DEFAULT_ADDRESS))?)
+    }
+
+    #[js_func]
+    pub fn report_exception(name: String, message: String, stack: String) {
+        pause_on_exception(&name, &message, &stack);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::js::js_debugger::{parse_stack, read_message, StackFrame};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_stack() {
+        let stack = "Error: boom\n    at foo (index.js:12:5)\n    at <eval> (main.js:3)\n\
+                     at x\n    at 5";
+        assert_eq!(
+            parse_stack(stack),
+            vec![
+                StackFrame {
+                    name: "foo".to_string(),
+                    file: "index.js".to_string(),
+                    line: 12,
+                    column: 5,
+                },
+                StackFrame {
+                    name: "<eval>".to_string(),
+                    file: "main.js".to_string(),
+                    line: 3,
+                    column: 1,
+                },
+                StackFrame {
+                    name: "<anonymous>".to_string(),
+                    file: "x".to_string(),
+                    line: 0,
+                    column: 0,
+                },
+                StackFrame {
+                    name: "<anonymous>".to_string(),
+                    file: "5".to_string(),
+                    line: 0,
+                    column: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_message() {
+        let body = r#"{"command":"attach"}"#;
+        let message = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        assert_eq!(
+            read_message(&mut message.as_bytes()),
+            Some(json!({ "command": "attach" }))
+        );
+        let http = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        assert_eq!(read_message(&mut http.as_bytes()), None);
+        let origin = format!(
+            "Content-Length: {}\r\nOrigin: http://example.com\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        assert_eq!(read_message(&mut origin.as_bytes()), None);
+    }
+}
@@ -1,4 +1,5 @@
 #![allow(unused)]
+use crate::js::js_engine::JsEngine;
 use crate::js::js_value_util::JsValueHelper;
 use quick_js::JsValue;
 use serde::de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor};
@@ -252,14 +253,29 @@ impl<'de> Deserializer<'de> for JsDeserializer {
     where
         V: Visitor<'de>,
     {
-        todo!()
+        self.deserialize_byte_buf(visitor)
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        let bytes = match self.value {
+            JsValue::Array(list) => bytes_from_numbers(list)?,
+            JsValue::String(s) => s.into_bytes(),
+            value => {
+                // ArrayBuffer or typed array
+                let binary = JsEngine::get()
+                    .js_context
+                    .call_function("Bytes_toBinaryString", vec![value])
+                    .map_err(|e| Error::custom(format!("deserialize bytes error: {:?}", e)))?;
+                match binary {
+                    JsValue::String(s) => bytes_from_binary_string(&s)?,
+                    _ => return Err(Error::custom("deserialize bytes error")),
+                }
+            }
+        };
+        visitor.visit_byte_buf(bytes)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -397,3 +413,47 @@ impl<'de> Deserializer<'de> for JsDeserializer {
         todo!()
     }
 }
+
+fn bytes_from_numbers(list: Vec<JsValue>) -> Result<Vec<u8>, JsError> {
+    let mut bytes = Vec::with_capacity(list.len());
+    for v in list {
+        let b = match v {
+            JsValue::Int(i) => u8::try_from(i).ok(),
+            JsValue::Float(f) if f.fract() == 0.0 && (0.0..=255.0).contains(&f) => Some(f as u8),
+            _ => None,
+        };
+        bytes.push(b.ok_or_else(|| Error::custom(format!("invalid byte: {:?}", v)))?);
+    }
+    Ok(bytes)
+}
+
+fn bytes_from_binary_string(binary: &str) -> Result<Vec<u8>, JsError> {
+    binary
+        .chars()
+        .map(|c| u8::try_from(c).map_err(|_| Error::custom("invalid binary string")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::js::js_deserialze::{bytes_from_binary_string, bytes_from_numbers};
+    use quick_js::JsValue;
+
+    #[test]
+    fn test_bytes_from_numbers() {
+        let list = vec![JsValue::Int(0), JsValue::Float(255.0), JsValue::Int(16)];
+        assert_eq!(bytes_from_numbers(list).unwrap(), vec![0, 255, 16]);
+        assert!(bytes_from_numbers(vec![JsValue::Int(256)]).is_err());
+        assert!(bytes_from_numbers(vec![JsValue::Int(-1)]).is_err());
+        assert!(bytes_from_numbers(vec![JsValue::Float(1.5)]).is_err());
+    }
+
+    #[test]
+    fn test_bytes_from_binary_string() {
+        assert_eq!(
+            bytes_from_binary_string("\u{0}a\u{ff}").unwrap(),
+            vec![0, 97, 255]
+        );
+        assert!(bytes_from_binary_string("\u{100}").is_err());
+    }
+}
@@ -4,18 +4,20 @@ use std::future::Future;
 use std::panic::RefUnwindSafe;
 use std::path::PathBuf;
 use tokio::runtime::Builder;
-use winit::dpi::{PhysicalSize};
-use winit::event::{DeviceEvent, DeviceId, ElementState, WindowEvent};
-use winit::window::{WindowId};
 
-use crate::app::App;
+use crate::a11y::a11y;
+use crate::app::{App, MainWindowTemplate};
+use crate::autofill::autofill;
 use crate::console::Console;
 use crate::element::button::Button;
 use crate::element::checkbox::Checkbox;
+use crate::element::custom::custom_element;
+use crate::element::external_texture::ExternalTexture;
 use crate::element::image::Image;
 use crate::element::label::Label;
 use crate::element::radio::Radio;
 use crate::element::richtext::RichText;
+use crate::element::richtextedit::RichTextEdit;
 use crate::element::scroll::Scroll;
 use crate::element::select::Select;
 use crate::element::textedit::TextEdit;
@@ -29,35 +31,66 @@ use crate::ext::ext_console::Console as ExtConsole;
 use crate::ext::ext_env::env;
 #[cfg(fs_enabled)]
 use crate::ext::ext_fs::{
-    fs_create_dir, fs_create_dir_all, fs_delete_file, fs_exists, fs_read_dir, fs_remove_dir,
-    fs_remove_dir_all, fs_rename, fs_stat,
+    fs_cancel, fs_copy, fs_create_cancel_token, fs_create_dir, fs_create_dir_all,
+    fs_delete_file, fs_exists, fs_move, fs_read_dir, fs_read_dir_entries,
+    fs_release_cancel_token, fs_remove, fs_remove_dir, fs_remove_dir_all, fs_rename, fs_stat,
+    FileHandle,
 };
-use crate::ext::ext_localstorage::localstorage;
+use crate::ext::ext_localstorage::{localstorage, StorageOptions, DEFAULT_NAMESPACE};
+use crate::ext::ext_metrics::metrics;
 use crate::ext::ext_path::path;
 use crate::ext::ext_process::process;
+use crate::ext::ext_recovery::recovery;
 use crate::ext::ext_shell::shell;
 use crate::ext::ext_timer::{
-    timer_clear_interval, timer_clear_timeout, timer_set_interval, timer_set_timeout,
+    timer_cancel_idle_callback, timer_clear_interval, timer_clear_timeout,
+    timer_request_idle_callback, timer_set_interval, timer_set_timeout,
 };
 #[cfg(feature = "tray")]
 use crate::ext::ext_tray::SystemTray;
-use crate::ext::ext_window::{handle_window_event, WINDOWS};
 use crate::ext::ext_worker::{SharedModuleLoader, Worker, WorkerInitParams};
+use crate::features::{app_features, app_version};
+use crate::headless::app_is_headless;
 use crate::js::js_binding::{JsCallError, JsFunc};
 use crate::js::js_runtime::{JsContext, PromiseResolver};
+use crate::js::js_stream::AsyncStream;
 use crate::js::ToJsCallResult;
 use crate::menu::{Menu, StandardMenuItem};
 use crate::mrc::Mrc;
-use crate::stylesheet::{stylesheet_add, stylesheet_remove, stylesheet_update};
-use crate::typeface::typeface_create;
+use crate::render::debug::debug;
+use crate::safe_mode::app_is_safe_mode;
+use crate::stylesheet::{stylesheet_add, stylesheet_remove, stylesheet_replace};
+use crate::typeface::{typeface_create, typeface_get_fallback_fonts, typeface_set_fallback_fonts};
 use crate::window::page::Page;
 use crate::window::popup::Popup;
-use crate::window::{Window, WindowHandle, WindowType};
+use crate::window::shortcut::{
+    app_register_shortcut, app_set_shortcut_enabled, app_unregister_shortcut,
+};
+use crate::window::Window;
 
 thread_local! {
     static JS_ENGINE: RefCell<Option<Mrc<JsEngine>>> = RefCell::new(None);
 }
 
+/// Errors of async native functions are rejected as DeftError objects with `code` and `cause`,
+/// which are created by `JsError::create_js_error`
+const NATIVE_ERROR_PRELUDE: &str = r#"
+(function () {
+    class DeftError extends Error {
+        constructor(message, code, cause) {
+            super(message);
+            this.name = "DeftError";
+            this.code = code;
+            if (cause !== undefined) {
+                this.cause = cause;
+            }
+        }
+    }
+    globalThis.DeftError = DeftError;
+    globalThis.DeftError_create = (message, code, cause) => new DeftError(message, code, cause);
+})();
+"#;
+
 pub struct JsEngine {
     pub js_context: Mrc<JsContext>,
     pub app: App,
@@ -79,6 +112,7 @@ impl Callback<()> for JsFuncCallback {
             Ok(v) => Ok(Ok(v)),
             Err(e) => match e {
                 JsCallError::ConversionError(ce) => Err(ce),
+                // Callbacks of quick_js could only throw strings
                 JsCallError::ExecutionError(ee) => Ok(Err(ee.to_string())),
             },
         }
@@ -94,6 +128,11 @@ impl JsEngine {
         })
     }
 
+    /// Engine of current thread, None for pure rust ui, see `IApp::use_js_engine`
+    pub fn try_get() -> Option<Mrc<JsEngine>> {
+        JS_ENGINE.with(|e| e.borrow().clone())
+    }
+
     pub fn init(app: App) {
         let loader = {
             let mut app = app.app_impl.lock().unwrap();
@@ -115,6 +154,7 @@ impl JsEngine {
             .module_loader(loader.clone())
             .build()
             .unwrap();
+        js_context.eval(NATIVE_ERROR_PRELUDE).unwrap();
         let js_context = Mrc::new(JsContext::new(js_context, runtime));
 
         let engine = Self {
@@ -127,6 +167,7 @@ impl JsEngine {
 
         engine.add_global_functions(Menu::create_js_apis());
         engine.add_global_functions(StandardMenuItem::create_js_apis());
+        engine.add_global_functions(crate::ext::ext_menu::Menu::create_js_apis());
 
         engine.add_global_functions(Page::create_js_apis());
         engine.add_global_functions(ExtConsole::create_js_apis());
@@ -138,16 +179,30 @@ impl JsEngine {
         engine.add_global_functions(TextInput::create_js_apis());
         engine.add_global_functions(TextEdit::create_js_apis());
         engine.add_global_functions(RichText::create_js_apis());
+        engine.add_global_functions(RichTextEdit::create_js_apis());
         engine.add_global_functions(Label::create_js_apis());
         engine.add_global_functions(Image::create_js_apis());
+        engine.add_global_functions(ExternalTexture::create_js_apis());
         engine.add_global_functions(Select::create_js_apis());
+        engine.add_global_functions(custom_element::create_js_apis());
         #[cfg(feature = "sqlite")]
         engine.add_global_functions(crate::ext::ext_sqlite::SqliteConn::create_js_apis());
+        #[cfg(feature = "sqlite")]
+        engine.add_global_functions(crate::ext::ext_object_store::ObjectStoreDb::create_js_apis());
         #[cfg(feature = "tray")]
         {
             engine.add_global_functions(SystemTray::create_js_apis());
         }
         engine.add_global_functions(process::create_js_apis());
+        engine.add_global_functions(recovery::create_js_apis());
+        engine.add_global_functions(metrics::create_js_apis());
+        engine.add_global_functions(crate::performance::performance::create_js_apis());
+        engine.add_global_functions(crate::gc::gc::create_js_apis());
+        engine.add_global_functions(autofill::create_js_apis());
+        engine.add_global_functions(a11y::create_js_apis());
+        engine.add_global_functions(crate::system_metrics::system_metrics::create_js_apis());
+        engine.add_global_functions(crate::ext::ext_window::screen::create_js_apis());
+        engine.add_global_functions(debug::create_js_apis());
         #[cfg(feature = "dialog")]
         engine.add_global_functions(crate::ext::ext_dialog::dialog::create_js_apis());
         engine.add_global_functions(Base64::create_js_apis());
@@ -161,17 +216,37 @@ impl JsEngine {
         #[cfg(fs_enabled)]
         engine.add_global_functions(appfs::create_js_apis());
         engine.add_global_functions(localstorage::create_js_apis());
+        engine.add_global_functions(crate::config::config::create_js_apis());
+        #[cfg(feature = "sqlite")]
+        engine.add_global_functions(crate::ext::ext_large_storage::largestorage::create_js_apis());
+        #[cfg(feature = "devtools")]
+        engine.add_global_functions(crate::devtools::devtools::create_js_apis());
+        #[cfg(feature = "inspector")]
+        engine.add_global_functions(crate::inspector::inspector::create_js_apis());
+        #[cfg(feature = "debugger")]
+        engine.add_global_functions(crate::js::js_debugger::debugger::create_js_apis());
         // websocket
         #[cfg(feature = "websocket")]
         engine.add_global_functions(crate::ext::ext_websocket::WsConnection::create_js_apis());
         #[cfg(feature = "http")]
         engine.add_global_functions(crate::ext::ext_fetch::fetch::create_js_apis());
+        #[cfg(feature = "http")]
+        engine.add_global_functions(crate::ext::ext_download::download::create_js_apis());
+        #[cfg(feature = "iap")]
+        engine.add_global_functions(crate::ext::ext_iap::iap::create_js_apis());
+        #[cfg(feature = "oauth")]
+        engine.add_global_functions(crate::ext::ext_oauth::oauth::create_js_apis());
+        engine.add_global_functions(crate::network::network::create_js_apis());
+        engine.add_global_functions(crate::protocol::protocol::create_js_apis());
 
         engine.add_global_functions(Window::create_js_apis());
+        engine.add_global_functions(AsyncStream::create_js_apis());
         engine.add_global_func(timer_set_timeout::new());
         engine.add_global_func(timer_clear_timeout::new());
         engine.add_global_func(timer_set_interval::new());
         engine.add_global_func(timer_clear_interval::new());
+        engine.add_global_func(timer_request_idle_callback::new());
+        engine.add_global_func(timer_cancel_idle_callback::new());
 
         #[cfg(fs_enabled)]
         {
@@ -184,16 +259,35 @@ impl JsEngine {
             engine.add_global_func(fs_create_dir_all::new());
             engine.add_global_func(fs_remove_dir::new());
             engine.add_global_func(fs_remove_dir_all::new());
+            engine.add_global_func(fs_create_cancel_token::new());
+            engine.add_global_func(fs_cancel::new());
+            engine.add_global_func(fs_release_cancel_token::new());
+            engine.add_global_func(fs_read_dir_entries::new());
+            engine.add_global_func(fs_copy::new());
+            engine.add_global_func(fs_move::new());
+            engine.add_global_func(fs_remove::new());
+            engine.add_global_functions(FileHandle::create_js_apis());
         }
 
         engine.add_global_func(animation_create::new());
         engine.add_global_func(typeface_create::new());
+        engine.add_global_func(typeface_get_fallback_fonts::new());
+        engine.add_global_func(typeface_set_fallback_fonts::new());
+        engine.add_global_func(app_is_safe_mode::new());
+        engine.add_global_func(app_is_headless::new());
+        engine.add_global_func(app_version::new());
+        engine.add_global_func(app_features::new());
+        engine.add_global_func(app_register_shortcut::new());
+        engine.add_global_func(app_unregister_shortcut::new());
+        engine.add_global_func(app_set_shortcut_enabled::new());
 
         #[cfg(feature = "clipboard")]
         engine.add_global_functions(crate::ext::ext_clipboard::Clipboard::create_js_apis());
+        engine.add_global_func(batch_begin::new());
+        engine.add_global_func(batch_end::new());
         engine.add_global_func(stylesheet_add::new());
         engine.add_global_func(stylesheet_remove::new());
-        engine.add_global_func(stylesheet_update::new());
+        engine.add_global_func(stylesheet_replace::new());
 
         Worker::init_js_api(WorkerInitParams { app });
         engine.add_global_functions(Worker::create_js_apis());
@@ -201,7 +295,34 @@ impl JsEngine {
     }
 
     pub fn enable_localstorage(&mut self, p: PathBuf) {
-        localstorage::init(p);
+        self.enable_localstorage_with_namespace(DEFAULT_NAMESPACE, p, StorageOptions::default());
+    }
+
+    /// Enable a separate localstorage namespace, e.g. for a window or a user profile
+    pub fn enable_localstorage_with_namespace(
+        &mut self,
+        namespace: &str,
+        p: PathBuf,
+        options: StorageOptions,
+    ) {
+        #[cfg(feature = "sqlite")]
+        {
+            let large_storage_path = if options.private {
+                None
+            } else {
+                Some(p.with_extension("large.db"))
+            };
+            crate::ext::ext_large_storage::largestorage::init_namespace(
+                namespace,
+                large_storage_path,
+            );
+        }
+        localstorage::init_namespace(namespace, Some(p), options);
+    }
+
+    /// Enable crash recovery, state saved by js is kept in dir and delivered to next session after crash
+    pub fn enable_crash_recovery(&mut self, dir: PathBuf, interval_millis: u64) {
+        recovery::init(dir, interval_millis);
     }
 
     pub fn create_async_task<F, O>(&mut self, future: F) -> JsValue
@@ -216,6 +337,13 @@ impl JsEngine {
         self.js_context.create_promise()
     }
 
+    /// Handle of the tokio runtime shared by async js functions, extensions could spawn their
+    /// own tasks on it instead of creating another runtime, e.g.
+    /// `JsEngine::get().runtime_handle().spawn(task)`.
+    pub fn runtime_handle(&self) -> tokio::runtime::Handle {
+        self.js_context.runtime_handle()
+    }
+
     pub fn init_api(&self) {
         let default_css = include_str!("../../deft.css");
         CSS_MANAGER.with_borrow_mut(|manager| {
@@ -265,51 +393,26 @@ impl JsEngine {
         self.js_context.execute_module(module_name)
     }
 
-    pub fn eval_module(&mut self, code: &str, filename: &str) -> Result<JsValue, ExecutionError> {
-        self.js_context.eval_module(code, filename)
-    }
-
-    pub fn handle_window_event(&mut self, window_id: WindowId, event: WindowEvent) {
-        handle_window_event(window_id, event);
+    /// Create window of the template and pass it to `onCreate` exported by its initial module
+    pub fn execute_main_window(
+        &mut self,
+        template: &MainWindowTemplate,
+    ) -> Result<(), ExecutionError> {
+        let module_name = serde_json::to_string(&template.initial_module).unwrap();
+        let attrs = serde_json::to_string(&template.attrs).unwrap();
+        let code = format!(
+            r#"import * as main from {module_name};
+const window = new Window({attrs});
+if (typeof main.onCreate === "function") {{
+    main.onCreate(window);
+}}"#
+        );
+        self.js_context.eval_module(&code, "main-window.js")?;
+        Ok(())
     }
 
-    pub fn handle_device_event(&mut self, _device_id: DeviceId, event: DeviceEvent) {
-        if let DeviceEvent::Button { state, .. } = event {
-            if state == ElementState::Pressed {
-                let close_windows: Vec<WindowHandle> = WINDOWS.with_borrow(|windows| {
-                    windows
-                        .iter()
-                        .filter(|(_, f)| {
-                            f.upgrade_mut()
-                                .ok()
-                                .map(|f| f.window_type == WindowType::Menu)
-                                .unwrap_or(false)
-                        })
-                        .map(|(_, f)| f.clone())
-                        .filter(|w| {
-                            if let Ok(window) = w.upgrade_mut() {
-                                let w_size: PhysicalSize<i32> = window.window.outer_size().cast();
-                                if let Some(ptr_pos) = window.window.pointer_position() {
-                                    let is_in_window = ptr_pos.x >= 0
-                                        && ptr_pos.x <= w_size.width
-                                        && ptr_pos.y >= 0
-                                        && ptr_pos.y <= w_size.height;
-                                    if !is_in_window {
-                                        return true;
-                                    }
-                                }
-                            }
-                            false
-                        })
-                        .collect()
-                });
-                for f in close_windows {
-                    if let Ok(mut f) = f.upgrade_mut() {
-                        let _ = f.close();
-                    }
-                }
-            }
-        }
+    pub fn eval_module(&mut self, code: &str, filename: &str) -> Result<JsValue, ExecutionError> {
+        self.js_context.eval_module(code, filename)
     }
 
     pub fn execute_pending_jobs(&self) {
@@ -323,10 +426,14 @@ impl JsEngine {
                     }
                 }
                 Err(e) => {
+                    crate::metrics::increment(crate::metrics::JS_EXCEPTIONS, 1.0);
                     eprint!("job error:{:?}", e);
+                    #[cfg(feature = "debugger")]
+                    crate::js::js_debugger::pause_on_exception("Error", &format!("{:?}", e), "");
                     break;
                 }
             }
         }
+        batch::flush_auto();
     }
 }
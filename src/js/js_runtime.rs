@@ -1,13 +1,13 @@
 use crate::base::UnsafeFnOnce;
 use crate::js::js_event_loop::{js_create_event_loop_proxy, JsEventLoopProxy};
 use crate::js::js_value_util::JsValueHelper;
-use crate::js::{FromJsValue, ToJsCallResult, ToJsValue};
+use crate::js::{FromJsValue, JsCallError, JsError, ToJsCallResult, ToJsValue};
 use quick_js::{Context, ExecutionError, JsPromise, JsValue, ValueError};
 use std::env;
 use std::future::Future;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
-use tokio::runtime::Runtime;
+use tokio::runtime::{Handle, Runtime};
 use winit::window::{Cursor, CursorIcon};
 
 pub struct JsContext {
@@ -28,6 +28,11 @@ impl JsContext {
         (result, resolver)
     }
 
+    /// Handle of the tokio runtime running async js functions
+    pub fn runtime_handle(&self) -> Handle {
+        self.runtime.handle().clone()
+    }
+
     pub fn create_async_task2<F, O>(&mut self, future: F) -> JsValue
     where
         F: Future<Output = O> + Send + 'static,
@@ -38,6 +43,7 @@ impl JsContext {
             let res = future.await;
             match res.to_js_call_result() {
                 Ok(r) => resolver.resolve(r),
+                Err(JsCallError::ExecutionError(e)) => resolver.reject_error(e),
                 Err(e) => resolver.reject(JsValue::String(format!("js call error:{:?}", e))),
             }
         });
@@ -56,6 +62,11 @@ impl JsContext {
     pub fn execute_pending_job(&self) -> Result<bool, ExecutionError> {
         self.context.execute_pending_job()
     }
+
+    /// Collect garbage of js heap, including cycles which reference counting could not free
+    pub fn run_gc(&self) {
+        self.context.run_gc();
+    }
 }
 
 impl Deref for JsContext {
@@ -97,6 +108,20 @@ impl PromiseResolver {
         }
     }
 
+    /// Reject with a DeftError object created on the js thread
+    pub fn reject_error(mut self, error: JsError) {
+        unsafe {
+            let p = self.promise.take().unwrap();
+            let callback = UnsafeFnOnce::new(move || {
+                let mut promise = Box::from_raw(p);
+                promise.reject(error.create_js_error())
+            });
+            self.event_loop_proxy
+                .schedule_macro_task(callback.into_box())
+                .unwrap();
+        }
+    }
+
     pub fn settle(self, result: Result<JsValue, String>) {
         match result {
             Ok(v) => self.resolve(v),
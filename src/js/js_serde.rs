@@ -1,4 +1,5 @@
 #![allow(unused)]
+use crate::js::js_engine::JsEngine;
 use quick_js::JsValue;
 use serde::ser::{
     SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
@@ -85,7 +86,13 @@ impl Serializer for JsValueSerializer {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        todo!()
+        // Latin-1 string is stored by quickjs as one byte per char, which is much cheaper to
+        // convert than an array of numbers
+        let binary: String = v.iter().map(|b| *b as char).collect();
+        JsEngine::get()
+            .js_context
+            .call_function("Bytes_toArrayBuffer", vec![JsValue::String(binary)])
+            .map_err(|_| Error)
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
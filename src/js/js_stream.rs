@@ -0,0 +1,96 @@
+use crate as deft;
+use crate::js::js_engine::JsEngine;
+use crate::js::{JsCallError, JsError, ToJsCallResult, ToJsValue};
+use deft_macros::js_methods;
+use futures_util::{Stream, StreamExt};
+use quick_js::{JsValue, ValueError};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+type BoxedStream = Pin<Box<dyn Stream<Item = Result<JsValue, JsCallError>> + Send>>;
+
+thread_local! {
+    static NEXT_STREAM_ID: Cell<u32> = Cell::new(1);
+    static STREAMS: RefCell<HashMap<u32, Arc<Mutex<BoxedStream>>>> = RefCell::new(HashMap::new());
+}
+
+/// Stream returned by js functions, surfaces as an async iterator in js.
+///
+/// ```ignore
+/// #[js_func]
+/// pub fn ticks(count: u32) -> JsStream {
+///     JsStream::new(futures_util::stream::iter(0..count))
+/// }
+/// ```
+///
+/// ```js
+/// for await (const tick of ticks(3)) {}
+/// ```
+pub struct JsStream {
+    stream: BoxedStream,
+}
+
+impl JsStream {
+    pub fn new<S, T>(stream: S) -> Self
+    where
+        S: Stream<Item = T> + Send + 'static,
+        T: ToJsCallResult + 'static,
+    {
+        Self {
+            stream: Box::pin(stream.map(|item| item.to_js_call_result())),
+        }
+    }
+}
+
+impl ToJsValue for JsStream {
+    fn to_js_value(self) -> Result<JsValue, ValueError> {
+        let id = NEXT_STREAM_ID.get();
+        NEXT_STREAM_ID.set(id + 1);
+        STREAMS.with_borrow_mut(|m| m.insert(id, Arc::new(Mutex::new(self.stream))));
+        let js_engine = JsEngine::get();
+        js_engine
+            .js_context
+            .call_function("AsyncStream_create", vec![JsValue::Int(id as i32)])
+            .map_err(|e| ValueError::Internal(format!("failed to create stream: {:?}", e)))
+    }
+}
+
+#[allow(nonstandard_style)]
+pub struct AsyncStream {}
+
+#[js_methods]
+impl AsyncStream {
+    /// Resolve the next item as an iterator result, i.e. `{done, value}`
+    #[js_func]
+    pub fn next(id: u32) -> Result<JsValue, JsError> {
+        let stream = STREAMS.with_borrow(|m| m.get(&id).cloned());
+        let stream = stream.ok_or_else(|| JsError::from_str("stream closed"))?;
+        let mut js_engine = JsEngine::get();
+        let (promise, resolver) = js_engine.create_promise();
+        js_engine.runtime_handle().spawn(async move {
+            let item = stream.lock().await.next().await;
+            let (done, value) = match item {
+                None => (true, JsValue::Undefined),
+                Some(Ok(v)) => (false, v),
+                Some(Err(e)) => {
+                    resolver.reject(JsValue::String(format!("stream error:{:?}", e)));
+                    return;
+                }
+            };
+            let mut result = HashMap::new();
+            result.insert("done".to_string(), JsValue::Bool(done));
+            result.insert("value".to_string(), value);
+            resolver.resolve(JsValue::Object(result));
+        });
+        Ok(promise)
+    }
+
+    /// Drop the stream, pending items are discarded
+    #[js_func]
+    pub fn close(id: u32) {
+        STREAMS.with_borrow_mut(|m| m.remove(&id));
+    }
+}
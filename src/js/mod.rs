@@ -1,11 +1,17 @@
 pub mod js_binding;
+pub mod js_callback;
+#[cfg(feature = "debugger")]
+pub mod js_debugger;
 pub mod js_deserialze;
 pub mod js_engine;
 pub mod js_event_loop;
 pub mod js_runtime;
 pub mod js_serde;
+pub mod js_stream;
 pub mod js_value_util;
 
 pub use js_binding::*;
+pub use js_callback::JsCallbackHandle;
 pub use js_runtime::JsContext;
+pub use js_stream::JsStream;
 pub use quick_js::*;
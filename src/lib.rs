@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 #![allow(deprecated)]
 
-use crate::app::{App, AppEvent, AppEventPayload, WinitApp};
+use crate::app::{App, AppEvent, WinitApp};
 use anyhow::{anyhow, Error};
 use measure_time::debug_time;
 use std::sync::OnceLock;
@@ -10,10 +10,15 @@ use winit::platform::android::activity::AndroidApp;
 
 pub use quick_js::JsValue;
 pub use winit::event_loop::{ActiveEventLoop, EventLoop, EventLoopBuilder, EventLoopProxy};
+pub mod a11y;
 pub mod app;
+pub mod atom;
+pub mod autofill;
 pub mod base;
+pub mod batch;
 pub mod border;
 pub mod color;
+pub mod config;
 pub mod console;
 pub mod mrc;
 pub mod style;
@@ -21,21 +26,34 @@ pub mod style;
 pub mod canvas_util;
 pub mod cursor;
 pub mod data_dir;
+#[cfg(feature = "devtools")]
+pub mod devtools;
 pub mod element;
 pub mod event;
 pub mod event_loop;
 pub mod ext;
+pub mod features;
+pub mod gc;
+pub mod headless;
 pub mod img_manager;
+#[cfg(feature = "inspector")]
+pub mod inspector;
 pub mod js;
 pub mod loader;
 pub mod macro_mod;
+pub mod metrics;
+pub mod network;
 pub mod number;
 pub mod performance;
+pub mod protocol;
 pub mod renderer;
 pub mod resource_table;
+pub mod safe_mode;
 pub mod string;
+pub mod system_metrics;
 pub mod time;
 pub mod timer;
+pub mod view;
 mod trace;
 #[cfg(feature = "websocket")]
 pub mod websocket;
@@ -45,8 +63,9 @@ pub mod window;
 mod android;
 pub mod animation;
 pub mod cache;
+mod color_profile;
 mod computed;
-mod error;
+pub mod error;
 mod font;
 mod frame_rate;
 mod id_generator;
@@ -68,22 +87,23 @@ pub mod winit;
 
 use crate::base::ResultWaiter;
 use crate::console::init_console;
-use crate::event_loop::AppEventProxy;
+use crate::event_loop::{AppEventProxy, AppEventWakeUp};
 pub use deft_macros::*;
 
 pub static APP_EVENT_PROXY: OnceLock<AppEventProxy> = OnceLock::new();
 
-fn run_event_loop(event_loop: EventLoop<AppEventPayload>, deft_app: App) {
-    let el_proxy = AppEventProxy::new(event_loop.create_proxy());
+fn create_app(deft_app: App, el_proxy: AppEventProxy) -> WinitApp {
     {
         let el_proxy = el_proxy.clone();
         APP_EVENT_PROXY.get_or_init(move || el_proxy);
     }
+    debug_time!("init engine time");
+    WinitApp::new(deft_app, el_proxy)
+}
+
+fn run_event_loop(event_loop: EventLoop<AppEventWakeUp>, deft_app: App) {
     #[allow(unused_mut)]
-    let mut app = {
-        debug_time!("init engine time");
-        WinitApp::new(deft_app, el_proxy)
-    };
+    let mut app = create_app(deft_app, AppEventProxy::new(event_loop.create_proxy()));
     #[cfg(ohos)]
     platform::run_app(event_loop, app);
     #[cfg(not(ohos))]
@@ -93,7 +113,7 @@ fn run_event_loop(event_loop: EventLoop<AppEventPayload>, deft_app: App) {
 /// Boostrap for desktop apps
 pub fn bootstrap(deft_app: App) {
     init_console();
-    let mut elb: EventLoopBuilder<AppEventPayload> = EventLoop::with_user_event();
+    let mut elb: EventLoopBuilder<AppEventWakeUp> = EventLoop::with_user_event();
     #[cfg(all(x11_platform, wayland_platform))]
     {
         use ::winit::platform::wayland::EventLoopBuilderExtWayland;
@@ -104,10 +124,83 @@ pub fn bootstrap(deft_app: App) {
             elb.with_x11();
         }
     }
+    #[cfg(all(target_os = "windows", feature = "menu"))]
+    {
+        use ::winit::platform::windows::EventLoopBuilderExtWindows;
+        elb.with_msg_hook(|msg| ext::ext_menu::translate_accelerator(msg));
+    }
     let event_loop = elb.build().unwrap();
     run_event_loop(event_loop, deft_app);
 }
 
+/// Bootstrap for the web target, call it from `main` and return. Modules are fetched relative to
+/// the page before app started, `module_names` must include the entry module and all modules it
+/// imports, e.g. a single bundled `index.js`.
+#[cfg(emscripten_platform)]
+pub fn bootstrap_web(module_names: &[&str]) {
+    loader::FetchModuleLoader::fetch(".", module_names, |result| match result {
+        Ok(loader) => bootstrap(App::new(WebApp {
+            loader: Some(loader),
+        })),
+        Err(e) => log::error!("Failed to fetch modules: {}", e),
+    });
+}
+
+#[cfg(emscripten_platform)]
+struct WebApp {
+    loader: Option<loader::FetchModuleLoader>,
+}
+
+#[cfg(emscripten_platform)]
+impl app::IApp for WebApp {
+    fn create_module_loader(
+        &mut self,
+    ) -> Box<dyn quick_js::loader::JsModuleLoader + Send + Sync + 'static> {
+        match self.loader.take() {
+            Some(loader) => Box::new(loader),
+            None => Box::new(loader::StaticModuleLoader::new()),
+        }
+    }
+}
+
+/// Bootstrap without physical display, e.g. for screenshot tests in CI. Windows are kept hidden
+/// and rendered into memory by the headless backend, use `Window::render_to_image` to capture them.
+/// On linux, a virtual display is started with `Xvfb` if `DISPLAY` is not set.
+pub fn bootstrap_headless(deft_app: App) {
+    headless::set_headless(true);
+    #[cfg(linux_platform)]
+    let _display = if std::env::var_os("DISPLAY").is_none() {
+        match headless::VirtualDisplay::start(1920, 1080) {
+            Ok(display) => Some(display),
+            Err(e) => {
+                log::error!("Failed to start virtual display: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    bootstrap(deft_app);
+}
+
+/// Create app driven by an event loop owned by host instead of `bootstrap`, e.g. to run deft
+/// windows inside an existing winit app. The host must forward callbacks of its
+/// `ApplicationHandler` to the returned handler on the main thread, and call its `user_event`
+/// with `AppEventWakeUp` whenever `waker` is called. `waker` could be called from any thread,
+/// it should return false if the event loop is closed. Only one app could be created in a process.
+/// Quitting deft, e.g. closing its last window, never exits the event loop of host.
+pub fn embed<F>(deft_app: App, waker: F) -> Result<WinitApp, Error>
+where
+    F: Fn() -> bool + Send + Sync + 'static,
+{
+    if APP_EVENT_PROXY.get().is_some() {
+        return Err(anyhow!("app already created"));
+    }
+    init_console();
+    app::set_embedded();
+    Ok(create_app(deft_app, AppEventProxy::with_waker(waker)))
+}
+
 /// Send an app event. Could call from any thread.
 pub fn send_app_event(event: AppEvent) -> Result<ResultWaiter<()>, Error> {
     let proxy = APP_EVENT_PROXY
@@ -127,7 +220,7 @@ pub fn is_mobile_platform() -> bool {
 
 /// Show repaint area, just for debug
 pub fn show_repaint_area() -> bool {
-    false
+    render::debug::is_repaint_areas_shown()
 }
 
 /// Show focus hint, just for debug
@@ -137,7 +230,7 @@ pub fn show_focus_hint() -> bool {
 
 /// Show layer hint, just for debug
 pub fn show_layer_hint() -> bool {
-    false
+    render::debug::is_layer_borders_shown()
 }
 
 /// Bootstrap for android apps
@@ -165,6 +258,24 @@ pub fn android_bootstrap(app: AndroidApp, deft_app: App) {
     run_event_loop(event_loop, deft_app);
 }
 
+/// Bootstrap for ios apps, called from the entry of the Xcode project and never returns
+#[cfg(ios_platform)]
+pub fn ios_bootstrap(deft_app: App) {
+    crate::log::SimpleLogger::init_with_max_level(::log::LevelFilter::Warn);
+    if std::env::var(data_dir::ENV_KEY).is_err() {
+        // Home is the sandbox of the app, files in Application Support are kept across launches
+        if let Ok(home) = std::env::var("HOME") {
+            let data_path = std::path::Path::new(&home).join("Library/Application Support");
+            unsafe {
+                std::env::set_var(data_dir::ENV_KEY, data_path);
+            }
+        }
+    }
+    ::log::debug!("data path: {:?}", data_dir::get_data_path(""));
+    let event_loop = EventLoop::with_user_event().build().unwrap();
+    run_event_loop(event_loop, deft_app);
+}
+
 #[cfg(ohos)]
 pub fn ohos_bootstrap(openharmony_app: openharmony_ability::OpenHarmonyApp, deft_app: App) {
     use winit::platform::ohos::EventLoopBuilderExtOpenHarmony;
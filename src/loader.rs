@@ -1,34 +1,164 @@
 #[cfg(feature = "http")]
 mod http_loader;
+mod integrity;
 
+use crate::protocol::{self, ProtocolRequest};
 use anyhow::anyhow;
 use quick_js::loader::JsModuleLoader;
+#[cfg(emscripten_platform)]
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::{Error, ErrorKind};
+#[cfg(emscripten_platform)]
+use std::rc::Rc;
 
 #[cfg(feature = "http")]
 pub use crate::loader::http_loader::DevModuleLoader;
+pub use crate::loader::integrity::ModuleIntegrity;
+
+/// Load module served by protocol handlers of rust, None if no handler serves its scheme.
+/// Handlers of js are not available as modules are loaded before js starts. Loaders verifying
+/// integrity should verify these modules too.
+pub fn load_protocol_module(module_name: &str) -> Option<Result<String, Error>> {
+    let result = protocol::load(&ProtocolRequest::get(module_name))?;
+    Some(
+        result
+            .map_err(|e| Error::new(ErrorKind::NotFound, e))
+            .and_then(|r| {
+                String::from_utf8(r.body).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+            }),
+    )
+}
 
 pub struct StaticModuleLoader {
     sources: HashMap<String, String>,
+    integrity: Option<ModuleIntegrity>,
 }
 
 impl StaticModuleLoader {
     pub fn new() -> Self {
         StaticModuleLoader {
             sources: HashMap::new(),
+            integrity: None,
         }
     }
     pub fn add_module(&mut self, module_name: String, source: String) {
         self.sources.insert(module_name, source);
     }
+
+    /// Verify modules with the integrity hashes before they are evaluated
+    pub fn set_integrity(&mut self, integrity: ModuleIntegrity) {
+        self.integrity = Some(integrity);
+    }
 }
 
 impl JsModuleLoader for StaticModuleLoader {
     fn load(&mut self, module_name: &str) -> Result<String, Error> {
+        let source = match load_protocol_module(module_name) {
+            Some(result) => result?,
+            None => match self.sources.get(module_name) {
+                None => return Err(Error::new(ErrorKind::NotFound, anyhow!("Not found"))),
+                Some(s) => s.to_string(),
+            },
+        };
+        if let Some(integrity) = &self.integrity {
+            integrity.verify(module_name, &source)?;
+        }
+        Ok(source)
+    }
+}
+
+/// Load modules fetched relative to the page, for the web target. Module loading of the js engine
+/// is synchronous, so modules are fetched asynchronously before app started, see `bootstrap_web`.
+#[cfg(emscripten_platform)]
+pub struct FetchModuleLoader {
+    sources: HashMap<String, String>,
+}
+
+#[cfg(emscripten_platform)]
+impl FetchModuleLoader {
+    /// Fetch modules relative to base, callback is called with the loader when all are fetched or
+    /// with the first error.
+    pub fn fetch<F: FnOnce(Result<Self, Error>) + 'static>(
+        base: &str,
+        module_names: &[&str],
+        callback: F,
+    ) {
+        let pending = Rc::new(RefCell::new(PendingFetch {
+            remaining: module_names.len(),
+            sources: HashMap::new(),
+            callback: Some(Box::new(callback)),
+        }));
+        if module_names.is_empty() {
+            return PendingFetch::finish(&pending, Ok(()));
+        }
+        for module_name in module_names {
+            let url = if module_name.contains("://") || module_name.starts_with("/") {
+                module_name.to_string()
+            } else {
+                format!("{}/{}", base.trim_end_matches("/"), module_name)
+            };
+            let module_name = module_name.to_string();
+            let pending = pending.clone();
+            crate::platform::fetch_text(&url.clone(), move |result| match result {
+                Ok(source) => {
+                    let remaining = {
+                        let mut p = pending.borrow_mut();
+                        p.sources.insert(module_name, source);
+                        p.remaining -= 1;
+                        p.remaining
+                    };
+                    if remaining == 0 {
+                        PendingFetch::finish(&pending, Ok(()));
+                    }
+                }
+                Err(e) => PendingFetch::finish(
+                    &pending,
+                    Err(Error::new(
+                        ErrorKind::NotFound,
+                        anyhow!("Failed to load module {}: {}", url, e),
+                    )),
+                ),
+            });
+        }
+    }
+}
+
+#[cfg(emscripten_platform)]
+struct PendingFetch {
+    remaining: usize,
+    sources: HashMap<String, String>,
+    callback: Option<Box<dyn FnOnce(Result<FetchModuleLoader, Error>)>>,
+}
+
+#[cfg(emscripten_platform)]
+impl PendingFetch {
+    /// Call the callback once, outside of the borrow because it usually starts the app
+    fn finish(pending: &RefCell<Self>, result: Result<(), Error>) {
+        let (callback, result) = {
+            let mut p = pending.borrow_mut();
+            let Some(callback) = p.callback.take() else {
+                return;
+            };
+            let sources = std::mem::take(&mut p.sources);
+            (callback, result.map(|_| FetchModuleLoader { sources }))
+        };
+        callback(result);
+    }
+}
+
+#[cfg(emscripten_platform)]
+impl JsModuleLoader for FetchModuleLoader {
+    fn load(&mut self, module_name: &str) -> Result<String, Error> {
+        if let Some(result) = load_protocol_module(module_name) {
+            return result;
+        }
         match self.sources.get(module_name) {
-            None => Err(Error::new(ErrorKind::NotFound, anyhow!("Not found"))),
-            Some(s) => Ok(s.to_string()),
+            Some(source) => Ok(source.clone()),
+            None => Err(Error::new(
+                ErrorKind::NotFound,
+                anyhow!("Module {} was not fetched", module_name),
+            )),
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::loader::{load_protocol_module, ModuleIntegrity};
 use anyhow::anyhow;
 use quick_js::loader::{FsJsModuleLoader, JsModuleLoader};
 use std::io::{Error, ErrorKind};
@@ -44,6 +45,7 @@ impl JsModuleLoader for RemoteModuleLoader {
 pub struct DevModuleLoader {
     is_first_load: bool,
     remote_module_loader: RemoteModuleLoader,
+    integrity: Option<ModuleIntegrity>,
 }
 
 impl DevModuleLoader {
@@ -52,8 +54,14 @@ impl DevModuleLoader {
         Self {
             is_first_load: true,
             remote_module_loader: RemoteModuleLoader::new(base),
+            integrity: None,
         }
     }
+
+    /// Verify downloaded modules with the integrity hashes before they are evaluated
+    pub fn set_integrity(&mut self, integrity: ModuleIntegrity) {
+        self.integrity = Some(integrity);
+    }
 }
 
 impl JsModuleLoader for DevModuleLoader {
@@ -62,9 +70,15 @@ impl JsModuleLoader for DevModuleLoader {
         self.is_first_load = false;
         let start_time = std::time::Instant::now();
         loop {
-            let result = self.remote_module_loader.load(module_name);
+            let result = match load_protocol_module(module_name) {
+                Some(result) => result,
+                None => self.remote_module_loader.load(module_name),
+            };
             match result {
                 Ok(source) => {
+                    if let Some(integrity) = &self.integrity {
+                        integrity.verify(module_name, &source)?;
+                    }
                     return Ok(source);
                 }
                 Err(err) => {
@@ -103,6 +117,9 @@ impl DefaultModuleLoader {
 
 impl JsModuleLoader for DefaultModuleLoader {
     fn load(&mut self, module_name: &str) -> Result<String, Error> {
+        if let Some(result) = load_protocol_module(module_name) {
+            return result;
+        }
         if let Some(fs_loader) = &mut self.fs_module_loader {
             if let Ok(module) = fs_loader.load(module_name) {
                 return Ok(module);
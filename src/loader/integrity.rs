@@ -0,0 +1,100 @@
+use anyhow::anyhow;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+const SHA256_PREFIX: &str = "sha256-";
+
+/// SHA-256 hashes of modules, sources are verified before evaluation.
+/// Hashes use the subresource integrity format, e.g. "sha256-<base64 digest>".
+pub struct ModuleIntegrity {
+    hashes: HashMap<String, Vec<u8>>,
+    strict: bool,
+}
+
+impl ModuleIntegrity {
+    /// Modules without hashes are refused in release builds
+    pub fn new() -> Self {
+        Self {
+            hashes: HashMap::new(),
+            strict: !cfg!(debug_assertions),
+        }
+    }
+
+    /// Parse manifest of module names to hashes, e.g. `{"index.js": "sha256-..."}`
+    pub fn from_manifest(manifest: &str) -> Result<Self, anyhow::Error> {
+        let entries: HashMap<String, String> = serde_json::from_str(manifest)?;
+        let mut integrity = Self::new();
+        for (module_name, hash) in entries {
+            integrity.add_hash(&module_name, &hash)?;
+        }
+        Ok(integrity)
+    }
+
+    /// Hash of source in the manifest format
+    pub fn hash(source: &str) -> String {
+        let digest = Sha256::digest(source.as_bytes());
+        format!("{}{}", SHA256_PREFIX, BASE64_STANDARD.encode(digest))
+    }
+
+    pub fn add_hash(&mut self, module_name: &str, hash: &str) -> Result<(), anyhow::Error> {
+        let digest = hash
+            .strip_prefix(SHA256_PREFIX)
+            .ok_or_else(|| anyhow!("unsupported hash of {}: {}", module_name, hash))?;
+        let digest = BASE64_STANDARD.decode(digest)?;
+        if digest.len() != 32 {
+            return Err(anyhow!("invalid sha256 digest of {}", module_name));
+        }
+        self.hashes.insert(module_name.to_string(), digest);
+        Ok(())
+    }
+
+    /// Whether modules without hashes are refused, defaults to true in release builds
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn verify(&self, module_name: &str, source: &str) -> Result<(), Error> {
+        match self.hashes.get(module_name) {
+            Some(expected) => {
+                let digest = Sha256::digest(source.as_bytes());
+                if digest.as_slice() != expected.as_slice() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        anyhow!("Integrity check failed for module {}", module_name),
+                    ));
+                }
+                Ok(())
+            }
+            None if self.strict => Err(Error::new(
+                ErrorKind::PermissionDenied,
+                anyhow!("No integrity hash for module {}", module_name),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader::integrity::ModuleIntegrity;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_verify() {
+        let source = "console.log('hello')";
+        let manifest = format!(r#"{{"index.js": "{}"}}"#, ModuleIntegrity::hash(source));
+        let mut integrity = ModuleIntegrity::from_manifest(&manifest).unwrap();
+        integrity.set_strict(true);
+        assert!(integrity.verify("index.js", source).is_ok());
+        let err = integrity.verify("index.js", "alert(1)").unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+        let err = integrity.verify("other.js", source).unwrap_err();
+        assert_eq!(ErrorKind::PermissionDenied, err.kind());
+        integrity.set_strict(false);
+        assert!(integrity.verify("other.js", source).is_ok());
+        assert!(ModuleIntegrity::from_manifest(r#"{"a.js": "md5-abc"}"#).is_err());
+    }
+}
@@ -3,8 +3,8 @@ use crate::element::container::Container;
 use crate::element::label::Label;
 use crate::element::{Element, ElementBackend, ElementType};
 use crate::event::ClickEventListener;
-use crate::js_value;
 use crate::mrc::Mrc;
+use crate::{js_value, ok_or_return, some_or_return};
 use deft_macros::{js_methods, mrc_object};
 use log::error;
 use quick_js::JsValue;
@@ -35,12 +35,30 @@ impl Menu {
     pub fn add_separator(&mut self) {
         self.items.push(MenuItem::Separator);
     }
+
+    /// Add an item opening menu next to it when clicked
+    pub fn add_submenu(&mut self, label: &str, menu: Menu) {
+        self.items.push(MenuItem::Submenu(label.to_string(), menu));
+    }
+
+    /// Append items of other menu
+    pub fn extend(&mut self, other: &Menu) {
+        self.items.extend(other.items.iter().cloned());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
 }
 
 #[mrc_object]
 pub struct StandardMenuItem {
     pub disabled: bool,
     pub label: String,
+    /// Shortcut hint shown after label, e.g. "CmdOrCtrl+C"
+    pub accelerator: Option<String>,
+    /// Check mark shown before label if some
+    pub checked: Option<bool>,
     pub onclick: Mrc<Option<Box<dyn FnMut()>>>,
 }
 
@@ -52,6 +70,8 @@ impl StandardMenuItem {
         StandardMenuItemData {
             disabled: false,
             label: label.to_string(),
+            accelerator: None,
+            checked: None,
             onclick: Mrc::new(Some(Box::new(callback))),
         }
         .to_ref()
@@ -62,6 +82,8 @@ impl StandardMenuItem {
         StandardMenuItemData {
             disabled: false,
             label,
+            accelerator: None,
+            checked: None,
             onclick: Mrc::new(Some(Box::new(move || {
                 if let Err(e) = callback.clone().call_as_function(vec![]) {
                     error!("Error calling callback: {}", e);
@@ -80,34 +102,70 @@ impl StandardMenuItem {
     pub fn get_disabled(&self) -> bool {
         self.disabled
     }
+
+    /// Set shortcut hint, the shortcut itself should be handled by the app
+    #[js_func]
+    pub fn set_accelerator(&mut self, accelerator: Option<String>) {
+        self.accelerator = accelerator;
+    }
+
+    #[js_func]
+    pub fn get_accelerator(&self) -> Option<String> {
+        self.accelerator.clone()
+    }
 }
 
 #[derive(Clone)]
 pub enum MenuItem {
     Separator,
     Standard(StandardMenuItem),
+    Submenu(String, Menu),
+}
+
+/// Accelerator shown in menus, "CmdOrCtrl" is shown as the modifier of current platform
+fn accelerator_label(accelerator: &str) -> String {
+    let modifier = if cfg!(target_os = "macos") {
+        "Cmd"
+    } else {
+        "Ctrl"
+    };
+    accelerator
+        .split('+')
+        .map(|t| match t.to_lowercase().as_str() {
+            "cmdorctrl" | "commandorcontrol" => modifier,
+            _ => t,
+        })
+        .collect::<Vec<_>>()
+        .join("+")
 }
 
 pub fn build_menu_elements(menu: Menu) -> Element {
     let mut root = Element::create(Container::create);
-    root.tag = "menu".to_string();
+    root.tag = "menu".into();
     root.set_element_type(ElementType::Widget);
     for it in menu.items.clone() {
         match it {
             MenuItem::Separator => {
                 let mut e = Element::create(Container::create);
-                e.tag = "menu-item-separator".to_string();
+                e.tag = "menu-item-separator".into();
                 e.set_element_type(ElementType::Widget);
                 root.add_child(e, -1).unwrap();
             }
             MenuItem::Standard(s) => {
-                let mut e = Element::create(Label::create);
+                let mut e = Element::create(Container::create);
                 e.is_form_element = true;
-                e.tag = "menu-item-standard".to_string();
+                e.tag = "menu-item-standard".into();
                 e.set_element_type(ElementType::Widget);
                 e.set_disabled(s.disabled);
-                let label = e.get_backend_mut_as::<Label>();
-                label.set_text(s.label.to_string());
+                if let Some(checked) = s.checked {
+                    let mark = if checked { "✓" } else { "" };
+                    e.add_child(create_label("menu-item-check", mark), -1).unwrap();
+                }
+                e.add_child(create_label("menu-item-label", &s.label), -1).unwrap();
+                if let Some(accelerator) = &s.accelerator {
+                    let accelerator = accelerator_label(accelerator);
+                    e.add_child(create_label("menu-item-accelerator", &accelerator), -1).unwrap();
+                }
                 let mut onclick = s.onclick.clone();
                 e.register_event_listener(ClickEventListener::new(move |_, _| {
                     if let Some(onclick) = &mut *onclick {
@@ -116,7 +174,57 @@ pub fn build_menu_elements(menu: Menu) -> Element {
                 }));
                 root.add_child(e, -1).unwrap();
             }
+            MenuItem::Submenu(label, submenu) => {
+                let mut e = Element::create(Container::create);
+                e.is_form_element = true;
+                e.tag = "menu-item-submenu".into();
+                e.set_element_type(ElementType::Widget);
+                e.add_child(create_label("menu-item-label", &label), -1)
+                    .unwrap();
+                e.add_child(create_label("menu-item-arrow", "›"), -1)
+                    .unwrap();
+                let weak = e.as_weak();
+                e.register_event_listener(ClickEventListener::new(move |_, ctx| {
+                    // Keep this menu open while the submenu is shown
+                    ctx.propagation_cancelled = true;
+                    let el = ok_or_return!(weak.upgrade());
+                    let window = some_or_return!(el.get_window());
+                    let bounds = el.get_origin_bounds();
+                    if let Ok(window) = window.upgrade_mut() {
+                        window.popup_menu(submenu.clone(), bounds.right(), bounds.y);
+                    }
+                }));
+                root.add_child(e, -1).unwrap();
+            }
         }
     }
     root
 }
+
+fn create_label(tag: &str, text: &str) -> Element {
+    let mut e = Element::create(Label::create);
+    e.tag = tag.into();
+    e.set_element_type(ElementType::Widget);
+    let label = e.get_backend_mut_as::<Label>();
+    label.set_text(text.to_string());
+    e
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::menu::accelerator_label;
+
+    #[test]
+    fn test_accelerator_label() {
+        let modifier = if cfg!(target_os = "macos") {
+            "Cmd"
+        } else {
+            "Ctrl"
+        };
+        assert_eq!(
+            format!("{}+Shift+Z", modifier),
+            accelerator_label("CmdOrCtrl+Shift+Z")
+        );
+        assert_eq!("Alt+F4", accelerator_label("Alt+F4"));
+    }
+}
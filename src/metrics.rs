@@ -0,0 +1,347 @@
+use log::{error, info, warn};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::net::UdpSocket;
+use std::sync::mpsc::{
+    channel, sync_channel, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError,
+};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const FRAME_DROPS: &str = "deft.frame.drops";
+pub const FRAME_TIME: &str = "deft.frame.time";
+pub const GC_TIME: &str = "deft.js.gc_time";
+pub const INPUT_LATENCY: &str = "deft.input.latency";
+pub const JS_EXCEPTIONS: &str = "deft.js.exceptions";
+pub const LONG_TASKS: &str = "deft.event_loop.long_tasks";
+pub const LONG_TASK_TIME: &str = "deft.event_loop.long_task_time";
+
+/// Tasks running longer than this are reported as long tasks
+pub const LONG_TASK_MILLIS: u64 = 50;
+
+const MAX_BUFFERED_RECORDS: usize = 10000;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+/// Batches waiting for the exporter thread, flushing more than this keeps records buffered
+const MAX_PENDING_BATCHES: usize = 8;
+/// Time to wait for exporters to finish when the app shuts down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+static METRICS: LazyLock<Mutex<MetricsBuffer>> = LazyLock::new(|| {
+    Mutex::new(MetricsBuffer {
+        enabled: false,
+        records: VecDeque::new(),
+        sender: None,
+        exporter_done: None,
+    })
+});
+
+static EXPORTERS: LazyLock<Mutex<Vec<Box<dyn MetricsExporter>>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+struct MetricsBuffer {
+    /// Metrics are collected only when any exporter added
+    enabled: bool,
+    records: VecDeque<MetricRecord>,
+    /// Queue of the exporter thread
+    sender: Option<SyncSender<Vec<MetricRecord>>>,
+    /// Disconnected when the exporter thread exits
+    exporter_done: Option<Receiver<()>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricKind {
+    Counter,
+    Timing,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MetricRecord {
+    pub name: String,
+    pub kind: MetricKind,
+    pub value: f64,
+    /// Milliseconds since unix epoch
+    pub time: u64,
+}
+
+pub trait MetricsExporter: Send {
+    fn export(&mut self, records: &[MetricRecord]);
+}
+
+pub fn increment(name: &str, value: f64) {
+    record(name, MetricKind::Counter, value);
+}
+
+pub fn timing(name: &str, millis: f64) {
+    record(name, MetricKind::Timing, millis);
+}
+
+pub fn is_enabled() -> bool {
+    METRICS.lock().unwrap().enabled
+}
+
+/// Add an exporter, records are exported periodically in a background thread
+pub fn add_exporter<E: MetricsExporter + 'static>(exporter: E) {
+    EXPORTERS.lock().unwrap().push(Box::new(exporter));
+    let mut metrics = METRICS.lock().unwrap();
+    if !metrics.enabled {
+        metrics.enabled = true;
+        let (sender, receiver) = sync_channel(MAX_PENDING_BATCHES);
+        let (done_sender, done_receiver) = channel();
+        metrics.sender = Some(sender);
+        metrics.exporter_done = Some(done_receiver);
+        let _ = thread::Builder::new()
+            .name("metrics".to_string())
+            .spawn(move || run_exporter(receiver, done_sender));
+    }
+}
+
+/// Queue all buffered records for export, exporters run in the metrics thread
+pub fn flush() {
+    let mut metrics = METRICS.lock().unwrap();
+    if metrics.records.is_empty() {
+        return;
+    }
+    let Some(sender) = metrics.sender.clone() else {
+        return;
+    };
+    let records = Vec::from(std::mem::take(&mut metrics.records));
+    match sender.try_send(records) {
+        Ok(_) => {}
+        Err(TrySendError::Full(records)) => {
+            // Keep them buffered until the exporter catches up
+            metrics.records = VecDeque::from(records);
+        }
+        Err(TrySendError::Disconnected(_)) => {
+            warn!("metrics exporter thread stopped");
+        }
+    }
+}
+
+/// Export buffered records before the app exits, waits for exporters to finish queued batches
+/// within a timeout. Records are no longer collected after it.
+pub fn shutdown() {
+    shutdown_with_timeout(SHUTDOWN_TIMEOUT);
+}
+
+fn shutdown_with_timeout(timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    // Sent without the lock, the exporter thread takes buffered records with it
+    let (sender, exporter_done, mut records) = {
+        let mut metrics = METRICS.lock().unwrap();
+        metrics.enabled = false;
+        let records = Vec::from(std::mem::take(&mut metrics.records));
+        (metrics.sender.take(), metrics.exporter_done.take(), records)
+    };
+    let Some(sender) = sender else {
+        return;
+    };
+    while !records.is_empty() {
+        match sender.try_send(records) {
+            Ok(_) => break,
+            Err(TrySendError::Full(r)) if Instant::now() < deadline => {
+                records = r;
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(_) => {
+                warn!("failed to export metrics before shutdown");
+                break;
+            }
+        }
+    }
+    // The exporter thread exits after queued batches are exported
+    drop(sender);
+    if let Some(done) = exporter_done {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if let Err(RecvTimeoutError::Timeout) = done.recv_timeout(timeout) {
+            warn!("timed out exporting metrics before shutdown");
+        }
+    }
+}
+
+fn run_exporter(receiver: Receiver<Vec<MetricRecord>>, _done: Sender<()>) {
+    loop {
+        let records = match receiver.recv_timeout(FLUSH_INTERVAL) {
+            Ok(records) => records,
+            Err(RecvTimeoutError::Timeout) => {
+                Vec::from(std::mem::take(&mut METRICS.lock().unwrap().records))
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if records.is_empty() {
+            continue;
+        }
+        // Export without holding the lock so that add_exporter never waits for a slow exporter
+        let mut exporters = std::mem::take(&mut *EXPORTERS.lock().unwrap());
+        for exporter in exporters.iter_mut() {
+            exporter.export(&records);
+        }
+        let mut added = EXPORTERS.lock().unwrap();
+        exporters.append(&mut added);
+        *added = exporters;
+    }
+}
+
+fn record(name: &str, kind: MetricKind, value: f64) {
+    let mut metrics = METRICS.lock().unwrap();
+    if !metrics.enabled {
+        return;
+    }
+    if metrics.records.len() >= MAX_BUFFERED_RECORDS {
+        metrics.records.pop_front();
+    }
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    metrics.records.push_back(MetricRecord {
+        name: name.to_string(),
+        kind,
+        value,
+        time,
+    });
+}
+
+pub struct LogExporter;
+
+impl MetricsExporter for LogExporter {
+    fn export(&mut self, records: &[MetricRecord]) {
+        for r in records {
+            info!("metric {} {:?} {}", r.name, r.kind, r.value);
+        }
+    }
+}
+
+pub struct StatsdExporter {
+    socket: UdpSocket,
+    address: String,
+    prefix: String,
+}
+
+impl StatsdExporter {
+    const MAX_PACKET_SIZE: usize = 1400;
+
+    pub fn new(address: &str, prefix: &str) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        Ok(Self {
+            socket,
+            address: address.to_string(),
+            prefix: prefix.to_string(),
+        })
+    }
+
+    fn format_line(&self, record: &MetricRecord) -> String {
+        let ty = match record.kind {
+            MetricKind::Counter => "c",
+            MetricKind::Timing => "ms",
+        };
+        format!("{}{}:{}|{}", self.prefix, record.name, record.value, ty)
+    }
+
+    fn send(&self, packet: &str) {
+        if let Err(e) = self.socket.send_to(packet.as_bytes(), &self.address) {
+            error!("failed to send metrics to statsd: {:?}", e);
+        }
+    }
+}
+
+impl MetricsExporter for StatsdExporter {
+    fn export(&mut self, records: &[MetricRecord]) {
+        let mut packet = String::new();
+        for r in records {
+            let line = self.format_line(r);
+            if !packet.is_empty() && packet.len() + line.len() + 1 > Self::MAX_PACKET_SIZE {
+                self.send(&packet);
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            self.send(&packet);
+        }
+    }
+}
+
+/// Post records to url as a json array
+#[cfg(feature = "http")]
+pub struct HttpBatchExporter {
+    url: String,
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "http")]
+impl HttpBatchExporter {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl MetricsExporter for HttpBatchExporter {
+    fn export(&mut self, records: &[MetricRecord]) {
+        let body = records_to_json(records);
+        let result = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send();
+        if let Err(e) = result {
+            error!("failed to post metrics: {:?}", e);
+        }
+    }
+}
+
+#[cfg(any(feature = "http", test))]
+fn records_to_json(records: &[MetricRecord]) -> String {
+    serde_json::to_string(records).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::{
+        add_exporter, increment, records_to_json, shutdown_with_timeout, MetricKind, MetricRecord,
+        MetricsExporter,
+    };
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    struct CollectExporter(Arc<Mutex<Vec<String>>>);
+
+    impl MetricsExporter for CollectExporter {
+        fn export(&mut self, records: &[MetricRecord]) {
+            let mut names = self.0.lock().unwrap();
+            names.extend(records.iter().map(|r| r.name.clone()));
+        }
+    }
+
+    #[test]
+    fn test_shutdown_exports_buffered_records() {
+        let names = Arc::new(Mutex::new(Vec::new()));
+        add_exporter(CollectExporter(names.clone()));
+        increment("test.shutdown", 1.0);
+        shutdown_with_timeout(Duration::from_secs(10));
+        assert!(names.lock().unwrap().contains(&"test.shutdown".to_string()));
+    }
+
+    #[test]
+    fn test_records_to_json() {
+        let records = vec![MetricRecord {
+            name: "a\"b".to_string(),
+            kind: MetricKind::Timing,
+            value: 1.5,
+            time: 10,
+        }];
+        assert_eq!(
+            records_to_json(&records),
+            r#"[{"name":"a\"b","kind":"timing","value":1.5,"time":10}]"#
+        );
+    }
+}
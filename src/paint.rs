@@ -1,9 +1,12 @@
 use crate::base::{Id, IdKey, Rect};
 use crate::element::Element;
+use crate::render::dump::{ElementObjectDump, LayerDump, StyleSummary};
 use crate::render::layout_tree::LayoutTree;
 use crate::render::paint_object::{ElementPO, LayerPO};
 use crate::render::RenderFn;
 use crate::renderer::CpuRenderer;
+use crate::style::{format_matrix, PropValueParse};
+use crate::window::find::FindHighlights;
 use crate::{some_or_continue, some_or_return};
 use skia_safe::Canvas;
 use skia_safe::{scalar, Color, Image, Matrix, Path, PathOp, Point, Vector};
@@ -91,6 +94,9 @@ pub struct ElementObjectData {
     pub background_image: Option<Image>,
     pub background_color: Color,
     pub border_width: (f32, f32, f32, f32),
+    pub border_radius: [f32; 4],
+    /// Transform of element relative to its coord, only set when no layer created for the element
+    pub transform: Option<Matrix>,
     pub width: f32,
     pub height: f32,
     pub layer_object_idx: Option<usize>,
@@ -116,6 +122,8 @@ pub struct LayerObjectData {
     pub surface_bounds: Rect,
     pub visible_bounds: Rect,
     pub clip_rect: Option<Rect>,
+    /// Scroll offset of children layer, which is translated into the matrix
+    pub scroll_offset: (f32, f32),
 }
 
 #[derive(Clone)]
@@ -170,6 +178,9 @@ impl RenderTree {
         let mut mc = MatrixCalculator::new();
         mc.concat(&layer_object.total_matrix);
         mc.translate(element_object.layer_coord);
+        if let Some(transform) = &element_object.transform {
+            mc.concat(transform);
+        }
         Some(mc.get_total_matrix())
     }
 
@@ -216,23 +227,21 @@ impl RenderTree {
         y: f32,
     ) -> Option<(&ElementObjectData, f32, f32)> {
         let eod = &self.element_objects[lo.element_object_idx];
-        if x >= eod.coord.0
-            && x <= eod.coord.0 + eod.width
-            && y >= eod.coord.1
-            && y <= eod.coord.1 + eod.height
-        {
-            for c in lo.children.iter().rev() {
-                let r = some_or_continue!(self.get_element_object_in_normal_nodes_by_pos_recurse(
-                    c,
-                    x - eod.coord.0,
-                    y - eod.coord.1
-                ));
-                return Some(r);
-            }
-            Some((eod, x - eod.coord.0, y - eod.coord.1))
-        } else {
-            None
+        let (mut x, mut y) = (x - eod.coord.0, y - eod.coord.1);
+        if let Some(transform) = &eod.transform {
+            let Point { x: tx, y: ty } = transform.invert()?.map_xy(x, y);
+            x = tx;
+            y = ty;
+        }
+        if !contains_point_in_border_box(x, y, eod.width, eod.height, &eod.border_radius) {
+            return None;
+        }
+        for c in lo.children.iter().rev() {
+            let r =
+                some_or_continue!(self.get_element_object_in_normal_nodes_by_pos_recurse(c, x, y));
+            return Some(r);
         }
+        Some((eod, x, y))
     }
 
     pub fn create_node(&mut self, element: &mut Element) {
@@ -250,6 +259,8 @@ impl RenderTree {
             background_image: element.style.background_image.clone(),
             background_color: element.style.background_color,
             border_width: element.get_border_width(),
+            border_radius: element.style.border_radius,
+            transform: None,
             width: bounds.width,
             height: bounds.height,
 
@@ -286,6 +297,146 @@ impl RenderTree {
         }
     }
 
+    /// Move children layers of scrolled elements and layers inside them without rebuilding the
+    /// render tree, returns false if the render tree must be rebuilt instead
+    pub fn update_scroll_layers(
+        &mut self,
+        elements: &[Element],
+        layer_cache_enabled: bool,
+    ) -> bool {
+        let layer_node = some_or_return!(self.layout_tree.layer_node.clone(), false);
+        for element in elements {
+            let scroll_offset = element.scrollable.scroll_offset();
+            let node = some_or_return!(
+                self.find_children_layer_node(&layer_node, element.get_eid()),
+                false
+            );
+            let lo = &mut self.layout_tree.layer_objects[node.layer_object_idx];
+            let old_scroll_offset = lo.scroll_offset;
+            if old_scroll_offset == scroll_offset {
+                continue;
+            }
+            let correction = some_or_return!(
+                scroll_correction(&lo.total_matrix, old_scroll_offset, scroll_offset),
+                false
+            );
+            let (dx, dy) = (
+                scroll_offset.0 - old_scroll_offset.0,
+                scroll_offset.1 - old_scroll_offset.1,
+            );
+            lo.matrix = Matrix::translate((-scroll_offset.0, -scroll_offset.1));
+            lo.clip_rect = lo.clip_rect.map(|r| r.translate(dx, dy));
+            lo.scroll_offset = scroll_offset;
+            let mut layer_indices = Vec::new();
+            Self::collect_layer_indices(node, &mut layer_indices);
+            for idx in layer_indices {
+                let lo = &mut self.layout_tree.layer_objects[idx];
+                lo.total_matrix.post_concat(&correction);
+            }
+        }
+        if layer_cache_enabled {
+            // Root layer is painted on window canvas directly
+            self.layout_tree.layer_objects[layer_node.layer_object_idx].invalid_area =
+                InvalidArea::Full;
+        } else {
+            for lo in &mut self.layout_tree.layer_objects {
+                lo.invalid_area = InvalidArea::Full;
+            }
+        }
+        true
+    }
+
+    fn find_children_layer_node<'a>(
+        &self,
+        node: &'a LayerNode,
+        element_id: u32,
+    ) -> Option<&'a LayerNode> {
+        let key = &self.layout_tree.layer_objects[node.layer_object_idx].key;
+        if key.root_element_id == element_id && key.layer_type == RenderLayerType::Children {
+            return Some(node);
+        }
+        node.layer_nodes
+            .iter()
+            .find_map(|n| self.find_children_layer_node(n, element_id))
+    }
+
+    fn collect_layer_indices(node: &LayerNode, result: &mut Vec<usize>) {
+        result.push(node.layer_object_idx);
+        for n in &node.layer_nodes {
+            Self::collect_layer_indices(n, result);
+        }
+    }
+
+    /// Snapshot of layers and element objects, None if no layer built yet
+    pub fn dump(&self) -> Option<LayerDump> {
+        Some(self.dump_layer_node(self.layout_tree.layer_node.as_ref()?))
+    }
+
+    fn dump_layer_node(&self, node: &LayerNode) -> LayerDump {
+        let lo = &self.layout_tree.layer_objects[node.layer_object_idx];
+        let (invalid_area, invalid_rects) = match &lo.invalid_area {
+            InvalidArea::Full => ("full", Vec::new()),
+            InvalidArea::Partial(pia) => ("partial", pia.rects.values().cloned().collect()),
+            InvalidArea::None => ("none", Vec::new()),
+        };
+        let layer_type = match lo.key.layer_type {
+            RenderLayerType::Root => "root",
+            RenderLayerType::Children => "children",
+        };
+        LayerDump {
+            element_id: lo.key.root_element_id,
+            layer_type: layer_type.to_string(),
+            matrix: format_matrix(&lo.matrix),
+            total_matrix: format_matrix(&lo.total_matrix),
+            width: lo.width,
+            height: lo.height,
+            origin_absolute_pos: lo.origin_absolute_pos,
+            scroll_offset: lo.scroll_offset,
+            surface_bounds: lo.surface_bounds,
+            visible_bounds: lo.visible_bounds,
+            clip_rect: lo.clip_rect,
+            invalid_area: invalid_area.to_string(),
+            invalid_rects,
+            elements: node
+                .normal_nodes
+                .iter()
+                .map(|n| self.dump_normal_node(n))
+                .collect(),
+            layers: node
+                .layer_nodes
+                .iter()
+                .map(|n| self.dump_layer_node(n))
+                .collect(),
+            image: None,
+        }
+    }
+
+    fn dump_normal_node(&self, node: &NormalNode) -> ElementObjectDump {
+        let eo = &self.element_objects[node.element_object_idx];
+        let style = StyleSummary {
+            background_color: eo.background_color.to_style_string(),
+            background_image: eo.background_image.is_some(),
+            border_width: eo.border_width,
+            border_color: eo.border_color.map(|c| c.to_style_string()),
+            border_radius: eo.border_radius,
+        };
+        ElementObjectDump {
+            id: eo.element_id,
+            tag: eo.element.tag.to_string(),
+            coord: eo.coord,
+            layer_coord: eo.layer_coord,
+            width: eo.width,
+            height: eo.height,
+            transform: eo.transform.as_ref().map(format_matrix),
+            style,
+            children: node
+                .children
+                .iter()
+                .map(|n| self.dump_normal_node(n))
+                .collect(),
+        }
+    }
+
     fn build_layer_tree(&mut self, layer_object: &LayerRO) -> LayerNode {
         let mut normal_nodes = Vec::new();
         let mut layer_objects = Vec::new();
@@ -363,7 +514,7 @@ impl RenderTree {
         layer_object_idx: Option<usize>,
     ) -> Vec<RenderObject> {
         let mut children = Vec::new();
-        for mut c in element.get_children() {
+        for mut c in element.get_layout_children() {
             let child_bounds = c.get_bounds();
             matrix_calculator.save();
             matrix_calculator.translate((child_bounds.x, child_bounds.y));
@@ -427,6 +578,7 @@ impl RenderTree {
                 surface_bounds: Rect::default(),
                 visible_bounds: Rect::default(),
                 clip_rect: Some(clip_rect),
+                scroll_offset: (scroll_left, scroll_top),
             };
             self.layout_tree.layer_objects.push(layer_object_data);
             let children_layer_object = LayerRO {
@@ -488,6 +640,7 @@ impl RenderTree {
                 surface_bounds: Rect::default(),
                 visible_bounds: Rect::default(),
                 clip_rect: None,
+                scroll_offset: (0.0, 0.0),
             };
             self.layout_tree.layer_objects.push(layer_object_data);
             let obj = self.create_normal_render_object(
@@ -508,7 +661,7 @@ impl RenderTree {
             matrix_calculator.restore();
             RenderObject::Layer(layer_object)
         } else {
-            self.create_normal_render_object(
+            let obj = self.create_normal_render_object(
                 element,
                 bounds,
                 origin_x,
@@ -517,7 +670,14 @@ impl RenderTree {
                 layer_y,
                 layer_object_idx.unwrap(),
                 matrix_calculator,
-            )
+            );
+            if element.style.transform.is_some() {
+                let mut mc = MatrixCalculator::new();
+                element.apply_transform(&mut mc);
+                let element_object_idx = element.render_object_idx.unwrap();
+                self.element_objects[element_object_idx].transform = Some(mc.get_total_matrix());
+            }
+            obj
         }
     }
 
@@ -539,6 +699,8 @@ impl RenderTree {
         element_data.background_image = element.style.background_image.clone();
         element_data.background_color = element.style.background_color;
         element_data.border_width = element.get_border_width();
+        element_data.border_radius = element.style.border_radius;
+        element_data.transform = None;
         element_data.coord = (bounds.x, bounds.y);
         element_data.layer_object_idx = Some(layer_object_idx);
         element_data.layer_coord = (layer_x, layer_y);
@@ -640,6 +802,7 @@ impl RenderTree {
             element_id: eo.element_id,
             need_paint,
             focused: eo.element.is_focused(),
+            find_highlights: eo.element.resource_table.get::<FindHighlights>().cloned(),
         };
         epo
     }
@@ -770,8 +933,12 @@ impl RenderTree {
             lo.visible_bounds = visible_bounds.clone();
             invalid_area.build(visible_bounds.clone())
         };
-        let normal_nodes =
-            self.build_paint_normal_nodes(&lod.normal_nodes, viewport, &invalid_rects);
+        // Nothing to repaint, the layer is composited from its cached surface
+        let normal_nodes = if invalid_rects.is_empty() {
+            Vec::new()
+        } else {
+            self.build_paint_normal_nodes(&lod.normal_nodes, viewport, &invalid_rects)
+        };
         let mut layers = Vec::new();
         for lo in &lod.layer_nodes {
             layers.push(self.build_paint_layer_node(lo, viewport));
@@ -797,6 +964,57 @@ impl RenderTree {
     }
 }
 
+/// Check whether the point is inside the border box, corners outside of border radius are excluded.
+fn contains_point_in_border_box(
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    radius: &[f32; 4],
+) -> bool {
+    if x < 0.0 || x > width || y < 0.0 || y > height {
+        return false;
+    }
+    // (radius, center_x, center_y), order: top-left, top-right, bottom-right, bottom-left
+    let corners = [
+        (radius[0], radius[0], radius[0]),
+        (radius[1], width - radius[1], radius[1]),
+        (radius[2], width - radius[2], height - radius[2]),
+        (radius[3], radius[3], height - radius[3]),
+    ];
+    for (i, (r, cx, cy)) in corners.into_iter().enumerate() {
+        if r <= 0.0 {
+            continue;
+        }
+        let in_corner_x = if i == 0 || i == 3 { x < cx } else { x > cx };
+        let in_corner_y = if i == 0 || i == 1 { y < cy } else { y > cy };
+        if in_corner_x && in_corner_y {
+            let (dx, dy) = (x - cx, y - cy);
+            if dx * dx + dy * dy > r * r {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Matrix to concat after total matrices of a layer and layers inside it when the layer is
+/// scrolled from old_offset to new_offset, total_matrix is the one of the layer at old_offset
+fn scroll_correction(
+    total_matrix: &Matrix,
+    old_offset: (f32, f32),
+    new_offset: (f32, f32),
+) -> Option<Matrix> {
+    // Total matrix of the scrolled element
+    let mut origin = total_matrix.clone();
+    origin.pre_translate(old_offset);
+    let inverted = origin.invert()?;
+    let mut correction = origin;
+    correction.pre_translate((old_offset.0 - new_offset.0, old_offset.1 - new_offset.1));
+    correction.pre_concat(&inverted);
+    Some(correction)
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum InvalidArea {
     Full,
@@ -1006,7 +1224,7 @@ impl MatrixCalculator {
 #[cfg(test)]
 pub mod tests {
     use crate::base::Rect;
-    use crate::paint::InvalidArea;
+    use crate::paint::{contains_point_in_border_box, scroll_correction, InvalidArea};
     use log::debug;
     use measure_time::print_time;
     use skia_safe::{Matrix, Path, Vector};
@@ -1047,6 +1265,29 @@ pub mod tests {
         assert!(path.contains((30.0, 80.0)));
     }
 
+    #[test]
+    pub fn test_contains_point_in_border_box() {
+        let radius = [10.0, 0.0, 10.0, 0.0];
+        assert!(contains_point_in_border_box(
+            50.0, 50.0, 100.0, 100.0, &radius
+        ));
+        assert!(!contains_point_in_border_box(
+            1.0, 1.0, 100.0, 100.0, &radius
+        ));
+        assert!(contains_point_in_border_box(
+            99.0, 1.0, 100.0, 100.0, &radius
+        ));
+        assert!(!contains_point_in_border_box(
+            99.0, 99.0, 100.0, 100.0, &radius
+        ));
+        assert!(contains_point_in_border_box(
+            5.0, 5.0, 100.0, 100.0, &radius
+        ));
+        assert!(!contains_point_in_border_box(
+            101.0, 50.0, 100.0, 100.0, &radius
+        ));
+    }
+
     #[test]
     pub fn test_matrix() {
         let mut matrix = Matrix::translate(Vector::new(100.0, 200.0));
@@ -1070,4 +1311,18 @@ pub mod tests {
             }
         }
     }
+
+    #[test]
+    pub fn test_scroll_correction() {
+        // Scrolled element at (10, 20) with a layer inside content at (5, 5)
+        let mut total_matrix = Matrix::translate((10.0, 20.0));
+        total_matrix.pre_translate((0.0, -30.0));
+        let mut inner_matrix = total_matrix.clone();
+        inner_matrix.pre_translate((5.0, 5.0));
+        let correction = scroll_correction(&total_matrix, (0.0, 30.0), (0.0, 50.0)).unwrap();
+        total_matrix.post_concat(&correction);
+        inner_matrix.post_concat(&correction);
+        assert_eq!(total_matrix.map_xy(0.0, 0.0), (10.0, -30.0).into());
+        assert_eq!(inner_matrix.map_xy(0.0, 0.0), (15.0, -25.0).into());
+    }
 }
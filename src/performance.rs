@@ -1,5 +1,166 @@
+use crate as deft;
+use crate::js::JsError;
+use crate::{js_serialize, metrics};
+use deft_macros::js_methods;
 use log::{log, Level};
-use std::time::Instant;
+use serde::Serialize;
+use skia_window::pool;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const MAX_INPUT_LATENCY_ENTRIES: usize = 100;
+
+static INPUT_LATENCIES: LazyLock<Mutex<VecDeque<InputLatency>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+static FRAME_STATS: LazyLock<Mutex<BTreeMap<i32, FrameStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Time from receipt of input to completion of the frame painted after it
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InputLatency {
+    pub window_id: i32,
+    /// In milliseconds
+    pub latency: f64,
+    /// Milliseconds since unix epoch when the frame is completed
+    pub time: u64,
+}
+
+js_serialize!(InputLatency);
+
+/// Record latency of input, only latest entries are kept
+pub fn record_input_latency(window_id: i32, latency: Duration) {
+    let latency = latency.as_secs_f64() * 1000.0;
+    metrics::timing(metrics::INPUT_LATENCY, latency);
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let mut entries = INPUT_LATENCIES.lock().unwrap();
+    if entries.len() >= MAX_INPUT_LATENCY_ENTRIES {
+        entries.pop_front();
+    }
+    entries.push_back(InputLatency {
+        window_id,
+        latency,
+        time,
+    });
+}
+
+/// Frames produced by a window since it is created or stats are reset
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameStats {
+    pub window_id: i32,
+    pub frames: u64,
+    /// Frames missed because producing the previous frames took too long
+    pub dropped_frames: u64,
+    /// Frame rate the window is paced at
+    pub fps: f32,
+    /// Refresh rate of the monitor the window is on, if known
+    pub refresh_rate: Option<f32>,
+}
+
+js_serialize!(FrameStats);
+
+/// Record a frame of window which missed `dropped` frames
+pub fn record_frame(window_id: i32, fps: f32, refresh_rate: Option<f32>, dropped: u64) {
+    let mut stats = FRAME_STATS.lock().unwrap();
+    let stats = stats.entry(window_id).or_insert_with(|| FrameStats {
+        window_id,
+        frames: 0,
+        dropped_frames: 0,
+        fps,
+        refresh_rate,
+    });
+    stats.frames += 1;
+    stats.dropped_frames += dropped;
+    stats.fps = fps;
+    stats.refresh_rate = refresh_rate;
+}
+
+/// Forget stats of closed window
+pub fn remove_frame_stats(window_id: i32) {
+    FRAME_STATS.lock().unwrap().remove(&window_id);
+}
+
+pub fn get_frame_stats() -> Vec<FrameStats> {
+    FRAME_STATS.lock().unwrap().values().cloned().collect()
+}
+
+/// Layer surfaces held by renderers of all windows
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SurfacePoolStats {
+    pub budget_bytes: usize,
+    /// Bytes of surfaces used by layers
+    pub live_bytes: usize,
+    /// Bytes of idle surfaces kept for reuse
+    pub pooled_bytes: usize,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+js_serialize!(SurfacePoolStats);
+
+pub fn get_input_latencies() -> Vec<InputLatency> {
+    INPUT_LATENCIES.lock().unwrap().iter().cloned().collect()
+}
+
+#[allow(nonstandard_style)]
+pub struct performance {}
+
+#[js_methods]
+impl performance {
+    #[js_func]
+    pub fn get_input_latencies() -> Result<Vec<InputLatency>, JsError> {
+        Ok(get_input_latencies())
+    }
+
+    #[js_func]
+    pub fn clear_input_latencies() {
+        INPUT_LATENCIES.lock().unwrap().clear();
+    }
+
+    /// Frame stats of all windows
+    #[js_func]
+    pub fn get_frame_stats() -> Vec<FrameStats> {
+        get_frame_stats()
+    }
+
+    /// Restart counting frames, pacing of windows is kept
+    #[js_func]
+    pub fn reset_frame_stats() {
+        for stats in FRAME_STATS.lock().unwrap().values_mut() {
+            stats.frames = 0;
+            stats.dropped_frames = 0;
+        }
+    }
+
+    #[js_func]
+    pub fn get_surface_pool_stats() -> SurfacePoolStats {
+        let stats = pool::stats();
+        SurfacePoolStats {
+            budget_bytes: stats.budget_bytes,
+            live_bytes: stats.live_bytes,
+            pooled_bytes: stats.pooled_bytes,
+            hits: stats.hits,
+            misses: stats.misses,
+        }
+    }
+
+    #[js_func]
+    pub fn set_surface_memory_budget(bytes: usize) {
+        pool::set_memory_budget(bytes);
+    }
+
+    #[js_func]
+    pub fn trim_surface_pool() {
+        pool::trim();
+    }
+}
 
 pub struct TimeLog {
     message: String,
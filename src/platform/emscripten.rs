@@ -1,4 +1,96 @@
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+
 pub const PLATFORM_NAME: &'static str = "web";
 pub fn support_multiple_windows() -> bool {
     false
 }
+
+extern "C" {
+    fn emscripten_run_script(script: *const c_char);
+    fn emscripten_run_script_string(script: *const c_char) -> *const c_char;
+    fn emscripten_async_wget_data(
+        url: *const c_char,
+        arg: *mut c_void,
+        onload: extern "C" fn(*mut c_void, *mut c_void, c_int),
+        onerror: extern "C" fn(*mut c_void),
+    );
+}
+
+type FetchCallback = Box<dyn FnOnce(Result<String, String>)>;
+
+/// Evaluate script in the page
+pub fn run_script(script: &str) {
+    let script = CString::new(script).unwrap();
+    unsafe { emscripten_run_script(script.as_ptr()) }
+}
+
+/// Evaluate script in the page and return the result converted to string
+pub fn run_script_string(script: &str) -> String {
+    let script = CString::new(script).unwrap();
+    unsafe {
+        let result = emscripten_run_script_string(script.as_ptr());
+        if result.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(result).to_string_lossy().into_owned()
+    }
+}
+
+fn js_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+/// Request url asynchronously, callback is called on the main thread when done
+pub fn fetch_text<F: FnOnce(Result<String, String>) + 'static>(url: &str, callback: F) {
+    let url_str = match CString::new(url) {
+        Ok(s) => s,
+        Err(e) => return callback(Err(e.to_string())),
+    };
+    let callback: Box<FetchCallback> = Box::new(Box::new(callback));
+    let arg = Box::into_raw(callback) as *mut c_void;
+    unsafe { emscripten_async_wget_data(url_str.as_ptr(), arg, on_fetch_load, on_fetch_error) }
+}
+
+extern "C" fn on_fetch_load(arg: *mut c_void, data: *mut c_void, size: c_int) {
+    let callback = unsafe { Box::from_raw(arg as *mut FetchCallback) };
+    let bytes = unsafe { std::slice::from_raw_parts(data as *const u8, size as usize) };
+    callback(Ok(String::from_utf8_lossy(bytes).into_owned()));
+}
+
+extern "C" fn on_fetch_error(arg: *mut c_void) {
+    let callback = unsafe { Box::from_raw(arg as *mut FetchCallback) };
+    callback(Err("request failed".to_string()));
+}
+
+/// Entries of window.localStorage whose keys start with prefix, prefix is stripped from keys
+pub fn web_storage_entries(prefix: &str) -> HashMap<String, String> {
+    let script = format!(
+        r#"(function() {{
+            var prefix = {prefix};
+            var entries = {{}};
+            for (var i = 0; i < localStorage.length; i++) {{
+                var key = localStorage.key(i);
+                if (key.startsWith(prefix)) {{
+                    entries[key.substring(prefix.length)] = localStorage.getItem(key);
+                }}
+            }}
+            return JSON.stringify(entries);
+        }})()"#,
+        prefix = js_string(prefix)
+    );
+    serde_json::from_str(&run_script_string(&script)).unwrap_or_default()
+}
+
+/// Write to window.localStorage, the item is removed if value is None
+pub fn web_storage_set(key: &str, value: Option<&str>) {
+    let script = match value {
+        Some(value) => format!(
+            "localStorage.setItem({}, {})",
+            js_string(key),
+            js_string(value)
+        ),
+        None => format!("localStorage.removeItem({})", js_string(key)),
+    };
+    run_script(&script);
+}
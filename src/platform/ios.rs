@@ -2,3 +2,10 @@ pub const PLATFORM_NAME: &'static str = "ios";
 pub fn support_multiple_windows() -> bool {
     false
 }
+
+/// Find a function exported by the app. Optional hooks implemented by apps are looked up at
+/// runtime instead of declared as extern, so apps without them still link.
+pub fn find_host_symbol(name: &std::ffi::CStr) -> Option<*mut std::ffi::c_void> {
+    let ptr = unsafe { libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr()) };
+    (!ptr.is_null()).then_some(ptr)
+}
@@ -1,6 +1,7 @@
 pub const PLATFORM_NAME: &'static str = "ohos";
 
-use crate::app::{AppEvent, AppEventPayload, WinitApp};
+use crate::app::{AppEvent, WinitApp};
+use crate::event_loop::AppEventWakeUp;
 use crate::{send_app_event, some_or_return};
 use log::debug;
 use napi_derive_ohos::napi;
@@ -70,7 +71,7 @@ pub fn hide_soft_keyboard(_window_id: i32) {
     }
 }
 
-pub fn run_app(event_loop: EventLoop<AppEventPayload>, app: WinitApp) {
+pub fn run_app(event_loop: EventLoop<AppEventWakeUp>, app: WinitApp) {
     event_loop.spawn_app(app);
 }
 
@@ -0,0 +1,283 @@
+use crate as deft;
+use crate::js::js_serde::JsValueSerializer;
+use crate::js::{FromJsValue, JsBytes};
+use crate::{js_deserialize, js_serialize};
+use anyhow::{anyhow, Error};
+use deft_macros::js_methods;
+use quick_js::JsValue;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+static HANDLERS: LazyLock<RwLock<HashMap<String, Arc<dyn ProtocolHandler>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+thread_local! {
+    static JS_HANDLERS: RefCell<HashMap<String, JsValue>> = RefCell::new(HashMap::new());
+}
+
+pub struct ProtocolRequest {
+    pub url: String,
+    /// Upper case method, e.g. "GET"
+    pub method: String,
+    /// Lower case names of headers
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl ProtocolRequest {
+    pub fn get(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            method: "GET".to_string(),
+            headers: HashMap::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(|s| s.as_str())
+    }
+}
+
+pub struct ProtocolResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl ProtocolResponse {
+    pub fn new(body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            headers: HashMap::new(),
+            body,
+        }
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_lowercase(), value.to_string());
+        self
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get("content-type").map(|s| s.as_str())
+    }
+}
+
+/// Handler serving urls of a scheme, called on the js thread for images and on other threads
+/// for fetch and modules
+pub trait ProtocolHandler: Send + Sync + 'static {
+    fn handle(&self, request: &ProtocolRequest) -> Result<ProtocolResponse, Error>;
+}
+
+impl<F> ProtocolHandler for F
+where
+    F: Fn(&ProtocolRequest) -> Result<ProtocolResponse, Error> + Send + Sync + 'static,
+{
+    fn handle(&self, request: &ProtocolRequest) -> Result<ProtocolResponse, Error> {
+        self(request)
+    }
+}
+
+/// Serve urls like `{scheme}://...` with handler, replacing the handler registered before.
+/// Registering schemes like http intercepts the requests.
+pub fn register_protocol<H: ProtocolHandler>(scheme: &str, handler: H) {
+    HANDLERS
+        .write()
+        .unwrap()
+        .insert(scheme.to_lowercase(), Arc::new(handler));
+}
+
+pub fn unregister_protocol(scheme: &str) {
+    HANDLERS.write().unwrap().remove(&scheme.to_lowercase());
+}
+
+fn scheme_of(url: &str) -> Option<String> {
+    let (scheme, _) = url.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then(|| scheme.to_lowercase())
+}
+
+/// Load request with handlers registered in rust, None if no handler serves its scheme
+pub fn load(request: &ProtocolRequest) -> Option<Result<ProtocolResponse, Error>> {
+    let scheme = scheme_of(&request.url)?;
+    let handler = HANDLERS.read().unwrap().get(&scheme)?.clone();
+    Some(handler.handle(request))
+}
+
+/// Load request with handlers registered in rust or js, must be called on the js thread.
+/// Handlers of js should return synchronously.
+pub fn load_sync(request: &ProtocolRequest) -> Option<Result<ProtocolResponse, Error>> {
+    if let Some(result) = load(request) {
+        return Some(result);
+    }
+    let scheme = scheme_of(&request.url)?;
+    let handler = JS_HANDLERS.with_borrow(|h| h.get(&scheme).cloned())?;
+    Some(call_js_handler(&handler, request))
+}
+
+fn call_js_handler(
+    handler: &JsValue,
+    request: &ProtocolRequest,
+) -> Result<ProtocolResponse, Error> {
+    let request = JsProtocolRequest {
+        url: request.url.clone(),
+        method: request.method.clone(),
+        headers: request.headers.clone(),
+        body: (!request.body.is_empty()).then(|| JsBytes(request.body.clone())),
+    };
+    let request = request
+        .serialize(JsValueSerializer {})
+        .map_err(|e| anyhow!("failed to serialize protocol request: {:?}", e))?;
+    let result = handler
+        .call_as_function(vec![request])
+        .map_err(|e| anyhow!("protocol handler failed: {:?}", e))?;
+    let response = JsProtocolResponse::from_js_value(result)
+        .map_err(|e| anyhow!("invalid protocol response: {:?}", e))?;
+    Ok(ProtocolResponse {
+        status: response.status.unwrap_or(200),
+        headers: response
+            .headers
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect(),
+        body: response.data.map(|d| d.0).unwrap_or_default(),
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsProtocolRequest {
+    url: String,
+    method: String,
+    headers: HashMap<String, String>,
+    /// String for fetch, ArrayBuffer otherwise
+    body: Option<JsBytes>,
+}
+
+js_deserialize!(JsProtocolRequest);
+
+/// Response returned by handlers of js, normalized by lib.js
+#[derive(Serialize, Deserialize)]
+struct JsProtocolResponse {
+    status: Option<u16>,
+    headers: Option<HashMap<String, String>>,
+    data: Option<JsBytes>,
+}
+
+js_deserialize!(JsProtocolResponse);
+
+#[derive(Serialize, Deserialize)]
+pub struct ProtocolFetchResponse {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: JsBytes,
+}
+
+js_serialize!(ProtocolFetchResponse);
+
+#[allow(nonstandard_style)]
+pub struct protocol;
+
+#[js_methods]
+impl protocol {
+    /// Handler is called with url and should return a normalized response synchronously
+    #[js_func]
+    pub fn register(scheme: String, handler: JsValue) {
+        JS_HANDLERS.with_borrow_mut(|h| h.insert(scheme.to_lowercase(), handler));
+    }
+
+    #[js_func]
+    pub fn unregister(scheme: String) {
+        JS_HANDLERS.with_borrow_mut(|h| h.remove(&scheme.to_lowercase()));
+    }
+
+    /// Whether scheme is served by a handler registered in rust
+    #[js_func]
+    pub fn has_native_handler(scheme: String) -> bool {
+        HANDLERS
+            .read()
+            .unwrap()
+            .contains_key(&scheme.to_lowercase())
+    }
+
+    /// Load request with handlers registered in rust, for fetch
+    #[js_func]
+    pub async fn fetch(request: JsProtocolRequest) -> Result<ProtocolFetchResponse, Error> {
+        let request = ProtocolRequest {
+            url: request.url,
+            method: request.method.to_uppercase(),
+            headers: request
+                .headers
+                .into_iter()
+                .map(|(k, v)| (k.to_lowercase(), v))
+                .collect(),
+            body: request.body.map(|b| b.0).unwrap_or_default(),
+        };
+        let response = tokio::task::spawn_blocking(move || load(&request))
+            .await?
+            .ok_or_else(|| anyhow!("no protocol handler"))??;
+        Ok(ProtocolFetchResponse {
+            status: response.status,
+            headers: response.headers,
+            body: JsBytes(response.body),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::{load, register_protocol, scheme_of, ProtocolRequest, ProtocolResponse};
+
+    #[test]
+    fn test_scheme_of() {
+        assert_eq!(Some("app".to_string()), scheme_of("App://index.js"));
+        assert_eq!(
+            Some("x-asset+enc".to_string()),
+            scheme_of("x-asset+enc:logo.png")
+        );
+        assert_eq!(None, scheme_of("index.js"));
+        assert_eq!(None, scheme_of("1app://a"));
+        assert_eq!(None, scheme_of("./a:b"));
+    }
+
+    #[test]
+    fn test_load() {
+        register_protocol("test-vfs", |request: &ProtocolRequest| {
+            let path = request.url.trim_start_matches("test-vfs://");
+            let body = [
+                request.method.as_bytes(),
+                b" ",
+                path.as_bytes(),
+                &request.body,
+            ]
+            .concat();
+            Ok(ProtocolResponse::new(body).with_header("Content-Type", "text/plain"))
+        });
+        let response = load(&ProtocolRequest::get("test-vfs://a/b.txt"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(b"GET a/b.txt".to_vec(), response.body);
+        assert_eq!(Some("text/plain"), response.content_type());
+        let request = ProtocolRequest {
+            method: "POST".to_string(),
+            body: b"?q=1".to_vec(),
+            ..ProtocolRequest::get("test-vfs://a")
+        };
+        assert_eq!(
+            b"POST a?q=1".to_vec(),
+            load(&request).unwrap().unwrap().body
+        );
+        assert!(load(&ProtocolRequest::get("other://a")).is_none());
+    }
+}
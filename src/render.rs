@@ -1,4 +1,6 @@
 pub mod cssborder;
+pub mod debug;
+pub mod dump;
 pub mod layout_tree;
 pub mod paint_object;
 pub mod painter;
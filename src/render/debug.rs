@@ -0,0 +1,154 @@
+use crate as deft;
+use crate::ext::ext_window::WINDOWS;
+use crate::js::JsError;
+use crate::js_serialize;
+use crate::render::RenderFn;
+use crate::text::textbox::{TextBox, TextElement, TextUnit};
+use deft_macros::js_methods;
+use serde::Serialize;
+use skia_safe::{Color, Paint, Rect};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SHOW_REPAINT_AREAS: AtomicBool = AtomicBool::new(false);
+static SHOW_LAYER_BORDERS: AtomicBool = AtomicBool::new(false);
+static SHOW_OVERDRAW: AtomicBool = AtomicBool::new(false);
+static SHOW_HUD: AtomicBool = AtomicBool::new(false);
+
+const HUD_MARGIN: f32 = 8.0;
+const HUD_PADDING: f32 = 6.0;
+
+/// Statistics collected by painter while drawing a frame
+#[derive(Serialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderStats {
+    pub layer_count: usize,
+    /// Elements intersecting with invalid area, which are painted
+    pub painted_elements: usize,
+    /// Elements skipped because they are outside of invalid area
+    pub culled_elements: usize,
+    /// Bytes of layer surfaces kept for next frame
+    pub cached_surface_bytes: usize,
+    /// Time of drawing the frame in renderer
+    pub frame_time_ms: f32,
+}
+
+js_serialize!(RenderStats);
+
+pub fn is_repaint_areas_shown() -> bool {
+    SHOW_REPAINT_AREAS.load(Ordering::Relaxed)
+}
+
+pub fn is_layer_borders_shown() -> bool {
+    SHOW_LAYER_BORDERS.load(Ordering::Relaxed)
+}
+
+pub fn is_overdraw_shown() -> bool {
+    SHOW_OVERDRAW.load(Ordering::Relaxed)
+}
+
+pub fn is_hud_shown() -> bool {
+    SHOW_HUD.load(Ordering::Relaxed)
+}
+
+/// Translucent fill painted over every element, overlapped areas become deeper
+pub fn overdraw_paint() -> Paint {
+    let mut paint = Paint::default();
+    paint.set_color(Color::from_argb(32, 255, 0, 0));
+    paint
+}
+
+/// Build renderer of HUD showing stats of the last frame, should be called in main thread
+pub fn build_hud(stats: &RenderStats) -> RenderFn {
+    let lines = [
+        format!("Layers: {}", stats.layer_count),
+        format!(
+            "Elements: {} painted, {} culled",
+            stats.painted_elements, stats.culled_elements
+        ),
+        format!(
+            "Cached surfaces: {:.1} MB",
+            stats.cached_surface_bytes as f32 / (1024.0 * 1024.0)
+        ),
+        format!("Frame time: {:.1} ms", stats.frame_time_ms),
+    ];
+    let mut text_box = TextBox::new();
+    text_box.set_text_wrap(false);
+    text_box.set_font_size(12.0);
+    text_box.set_color(Color::WHITE);
+    for text in lines {
+        let unit = TextUnit {
+            text,
+            font_families: None,
+            font_size: None,
+            color: None,
+            text_decoration_line: None,
+            weight: None,
+            background_color: None,
+            style: None,
+        };
+        text_box.add_line(vec![TextElement::Text(unit)]);
+    }
+    text_box.layout();
+    let width = text_box.max_intrinsic_width() + HUD_PADDING * 2.0;
+    let height = text_box.height() + HUD_PADDING * 2.0;
+    let text_renderer = text_box.render();
+    RenderFn::new(move |painter| {
+        let canvas = painter.canvas;
+        canvas.save();
+        canvas.translate((HUD_MARGIN, HUD_MARGIN));
+        let mut bg = Paint::default();
+        bg.set_color(Color::from_argb(180, 0, 0, 0));
+        canvas.draw_rect(Rect::from_xywh(0.0, 0.0, width, height), &bg);
+        canvas.translate((HUD_PADDING, HUD_PADDING));
+        text_renderer.run(painter);
+        canvas.restore();
+    })
+}
+
+fn set_flag(flag: &AtomicBool, value: bool) {
+    if flag.swap(value, Ordering::Relaxed) != value {
+        repaint_all_windows();
+    }
+}
+
+fn repaint_all_windows() {
+    WINDOWS.with_borrow_mut(|m| {
+        for w in m.values() {
+            if let Ok(mut w) = w.upgrade_mut() {
+                if let Some(body) = w.get_body() {
+                    w.invalid_layout(body);
+                }
+            }
+        }
+    });
+}
+
+#[allow(nonstandard_style)]
+pub struct debug {}
+
+#[js_methods]
+impl debug {
+    #[js_func]
+    pub fn set_show_repaint_areas(value: bool) -> Result<(), JsError> {
+        set_flag(&SHOW_REPAINT_AREAS, value);
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn set_show_layer_borders(value: bool) -> Result<(), JsError> {
+        set_flag(&SHOW_LAYER_BORDERS, value);
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn set_show_overdraw(value: bool) -> Result<(), JsError> {
+        set_flag(&SHOW_OVERDRAW, value);
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn set_show_hud(value: bool) -> Result<(), JsError> {
+        set_flag(&SHOW_HUD, value);
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use crate as deft;
+use crate::base::Rect;
+use crate::js_serialize;
+use crate::paint::{PaintContext, Painter};
+use crate::render::paint_object::LayerPO;
+use crate::render::painter::ElementPainter;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use quick_js::JsValue;
+use serde::Serialize;
+use skia_safe::{ColorSpace, EncodedImageFormat};
+use skia_window::headless;
+use skia_window::renderer::Renderer;
+use std::mem;
+
+pub struct RenderTreeDumpOptions {
+    /// Whether to attach png images of layer contents
+    pub layer_images: bool,
+}
+
+impl RenderTreeDumpOptions {
+    pub fn from_js_value(value: &JsValue) -> Self {
+        let layer_images = match value {
+            JsValue::Object(map) => matches!(map.get("layerImages"), Some(JsValue::Bool(true))),
+            _ => false,
+        };
+        Self { layer_images }
+    }
+}
+
+/// Snapshot of render trees of a window, attached to rendering bug reports
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderTreeDump {
+    pub scale_factor: f32,
+    pub viewport: Rect,
+    pub layer_roots: Vec<LayerRootDump>,
+}
+
+js_serialize!(RenderTreeDump);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerRootDump {
+    pub element_id: u32,
+    pub x: f32,
+    pub y: f32,
+    /// None if the render tree is not built yet
+    pub layer: Option<LayerDump>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerDump {
+    /// Id of element creating the layer
+    pub element_id: u32,
+    /// "root" for the element itself or "children" for scrolled children
+    pub layer_type: String,
+    /// Matrix relative to parent element
+    pub matrix: String,
+    pub total_matrix: String,
+    pub width: f32,
+    pub height: f32,
+    pub origin_absolute_pos: (f32, f32),
+    pub scroll_offset: (f32, f32),
+    pub surface_bounds: Rect,
+    pub visible_bounds: Rect,
+    pub clip_rect: Option<Rect>,
+    /// One of "full", "partial" and "none"
+    pub invalid_area: String,
+    /// Rects to repaint in next frame, only for partial invalid area
+    pub invalid_rects: Vec<Rect>,
+    pub elements: Vec<ElementObjectDump>,
+    pub layers: Vec<LayerDump>,
+    /// Png image of visible content in base64, without sublayers
+    pub image: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementObjectDump {
+    pub id: u32,
+    pub tag: String,
+    /// Position relative to parent element
+    pub coord: (f32, f32),
+    /// Position relative to layer
+    pub layer_coord: (f32, f32),
+    pub width: f32,
+    pub height: f32,
+    pub transform: Option<String>,
+    pub style: StyleSummary,
+    pub children: Vec<ElementObjectDump>,
+}
+
+/// Paint related style of element object
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StyleSummary {
+    pub background_color: String,
+    pub background_image: bool,
+    /// (top, right, bottom, left)
+    pub border_width: (f32, f32, f32, f32),
+    /// (top, right, bottom, left)
+    pub border_color: [String; 4],
+    /// (top-left, top-right, bottom-right, bottom-left)
+    pub border_radius: [f32; 4],
+}
+
+/// Attach images of paint tree to layers of dump, the paint tree must be built from the same
+/// render tree
+pub fn attach_layer_images(
+    dump: &mut LayerDump,
+    paint_tree: &mut LayerPO,
+    scale_factor: f32,
+    color_space: &Option<ColorSpace>,
+) {
+    dump.image = render_layer_image(paint_tree, scale_factor, color_space);
+    for (d, p) in dump.layers.iter_mut().zip(paint_tree.layers.iter_mut()) {
+        attach_layer_images(d, p, scale_factor, color_space);
+    }
+}
+
+fn render_layer_image(
+    layer: &mut LayerPO,
+    scale_factor: f32,
+    color_space: &Option<ColorSpace>,
+) -> Option<String> {
+    let bounds = layer.visible_bounds;
+    let width = (bounds.width * scale_factor).ceil() as u32;
+    let height = (bounds.height * scale_factor).ceil() as u32;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let mut elements = mem::take(&mut layer.elements);
+    let renderer = Renderer::new(move |canvas, ctx| {
+        canvas.save();
+        canvas.scale((scale_factor, scale_factor));
+        canvas.translate((-bounds.x, -bounds.y));
+        let mut element_painter = ElementPainter::take(ctx);
+        element_painter.set_layer_cache(false);
+        let painter = Painter::new(canvas, PaintContext { scale_factor });
+        element_painter.draw_elements(&painter, &mut elements, ctx);
+        element_painter.put(ctx);
+        canvas.restore();
+    });
+    let image = headless::render_to_image(width, height, color_space.clone(), renderer)?;
+    let data = image.encode(None, EncodedImageFormat::PNG, None)?;
+    Some(BASE64_STANDARD.encode(data.as_bytes()))
+}
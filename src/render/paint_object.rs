@@ -1,8 +1,10 @@
 use crate::base;
+use crate::some_or_return;
 use crate::border::tiny_path_to_skia_path;
 use crate::paint::{InvalidRects, RenderLayerKey};
 use crate::render::RenderFn;
 use crate::style::color::ColorHelper;
+use crate::window::find::FindHighlights;
 use skia_safe::PaintStyle::{Fill, Stroke};
 use skia_safe::{Canvas, Color, Image, Matrix, Paint, Rect};
 use tiny_skia::Path;
@@ -25,6 +27,7 @@ pub struct ElementPO {
     pub element_id: u32,
     pub need_paint: bool,
     pub focused: bool,
+    pub find_highlights: Option<FindHighlights>,
 }
 
 impl ElementPO {
@@ -60,6 +63,21 @@ impl ElementPO {
         }
     }
 
+    pub fn draw_find_highlights(&self, canvas: &Canvas) {
+        let highlights = some_or_return!(&self.find_highlights);
+        let mut paint = Paint::default();
+        paint.set_style(Fill);
+        for (i, r) in highlights.rects.iter().enumerate() {
+            let color = if highlights.active == Some(i) {
+                Color::from_argb(0x99, 0xFF, 0x96, 0x32)
+            } else {
+                Color::from_argb(0x80, 0xFF, 0xFF, 0x00)
+            };
+            paint.set_color(color);
+            canvas.draw_rect(&Rect::from_xywh(r.x, r.y, r.width, r.height), &paint);
+        }
+    }
+
     pub fn draw_hit_rect(&mut self, canvas: &Canvas) {
         let rect = Rect::from_xywh(1.0, 1.0, self.width - 2.0, self.height - 2.0);
         let mut paint = Paint::default();
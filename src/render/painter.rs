@@ -2,6 +2,7 @@ use crate::base::Rect;
 use crate::border::tiny_path_to_skia_path;
 use crate::canvas_util::CanvasHelper;
 use crate::paint::{DrawLayer, InvalidRects, LayerState, Painter, RenderLayerKey};
+use crate::render::debug::{is_overdraw_shown, overdraw_paint, RenderStats};
 use crate::render::paint_object::{ElementPO, LayerPO};
 use crate::{show_focus_hint, show_layer_hint, show_repaint_area};
 use skia_safe::{Canvas, ClipOp, Color, FilterMode, Matrix, Paint, PaintStyle, SamplingOptions};
@@ -14,6 +15,7 @@ pub struct ElementPainter {
     viewport: Rect,
     layer_state_map: HashMap<RenderLayerKey, LayerState>,
     layer_cache_enabled: bool,
+    stats: RenderStats,
 }
 
 impl ElementPainter {
@@ -23,6 +25,7 @@ impl ElementPainter {
             viewport: Rect::new_empty(),
             layer_state_map: HashMap::new(),
             layer_cache_enabled: false,
+            stats: RenderStats::default(),
         }
     }
 
@@ -47,6 +50,17 @@ impl ElementPainter {
         }
     }
 
+    /// Take stats collected since last call
+    pub fn take_stats(&mut self) -> RenderStats {
+        let mut stats = mem::take(&mut self.stats);
+        stats.cached_surface_bytes = self
+            .layer_state_map
+            .values()
+            .map(|l| l.surface_width * l.surface_height * 4)
+            .sum();
+        stats
+    }
+
     pub fn draw_root(
         &mut self,
         painter: &Painter,
@@ -57,6 +71,18 @@ impl ElementPainter {
         self.draw_layer(painter, context, root, &mut state, true);
     }
 
+    /// Draw elements of a layer without sublayers
+    pub fn draw_elements(
+        &mut self,
+        painter: &Painter,
+        elements: &mut Vec<ElementPO>,
+        context: &mut RenderContext,
+    ) {
+        for e in elements {
+            self.draw_element_object_recurse(painter, e, context);
+        }
+    }
+
     fn draw_element_object_recurse(
         &mut self,
         painter: &Painter,
@@ -66,8 +92,13 @@ impl ElementPainter {
         let canvas = painter.canvas;
         // debug!("Painting {}", epo.element_id);
         //TODO optimize
-        if !epo.need_paint && epo.children.is_empty() {
-            return;
+        if !epo.need_paint {
+            self.stats.culled_elements += 1;
+            if epo.children.is_empty() {
+                return;
+            }
+        } else {
+            self.stats.painted_elements += 1;
         }
         canvas.save();
         canvas.translate(epo.coord);
@@ -146,6 +177,7 @@ impl ElementPainter {
         if surface_width <= 0 || surface_height <= 0 {
             return;
         }
+        self.stats.layer_count += 1;
         {
             let mut graphic_layer = if is_root {
                 LayerState {
@@ -310,6 +342,11 @@ impl ElementPainter {
                 }
             }
             canvas.restore();
+            node.draw_find_highlights(canvas);
+            if is_overdraw_shown() {
+                let rect = Rect::from_xywh(0.0, 0.0, width, height);
+                canvas.draw_rect(&rect.to_skia_rect(), &overdraw_paint());
+            }
             if show_focus_hint() && node.focused {
                 node.draw_hit_rect(canvas);
             }
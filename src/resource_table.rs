@@ -50,6 +50,11 @@ impl ResourceTableInner {
         self.type_resources.insert(value.type_id(), Box::new(value));
     }
 
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        let v = self.type_resources.remove(&TypeId::of::<T>())?;
+        v.downcast::<T>().ok().map(|v| *v)
+    }
+
     pub fn get<T: 'static>(&self) -> Option<&T> {
         if let Some(v) = self.type_resources.get(&TypeId::of::<T>()) {
             v.downcast_ref::<T>()
@@ -0,0 +1,31 @@
+use crate as deft;
+use deft_macros::js_func;
+use std::env;
+use std::sync::OnceLock;
+
+const SAFE_MODE_ARG: &str = "--safe-mode";
+
+static SAFE_MODE: OnceLock<bool> = OnceLock::new();
+
+/// Safe mode renders with software only and disables animations, so that apps could still
+/// start when gpu drivers are broken. Enabled by `--safe-mode` argument or `DEFT_SAFE_MODE`
+/// environment variable.
+pub fn is_safe_mode() -> bool {
+    *SAFE_MODE.get_or_init(|| {
+        env::args().any(|a| a == SAFE_MODE_ARG)
+            || env::var("DEFT_SAFE_MODE")
+                .map(|v| v != "0" && v != "false")
+                .unwrap_or(false)
+    })
+}
+
+/// Enable or disable safe mode, must be called before the first window is created.
+/// Returns false if safe mode is determined already.
+pub fn set_safe_mode(enabled: bool) -> bool {
+    SAFE_MODE.set(enabled).is_ok()
+}
+
+#[js_func]
+pub fn app_is_safe_mode() -> bool {
+    is_safe_mode()
+}
@@ -1,23 +1,32 @@
 pub mod animation;
+pub mod app_region;
 pub mod border;
 pub mod border_path;
 pub mod color;
 pub mod css_manager;
+pub mod display;
 pub mod flex;
 pub mod font;
+pub mod gradient;
+pub mod grid;
 pub mod length;
+pub mod line_clamp;
+pub mod list_style;
+pub mod media;
 mod node_item;
 pub mod overflow;
+pub mod ruby;
 mod select;
 pub mod style_vars;
 pub mod styles;
+pub mod text_paint;
 pub mod transform;
 pub mod var_expr;
 
 use crate as deft;
 use crate::animation::css_actor::CssAnimationActor;
 use crate::animation::ANIMATIONS;
-use crate::animation::{AnimationInstance, WindowAnimationController};
+use crate::animation::{AnimationInstance, FrameSkipPolicy, WindowAnimationController};
 use crate::base::Rect;
 use crate::element::scroll::ScrollBarStrategy;
 use crate::element::ElementWeak;
@@ -26,11 +35,18 @@ use crate::font::family::FontFamilies;
 use crate::mrc::{Mrc, MrcWeak};
 use crate::number::DeNan;
 use crate::style::animation::AnimationParams;
+use crate::style::app_region::AppRegion;
+use crate::style::display::Display;
 use crate::style::font::{FontStyle, LineHeightVal};
+use crate::style::grid::{GridPlacement, GridTemplate, TrackSize};
 use crate::style::length::{Length, LengthContext, LengthOrPercent};
+use crate::style::line_clamp::LineClamp;
+use crate::style::list_style::{CounterList, ListStyleType};
 use crate::style::node_item::NodeItem;
 use crate::style::overflow::Overflow;
+use crate::style::ruby::{RubyPosition, DEFAULT_RUBY_SIZE};
 use crate::style::style_vars::StyleVars;
+use crate::style::text_paint::{TextFill, TextStroke};
 use crate::style::transform::StyleTransform;
 use crate::style_list::ParsedStyleProp;
 use crate::{ok_or_return, some_or_return};
@@ -43,9 +59,7 @@ use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 use swash::Style;
-use yoga::{
-    Align, Direction, Display, FlexDirection, Justify, Node, PositionType, StyleUnit, Wrap,
-};
+use yoga::{Align, Direction, FlexDirection, Justify, Node, PositionType, StyleUnit, Wrap};
 
 //TODO rename
 pub trait PropValueParse: Sized {
@@ -306,6 +320,10 @@ define_style_props!(
     FlexWrap => Wrap, Wrap;
     ColumnGap => Length, f32;
     RowGap => Length, f32;
+    GridTemplateColumns => GridTemplate, Vec<TrackSize>;
+    GridTemplateRows => GridTemplate, Vec<TrackSize>;
+    GridColumn => GridPlacement, GridPlacement;
+    GridRow => GridPlacement, GridPlacement;
 
     Top => LengthOrPercent, StyleUnit;
     Right => LengthOrPercent, StyleUnit;
@@ -316,6 +334,17 @@ define_style_props!(
     AnimationName => String, String;
     AnimationDuration => f32, f32;
     AnimationIterationCount => f32, f32;
+    DeftAnimationFrameRate => f32, f32;
+    DeftAppRegion => AppRegion, AppRegion;
+    DeftTextStroke => TextStroke, TextStroke;
+    DeftTextFill => TextFill, TextFill;
+    DeftLineClamp => LineClamp, LineClamp;
+    RubyPosition => RubyPosition, RubyPosition;
+    DeftRubySize => f32, f32;
+    Content => String, String;
+    ListStyleType => ListStyleType, ListStyleType;
+    CounterReset => CounterList, CounterList;
+    CounterIncrement => CounterList, CounterList;
 );
 
 pub fn parse_box_prop(str: &str, default: &str) -> (String, String, String, String) {
@@ -387,6 +416,16 @@ pub struct StyleNode {
     pub font_family: FontFamilies,
     pub font_weight: Weight,
     pub font_style: FontStyle,
+    pub app_region: AppRegion,
+    pub text_stroke_width: f32,
+    pub text_stroke_color: Color,
+    pub text_fill: TextFill,
+    pub line_clamp: LineClamp,
+    pub ruby_position: RubyPosition,
+    pub ruby_size: f32,
+    pub list_style_type: ListStyleType,
+    pub counter_reset: CounterList,
+    pub counter_increment: CounterList,
 }
 
 impl StyleNode {
@@ -412,6 +451,16 @@ impl StyleNode {
             font_family: FontFamilies::default(),
             font_weight: Weight::NORMAL,
             font_style: FontStyle::Normal,
+            app_region: AppRegion::Auto,
+            text_stroke_width: 0.0,
+            text_stroke_color: Color::TRANSPARENT,
+            text_fill: TextFill::Auto,
+            line_clamp: LineClamp::None,
+            ruby_position: RubyPosition::Over,
+            ruby_size: DEFAULT_RUBY_SIZE,
+            list_style_type: ListStyleType::None,
+            counter_reset: CounterList::default(),
+            counter_increment: CounterList::default(),
         };
         inner.yoga_node.position_type = PositionType::Static;
         inner.to_ref()
@@ -565,10 +614,17 @@ impl StyleNode {
             }
             StylePropKey::Transform => ResolvedStyleProp::Transform(StyleTransform::empty()),
             StylePropKey::AnimationName => ResolvedStyleProp::AnimationName("".to_string()),
+            StylePropKey::Content => ResolvedStyleProp::Content("".to_string()),
+            StylePropKey::ListStyleType => ResolvedStyleProp::ListStyleType(ListStyleType::None),
+            StylePropKey::CounterReset => ResolvedStyleProp::CounterReset(CounterList::default()),
+            StylePropKey::CounterIncrement => {
+                ResolvedStyleProp::CounterIncrement(CounterList::default())
+            }
             StylePropKey::AnimationDuration => ResolvedStyleProp::AnimationDuration(0.0),
             StylePropKey::AnimationIterationCount => {
                 ResolvedStyleProp::AnimationIterationCount(1.0)
             }
+            StylePropKey::DeftAnimationFrameRate => ResolvedStyleProp::DeftAnimationFrameRate(0.0),
 
             StylePropKey::JustifyContent => ResolvedStyleProp::JustifyContent(Justify::FlexStart),
             StylePropKey::FlexDirection => ResolvedStyleProp::FlexDirection(FlexDirection::Column),
@@ -577,6 +633,20 @@ impl StyleNode {
             StylePropKey::FlexWrap => ResolvedStyleProp::FlexWrap(Wrap::NoWrap),
             StylePropKey::ColumnGap => ResolvedStyleProp::ColumnGap(Length::PX(0.0)),
             StylePropKey::RowGap => ResolvedStyleProp::RowGap(Length::PX(0.0)),
+            StylePropKey::GridTemplateColumns => {
+                ResolvedStyleProp::GridTemplateColumns(GridTemplate::default())
+            }
+            StylePropKey::GridTemplateRows => {
+                ResolvedStyleProp::GridTemplateRows(GridTemplate::default())
+            }
+            StylePropKey::GridColumn => ResolvedStyleProp::GridColumn(GridPlacement::default()),
+            StylePropKey::GridRow => ResolvedStyleProp::GridRow(GridPlacement::default()),
+            StylePropKey::DeftAppRegion => ResolvedStyleProp::DeftAppRegion(AppRegion::Auto),
+            StylePropKey::DeftTextStroke => ResolvedStyleProp::DeftTextStroke(TextStroke::none()),
+            StylePropKey::DeftTextFill => ResolvedStyleProp::DeftTextFill(TextFill::Auto),
+            StylePropKey::DeftLineClamp => ResolvedStyleProp::DeftLineClamp(LineClamp::None),
+            StylePropKey::RubyPosition => ResolvedStyleProp::RubyPosition(RubyPosition::Over),
+            StylePropKey::DeftRubySize => ResolvedStyleProp::DeftRubySize(DEFAULT_RUBY_SIZE),
             //TODO aspectratio
         }
     }
@@ -755,6 +825,22 @@ impl StyleNode {
                 self.animation_params.name = name;
                 self.update_animation();
             }
+            ResolvedStyleProp::Content(_) => {
+                // Only used by ::before and ::after
+                need_layout = false;
+            }
+            ResolvedStyleProp::ListStyleType(value) => {
+                need_layout = false;
+                self.list_style_type = value;
+            }
+            ResolvedStyleProp::CounterReset(value) => {
+                need_layout = false;
+                self.counter_reset = value;
+            }
+            ResolvedStyleProp::CounterIncrement(value) => {
+                need_layout = false;
+                self.counter_increment = value;
+            }
             ResolvedStyleProp::AnimationDuration(value) => {
                 need_layout = false;
                 let duration = value;
@@ -767,6 +853,13 @@ impl StyleNode {
                 self.animation_params.iteration_count = ic;
                 self.update_animation();
             }
+            ResolvedStyleProp::DeftAnimationFrameRate(value) => {
+                need_layout = false;
+                self.animation_params.frame_rate = value;
+                if let Some(ai) = &mut self.animation_instance {
+                    ai.set_frame_skip_policy(FrameSkipPolicy::from_max_fps(value));
+                }
+            }
 
             // container node style
             ResolvedStyleProp::JustifyContent(value) => {
@@ -790,6 +883,40 @@ impl StyleNode {
             ResolvedStyleProp::RowGap(value) => {
                 self.yoga_node.row_gap = value.to_px(&length_ctx);
             } //TODO aspectratio
+            ResolvedStyleProp::GridTemplateColumns(value) => {
+                self.yoga_node.grid_template_columns = value.to_track_sizes(&length_ctx);
+            }
+            ResolvedStyleProp::GridTemplateRows(value) => {
+                self.yoga_node.grid_template_rows = value.to_track_sizes(&length_ctx);
+            }
+            ResolvedStyleProp::GridColumn(value) => {
+                self.yoga_node.grid_item.column = value;
+            }
+            ResolvedStyleProp::GridRow(value) => {
+                self.yoga_node.grid_item.row = value;
+            }
+            ResolvedStyleProp::DeftAppRegion(value) => {
+                need_layout = false;
+                self.app_region = value;
+            }
+            ResolvedStyleProp::DeftTextStroke(value) => {
+                need_layout = false;
+                self.text_stroke_width = value.width.to_px(length_ctx);
+                self.text_stroke_color = value.color;
+            }
+            ResolvedStyleProp::DeftTextFill(value) => {
+                need_layout = false;
+                self.text_fill = value;
+            }
+            ResolvedStyleProp::DeftLineClamp(value) => {
+                self.line_clamp = value;
+            }
+            ResolvedStyleProp::RubyPosition(value) => {
+                self.ruby_position = value;
+            }
+            ResolvedStyleProp::DeftRubySize(value) => {
+                self.ruby_size = value;
+            }
         }
         if !change_notified {
             if let Some(on_changed) = &mut self.on_changed {
@@ -822,6 +949,8 @@ impl StyleNode {
                             iteration_count,
                             Box::new(frame_controller),
                         );
+                        ani_instance
+                            .set_frame_skip_policy(FrameSkipPolicy::from_max_fps(p.frame_rate));
                         ani_instance.run();
                         Some(ani_instance)
                     })
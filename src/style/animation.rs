@@ -2,6 +2,8 @@ pub struct AnimationParams {
     pub name: String,
     pub duration: f32,
     pub iteration_count: f32,
+    /// Maximum frames applied per second, 0 to apply every frame
+    pub frame_rate: f32,
 }
 
 impl AnimationParams {
@@ -10,6 +12,7 @@ impl AnimationParams {
             name: "".to_string(),
             duration: 0.0,
             iteration_count: 1.0,
+            frame_rate: 0.0,
         }
     }
 }
@@ -0,0 +1,25 @@
+use crate::style::PropValueParse;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AppRegion {
+    Auto,
+    /// Disable edge resizing of undecorated window over the element
+    NoResize,
+}
+
+impl PropValueParse for AppRegion {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "no-resize" => Some(Self::NoResize),
+            _ => None,
+        }
+    }
+    fn to_style_string(&self) -> String {
+        match self {
+            AppRegion::Auto => "auto",
+            AppRegion::NoResize => "no-resize",
+        }
+        .to_owned()
+    }
+}
@@ -1,9 +1,12 @@
+use crate::a11y::get_a11y_preferences;
+use crate::atom::Atom;
 use crate::base::{Id, IdKey};
 use crate::element::Element;
-use crate::style::select::{Selector, Selectors};
+use crate::style::media::{get_color_scheme, split_media_blocks, MediaQuery};
+use crate::style::select::{Selector, SelectorSubject, Selectors};
 use anyhow::{anyhow, Error};
 use simplecss::StyleSheet;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 thread_local! {
     static STYLESHEET_ID_KEY: IdKey = IdKey::new();
@@ -12,13 +15,70 @@ thread_local! {
 pub struct CSS {
     id: Id<CSS>,
     rules: Vec<CSSRule>,
-    declared_classes: Vec<String>,
-    declared_attrs: Vec<String>,
+    declared_classes: HashSet<Atom>,
+    declared_attrs: HashSet<Atom>,
+    has_hover: bool,
+    scope: StyleScope,
+}
+
+/// Elements which rules of a stylesheet could apply to, elements out of scope don't need
+/// selector matching again when the stylesheet is changed
+#[derive(Clone, Default, Debug)]
+pub struct StyleScope {
+    universal: bool,
+    tags: HashSet<Atom>,
+    classes: HashSet<Atom>,
+    attrs: HashSet<Atom>,
+}
+
+impl StyleScope {
+    pub fn universal() -> Self {
+        Self {
+            universal: true,
+            ..Default::default()
+        }
+    }
+
+    /// Add the subject of a selector, an element must have all classes, attributes and tag of it
+    /// to match, so checking one of them is enough
+    pub fn add_subject(&mut self, subject: &SelectorSubject) {
+        if let Some(class) = subject.classes.first() {
+            self.classes.insert(class.clone());
+        } else if let Some(attr) = subject.attribute_names.first() {
+            self.attrs.insert(attr.clone());
+        } else if let Some(tag) = &subject.tag {
+            self.tags.insert(tag.clone());
+        } else {
+            self.universal = true;
+        }
+    }
+
+    pub fn merge(&mut self, other: &StyleScope) {
+        self.universal |= other.universal;
+        self.tags.extend(other.tags.iter().cloned());
+        self.classes.extend(other.classes.iter().cloned());
+        self.attrs.extend(other.attrs.iter().cloned());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.universal && self.tags.is_empty() && self.classes.is_empty() && self.attrs.is_empty()
+    }
+
+    pub fn may_match(&self, element: &Element) -> bool {
+        self.universal
+            || self.tags.contains(&element.tag)
+            || element.classes.iter().any(|c| self.classes.contains(c))
+            || element.attributes.keys().any(|a| self.attrs.contains(a))
+    }
 }
 
 pub struct CSSRule {
     selector: Selector,
+    /// Source of selector list the rule is declared with
+    selector_text: String,
     declarations: String,
+    /// Condition of `@media` block containing the rule
+    media: Option<MediaQuery>,
     id: Id<CSS>,
 }
 
@@ -35,59 +95,94 @@ impl CssManager {
 
     pub fn add(&mut self, stylesheet_source: &str) -> Result<Id<CSS>, Error> {
         let id = Id::next(&STYLESHEET_ID_KEY);
-        let mut css = CSS {
-            id,
-            rules: Vec::new(),
-            declared_classes: Vec::new(),
-            declared_attrs: Vec::new(),
-        };
-        Self::update_css(&mut css, stylesheet_source)?;
+        let css = Self::parse_css(id, stylesheet_source)?;
         self.stylesheets.push(css);
         Ok(id)
     }
 
-    pub fn update(&mut self, id: &Id<CSS>, stylesheet_source: &str) -> Result<(), Error> {
-        for css in &mut self.stylesheets {
-            if css.id == *id {
-                Self::update_css(css, stylesheet_source)?;
-                return Ok(());
-            }
-        }
-        Err(anyhow!("style sheet not found: {}", id))
+    /// Replace source of a stylesheet, returns scope of both old and new rules. The stylesheet is
+    /// unchanged if the new source is invalid.
+    pub fn update(&mut self, id: &Id<CSS>, stylesheet_source: &str) -> Result<StyleScope, Error> {
+        let css = self
+            .stylesheets
+            .iter_mut()
+            .find(|css| css.id == *id)
+            .ok_or_else(|| anyhow!("style sheet not found: {}", id))?;
+        let new_css = Self::parse_css(*id, stylesheet_source)?;
+        let mut scope = css.scope.clone();
+        scope.merge(&new_css.scope);
+        *css = new_css;
+        Ok(scope)
     }
 
-    pub fn remove(&mut self, id: &Id<CSS>) {
-        self.stylesheets.retain(|css| css.id != *id);
+    /// Remove a stylesheet, returns scope of its rules if found
+    pub fn remove(&mut self, id: &Id<CSS>) -> Option<StyleScope> {
+        let index = self.stylesheets.iter().position(|css| css.id == *id)?;
+        Some(self.stylesheets.remove(index).scope)
     }
 
-    pub fn contains_class(&self, clazz: &str) -> bool {
-        for ss in &self.stylesheets {
-            for c in &ss.declared_classes {
-                if c == clazz {
-                    return true;
-                }
-            }
-        }
-        false
+    pub fn get_scope(&self, id: &Id<CSS>) -> Option<&StyleScope> {
+        self.stylesheets
+            .iter()
+            .find(|css| css.id == *id)
+            .map(|css| &css.scope)
     }
 
-    pub fn contains_attr(&self, attr: &str) -> bool {
-        for ss in &self.stylesheets {
-            for a in &ss.declared_attrs {
-                if a == attr {
-                    return true;
-                }
-            }
-        }
-        false
+    pub fn contains_class(&self, clazz: &Atom) -> bool {
+        self.stylesheets
+            .iter()
+            .any(|ss| ss.declared_classes.contains(clazz))
+    }
+
+    pub fn contains_attr(&self, attr: &Atom) -> bool {
+        self.stylesheets
+            .iter()
+            .any(|ss| ss.declared_attrs.contains(attr))
+    }
+
+    /// Whether any rule depends on :hover
+    pub fn contains_hover(&self) -> bool {
+        self.stylesheets.iter().any(|ss| ss.has_hover)
     }
 
     pub fn match_styles(&self, element: &Element) -> (Vec<String>, HashMap<String, Vec<String>>) {
         let mut list = Vec::new();
         let mut pm = HashMap::new();
+        for rule in self.matched_rules(element) {
+            let rule_str = rule.declarations.clone();
+            if let Some(pe) = rule.selector.pseudo_element() {
+                pm.entry(pe.name.clone())
+                    .or_insert_with(Vec::new)
+                    .push(rule_str);
+            } else {
+                list.push(rule_str);
+            }
+        }
+        (list, pm)
+    }
+
+    /// Selectors and declarations of rules matching element, in cascade order
+    pub fn trace_rules(&self, element: &Element) -> Vec<(String, String)> {
+        self.matched_rules(element)
+            .into_iter()
+            .filter(|r| r.selector.pseudo_element().is_none())
+            .map(|r| (r.selector_text.clone(), r.declarations.clone()))
+            .collect()
+    }
+
+    fn matched_rules(&self, element: &Element) -> Vec<&CSSRule> {
         let mut rules = Vec::new();
+        let prefs = get_a11y_preferences();
+        let color_scheme = get_color_scheme();
         for css in &self.stylesheets {
             for rule in &css.rules {
+                if rule
+                    .media
+                    .as_ref()
+                    .is_some_and(|m| !m.matches(&prefs, color_scheme))
+                {
+                    continue;
+                }
                 if rule.selector.matches(element) {
                     rules.push(rule);
                 }
@@ -98,24 +193,27 @@ impl CssManager {
             let b = b.selector.specificity();
             a.cmp(&b)
         });
-        for rule in rules {
-            let rule_str = rule.declarations.clone();
-            if let Some(pe) = rule.selector.pseudo_element() {
-                pm.entry(pe.name.clone())
-                    .or_insert_with(Vec::new)
-                    .push(rule_str);
-            } else {
-                list.push(rule_str);
-            }
+        rules
+    }
+
+    fn parse_css(id: Id<CSS>, stylesheet_source: &str) -> Result<CSS, Error> {
+        let mut css = CSS {
+            id,
+            rules: Vec::new(),
+            declared_classes: HashSet::new(),
+            declared_attrs: HashSet::new(),
+            has_hover: false,
+            scope: StyleScope::default(),
+        };
+        // Rules are kept in source order so that later rules win regardless of `@media`
+        for (media, source) in split_media_blocks(stylesheet_source) {
+            Self::add_rules(&mut css, &source, media)?;
         }
-        (list, pm)
+        Ok(css)
     }
 
-    fn update_css(css: &mut CSS, stylesheet_source: &str) -> Result<(), Error> {
-        css.declared_classes.clear();
-        css.declared_attrs.clear();
-        css.rules.clear();
-        let stylesheet = StyleSheet::parse(&stylesheet_source);
+    fn add_rules(css: &mut CSS, source: &str, media: Option<MediaQuery>) -> Result<(), Error> {
+        let stylesheet = StyleSheet::parse(source);
         for rule in &stylesheet.rules {
             let selectors = rule.selector.source().to_string();
             let mut declarations = Vec::new();
@@ -123,15 +221,20 @@ impl CssManager {
                 declarations.push(format!("{}:{}", decl.name, decl.value));
             }
             //println!("selectors: {:?} => {:?}", selectors, declarations.join(";"));
+            let selectors_text = selectors.trim().to_string();
             let selectors = Selectors::compile(&selectors)?;
             for selector in selectors.0 {
                 css.declared_classes
-                    .append(&mut selector.get_classes().clone());
+                    .extend(selector.get_classes().iter().cloned());
                 css.declared_attrs
-                    .append(&mut selector.get_attribute_names().clone());
+                    .extend(selector.get_attribute_names().iter().cloned());
+                css.has_hover |= selector.has_hover();
+                css.scope.add_subject(selector.get_subject());
                 let rule = CSSRule {
                     selector,
+                    selector_text: selectors_text.clone(),
                     declarations: declarations.join(";"),
+                    media: media.clone(),
                     id: css.id,
                 };
                 css.rules.push(rule);
@@ -143,6 +246,7 @@ impl CssManager {
 
 #[cfg(test)]
 mod tests {
+    use crate::atom::Atom;
     use crate::element::button::Button;
     use crate::element::container::Container;
     use crate::element::{Element, ElementBackend};
@@ -162,4 +266,30 @@ mod tests {
         assert_eq!(1, containers_styles.len());
         assert_eq!(1, button_styles.len());
     }
+
+    #[test]
+    fn test_update_scope() {
+        let mut manager = CssManager::new();
+        let id = manager.add(".a button { color: red }").unwrap();
+        let mut button = Element::create(Button::create);
+        button.set_tag("button".to_string());
+        let mut container = Element::create(Container::create);
+        container.set_tag("container".to_string());
+        assert!(manager.get_scope(&id).unwrap().may_match(&button));
+        assert!(!manager.get_scope(&id).unwrap().may_match(&container));
+
+        let scope = manager
+            .update(&id, "container[disabled] { color: red }")
+            .unwrap();
+        assert!(scope.may_match(&button));
+        assert!(!scope.may_match(&container));
+        container
+            .attributes
+            .insert(Atom::new("disabled"), "".to_string());
+        assert!(scope.may_match(&container));
+
+        let scope = manager.remove(&id).unwrap();
+        assert!(!scope.may_match(&button));
+        assert!(manager.remove(&id).is_none());
+    }
 }
@@ -0,0 +1,37 @@
+use crate::style::PropValueParse;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Display {
+    Flex,
+    /// Children are placed by the grid layout, see `crate::style::grid`
+    Grid,
+    None,
+}
+
+impl Display {
+    pub fn to_yoga_display(&self) -> yoga::Display {
+        match self {
+            Display::Flex | Display::Grid => yoga::Display::Flex,
+            Display::None => yoga::Display::None,
+        }
+    }
+}
+
+impl PropValueParse for Display {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "flex" => Some(Display::Flex),
+            "grid" => Some(Display::Grid),
+            "none" => Some(Display::None),
+            _ => None,
+        }
+    }
+    fn to_style_string(&self) -> String {
+        match self {
+            Display::Flex => "flex",
+            Display::Grid => "grid",
+            Display::None => "none",
+        }
+        .to_string()
+    }
+}
@@ -1,14 +1,6 @@
 use crate::style::PropValueParse;
 use std::str::FromStr;
-use yoga::{Align, Direction, Display, FlexDirection, Justify, PositionType, Wrap};
-impl PropValueParse for Display {
-    fn parse_prop_value(value: &str) -> Option<Self> {
-        Display::from_str(value).ok()
-    }
-    fn to_style_string(&self) -> String {
-        self.to_string()
-    }
-}
+use yoga::{Align, Direction, FlexDirection, Justify, PositionType, Wrap};
 impl PropValueParse for FlexDirection {
     fn parse_prop_value(value: &str) -> Option<Self> {
         FlexDirection::from_str(value).ok()
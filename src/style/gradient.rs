@@ -0,0 +1,239 @@
+use crate::style::color::parse_color;
+use crate::style::length::parse_percent;
+use crate::style::PropValueParse;
+use skia_safe::{Color, Point, Rect, Shader, TileMode};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientDirection {
+    /// Angle in degrees, 0deg points up and 90deg points right
+    Angle(f32),
+    /// Horizontal and vertical signs of `to <side-or-corner>`
+    Corner(f32, f32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearGradient {
+    pub direction: GradientDirection,
+    /// Color stops with optional position in range [0, 1]
+    pub stops: Vec<(Color, Option<f32>)>,
+}
+
+impl LinearGradient {
+    /// Create a shader which fills the given rect like css linear-gradient
+    pub fn to_shader(&self, rect: &Rect) -> Option<Shader> {
+        let (w, h) = (rect.width(), rect.height());
+        let (dx, dy) = match self.direction {
+            GradientDirection::Angle(deg) => {
+                let rad = deg.to_radians();
+                (rad.sin(), -rad.cos())
+            }
+            // Perpendicular to the diagonal which doesn't pass the target corner
+            GradientDirection::Corner(sx, sy) if sx != 0.0 && sy != 0.0 => {
+                let len = w.hypot(h);
+                if len == 0.0 {
+                    return None;
+                }
+                (sx * h / len, sy * w / len)
+            }
+            GradientDirection::Corner(sx, sy) => (sx, sy),
+        };
+        let half_len = ((w * dx).abs() + (h * dy).abs()) / 2.0;
+        let center = rect.center();
+        let start = Point::new(center.x - dx * half_len, center.y - dy * half_len);
+        let end = Point::new(center.x + dx * half_len, center.y + dy * half_len);
+        let colors = self.stops.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+        let positions = self.resolve_positions();
+        Shader::linear_gradient(
+            (start, end),
+            colors.as_slice(),
+            positions.as_slice(),
+            TileMode::Clamp,
+            None,
+            None,
+        )
+    }
+
+    /// Positions of all stops, missing ones are spread evenly between their neighbors
+    pub fn resolve_positions(&self) -> Vec<f32> {
+        let count = self.stops.len();
+        let mut positions: Vec<Option<f32>> = self.stops.iter().map(|(_, p)| *p).collect();
+        if let Some(first) = positions.first_mut() {
+            first.get_or_insert(0.0);
+        }
+        if let Some(last) = positions.last_mut() {
+            last.get_or_insert(1.0);
+        }
+        let mut prev = 0;
+        for i in 1..count {
+            if let Some(end) = positions[i] {
+                let start = positions[prev].unwrap_or(0.0).min(end);
+                let steps = (i - prev) as f32;
+                for j in prev + 1..i {
+                    positions[j] = Some(start + (end - start) * (j - prev) as f32 / steps);
+                }
+                prev = i;
+            }
+        }
+        let mut max = 0.0f32;
+        positions
+            .into_iter()
+            .map(|p| {
+                max = max.max(p.unwrap_or(0.0));
+                max
+            })
+            .collect()
+    }
+}
+
+impl PropValueParse for LinearGradient {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let prefix = "linear-gradient(";
+        if !value.get(..prefix.len())?.eq_ignore_ascii_case(prefix) {
+            return None;
+        }
+        let args = split_args(value[prefix.len()..].strip_suffix(')')?);
+        let mut args = args.into_iter().peekable();
+        let direction = match parse_direction(args.peek()?) {
+            Some(d) => {
+                args.next();
+                d
+            }
+            None => GradientDirection::Angle(180.0),
+        };
+        let mut stops = Vec::new();
+        for arg in args {
+            stops.push(parse_color_stop(arg)?);
+        }
+        if stops.len() < 2 {
+            return None;
+        }
+        Some(Self { direction, stops })
+    }
+
+    fn to_style_string(&self) -> String {
+        let mut args = Vec::with_capacity(self.stops.len() + 1);
+        args.push(match self.direction {
+            GradientDirection::Angle(deg) => format!("{}deg", deg),
+            GradientDirection::Corner(sx, sy) => {
+                let mut sides = Vec::new();
+                if sy != 0.0 {
+                    sides.push(if sy < 0.0 { "top" } else { "bottom" });
+                }
+                if sx != 0.0 {
+                    sides.push(if sx < 0.0 { "left" } else { "right" });
+                }
+                format!("to {}", sides.join(" "))
+            }
+        });
+        for (color, pos) in &self.stops {
+            let mut stop = color.to_style_string();
+            if let Some(p) = pos {
+                stop.push_str(&format!(" {}%", p * 100.0));
+            }
+            args.push(stop);
+        }
+        format!("linear-gradient({})", args.join(", "))
+    }
+}
+
+/// Split by commas which are not inside parentheses
+fn split_args(value: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut begin = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(value[begin..i].trim());
+                begin = i + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(value[begin..].trim());
+    result
+}
+
+fn parse_direction(value: &str) -> Option<GradientDirection> {
+    let value = value.to_lowercase();
+    if let Some(sides) = value.strip_prefix("to ") {
+        let (mut sx, mut sy) = (0.0, 0.0);
+        for side in sides.split_whitespace() {
+            match side {
+                "top" => sy = -1.0,
+                "bottom" => sy = 1.0,
+                "left" => sx = -1.0,
+                "right" => sx = 1.0,
+                _ => return None,
+            }
+        }
+        return if sx == 0.0 && sy == 0.0 {
+            None
+        } else {
+            Some(GradientDirection::Corner(sx, sy))
+        };
+    }
+    let deg = if let Some(v) = value.strip_suffix("deg") {
+        f32::from_str(v).ok()?
+    } else if let Some(v) = value.strip_suffix("grad") {
+        f32::from_str(v).ok()? * 0.9
+    } else if let Some(v) = value.strip_suffix("rad") {
+        f32::from_str(v).ok()?.to_degrees()
+    } else if let Some(v) = value.strip_suffix("turn") {
+        f32::from_str(v).ok()? * 360.0
+    } else {
+        return None;
+    };
+    Some(GradientDirection::Angle(deg))
+}
+
+fn parse_color_stop(value: &str) -> Option<(Color, Option<f32>)> {
+    if let Some((color, pos)) = value.rsplit_once(char::is_whitespace) {
+        if let Some(p) = parse_percent(pos) {
+            return Some((parse_color(color.trim())?, Some(p / 100.0)));
+        }
+    }
+    Some((parse_color(value)?, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::gradient::{GradientDirection, LinearGradient};
+    use crate::style::PropValueParse;
+    use skia_safe::Color;
+
+    #[test]
+    fn test_parse_linear_gradient() {
+        let g = LinearGradient::parse_prop_value("linear-gradient(90deg, red, rgb(0, 0, 255) 80%)")
+            .unwrap();
+        assert_eq!(GradientDirection::Angle(90.0), g.direction);
+        assert_eq!((Color::RED, None), g.stops[0]);
+        assert_eq!((Color::BLUE, Some(0.8)), g.stops[1]);
+
+        let g =
+            LinearGradient::parse_prop_value("linear-gradient(to top right, red, blue)").unwrap();
+        assert_eq!(GradientDirection::Corner(1.0, -1.0), g.direction);
+
+        let g = LinearGradient::parse_prop_value("linear-gradient(red, blue)").unwrap();
+        assert_eq!(GradientDirection::Angle(180.0), g.direction);
+
+        assert_eq!(
+            None,
+            LinearGradient::parse_prop_value("linear-gradient(red)")
+        );
+        assert_eq!(None, LinearGradient::parse_prop_value("red"));
+    }
+
+    #[test]
+    fn test_resolve_positions() {
+        let g = LinearGradient::parse_prop_value(
+            "linear-gradient(red, green, blue 60%, white 40%, black)",
+        )
+        .unwrap();
+        assert_eq!(vec![0.0, 0.3, 0.6, 0.6, 1.0], g.resolve_positions());
+    }
+}
@@ -0,0 +1,582 @@
+use crate::some_or_continue;
+use crate::style::length::{parse_percent, Length, LengthContext};
+use crate::style::PropValueParse;
+use std::str::FromStr;
+
+/// Max tracks of a template and max line numbers or spans of items, larger values are clamped so
+/// that styles can not allocate unbounded tracks or cells
+const MAX_TRACKS: usize = 1000;
+
+/// Track of grid-template-columns and grid-template-rows
+#[derive(Clone, Debug, PartialEq)]
+pub enum GridTrack {
+    Length(Length),
+    Percent(f32),
+    Fr(f32),
+    Auto,
+}
+
+/// Track list, e.g. "100px 1fr 2fr" or "repeat(3, 1fr)"
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct GridTemplate {
+    pub tracks: Vec<GridTrack>,
+}
+
+/// Track size with lengths resolved to pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrackSize {
+    Fixed(f32),
+    Percent(f32),
+    Fr(f32),
+    Auto,
+}
+
+/// Lines occupied by an item in one axis, e.g. "2", "span 2", "1 / 3" or "2 / span 2"
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridPlacement {
+    /// 1-based start line, auto placed if None
+    pub start: Option<usize>,
+    pub span: usize,
+}
+
+impl Default for GridPlacement {
+    fn default() -> Self {
+        Self {
+            start: None,
+            span: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct GridItem {
+    pub column: GridPlacement,
+    pub row: GridPlacement,
+}
+
+/// Area of an item relative to the content box of grid container
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct GridArea {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct GridLayout {
+    pub areas: Vec<GridArea>,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl GridTrack {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("auto") {
+            Some(GridTrack::Auto)
+        } else if let Some(fr) = value.strip_suffix("fr") {
+            let fr = f32::from_str(fr.trim()).ok()?;
+            (fr >= 0.0).then_some(GridTrack::Fr(fr))
+        } else if let Some(p) = parse_percent(value) {
+            Some(GridTrack::Percent(p))
+        } else {
+            Length::from_str(value).map(GridTrack::Length)
+        }
+    }
+
+    fn to_style_string(&self) -> String {
+        match self {
+            GridTrack::Length(v) => v.to_str(),
+            GridTrack::Percent(v) => format!("{}%", v),
+            GridTrack::Fr(v) => format!("{}fr", v),
+            GridTrack::Auto => "auto".to_string(),
+        }
+    }
+
+    fn to_track_size(&self, ctx: &LengthContext) -> TrackSize {
+        match self {
+            GridTrack::Length(v) => TrackSize::Fixed(v.to_px(ctx)),
+            GridTrack::Percent(v) => TrackSize::Percent(*v),
+            GridTrack::Fr(v) => TrackSize::Fr(*v),
+            GridTrack::Auto => TrackSize::Auto,
+        }
+    }
+}
+
+impl GridTemplate {
+    pub fn to_track_sizes(&self, ctx: &LengthContext) -> Vec<TrackSize> {
+        self.tracks.iter().map(|t| t.to_track_size(ctx)).collect()
+    }
+}
+
+impl PropValueParse for GridTemplate {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("none") {
+            return Some(Self::default());
+        }
+        let mut tracks = Vec::new();
+        let mut rest = value;
+        while !rest.is_empty() {
+            if let Some(args) = rest.strip_prefix("repeat(") {
+                let end = args.find(')')?;
+                let (count, list) = args[..end].split_once(',')?;
+                let count = usize::from_str(count.trim()).ok()?;
+                let list = list
+                    .split_whitespace()
+                    .map(GridTrack::parse)
+                    .collect::<Option<Vec<_>>>()?;
+                let remaining = MAX_TRACKS.saturating_sub(tracks.len());
+                tracks.extend(
+                    list.iter()
+                        .cycle()
+                        .take(remaining.min(count.saturating_mul(list.len())))
+                        .cloned(),
+                );
+                rest = args[end + 1..].trim_start();
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                tracks.push(GridTrack::parse(&rest[..end])?);
+                rest = rest[end..].trim_start();
+            }
+        }
+        tracks.truncate(MAX_TRACKS);
+        Some(Self { tracks })
+    }
+
+    fn to_style_string(&self) -> String {
+        if self.tracks.is_empty() {
+            return "none".to_string();
+        }
+        let tracks: Vec<String> = self.tracks.iter().map(|t| t.to_style_string()).collect();
+        tracks.join(" ")
+    }
+}
+
+impl PropValueParse for GridPlacement {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        let parse_span = |v: &str| -> Option<Option<usize>> {
+            match v.strip_prefix("span") {
+                Some(span) => usize::from_str(span.trim())
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .map(|s| Some(s.min(MAX_TRACKS))),
+                None => Some(None),
+            }
+        };
+        let parse_line = |v: &str| -> Option<Option<usize>> {
+            if v.eq_ignore_ascii_case("auto") {
+                return Some(None);
+            }
+            usize::from_str(v)
+                .ok()
+                .filter(|l| *l > 0)
+                .map(|l| Some(l.min(MAX_TRACKS + 1)))
+        };
+        let (start, end) = match value.split_once('/') {
+            Some((start, end)) => (start.trim(), Some(end.trim())),
+            None => (value.trim(), None),
+        };
+        let mut placement = GridPlacement::default();
+        if let Some(span) = parse_span(start)? {
+            placement.span = span;
+        } else {
+            placement.start = parse_line(start)?;
+        }
+        if let Some(end) = end {
+            if let Some(span) = parse_span(end)? {
+                placement.span = span;
+            } else if let Some(end) = parse_line(end)? {
+                match placement.start {
+                    Some(start) if end > start => placement.span = end - start,
+                    Some(_) => {}
+                    // "span 2 / 4"
+                    None => {
+                        placement.start = Some(end.saturating_sub(placement.span).max(1));
+                    }
+                }
+            }
+        }
+        Some(placement)
+    }
+
+    fn to_style_string(&self) -> String {
+        match self.start {
+            Some(start) => format!("{} / span {}", start, self.span),
+            None => format!("span {}", self.span),
+        }
+    }
+}
+
+/// Place items into cells of (column, row, column span, row span), items without start lines
+/// are placed row by row into the first free cells after previously placed items.
+fn place_items(items: &[GridItem], explicit_columns: usize) -> Vec<(usize, usize, usize, usize)> {
+    let clamp = |p: GridPlacement| GridPlacement {
+        start: p.start.map(|s| s.clamp(1, MAX_TRACKS)),
+        span: p.span.clamp(1, MAX_TRACKS),
+    };
+    let items: Vec<GridItem> = items
+        .iter()
+        .map(|it| GridItem {
+            column: clamp(it.column),
+            row: clamp(it.row),
+        })
+        .collect();
+    let explicit_columns = explicit_columns.min(MAX_TRACKS);
+    let columns = items
+        .iter()
+        .map(|it| it.column.start.map(|s| s - 1).unwrap_or(0) + it.column.span)
+        .fold(explicit_columns.max(1), usize::max);
+    let mut occupied: Vec<Vec<bool>> = Vec::new();
+    let fits =
+        |occupied: &[Vec<bool>], col: usize, row: usize, col_span: usize, row_span: usize| {
+            if col + col_span > columns {
+                return false;
+            }
+            (row..row + row_span).all(|r| {
+                occupied
+                    .get(r)
+                    .map(|cells| !cells[col..col + col_span].iter().any(|c| *c))
+                    .unwrap_or(true)
+            })
+        };
+    let occupy = |occupied: &mut Vec<Vec<bool>>,
+                  col: usize,
+                  row: usize,
+                  col_span: usize,
+                  row_span: usize| {
+        while occupied.len() < row + row_span {
+            occupied.push(vec![false; columns]);
+        }
+        for cells in &mut occupied[row..row + row_span] {
+            cells[col..col + col_span].fill(true);
+        }
+    };
+    let mut result = vec![(0, 0, 0, 0); items.len()];
+    let mut placed = vec![false; items.len()];
+    // Items with definite rows go first
+    for (i, it) in items.iter().enumerate() {
+        let row = some_or_continue!(it.row.start) - 1;
+        let col_span = it.column.span.min(columns);
+        let col = match it.column.start {
+            Some(col) => col - 1,
+            None => (0..columns)
+                .find(|c| fits(&occupied, *c, row, col_span, it.row.span))
+                .unwrap_or(0),
+        };
+        occupy(&mut occupied, col, row, col_span, it.row.span);
+        result[i] = (col, row, col_span, it.row.span);
+        placed[i] = true;
+    }
+    let (mut cursor_col, mut cursor_row) = (0, 0);
+    for (i, it) in items.iter().enumerate() {
+        if placed[i] {
+            continue;
+        }
+        let col_span = it.column.span.min(columns);
+        let row_span = it.row.span;
+        if let Some(col) = it.column.start {
+            if col - 1 < cursor_col {
+                cursor_row += 1;
+            }
+            cursor_col = col - 1;
+            while !fits(&occupied, cursor_col, cursor_row, col_span, row_span) {
+                cursor_row += 1;
+            }
+        } else {
+            while !fits(&occupied, cursor_col, cursor_row, col_span, row_span) {
+                if cursor_col + col_span < columns {
+                    cursor_col += 1;
+                } else {
+                    cursor_col = 0;
+                    cursor_row += 1;
+                }
+            }
+        }
+        occupy(&mut occupied, cursor_col, cursor_row, col_span, row_span);
+        result[i] = (cursor_col, cursor_row, col_span, row_span);
+        cursor_col += col_span;
+    }
+    result
+}
+
+/// Size tracks of one axis. `spans` are (start, span) of items and `content_size` returns the
+/// size of item content in this axis.
+fn size_tracks<F: FnMut(usize) -> f32>(
+    tracks: &[TrackSize],
+    count: usize,
+    available: f32,
+    gap: f32,
+    spans: &[(usize, usize)],
+    mut content_size: F,
+) -> Vec<f32> {
+    let track_at = |i: usize| match tracks.get(i).copied().unwrap_or(TrackSize::Auto) {
+        TrackSize::Percent(_) if available.is_nan() => TrackSize::Auto,
+        TrackSize::Fr(_) if available.is_nan() => TrackSize::Auto,
+        t => t,
+    };
+    let is_intrinsic = |t: TrackSize| matches!(t, TrackSize::Auto | TrackSize::Fr(_));
+    let mut sizes: Vec<f32> = (0..count)
+        .map(|i| match track_at(i) {
+            TrackSize::Fixed(v) => v,
+            TrackSize::Percent(p) => available * p / 100.0,
+            _ => 0.0,
+        })
+        .collect();
+    let mut contents = vec![0.0; spans.len()];
+    for (i, (start, span)) in spans.iter().enumerate() {
+        let intrinsic: Vec<usize> = (*start..start + span)
+            .filter(|t| is_intrinsic(track_at(*t)))
+            .collect();
+        if intrinsic.is_empty() {
+            continue;
+        }
+        contents[i] = content_size(i);
+        if *span == 1 {
+            sizes[*start] = sizes[*start].max(contents[i]);
+        }
+    }
+    // Items spanning multiple tracks grow their intrinsic tracks evenly
+    for (i, (start, span)) in spans.iter().enumerate() {
+        if *span == 1 {
+            continue;
+        }
+        let intrinsic: Vec<usize> = (*start..start + span)
+            .filter(|t| is_intrinsic(track_at(*t)))
+            .collect();
+        if intrinsic.is_empty() {
+            continue;
+        }
+        let spanned = sizes[*start..start + span].iter().sum::<f32>() + gap * (*span - 1) as f32;
+        let extra = (contents[i] - spanned) / intrinsic.len() as f32;
+        if extra > 0.0 {
+            intrinsic.iter().for_each(|t| sizes[*t] += extra);
+        }
+    }
+    // Flexible tracks share the free space but never shrink below their content
+    let total_fr: f32 = (0..count)
+        .filter_map(|i| match track_at(i) {
+            TrackSize::Fr(fr) => Some(fr),
+            _ => None,
+        })
+        .sum();
+    if total_fr > 0.0 {
+        let fixed: f32 = (0..count)
+            .filter(|i| !matches!(track_at(*i), TrackSize::Fr(_)))
+            .map(|i| sizes[i])
+            .sum();
+        let gaps = gap * count.saturating_sub(1) as f32;
+        let free = (available - fixed - gaps).max(0.0);
+        let fr_size = free / total_fr.max(1.0);
+        for (i, size) in sizes.iter_mut().enumerate() {
+            if let TrackSize::Fr(fr) = track_at(i) {
+                *size = size.max(fr_size * fr);
+            }
+        }
+    }
+    sizes
+}
+
+fn track_offsets(sizes: &[f32], gap: f32) -> Vec<f32> {
+    let mut offsets = Vec::with_capacity(sizes.len());
+    let mut offset = 0.0;
+    for size in sizes {
+        offsets.push(offset);
+        offset += size + gap;
+    }
+    offsets
+}
+
+fn span_size(sizes: &[f32], gap: f32, start: usize, span: usize) -> f32 {
+    sizes[start..start + span].iter().sum::<f32>() + gap * (span - 1) as f32
+}
+
+fn total_size(sizes: &[f32], gap: f32) -> f32 {
+    sizes.iter().sum::<f32>() + gap * sizes.len().saturating_sub(1) as f32
+}
+
+/// Tracks and gaps of a grid container
+pub struct GridContainer<'a> {
+    pub columns: &'a [TrackSize],
+    pub rows: &'a [TrackSize],
+    pub column_gap: f32,
+    pub row_gap: f32,
+}
+
+impl GridContainer<'_> {
+    /// Lay out items in the content box, NAN available size means unconstrained.
+    /// `measure` returns the size of an item laid out with the given available width.
+    pub fn layout<M: FnMut(usize, f32) -> (f32, f32)>(
+        &self,
+        items: &[GridItem],
+        available_width: f32,
+        available_height: f32,
+        mut measure: M,
+    ) -> GridLayout {
+        let cells = place_items(items, self.columns.len());
+        let column_count = cells
+            .iter()
+            .map(|(col, _, span, _)| col + span)
+            .fold(self.columns.len(), usize::max);
+        let row_count = cells
+            .iter()
+            .map(|(_, row, _, span)| row + span)
+            .fold(self.rows.len(), usize::max);
+
+        let column_spans: Vec<(usize, usize)> = cells.iter().map(|c| (c.0, c.2)).collect();
+        let column_sizes = size_tracks(
+            self.columns,
+            column_count,
+            available_width,
+            self.column_gap,
+            &column_spans,
+            |i| measure(i, f32::NAN).0,
+        );
+        let row_spans: Vec<(usize, usize)> = cells.iter().map(|c| (c.1, c.3)).collect();
+        let row_sizes = size_tracks(
+            self.rows,
+            row_count,
+            available_height,
+            self.row_gap,
+            &row_spans,
+            |i| {
+                let (col, _, span, _) = cells[i];
+                measure(i, span_size(&column_sizes, self.column_gap, col, span)).1
+            },
+        );
+
+        let column_offsets = track_offsets(&column_sizes, self.column_gap);
+        let row_offsets = track_offsets(&row_sizes, self.row_gap);
+        let areas = cells
+            .iter()
+            .map(|(col, row, col_span, row_span)| GridArea {
+                x: column_offsets[*col],
+                y: row_offsets[*row],
+                width: span_size(&column_sizes, self.column_gap, *col, *col_span),
+                height: span_size(&row_sizes, self.row_gap, *row, *row_span),
+            })
+            .collect();
+        GridLayout {
+            areas,
+            width: total_size(&column_sizes, self.column_gap),
+            height: total_size(&row_sizes, self.row_gap),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::grid::{
+        place_items, GridContainer, GridItem, GridPlacement, GridTemplate, GridTrack, TrackSize,
+    };
+    use crate::style::length::Length;
+    use crate::style::PropValueParse;
+
+    #[test]
+    fn test_parse_template() {
+        let template = GridTemplate::parse_prop_value("100px repeat(2, 1fr) auto").unwrap();
+        assert_eq!(
+            vec![
+                GridTrack::Length(Length::PX(100.0)),
+                GridTrack::Fr(1.0),
+                GridTrack::Fr(1.0),
+                GridTrack::Auto
+            ],
+            template.tracks
+        );
+        assert!(GridTemplate::parse_prop_value("none")
+            .unwrap()
+            .tracks
+            .is_empty());
+        assert_eq!(None, GridTemplate::parse_prop_value("1fr foo"));
+        let template = GridTemplate::parse_prop_value("repeat(99999999999, 1fr 2fr) 1fr").unwrap();
+        assert_eq!(1000, template.tracks.len());
+    }
+
+    #[test]
+    fn test_parse_placement() {
+        let parse = |v| GridPlacement::parse_prop_value(v).unwrap();
+        assert_eq!((Some(2), 1), (parse("2").start, parse("2").span));
+        assert_eq!((None, 2), (parse("span 2").start, parse("span 2").span));
+        assert_eq!((Some(1), 2), (parse("1 / 3").start, parse("1 / 3").span));
+        assert_eq!(
+            (Some(2), 3),
+            (parse("2 / span 3").start, parse("2 / span 3").span)
+        );
+        assert_eq!(
+            (Some(2), 2),
+            (parse("span 2 / 4").start, parse("span 2 / 4").span)
+        );
+        assert_eq!(None, GridPlacement::parse_prop_value("0"));
+        assert_eq!(
+            (Some(1), 1000),
+            (parse("1 / 99999999").start, parse("1 / 99999999").span)
+        );
+        assert_eq!(1000, parse("span 99999999").span);
+    }
+
+    #[test]
+    fn test_layout_grid() {
+        let columns = [
+            TrackSize::Fixed(100.0),
+            TrackSize::Fr(1.0),
+            TrackSize::Fr(1.0),
+        ];
+        let mut items = vec![GridItem::default(); 4];
+        items[3].column.span = 2;
+        let container = GridContainer {
+            columns: &columns,
+            rows: &[],
+            column_gap: 10.0,
+            row_gap: 5.0,
+        };
+        let layout = container.layout(&items, 400.0, f32::NAN, |i, _| (10.0, 20.0 + i as f32));
+        assert_eq!(400.0, layout.width);
+        assert_eq!(
+            (0.0, 0.0, 100.0),
+            (layout.areas[0].x, layout.areas[0].y, layout.areas[0].width)
+        );
+        assert_eq!((110.0, 140.0), (layout.areas[1].x, layout.areas[1].width));
+        assert_eq!(260.0, layout.areas[2].x);
+        // Wraps to second row, whose height is the height of its tallest item
+        assert_eq!(
+            (0.0, 27.0, 250.0),
+            (layout.areas[3].x, layout.areas[3].y, layout.areas[3].width)
+        );
+        assert_eq!(22.0, layout.areas[0].height);
+        assert_eq!(22.0 + 5.0 + 23.0, layout.height);
+    }
+
+    #[test]
+    fn test_layout_grid_explicit_placement() {
+        let columns = [TrackSize::Auto, TrackSize::Auto];
+        let mut items = vec![GridItem::default(); 2];
+        items[0].row.start = Some(2);
+        items[0].column.start = Some(2);
+        let container = GridContainer {
+            columns: &columns,
+            rows: &[],
+            column_gap: 0.0,
+            row_gap: 0.0,
+        };
+        let layout = container.layout(&items, f32::NAN, f32::NAN, |i, _| {
+            (30.0 + i as f32 * 10.0, 10.0)
+        });
+        assert_eq!((40.0, 10.0), (layout.areas[0].x, layout.areas[0].y));
+        assert_eq!((0.0, 0.0), (layout.areas[1].x, layout.areas[1].y));
+        assert_eq!(70.0, layout.width);
+        assert_eq!(20.0, layout.height);
+    }
+
+    #[test]
+    fn test_place_items_clamped() {
+        let mut items = vec![GridItem::default(); 2];
+        items[0].column.start = Some(usize::MAX);
+        items[0].row.start = Some(usize::MAX);
+        items[1].column.span = usize::MAX;
+        let cells = place_items(&items, 2);
+        assert_eq!((999, 999, 1, 1), cells[0]);
+        assert_eq!((0, 0, 1000, 1), cells[1]);
+    }
+}
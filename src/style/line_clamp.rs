@@ -0,0 +1,64 @@
+use crate::style::PropValueParse;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TruncationMode {
+    /// Append ellipsis to the last visible line
+    Ellipsis,
+    /// Fade out the end of the last visible line
+    Fade,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineClamp {
+    None,
+    Lines(usize, TruncationMode),
+}
+
+impl PropValueParse for LineClamp {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        if value == "none" {
+            return Some(Self::None);
+        }
+        let mut parts = value.split_whitespace();
+        let lines = usize::from_str(parts.next()?).ok()?;
+        let mode = match parts.next() {
+            None | Some("ellipsis") => TruncationMode::Ellipsis,
+            Some("fade") => TruncationMode::Fade,
+            _ => return None,
+        };
+        if lines == 0 || parts.next().is_some() {
+            return None;
+        }
+        Some(Self::Lines(lines, mode))
+    }
+    fn to_style_string(&self) -> String {
+        match self {
+            LineClamp::None => "none".to_string(),
+            LineClamp::Lines(lines, TruncationMode::Ellipsis) => lines.to_string(),
+            LineClamp::Lines(lines, TruncationMode::Fade) => format!("{} fade", lines),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::line_clamp::{LineClamp, TruncationMode};
+    use crate::style::PropValueParse;
+
+    #[test]
+    fn test_parse_line_clamp() {
+        assert_eq!(Some(LineClamp::None), LineClamp::parse_prop_value("none"));
+        assert_eq!(
+            Some(LineClamp::Lines(3, TruncationMode::Ellipsis)),
+            LineClamp::parse_prop_value("3")
+        );
+        assert_eq!(
+            Some(LineClamp::Lines(2, TruncationMode::Fade)),
+            LineClamp::parse_prop_value("2 fade")
+        );
+        assert_eq!(None, LineClamp::parse_prop_value("0"));
+        assert_eq!(None, LineClamp::parse_prop_value("2 clip"));
+    }
+}
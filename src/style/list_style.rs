@@ -0,0 +1,223 @@
+use crate::style::PropValueParse;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListStyleType {
+    None,
+    Disc,
+    Circle,
+    Square,
+    Decimal,
+    DecimalLeadingZero,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+    /// Quoted string used as marker of every item
+    Custom(String),
+}
+
+impl ListStyleType {
+    /// Text of counter value, used by `counter()` of generated content
+    pub fn format_counter(&self, value: i32) -> String {
+        match self {
+            ListStyleType::None => String::new(),
+            ListStyleType::Disc => "•".to_string(),
+            ListStyleType::Circle => "◦".to_string(),
+            ListStyleType::Square => "▪".to_string(),
+            ListStyleType::Decimal => value.to_string(),
+            ListStyleType::DecimalLeadingZero => {
+                if (0..10).contains(&value) {
+                    format!("0{}", value)
+                } else {
+                    value.to_string()
+                }
+            }
+            ListStyleType::LowerAlpha => format_alpha(value, b'a'),
+            ListStyleType::UpperAlpha => format_alpha(value, b'A'),
+            ListStyleType::LowerRoman => format_roman(value).to_lowercase(),
+            ListStyleType::UpperRoman => format_roman(value),
+            ListStyleType::Custom(s) => s.clone(),
+        }
+    }
+
+    /// Text of ::marker box of list item
+    pub fn format_marker(&self, value: i32) -> String {
+        match self {
+            ListStyleType::None => String::new(),
+            ListStyleType::Disc | ListStyleType::Circle | ListStyleType::Square => {
+                format!("{} ", self.format_counter(value))
+            }
+            ListStyleType::Custom(s) => s.clone(),
+            _ => format!("{}. ", self.format_counter(value)),
+        }
+    }
+}
+
+/// Alphabetic numbering, falls back to decimal for non-positive values
+fn format_alpha(value: i32, first: u8) -> String {
+    if value <= 0 {
+        return value.to_string();
+    }
+    let mut chars = Vec::new();
+    let mut v = value as u32;
+    while v > 0 {
+        v -= 1;
+        chars.push((first + (v % 26) as u8) as char);
+        v /= 26;
+    }
+    chars.iter().rev().collect()
+}
+
+/// Roman numbering, falls back to decimal out of range [1, 3999]
+fn format_roman(value: i32) -> String {
+    if !(1..4000).contains(&value) {
+        return value.to_string();
+    }
+    const SYMBOLS: [(i32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut result = String::new();
+    let mut v = value;
+    for (n, s) in SYMBOLS {
+        while v >= n {
+            result.push_str(s);
+            v -= n;
+        }
+    }
+    result
+}
+
+impl PropValueParse for ListStyleType {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        let value = value.trim();
+        for quote in ['"', '\''] {
+            if let Some(s) = value
+                .strip_prefix(quote)
+                .and_then(|v| v.strip_suffix(quote))
+            {
+                return Some(Self::Custom(s.to_string()));
+            }
+        }
+        let v = match value.to_lowercase().as_str() {
+            "none" => Self::None,
+            "disc" => Self::Disc,
+            "circle" => Self::Circle,
+            "square" => Self::Square,
+            "decimal" => Self::Decimal,
+            "decimal-leading-zero" => Self::DecimalLeadingZero,
+            "lower-alpha" | "lower-latin" => Self::LowerAlpha,
+            "upper-alpha" | "upper-latin" => Self::UpperAlpha,
+            "lower-roman" => Self::LowerRoman,
+            "upper-roman" => Self::UpperRoman,
+            _ => return None,
+        };
+        Some(v)
+    }
+    fn to_style_string(&self) -> String {
+        match self {
+            ListStyleType::None => "none",
+            ListStyleType::Disc => "disc",
+            ListStyleType::Circle => "circle",
+            ListStyleType::Square => "square",
+            ListStyleType::Decimal => "decimal",
+            ListStyleType::DecimalLeadingZero => "decimal-leading-zero",
+            ListStyleType::LowerAlpha => "lower-alpha",
+            ListStyleType::UpperAlpha => "upper-alpha",
+            ListStyleType::LowerRoman => "lower-roman",
+            ListStyleType::UpperRoman => "upper-roman",
+            ListStyleType::Custom(s) => return format!("\"{}\"", s),
+        }
+        .to_string()
+    }
+}
+
+/// Value of `counter-reset` and `counter-increment`, a list of counter names with optional values
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct CounterList(pub Vec<(String, Option<i32>)>);
+
+impl CounterList {
+    /// Value of counter, `default_value` is used if value is omitted
+    pub fn get(&self, name: &str, default_value: i32) -> Option<i32> {
+        let (_, v) = self.0.iter().rev().find(|(n, _)| n == name)?;
+        Some(v.unwrap_or(default_value))
+    }
+}
+
+impl PropValueParse for CounterList {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("none") {
+            return Some(Self::default());
+        }
+        let mut list: Vec<(String, Option<i32>)> = Vec::new();
+        for token in value.split_whitespace() {
+            if let Ok(v) = i32::from_str(token) {
+                list.last_mut()?.1 = Some(v);
+            } else {
+                list.push((token.to_string(), None));
+            }
+        }
+        Some(Self(list))
+    }
+    fn to_style_string(&self) -> String {
+        if self.0.is_empty() {
+            return "none".to_string();
+        }
+        let items: Vec<String> = self
+            .0
+            .iter()
+            .map(|(n, v)| match v {
+                Some(v) => format!("{} {}", n, v),
+                None => n.clone(),
+            })
+            .collect();
+        items.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::list_style::{CounterList, ListStyleType};
+    use crate::style::PropValueParse;
+
+    #[test]
+    fn test_format_counter() {
+        assert_eq!("28", ListStyleType::Decimal.format_counter(28));
+        assert_eq!("05", ListStyleType::DecimalLeadingZero.format_counter(5));
+        assert_eq!("ab", ListStyleType::LowerAlpha.format_counter(28));
+        assert_eq!("Z", ListStyleType::UpperAlpha.format_counter(26));
+        assert_eq!("xiv", ListStyleType::LowerRoman.format_counter(14));
+        assert_eq!("MCMXCIV", ListStyleType::UpperRoman.format_counter(1994));
+        assert_eq!("3. ", ListStyleType::Decimal.format_marker(3));
+        assert_eq!(
+            ListStyleType::Custom("- ".to_string()),
+            ListStyleType::parse_prop_value("\"- \"").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_counter_list() {
+        let list = CounterList::parse_prop_value("chapter section 2").unwrap();
+        assert_eq!(Some(1), list.get("chapter", 1));
+        assert_eq!(Some(2), list.get("section", 1));
+        assert_eq!(None, list.get("figure", 1));
+        assert_eq!(
+            CounterList::default(),
+            CounterList::parse_prop_value("none").unwrap()
+        );
+        assert_eq!(None, CounterList::parse_prop_value("3"));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::a11y::A11yPreferences;
+use cssparser::{ParseError, Parser, ParserInput, Token};
+use std::cell::Cell;
+
+thread_local! {
+    static COLOR_SCHEME: Cell<ColorScheme> = Cell::new(ColorScheme::Light);
+}
+
+/// Theme of system tested by `prefers-color-scheme`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(ColorScheme::Light),
+            "dark" => Some(ColorScheme::Dark),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+}
+
+pub fn get_color_scheme() -> ColorScheme {
+    COLOR_SCHEME.get()
+}
+
+/// Returns true if color scheme is changed, styles should be refreshed then
+pub fn set_color_scheme(color_scheme: ColorScheme) -> bool {
+    COLOR_SCHEME.replace(color_scheme) != color_scheme
+}
+
+/// Feature tested by `@media` rules, only accessibility preferences and color scheme are supported.
+#[derive(Debug, Clone, PartialEq)]
+enum MediaFeature {
+    ReducedMotion(bool),
+    HighContrast(bool),
+    MinTextScale(f32),
+    MaxTextScale(f32),
+    ColorScheme(ColorScheme),
+    Unsupported,
+}
+
+impl MediaFeature {
+    fn parse(feature: &str) -> Self {
+        let (name, value) = match feature.split_once(':') {
+            Some((n, v)) => (n.trim(), v.trim()),
+            None => (feature.trim(), ""),
+        };
+        match (name, value) {
+            ("prefers-reduced-motion", "reduce" | "") => MediaFeature::ReducedMotion(true),
+            ("prefers-reduced-motion", "no-preference") => MediaFeature::ReducedMotion(false),
+            ("prefers-contrast", "more" | "") => MediaFeature::HighContrast(true),
+            ("prefers-contrast", "no-preference") => MediaFeature::HighContrast(false),
+            ("min-text-scale", v) => v
+                .parse()
+                .map(MediaFeature::MinTextScale)
+                .unwrap_or(MediaFeature::Unsupported),
+            ("max-text-scale", v) => v
+                .parse()
+                .map(MediaFeature::MaxTextScale)
+                .unwrap_or(MediaFeature::Unsupported),
+            ("prefers-color-scheme", v) => ColorScheme::parse(v)
+                .map(MediaFeature::ColorScheme)
+                .unwrap_or(MediaFeature::Unsupported),
+            _ => MediaFeature::Unsupported,
+        }
+    }
+
+    fn matches(&self, prefs: &A11yPreferences, color_scheme: ColorScheme) -> bool {
+        match self {
+            MediaFeature::ReducedMotion(v) => prefs.reduce_motion == *v,
+            MediaFeature::HighContrast(v) => prefs.high_contrast == *v,
+            MediaFeature::MinTextScale(v) => prefs.text_scale >= *v,
+            MediaFeature::MaxTextScale(v) => prefs.text_scale <= *v,
+            MediaFeature::ColorScheme(v) => color_scheme == *v,
+            MediaFeature::Unsupported => false,
+        }
+    }
+}
+
+/// Condition of `@media` rule, e.g. `(prefers-reduced-motion: reduce) and (min-text-scale: 1.5)`
+/// or `(prefers-color-scheme: dark)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    features: Vec<MediaFeature>,
+}
+
+impl MediaQuery {
+    pub fn parse(condition: &str) -> Self {
+        let features = condition
+            .split(" and ")
+            .map(|f| f.trim().trim_start_matches('(').trim_end_matches(')'))
+            .map(MediaFeature::parse)
+            .collect();
+        Self { features }
+    }
+
+    pub fn matches(&self, prefs: &A11yPreferences, color_scheme: ColorScheme) -> bool {
+        self.features.iter().all(|f| f.matches(prefs, color_scheme))
+    }
+}
+
+/// Split stylesheet source at `@media` blocks, returns segments in source order with
+/// conditions of the blocks containing them. Strings and comments are skipped by the tokenizer.
+pub fn split_media_blocks(source: &str) -> Vec<(Option<MediaQuery>, String)> {
+    let mut input = ParserInput::new(source);
+    let mut parser = Parser::new(&mut input);
+    let mut segments = Vec::new();
+    let mut segment_start = parser.position();
+    loop {
+        let media_start = parser.position();
+        let is_media = match parser.next_including_whitespace_and_comments() {
+            Ok(Token::AtKeyword(name)) => name.eq_ignore_ascii_case("media"),
+            Ok(_) => false,
+            Err(_) => break,
+        };
+        if !is_media {
+            continue;
+        }
+        let condition_start = parser.position();
+        let condition_end = loop {
+            let position = parser.position();
+            match parser.next_including_whitespace_and_comments() {
+                Ok(Token::CurlyBracketBlock) => break Some(position),
+                Ok(Token::Semicolon) | Err(_) => break None,
+                Ok(_) => {}
+            }
+        };
+        let before = parser.slice(segment_start..media_start);
+        segments.push((None, before.to_string()));
+        // Invalid `@media` rules are dropped
+        let Some(condition_end) = condition_end else {
+            segment_start = parser.position();
+            continue;
+        };
+        let condition = parser.slice(condition_start..condition_end).to_string();
+        let body = parser
+            .parse_nested_block(|p| {
+                let start = p.position();
+                while p.next_including_whitespace_and_comments().is_ok() {}
+                Ok::<_, ParseError<()>>(p.slice_from(start).to_string())
+            })
+            .unwrap_or_default();
+        segments.push((Some(MediaQuery::parse(&condition)), body));
+        segment_start = parser.position();
+    }
+    segments.push((None, parser.slice_from(segment_start).to_string()));
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::a11y::A11yPreferences;
+    use crate::style::media::{split_media_blocks, ColorScheme, MediaQuery};
+
+    #[test]
+    fn test_media_blocks() {
+        let source = "a { color: red }\n\
+            @media (prefers-reduced-motion: reduce) { b { color: blue } }\n\
+            c { content: \"@media\" } /* @media { */ d { color: green }";
+        let segments = split_media_blocks(source);
+        assert_eq!(3, segments.len());
+        assert!(segments[0].0.is_none() && segments[0].1.contains("a { color: red }"));
+        assert_eq!(" b { color: blue } ", segments[1].1);
+        assert!(segments[2].0.is_none() && segments[2].1.contains("d { color: green }"));
+        let media = segments[1].0.as_ref().unwrap();
+        let mut prefs = A11yPreferences::default();
+        assert!(!media.matches(&prefs, ColorScheme::Light));
+        prefs.reduce_motion = true;
+        assert!(media.matches(&prefs, ColorScheme::Light));
+    }
+
+    #[test]
+    fn test_invalid_media_rule() {
+        let source = "a { color: red } @media (prefers-contrast: more); b { color: blue }";
+        let segments = split_media_blocks(source);
+        assert!(segments.iter().all(|(media, _)| media.is_none()));
+        let css: String = segments.iter().map(|(_, css)| css.as_str()).collect();
+        assert!(css.contains("a { color: red }"));
+        assert!(css.contains("b { color: blue }"));
+        assert!(!css.contains("@media"));
+    }
+
+    #[test]
+    fn test_color_scheme() {
+        let prefs = A11yPreferences::default();
+        let query = MediaQuery::parse("(prefers-color-scheme: dark)");
+        assert!(query.matches(&prefs, ColorScheme::Dark));
+        assert!(!query.matches(&prefs, ColorScheme::Light));
+        let query = MediaQuery::parse("(prefers-color-scheme: sepia)");
+        assert!(!query.matches(&prefs, ColorScheme::Dark));
+    }
+}
@@ -1,9 +1,14 @@
 use crate as deft;
+use crate::number::DeNan;
+use crate::some_or_return;
+use crate::style::display::Display;
+use crate::style::grid::{GridContainer, GridItem, TrackSize};
 use crate::style::YogaNode;
 use deft_macros::mrc_object;
+use ordered_float::OrderedFloat;
 use yoga::{
-    Align, Context, Direction, Display, Edge, FlexDirection, Justify, MeasureMode, Node, NodeRef,
-    Overflow, PositionType, Size, StyleUnit, Wrap,
+    Align, Context, Direction, Edge, FlexDirection, Justify, MeasureMode, Node, NodeRef, Overflow,
+    PositionType, Size, StyleUnit, Wrap,
 };
 
 pub struct MeasureParams {
@@ -38,6 +43,32 @@ extern "C" fn custom_measure_shadow(
     unreachable!()
 }
 
+extern "C" fn custom_measure_grid(
+    node_ref: NodeRef,
+    width: f32,
+    width_mode: MeasureMode,
+    height: f32,
+    height_mode: MeasureMode,
+) -> Size {
+    if let Some(ctx) = Node::get_context_mut(&node_ref) {
+        if let Some(node_item) = ctx.downcast_mut::<NodeItem>() {
+            let width = if width_mode == MeasureMode::Undefined {
+                f32::NAN
+            } else {
+                width
+            };
+            let height = if height_mode == MeasureMode::Undefined {
+                f32::NAN
+            } else {
+                height
+            };
+            let (width, height) = node_item.layout_grid_items(width, height, None);
+            return Size { width, height };
+        }
+    }
+    unreachable!()
+}
+
 extern "C" fn custom_measure_fn(
     node_ref: NodeRef,
     width: f32,
@@ -101,6 +132,10 @@ pub struct NodeItem {
     pub border_right: f32,
     pub border_bottom: f32,
     pub border_left: f32,
+    pub grid_template_columns: Vec<TrackSize>,
+    pub grid_template_rows: Vec<TrackSize>,
+    /// Placement in the parent grid container
+    pub grid_item: GridItem,
     pub measure_fn: Option<CustomMeasureFn>,
     pub children: Vec<NodeItem>,
 }
@@ -151,6 +186,9 @@ impl NodeItem {
             border_right: 0.0,
             border_bottom: 0.0,
             border_left: 0.0,
+            grid_template_columns: Vec::new(),
+            grid_template_rows: Vec::new(),
+            grid_item: GridItem::default(),
             measure_fn: None,
             children: vec![],
         }
@@ -174,6 +212,7 @@ impl NodeItem {
         self.build_yoga_node(false);
         self._yn
             .calculate_layout(available_width, available_height, direction);
+        self.place_grid_items();
     }
 
     pub fn calculate_shadow_layout(
@@ -186,6 +225,100 @@ impl NodeItem {
         if let Some(sn) = &mut self._shadow_yn {
             sn.calculate_layout(available_width, available_height, direction);
         }
+        let sn = some_or_return!(self._shadow_yn.clone());
+        if self.is_grid_container() {
+            self.place_grid_items_in(&sn);
+        } else {
+            for c in &mut self.children {
+                c.place_grid_items();
+            }
+        }
+    }
+
+    fn is_grid_container(&self) -> bool {
+        self.display == Display::Grid && self.measure_fn.is_none()
+    }
+
+    /// Place children of grid containers in this subtree, which are not children of yoga nodes
+    /// and laid out as separate roots. Scrollable grid containers place their children when
+    /// the shadow layout is calculated.
+    fn place_grid_items(&mut self) {
+        if self.display == Display::None || self.has_shadow() {
+            return;
+        }
+        if self.is_grid_container() {
+            let yn = self._yn.clone();
+            self.place_grid_items_in(&yn);
+        } else if self.measure_fn.is_none() {
+            for c in &mut self.children {
+                c.place_grid_items();
+            }
+        }
+    }
+
+    fn place_grid_items_in(&mut self, container: &YogaNode) {
+        let left =
+            container.get_layout_padding_left().de_nan(0.0) + container.get_layout_border_left();
+        let top =
+            container.get_layout_padding_top().de_nan(0.0) + container.get_layout_border_top();
+        let right =
+            container.get_layout_padding_right().de_nan(0.0) + container.get_layout_border_right();
+        let bottom = container.get_layout_padding_bottom().de_nan(0.0)
+            + container.get_layout_border_bottom();
+        let width = container.get_layout_width() - left - right;
+        let height = container.get_layout_height() - top - bottom;
+        self.layout_grid_items(width, height, Some((left, top)));
+    }
+
+    /// Lay out children in grid tracks and return the content size. Children are moved to
+    /// their grid areas offset by `origin` if it is set, otherwise they are only measured.
+    fn layout_grid_items(
+        &mut self,
+        available_width: f32,
+        available_height: f32,
+        origin: Option<(f32, f32)>,
+    ) -> (f32, f32) {
+        let mut in_flow = Vec::new();
+        for c in &mut self.children {
+            if c.display == Display::None || c.position_type == PositionType::Absolute {
+                if origin.is_some() {
+                    // Absolute children are positioned by their own offsets
+                    c.calculate_layout(available_width, available_height, Direction::LTR);
+                }
+            } else {
+                in_flow.push(c.clone());
+            }
+        }
+        let items: Vec<GridItem> = in_flow.iter().map(|c| c.grid_item).collect();
+        let container = GridContainer {
+            columns: &self.grid_template_columns,
+            rows: &self.grid_template_rows,
+            column_gap: self.column_gap.de_nan(0.0),
+            row_gap: self.row_gap.de_nan(0.0),
+        };
+        let layout = container.layout(&items, available_width, available_height, |i, width| {
+            let c = &mut in_flow[i];
+            c.calculate_layout(width, f32::NAN, Direction::LTR);
+            (c._yn.get_layout_width(), c._yn.get_layout_height())
+        });
+        if let Some((left, top)) = origin {
+            for (c, area) in in_flow.iter_mut().zip(&layout.areas) {
+                c.calculate_layout_in_area(left + area.x, top + area.y, area.width, area.height);
+            }
+        }
+        (layout.width, layout.height)
+    }
+
+    /// Lay out as a root filling the area unless its size is set
+    fn calculate_layout_in_area(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.build_yoga_node(false);
+        self._yn.set_position_type(PositionType::Relative);
+        self._yn
+            .set_position(Edge::Left, StyleUnit::Point(OrderedFloat(x)));
+        self._yn
+            .set_position(Edge::Top, StyleUnit::Point(OrderedFloat(y)));
+        self._yn.calculate_layout(width, height, Direction::LTR);
+        self.place_grid_items();
     }
 
     fn build_yoga_node(&mut self, is_shadow_root: bool) {
@@ -199,7 +332,7 @@ impl NodeItem {
         let mut n = YogaNode::new();
         let mut s = YogaNode::new();
         n.set_position_type(self.position_type);
-        n.set_display(self.display);
+        n.set_display(self.display.to_yoga_display());
         n.set_width(self.width);
         n.set_height(self.height);
         n.set_max_width(self.max_width);
@@ -249,7 +382,11 @@ impl NodeItem {
         container.set_row_gap(self.row_gap);
         container.set_direction(self.direction);
 
-        if visit_children {
+        // Children of measured node are laid out by the measure function
+        if visit_children && self.is_grid_container() {
+            container.set_context(Some(Context::new(self.clone())));
+            container.set_measure_func(Some(custom_measure_grid));
+        } else if visit_children && self.measure_fn.is_none() {
             let mut idx = 0;
             for c in &mut self.children {
                 container.insert_child(&mut c._yn, idx);
@@ -278,6 +415,8 @@ impl NodeItem {
 
 #[cfg(test)]
 mod tests {
+    use crate::style::display::Display;
+    use crate::style::grid::TrackSize;
     use crate::style::node_item::NodeItem;
     use ordered_float::OrderedFloat;
     use yoga::{Direction, Overflow, StyleUnit};
@@ -313,4 +452,23 @@ mod tests {
         assert_eq!(sn.get_layout_height(), 100.0);
         // assert_eq!(root._yn.get_layout_height(), 20.0);
     }
+
+    #[test]
+    fn test_grid_layout() {
+        let mut root = NodeItem::new();
+        root.display = Display::Grid;
+        root.grid_template_columns = vec![TrackSize::Fixed(40.0), TrackSize::Fr(1.0)];
+        let mut children = Vec::new();
+        for height in [10.0, 20.0, 30.0] {
+            let mut child = NodeItem::new();
+            child.height = StyleUnit::Point(OrderedFloat(height));
+            root.children.push(child.clone());
+            children.push(child);
+        }
+        root.calculate_layout(100.0, f32::NAN, Direction::LTR);
+        assert_eq!(root._yn.get_layout_height(), 50.0);
+        assert_eq!(children[1]._yn.get_layout_left(), 40.0);
+        assert_eq!(children[1]._yn.get_layout_width(), 60.0);
+        assert_eq!(children[2]._yn.get_layout_top(), 20.0);
+    }
 }
@@ -0,0 +1,30 @@
+use crate::style::PropValueParse;
+
+/// Default annotation font size relative to the base font size
+pub const DEFAULT_RUBY_SIZE: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RubyPosition {
+    /// Render annotation above the base text
+    #[default]
+    Over,
+    /// Render annotation below the base text
+    Under,
+}
+
+impl PropValueParse for RubyPosition {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "over" => Some(Self::Over),
+            "under" => Some(Self::Under),
+            _ => None,
+        }
+    }
+    fn to_style_string(&self) -> String {
+        match self {
+            RubyPosition::Over => "over",
+            RubyPosition::Under => "under",
+        }
+        .to_string()
+    }
+}
@@ -1,17 +1,17 @@
+use crate::atom::Atom;
 use crate::element::Element;
 use crate::some_or_return;
 use anyhow::{anyhow, Error};
 use cssparser::{self, CowRcStr, ParseError, SourceLocation, ToCss};
 use selectors::attr::{AttrSelectorOperation, CaseSensitivity, NamespaceConstraint};
 use selectors::context::{MatchingMode, QuirksMode};
-use selectors::parser::{Component, SelectorParseErrorKind};
+use selectors::parser::{Combinator, Component, SelectorParseErrorKind};
 use selectors::parser::{
     NonTSPseudoClass, Parser, Selector as GenericSelector, SelectorImpl, SelectorList,
 };
 use selectors::{self, matching, OpaqueElement};
 use std::fmt;
 
-type LocalName = String;
 type Namespace = String;
 
 #[derive(Debug, Clone)]
@@ -21,14 +21,14 @@ impl SelectorImpl for DeftSelectors {
     type ExtraMatchingData = ();
     type AttrValue = String;
     type Identifier = String;
-    type ClassName = String;
+    type ClassName = Atom;
     type PartName = String;
-    type LocalName = String;
+    type LocalName = Atom;
     type NamespaceUrl = String;
     type NamespacePrefix = String;
     type BorrowedNamespaceUrl = String;
 
-    type BorrowedLocalName = String;
+    type BorrowedLocalName = Atom;
     type NonTSPseudoClass = PseudoClass;
 
     type PseudoElement = PseudoElement;
@@ -160,8 +160,9 @@ impl selectors::Element for Element {
         true
     }
     #[inline]
-    fn has_local_name(&self, name: &LocalName) -> bool {
-        self.tag.eq_ignore_ascii_case(name)
+    fn has_local_name(&self, name: &Atom) -> bool {
+        // Tags are lowercase, so is the name of html elements
+        self.tag == *name
     }
 
     #[inline]
@@ -179,7 +180,7 @@ impl selectors::Element for Element {
     fn attr_matches(
         &self,
         _ns: &NamespaceConstraint<&Namespace>,
-        local_name: &LocalName,
+        local_name: &Atom,
         operation: &AttrSelectorOperation<&String>,
     ) -> bool {
         let attr = some_or_return!(self.attributes.get(local_name), false);
@@ -231,13 +232,13 @@ impl selectors::Element for Element {
     }
 
     #[inline]
-    fn has_id(&self, _id: &LocalName, _case_sensitivity: CaseSensitivity) -> bool {
+    fn has_id(&self, _id: &String, _case_sensitivity: CaseSensitivity) -> bool {
         //TODO fix
         false
     }
 
     #[inline]
-    fn has_class(&self, name: &LocalName, case_sensitivity: CaseSensitivity) -> bool {
+    fn has_class(&self, name: &Atom, case_sensitivity: CaseSensitivity) -> bool {
         match case_sensitivity {
             CaseSensitivity::AsciiCaseInsensitive => self
                 .classes
@@ -249,17 +250,17 @@ impl selectors::Element for Element {
     }
 
     #[inline]
-    fn exported_part(&self, _: &LocalName) -> Option<LocalName> {
+    fn exported_part(&self, _: &String) -> Option<String> {
         None
     }
 
     #[inline]
-    fn imported_part(&self, _: &LocalName) -> Option<LocalName> {
+    fn imported_part(&self, _: &String) -> Option<String> {
         None
     }
 
     #[inline]
-    fn is_part(&self, _name: &LocalName) -> bool {
+    fn is_part(&self, _name: &String) -> bool {
         false
     }
 
@@ -279,8 +280,20 @@ pub struct Selectors(pub Vec<Selector>);
 #[derive(Clone)]
 pub struct Selector {
     selector: GenericSelector<DeftSelectors>,
-    class_names: Vec<String>,
-    attribute_names: Vec<String>,
+    class_names: Vec<Atom>,
+    attribute_names: Vec<Atom>,
+    has_hover: bool,
+    subject: SelectorSubject,
+}
+
+/// Tag, classes and attributes of the rightmost compound selector, an element could only match
+/// the selector if it has all of them
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct SelectorSubject {
+    /// Lowercase tag
+    pub tag: Option<Atom>,
+    pub classes: Vec<Atom>,
+    pub attribute_names: Vec<Atom>,
 }
 
 impl Selectors {
@@ -307,27 +320,52 @@ impl Selector {
     pub fn new(selector: GenericSelector<DeftSelectors>) -> Self {
         let mut list = Vec::new();
         let mut attribute_names = Vec::new();
+        let mut has_hover = false;
+        let mut subject = SelectorSubject::default();
+        // Match order starts from the rightmost compound selector
+        let mut in_subject = true;
         for e in selector.iter_raw_match_order() {
             match e {
                 Component::Class(c) => {
                     list.push(c.clone());
+                    if in_subject {
+                        subject.classes.push(c.clone());
+                    }
                 }
                 Component::AttributeInNoNamespaceExists { local_name, .. } => {
                     attribute_names.push(local_name.clone());
+                    if in_subject {
+                        subject.attribute_names.push(local_name.clone());
+                    }
                 }
                 Component::AttributeInNoNamespace { local_name, .. } => {
                     attribute_names.push(local_name.clone());
+                    if in_subject {
+                        subject.attribute_names.push(local_name.clone());
+                    }
                 }
                 Component::AttributeOther(a) => {
                     attribute_names.push(a.local_name.clone());
+                    if in_subject {
+                        subject.attribute_names.push(a.local_name.clone());
+                    }
+                }
+                Component::Combinator(c) => {
+                    // Pseudo element belongs to the compound selector before it
+                    if *c != Combinator::PseudoElement {
+                        in_subject = false;
+                    }
                 }
-                Component::Combinator(_) => {}
                 Component::ExplicitAnyNamespace => {}
                 Component::ExplicitNoNamespace => {}
                 Component::DefaultNamespace(_) => {}
                 Component::Namespace(_, _) => {}
                 Component::ExplicitUniversalType => {}
-                Component::LocalName(_) => {}
+                Component::LocalName(l) => {
+                    if in_subject {
+                        subject.tag = Some(l.lower_name.clone());
+                    }
+                }
                 Component::ID(_) => {}
                 Component::Negation(_) => {}
                 Component::FirstChild => {}
@@ -343,7 +381,9 @@ impl Selector {
                 Component::FirstOfType => {}
                 Component::LastOfType => {}
                 Component::OnlyOfType => {}
-                Component::NonTSPseudoClass(_) => {}
+                Component::NonTSPseudoClass(pc) => {
+                    has_hover |= *pc == PseudoClass::Hover;
+                }
                 Component::Slotted(_) => {}
                 Component::Part(_) => {}
                 Component::Host(_) => {}
@@ -354,6 +394,8 @@ impl Selector {
             selector,
             class_names: list,
             attribute_names,
+            has_hover,
+            subject,
         }
     }
 
@@ -378,14 +420,23 @@ impl Selector {
         self.selector.pseudo_element()
     }
 
-    pub fn get_classes(&self) -> &Vec<String> {
+    pub fn get_classes(&self) -> &Vec<Atom> {
         &self.class_names
     }
 
-    pub fn get_attribute_names(&self) -> &Vec<String> {
+    pub fn get_attribute_names(&self) -> &Vec<Atom> {
         &self.attribute_names
     }
 
+    /// Whether the selector contains :hover
+    pub fn has_hover(&self) -> bool {
+        self.has_hover
+    }
+
+    pub fn get_subject(&self) -> &SelectorSubject {
+        &self.subject
+    }
+
     pub fn specificity(&self) -> u32 {
         self.selector.specificity()
     }
@@ -393,6 +444,7 @@ impl Selector {
 
 #[cfg(test)]
 pub mod tests {
+    use crate::atom::Atom;
     use crate::element::button::Button;
     use crate::element::container::Container;
     use crate::element::{Element, ElementBackend};
@@ -417,6 +469,21 @@ pub mod tests {
         let selectors = Selectors::compile("p.a .b button").unwrap();
         let selector = selectors.0.get(0).unwrap();
         let classes = selector.get_classes();
-        assert_eq!(classes, &vec!["b", "a"]);
+        assert_eq!(classes, &vec![Atom::new("b"), Atom::new("a")]);
+    }
+
+    #[test]
+    fn test_subject() {
+        let selectors = Selectors::compile("p.a .b[disabled] button.c::before").unwrap();
+        let subject = selectors.0.get(0).unwrap().get_subject();
+        assert_eq!(Some(Atom::new("button")), subject.tag);
+        assert_eq!(vec![Atom::new("c")], subject.classes);
+        assert!(subject.attribute_names.is_empty());
+
+        let selectors = Selectors::compile(".a > [disabled]").unwrap();
+        let subject = selectors.0.get(0).unwrap().get_subject();
+        assert_eq!(None, subject.tag);
+        assert!(subject.classes.is_empty());
+        assert_eq!(vec![Atom::new("disabled")], subject.attribute_names);
     }
 }
@@ -17,6 +17,14 @@ impl Styles {
         }
     }
 
+    pub fn get(&self, key: &StylePropKey) -> Option<&ResolvedStyleProp> {
+        self.list.get(key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &ResolvedStyleProp> {
+        self.list.values()
+    }
+
     pub fn compute_changed_style<F: Fn(StylePropKey) -> ResolvedStyleProp>(
         &self,
         old_styles: &Styles,
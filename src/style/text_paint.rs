@@ -0,0 +1,61 @@
+use crate::style::color::parse_color;
+use crate::style::gradient::LinearGradient;
+use crate::style::length::Length;
+use crate::style::PropValueParse;
+use skia_safe::Color;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStroke {
+    pub width: Length,
+    pub color: Color,
+}
+
+impl TextStroke {
+    pub fn none() -> Self {
+        Self {
+            width: Length::PX(0.0),
+            color: Color::TRANSPARENT,
+        }
+    }
+}
+
+impl PropValueParse for TextStroke {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("none") {
+            return Some(Self::none());
+        }
+        let (width, color) = match value.split_once(char::is_whitespace) {
+            Some((width, color)) => (width, parse_color(color.trim())?),
+            None => (value, Color::BLACK),
+        };
+        let width = Length::from_str(width)?;
+        Some(Self { width, color })
+    }
+    fn to_style_string(&self) -> String {
+        format!("{} {}", self.width.to_str(), self.color.to_style_string())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextFill {
+    /// Fill glyphs with the color of text
+    Auto,
+    LinearGradient(LinearGradient),
+}
+
+impl PropValueParse for TextFill {
+    fn parse_prop_value(value: &str) -> Option<Self> {
+        if value.trim().eq_ignore_ascii_case("auto") {
+            Some(Self::Auto)
+        } else {
+            LinearGradient::parse_prop_value(value).map(Self::LinearGradient)
+        }
+    }
+    fn to_style_string(&self) -> String {
+        match self {
+            TextFill::Auto => "auto".to_string(),
+            TextFill::LinearGradient(g) => g.to_style_string(),
+        }
+    }
+}
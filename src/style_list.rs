@@ -1,18 +1,25 @@
 use crate as deft;
+use crate::atom::Atom;
 use crate::style::border::parse_border;
 use crate::style::style_vars::StyleVars;
 use crate::style::var_expr::StyleExpr;
 use crate::style::{parse_style_obj, FixedStyleProp, PropValueParse, StylePropKey, StylePropVal};
 use deft_macros::mrc_object;
 use quick_js::JsValue;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use yoga::PositionType;
 
 type CssValueResolver = Box<dyn Fn(&HashMap<String, String>) -> String>;
 
+thread_local! {
+    static PROP_NAMES: RefCell<HashMap<StylePropKey, Atom>> = RefCell::new(HashMap::new());
+}
+
 pub enum ParsedStyleProp {
     Fixed(FixedStyleProp),
-    Var(String, String, StyleExpr, Vec<FixedStyleProp>),
+    /// Lowercase key, value, expression and props resolved from variables
+    Var(Atom, String, StyleExpr, Vec<FixedStyleProp>),
 }
 
 impl PartialEq for ParsedStyleProp {
@@ -52,7 +59,7 @@ impl ParsedStyleProp {
         let mut result = Vec::new();
         if let Some(style_expr) = StyleExpr::parse(&value) {
             result.push(ParsedStyleProp::Var(
-                key.to_string(),
+                Atom::from(key.to_lowercase()),
                 value.to_string(),
                 style_expr,
                 Vec::new(),
@@ -86,10 +93,16 @@ impl ParsedStyleProp {
             ParsedStyleProp::Var(_, _, _, v) => v.clone(),
         }
     }
-    pub fn key(&self) -> String {
+    pub fn key(&self) -> Atom {
         match self {
-            ParsedStyleProp::Fixed(p) => p.key().name().to_lowercase(),
-            ParsedStyleProp::Var(k, _v, _, _) => k.to_lowercase(),
+            ParsedStyleProp::Fixed(p) => PROP_NAMES.with_borrow_mut(|names| {
+                let key = p.key();
+                names
+                    .entry(key)
+                    .or_insert_with(|| Atom::from(key.name().to_lowercase()))
+                    .clone()
+            }),
+            ParsedStyleProp::Var(k, _v, _, _) => k.clone(),
         }
     }
 }
@@ -115,6 +128,10 @@ impl StyleList {
             FixedStyleProp::FontFamily(StylePropVal::Inherit),
             FixedStyleProp::FontWeight(StylePropVal::Inherit),
             FixedStyleProp::FontStyle(StylePropVal::Inherit),
+            FixedStyleProp::DeftTextStroke(StylePropVal::Inherit),
+            FixedStyleProp::DeftTextFill(StylePropVal::Inherit),
+            FixedStyleProp::RubyPosition(StylePropVal::Inherit),
+            FixedStyleProp::DeftRubySize(StylePropVal::Inherit),
         ];
         for d in default_styles {
             default_style_props.push(ParsedStyleProp::Fixed(d));
@@ -207,6 +224,21 @@ impl StyleList {
         result
     }
 
+    /// Values of key declared by default styles, used for style debugging
+    pub fn trace_default(&self, key: StylePropKey) -> Vec<String> {
+        declared_values(&self.default_style_props, key)
+    }
+
+    /// Values of key declared by inline styles, used for style debugging
+    pub fn trace_inline(&self, key: StylePropKey) -> Vec<String> {
+        declared_values(&self.values, key)
+    }
+
+    /// Values of key declared by inline hover styles, used for style debugging
+    pub fn trace_hover(&self, key: StylePropKey) -> Vec<String> {
+        declared_values(&self.hover_style_props, key)
+    }
+
     pub fn get_pseudo_element_style_props(
         &self,
     ) -> HashMap<String, HashMap<StylePropKey, FixedStyleProp>> {
@@ -405,6 +437,28 @@ impl StyleList {
     }
 }
 
+/// Values of declarations of key in declaration order, variables are shown unresolved
+pub fn declared_values(props: &[ParsedStyleProp], key: StylePropKey) -> Vec<String> {
+    let mut result = Vec::new();
+    for p in props {
+        match p {
+            ParsedStyleProp::Fixed(f) => {
+                if f.key() == key {
+                    result.push(f.to_style_string());
+                }
+            }
+            ParsedStyleProp::Var(k, v, _, fixed) => {
+                let matched = fixed.iter().any(|f| f.key() == key)
+                    || StylePropKey::parse(&k.replace('-', "")) == Some(key);
+                if matched {
+                    result.push(v.clone());
+                }
+            }
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 pub mod tests {
     use crate::computed::ComputedValue;
@@ -420,3 +474,4 @@ pub mod tests {
         // style_vars.update_value("height", "6".to_string());
     }
 }
+
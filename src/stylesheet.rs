@@ -1,44 +1,66 @@
 use crate as deft;
 use crate::base::Id;
-use crate::element::CSS_MANAGER;
+use crate::element::{Element, CSS_MANAGER};
 use crate::ext::ext_window::WINDOWS;
 use crate::js::JsError;
-use crate::style::css_manager::CSS;
+use crate::style::css_manager::{StyleScope, CSS};
 use deft_macros::js_func;
 
 #[js_func]
 pub fn stylesheet_add(source: String) -> Result<Id<CSS>, JsError> {
-    let id = CSS_MANAGER
-        .with_borrow_mut(|manager| manager.add(&source))
-        .map_err(|e| JsError::new(format!("failed to add stylesheet: {}", e)));
-    refresh_windows_style();
-    id
+    let (id, scope) = CSS_MANAGER
+        .with_borrow_mut(|manager| {
+            let id = manager.add(&source)?;
+            let scope = manager.get_scope(&id).cloned().unwrap_or_default();
+            Ok::<_, anyhow::Error>((id, scope))
+        })
+        .map_err(|e| JsError::new(format!("failed to add stylesheet: {}", e)))?;
+    refresh_windows_style_in(&scope);
+    Ok(id)
 }
 
 #[js_func]
 pub fn stylesheet_remove(id: Id<CSS>) -> Result<(), JsError> {
-    CSS_MANAGER.with_borrow_mut(|manager| manager.remove(&id));
-    refresh_windows_style();
+    if let Some(scope) = CSS_MANAGER.with_borrow_mut(|manager| manager.remove(&id)) {
+        refresh_windows_style_in(&scope);
+    }
     Ok(())
 }
 
 #[js_func]
-pub fn stylesheet_update(id: Id<CSS>, source: String) -> Result<(), JsError> {
-    CSS_MANAGER.with_borrow_mut(|manager| {
-        let _ = manager.update(&id, &source);
-    });
-    refresh_windows_style();
+pub fn stylesheet_replace(id: Id<CSS>, source: String) -> Result<(), JsError> {
+    let scope = CSS_MANAGER
+        .with_borrow_mut(|manager| manager.update(&id, &source))
+        .map_err(|e| JsError::new(format!("failed to replace stylesheet: {}", e)))?;
+    refresh_windows_style_in(&scope);
     Ok(())
 }
 
-fn refresh_windows_style() {
+pub(crate) fn refresh_windows_style() {
+    refresh_windows_style_in(&StyleScope::universal());
+}
+
+/// Match selectors again for elements which rules in scope could apply to
+pub(crate) fn refresh_windows_style_in(scope: &StyleScope) {
+    if scope.is_empty() {
+        return;
+    }
     WINDOWS.with_borrow_mut(|windows| {
         for (_, window) in windows.iter_mut() {
             if let Ok(window) = window.upgrade_mut() {
                 if let Some(mut body) = window.get_body() {
-                    body.update_select_style_recurse();
+                    select_style_in(&mut body, scope);
                 }
             }
         }
     });
 }
+
+fn select_style_in(element: &mut Element, scope: &StyleScope) {
+    if scope.may_match(element) {
+        element.select_style();
+    }
+    for mut child in element.get_layout_children() {
+        select_style_in(&mut child, scope);
+    }
+}
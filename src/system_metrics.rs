@@ -0,0 +1,423 @@
+use crate as deft;
+use crate::app::AppEvent;
+use crate::ext::ext_window::WINDOWS;
+use crate::js::ToJsValue;
+use crate::style::style_vars::StyleVars;
+use crate::{js_serialize, send_app_event, some_or_return};
+use deft_macros::js_methods;
+use log::error;
+use quick_js::JsValue;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::thread;
+
+thread_local! {
+    static METRICS: RefCell<Option<SystemMetrics>> = RefCell::new(None);
+    static CHANGE_HANDLER: RefCell<Option<JsValue>> = RefCell::new(None);
+    static DETECTING: Cell<bool> = Cell::new(false);
+}
+
+/// Metrics and theme settings of the system
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemMetrics {
+    /// Accent color of system theme, like "#0078d4"
+    pub accent_color: String,
+    pub font_family: String,
+    /// Default font size in logical pixels
+    pub font_size: f32,
+    pub scrollbar_width: f32,
+    /// Max interval between clicks of a double click in milliseconds
+    pub double_click_interval: u32,
+    /// Interval of caret switching between visible and hidden in milliseconds, 0 if not blinking
+    pub caret_blink_interval: u32,
+}
+
+js_serialize!(SystemMetrics);
+
+impl Default for SystemMetrics {
+    fn default() -> Self {
+        Self {
+            accent_color: "#3daee9".to_string(),
+            font_family: "sans-serif".to_string(),
+            font_size: 14.0,
+            scrollbar_width: 14.0,
+            double_click_interval: 500,
+            caret_blink_interval: 530,
+        }
+    }
+}
+
+impl SystemMetrics {
+    /// Read metrics of system, values unavailable on the platform are defaults.
+    /// This may spawn processes like gsettings, avoid calling it on the ui thread.
+    pub fn detect() -> Self {
+        let mut metrics = Self::default();
+        platform::detect(&mut metrics);
+        metrics
+    }
+
+    /// Style variables like `--deft-accent-color`, sizes are in px
+    pub fn to_style_vars(&self) -> StyleVars {
+        let mut vars = StyleVars::new();
+        vars.set("deft-accent-color", &self.accent_color);
+        vars.set("deft-system-font-family", &self.font_family);
+        vars.set("deft-system-font-size", &format!("{}px", self.font_size));
+        vars.set(
+            "deft-scrollbar-width",
+            &format!("{}px", self.scrollbar_width),
+        );
+        vars
+    }
+}
+
+/// Cached metrics, defaults are returned until the first detection in background finishes
+pub fn get_system_metrics() -> SystemMetrics {
+    if let Some(metrics) = METRICS.with_borrow(|m| m.clone()) {
+        return metrics;
+    }
+    let metrics = SystemMetrics::default();
+    METRICS.set(Some(metrics.clone()));
+    refresh_system_metrics();
+    metrics
+}
+
+/// Detect metrics again in background, e.g. when the theme of system changed. If any metric
+/// changed, style variables of windows are updated and the change handler is notified.
+pub fn refresh_system_metrics() {
+    if DETECTING.replace(true) {
+        return;
+    }
+    let result = thread::Builder::new()
+        .name("system-metrics".to_string())
+        .spawn(|| {
+            let metrics = SystemMetrics::detect();
+            let apply = AppEvent::Callback(Box::new(move || apply_system_metrics(metrics)));
+            if let Err(e) = send_app_event(apply) {
+                error!("Failed to send system metrics: {}", e);
+            }
+        });
+    if let Err(e) = result {
+        error!("Failed to detect system metrics: {}", e);
+        DETECTING.set(false);
+    }
+}
+
+fn apply_system_metrics(metrics: SystemMetrics) {
+    DETECTING.set(false);
+    let changed = METRICS.with_borrow_mut(|m| {
+        let changed = m.as_ref() != Some(&metrics);
+        *m = Some(metrics.clone());
+        changed
+    });
+    if !changed {
+        return;
+    }
+    let style_vars = metrics.to_style_vars();
+    let windows: Vec<_> = WINDOWS.with_borrow(|w| w.values().cloned().collect());
+    for window in windows {
+        if let Ok(mut window) = window.upgrade_mut() {
+            window.update_style_vars(style_vars.clone());
+        }
+    }
+    call_change_handler(metrics);
+}
+
+fn call_change_handler(metrics: SystemMetrics) {
+    let handler = some_or_return!(CHANGE_HANDLER.with_borrow(|h| h.clone()));
+    let result = metrics
+        .to_js_value()
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|m| {
+            handler
+                .call_as_function(vec![m])
+                .map_err(|e| format!("{:?}", e))
+        });
+    if let Err(e) = result {
+        error!("Failed to call system metrics change handler: {}", e);
+    }
+}
+
+/// Color of accent names of gnome
+fn gnome_accent_color(name: &str) -> Option<&'static str> {
+    let color = match name {
+        "blue" => "#3584e4",
+        "teal" => "#2190a4",
+        "green" => "#3a944a",
+        "yellow" => "#c88800",
+        "orange" => "#ed5b00",
+        "red" => "#e62d42",
+        "pink" => "#d56199",
+        "purple" => "#9141ac",
+        "slate" => "#6f8396",
+        _ => return None,
+    };
+    Some(color)
+}
+
+/// Color of `AppleAccentColor` values of macos, blue if not set
+fn macos_accent_color(value: Option<i32>) -> &'static str {
+    match value {
+        Some(-1) => "#8c8c8c",
+        Some(0) => "#ff5257",
+        Some(1) => "#f7821b",
+        Some(2) => "#ffc600",
+        Some(3) => "#62ba46",
+        Some(5) => "#a550a7",
+        Some(6) => "#f74f9e",
+        _ => "#007aff",
+    }
+}
+
+/// Color of the `AccentColor` dword of windows which is in 0xAABBGGRR
+fn windows_accent_color(value: u32) -> String {
+    let (r, g, b) = (value & 0xff, (value >> 8) & 0xff, (value >> 16) & 0xff);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Parse font names of gtk like "Cantarell 11", returns family and size in px
+fn parse_gtk_font(font: &str) -> Option<(String, f32)> {
+    let (family, size) = font.trim().rsplit_once(' ')?;
+    let size = size.parse::<f32>().ok()?;
+    Some((family.trim().to_string(), size * 96.0 / 72.0))
+}
+
+#[cfg(windows_platform)]
+mod platform {
+    use crate::system_metrics::{windows_accent_color, SystemMetrics};
+    use std::ffi::c_void;
+    use std::os::windows::process::CommandExt;
+    use windows::Win32::UI::Input::KeyboardAndMouse::GetDoubleClickTime;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetCaretBlinkTime, GetSystemMetrics, SystemParametersInfoW, NONCLIENTMETRICSW,
+        SM_CXVSCROLL, SPI_GETNONCLIENTMETRICS, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    fn accent_color() -> Option<String> {
+        let output = std::process::Command::new("reg")
+            .args([
+                "query",
+                r"HKCU\Software\Microsoft\Windows\DWM",
+                "/v",
+                "AccentColor",
+            ])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .ok()?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        let value = output.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            (parts.next() == Some("AccentColor")).then(|| parts.nth(1))?
+        })?;
+        let value = u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()?;
+        Some(windows_accent_color(value))
+    }
+
+    pub fn detect(metrics: &mut SystemMetrics) {
+        if let Some(color) = accent_color() {
+            metrics.accent_color = color;
+        }
+        unsafe {
+            metrics.double_click_interval = GetDoubleClickTime();
+            metrics.caret_blink_interval = match GetCaretBlinkTime() {
+                u32::MAX => 0,
+                t => t,
+            };
+            let width = GetSystemMetrics(SM_CXVSCROLL);
+            if width > 0 {
+                metrics.scrollbar_width = width as f32;
+            }
+            let mut ncm = NONCLIENTMETRICSW {
+                cbSize: std::mem::size_of::<NONCLIENTMETRICSW>() as u32,
+                ..Default::default()
+            };
+            let size = ncm.cbSize;
+            let ptr = &mut ncm as *mut _ as *mut c_void;
+            let no_update = SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0);
+            if SystemParametersInfoW(SPI_GETNONCLIENTMETRICS, size, Some(ptr), no_update).is_ok() {
+                let font = &ncm.lfMessageFont;
+                let len = font.lfFaceName.iter().position(|c| *c == 0).unwrap_or(0);
+                if len > 0 {
+                    metrics.font_family = String::from_utf16_lossy(&font.lfFaceName[..len]);
+                }
+                if font.lfHeight != 0 {
+                    metrics.font_size = font.lfHeight.unsigned_abs() as f32;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(macos_platform)]
+mod platform {
+    use crate::system_metrics::{macos_accent_color, SystemMetrics};
+
+    fn read_global_default(key: &str) -> Option<String> {
+        let output = std::process::Command::new("defaults")
+            .args(["read", "-g", key])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    pub fn detect(metrics: &mut SystemMetrics) {
+        let accent = read_global_default("AppleAccentColor").and_then(|v| v.parse().ok());
+        metrics.accent_color = macos_accent_color(accent).to_string();
+        metrics.font_size = 13.0;
+        metrics.scrollbar_width = 15.0;
+        let threshold = read_global_default("com.apple.mouse.doubleClickThreshold");
+        if let Some(seconds) = threshold.and_then(|v| v.parse::<f32>().ok()) {
+            metrics.double_click_interval = (seconds * 1000.0) as u32;
+        }
+    }
+}
+
+#[cfg(linux_platform)]
+mod platform {
+    use crate::system_metrics::{gnome_accent_color, parse_gtk_font, SystemMetrics};
+
+    /// Read setting of gnome with gsettings, quotes of strings are removed
+    fn read_setting(schema: &str, key: &str) -> Option<String> {
+        let output = std::process::Command::new("gsettings")
+            .args(["get", schema, key])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let value = String::from_utf8_lossy(&output.stdout);
+        Some(value.trim().trim_matches('\'').to_string())
+    }
+
+    pub fn detect(metrics: &mut SystemMetrics) {
+        const INTERFACE: &str = "org.gnome.desktop.interface";
+        if let Some(color) = read_setting(INTERFACE, "accent-color") {
+            if let Some(color) = gnome_accent_color(&color) {
+                metrics.accent_color = color.to_string();
+            }
+        }
+        let font = read_setting(INTERFACE, "font-name");
+        if let Some((family, size)) = font.and_then(|f| parse_gtk_font(&f)) {
+            metrics.font_family = family;
+            metrics.font_size = size;
+        }
+        let blink = read_setting(INTERFACE, "cursor-blink");
+        let blink_time = read_setting(INTERFACE, "cursor-blink-time").and_then(|v| v.parse().ok());
+        metrics.caret_blink_interval = match (blink.as_deref(), blink_time) {
+            (Some("false"), _) => 0,
+            // Blink time of gtk is a full cycle
+            (_, Some(time)) => time / 2,
+            _ => metrics.caret_blink_interval,
+        };
+        let double_click = read_setting("org.gnome.desktop.peripherals.mouse", "double-click");
+        if let Some(interval) = double_click.and_then(|v| v.parse().ok()) {
+            metrics.double_click_interval = interval;
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+mod platform {
+    use crate::android::with_activity;
+    use crate::system_metrics::SystemMetrics;
+    use anyhow::Error;
+    use jni::JNIEnv;
+    use log::error;
+
+    fn double_tap_timeout(env: &mut JNIEnv) -> Result<i32, Error> {
+        let class = "android/view/ViewConfiguration";
+        Ok(env
+            .call_static_method(class, "getDoubleTapTimeout", "()I", &[])?
+            .i()?)
+    }
+
+    pub fn detect(metrics: &mut SystemMetrics) {
+        // Scrollbars are overlays and the accent color is not exposed to native code
+        metrics.font_family = "Roboto".to_string();
+        metrics.scrollbar_width = 4.0;
+        metrics.caret_blink_interval = 500;
+        let timeout = with_activity(|env, _| {
+            let timeout = double_tap_timeout(env);
+            let _ = env.exception_clear();
+            timeout
+        });
+        match timeout {
+            Ok(timeout) => metrics.double_click_interval = timeout as u32,
+            Err(e) => error!("Failed to read double tap timeout: {}", e),
+        }
+    }
+}
+
+#[cfg(target_os = "ios")]
+mod platform {
+    use crate::system_metrics::SystemMetrics;
+
+    /// Defaults of UIKit, which are not configurable by users
+    pub fn detect(metrics: &mut SystemMetrics) {
+        metrics.accent_color = "#007aff".to_string();
+        metrics.font_size = 17.0;
+        metrics.scrollbar_width = 3.0;
+        metrics.double_click_interval = 350;
+        metrics.caret_blink_interval = 500;
+    }
+}
+
+#[cfg(not(any(desktop_platform, target_os = "android", target_os = "ios")))]
+mod platform {
+    use crate::system_metrics::SystemMetrics;
+
+    pub fn detect(metrics: &mut SystemMetrics) {
+        metrics.scrollbar_width = 4.0;
+    }
+}
+
+#[allow(nonstandard_style)]
+pub struct system_metrics;
+
+#[js_methods]
+impl system_metrics {
+    #[js_func]
+    pub fn get() -> SystemMetrics {
+        get_system_metrics()
+    }
+
+    /// Handler is called with SystemMetrics when any metric changed
+    #[js_func]
+    pub fn set_change_handler(handler: JsValue) {
+        let handler = match handler {
+            JsValue::Null | JsValue::Undefined => None,
+            h => Some(h),
+        };
+        CHANGE_HANDLER.set(handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::system_metrics::{
+        gnome_accent_color, macos_accent_color, parse_gtk_font, windows_accent_color,
+    };
+
+    #[test]
+    fn test_accent_colors() {
+        assert_eq!("#0078d7", windows_accent_color(0xffd77800));
+        assert_eq!("#007aff", macos_accent_color(None));
+        assert_eq!("#62ba46", macos_accent_color(Some(3)));
+        assert_eq!(Some("#3584e4"), gnome_accent_color("blue"));
+        assert_eq!(None, gnome_accent_color("unknown"));
+    }
+
+    #[test]
+    fn test_parse_gtk_font() {
+        assert_eq!(
+            Some(("Noto Sans".to_string(), 16.0)),
+            parse_gtk_font("Noto Sans 12")
+        );
+        assert_eq!(None, parse_gtk_font("Cantarell"));
+    }
+}
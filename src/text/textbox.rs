@@ -4,10 +4,13 @@ mod util;
 use crate as deft;
 use crate::base::EventContext;
 use crate::color::parse_hex_color;
+use crate::element::common::image_object::ImageObject;
 use crate::element::paragraph::simple_paragraph_builder::SimpleParagraphBuilder;
 use crate::element::paragraph::ParagraphParams;
 use crate::element::text::intersect_range;
-use crate::element::text::simple_text_paragraph::SimpleTextParagraph;
+use crate::element::text::simple_text_paragraph::{
+    Placeholder, SimpleTextParagraph, TextLayout, OBJECT_REPLACEMENT_CHARACTER,
+};
 use crate::element::{ElementBackend, ElementWeak};
 use crate::event::{
     ClickEvent, Event, KeyDownEvent, KeyEventDetail, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
@@ -18,16 +21,25 @@ use crate::number::DeNan;
 use crate::paint::Painter;
 use crate::render::RenderFn;
 use crate::string::StringUtils;
-use crate::style::color::parse_optional_color_str;
+use crate::style::color::{parse_optional_color_str, ColorHelper};
 use crate::style::font::FontStyle;
+use crate::style::line_clamp::TruncationMode;
+use crate::style::ruby::{RubyPosition, DEFAULT_RUBY_SIZE};
+use crate::style::text_paint::TextFill;
 use crate::style::PropValueParse;
 use crate::text::textbox::line::Line;
-use crate::text::textbox::util::{parse_optional_text_decoration, parse_optional_weight};
-use crate::text::{TextAlign, TextStyle};
+use crate::text::textbox::util::{
+    parse_optional_placeholder_alignment, parse_optional_text_decoration, parse_optional_weight,
+};
+use crate::text::{TextAlign, TextDecoration, TextStyle};
 use crate::{base, js_deserialize, js_serialize, some_or_continue};
 use serde::{Deserialize, Serialize};
+use skia_safe::canvas::SaveLayerRec;
 use skia_safe::font_style::{Weight, Width};
-use skia_safe::{Color, Paint};
+use skia_safe::{BlendMode, Color, Paint, Rect, Shader, TileMode};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 #[cfg(target_os = "windows")]
 pub const DEFAULT_FALLBACK_FONTS: &str = "sans-serif,Microsoft YaHei,Segoe UI Emoji";
@@ -47,10 +59,55 @@ pub const DEFAULT_FALLBACK_FONTS: &str = "Noto Serif,sans-serif";
 
 const ZERO_WIDTH_WHITESPACE: &str = "\u{200B}";
 
+const DEFAULT_LINK_COLOR: Color = Color::new(0xFF0066CC);
+
+/// Scale of rasterizing svg of inline images, which keeps them sharp on hidpi screens
+const IMAGE_RASTER_SCALE: f32 = 2.0;
+
+/// Length of fade-out truncation relative to line height
+const FADE_LENGTH_RATIO: f32 = 3.0;
+
+thread_local! {
+    /// Decoded inline images by src, paragraphs are rebuilt on every text or style change
+    static INLINE_IMAGES: RefCell<HashMap<String, InlineImage>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Clone)]
+struct InlineImage {
+    natural_size: (f32, f32),
+    image: Option<skia_safe::Image>,
+}
+
+impl InlineImage {
+    fn load(src: &str) -> Self {
+        INLINE_IMAGES.with_borrow_mut(|images| {
+            images
+                .entry(src.to_string())
+                .or_insert_with(|| {
+                    let img = ImageObject::new(src);
+                    Self {
+                        natural_size: img.get_size(),
+                        image: img.to_raster_image(IMAGE_RASTER_SCALE),
+                    }
+                })
+                .clone()
+        })
+    }
+}
+
+/// Drop decoded inline images, they are decoded again when the paragraph is rebuilt
+pub fn clear_inline_image_cache() {
+    INLINE_IMAGES.with_borrow_mut(|images| images.clear());
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum TextElement {
     Text(TextUnit),
+    Link(TextLink),
+    Ruby(TextRuby),
+    Image(TextImage),
+    Embed(TextEmbed),
 }
 
 js_serialize!(TextElement);
@@ -58,20 +115,19 @@ js_deserialize!(TextElement);
 
 impl TextElement {
     fn atom_count(&self) -> usize {
-        match self {
-            TextElement::Text(text) => text.text.chars_count(),
-        }
+        self.text().chars_count()
     }
     fn text(&self) -> &str {
         match self {
-            TextElement::Text(t) => t.text.as_str(),
+            TextElement::Text(unit) => unit.text.as_str(),
+            TextElement::Link(link) => link.unit.text.as_str(),
+            TextElement::Ruby(ruby) => ruby.unit.text.as_str(),
+            TextElement::Image(_) | TextElement::Embed(_) => OBJECT_REPLACEMENT_CHARACTER,
         }
     }
 
     fn get_text(&self, begin: usize, end: usize) -> &str {
-        match self {
-            TextElement::Text(t) => t.text.substring(begin, end - begin),
-        }
+        self.text().substring(begin, end - begin)
     }
 }
 
@@ -91,6 +147,90 @@ pub struct TextUnit {
 js_serialize!(TextUnit);
 js_deserialize!(TextUnit);
 
+/// Text unit which is clickable and carries a payload
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TextLink {
+    #[serde(flatten)]
+    pub unit: TextUnit,
+    /// Payload of link, passed to `linkclick` event
+    pub href: String,
+}
+
+/// Text unit annotated with ruby text, e.g. furigana of kanji
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TextRuby {
+    #[serde(flatten)]
+    pub unit: TextUnit,
+    /// Text rendered over or under the base text, in smaller font size
+    pub annotation: String,
+}
+
+/// Image which flows inline with text
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TextImage {
+    pub src: String,
+    /// Defaults to natural width, or scaled by height if only height is specified
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    /// One of "baseline"(default), "top", "middle" and "bottom"
+    pub vertical_align: Option<String>,
+}
+
+impl TextImage {
+    fn to_placeholder(&self) -> Placeholder {
+        let img = InlineImage::load(&self.src);
+        let (natural_width, natural_height) = img.natural_size;
+        let ratio = if natural_height > 0.0 {
+            natural_width / natural_height
+        } else {
+            1.0
+        };
+        let (width, height) = match (self.width, self.height) {
+            (Some(w), Some(h)) => (w, h),
+            (Some(w), None) => (w, w / ratio),
+            (None, Some(h)) => (h * ratio, h),
+            (None, None) => (natural_width, natural_height),
+        };
+        Placeholder {
+            width,
+            height,
+            alignment: parse_optional_placeholder_alignment(self.vertical_align.as_ref()),
+            image: img.image,
+        }
+    }
+}
+
+/// Child element of the owner which flows inline with text, e.g. a mention chip
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEmbed {
+    /// Id of the element
+    pub element: u32,
+    /// One of "baseline"(default), "top", "middle" and "bottom"
+    pub vertical_align: Option<String>,
+    /// Size of the element, measured by the owner of text box
+    #[serde(skip)]
+    pub size: (f32, f32),
+}
+
+impl TextEmbed {
+    fn to_placeholder(&self) -> Placeholder {
+        Placeholder {
+            width: self.size.0,
+            height: self.size.1,
+            alignment: parse_optional_placeholder_alignment(self.vertical_align.as_ref()),
+            image: None,
+        }
+    }
+}
+
+/// Position of link in text box, (row, unit index)
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub struct LinkPosition(pub usize, pub usize);
+
 #[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct TextCoord(pub usize, pub usize);
 
@@ -148,6 +288,10 @@ pub struct TextBox {
     selecting_begin: Option<TextCoord>,
     selection_bg: Paint,
     selection_fg: Paint,
+    text_stroke: (f32, Color),
+    text_fill: TextFill,
+    /// Max count of visible lines and how to truncate the last one
+    line_clamp: Option<(usize, TruncationMode)>,
     width: f32,
     padding: (f32, f32, f32, f32),
     /// (row_offset, column_offset)
@@ -234,6 +378,20 @@ impl TextBox {
         }
     }
 
+    pub fn set_ruby_size(&mut self, ruby_size: f32) {
+        if self.params.ruby_size != ruby_size {
+            self.params.ruby_size = ruby_size;
+            self.rebuild_paragraph();
+        }
+    }
+
+    pub fn set_ruby_position(&mut self, ruby_position: RubyPosition) {
+        if self.params.ruby_position != ruby_position {
+            self.params.ruby_position = ruby_position;
+            self.rebuild_paragraph();
+        }
+    }
+
     pub fn set_line_height(&mut self, line_height: Option<f32>) {
         if self.params.line_height != line_height {
             self.params.line_height = line_height;
@@ -241,6 +399,43 @@ impl TextBox {
         }
     }
 
+    pub fn set_text_stroke(&mut self, width: f32, color: Color) {
+        if self.text_stroke != (width, color) {
+            self.text_stroke = (width, color);
+            self.request_repaint();
+        }
+    }
+
+    pub fn set_text_fill(&mut self, fill: TextFill) {
+        if self.text_fill != fill {
+            self.text_fill = fill;
+            self.request_repaint();
+        }
+    }
+
+    pub fn set_line_clamp(&mut self, line_clamp: Option<(usize, TruncationMode)>) {
+        if self.line_clamp != line_clamp {
+            self.line_clamp = line_clamp;
+            self.request_layout();
+        }
+    }
+
+    /// Return true if some lines are hidden by line clamp
+    pub fn is_truncated(&self) -> bool {
+        let (max_lines, _) = match self.line_clamp {
+            Some(c) => c,
+            None => return false,
+        };
+        let mut line_count = 0;
+        for ln in &self.lines {
+            line_count += match &ln.sk_paragraph.layout {
+                Some(layout) => layout.line_count(),
+                None => 1,
+            };
+        }
+        line_count > max_lines
+    }
+
     pub fn get_paragraph_params(&self) -> &ParagraphParams {
         &self.params
     }
@@ -278,8 +473,25 @@ impl TextBox {
 
     pub fn height(&self) -> f32 {
         let mut height = 0.0;
+        let mut remaining_lines = self.line_clamp.map(|(n, _)| n).unwrap_or(usize::MAX);
         for ln in &self.lines {
-            height += ln.sk_paragraph.height().de_nan(0.0);
+            if remaining_lines == 0 {
+                break;
+            }
+            match &ln.sk_paragraph.layout {
+                Some(layout) if layout.line_count() > remaining_lines => {
+                    height += layout.lines_height(remaining_lines);
+                    remaining_lines = 0;
+                }
+                Some(layout) => {
+                    height += ln.sk_paragraph.height().de_nan(0.0);
+                    remaining_lines -= layout.line_count();
+                }
+                None => {
+                    height += ln.sk_paragraph.height().de_nan(0.0);
+                    remaining_lines -= 1;
+                }
+            }
         }
         let (padding_top, _, padding_bottom, _) = self.padding;
         height + padding_top + padding_bottom
@@ -419,6 +631,39 @@ impl TextBox {
         }
     }
 
+    /// Move caret and selection end to point while selecting by mouse, e.g. when auto scrolling.
+    /// Returns false if not selecting
+    pub fn update_selecting_by_pixel_coord(&mut self, point: (f32, f32)) -> bool {
+        if self.selecting_begin.is_none() {
+            return false;
+        }
+        let caret = self.get_text_coord_by_pixel_coord(point);
+        self.update_caret(caret);
+        self.selection_update(caret)
+    }
+
+    /// Move caret to start or end of current line, selection is extended if `select` is true
+    pub fn move_caret_to_line_edge(&mut self, to_end: bool, select: bool) {
+        let row = self.caret.0;
+        let col = match self.lines.get(row) {
+            None => return,
+            Some(ln) if to_end => ln.atom_count(),
+            Some(_) => 0,
+        };
+        let anchor = if self.selection.is_empty() {
+            self.caret
+        } else if self.caret == self.selection.min() {
+            self.selection.max()
+        } else {
+            self.selection.min()
+        };
+        let new_caret = TextCoord(row, col);
+        self.update_caret_value(new_caret, false);
+        if select {
+            self.select(TextCoord::min(anchor, new_caret), TextCoord::max(anchor, new_caret));
+        }
+    }
+
     pub fn get_text_coord_by_pixel_coord(&self, mut coord: (f32, f32)) -> TextCoord {
         let (padding_top, _, _, padding_left) = self.padding;
         coord.0 -= padding_left;
@@ -487,6 +732,147 @@ impl TextBox {
         ))
     }
 
+    /// Link under the point which is relative to the padding box
+    pub fn get_link_at(&self, point: (f32, f32)) -> Option<LinkPosition> {
+        let TextCoord(row, col) = self.get_text_coord_by_pixel_coord(point);
+        let line = self.lines.get(row)?;
+        let layout = line.sk_paragraph.layout.as_ref()?;
+        let (_, _, _, padding_left) = self.padding;
+        let x = point.0 - padding_left;
+        // Caret column may be on either side of the char under point
+        for c in [col.checked_sub(1), Some(col)].into_iter().flatten() {
+            let bounds = some_or_continue!(layout.get_char_bounds(c));
+            if bounds.left <= x && x < bounds.right {
+                let (index, _) = line.get_unit_at(c)?;
+                if let Some(TextElement::Link(_)) = line.units.get(index) {
+                    return Some(LinkPosition(row, index));
+                }
+            }
+        }
+        None
+    }
+
+    pub fn get_link(&self, position: LinkPosition) -> Option<&TextLink> {
+        match self.lines.get(position.0)?.units.get(position.1)? {
+            TextElement::Link(link) => Some(link),
+            _ => None,
+        }
+    }
+
+    /// Next link after `from` in document order, or the first one if `from` is None
+    pub fn get_next_link(
+        &self,
+        from: Option<LinkPosition>,
+        backward: bool,
+    ) -> Option<LinkPosition> {
+        let mut links = Vec::new();
+        for (row, ln) in self.lines.iter().enumerate() {
+            for (index, unit) in ln.units.iter().enumerate() {
+                if let TextElement::Link(_) = unit {
+                    links.push(LinkPosition(row, index));
+                }
+            }
+        }
+        if backward {
+            links.reverse();
+        }
+        match from.and_then(|f| links.iter().position(|l| *l == f)) {
+            Some(i) => links.get(i + 1).copied(),
+            None => links.first().copied(),
+        }
+    }
+
+    /// Bounds of link for each visual line it occupies
+    pub fn get_link_rects(&mut self, position: LinkPosition) -> Vec<base::Rect> {
+        let LinkPosition(row, index) = position;
+        let (start, end) = match self.lines.get(row) {
+            Some(ln) if index < ln.units.len() => {
+                let start: usize = ln.units[..index].iter().map(|u| u.atom_count()).sum();
+                (start, start + ln.units[index].atom_count())
+            }
+            _ => return Vec::new(),
+        };
+        let mut result: Vec<base::Rect> = Vec::new();
+        for col in start..end {
+            let r = some_or_continue!(self.get_char_rect(TextCoord(row, col)));
+            match result.last_mut() {
+                Some(last) if last.y == r.y => {
+                    let right = last.right().max(r.right());
+                    last.x = last.x.min(r.x);
+                    last.width = right - last.x;
+                }
+                _ => result.push(r),
+            }
+        }
+        result
+    }
+
+    /// Ids of embedded elements
+    pub fn get_embeds(&self) -> Vec<u32> {
+        let mut result = Vec::new();
+        for ln in &self.lines {
+            for unit in &ln.units {
+                if let TextElement::Embed(embed) = unit {
+                    result.push(embed.element);
+                }
+            }
+        }
+        result
+    }
+
+    /// Update size of embedded element, lines containing it are rebuilt if size changed
+    pub fn set_embed_size(&mut self, element: u32, size: (f32, f32)) -> bool {
+        let mut changed = false;
+        for ln in &mut self.lines {
+            let mut line_changed = false;
+            for unit in &mut ln.units {
+                match unit {
+                    TextElement::Embed(embed) if embed.element == element && embed.size != size => {
+                        embed.size = size;
+                        line_changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            if line_changed {
+                ln.rebuild_paragraph(&self.params);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Bounds of embedded elements relative to the padding box
+    pub fn get_embed_bounds(&self) -> Vec<(u32, base::Rect)> {
+        let (padding_top, _, _, padding_left) = self.padding;
+        let mut result = Vec::new();
+        let mut top = padding_top;
+        for ln in &self.lines {
+            let mut col = 0;
+            for unit in &ln.units {
+                if let TextElement::Embed(embed) = unit {
+                    let bounds = ln
+                        .sk_paragraph
+                        .layout
+                        .as_ref()
+                        .and_then(|l| l.get_char_bounds(col));
+                    if let Some(b) = bounds {
+                        let rect = base::Rect::new(
+                            b.left + padding_left,
+                            b.top + top,
+                            b.width(),
+                            b.height(),
+                        );
+                        result.push((embed.element, rect));
+                    }
+                }
+                col += unit.atom_count();
+            }
+            top += ln.sk_paragraph.height();
+        }
+        result
+    }
+
     pub fn on_event(
         &mut self,
         event: &Event,
@@ -691,52 +1077,65 @@ impl TextBox {
         let p_color = paragraph_params.color;
         let mask_char = paragraph_params.mask_char;
         for u in units {
-            match u {
-                TextElement::Text(unit) => {
-                    let mut text_style = TextStyle::new();
-                    let unit_font_families = match &unit.font_families {
-                        Some(list) => {
-                            let list = list.iter().map(|it| FontFamily::new(it.as_str())).collect();
-                            FontFamilies::new(list)
-                        }
-                        None => FontFamilies::default(),
-                    };
-                    text_style.set_font_families(Some(unit_font_families));
-                    let font_size = unit.font_size.unwrap_or(paragraph_params.font_size);
-                    text_style.set_font_size(font_size);
-
-                    let weight = parse_optional_weight(unit.weight.as_ref())
-                        .unwrap_or(paragraph_params.font_weight);
-
-                    let unit_style =
-                        Self::parse_font_style(&unit.style, paragraph_params.font_style);
-                    let font_style =
-                        skia_safe::FontStyle::new(weight, Width::NORMAL, unit_style.to_slant());
-                    text_style.set_font_style(font_style);
-
-                    let decoration =
-                        parse_optional_text_decoration(unit.text_decoration_line.as_ref());
-                    text_style.set_decoration_type(decoration);
-
-                    let color = parse_optional_color_str(unit.color.as_ref()).unwrap_or(p_color);
-                    let mut paint = Paint::default();
-                    paint.set_color(color);
-                    text_style.set_foreground_paint(&paint);
+            let (unit, is_link, annotation) = match u {
+                TextElement::Text(unit) => (unit, false, None),
+                TextElement::Link(link) => (&link.unit, true, None),
+                TextElement::Ruby(ruby) => (&ruby.unit, false, Some(&ruby.annotation)),
+                TextElement::Image(image) => {
+                    pb.add_placeholder(image.to_placeholder());
+                    continue;
+                }
+                TextElement::Embed(embed) => {
+                    pb.add_placeholder(embed.to_placeholder());
+                    continue;
+                }
+            };
+            let mut text_style = TextStyle::new();
+            let unit_font_families = match &unit.font_families {
+                Some(list) => {
+                    let list = list.iter().map(|it| FontFamily::new(it.as_str())).collect();
+                    FontFamilies::new(list)
+                }
+                None => FontFamilies::default(),
+            };
+            text_style.set_font_families(Some(unit_font_families));
+            let font_size = unit.font_size.unwrap_or(paragraph_params.font_size);
+            text_style.set_font_size(font_size);
 
-                    if let Some(bg) = parse_optional_color_str(unit.background_color.as_ref()) {
-                        let mut bg_paint = Paint::default();
-                        bg_paint.set_color(bg);
-                        text_style.set_background_paint(&bg_paint);
-                    }
+            let weight =
+                parse_optional_weight(unit.weight.as_ref()).unwrap_or(paragraph_params.font_weight);
 
-                    pb.push_style(&text_style);
-                    if let Some(mc) = mask_char {
-                        let mask_str = mc.to_string().repeat(unit.text.chars_count());
-                        pb.add_text(&mask_str);
-                    } else {
-                        pb.add_text(&unit.text);
-                    }
-                }
+            let unit_style = Self::parse_font_style(&unit.style, paragraph_params.font_style);
+            let font_style =
+                skia_safe::FontStyle::new(weight, Width::NORMAL, unit_style.to_slant());
+            text_style.set_font_style(font_style);
+
+            let decoration = match &unit.text_decoration_line {
+                None if is_link => TextDecoration::UNDERLINE,
+                v => parse_optional_text_decoration(v.as_ref()),
+            };
+            text_style.set_decoration_type(decoration);
+
+            let default_color = if is_link { DEFAULT_LINK_COLOR } else { p_color };
+            let color = parse_optional_color_str(unit.color.as_ref()).unwrap_or(default_color);
+            let mut paint = Paint::default();
+            paint.set_color(color);
+            text_style.set_foreground_paint(&paint);
+
+            if let Some(bg) = parse_optional_color_str(unit.background_color.as_ref()) {
+                let mut bg_paint = Paint::default();
+                bg_paint.set_color(bg);
+                text_style.set_background_paint(&bg_paint);
+            }
+
+            pb.push_style(&text_style);
+            if let Some(mc) = mask_char {
+                let mask_str = mc.to_string().repeat(unit.text.chars_count());
+                pb.add_text(&mask_str);
+            } else if let Some(annotation) = annotation {
+                pb.add_ruby(&unit.text, annotation);
+            } else {
+                pb.add_text(&unit.text);
             }
         }
         pb.add_text(ZERO_WIDTH_WHITESPACE);
@@ -757,6 +1156,8 @@ impl TextBox {
             font_weight: Weight::NORMAL,
             font_style: FontStyle::Normal,
             mask_char: None,
+            ruby_size: DEFAULT_RUBY_SIZE,
+            ruby_position: RubyPosition::Over,
         };
 
         let mut selection_bg = Paint::default();
@@ -770,6 +1171,9 @@ impl TextBox {
             selecting_begin: None,
             selection_bg,
             selection_fg,
+            text_stroke: (0.0, Color::TRANSPARENT),
+            text_fill: TextFill::Auto,
+            line_clamp: None,
             width: f32::NAN,
             padding: (0.0, 0.0, 0.0, 0.0),
             caret: TextCoord(0, 0),
@@ -796,20 +1200,75 @@ impl TextBox {
         let selection_bg = self.selection_bg.clone();
         let selection_fg = self.selection_fg.clone();
 
+        let (stroke_width, stroke_color) = self.text_stroke;
+        let has_stroke = stroke_width > 0.0 && !stroke_color.is_transparent();
+        let fill_paint = match &self.text_fill {
+            TextFill::Auto => None,
+            TextFill::LinearGradient(gradient) => {
+                let (padding_top, padding_right, padding_bottom, padding_left) = self.padding;
+                let width = self.width.de_nan(self.max_intrinsic_width());
+                let fill_rect = Rect::from_xywh(
+                    0.0,
+                    0.0,
+                    width - padding_left - padding_right,
+                    self.height() - padding_top - padding_bottom,
+                );
+                gradient.to_shader(&fill_rect).map(|shader| {
+                    let mut paint = Paint::default();
+                    paint.set_shader(shader);
+                    paint.set_blend_mode(BlendMode::SrcIn);
+                    (fill_rect, paint)
+                })
+            }
+        };
+
+        let truncated = self.is_truncated();
+        let line_clamp = self.line_clamp;
+        let (_, padding_right, _, padding_left) = self.padding;
+        let content_width = (self.width - padding_left - padding_right).de_nan(f32::INFINITY);
+        let mut remaining_lines = line_clamp.map(|(n, _)| n).unwrap_or(usize::MAX);
+
         let mut line_painters = Vec::with_capacity(self.lines.len());
         for ln in &mut self.lines {
+            if remaining_lines == 0 {
+                break;
+            }
             let ln_row = consumed_rows;
             consumed_rows += 1;
 
-            let ln_height = ln.sk_paragraph.height();
+            let mut ln_height = ln.sk_paragraph.height();
+            let mut ln_layout = match ln.sk_paragraph.layout.clone() {
+                Some(layout) => layout,
+                None => {
+                    consumed_top += ln_height;
+                    remaining_lines -= 1;
+                    continue;
+                }
+            };
+            let mut fade_paint = None;
+            if ln_layout.line_count() < remaining_lines {
+                remaining_lines -= ln_layout.line_count();
+            } else {
+                if let (true, Some((_, mode))) = (truncated, line_clamp) {
+                    let ellipsis_width =
+                        (mode == TruncationMode::Ellipsis).then_some(content_width);
+                    let layout = ln_layout.truncate(remaining_lines, ellipsis_width);
+                    ln_height = layout.lines_height(remaining_lines);
+                    if mode == TruncationMode::Fade {
+                        fade_paint = Self::build_fade_paint(&layout, consumed_top);
+                    }
+                    ln_layout = Arc::new(layout);
+                }
+                remaining_lines = 0;
+            }
             let ln_top = consumed_top;
             consumed_top += ln_height;
             let ln_bottom = consumed_top;
             let atom_count = ln.atom_count();
-            let ln_layout = some_or_continue!(ln.sk_paragraph.layout.clone());
 
             let selection_bg = selection_bg.clone();
             let selection_fg = selection_fg.clone();
+            let fill_paint = fill_paint.clone();
             let ln_renderer = move |painter: &Painter| {
                 let clip_rect = painter.canvas.local_clip_bounds();
                 if let Some(cp) = clip_rect {
@@ -819,7 +1278,30 @@ impl TextBox {
                         return false;
                     }
                 }
-                ln_layout.paint(painter, (0.0, ln_top).into());
+                if fade_paint.is_some() {
+                    painter.canvas.save_layer(&SaveLayerRec::default());
+                }
+                if let Some((fill_rect, fill_paint)) = &fill_paint {
+                    // Replace colors of glyphs with the gradient
+                    painter.canvas.save_layer(&SaveLayerRec::default());
+                    ln_layout.paint(painter, (0.0, ln_top).into());
+                    painter.canvas.draw_rect(fill_rect, fill_paint);
+                    painter.canvas.restore();
+                } else {
+                    ln_layout.paint(painter, (0.0, ln_top).into());
+                }
+                if has_stroke {
+                    ln_layout.paint_stroke(
+                        painter,
+                        (0.0, ln_top).into(),
+                        stroke_width,
+                        stroke_color,
+                    );
+                }
+                if let Some((fade_rect, fade_paint)) = &fade_paint {
+                    painter.canvas.draw_rect(fade_rect, fade_paint);
+                    painter.canvas.restore();
+                }
 
                 if atom_count > 0 {
                     if !selection.is_empty() {
@@ -854,6 +1336,27 @@ impl TextBox {
         })
     }
 
+    /// Paint which fades out the end of the last line of truncated layout
+    fn build_fade_paint(layout: &TextLayout, top: f32) -> Option<(Rect, Paint)> {
+        let last_line = layout.line_count().checked_sub(1)?;
+        let line_top = top + layout.lines_height(last_line);
+        let line_bottom = top + layout.lines_height(last_line + 1);
+        let right = layout.line_width(last_line);
+        let left = f32::max(0.0, right - (line_bottom - line_top) * FADE_LENGTH_RATIO);
+        let shader = Shader::linear_gradient(
+            ((left, 0.0), (right, 0.0)),
+            [Color::BLACK, Color::TRANSPARENT].as_slice(),
+            None,
+            TileMode::Clamp,
+            None,
+            None,
+        )?;
+        let mut paint = Paint::default();
+        paint.set_shader(shader);
+        paint.set_blend_mode(BlendMode::DstIn);
+        Some((Rect::new(left, line_top, right, line_bottom), paint))
+    }
+
     pub fn set_layout_callback<F: FnMut() + 'static>(&mut self, callback: F) {
         self.layout_callback = Box::new(callback);
     }
@@ -931,6 +1434,8 @@ mod tests {
             font_style: FontStyle::Normal,
             text_wrap: Some(false),
             mask_char: None,
+            ruby_size: DEFAULT_RUBY_SIZE,
+            ruby_position: RubyPosition::Over,
         };
         let mut text = String::new();
         for _ in 0..200 {
@@ -27,6 +27,19 @@ impl Line {
         count
     }
 
+    /// Index of the unit containing the char at column, and the column where the unit starts
+    pub fn get_unit_at(&self, col: usize) -> Option<(usize, usize)> {
+        let mut start = 0;
+        for (index, u) in self.units.iter().enumerate() {
+            let end = start + u.atom_count();
+            if col < end {
+                return Some((index, start));
+            }
+            start = end;
+        }
+        None
+    }
+
     pub fn get_text(&self) -> String {
         let mut result = String::new();
         for u in &self.units {
@@ -1,3 +1,4 @@
+use crate::element::text::simple_text_paragraph::PlaceholderAlignment;
 use crate::text::TextDecoration;
 use skia_safe::font_style::Weight;
 use std::str::FromStr;
@@ -49,3 +50,12 @@ pub fn parse_text_decoration(value: &str) -> TextDecoration {
     }
     decoration
 }
+
+pub fn parse_optional_placeholder_alignment(value: Option<&String>) -> PlaceholderAlignment {
+    match value.map(|v| v.as_str()) {
+        Some("top") => PlaceholderAlignment::Top,
+        Some("middle") => PlaceholderAlignment::Middle,
+        Some("bottom") => PlaceholderAlignment::Bottom,
+        _ => PlaceholderAlignment::Baseline,
+    }
+}
@@ -2,6 +2,8 @@ use crate::js::js_event_loop::js_create_event_loop_proxy;
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::BTreeSet;
+use std::marker::PhantomData;
+use std::mem;
 use std::ops::Add;
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
@@ -61,6 +63,8 @@ impl Ord for TimeTask {
 struct Timer {
     next_task_id: u64,
     tasks: Arc<Mutex<BTreeSet<TimeTask>>>,
+    /// Frozen clock while timers are controlled by a [VirtualTime]
+    virtual_now: Arc<Mutex<Option<Instant>>>,
     sender: Sender<()>,
 }
 
@@ -114,6 +118,8 @@ impl Timer {
         let (sender, receiver) = channel();
         let tasks = Arc::new(Mutex::new(BTreeSet::<TimeTask>::new()));
         let tasks_arc = tasks.clone();
+        let virtual_now = Arc::new(Mutex::new(None));
+        let virtual_now_arc = virtual_now.clone();
         let js_event_loop_proxy = js_create_event_loop_proxy();
         #[cfg(not(emscripten_platform))]
         thread::spawn(move || {
@@ -122,7 +128,9 @@ impl Timer {
                 if let Ok(()) = receiver.recv_timeout(sleep_time) {
                     let new_sleep_time = match tasks_arc.lock().unwrap().first() {
                         None => Duration::from_millis(DEFAULT_SLEEP_TIME),
-                        Some(t) => t.next_execute_time.duration_since(get_now_time()),
+                        Some(t) => t
+                            .next_execute_time
+                            .duration_since(current_time(&virtual_now_arc)),
                     };
                     if !new_sleep_time.is_zero() {
                         sleep_time = new_sleep_time;
@@ -142,6 +150,7 @@ impl Timer {
         Self {
             next_task_id: 1,
             tasks,
+            virtual_now,
             sender,
         }
     }
@@ -193,6 +202,8 @@ pub fn set_timeout_nanos<F: FnOnce() + 'static>(callback: F, nanos: u64) -> Time
 
 #[cfg(not(target_os = "emscripten"))]
 pub fn set_interval<F: Fn() + 'static>(callback: F, interval: u64) -> TimerHandle {
+    // Zero interval would keep the task due forever
+    let interval = interval.max(1);
     let id = get_next_id();
     let next_execute_time = get_now_time().add(Duration::from_millis(interval));
     add_time_task(TimeTask {
@@ -242,36 +253,130 @@ fn remove_time_task(id: u64) {
 }
 
 fn check_task() {
-    let task = TIMER.with_borrow_mut(move |t| {
-        let now = get_now_time();
-        let mut tasks = t.tasks.lock().unwrap();
-        let execute = match tasks.first() {
-            None => false,
-            Some(task) => task.next_execute_time <= now,
-        };
-        if execute {
-            tasks.pop_first()
-        } else {
-            None
+    if let Some(task) = pop_due_task() {
+        run_task(task);
+    }
+    wakeup_sleep();
+}
+
+fn pop_due_task() -> Option<TimeTask> {
+    TIMER.with_borrow_mut(move |t| {
+        let now = current_time(&t.virtual_now);
+        pop_task(&mut t.tasks.lock().unwrap(), now, u64::MAX)
+    })
+}
+
+/// Remove the earliest task due at `deadline` whose id is less than `id_limit`
+fn pop_task(tasks: &mut BTreeSet<TimeTask>, deadline: Instant, id_limit: u64) -> Option<TimeTask> {
+    let mut skipped = Vec::new();
+    let mut found = None;
+    while let Some(task) = tasks.pop_first() {
+        if task.next_execute_time > deadline {
+            skipped.push(task);
+            break;
+        } else if task.id < id_limit {
+            found = Some(task);
+            break;
         }
-    });
-    if let Some(task) = task {
-        match task.task {
-            Task::Timeout(callback) => {
-                callback();
-            }
-            Task::Interval(interval, callback) => {
-                (&callback)();
-                let next_execute_time = get_now_time().add(Duration::from_millis(interval));
-                add_time_task(TimeTask {
-                    id: task.id,
-                    next_execute_time,
-                    task: Task::Interval(interval, callback),
-                });
+        skipped.push(task);
+    }
+    tasks.extend(skipped);
+    found
+}
+
+/// Move deadlines of tasks from frozen clock to real clock, keeping remaining delays
+fn rebase_tasks(tasks: &mut BTreeSet<TimeTask>, frozen: Instant, now: Instant) {
+    *tasks = mem::take(tasks)
+        .into_iter()
+        .map(|mut task| {
+            task.next_execute_time = now + task.next_execute_time.saturating_duration_since(frozen);
+            task
+        })
+        .collect();
+}
+
+fn run_task(task: TimeTask) {
+    match task.task {
+        Task::Timeout(callback) => {
+            callback();
+        }
+        Task::Interval(interval, callback) => {
+            (&callback)();
+            let next_execute_time = get_now_time().add(Duration::from_millis(interval));
+            add_time_task(TimeTask {
+                id: task.id,
+                next_execute_time,
+                task: Task::Interval(interval, callback),
+            });
+        }
+    }
+}
+
+/// Frozen clock of timers of current thread, which is advanced manually so that timers fire at
+/// the same points of replayed input regardless of replay speed. The real clock is resumed when
+/// it is dropped.
+pub struct VirtualTime {
+    // Timers are thread local
+    _thread_bound: PhantomData<*const ()>,
+}
+
+impl VirtualTime {
+    /// Freeze clock of timers at current time, returns None if it is frozen by others
+    pub fn freeze() -> Option<Self> {
+        let frozen = TIMER.with_borrow(|t| {
+            let mut now = t.virtual_now.lock().unwrap();
+            if now.is_some() {
+                return false;
             }
+            *now = Some(Instant::now());
+            true
+        });
+        wakeup_sleep();
+        frozen.then_some(Self {
+            _thread_bound: PhantomData,
+        })
+    }
+
+    /// Move clock forward and run timers due in order. Timers created while advancing wait for
+    /// the next advance, so timers rescheduling themselves with zero delay could not loop forever.
+    pub fn advance(&self, duration: Duration) {
+        let (target, id_limit) = TIMER.with_borrow(|t| {
+            let now = current_time(&t.virtual_now);
+            (now + duration, t.next_task_id)
+        });
+        loop {
+            let task =
+                TIMER.with_borrow(|t| pop_task(&mut t.tasks.lock().unwrap(), target, id_limit));
+            let Some(task) = task else {
+                break;
+            };
+            // Callbacks and rescheduled intervals see the time the task is due at
+            set_virtual_now(task.next_execute_time);
+            run_task(task);
         }
+        set_virtual_now(target);
+        wakeup_sleep();
     }
-    wakeup_sleep();
+}
+
+impl Drop for VirtualTime {
+    fn drop(&mut self) {
+        TIMER.with_borrow(|t| {
+            let frozen = t.virtual_now.lock().unwrap().take();
+            if let Some(frozen) = frozen {
+                rebase_tasks(&mut t.tasks.lock().unwrap(), frozen, Instant::now());
+            }
+        });
+        wakeup_sleep();
+    }
+}
+
+fn set_virtual_now(time: Instant) {
+    TIMER.with_borrow(|t| {
+        if let Some(now) = t.virtual_now.lock().unwrap().as_mut() {
+            *now = (*now).max(time);
+        }
+    })
 }
 
 fn wakeup_sleep() {
@@ -281,5 +386,50 @@ fn wakeup_sleep() {
 }
 
 fn get_now_time() -> Instant {
-    Instant::now()
+    TIMER.with_borrow(|t| current_time(&t.virtual_now))
+}
+
+fn current_time(virtual_now: &Mutex<Option<Instant>>) -> Instant {
+    virtual_now.lock().unwrap().unwrap_or_else(Instant::now)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timer::{pop_task, rebase_tasks, Task, TimeTask};
+    use std::collections::BTreeSet;
+    use std::time::{Duration, Instant};
+
+    fn task(id: u64, next_execute_time: Instant) -> TimeTask {
+        TimeTask {
+            id,
+            next_execute_time,
+            task: Task::Timeout(Box::new(|| {})),
+        }
+    }
+
+    #[test]
+    fn test_pop_task() {
+        let now = Instant::now();
+        let mut tasks = BTreeSet::new();
+        tasks.insert(task(3, now));
+        tasks.insert(task(1, now + Duration::from_millis(5)));
+        tasks.insert(task(2, now + Duration::from_millis(20)));
+        let deadline = now + Duration::from_millis(10);
+        assert_eq!(pop_task(&mut tasks, deadline, 3).unwrap().id, 1);
+        assert!(pop_task(&mut tasks, deadline, 3).is_none());
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(pop_task(&mut tasks, deadline, 4).unwrap().id, 3);
+    }
+
+    #[test]
+    fn test_rebase_tasks() {
+        let frozen = Instant::now();
+        let now = frozen + Duration::from_secs(10);
+        let mut tasks = BTreeSet::new();
+        tasks.insert(task(1, frozen - Duration::from_millis(5)));
+        tasks.insert(task(2, frozen + Duration::from_millis(5)));
+        rebase_tasks(&mut tasks, frozen, now);
+        let times: Vec<Instant> = tasks.iter().map(|t| t.next_execute_time).collect();
+        assert_eq!(times, vec![now, now + Duration::from_millis(5)]);
+    }
 }
@@ -4,6 +4,7 @@ use crate::element::label::Label;
 use crate::element::{Element, ElementBackend, ElementType};
 use crate::mrc::Mrc;
 use crate::timer::{set_timeout, TimerHandle};
+use crate::window::placement::{Alignment, Placement, PlacementOptions, Side};
 use crate::window::popup::Popup;
 use crate::window::WindowHandle;
 use std::ops::Deref;
@@ -16,10 +17,10 @@ pub struct Tooltip {
 impl Tooltip {
     pub fn new(window_handle: WindowHandle, text: String, target: Rect) -> Self {
         let mut container_el = Element::create(Container::create);
-        container_el.tag = "tooltip".to_string();
+        container_el.tag = "tooltip".into();
         container_el.set_element_type(ElementType::Widget);
         let mut el = Element::create(Label::create);
-        el.tag = "label".to_string();
+        el.tag = "label".into();
         el.set_element_type(ElementType::Widget);
         let label = el.get_backend_mut_as::<Label>();
         label.set_text(text);
@@ -31,7 +32,9 @@ impl Tooltip {
             set_timeout(
                 move || {
                     if let Ok(w) = window_handle.upgrade_mut() {
-                        let p = w.popup_ex(container_el, target, false);
+                        let placement = Placement::new(Side::Bottom, Alignment::Center);
+                        let options = PlacementOptions::with_placement(placement).with_offset(4.0);
+                        let p = w.popup_ex(container_el, target, false, options);
                         popup_holder.replace(p);
                     }
                 },
@@ -1,4 +1,5 @@
 use crate as deft;
+use crate::font::fallback::{get_fallback_fonts, set_fallback_fonts};
 use crate::js_deserialize;
 use deft_macros::js_func;
 use serde::{Deserialize, Serialize};
@@ -28,3 +29,14 @@ pub fn typeface_create(_name: String, _source: TypefaceSource) -> bool {
     })
      */
 }
+
+/// Families tried in order when chars are missing in the fonts of text
+#[js_func]
+pub fn typeface_get_fallback_fonts() -> Vec<String> {
+    get_fallback_fonts()
+}
+
+#[js_func]
+pub fn typeface_set_fallback_fonts(families: Vec<String>) {
+    set_fallback_fonts(families);
+}
@@ -0,0 +1,306 @@
+use crate::base::EventListener;
+use crate::element::label::Label;
+use crate::element::{Element, ElementBackend, ElementWeak};
+use crate::error::DeftError;
+use crate::ext::ext_window::WindowAttrs;
+use crate::window::{Window, WindowHandle};
+use anyhow::Error;
+use quick_js::JsValue;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Builder to construct element trees in rust
+///
+/// ```ignore
+/// let count = Observable::new(0);
+/// let body = ElementBuilder::new("body")?
+///     .child(
+///         ElementBuilder::new("label")?
+///             .bind(&count, |el, v| el.get_backend_mut_as::<Label>().set_text(v.to_string())),
+///     )
+///     .child(ElementBuilder::new("button")?.on(ClickEventListener::new({
+///         let count = count.clone();
+///         move |_, _| count.update(|v| *v += 1)
+///     })));
+/// create_window(WindowAttrs::default(), body)?;
+/// ```
+pub struct ElementBuilder {
+    element: Element,
+    style: HashMap<String, JsValue>,
+}
+
+impl ElementBuilder {
+    /// Create element by tag registered with `register_component`
+    pub fn new(tag: &str) -> Result<Self, Error> {
+        let element = Element::create_by_tag(tag.to_string(), JsValue::Undefined)?;
+        Ok(Self::from_element(element))
+    }
+
+    pub fn from_element(element: Element) -> Self {
+        Self {
+            element,
+            style: HashMap::new(),
+        }
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.set_class(class.to_string());
+        self
+    }
+
+    pub fn attr(mut self, key: &str, value: &str) -> Self {
+        self.element.set_attribute(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Set inline style, e.g. `style("padding", "4px")`
+    pub fn style(mut self, key: &str, value: &str) -> Self {
+        self.style
+            .insert(key.to_string(), JsValue::String(value.to_string()));
+        self
+    }
+
+    /// Set text of label
+    pub fn text(self, text: &str) -> Self {
+        let text = text.to_string();
+        self.with_backend(move |label: &mut Label| label.set_text(text))
+    }
+
+    /// Access backend of element, panic if backend type mismatched
+    pub fn with_backend<T: ElementBackend, F: FnOnce(&mut T)>(mut self, callback: F) -> Self {
+        callback(self.element.get_backend_mut_as::<T>());
+        self
+    }
+
+    pub fn child(mut self, child: ElementBuilder) -> Self {
+        self.element.add_child_view(child.build(), None);
+        self
+    }
+
+    pub fn children<I: IntoIterator<Item = ElementBuilder>>(mut self, children: I) -> Self {
+        for child in children {
+            self.element.add_child_view(child.build(), None);
+        }
+        self
+    }
+
+    pub fn on<T: 'static, H: EventListener<T, ElementWeak> + 'static>(
+        mut self,
+        listener: H,
+    ) -> Self {
+        self.element.register_event_listener(listener);
+        self
+    }
+
+    /// Call updater with current value and whenever the value of observable changed
+    pub fn bind<T: 'static, F: FnMut(&mut Element, &T) + 'static>(
+        mut self,
+        observable: &Observable<T>,
+        mut updater: F,
+    ) -> Self {
+        observable.with(|v| updater(&mut self.element, v));
+        let weak = self.element.as_weak();
+        observable.subscribe_while(move |v| match weak.upgrade() {
+            Ok(mut el) => {
+                updater(&mut el, v);
+                true
+            }
+            Err(_) => false,
+        });
+        self
+    }
+
+    pub fn build(mut self) -> Element {
+        if !self.style.is_empty() {
+            let style = std::mem::take(&mut self.style);
+            self.element.update_style(JsValue::Object(style), false);
+        }
+        self.element
+    }
+}
+
+/// Create a window with body built in rust
+pub fn create_window(
+    attrs: WindowAttrs,
+    body: ElementBuilder,
+) -> Result<WindowHandle, DeftError> {
+    let handle = Window::create(attrs)?;
+    handle.upgrade_mut()?.set_body(body.build())?;
+    Ok(handle)
+}
+
+struct ObservableData<T> {
+    /// Shared with notifying subscribers so that no borrow is held while they run
+    value: Rc<T>,
+    next_id: u32,
+    /// Subscriber is removed when returning false
+    subscribers: Vec<(u32, Box<dyn FnMut(&T) -> bool>)>,
+    /// Set while notifying, changes made by subscribers are notified after they return
+    notifying: bool,
+    changed: bool,
+    /// Subscribers unsubscribed while notifying
+    removed: Vec<u32>,
+}
+
+/// Typed state shared between rust views, subscribers are notified when value changed.
+/// Subscribers are free to read or change the value, changes are notified once the current
+/// notification finished.
+pub struct Observable<T> {
+    data: Rc<RefCell<ObservableData<T>>>,
+}
+
+impl<T> Clone for Observable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<T: 'static> Observable<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            data: Rc::new(RefCell::new(ObservableData {
+                value: Rc::new(value),
+                next_id: 1,
+                subscribers: Vec::new(),
+                notifying: false,
+                changed: false,
+                removed: Vec::new(),
+            })),
+        }
+    }
+
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.data.borrow().value.as_ref().clone()
+    }
+
+    pub fn with<R, F: FnOnce(&T) -> R>(&self, callback: F) -> R {
+        let value = self.data.borrow().value.clone();
+        callback(&value)
+    }
+
+    pub fn set(&self, value: T) {
+        self.data.borrow_mut().value = Rc::new(value);
+        self.notify();
+    }
+
+    /// Update value in place, it's cloned if subscribers are reading it
+    pub fn update<F: FnOnce(&mut T)>(&self, updater: F)
+    where
+        T: Clone,
+    {
+        updater(Rc::make_mut(&mut self.data.borrow_mut().value));
+        self.notify();
+    }
+
+    /// Return subscriber id
+    pub fn subscribe<F: FnMut(&T) + 'static>(&self, mut callback: F) -> u32 {
+        self.subscribe_while(move |v| {
+            callback(v);
+            true
+        })
+    }
+
+    pub fn unsubscribe(&self, id: u32) {
+        let mut data = self.data.borrow_mut();
+        data.subscribers.retain(|(sid, _)| *sid != id);
+        if data.notifying {
+            data.removed.push(id);
+        }
+    }
+
+    fn subscribe_while<F: FnMut(&T) -> bool + 'static>(&self, callback: F) -> u32 {
+        let mut data = self.data.borrow_mut();
+        let id = data.next_id;
+        data.next_id += 1;
+        data.subscribers.push((id, Box::new(callback)));
+        id
+    }
+
+    fn notify(&self) {
+        {
+            let mut data = self.data.borrow_mut();
+            if data.notifying {
+                data.changed = true;
+                return;
+            }
+            data.notifying = true;
+        }
+        loop {
+            // Subscribers are taken out so that they are able to subscribe or change the value
+            let (mut subscribers, value) = {
+                let mut data = self.data.borrow_mut();
+                data.changed = false;
+                (std::mem::take(&mut data.subscribers), data.value.clone())
+            };
+            let mut i = 0;
+            while i < subscribers.len() {
+                let removed = self.data.borrow().removed.contains(&subscribers[i].0);
+                if removed || !(subscribers[i].1)(&value) {
+                    subscribers.remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+            let mut data = self.data.borrow_mut();
+            let removed = std::mem::take(&mut data.removed);
+            subscribers.retain(|(id, _)| !removed.contains(id));
+            subscribers.append(&mut data.subscribers);
+            data.subscribers = subscribers;
+            if !data.changed {
+                data.notifying = false;
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::view::Observable;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_observable() {
+        let observable = Observable::new(1);
+        let sum = Rc::new(Cell::new(0));
+        let id = {
+            let sum = sum.clone();
+            observable.subscribe(move |v| sum.set(sum.get() + *v))
+        };
+        observable.set(2);
+        observable.update(|v| *v += 1);
+        assert_eq!(sum.get(), 5);
+        observable.unsubscribe(id);
+        observable.set(10);
+        assert_eq!(sum.get(), 5);
+        assert_eq!(observable.get(), 10);
+    }
+
+    #[test]
+    fn test_set_in_subscriber() {
+        let observable = Observable::new(0);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        {
+            let observable = observable.clone();
+            observable.clone().subscribe(move |v| {
+                if *v < 3 {
+                    observable.set(*v + 1);
+                }
+            });
+        }
+        {
+            let seen = seen.clone();
+            observable.subscribe(move |v| seen.borrow_mut().push(*v));
+        }
+        observable.set(1);
+        assert_eq!(observable.get(), 3);
+        assert_eq!(*seen.borrow(), vec![1, 2, 3]);
+    }
+}
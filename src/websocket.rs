@@ -1,4 +1,5 @@
 use crate::mrc::Mrc;
+use crate::network::connect_websocket;
 use crate::some_or_return;
 use anyhow::anyhow;
 use futures_util::stream::{SplitSink, SplitStream};
@@ -9,7 +10,7 @@ use std::io::ErrorKind;
 use std::ops::{Deref, DerefMut};
 use tokio::net::TcpStream;
 use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
 #[derive(Clone)]
 pub struct WebSocketManager {
@@ -58,7 +59,7 @@ impl WebSocketManagerInner {
     pub async fn create_connection(&mut self, url: &str) -> Result<i32, io::Error> {
         let id = self.next_client_id;
         self.next_client_id += 1;
-        let (socket, _) = connect_async(url)
+        let socket = connect_websocket(url)
             .await
             .map_err(|e| io::Error::new(ErrorKind::Other, e))?;
         let (writer, reader) = socket.split();
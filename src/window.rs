@@ -1,57 +1,104 @@
+pub mod effect;
+pub mod element_index;
+pub mod find;
+pub mod frame;
+pub mod gesture;
+pub mod input_injection;
+pub mod input_recording;
+pub mod kiosk;
 pub mod page;
+pub mod placement;
+pub mod pop_out;
 pub mod popup;
+pub mod resize;
+pub mod shortcut;
+pub mod snap;
 
 use crate as deft;
 use crate::app::{exit_app, AppEvent, InsetType};
 use crate::base::MouseEventType::{MouseClick, MouseUp};
 use crate::base::{
-    Callback, EventContext, EventHandler, EventListener, EventRegistration, JsValueContext,
-    MouseDetail, MouseEventType, Rect, ResultWaiter, Touch, TouchDetail,
+    Callback, EventContext, EventHandler, EventListener, EventListenerOptions, EventRegistration,
+    JsValueContext, MouseDetail, MouseEventType, Rect, ResultWaiter, Touch, TouchDetail,
 };
+use crate::batch;
 use crate::cursor::search_cursor;
 use crate::element::body::Body;
 use crate::element::util::get_tree_level;
-use crate::element::{Element, ElementBackend, ElementParent};
-use crate::error::{DeftError, DeftResult};
-use crate::event::{build_modifier, named_key_to_str, str_to_named_key, BlurEvent, ClickEvent, ClickEventListener, ContextMenuEvent, DragOverEvent, DragStartEvent, DropEvent, DroppedFileEvent, FocusEvent, FocusShiftEvent, HoveredFileEvent, KeyDownEvent, KeyEventDetail, KeyUpEvent, MouseDownEvent, MouseEnterEvent, MouseLeaveEvent, MouseMoveEvent, MouseUpEvent, MouseWheelEvent, PreeditEvent, TextInputEvent, TouchCancelEvent, TouchEndEvent, TouchMoveEvent, TouchStartEvent, WheelEvent, KEY_MOD_ALT, KEY_MOD_CTRL, KEY_MOD_META, KEY_MOD_SHIFT};
+use crate::element::{Element, ElementBackend, ElementParent, CSS_MANAGER};
+use crate::error::{DeftError, DeftResult, ErrorKind};
+use crate::event::{build_modifier, named_key_to_str, str_to_named_key, BlurEvent, ClickEvent, ClickEventListener, ContextMenuEvent, DragOverEvent, DragStartEvent, DropEvent, DroppedFileEvent, FocusEvent, FocusShiftEvent, FullscreenChangeEvent, GesturePanEvent, GestureRotateEvent, GestureZoomEvent, HoveredFileEvent, KeyDownEvent, KeyEventDetail, KeyUpEvent, MouseDownEvent, MouseEnterEvent, MouseLeaveEvent, MouseMoveEvent, MouseUpEvent, MouseWheelEvent, PointerLockChangeEvent, PreeditEvent, TextInputEvent, TouchCancelEvent, TouchEndEvent, TouchMoveEvent, TouchStartEvent, WheelEvent, KEY_MOD_ALT, KEY_MOD_CTRL, KEY_MOD_META, KEY_MOD_SHIFT};
 use crate::event_loop::run_with_event_loop;
 use crate::ext::ext_window::{
-    WindowAttrs, MODAL_TO_OWNERS, WINDOWS, WINDOW_TYPE_MENU, WINDOW_TYPE_NORMAL, WINIT_TO_WINDOW,
+    center_in_monitor, WindowAttrs, MODAL_TO_OWNERS, WINDOWS, WINDOW_TYPE_MENU,
+    WINDOW_TYPE_NORMAL, WINIT_TO_WINDOW,
 };
-use crate::frame_rate::FrameRateController;
+use crate::frame_rate::{effective_fps, target_fps, FrameRateController};
+use crate::headless::is_headless;
 use crate::js::{BorrowFromJs, FromJsValue, JsError};
 use crate::menu::{build_menu_elements, Menu};
 use crate::mrc::Mrc;
 use crate::paint::{PaintContext, Painter, RenderTree};
+use crate::performance;
 use crate::platform::support_multiple_windows;
+use crate::render::debug::{build_hud, is_hud_shown, RenderStats};
+use crate::render::dump::{
+    attach_layer_images, LayerRootDump, RenderTreeDump, RenderTreeDumpOptions,
+};
+use crate::render::paint_object::LayerPO;
 use crate::render::painter::ElementPainter;
 use crate::resource_table::ResourceTable;
+use crate::safe_mode::is_safe_mode;
 use crate::state::{State, StateManager, StateMutRef};
+use crate::style::app_region::AppRegion;
 use crate::style::length::LengthContext;
+use crate::style::media::{set_color_scheme, ColorScheme};
 use crate::style::style_vars::StyleVars;
-use crate::timer::{set_timeout_nanos, TimerHandle};
+use crate::stylesheet::refresh_windows_style;
+use crate::system_metrics::{get_system_metrics, refresh_system_metrics};
+use crate::timer::{set_timeout, set_timeout_nanos, TimerHandle};
 use crate::tooltip::Tooltip;
+use crate::window::effect::WindowEffect;
+use crate::window::element_index::ElementIndex;
+use crate::window::find::{FindOptions, FindResult, FindState};
+use crate::window::frame::FrameStyle;
+use crate::window::gesture::GestureRecognizer;
+use crate::window::input_injection::{InjectedInput, InjectedKey, InjectedModifiers};
+use crate::window::input_recording::{
+    parse_recording, record_window_event, InputRecorder, InputReplay, ReplayOptions,
+};
+use crate::window::kiosk::{is_close_shortcut, Kiosk};
 use crate::window::page::Page;
-use crate::window::popup::Popup;
+use crate::window::placement::{compute_position, PlacementOptions, PlacementResult};
+use crate::window::popup::{update_placement_attribute, Popup};
+use crate::window::resize::EdgeResizing;
+use crate::window::shortcut::{
+    find_global_shortcut, js_shortcut_handler, parse_key_code, ShortcutRegistry,
+};
+use crate::window::snap::{snap_position, SnapEdges};
 use crate::{
     base, bind_js_event_listener, ok_or_return, send_app_event, show_focus_hint, some_or_return,
     warn_time,
 };
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use deft_macros::{js_methods, window_event};
 use log::{debug, error};
 use quick_js::{JsValue, ValueError};
-use skia_safe::{Color, Point};
-use skia_window::renderer::Renderer;
-use skia_window::skia_window::{RenderBackendType, SkiaWindow};
+use skia_safe::canvas::SaveLayerRec;
+use skia_safe::{color_filters, Color, EncodedImageFormat, Paint, Path, Point, RRect};
+use skia_window::headless;
+use skia_window::renderer::{Renderer, MAX_FRAMES_IN_FLIGHT};
+use skia_window::skia_window::{RenderBackendType, SkiaWindow, SurfaceColorSpace};
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::ops::Deref;
+use std::rc::Rc;
 use std::string::ToString;
-use std::time::SystemTime;
-use std::{env, mem};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use std::{env, fs, mem};
 use winit::dpi::Position::Logical;
-use winit::dpi::{LogicalPosition, LogicalSize, Size};
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition, Size};
 use winit::event::{
     ElementState, Ime, Modifiers, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
 };
@@ -61,15 +108,36 @@ use winit::platform::windows::WindowExtWindows;
 #[cfg(x11_platform)]
 use winit::platform::x11::WindowAttributesExtX11;
 use winit::window::{
-    Cursor, CursorIcon, Fullscreen, ResizeDirection, Theme, WindowAttributes, WindowButtons,
-    WindowId,
+    Cursor, CursorGrabMode, CursorIcon, Fullscreen, ResizeDirection, Theme, WindowAttributes,
+    WindowButtons, WindowId,
 };
 use crate::ext::ext_process::{EXIT_ON_ALL_WINDOWS_CLOSED};
+use crate::ext::ext_localstorage::localstorage;
+use crate::ext::ext_menu;
 
 thread_local! {
     static WIN_STATE_MANAGER: RefCell<StateManager> = RefCell::new(StateManager::new());
 }
 
+/// Zoom factors stepped through by zoom shortcuts
+const ZOOM_LEVELS: [f32; 15] = [
+    0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 3.0, 5.0,
+];
+/// Key of localstorage in which zoom of windows is saved
+const ZOOM_STORAGE_KEY: &str = "deft.window.zoom";
+/// Luminance of 1.0 in scRGB
+const SCRGB_WHITE_NITS: f32 = 80.0;
+
+/// The next zoom level larger or smaller than current
+pub fn step_zoom(current: f32, zoom_in: bool) -> f32 {
+    let next = if zoom_in {
+        ZOOM_LEVELS.iter().find(|z| **z > current + f32::EPSILON)
+    } else {
+        ZOOM_LEVELS.iter().rev().find(|z| **z < current - f32::EPSILON)
+    };
+    next.copied().unwrap_or(current)
+}
+
 #[derive(Clone)]
 struct MouseDownInfo {
     button_enum: MouseButton,
@@ -85,6 +153,9 @@ struct TouchingInfo {
     start_point: (f32, f32),
     scrolled: bool,
     touches: HashMap<u64, Touch>,
+    gesture: GestureRecognizer,
+    /// Element under the center of fingers when gesture started
+    gesture_target: Option<Element>,
 }
 
 fn treat_mouse_as_touch() -> bool {
@@ -94,12 +165,56 @@ fn treat_mouse_as_touch() -> bool {
         != "0"
 }
 
+/// Return false on touch-only devices, i.e. `hover: none` of css media features
+pub fn is_hover_supported() -> bool {
+    !cfg!(any(target_os = "android", target_os = "ios")) && !treat_mouse_as_touch()
+}
+
+/// The element and its ancestors
+fn get_hover_path(element: &Element) -> Vec<Element> {
+    let mut path = vec![element.clone()];
+    let mut e = element.clone();
+    while let Some(p) = e.get_parent() {
+        path.push(p.clone());
+        e = p;
+    }
+    path
+}
+
+fn is_no_resize_region(element: &Element) -> bool {
+    if element.style.app_region == AppRegion::NoResize {
+        return true;
+    }
+    match element.get_parent() {
+        Some(p) => is_no_resize_region(&p),
+        None => false,
+    }
+}
+
 #[derive(PartialEq)]
 pub enum WindowType {
     Normal,
     Menu,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResizeEdges {
+    All,
+    Corners,
+    None,
+}
+
+impl ResizeEdges {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "all" => Some(Self::All),
+            "corners" => Some(Self::Corners),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LayerRoot {
     body: Element,
@@ -107,6 +222,15 @@ pub struct LayerRoot {
     y: f32,
     focusing: Element,
     focusable: bool,
+    anchor: Option<LayerAnchor>,
+}
+
+/// Layer positioned next to a target rect
+#[derive(Clone, Debug)]
+struct LayerAnchor {
+    target: base::Rect,
+    options: PlacementOptions,
+    placement: Rc<Cell<Option<PlacementResult>>>,
 }
 
 impl LayerRoot {
@@ -118,6 +242,7 @@ impl LayerRoot {
             y,
             focusing,
             focusable: true,
+            anchor: None,
         }
     }
 
@@ -129,6 +254,7 @@ impl LayerRoot {
             y,
             focusing,
             focusable: false,
+            anchor: None,
         }
     }
 }
@@ -146,30 +272,97 @@ pub struct Window {
     /// (element, button)
     pressing: Option<(Element, MouseDownInfo)>,
     drag_window_called: bool,
+    /// Whether window is being dragged by `drag`, it ends on next mouse input
+    moving: bool,
+    /// Distance in logical pixels within which dragged window snaps to edges, 0 to disable
+    snap_threshold: f32,
+    snap_edges: SnapEdges,
     touching: TouchingInfo,
     dragging: bool,
     last_drag_over: Option<Element>,
     hover: Option<Element>,
+    find_state: FindState,
+    element_index: ElementIndex,
+    shortcuts: ShortcutRegistry,
     modifiers: Modifiers,
+    /// Modifier keys held by injected input
+    injected_modifiers: InjectedModifiers,
+    input_recorder: Option<InputRecorder>,
+    input_replay: Option<InputReplay>,
     dirty: bool,
     layout_dirty_list: HashMap<u32, Element>,
+    /// Elements scrolled since the last frame
+    scroll_dirty_list: HashMap<u32, Element>,
+    /// Whether anything other than scroll offsets changed since the last frame, so that render
+    /// trees must be rebuilt
+    render_tree_dirty: bool,
     repaint_timer_handle: Option<TimerHandle>,
     event_registration: EventRegistration<WindowHandle>,
     attributes: WindowAttributes,
     init_width: Option<f32>,
     init_height: Option<f32>,
     background_color: Color,
-    pub renderer_idle: bool,
+    frame_style: Option<FrameStyle>,
+    /// Radius of corners clipped when painting if the platform can't round corners of window
+    corner_clip_radius: f32,
+    effect: Option<WindowEffect>,
+    /// Shape of window in logical pixels, pixels out of it are cleared when painting
+    shape: Option<Path>,
+    /// Frames submitted to renderer but not presented yet
+    pub frames_in_flight: usize,
     next_frame_callbacks: Vec<Callback>,
     next_paint_callbacks: Vec<Callback>,
     pub render_tree: HashMap<Element, RenderTree>,
     pub style_vars: StyleVars,
     frame_rate_controller: FrameRateController,
+    /// Refresh rate of the monitor the window is on
+    refresh_rate: Option<f32>,
+    /// Frame rate cap, None to follow refresh rate of monitor
+    max_fps: Option<f32>,
+    /// Frame rate while window is not focused, None if not throttled
+    unfocused_fps: Option<f32>,
+    focused: bool,
+    /// Stats of the last frame, updated by renderer
+    render_stats: Arc<Mutex<RenderStats>>,
     next_frame_timer_handle: Option<TimerHandle>,
+    /// Receipt time of the earliest input not painted yet
+    unpainted_input_time: Option<Instant>,
     resource_table: ResourceTable,
     render_backend_types: Vec<RenderBackendType>,
+    color_space: SurfaceColorSpace,
+    /// Whether hdr surface is requested, it may not be supported by the renderer
+    hdr_enabled: bool,
+    /// Luminance of sdr white in nits on hdr surface
+    sdr_white_level: f32,
     /// (ElementId, Tooltip)
     tooltip_instance: Option<(u32, Tooltip)>,
+    /// Hit margin of edge resizing for undecorated window
+    resize_margin: f32,
+    resize_edges: ResizeEdges,
+    resizing: EdgeResizing,
+    /// Timer of ending edge resizing when no resize happens for a while
+    resize_settle_timer_handle: Option<TimerHandle>,
+    storage_namespace: String,
+    /// Scale of layout and rendering on top of scale factor of system
+    zoom: f32,
+    fullscreen_element: Option<FullscreenElement>,
+    /// Element receiving relative mouse motion while cursor is hidden and locked
+    pointer_lock: Option<Element>,
+    /// Timer of closing window forcibly when closing is deferred
+    close_timer_handle: Option<TimerHandle>,
+    /// Whether window is minimized or fully covered by other windows
+    occluded: bool,
+    /// Whether frames are skipped while window is occluded
+    pause_when_occluded: bool,
+    kiosk: Option<Kiosk>,
+}
+
+/// Element shown in fullscreen and where it is restored to
+struct FullscreenElement {
+    element: Element,
+    parent: Element,
+    position: u32,
+    page: Page,
 }
 
 #[derive(Clone, PartialEq)]
@@ -196,6 +389,20 @@ pub struct WindowResizeEvent {
     pub height: u32,
 }
 
+#[window_event]
+pub struct WindowResizeStartEvent;
+
+#[window_event]
+pub struct WindowResizeEndEvent;
+
+#[window_event]
+pub struct WindowStorageEvent {
+    pub namespace: String,
+    pub key: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
 #[window_event]
 pub struct WindowCloseEvent;
 
@@ -205,6 +412,50 @@ pub struct WindowFocusEvent;
 #[window_event]
 pub struct WindowBlurEvent;
 
+/// Soft keyboard shown, hidden or resized, height is in logical pixels
+#[window_event]
+pub struct WindowImeEvent {
+    pub visible: bool,
+    pub height: f32,
+}
+
+#[window_event]
+pub struct WindowZoomEvent {
+    pub zoom: f32,
+}
+
+/// Theme of system changed, `theme` is "light" or "dark" unless overridden by `DEFT_THEME`
+#[window_event]
+pub struct WindowThemeChangeEvent {
+    pub theme: String,
+}
+
+/// Window becomes occluded (minimized or fully covered) or visible again
+#[window_event]
+pub struct WindowVisibilityChangeEvent {
+    pub visible: bool,
+}
+
+/// Dragged window snapped to or pulled away from screen edges or other windows, edges of
+/// window which stick are empty if pulled away. Position is in logical pixels.
+#[window_event]
+pub struct WindowSnapEvent {
+    pub x: f32,
+    pub y: f32,
+    pub edges: Vec<String>,
+}
+
+/// All inputs of a replay are injected
+#[window_event]
+pub struct WindowInputReplayEndEvent;
+
+/// Renderer of window changed, e.g. fell back to software rendering after gpu context lost
+#[window_event]
+pub struct WindowRendererChangeEvent {
+    pub renderer: String,
+    pub reason: String,
+}
+
 impl BorrowFromJs for Window {
     fn borrow_from_js<R, F: FnOnce(&mut Self) -> R>(
         value: JsValue,
@@ -235,6 +486,10 @@ impl Window {
         ws.update_inset(InsetType::Ime, Rect::new_empty());
         ws.update_inset(InsetType::Navigation, Rect::new_empty());
         ws.update_inset(InsetType::StatusBar, Rect::new_empty());
+        ws.update_inset(InsetType::SafeLeft, Rect::new_empty());
+        ws.update_inset(InsetType::SafeRight, Rect::new_empty());
+        #[cfg(ios_platform)]
+        ws.update_safe_area_insets();
 
         let winit_window_id = ws.get_window_id();
         let wid = ws.get_id();
@@ -279,6 +534,28 @@ impl Window {
             attributes.enabled_buttons.remove(WindowButtons::MAXIMIZE);
         }
         attributes.decorations = attrs.decorations.unwrap_or(true);
+        let effect = match attrs.effect.as_deref().map(WindowEffect::parse) {
+            Some(Ok(effect)) => effect,
+            Some(Err(e)) => {
+                error!("{}", e);
+                None
+            }
+            None => None,
+        };
+        let shape = attrs.shape.as_deref().and_then(|s| {
+            let path = Path::from_svg(s);
+            if path.is_none() {
+                error!("invalid window shape: {}", s);
+            }
+            path
+        });
+        let transparent =
+            attrs.transparent.unwrap_or(false) || effect.is_some() || shape.is_some();
+        attributes.transparent = transparent;
+        let frame_style = FrameStyle::from_attrs(&attrs);
+        if let Some(style) = &frame_style {
+            attributes = style.apply_to_attributes(attributes);
+        }
         #[cfg(desktop_platform)]
         if attrs.width.is_some() || attrs.height.is_some() {
             let (default_width, default_height) = if attributes.resizable {
@@ -332,9 +609,31 @@ impl Window {
             &env::var("DEFT_RENDERERS").unwrap_or("".to_string()),
         );
         let render_backend_types = RenderBackendType::merge(&user_pf_backends, &env_pf_backends);
-        let render_backend_types =
+        let mut render_backend_types =
             RenderBackendType::merge(&render_backend_types, &RenderBackendType::all());
-        let window = Self::create_window(attributes.clone(), &render_backend_types);
+        if is_safe_mode() {
+            render_backend_types.retain(|bt| bt.is_software());
+        }
+        if is_headless() {
+            render_backend_types = vec![RenderBackendType::Headless];
+        }
+        let resize_edges = attrs
+            .resize_edges
+            .as_ref()
+            .and_then(|e| ResizeEdges::parse(e))
+            .unwrap_or(ResizeEdges::All);
+        let color_space = attrs
+            .color_space
+            .clone()
+            .or_else(|| env::var("DEFT_COLOR_SPACE").ok())
+            .and_then(|cs| SurfaceColorSpace::from_str(&cs))
+            .unwrap_or_default();
+        let window = Self::create_window(
+            attributes.clone(),
+            &render_backend_types,
+            color_space,
+            false,
+        );
         // window.set_ime_allowed(true);
         let body = Element::create(Body::create);
         let mut render_tree = HashMap::new();
@@ -353,7 +652,13 @@ impl Window {
                 pressing: None,
                 focusing: None,
                 hover: None,
+                find_state: FindState::default(),
+                element_index: ElementIndex::new(),
+                shortcuts: ShortcutRegistry::new(),
                 modifiers: Modifiers::default(),
+                injected_modifiers: InjectedModifiers::default(),
+                input_recorder: None,
+                input_replay: None,
                 dirty: false,
                 dragging: false,
                 last_drag_over: None,
@@ -366,26 +671,66 @@ impl Window {
                     touches: Default::default(),
                     scrolled: false,
                     start_point: (0.0, 0.0),
+                    gesture: GestureRecognizer::default(),
+                    gesture_target: None,
                 },
                 window_type,
                 init_width: attrs.width,
                 init_height: attrs.height,
-                background_color: Color::from_rgb(0, 0, 0),
+                background_color: if transparent {
+                    Color::TRANSPARENT
+                } else {
+                    Color::from_rgb(0, 0, 0)
+                },
+                frame_style,
+                corner_clip_radius: 0.0,
+                effect,
+                shape,
                 repaint_timer_handle: None,
-                renderer_idle: true,
+                frames_in_flight: 0,
                 next_frame_callbacks: Vec::new(),
                 next_paint_callbacks: Vec::new(),
                 render_tree,
-                style_vars: StyleVars::new(),
+                style_vars: get_system_metrics().to_style_vars(),
                 frame_rate_controller: FrameRateController::new(),
+                refresh_rate: None,
+                max_fps: attrs.max_fps.filter(|fps| *fps > 0.0),
+                unfocused_fps: attrs.unfocused_fps.filter(|fps| *fps > 0.0),
+                focused: true,
+                render_stats: Arc::new(Mutex::new(RenderStats::default())),
                 next_frame_timer_handle: None,
+                unpainted_input_time: None,
                 resource_table: ResourceTable::new(),
                 drag_window_called: false,
+                moving: false,
+                snap_threshold: attrs.snap_threshold.unwrap_or(0.0).max(0.0),
+                snap_edges: SnapEdges::default(),
                 render_backend_types,
+                color_space,
+                hdr_enabled: false,
+                sdr_white_level: SCRGB_WHITE_NITS,
                 layout_dirty_list: HashMap::new(),
+                scroll_dirty_list: HashMap::new(),
+                render_tree_dirty: true,
                 pages: Vec::new(),
                 tooltip_instance: None,
+                resize_margin: attrs.resize_margin.unwrap_or(3.0),
+                resize_edges,
+                resizing: EdgeResizing::default(),
+                resize_settle_timer_handle: None,
+                storage_namespace: attrs.storage_namespace.clone().unwrap_or_default(),
+                zoom: 1.0,
+                fullscreen_element: None,
+                pointer_lock: None,
+                close_timer_handle: None,
+                occluded: false,
+                pause_when_occluded: true,
+                kiosk: None,
             };
+            win_info.zoom = win_info.load_zoom();
+            win_info.update_refresh_rate();
+            win_info.apply_frame_style();
+            win_info.apply_effect();
             win_info.on_resize();
             wsm.new_state(win_info)
         });
@@ -397,36 +742,301 @@ impl Window {
         handle
     }
 
+    /// Apply shadow and corners of undecorated window to the native window
+    fn apply_frame_style(&mut self) {
+        let style = some_or_return!(self.frame_style);
+        self.corner_clip_radius = match style.apply(&self.window) {
+            Ok(true) => 0.0,
+            Ok(false) => style.corner_radius,
+            Err(e) => {
+                error!("failed to apply frame style: {}", e);
+                style.corner_radius
+            }
+        };
+    }
+
+    /// Apply effect specified by attributes to the native window
+    fn apply_effect(&self) {
+        let effect = some_or_return!(self.effect);
+        if let Err(e) = WindowEffect::apply(Some(effect), &self.window) {
+            error!("failed to apply window effect: {}", e);
+        }
+    }
+
+    /// Set material drawn behind the window, one of "blur", "acrylic", "mica", "vibrancy" and
+    /// "none". Only windows created with transparent, effect or shape attributes support effects.
+    #[js_func]
+    pub fn set_effect(&mut self, effect: String) -> Result<(), Error> {
+        let effect = WindowEffect::parse(&effect)?;
+        if effect.is_some() && !self.attributes.transparent {
+            let message = "effects require window to be created as transparent";
+            return Err(DeftError::new(ErrorKind::INVALID_STATE, message).into());
+        }
+        WindowEffect::apply(effect, &self.window)?;
+        self.effect = effect;
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn get_effect(&self) -> String {
+        self.effect.map(|e| e.name()).unwrap_or("none").to_string()
+    }
+
+    /// Set shape of window as svg path in logical pixels, pixels out of it are transparent.
+    /// Window is restored to rectangle if shape is null.
+    #[js_func]
+    pub fn set_shape(&mut self, shape: Option<String>) -> Result<(), DeftError> {
+        let shape = match shape {
+            Some(s) => Some(Path::from_svg(&s).ok_or_else(|| {
+                DeftError::new(
+                    ErrorKind::INVALID_PARAMETER,
+                    format!("invalid window shape: {}", s),
+                )
+            })?),
+            None => None,
+        };
+        if shape.is_some() && !self.attributes.transparent {
+            let message = "shapes require window to be created as transparent";
+            return Err(DeftError::new(ErrorKind::INVALID_STATE, message));
+        }
+        self.shape = shape;
+        self.notify_update();
+        Ok(())
+    }
+
     pub fn inner_position(&self) -> (f32, f32) {
         let pos = ok_or_return!(self.window.inner_position(), (0.0, 0.0));
         let pos = pos.to_logical(self.window.scale_factor());
         (pos.x, pos.y)
     }
 
+    /// Merge style variables, e.g. of system metrics, styles are resolved again
+    pub fn update_style_vars(&mut self, vars: StyleVars) {
+        self.style_vars.merge(vars);
+        for mut lr in self.layer_roots.clone() {
+            lr.body.mark_style_dirty();
+        }
+    }
+
     pub fn update_inset(&mut self, ty: InsetType, rect: Rect) {
-        let name = match ty {
-            InsetType::Ime => "deft-ime-height",
-            InsetType::StatusBar => "deft-status-height",
-            InsetType::Navigation => "deft-navigation-height",
+        let (name, size) = match ty {
+            InsetType::Ime => ("deft-ime-height", rect.height()),
+            InsetType::StatusBar => ("deft-status-height", rect.height()),
+            InsetType::Navigation => ("deft-navigation-height", rect.height()),
+            InsetType::SafeLeft => ("deft-safe-left-width", rect.width()),
+            InsetType::SafeRight => ("deft-safe-right-width", rect.width()),
         };
-        let height = rect.height() / self.window.scale_factor() as f32;
-        debug!("updating style variable: {} {}", name, height);
-        self.style_vars.set(name, &format!("{:.6}", height));
+        let size = size / self.scale_factor() as f32;
+        debug!("updating style variable: {} {}", name, size);
+        self.style_vars.set(name, &format!("{:.6}", size));
         for mut lr in self.layer_roots.clone() {
             lr.body.mark_style_dirty();
         }
+        if let InsetType::Ime = ty {
+            self.emit(WindowImeEvent {
+                visible: size > 0.0,
+                height: size,
+            });
+        }
+    }
+
+    /// Map safe area of ios to status bar, navigation and side insets. Winit reports safe area as
+    /// inner position and size, and the whole screen as outer position and size.
+    #[cfg(ios_platform)]
+    fn update_safe_area_insets(&mut self) {
+        let inner_pos = ok_or_return!(self.window.inner_position());
+        let outer_pos = ok_or_return!(self.window.outer_position());
+        let inner_size = self.window.inner_size();
+        let outer_size = self.window.outer_size();
+        let (outer_width, outer_height) = (outer_size.width as f32, outer_size.height as f32);
+        let top = (inner_pos.y - outer_pos.y).max(0) as f32;
+        let bottom = (outer_height - inner_size.height as f32 - top).max(0.0);
+        let left = (inner_pos.x - outer_pos.x).max(0) as f32;
+        let right = (outer_width - inner_size.width as f32 - left).max(0.0);
+        self.update_inset(InsetType::StatusBar, Rect::new(0.0, 0.0, outer_width, top));
+        self.update_inset(
+            InsetType::Navigation,
+            Rect::new(0.0, outer_height - bottom, outer_width, bottom),
+        );
+        self.update_inset(InsetType::SafeLeft, Rect::new(0.0, 0.0, left, outer_height));
+        self.update_inset(
+            InsetType::SafeRight,
+            Rect::new(outer_width - right, 0.0, right, outer_height),
+        );
+    }
+
+    /// Show soft keyboard for custom editing surfaces, no-op on desktop platforms
+    #[js_func]
+    pub fn show_soft_keyboard(&mut self) {
+        #[cfg(target_os = "ios")]
+        self.window.set_ime_allowed(true);
+        send_app_event(AppEvent::ShowSoftInput(self.get_id())).unwrap();
+    }
+
+    #[js_func]
+    pub fn hide_soft_keyboard(&mut self) {
+        #[cfg(target_os = "ios")]
+        self.window.set_ime_allowed(false);
+        send_app_event(AppEvent::HideSoftInput(self.get_id())).unwrap();
     }
 
     pub fn resume(&mut self) {
-        self.window = Self::create_window(self.attributes.clone(), &self.render_backend_types);
+        self.window = Self::create_window(
+            self.attributes.clone(),
+            &self.render_backend_types,
+            self.color_space,
+            self.hdr_enabled,
+        );
+        self.apply_frame_style();
+        self.apply_effect();
         #[cfg(ohos)]
         crate::platform::resume_ime();
     }
 
+    /// Name of render backend, e.g. `gl` or `softbuffer`
+    #[js_func]
+    pub fn get_renderer(&self) -> String {
+        self.window.backend_type().name().to_string()
+    }
+
+    /// Color space of window surface, e.g. "display-p3" for wide gamut monitors
+    #[js_func]
+    pub fn set_color_space(&mut self, color_space: String) -> Result<(), JsError> {
+        let color_space = SurfaceColorSpace::from_str(&color_space)
+            .ok_or_else(|| JsError::new(format!("unknown color space:{}", color_space)))?;
+        if color_space != self.color_space {
+            self.color_space = color_space;
+            self.window.set_color_space(color_space);
+            for lr in self.layer_roots.clone() {
+                self.invalid_layout(lr.body);
+            }
+        }
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn get_color_space(&self) -> String {
+        self.color_space.name().to_string()
+    }
+
+    /// Recreate native window with software renderer since gpu context could not be restored
+    fn fallback_to_software_renderer(&mut self) {
+        let backend_types: Vec<RenderBackendType> = self
+            .render_backend_types
+            .iter()
+            .filter(|bt| bt.is_software())
+            .cloned()
+            .collect();
+        if backend_types.is_empty() {
+            error!("gpu context lost and no software renderer available");
+            return;
+        }
+        self.render_backend_types = backend_types;
+        self.recreate_window();
+        self.emit(WindowRendererChangeEvent {
+            renderer: self.get_renderer(),
+            reason: "contextlost".to_string(),
+        });
+    }
+
+    /// Replace native window with a new one of same size and position, e.g. to change renderer
+    fn recreate_window(&mut self) {
+        let old_window_id = self.get_window_id();
+        let mut attributes = self
+            .attributes
+            .clone()
+            .with_title(self.window.title())
+            .with_inner_size(self.window.inner_size());
+        if let Ok(position) = self.window.outer_position() {
+            attributes = attributes.with_position(position);
+        }
+        attributes.visible = self.window.is_visible().unwrap_or(attributes.visible);
+        self.window = Self::create_window(
+            attributes,
+            &self.render_backend_types,
+            self.color_space,
+            self.hdr_enabled,
+        );
+        self.apply_frame_style();
+        self.apply_effect();
+        if let Some(kiosk) = &self.kiosk {
+            kiosk.apply(&self.window);
+        }
+
+        let window_id = self.get_window_id();
+        for map in [&WINIT_TO_WINDOW, &MODAL_TO_OWNERS] {
+            map.with_borrow_mut(|m| {
+                if let Some(handle) = m.remove(&old_window_id) {
+                    m.insert(window_id, handle);
+                }
+            });
+        }
+        for lr in self.layer_roots.clone() {
+            self.invalid_layout(lr.body);
+        }
+    }
+
+    /// Request hdr surface for viewing hdr images or videos, sdr content is drawn at
+    /// sdr white level. Returns whether hdr is active, which requires gl renderer on
+    /// windows or macos.
+    #[js_func]
+    pub fn set_hdr_enabled(&mut self, enabled: bool) -> bool {
+        if enabled != self.hdr_enabled {
+            self.hdr_enabled = enabled;
+            if enabled != self.window.is_hdr() {
+                let renderer = self.get_renderer();
+                self.recreate_window();
+                if renderer != self.get_renderer() {
+                    self.emit(WindowRendererChangeEvent {
+                        renderer: self.get_renderer(),
+                        reason: "hdr".to_string(),
+                    });
+                }
+            }
+        }
+        self.window.is_hdr()
+    }
+
+    #[js_func]
+    pub fn is_hdr(&self) -> bool {
+        self.window.is_hdr()
+    }
+
+    /// Luminance in nits which sdr white is mapped to on hdr surface, usually the
+    /// "sdr content brightness" of system display settings
+    #[js_func]
+    pub fn set_sdr_white_level(&mut self, nits: f32) {
+        let nits = nits.max(1.0);
+        if nits != self.sdr_white_level {
+            self.sdr_white_level = nits;
+            for lr in self.layer_roots.clone() {
+                self.invalid_layout(lr.body);
+            }
+        }
+    }
+
     pub fn notify_update(&mut self) {
+        self.render_tree_dirty = true;
+        self.request_update();
+    }
+
+    /// Move layers of scrolled element and repaint, render tree is rebuilt only if something else
+    /// changed too
+    pub fn invalid_scroll(&mut self, element: Element) {
+        let root = element.get_root_element();
+        if let Some(tree) = self.render_tree.get_mut(&root) {
+            tree.invalid_element(&element);
+        }
+        self.scroll_dirty_list.insert(element.get_eid(), element);
+        self.request_update();
+    }
+
+    fn request_update(&mut self) {
         if !self.dirty {
             self.dirty = true;
-            send_app_event(AppEvent::Update(self.get_id())).unwrap();
+            if !batch::defer_window_update(self.get_id()) {
+                send_app_event(AppEvent::Update(self.get_id())).unwrap();
+            }
         }
     }
 
@@ -458,33 +1068,49 @@ impl Window {
 
     #[js_func]
     pub fn close(&mut self) -> Result<(), JsError> {
-        let window_id = self.get_window_id();
         if self.allow_close() {
-            WINIT_TO_WINDOW.with_borrow_mut(|m| m.remove(&window_id));
-            #[allow(unused)]
-            if let Some(modal_parent) = MODAL_TO_OWNERS.with_borrow_mut(|m| m.remove(&window_id)) {
-                #[cfg(windows_platform)]
-                if let Ok(p) = modal_parent.upgrade_mut() {
-                    p.window.set_enable(true);
-                }
+            self.force_close()?;
+        }
+        Ok(())
+    }
+
+    /// Close window without emitting close event, e.g. after close animation finished
+    #[js_func]
+    pub fn force_close(&mut self) -> Result<(), JsError> {
+        let window_id = self.get_window_id();
+        self.close_timer_handle = None;
+        WINIT_TO_WINDOW.with_borrow_mut(|m| m.remove(&window_id));
+        #[allow(unused)]
+        if let Some(modal_parent) = MODAL_TO_OWNERS.with_borrow_mut(|m| m.remove(&window_id)) {
+            #[cfg(windows_platform)]
+            if let Ok(p) = modal_parent.upgrade_mut() {
+                p.window.set_enable(true);
             }
-            WINDOWS.with_borrow_mut(|m| {
-                m.remove(&self.get_id());
-                if m.is_empty() && EXIT_ON_ALL_WINDOWS_CLOSED.get() {
-                    let _ = exit_app(0);
-                }
-            });
-            WIN_STATE_MANAGER.with_borrow_mut(|m| {
-                m.remove_state(&self.handle.state);
-            });
-            self.window.set_visible(false);
         }
+        performance::remove_frame_stats(self.get_id());
+        ext_menu::remove_window_menu(self);
+        for lr in &self.layer_roots {
+            lr.body.get_children().iter_mut().for_each(|c| c.release_js_context());
+        }
+        WINDOWS.with_borrow_mut(|m| {
+            m.remove(&self.get_id());
+            if m.is_empty() && EXIT_ON_ALL_WINDOWS_CLOSED.get() {
+                let _ = exit_app(0);
+            }
+        });
+        WIN_STATE_MANAGER.with_borrow_mut(|m| {
+            m.remove_state(&self.handle.state);
+        });
+        self.window.set_visible(false);
         Ok(())
     }
 
+    /// Windows are kept hidden in headless mode
     #[js_func]
     pub fn set_visible(&mut self, visible: bool) -> Result<(), JsError> {
-        self.window.set_visible(visible);
+        if !is_headless() {
+            self.window.set_visible(visible);
+        }
         Ok(())
     }
 
@@ -501,7 +1127,9 @@ impl Window {
 
     #[js_func]
     fn exit_fullscreen(&mut self) {
-        self.window.set_fullscreen(None);
+        if self.kiosk.is_none() {
+            self.window.set_fullscreen(None);
+        }
     }
 
     #[js_func]
@@ -509,9 +1137,74 @@ impl Window {
         self.window.fullscreen().is_some()
     }
 
+    /// Kiosk mode shows window in fullscreen on top of other windows, confines cursor in it and
+    /// ignores closing by users. Kiosk mode entered with passcode could only be exited with the
+    /// same passcode.
+    #[js_func]
+    pub fn set_kiosk(&mut self, enabled: bool, passcode: Option<String>) -> Result<(), JsError> {
+        if enabled {
+            if self.kiosk.is_none() {
+                self.kiosk = Some(Kiosk::enter(&self.window, passcode));
+            }
+        } else if let Some(kiosk) = self.kiosk.take() {
+            if !kiosk.check_passcode(passcode.as_deref()) {
+                self.kiosk = Some(kiosk);
+                return Err(JsError::from_str("invalid kiosk passcode"));
+            }
+            kiosk.exit(&self.window);
+        }
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn is_kiosk(&self) -> bool {
+        self.kiosk.is_some()
+    }
+
+    /// Close window requested by user, e.g. by clicking close button
+    pub fn handle_close_request(&mut self) {
+        if self.kiosk.is_some() {
+            debug!("closing is disabled in kiosk mode");
+            return;
+        }
+        let _ = self.close();
+    }
+
+    /// Stats of the last painted frame
+    #[js_func]
+    pub fn get_render_stats(&self) -> RenderStats {
+        self.render_stats.lock().map(|s| *s).unwrap_or_default()
+    }
+
+    /// Highlight text matching query in the window and scroll the first match into view,
+    /// options: { caseSensitive?: boolean }
+    #[js_func]
+    pub fn find_in_page(&mut self, query: String, options: JsValue) -> FindResult {
+        let options = FindOptions::from_js_value(&options);
+        let roots: Vec<Element> = self.layer_roots.iter().map(|r| r.body.clone()).collect();
+        self.find_state.find(&roots, &query, &options)
+    }
+
+    #[js_func]
+    pub fn find_next(&mut self) -> FindResult {
+        self.find_state.navigate(false)
+    }
+
+    #[js_func]
+    pub fn find_previous(&mut self) -> FindResult {
+        self.find_state.navigate(true)
+    }
+
+    #[js_func]
+    pub fn clear_find(&mut self) {
+        self.find_state.clear();
+    }
+
     #[js_func]
     fn drag(&mut self) {
         self.drag_window_called = true;
+        self.moving = true;
+        self.snap_edges = SnapEdges::default();
         let _ = self.window.drag_window();
     }
 
@@ -581,13 +1274,47 @@ impl Window {
         }
     }
 
+    /// Move window to the center of monitor at index of `screen.getMonitors()`
     #[js_func]
-    pub fn popup(&self, content: Element, target: base::Rect) -> Popup {
-        Popup::new(content, target, &self.handle)
+    pub fn move_to_monitor(&mut self, index: usize) -> DeftResult<()> {
+        let monitor = self.window.available_monitors().nth(index).ok_or_else(|| {
+            DeftError::new(ErrorKind::NOT_FOUND, format!("monitor not found: {}", index))
+        })?;
+        let maximized = self.window.is_maximized();
+        if maximized {
+            self.window.set_maximized(false);
+        }
+        let position = center_in_monitor(
+            monitor.position(),
+            monitor.size(),
+            self.window.outer_size(),
+        );
+        self.window.set_outer_position(position);
+        if maximized {
+            self.window.set_maximized(true);
+        }
+        Ok(())
     }
 
-    pub fn popup_ex(&self, content: Element, target: base::Rect, focusable: bool) -> Popup {
-        Popup::new_ex(content, target, &self.handle, focusable)
+    #[js_func]
+    pub fn popup(
+        &self,
+        content: Element,
+        target: base::Rect,
+        options: Option<PlacementOptions>,
+    ) -> Popup {
+        let options = options.unwrap_or_default();
+        Popup::new_ex(content, target, &self.handle, true, options)
+    }
+
+    pub fn popup_ex(
+        &self,
+        content: Element,
+        target: base::Rect,
+        focusable: bool,
+        options: PlacementOptions,
+    ) -> Popup {
+        Popup::new_ex(content, target, &self.handle, focusable, options)
     }
 
     #[js_func]
@@ -603,13 +1330,67 @@ impl Window {
                 }
             }));
         }
-        let p = self.popup(el, target);
+        let p = self.popup(el, target, None);
         popup_holder.replace(p);
     }
 
+    /// Show menu as native menubar, remove the menubar if menu is null. The menubar is released
+    /// when window is closed. Not supported on linux, where windows are not created by gtk.
+    #[js_func]
+    pub fn set_menu(&self, menu: Option<ext_menu::Menu>) -> Result<(), Error> {
+        ext_menu::set_window_menu(self, menu)
+    }
+
+    /// Register shortcut like "Ctrl+Shift+P" handled before key events of elements, shortcuts
+    /// of window take precedence over global shortcuts
+    #[js_func]
+    pub fn register_shortcut(
+        &mut self,
+        accelerator: String,
+        handler: JsValue,
+    ) -> Result<u32, Error> {
+        self.shortcuts
+            .register(&accelerator, js_shortcut_handler(handler))
+    }
+
+    #[js_func]
+    pub fn unregister_shortcut(&mut self, id: u32) {
+        self.shortcuts.unregister(id);
+    }
+
+    #[js_func]
+    pub fn set_shortcut_enabled(&mut self, id: u32, enabled: bool) {
+        self.shortcuts.set_enabled(id, enabled);
+    }
+
+    pub fn shortcuts_mut(&mut self) -> &mut ShortcutRegistry {
+        &mut self.shortcuts
+    }
+
+    /// Emit close event, return false if closing is prevented or deferred
+    #[js_func]
     pub fn allow_close(&mut self) -> bool {
+        if self.close_timer_handle.is_some() {
+            // Closing is deferred already, e.g. close animation is running
+            return false;
+        }
         let ctx = self.emit(WindowCloseEvent);
-        !ctx.prevent_default
+        !ctx.prevent_default && self.close_timer_handle.is_none()
+    }
+
+    /// Called by close event listeners to defer closing until force_close is called,
+    /// the window is closed anyway after timeout
+    #[js_func]
+    pub fn defer_close(&mut self, timeout: u64) {
+        let handle = self.handle.clone();
+        self.close_timer_handle = Some(set_timeout(
+            move || {
+                if let Ok(mut w) = handle.upgrade_mut() {
+                    let _ = w.force_close();
+                }
+            },
+            timeout,
+        ));
     }
 
     pub fn handle_input(&mut self, content: &str) {
@@ -631,6 +1412,7 @@ impl Window {
         &mut self,
         modifiers: u32,
         scancode: Option<u32>,
+        code: Option<KeyCode>,
         named_key: Option<NamedKey>,
         key: Option<String>,
         key_str: Option<String>,
@@ -651,6 +1433,43 @@ impl Window {
             pressed,
         };
 
+        if detail.pressed
+            && detail.named_key == Some(NamedKey::Escape)
+            && self.find_state.is_active()
+        {
+            self.find_state.clear();
+        }
+        if detail.pressed
+            && detail.named_key == Some(NamedKey::Escape)
+            && self.pointer_lock.is_some()
+        {
+            self.exit_pointer_lock();
+            return;
+        }
+        if detail.pressed
+            && detail.named_key == Some(NamedKey::Escape)
+            && self.fullscreen_element.is_some()
+        {
+            self.exit_element_fullscreen();
+            return;
+        }
+        if detail.pressed {
+            // Match physical keys so that shortcuts work with any layout
+            let code = code.or_else(|| detail.key.as_deref().and_then(parse_key_code));
+            if let Some(code) = code {
+                if self.kiosk.is_some() && is_close_shortcut(modifiers, code) {
+                    return;
+                }
+                let handler = self
+                    .shortcuts
+                    .find(modifiers, code)
+                    .or_else(|| find_global_shortcut(modifiers, code));
+                if let Some(handler) = handler {
+                    handler();
+                    return;
+                }
+            }
+        }
         if let Some(focusing) = &self.focusing {
             if detail.pressed {
                 focusing.emit(KeyDownEvent(detail));
@@ -661,14 +1480,40 @@ impl Window {
     }
 
     pub fn handle_event(&mut self, event: WindowEvent) {
+        if matches!(
+            event,
+            WindowEvent::Ime(_)
+                | WindowEvent::KeyboardInput { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::Touch(_)
+        ) && self.unpainted_input_time.is_none()
+        {
+            self.unpainted_input_time = Some(Instant::now());
+        }
+        if let Some(recorder) = &mut self.input_recorder {
+            let cursor = (self.cursor_position.x as f32, self.cursor_position.y as f32);
+            let modifiers = build_modifier(&self.modifiers.state());
+            let scale_factor = self.window.scale_factor();
+            if let Some(input) = record_window_event(&event, cursor, scale_factor, modifiers) {
+                recorder.record(input);
+            }
+        }
         match event {
             WindowEvent::RedrawRequested => {
                 self.dirty = true;
+                self.render_tree_dirty = true;
                 self.update_force();
             }
             WindowEvent::Resized(_physical_size) => {
+                self.resizing.on_resized(Instant::now());
                 self.on_resize();
             }
+            WindowEvent::Moved(position) => {
+                self.snap_on_move(position);
+                // Window may be moved to a monitor of different refresh rate
+                self.update_refresh_rate();
+            }
             WindowEvent::ModifiersChanged(new_modifiers) => self.modifiers = new_modifiers,
             WindowEvent::Ime(ime) => match ime {
                 Ime::Enabled => {}
@@ -681,10 +1526,11 @@ impl Window {
                 Ime::Disabled => {}
             },
             WindowEvent::KeyboardInput { event, .. } => {
-                let scancode = match event.physical_key {
-                    PhysicalKey::Code(c) => get_scancode(c),
+                let code = match event.physical_key {
+                    PhysicalKey::Code(c) => Some(c),
                     PhysicalKey::Unidentified(_e) => None,
                 };
+                let scancode = code.and_then(get_scancode);
                 let key = match &event.logical_key {
                     Key::Named(n) => Some(named_key_to_str(n).to_string()),
                     Key::Character(c) => Some(c.as_str().to_string()),
@@ -718,14 +1564,20 @@ impl Window {
                 let repeat = event.repeat;
                 let pressed = event.state == ElementState::Pressed;
                 self.handle_key(
-                    modifiers, scancode, named_key, key, key_str, repeat, pressed,
+                    modifiers, scancode, code, named_key, key, key_str, repeat, pressed,
                 );
             }
             WindowEvent::MouseInput { button, state, .. } => {
+                self.moving = false;
                 self.window.commit_ime();
+                self.end_resizing();
                 if let Some((dir, _)) = self.get_resize_direction() {
                     if let Err(e) = self.window.drag_resize_window(dir) {
                         error!("Failed to drag resize window: {:?}", e);
+                    } else if state == ElementState::Pressed {
+                        self.resizing.begin(Instant::now());
+                        self.schedule_resize_settle();
+                        self.emit(WindowResizeStartEvent);
                     }
                     return;
                 }
@@ -752,7 +1604,14 @@ impl Window {
                     self.emit_click(button, state);
                 }
             }
+            // Pointer is grabbed by window manager while moving on some platforms
+            WindowEvent::CursorEntered { .. } => {
+                self.moving = false;
+            }
             WindowEvent::CursorLeft { .. } => {
+                if self.pressing.is_none() {
+                    self.mouse_leave_window();
+                }
                 if self.drag_window_called {
                     self.drag_window_called = false;
                     if let Some((_, m)) = &self.pressing {
@@ -766,9 +1625,11 @@ impl Window {
                 ..
             } => {
                 //debug!("cursor moved:{:?}", position);
-                self.cursor_position = position.to_logical(self.window.scale_factor());
+                self.cursor_position = position.to_logical(self.scale_factor());
                 self.cursor_root_position = root_position.to_logical(self.window.scale_factor());
-                if treat_mouse_as_touch() {
+                if self.pointer_lock.is_some() {
+                    // Motion is reported by device events while pointer is locked
+                } else if treat_mouse_as_touch() {
                     if !self.touching.touches.is_empty() {
                         self.emit_touch_event(
                             0,
@@ -793,13 +1654,31 @@ impl Window {
                 // debug!("delta:{:?}", delta);
             }
             WindowEvent::Touch(touch) => {
-                let loc = touch.location.to_logical(self.window.scale_factor());
+                let loc = touch.location.to_logical(self.scale_factor());
                 self.emit_touch_event(touch.id, touch.phase, loc.x, loc.y);
             }
+            WindowEvent::Occluded(occluded) => {
+                self.set_occluded(occluded);
+            }
+            WindowEvent::ThemeChanged(theme) => {
+                refresh_system_metrics();
+                let theme = Self::theme_name(Some(theme));
+                if Self::update_color_scheme(&theme) {
+                    refresh_windows_style();
+                }
+                for mut lr in self.layer_roots.clone() {
+                    lr.body.set_attribute("theme".to_string(), theme.clone());
+                    lr.body.mark_style_dirty();
+                }
+                self.emit(WindowThemeChangeEvent { theme });
+            }
             WindowEvent::Focused(focus) => {
+                self.focused = focus;
+                self.update_frame_rate();
                 if focus {
                     self.emit(WindowFocusEvent);
                 } else {
+                    self.exit_pointer_lock();
                     self.emit(WindowBlurEvent);
                 }
             }
@@ -845,15 +1724,29 @@ impl Window {
         &mut self,
         event_type: String,
         listener: JsValue,
+        options: Option<EventListenerOptions>,
     ) -> Result<u32, JsError> {
         let id = bind_js_event_listener!(
             self, event_type.as_str(), listener;
             "resize" => WindowResizeEventListener,
+            "resizestart" => WindowResizeStartEventListener,
+            "resizeend" => WindowResizeEndEventListener,
+            "storage" => WindowStorageEventListener,
             "close"  => WindowCloseEventListener,
             "focus"  => WindowFocusEventListener,
             "blur"   => WindowBlurEventListener,
+            "ime"    => WindowImeEventListener,
+            "zoom"   => WindowZoomEventListener,
+            "rendererchange" => WindowRendererChangeEventListener,
+            "visibilitychange" => WindowVisibilityChangeEventListener,
+            "themechange" => WindowThemeChangeEventListener,
+            "snap" => WindowSnapEventListener,
+            "inputreplayend" => WindowInputReplayEndEventListener,
         );
         let id = id.ok_or_else(|| JsError::new(format!("unknown event_type:{}", event_type)))?;
+        if let Some(options) = options {
+            self.event_registration.set_listener_options(id, options);
+        }
         Ok(id)
     }
 
@@ -879,11 +1772,194 @@ impl Window {
             .remove_event_listener(&event_type, id)
     }
 
-    pub fn on_element_removed(&mut self, _element: &Element) {
-        if let Some(f) = &self.focusing {
-            if f.get_window().is_none() {
-                let lr = self.get_focused_layer();
-                self.focus(lr.body.clone());
+    /// Feed synthetic input through the same handlers as real input, for automation drivers and
+    /// interactive tutorials
+    #[js_func]
+    pub fn inject_input(&mut self, input: InjectedInput) {
+        if self.unpainted_input_time.is_none() {
+            self.unpainted_input_time = Some(Instant::now());
+        }
+        match input {
+            InjectedInput::MouseMove(p) => self.inject_cursor_move(p.x, p.y),
+            InjectedInput::MouseDown(m) => {
+                if let Some((x, y)) = m.position() {
+                    self.inject_cursor_move(x, y);
+                }
+                self.inject_mouse_button(m.mouse_button(), ElementState::Pressed);
+            }
+            InjectedInput::MouseUp(m) => {
+                if let Some((x, y)) = m.position() {
+                    self.inject_cursor_move(x, y);
+                }
+                self.inject_mouse_button(m.mouse_button(), ElementState::Released);
+            }
+            InjectedInput::Click(m) => {
+                if let Some((x, y)) = m.position() {
+                    self.inject_cursor_move(x, y);
+                }
+                self.inject_mouse_button(m.mouse_button(), ElementState::Pressed);
+                self.inject_mouse_button(m.mouse_button(), ElementState::Released);
+            }
+            InjectedInput::Wheel(w) => {
+                if let (Some(x), Some(y)) = (w.x, w.y) {
+                    self.inject_cursor_move(x, y);
+                }
+                self.handle_mouse_wheel(w.delta_x, w.delta_y, w.delta_mode);
+            }
+            InjectedInput::KeyDown(k) => self.inject_key(k, true),
+            InjectedInput::KeyUp(k) => self.inject_key(k, false),
+            InjectedInput::Text(t) => self.handle_input(&t.text),
+            InjectedInput::Touch(t) => {
+                if let Some(phase) = t.touch_phase() {
+                    self.emit_touch_event(t.id, phase, t.x, t.y);
+                }
+            }
+        }
+    }
+
+    /// Record input events of window to file as json lines, which could be replayed by
+    /// `replay_input` to reproduce bugs
+    #[js_func]
+    pub fn start_input_recording(&mut self, path: String) -> Result<(), Error> {
+        self.input_recorder = Some(InputRecorder::create(&path)?);
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn stop_input_recording(&mut self) {
+        self.input_recorder = None;
+    }
+
+    /// Inject inputs recorded by `start_input_recording` with original intervals divided by
+    /// speed, inputreplayend event is emitted when finished
+    #[js_func]
+    pub fn replay_input(
+        &mut self,
+        path: String,
+        options: Option<ReplayOptions>,
+    ) -> Result<(), Error> {
+        let inputs = parse_recording(&fs::read_to_string(&path)?)?;
+        self.stop_input_replay();
+        if inputs.is_empty() {
+            self.emit(WindowInputReplayEndEvent);
+            return Ok(());
+        }
+        let options = options.unwrap_or_default();
+        let window_id = self.get_id();
+        let replay = InputReplay::start(inputs, &options, move |index| {
+            let handle = WINDOWS.with_borrow(|m| m.get(&window_id).cloned());
+            if let Some(mut w) = handle.and_then(|h| h.upgrade_mut().ok()) {
+                w.replay_input_step(index);
+            }
+        })?;
+        self.input_replay = Some(replay);
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn stop_input_replay(&mut self) {
+        self.input_replay = None;
+    }
+
+    fn replay_input_step(&mut self, index: usize) {
+        let replay = some_or_return!(&mut self.input_replay);
+        let input = some_or_return!(replay.take(index));
+        let last = replay.is_last(index);
+        self.inject_input(input);
+        if last {
+            self.stop_input_replay();
+            self.emit(WindowInputReplayEndEvent);
+        }
+    }
+
+    fn inject_cursor_move(&mut self, x: f32, y: f32) {
+        let (left, top) = self.inner_position();
+        self.cursor_position = LogicalPosition::new(x as f64, y as f64);
+        self.cursor_root_position = LogicalPosition::new((left + x) as f64, (top + y) as f64);
+        if treat_mouse_as_touch() {
+            if !self.touching.touches.is_empty() {
+                self.emit_touch_event(0, TouchPhase::Moved, x, y);
+            }
+        } else {
+            self.handle_cursor_moved();
+        }
+    }
+
+    fn inject_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        if treat_mouse_as_touch() {
+            let phase = match state {
+                ElementState::Pressed => TouchPhase::Started,
+                ElementState::Released => TouchPhase::Ended,
+            };
+            let (x, y) = (self.cursor_position.x as f32, self.cursor_position.y as f32);
+            self.emit_touch_event(0, phase, x, y);
+        } else {
+            self.emit_click(button, state);
+        }
+    }
+
+    fn inject_key(&mut self, key: InjectedKey, pressed: bool) {
+        let modifiers = self.injected_modifiers.apply(&key, pressed);
+        let named_key = key.named_key();
+        let key_str = match named_key {
+            Some(_) => None,
+            None => Some(key.key.clone()),
+        };
+        self.handle_key(
+            modifiers,
+            None,
+            None,
+            named_key,
+            Some(key.key),
+            key_str,
+            key.repeat,
+            pressed,
+        );
+    }
+
+    /// Index attached element and its descendants by `id` and `data-testid` attributes
+    pub fn on_element_attached(&mut self, element: &Element) {
+        self.element_index.add_recurse(element);
+    }
+
+    pub fn on_indexed_attribute_changed(
+        &mut self,
+        element: &Element,
+        key: &str,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+    ) {
+        self.element_index
+            .update_attribute(element, key, old_value, new_value);
+    }
+
+    /// Attached element whose `id` attribute is id
+    #[js_func]
+    pub fn get_element_by_id(&self, id: String) -> Option<Element> {
+        self.element_index.get_by_id(&id)
+    }
+
+    /// Attached element whose `data-testid` attribute is test_id
+    #[js_func]
+    pub fn get_element_by_test_id(&self, test_id: String) -> Option<Element> {
+        self.element_index.get_by_test_id(&test_id)
+    }
+
+    pub fn on_element_removed(&mut self, parent: &Element, element: &Element) {
+        self.element_index.remove_recurse(element);
+        if let Some(f) = &self.focusing {
+            if f.get_window().is_none() {
+                // Move focus to the nearest focus scope which the removed element belonged to
+                let mut p = Some(parent.clone());
+                while let Some(e) = p {
+                    if e.is_focus_scope() {
+                        self.focus(e);
+                        return;
+                    }
+                    p = e.get_parent();
+                }
+                let lr = self.get_focused_layer();
+                self.focus(lr.body.clone());
             }
         }
     }
@@ -998,20 +2074,24 @@ impl Window {
     }
 
     fn get_resize_direction(&self) -> Option<(ResizeDirection, CursorIcon)> {
-        if self.pressing.is_some() || self.window.is_decorated() {
+        if self.pressing.is_some()
+            || self.window.is_decorated()
+            || self.resize_edges == ResizeEdges::None
+        {
             return None;
         }
         let window_x = self.cursor_position.x as f32;
         let window_y = self.cursor_position.y as f32;
         let size = self.window.outer_size();
-        let scale_factor = self.window.scale_factor() as f32;
+        let scale_factor = self.scale_factor() as f32;
         let win_width = size.width as f32 / scale_factor;
         let win_height = size.height as f32 / scale_factor;
-        let delta = 3.0;
+        let delta = self.resize_margin;
         let is_left = window_x < delta;
         let is_right = window_x > win_width - delta;
         let is_top = window_y < delta;
         let is_bottom = window_y > win_height - delta;
+        let corners_only = self.resize_edges == ResizeEdges::Corners;
         let (dir, icon) = if is_left && is_top {
             (ResizeDirection::NorthWest, CursorIcon::NwResize)
         } else if is_left && is_bottom {
@@ -1020,6 +2100,8 @@ impl Window {
             (ResizeDirection::NorthEast, CursorIcon::NeResize)
         } else if is_right && is_bottom {
             (ResizeDirection::SouthEast, CursorIcon::SeResize)
+        } else if corners_only {
+            return None;
         } else if is_left {
             (ResizeDirection::West, CursorIcon::WResize)
         } else if is_top {
@@ -1031,9 +2113,135 @@ impl Window {
         } else {
             return None;
         };
+        if let Some((node, _, _)) = self.get_node_by_pos(window_x, window_y) {
+            if is_no_resize_region(&node) {
+                return None;
+            }
+        }
         Some((dir, icon))
     }
 
+    fn end_resizing(&mut self) {
+        self.resize_settle_timer_handle = None;
+        if self.resizing.end() {
+            self.emit(WindowResizeEndEvent);
+        }
+    }
+
+    /// End resizing once it settles, the timer is rescheduled while resizes keep happening
+    fn schedule_resize_settle(&mut self) {
+        let delay = some_or_return!(self.resizing.settle_delay(Instant::now()));
+        let handle = self.handle.clone();
+        self.resize_settle_timer_handle = Some(set_timeout_nanos(
+            move || {
+                if let Ok(mut w) = handle.upgrade_mut() {
+                    w.resize_settle_timer_handle = None;
+                    if w.resizing.end_if_settled(Instant::now()) {
+                        w.emit(WindowResizeEndEvent);
+                    } else {
+                        w.schedule_resize_settle();
+                    }
+                }
+            },
+            delay.as_nanos() as u64,
+        ));
+    }
+
+    #[js_func]
+    pub fn set_resize_margin(&mut self, margin: f32) {
+        self.resize_margin = margin.max(0.0);
+    }
+
+    /// Distance in logical pixels within which window dragged by `drag` snaps to screen edges
+    /// or other windows, 0 to disable
+    #[js_func]
+    pub fn set_snap_threshold(&mut self, threshold: f32) {
+        self.snap_threshold = threshold.max(0.0);
+    }
+
+    #[js_func]
+    pub fn get_snap_threshold(&self) -> f32 {
+        self.snap_threshold
+    }
+
+    fn snap_on_move(&mut self, position: PhysicalPosition<i32>) {
+        if !self.moving || self.snap_threshold <= 0.0 {
+            return;
+        }
+        let size = self.window.outer_size();
+        let bounds = base::Rect {
+            x: position.x as f32,
+            y: position.y as f32,
+            width: size.width as f32,
+            height: size.height as f32,
+        };
+        let screens = self
+            .window
+            .available_monitors()
+            .map(|m| {
+                let (p, s) = (m.position(), m.size());
+                base::Rect {
+                    x: p.x as f32,
+                    y: p.y as f32,
+                    width: s.width as f32,
+                    height: s.height as f32,
+                }
+            })
+            .collect::<Vec<_>>();
+        let windows = self.other_window_bounds();
+        let threshold = self.snap_threshold * self.window.scale_factor() as f32;
+        let result = snap_position(bounds, &screens, &windows, threshold);
+        if result.x != bounds.x || result.y != bounds.y {
+            // Causes another move event, which snaps to the same position
+            self.window.set_outer_position(PhysicalPosition::new(
+                result.x.round() as i32,
+                result.y.round() as i32,
+            ));
+        }
+        if result.edges != self.snap_edges {
+            self.snap_edges = result.edges;
+            let scale_factor = self.window.scale_factor() as f32;
+            self.emit(WindowSnapEvent {
+                x: result.x / scale_factor,
+                y: result.y / scale_factor,
+                edges: result.edges.names(),
+            });
+        }
+    }
+
+    /// Outer bounds of other visible windows of app in physical pixels
+    fn other_window_bounds(&self) -> Vec<base::Rect> {
+        let handles = WINDOWS.with_borrow(|m| m.values().cloned().collect::<Vec<_>>());
+        let mut result = Vec::new();
+        for handle in handles {
+            if handle == self.handle {
+                continue;
+            }
+            let Ok(window) = handle.upgrade_mut() else {
+                continue;
+            };
+            let visible = window.window.is_visible().unwrap_or(true)
+                && !window.window.is_minimized().unwrap_or(false);
+            if let (true, Ok(position)) = (visible, window.window.outer_position()) {
+                let size = window.window.outer_size();
+                result.push(base::Rect {
+                    x: position.x as f32,
+                    y: position.y as f32,
+                    width: size.width as f32,
+                    height: size.height as f32,
+                });
+            }
+        }
+        result
+    }
+
+    #[js_func]
+    pub fn set_resize_edges(&mut self, edges: String) -> Result<(), JsError> {
+        self.resize_edges = ResizeEdges::parse(&edges)
+            .ok_or_else(|| JsError::new(format!("invalid resize edges:{}", edges)))?;
+        Ok(())
+    }
+
     fn update_cursor(&mut self, node: &Element) {
         let cursor = search_cursor(node);
         //TODO cache?
@@ -1070,9 +2278,55 @@ impl Window {
         } else {
             self.tooltip_instance = None;
         }
+        self.update_hover_path(Some(&node));
         self.hover = Some(node);
     }
 
+    fn mouse_leave_window(&mut self) {
+        let mut hover = some_or_return!(self.hover.clone());
+        self.emit_mouse_event(
+            &mut hover,
+            MouseEventType::MouseLeave,
+            0,
+            self.cursor_position.x as f32,
+            self.cursor_position.y as f32,
+            self.cursor_root_position.x as f32,
+            self.cursor_root_position.y as f32,
+        );
+        self.update_hover_path(None);
+        self.hover = None;
+        self.tooltip_instance = None;
+    }
+
+    /// Update hover state of elements whose hovered state changed, i.e. elements in only one of
+    /// the old and new ancestor chains
+    fn update_hover_path(&mut self, node: Option<&Element>) {
+        let old_path = self.hover.as_ref().map(get_hover_path).unwrap_or_default();
+        let new_path = match node {
+            Some(n) if is_hover_supported() => get_hover_path(n),
+            _ => Vec::new(),
+        };
+        let restyle = CSS_MANAGER.with_borrow(|cm| cm.contains_hover());
+        for (path, other, hover) in [(&old_path, &new_path, false), (&new_path, &old_path, true)] {
+            // Changed elements are always the leading part of path
+            let mut top_changed = None;
+            for e in path {
+                if other.contains(e) {
+                    break;
+                }
+                let mut e = e.clone();
+                if e.set_hover(hover) {
+                    top_changed = Some(e);
+                }
+            }
+            if restyle {
+                if let Some(mut e) = top_changed {
+                    e.update_select_style_recurse();
+                }
+            }
+        }
+    }
+
     fn find_tooltip(node: &Element, x: f32) -> Option<(u32, String, Rect)> {
         if !node.tooltip.is_empty() {
             let mut bounds = node.get_origin_bounds();
@@ -1267,6 +2521,7 @@ impl Window {
             self.touching.max_identifiers =
                 usize::max(self.touching.max_identifiers, self.touching.touches.len());
             let touches: Vec<Touch> = self.touching.touches.values().cloned().collect();
+            self.update_gesture(&touches);
             let touch_detail = TouchDetail { touches };
             match phase {
                 TouchPhase::Started => {
@@ -1305,7 +2560,79 @@ impl Window {
         None
     }
 
+    fn update_gesture(&mut self, touches: &[Touch]) {
+        let points: Vec<(u64, f32, f32)> = touches
+            .iter()
+            .map(|t| (t.identifier, t.window_x, t.window_y))
+            .collect();
+        let update = self.touching.gesture.update(&points);
+        if points.len() != 2 {
+            self.touching.gesture_target = None;
+            return;
+        }
+        let update = match update {
+            Some(update) => update,
+            None => {
+                if self.touching.gesture_target.is_none() {
+                    let center_x = (points[0].1 + points[1].1) / 2.0;
+                    let center_y = (points[0].2 + points[1].2) / 2.0;
+                    self.touching.gesture_target = self
+                        .get_node_by_pos(center_x, center_y)
+                        .map(|(node, _, _)| node);
+                }
+                return;
+            }
+        };
+        let node = some_or_return!(self.touching.gesture_target.clone());
+        let (window_x, window_y) = update.center;
+        let root = node.get_root_element();
+        let render_tree = some_or_return!(self.render_tree.get(&root));
+        let node_matrix = some_or_return!(render_tree.get_element_total_matrix(&node));
+        let inverted_matrix = some_or_return!(node_matrix.invert());
+        let Point { x, y } = inverted_matrix.map_xy(window_x, window_y);
+        let (border_top, _, _, border_left) = node.get_border_width();
+        let (offset_x, offset_y) = (x - border_left, y - border_top);
+        if let Some(zoom) = update.zoom {
+            node.emit(GestureZoomEvent {
+                scale: zoom.total,
+                delta_scale: zoom.delta,
+                offset_x,
+                offset_y,
+                window_x,
+                window_y,
+            });
+        }
+        if let Some(rotate) = update.rotate {
+            node.emit(GestureRotateEvent {
+                rotation: rotate.total,
+                delta_rotation: rotate.delta,
+                offset_x,
+                offset_y,
+                window_x,
+                window_y,
+            });
+        }
+        if let Some((pan_x, pan_y)) = update.pan {
+            node.emit(GesturePanEvent {
+                translation_x: pan_x.total,
+                translation_y: pan_y.total,
+                delta_x: pan_x.delta,
+                delta_y: pan_y.delta,
+                offset_x,
+                offset_y,
+                window_x,
+                window_y,
+            });
+        }
+    }
+
     pub fn focus(&mut self, mut node: Element) {
+        if let Some(target) = node.get_focus_restore_target() {
+            if self.focusing.as_ref() != Some(&target) {
+                self.focus(target);
+            }
+            return;
+        }
         if !node.is_focusable() {
             if let Some(p) = node.get_parent() {
                 self.focus(p);
@@ -1324,6 +2651,7 @@ impl Window {
             let layer = self.get_focused_layer_mut();
             layer.focusing = node.clone();
             self.focusing = focusing;
+            node.remember_focus_in_scopes();
             if let Some(old_focusing) = &mut old_focusing {
                 old_focusing.emit(BlurEvent);
 
@@ -1339,9 +2667,48 @@ impl Window {
             node.update_select_style_recurse();
             node.emit(FocusEvent);
             self.window.set_ime_allowed(node.allow_ime);
+            if node.allow_ime {
+                self.window.set_ime_purpose(node.ime_purpose);
+            }
         }
     }
 
+    /// Place IME candidate window next to `area`, which is in coordinates of `element`.
+    /// Transforms and the offset of the page or popup containing element are applied.
+    pub fn set_ime_cursor_area(&self, element: &Element, area: base::Rect) {
+        let root = element.get_root_element();
+        let (layer_x, layer_y) = self
+            .layer_roots
+            .iter()
+            .find(|lr| lr.body == root)
+            .map(|lr| (lr.x, lr.y))
+            .unwrap_or((0.0, 0.0));
+        let matrix = self
+            .render_tree
+            .get(&root)
+            .and_then(|rt| rt.get_element_total_matrix(element));
+        let area = match matrix {
+            Some(m) => base::Rect::from_skia_rect(m.map_rect(area.to_skia_rect()).0),
+            // Not rendered yet, transforms are ignored
+            None => {
+                let ob = element.get_origin_bounds();
+                base::Rect::new(ob.x + area.x, ob.y + area.y, area.width, area.height)
+            }
+        };
+        // Logical coordinates of winit are not zoomed
+        let zoom = self.zoom as f64;
+        self.window.set_ime_cursor_area(
+            Logical(LogicalPosition {
+                x: (layer_x + area.x) as f64 * zoom,
+                y: (layer_y + area.y) as f64 * zoom,
+            }),
+            Size::Logical(LogicalSize {
+                width: area.width.max(1.0) as f64 * zoom,
+                height: area.height.max(1.0) as f64 * zoom,
+            }),
+        );
+    }
+
     pub fn is_focusing(&self, element: &Element) -> bool {
         self.focusing.as_ref() == Some(element)
     }
@@ -1362,7 +2729,7 @@ impl Window {
 
     #[js_func]
     pub fn get_inner_size(&self) -> (f32, f32) {
-        let scale_factor = self.window.scale_factor();
+        let scale_factor = self.scale_factor();
         let size = self.window.inner_size().to_logical(scale_factor);
         (size.width, size.height)
     }
@@ -1403,9 +2770,10 @@ impl Window {
             let lr = some_or_return!(self.layer_roots.first());
             let (final_width, final_height) = lr.body.get_size();
             if win_width as u32 != final_width as u32 || win_height as u32 != final_height as u32 {
+                // Size of window is not zoomed
                 self.resize(crate::base::Size {
-                    width: final_width,
-                    height: final_height,
+                    width: final_width * self.zoom,
+                    height: final_height * self.zoom,
                 });
                 #[cfg(wayland_platform)]
                 //Note: No ResizeEvent will receive on wayland after Window::resize called.
@@ -1414,8 +2782,23 @@ impl Window {
         }
     }
 
+    /// Whether nothing is waiting to be painted, neither dirty content nor frame callbacks.
+    /// Paused window is always idle.
+    pub fn is_idle(&self) -> bool {
+        self.is_paused()
+            || (self.frames_in_flight == 0
+                && !self.dirty
+                && self.next_frame_timer_handle.is_none()
+                && self.next_frame_callbacks.is_empty())
+    }
+
+    /// Time left before next frame
+    pub fn time_to_next_frame(&self) -> Duration {
+        self.frame_rate_controller.time_to_next_frame()
+    }
+
     pub fn update(&mut self) -> ResultWaiter<bool> {
-        if !self.renderer_idle {
+        if self.frames_in_flight >= MAX_FRAMES_IN_FLIGHT || self.is_paused() {
             return ResultWaiter::new_finished(false);
         }
         if self.next_frame_timer_handle.is_some() {
@@ -1441,6 +2824,7 @@ impl Window {
 
     fn update_force(&mut self) -> ResultWaiter<bool> {
         // print_time!("window update time");
+        batch::flush_styles();
         let mut frame_callbacks = Vec::new();
         frame_callbacks.append(&mut self.next_frame_callbacks);
         for cb in frame_callbacks {
@@ -1450,6 +2834,7 @@ impl Window {
             // skip duplicate update
             return ResultWaiter::new_finished(false);
         }
+        let frame_start = Instant::now();
         let (viewport_width, viewport_height) = self.get_inner_size();
         warn_time!(16, "update window");
         for lr in &mut self.layer_roots.clone() {
@@ -1470,15 +2855,21 @@ impl Window {
         if layout_dirty {
             self.update_layout(dirty_roots.values().cloned().collect());
             //TODO should move to Popup?
-            let win_size = self
-                .window
-                .inner_size()
-                .to_logical(self.window.scale_factor());
+            let win_size = self.window.inner_size().to_logical(self.scale_factor());
             for i in 1..self.layer_roots.len() {
                 let lr = &mut self.layer_roots[i];
                 let (root, x, y) = (&mut lr.body, &mut lr.x, &mut lr.y);
                 let bounds = root.get_bounds();
-                if x.is_nan() {
+                if let Some(anchor) = &lr.anchor {
+                    let boundary = base::Rect::new(0.0, 0.0, win_size.width, win_size.height);
+                    let size = (bounds.width, bounds.height);
+                    let result = compute_position(&anchor.target, size, &boundary, &anchor.options);
+                    *x = result.x;
+                    *y = result.y;
+                    if let Some(content) = root.get_children().first() {
+                        update_placement_attribute(content, &anchor.placement, result);
+                    }
+                } else if x.is_nan() {
                     *x = (win_size.width - bounds.width) / 2.0;
                     *y = (win_size.height - bounds.height) / 2.0;
                 } else {
@@ -1491,19 +2882,37 @@ impl Window {
                 }
             }
         }
-        //TODO optimize performance
-        // if layout_dirty {
-        self.render_tree.clear();
-        for lr in self.layer_roots.clone() {
-            //TODO call before_renderer when layout is not dirty
-            let mut body = lr.body;
-            body.before_render_recurse();
-            let rt = build_render_nodes(&mut body);
-            let body = body.clone();
-            self.render_tree.insert(body, rt);
+        for lr in &mut self.layer_roots.clone() {
+            lr.body.before_render_recurse();
         }
-        // }
-        let r = self.paint();
+        let scrolled: Vec<Element> = mem::take(&mut self.scroll_dirty_list)
+            .into_values()
+            .collect();
+        let render_tree_dirty = mem::take(&mut self.render_tree_dirty);
+        // Layers are moved without rebuilding render trees if only scroll offsets changed
+        let scroll_only = !layout_dirty
+            && !render_tree_dirty
+            && self
+                .layer_roots
+                .iter()
+                .all(|lr| self.render_tree.contains_key(&lr.body));
+        if !scroll_only {
+            self.render_tree.clear();
+            for lr in self.layer_roots.clone() {
+                let mut body = lr.body;
+                let rt = build_render_nodes(&mut body);
+                self.render_tree.insert(body, rt);
+            }
+        }
+        let frame_time = frame_start.elapsed();
+        crate::metrics::timing(crate::metrics::FRAME_TIME, frame_time.as_secs_f64() * 1000.0);
+        let dropped = self.frame_rate_controller.dropped_frames(frame_time);
+        if dropped > 0 {
+            crate::metrics::increment(crate::metrics::FRAME_DROPS, dropped as f64);
+        }
+        let fps = self.frame_rate_controller.get_fps();
+        performance::record_frame(self.get_id(), fps, self.refresh_rate, dropped);
+        let r = self.paint(scroll_only.then_some(scrolled));
         self.layout_dirty_list.clear();
         self.dirty = false;
         r
@@ -1511,7 +2920,11 @@ impl Window {
 
     #[js_func]
     pub fn set_body(&mut self, body: Element) -> DeftResult<()> {
-        self.layer_roots[0] = LayerRoot::new(body.clone(), 0.0, 0.0);
+        let old_root = mem::replace(
+            &mut self.layer_roots[0],
+            LayerRoot::new(body.clone(), 0.0, 0.0),
+        );
+        self.element_index.remove_recurse(&old_root.body);
         self.init_element_root(body, ElementParent::Window(self.handle.clone()));
         Ok(())
     }
@@ -1535,12 +2948,149 @@ impl Window {
         page
     }
 
+    /// Position layer of page next to target, the layer is moved when its size changes
+    pub fn anchor_page(
+        &mut self,
+        page: &Page,
+        target: base::Rect,
+        options: PlacementOptions,
+        placement: Rc<Cell<Option<PlacementResult>>>,
+    ) {
+        let body = page.get_body();
+        if let Some(lr) = self.layer_roots.iter_mut().find(|lr| &lr.body == body) {
+            lr.anchor = Some(LayerAnchor {
+                target,
+                options,
+                placement,
+            });
+        }
+    }
+
+    /// Move element into a layer covering the whole window, it is moved back to its parent
+    /// when exiting fullscreen
+    pub fn request_element_fullscreen(&mut self, mut element: Element) -> Result<(), JsError> {
+        if self.is_element_fullscreen(&element) {
+            return Ok(());
+        }
+        self.exit_element_fullscreen();
+        let mut parent = element
+            .get_parent()
+            .ok_or_else(|| JsError::from_str("root element could not be fullscreen"))?;
+        let position = some_or_return!(parent.get_child_position(&element), Ok(()));
+        let _ = parent.remove_child(position);
+        element.set_attribute("fullscreen".to_string(), "".to_string());
+        let page = self.create_page_ex(element.clone(), 0.0, 0.0, true);
+        let mut backdrop = page.get_body().clone();
+        backdrop.set_attribute("fullscreen-backdrop".to_string(), "".to_string());
+        self.fullscreen_element = Some(FullscreenElement {
+            element: element.clone(),
+            parent,
+            position,
+            page,
+        });
+        self.focus(element.clone());
+        element.emit(FullscreenChangeEvent { fullscreen: true });
+        Ok(())
+    }
+
+    pub fn exit_element_fullscreen(&mut self) {
+        self.end_element_fullscreen(true);
+    }
+
+    /// Exit fullscreen of element moved out of parent without moving it back, e.g. it's removed
+    /// from parent by js while in fullscreen
+    pub(crate) fn discard_fullscreen_element(&mut self, element: &Element, parent: &Element) {
+        let fe = some_or_return!(self.fullscreen_element.as_ref());
+        if &fe.element == element && &fe.parent == parent {
+            self.end_element_fullscreen(false);
+        }
+    }
+
+    fn end_element_fullscreen(&mut self, move_back: bool) {
+        let mut fe = some_or_return!(self.fullscreen_element.take());
+        let mut backdrop = fe.page.get_body().clone();
+        if let Some(p) = backdrop.get_child_position(&fe.element) {
+            let _ = backdrop.remove_child(p);
+        }
+        self.close_page(fe.page);
+        fe.element.remove_attribute("fullscreen".to_string());
+        if move_back {
+            let position = fe.position.min(fe.parent.get_child_count());
+            let _ = fe.parent.add_child(fe.element.clone(), position as i32);
+            self.focus(fe.element.clone());
+        }
+        fe.element.emit(FullscreenChangeEvent { fullscreen: false });
+    }
+
+    pub fn request_pointer_lock(&mut self, element: Element) -> Result<(), JsError> {
+        if self.is_pointer_locked(&element) {
+            return Ok(());
+        }
+        self.exit_pointer_lock();
+        // Cursor could only be locked on some platforms, confined cursor keeps moving but is
+        // hidden and motion is still reported by device events
+        self.window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Confined))
+            .map_err(|e| JsError::new(format!("failed to lock pointer: {}", e)))?;
+        self.window.set_cursor_visible(false);
+        self.pointer_lock = Some(element.clone());
+        element.emit(PointerLockChangeEvent { locked: true });
+        Ok(())
+    }
+
+    #[js_func]
+    pub fn exit_pointer_lock(&mut self) {
+        let element = some_or_return!(self.pointer_lock.take());
+        // Kiosk mode keeps cursor in window
+        let mode = if self.kiosk.is_some() {
+            CursorGrabMode::Confined
+        } else {
+            CursorGrabMode::None
+        };
+        let _ = self.window.set_cursor_grab(mode);
+        self.window.set_cursor_visible(true);
+        element.emit(PointerLockChangeEvent { locked: false });
+    }
+
+    pub fn is_pointer_locked(&self, element: &Element) -> bool {
+        self.pointer_lock.as_ref() == Some(element)
+    }
+
+    /// Relative mouse motion reported by device, in unaccelerated device units
+    pub fn handle_pointer_lock_motion(&mut self, delta_x: f64, delta_y: f64) {
+        let element = some_or_return!(self.pointer_lock.clone());
+        let mut detail = some_or_return!(self.build_mouse_detail(
+            &element,
+            MouseEventType::MouseMove,
+            0,
+            self.cursor_position.x as f32,
+            self.cursor_position.y as f32,
+            self.cursor_root_position.x as f32,
+            self.cursor_root_position.y as f32,
+        ));
+        detail.movement_x = delta_x as f32;
+        detail.movement_y = delta_y as f32;
+        element.emit(MouseMoveEvent(detail));
+    }
+
+    pub fn is_element_fullscreen(&self, element: &Element) -> bool {
+        self.fullscreen_element.as_ref().map(|fe| &fe.element) == Some(element)
+    }
+
     #[js_func]
     pub fn close_page(&mut self, page: Page) {
+        page.get_body().get_children().iter_mut().for_each(|c| c.release_js_context());
         self.pages.retain(|p| p != &page);
         self.layer_roots.retain(|e| &e.body != page.get_body());
+        self.element_index.remove_recurse(page.get_body());
         let new_layer = self.get_focused_layer();
-        self.focus(new_layer.focusing.clone());
+        let focusing = if new_layer.focusing.get_window().is_some() {
+            new_layer.focusing.clone()
+        } else {
+            new_layer.body.clone()
+        };
+        self.focus(focusing);
         self.notify_update();
         //TODO emit close event?
     }
@@ -1548,21 +3098,46 @@ impl Window {
     fn init_element_root(&mut self, mut body: Element, parent: ElementParent) {
         body.set_parent(parent);
         body.set_focusable(true);
-        let theme = match env::var("DEFT_THEME") {
-            Ok(str) => str,
-            Err(_) => match self.window.theme().unwrap_or(Theme::Light) {
-                Theme::Light => "light".to_string(),
-                Theme::Dark => "dark".to_string(),
-            },
-        };
+        let theme = Self::theme_name(self.window.theme());
+        // Windows share the theme of system, the body is matched with the new color scheme below
+        Self::update_color_scheme(&theme);
         body.set_attribute("theme".to_string(), theme);
         // if self.focusing.is_none() {
         // TODO move focusing to page?
         self.focus(body.clone());
         // }
+        self.element_index.add_recurse(&body);
         self.invalid_layout(body);
     }
 
+    /// Value of `theme` attribute of body, `DEFT_THEME` environment variable overrides the theme
+    /// of system
+    fn theme_name(theme: Option<Theme>) -> String {
+        match env::var("DEFT_THEME") {
+            Ok(str) => str,
+            Err(_) => match theme.unwrap_or(Theme::Light) {
+                Theme::Light => "light".to_string(),
+                Theme::Dark => "dark".to_string(),
+            },
+        }
+    }
+
+    /// Update color scheme tested by `prefers-color-scheme`, returns true if it is changed
+    fn update_color_scheme(theme: &str) -> bool {
+        ColorScheme::parse(theme)
+            .map(set_color_scheme)
+            .unwrap_or(false)
+    }
+
+    /// Theme of window, i.e. `theme` attribute of body
+    #[js_func]
+    pub fn get_theme(&self) -> String {
+        self.layer_roots[0]
+            .body
+            .get_attribute("theme".to_string())
+            .unwrap_or_default()
+    }
+
     #[js_func]
     pub fn get_body(&self) -> Option<Element> {
         Some(self.layer_roots[0].body.clone())
@@ -1578,6 +3153,11 @@ impl Window {
         self.window.title()
     }
 
+    #[js_func]
+    pub fn get_storage_namespace(&self) -> String {
+        self.storage_namespace.clone()
+    }
+
     #[js_func]
     pub fn resize(&mut self, size: crate::base::Size) {
         let _ = self.window.request_inner_size(LogicalSize {
@@ -1593,10 +3173,12 @@ impl Window {
             return;
         }
         self.window.resize_surface(width, height);
+        #[cfg(ios_platform)]
+        self.update_safe_area_insets();
         if let Some(body) = self.get_body() {
             self.invalid_layout(body.clone());
         }
-        let scale_factor = self.window.scale_factor();
+        let scale_factor = self.scale_factor();
         self.emit(WindowResizeEvent {
             width: (width as f64 / scale_factor) as u32,
             height: (height as f64 / scale_factor) as u32,
@@ -1623,7 +3205,135 @@ impl Window {
         }
     }
 
-    fn paint(&mut self) -> ResultWaiter<bool> {
+    /// Paint trees of layer roots with their positions, layers are only moved if scrolled elements
+    /// are given and nothing else changed
+    fn build_paint_trees(
+        &mut self,
+        viewport: &Rect,
+        layer_cache_enabled: bool,
+        scrolled: Option<Vec<Element>>,
+    ) -> Vec<(LayerPO, f32, f32)> {
+        let mut paint_tree = Vec::new();
+        for lr in &mut self.layer_roots.clone() {
+            let (root, x, y) = (&mut lr.body, lr.x, lr.y);
+            let render_tree = self.render_tree.get_mut(root).unwrap();
+            let moved = match &scrolled {
+                Some(scrolled) => {
+                    let elements: Vec<Element> = scrolled
+                        .iter()
+                        .filter(|e| e.get_root_element() == *root)
+                        .cloned()
+                        .collect();
+                    render_tree.update_scroll_layers(&elements, layer_cache_enabled)
+                }
+                None => false,
+            };
+            if !moved {
+                render_tree.rebuild_render_tree(root, layer_cache_enabled);
+            }
+            let pt = self
+                .render_tree
+                .get_mut(root)
+                .unwrap()
+                .build_paint_tree(viewport);
+            //TODO notify absolute position change
+            paint_tree.push((pt, x, y));
+        }
+        paint_tree
+    }
+
+    /// Render content of window to png image in physical pixels without presenting it,
+    /// e.g. for screenshot tests in headless mode
+    #[js_func]
+    pub fn render_to_image(&mut self) -> Result<Vec<u8>, Error> {
+        if self.dirty {
+            self.update_force();
+        }
+        let size = self.window.inner_size();
+        let (width, height) = (size.width, size.height);
+        if width == 0 || height == 0 {
+            return Err(anyhow!("window is empty"));
+        }
+        let scale_factor = self.scale_factor() as f32;
+        let background_color = self.background_color;
+        let viewport = Rect::new(
+            0.0,
+            0.0,
+            width as f32 / scale_factor,
+            height as f32 / scale_factor,
+        );
+        let mut paint_tree = self.build_paint_trees(&viewport, false, None);
+        let renderer = Renderer::new(move |canvas, ctx| {
+            canvas.save();
+            canvas.scale((scale_factor, scale_factor));
+            canvas.clear(background_color);
+            let mut element_painter = ElementPainter::take(ctx);
+            element_painter.update_viewport(scale_factor, viewport);
+            element_painter.set_layer_cache(false);
+            let painter = Painter::new(canvas, PaintContext { scale_factor });
+            for (tree, x, y) in &mut paint_tree {
+                painter.canvas.save();
+                painter.canvas.translate((*x, *y));
+                element_painter.draw_root(&painter, tree, ctx);
+                painter.canvas.restore();
+            }
+            element_painter.put(ctx);
+            canvas.restore();
+        });
+        let color_space = Some(self.color_space.to_skia());
+        let image = headless::render_to_image(width, height, color_space, renderer)
+            .ok_or_else(|| anyhow!("failed to render window"))?;
+        let data = image
+            .encode(None, EncodedImageFormat::PNG, None)
+            .ok_or_else(|| anyhow!("failed to encode image"))?;
+        Ok(data.as_bytes().to_vec())
+    }
+
+    /// Snapshot of layers, element objects, matrices and invalid areas for rendering bug reports,
+    /// options: { layerImages?: boolean }. Invalid areas are the ones pending for next frame,
+    /// pending changes are painted first if layer images are requested.
+    #[js_func]
+    pub fn dump_render_tree(&mut self, options: JsValue) -> RenderTreeDump {
+        let options = RenderTreeDumpOptions::from_js_value(&options);
+        // Paint trees must be built from up-to-date render trees
+        if options.layer_images && self.dirty {
+            self.update_force();
+        }
+        let size = self.window.inner_size();
+        let scale_factor = self.scale_factor() as f32;
+        let viewport = Rect::new(
+            0.0,
+            0.0,
+            size.width as f32 / scale_factor,
+            size.height as f32 / scale_factor,
+        );
+        let mut layer_roots: Vec<LayerRootDump> = self
+            .layer_roots
+            .iter()
+            .map(|lr| LayerRootDump {
+                element_id: lr.body.get_eid(),
+                x: lr.x,
+                y: lr.y,
+                layer: self.render_tree.get(&lr.body).and_then(|rt| rt.dump()),
+            })
+            .collect();
+        if options.layer_images {
+            let color_space = Some(self.color_space.to_skia());
+            let paint_trees = self.build_paint_trees(&viewport, false, None);
+            for ((mut tree, _, _), root) in paint_trees.into_iter().zip(&mut layer_roots) {
+                if let Some(layer) = &mut root.layer {
+                    attach_layer_images(layer, &mut tree, scale_factor, &color_space);
+                }
+            }
+        }
+        RenderTreeDump {
+            scale_factor,
+            viewport,
+            layer_roots,
+        }
+    }
+
+    fn paint(&mut self, scrolled: Option<Vec<Element>>) -> ResultWaiter<bool> {
         let size = self.window.inner_size();
         let (width, height) = (size.width, size.height);
         // print_time!("paint time: {} {}", width, height);
@@ -1637,8 +3347,15 @@ impl Window {
         for cb in paint_callbacks {
             cb.call();
         }
-        let scale_factor = self.window.scale_factor() as f32;
+        let scale_factor = self.scale_factor() as f32;
         let background_color = self.background_color;
+        let corner_radius = self.corner_clip_radius;
+        let shape = self.shape.clone();
+        let sdr_white_scale = if self.window.is_hdr() {
+            self.sdr_white_level / SCRGB_WHITE_NITS
+        } else {
+            1.0
+        };
         let viewport = Rect::new(
             0.0,
             0.0,
@@ -1647,31 +3364,40 @@ impl Window {
         );
         //TODO support config
         let layer_cache_enabled = false;
-        let mut paint_tree = Vec::new();
-        for lr in &mut self.layer_roots.clone() {
-            let (root, x, y) = (&mut lr.body, lr.x, lr.y);
-            self.render_tree
-                .get_mut(root)
-                .unwrap()
-                .rebuild_render_tree(root, layer_cache_enabled);
-            let pt = self
-                .render_tree
-                .get_mut(root)
-                .unwrap()
-                .build_paint_tree(&viewport);
-            //TODO notify absolute position change
-            paint_tree.push((pt, x, y));
-        }
+        let mut paint_tree = self.build_paint_trees(&viewport, layer_cache_enabled, scrolled);
+        let hud = if is_hud_shown() {
+            Some(build_hud(&self.get_render_stats()))
+        } else {
+            None
+        };
+        let render_stats = self.render_stats.clone();
         let waiter_finisher = waiter.clone();
         let window_id = self.get_id();
-        self.renderer_idle = false;
+        let input_time = self.unpainted_input_time.take();
+        self.frames_in_flight += 1;
         self.window.render_with_result(
             Renderer::new(move |canvas, ctx| {
                 // print_time!("drawing time");
+                let render_start = Instant::now();
                 canvas.save();
                 if scale_factor != 1.0 {
                     canvas.scale((scale_factor, scale_factor));
                 }
+                if sdr_white_scale != 1.0 {
+                    let paint = sdr_white_paint(sdr_white_scale);
+                    canvas.save_layer(&SaveLayerRec::default().paint(&paint));
+                }
+                if corner_radius > 0.0 {
+                    // Pixels out of the rounded corners stay transparent
+                    canvas.clear(Color::TRANSPARENT);
+                    let rrect =
+                        RRect::new_rect_xy(viewport.to_skia_rect(), corner_radius, corner_radius);
+                    canvas.clip_rrect(rrect, None, true);
+                }
+                if let Some(shape) = &shape {
+                    canvas.clear(Color::TRANSPARENT);
+                    canvas.clip_path(shape, None, true);
+                }
                 canvas.clear(background_color);
                 let mut element_painter = ElementPainter::take(ctx);
                 element_painter.update_viewport(scale_factor, viewport);
@@ -1684,10 +3410,24 @@ impl Window {
                     element_painter.draw_root(&painter, tree, ctx);
                     painter.canvas.restore();
                 }
+                if let Some(hud) = hud {
+                    hud.run(&painter);
+                }
+                let mut stats = element_painter.take_stats();
+                stats.frame_time_ms = render_start.elapsed().as_secs_f32() * 1000.0;
+                if let Ok(mut rs) = render_stats.lock() {
+                    *rs = stats;
+                }
                 element_painter.put(ctx);
+                if sdr_white_scale != 1.0 {
+                    canvas.restore();
+                }
                 canvas.restore();
             }),
             move |r| {
+                if let Some(input_time) = input_time {
+                    performance::record_input_latency(window_id, input_time.elapsed());
+                }
                 waiter_finisher.finish(r);
                 send_app_event(AppEvent::RenderIdle(window_id)).unwrap();
             },
@@ -1697,7 +3437,150 @@ impl Window {
 
     #[inline]
     fn get_logical_len(&self, physical_len: f32) -> f32 {
-        physical_len * self.window.scale_factor() as f32
+        physical_len * self.scale_factor() as f32
+    }
+
+    /// Physical pixels per layout pixel, i.e. scale factor of system multiplied by zoom
+    pub fn scale_factor(&self) -> f64 {
+        self.window.scale_factor() * self.zoom as f64
+    }
+
+    /// Scale layout and rendering of the window, independent of scale factor of system.
+    /// Zoom is saved in localstorage of the window if available.
+    #[js_func]
+    pub fn set_zoom(&mut self, zoom: f32) {
+        let zoom = zoom.clamp(ZOOM_LEVELS[0], ZOOM_LEVELS[ZOOM_LEVELS.len() - 1]);
+        if zoom == self.zoom {
+            return;
+        }
+        let _ = localstorage::set_item(
+            self.storage_namespace.clone(),
+            ZOOM_STORAGE_KEY.to_string(),
+            zoom.to_string(),
+        );
+        self.apply_zoom(zoom);
+    }
+
+    /// Change zoom without saving it
+    pub(crate) fn apply_zoom(&mut self, zoom: f32) {
+        if zoom == self.zoom {
+            return;
+        }
+        self.zoom = zoom;
+        for lr in self.layer_roots.clone() {
+            self.invalid_layout(lr.body);
+        }
+        self.emit(WindowZoomEvent { zoom });
+        self.on_resize();
+    }
+
+    #[js_func]
+    pub fn get_zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Whether window is minimized or fully covered, not reported on all platforms
+    #[js_func]
+    pub fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+
+    /// Skip frames while window is occluded to reduce cpu usage, enabled by default
+    #[js_func]
+    pub fn set_pause_when_occluded(&mut self, pause: bool) {
+        let was_paused = self.is_paused();
+        self.pause_when_occluded = pause;
+        if was_paused && !self.is_paused() {
+            self.resume_frames();
+        }
+    }
+
+    #[js_func]
+    pub fn get_pause_when_occluded(&self) -> bool {
+        self.pause_when_occluded
+    }
+
+    /// Cap frame rate of window, 0 to follow refresh rate of monitor again
+    #[js_func]
+    pub fn set_max_fps(&mut self, fps: f32) {
+        self.max_fps = Some(fps).filter(|fps| *fps > 0.0);
+        self.update_frame_rate();
+    }
+
+    /// Frame rate cap, refresh rate of monitor if not capped
+    #[js_func]
+    pub fn get_max_fps(&self) -> f32 {
+        self.max_fps.unwrap_or_else(|| target_fps(self.refresh_rate))
+    }
+
+    /// Throttle frame rate while window is not focused, 0 to disable throttling
+    #[js_func]
+    pub fn set_unfocused_fps(&mut self, fps: f32) {
+        self.unfocused_fps = Some(fps).filter(|fps| *fps > 0.0);
+        self.update_frame_rate();
+    }
+
+    #[js_func]
+    pub fn get_unfocused_fps(&self) -> f32 {
+        self.unfocused_fps.unwrap_or(0.0)
+    }
+
+    /// Current frame rate after caps and throttling are applied
+    #[js_func]
+    pub fn get_fps(&self) -> f32 {
+        self.frame_rate_controller.get_fps()
+    }
+
+    fn update_refresh_rate(&mut self) {
+        self.refresh_rate = self
+            .window
+            .current_monitor()
+            .and_then(|m| m.refresh_rate_millihertz())
+            .map(|r| r as f32 / 1000.0);
+        self.update_frame_rate();
+    }
+
+    fn update_frame_rate(&mut self) {
+        let target = target_fps(self.refresh_rate);
+        let fps = effective_fps(target, self.max_fps, self.unfocused_fps, self.focused);
+        if !self.frame_rate_controller.set_fps(fps) {
+            return;
+        }
+        // Reschedule pending frame with the new frame rate
+        if self.next_frame_timer_handle.take().is_some() {
+            self.resume_frames();
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.occluded && self.pause_when_occluded
+    }
+
+    fn set_occluded(&mut self, occluded: bool) {
+        if self.occluded == occluded {
+            return;
+        }
+        let was_paused = self.is_paused();
+        self.occluded = occluded;
+        if was_paused && !self.is_paused() {
+            self.resume_frames();
+        }
+        self.emit(WindowVisibilityChangeEvent { visible: !occluded });
+    }
+
+    /// Paint updates and run frame callbacks requested while paused
+    fn resume_frames(&mut self) {
+        if self.dirty || !self.next_frame_callbacks.is_empty() {
+            send_app_event(AppEvent::Update(self.get_id())).unwrap();
+        }
+    }
+
+    fn load_zoom(&self) -> f32 {
+        localstorage::get_item(self.storage_namespace.clone(), ZOOM_STORAGE_KEY.to_string())
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1.0)
     }
 
     fn get_node_by_point(&self) -> (Element, f32, f32) {
@@ -1713,7 +3596,7 @@ impl Window {
         if element.get_eid() == id {
             return Some(element.clone());
         }
-        for child in element.get_children() {
+        for child in element.get_layout_children() {
             if let Some(element) = self.get_element_by_id(&child, id) {
                 return Some(element);
             }
@@ -1745,9 +3628,39 @@ impl Window {
         screen_x: f32,
         screen_y: f32,
     ) {
+        let detail = some_or_return!(self.build_mouse_detail(
+            node,
+            event_type_enum,
+            button,
+            window_x,
+            window_y,
+            screen_x,
+            screen_y
+        ));
+        match event_type_enum {
+            MouseEventType::MouseDown => node.emit(MouseDownEvent(detail)),
+            MouseEventType::MouseUp => node.emit(MouseUpEvent(detail)),
+            MouseEventType::MouseClick => node.emit(ClickEvent(detail)),
+            MouseEventType::ContextMenu => node.emit(ContextMenuEvent(detail)),
+            MouseEventType::MouseMove => node.emit(MouseMoveEvent(detail)),
+            MouseEventType::MouseEnter => node.emit(MouseEnterEvent(detail)),
+            MouseEventType::MouseLeave => node.emit(MouseLeaveEvent(detail)),
+        }
+    }
+
+    fn build_mouse_detail(
+        &self,
+        node: &Element,
+        event_type_enum: MouseEventType,
+        button: i32,
+        window_x: f32,
+        window_y: f32,
+        screen_x: f32,
+        screen_y: f32,
+    ) -> Option<MouseDetail> {
         let root = node.get_root_element();
-        let render_tree = some_or_return!(self.render_tree.get(&root));
-        let node_matrix = some_or_return!(render_tree.get_element_total_matrix(node));
+        let render_tree = self.render_tree.get(&root)?;
+        let node_matrix = render_tree.get_element_total_matrix(node)?;
         let (border_top, _, _, border_left) = node.get_border_width();
 
         //TODO maybe not inverted?
@@ -1760,7 +3673,7 @@ impl Window {
         let off_x = relative_x - border_left;
         let off_y = relative_y - border_top;
 
-        let detail = MouseDetail {
+        Some(MouseDetail {
             event_type: event_type_enum,
             button,
             offset_x: off_x,
@@ -1769,28 +3682,33 @@ impl Window {
             window_y,
             screen_x,
             screen_y,
-        };
-        match event_type_enum {
-            MouseEventType::MouseDown => node.emit(MouseDownEvent(detail)),
-            MouseEventType::MouseUp => node.emit(MouseUpEvent(detail)),
-            MouseEventType::MouseClick => node.emit(ClickEvent(detail)),
-            MouseEventType::ContextMenu => node.emit(ContextMenuEvent(detail)),
-            MouseEventType::MouseMove => node.emit(MouseMoveEvent(detail)),
-            MouseEventType::MouseEnter => node.emit(MouseEnterEvent(detail)),
-            MouseEventType::MouseLeave => node.emit(MouseLeaveEvent(detail)),
-        }
+            movement_x: 0.0,
+            movement_y: 0.0,
+        })
     }
 
     fn create_window(
         attributes: WindowAttributes,
         backend_types: &Vec<RenderBackendType>,
+        color_space: SurfaceColorSpace,
+        hdr: bool,
     ) -> SkiaWindow {
+        let mut backend_types = backend_types.clone();
+        if attributes.transparent {
+            // Stable sort keeps preferred order among backends supporting transparency
+            backend_types.sort_by_key(|bt| !bt.supports_transparency());
+        }
+        if hdr {
+            // Stable sort keeps preferred order among backends supporting hdr
+            backend_types.sort_by_key(|bt| !bt.supports_hdr());
+        }
         run_with_event_loop(|el| {
             debug!("render backends: {:?}", backend_types);
-            for bt in backend_types {
+            for bt in &backend_types {
                 let init_attributes = attributes.clone().with_visible(false);
-                if let Some(sw) = SkiaWindow::new(el, init_attributes, *bt) {
-                    if attributes.visible {
+                if let Some(mut sw) = SkiaWindow::new_with_hdr(el, init_attributes, *bt, hdr) {
+                    sw.set_color_space(color_space);
+                    if attributes.visible && *bt != RenderBackendType::Headless {
                         sw.set_visible(true);
                     }
                     debug!("created window with backend {:?}", bt);
@@ -1826,6 +3744,24 @@ fn get_scancode(code: KeyCode) -> Option<u32> {
     }
 }
 
+/// Scale sdr content to sdr white level on hdr surface, values are not clamped so that
+/// hdr images are still brighter than sdr white
+fn sdr_white_paint(scale: f32) -> Paint {
+    #[rustfmt::skip]
+    let matrix = [
+        scale, 0.0, 0.0, 0.0, 0.0,
+        0.0, scale, 0.0, 0.0, 0.0,
+        0.0, 0.0, scale, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0, 0.0,
+    ];
+    let mut paint = Paint::default();
+    paint.set_color_filter(color_filters::matrix_row_major(
+        &matrix,
+        color_filters::Clamp::No,
+    ));
+    paint
+}
+
 pub fn build_render_nodes(root: &mut Element) -> RenderTree {
     let count = count_elements(root);
     let mut render_tree = RenderTree::new(count);
@@ -1837,7 +3773,7 @@ pub fn build_render_nodes(root: &mut Element) -> RenderTree {
 fn collect_render_nodes(root: &mut Element, tree: &mut RenderTree) {
     // build_render_paint_info(root, &mut result.invalid_rects_list, &mut invalid_rects_idx, &mut node);
     tree.create_node(root);
-    let children = root.get_children();
+    let children = root.get_layout_children();
     for mut child in children {
         collect_render_nodes(&mut child, tree);
     }
@@ -1845,7 +3781,7 @@ fn collect_render_nodes(root: &mut Element, tree: &mut RenderTree) {
 
 fn count_elements(root: &Element) -> usize {
     let mut elements_count = 1;
-    let children = root.get_children();
+    let children = root.get_layout_children();
     for child in children {
         elements_count += count_elements(&child);
     }
@@ -1854,7 +3790,7 @@ fn count_elements(root: &Element) -> usize {
 
 fn print_tree(node: &Element, padding: &str) {
     let name = &node.tag;
-    let children = node.get_children();
+    let children = node.get_layout_children();
     if children.is_empty() {
         debug!("{}{}", padding, name);
     } else {
@@ -1886,6 +3822,7 @@ pub fn window_send_key(window_id: i32, key: &str, pressed: bool) {
                     f.handle_key(
                         0,
                         None,
+                        None,
                         Some(k),
                         Some(key.to_string()),
                         None,
@@ -1913,11 +3850,15 @@ pub fn window_on_render_idle(window_id: i32) {
     WINDOWS.with_borrow_mut(|m| {
         if let Some(f) = m.get_mut(&window_id) {
             if let Ok(mut f) = f.upgrade_mut() {
-                f.renderer_idle = true;
+                f.frames_in_flight = f.frames_in_flight.saturating_sub(1);
+                if f.window.is_context_lost() {
+                    f.fallback_to_software_renderer();
+                }
                 f.update();
             }
         }
     });
+    crate::gc::on_frame_painted();
 }
 
 pub fn window_check_update(window_id: i32) {
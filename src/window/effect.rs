@@ -0,0 +1,327 @@
+use crate::error::{DeftError, ErrorKind};
+use anyhow::Error;
+
+/// Material drawn by the system behind transparent pixels of window
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WindowEffect {
+    /// Blurred content behind window
+    Blur,
+    /// Blurred and tinted content behind window, acrylic backdrop on windows 11
+    Acrylic,
+    /// Desktop wallpaper tinted material, mica backdrop on windows 11
+    Mica,
+    /// Vibrant material of macOS
+    Vibrancy,
+}
+
+impl WindowEffect {
+    /// Parse effect name, "none" for no effect
+    pub fn parse(value: &str) -> Result<Option<Self>, DeftError> {
+        let effect = match value {
+            "none" => return Ok(None),
+            "blur" => WindowEffect::Blur,
+            "acrylic" => WindowEffect::Acrylic,
+            "mica" => WindowEffect::Mica,
+            "vibrancy" => WindowEffect::Vibrancy,
+            _ => {
+                let message = format!("invalid window effect: {}", value);
+                return Err(DeftError::new(ErrorKind::INVALID_PARAMETER, message));
+            }
+        };
+        Ok(Some(effect))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowEffect::Blur => "blur",
+            WindowEffect::Acrylic => "acrylic",
+            WindowEffect::Mica => "mica",
+            WindowEffect::Vibrancy => "vibrancy",
+        }
+    }
+
+    /// Apply effect to created window, or remove the effect applied before if `effect` is None.
+    /// The window should be transparent so that the effect shows through.
+    pub fn apply(effect: Option<Self>, window: &winit::window::Window) -> Result<(), Error> {
+        platform::apply(effect, window)
+    }
+
+    fn unsupported(self) -> Error {
+        let message = format!("window effect is not supported: {}", self.name());
+        DeftError::new(ErrorKind::NOT_SUPPORTED, message).into()
+    }
+}
+
+#[cfg(windows_platform)]
+mod platform {
+    use crate::error::{DeftError, ErrorKind};
+    use crate::window::effect::WindowEffect;
+    use anyhow::{anyhow, Error};
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Dwm::{
+        DwmEnableBlurBehindWindow, DwmExtendFrameIntoClientArea, DwmSetWindowAttribute,
+        DWMSBT_MAINWINDOW, DWMSBT_NONE, DWMSBT_TRANSIENTWINDOW, DWMWA_SYSTEMBACKDROP_TYPE,
+        DWM_BB_ENABLE, DWM_BLURBEHIND,
+    };
+    use windows::Win32::UI::Controls::MARGINS;
+
+    pub fn apply(
+        effect: Option<WindowEffect>,
+        window: &winit::window::Window,
+    ) -> Result<(), Error> {
+        if effect == Some(WindowEffect::Vibrancy) {
+            return Err(WindowEffect::Vibrancy.unsupported());
+        }
+        let RawWindowHandle::Win32(h) = window.raw_window_handle()? else {
+            return Err(anyhow!("unsupported window handle"));
+        };
+        let hwnd = HWND(h.hwnd.get() as *mut c_void);
+        // Backdrops are drawn in the frame, which is extended to the whole client area
+        let inset = if effect.is_some() { -1 } else { 0 };
+        let margins = MARGINS {
+            cxLeftWidth: inset,
+            cxRightWidth: inset,
+            cyTopHeight: inset,
+            cyBottomHeight: inset,
+        };
+        let blur_behind = DWM_BLURBEHIND {
+            dwFlags: DWM_BB_ENABLE,
+            fEnable: (effect == Some(WindowEffect::Blur)).into(),
+            ..Default::default()
+        };
+        let backdrop = match effect {
+            Some(WindowEffect::Mica) => DWMSBT_MAINWINDOW,
+            Some(WindowEffect::Acrylic) => DWMSBT_TRANSIENTWINDOW,
+            _ => DWMSBT_NONE,
+        };
+        unsafe {
+            DwmExtendFrameIntoClientArea(hwnd, &margins)?;
+            DwmEnableBlurBehindWindow(hwnd, &blur_behind)?;
+            let result = DwmSetWindowAttribute(
+                hwnd,
+                DWMWA_SYSTEMBACKDROP_TYPE,
+                &backdrop as *const _ as *const c_void,
+                size_of_val(&backdrop) as u32,
+            );
+            // System backdrops are only available since windows 11
+            if let (Err(e), Some(effect)) = (result, effect) {
+                if backdrop != DWMSBT_NONE {
+                    let message = format!("window effect is not supported: {}", effect.name());
+                    return Err(DeftError::new(ErrorKind::NOT_SUPPORTED, message)
+                        .with_cause(e)
+                        .into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(macos_platform)]
+mod platform {
+    use crate::window::effect::WindowEffect;
+    use anyhow::{anyhow, Error};
+    use objc2::encode::{Encode, Encoding};
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use std::ffi::CStr;
+    use std::ptr;
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    unsafe impl Encode for CGPoint {
+        const ENCODING: Encoding = Encoding::Struct("CGPoint", &[f64::ENCODING, f64::ENCODING]);
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    struct CGSize {
+        width: f64,
+        height: f64,
+    }
+
+    unsafe impl Encode for CGSize {
+        const ENCODING: Encoding = Encoding::Struct("CGSize", &[f64::ENCODING, f64::ENCODING]);
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    struct CGRect {
+        origin: CGPoint,
+        size: CGSize,
+    }
+
+    unsafe impl Encode for CGRect {
+        const ENCODING: Encoding =
+            Encoding::Struct("CGRect", &[CGPoint::ENCODING, CGSize::ENCODING]);
+    }
+
+    /// Identifier of effect views added to content view
+    const EFFECT_VIEW_IDENTIFIER: &CStr = c"DeftWindowEffect";
+
+    /// NSVisualEffectMaterial of effect
+    fn material(effect: WindowEffect) -> isize {
+        match effect {
+            WindowEffect::Blur => 13,
+            WindowEffect::Acrylic => 6,
+            WindowEffect::Mica => 12,
+            WindowEffect::Vibrancy => 21,
+        }
+    }
+
+    pub fn apply(
+        effect: Option<WindowEffect>,
+        window: &winit::window::Window,
+    ) -> Result<(), Error> {
+        let RawWindowHandle::AppKit(h) = window.raw_window_handle()? else {
+            return Err(anyhow!("unsupported window handle"));
+        };
+        unsafe {
+            let view = h.ns_view.as_ptr() as *mut AnyObject;
+            let identifier: *mut AnyObject = msg_send![
+                class!(NSString),
+                stringWithUTF8String: EFFECT_VIEW_IDENTIFIER.as_ptr()
+            ];
+            remove_effect_views(view, identifier);
+            let Some(effect) = effect else {
+                return Ok(());
+            };
+            let bounds: CGRect = msg_send![view, bounds];
+            let effect_view: *mut AnyObject = msg_send![class!(NSVisualEffectView), alloc];
+            let effect_view: *mut AnyObject = msg_send![effect_view, initWithFrame: bounds];
+            if effect_view.is_null() {
+                return Err(effect.unsupported());
+            }
+            let _: () = msg_send![effect_view, setMaterial: material(effect)];
+            // NSVisualEffectBlendingModeBehindWindow
+            let _: () = msg_send![effect_view, setBlendingMode: 0isize];
+            // NSVisualEffectStateActive, keep the effect while window is inactive
+            let _: () = msg_send![effect_view, setState: 1isize];
+            // NSViewWidthSizable | NSViewHeightSizable
+            let _: () = msg_send![effect_view, setAutoresizingMask: 18usize];
+            let _: () = msg_send![effect_view, setIdentifier: identifier];
+            // NSWindowBelow, so that the content is drawn over the effect
+            let _: () = msg_send![
+                view,
+                addSubview: effect_view,
+                positioned: -1isize,
+                relativeTo: ptr::null_mut::<AnyObject>()
+            ];
+            let _: () = msg_send![effect_view, release];
+        }
+        Ok(())
+    }
+
+    unsafe fn remove_effect_views(view: *mut AnyObject, identifier: *mut AnyObject) {
+        let subviews: *mut AnyObject = msg_send![view, subviews];
+        let count: usize = msg_send![subviews, count];
+        for i in (0..count).rev() {
+            let subview: *mut AnyObject = msg_send![subviews, objectAtIndex: i];
+            let id: *mut AnyObject = msg_send![subview, identifier];
+            if !id.is_null() {
+                let is_effect_view: bool = msg_send![id, isEqualToString: identifier];
+                if is_effect_view {
+                    let _: () = msg_send![subview, removeFromSuperview];
+                }
+            }
+        }
+    }
+}
+
+#[cfg(linux_platform)]
+mod platform {
+    use crate::window::effect::WindowEffect;
+    use anyhow::{anyhow, Error};
+    use raw_window_handle::{
+        HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+    };
+    use std::ffi::c_ulong;
+
+    pub fn apply(
+        effect: Option<WindowEffect>,
+        window: &winit::window::Window,
+    ) -> Result<(), Error> {
+        // Only blur is supported by compositors, there is no hint for materials
+        if let Some(effect) = effect.filter(|e| *e != WindowEffect::Blur) {
+            return Err(effect.unsupported());
+        }
+        match (window.raw_window_handle()?, window.raw_display_handle()?) {
+            (RawWindowHandle::Xlib(w), RawDisplayHandle::Xlib(d)) => {
+                let display = d.display.ok_or_else(|| anyhow!("no display"))?;
+                set_x11_blur_hint(display.as_ptr() as _, w.window, effect.is_some())
+            }
+            _ => match effect {
+                Some(effect) => Err(effect.unsupported()),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Set `_KDE_NET_WM_BLUR_BEHIND_REGION` hint which is respected by kwin and picom, an empty
+    /// region blurs the whole window
+    fn set_x11_blur_hint(
+        display: *mut x11_dl::xlib::Display,
+        window: c_ulong,
+        blur: bool,
+    ) -> Result<(), Error> {
+        let xlib = x11_dl::xlib::Xlib::open()?;
+        unsafe {
+            let atom = (xlib.XInternAtom)(display, c"_KDE_NET_WM_BLUR_BEHIND_REGION".as_ptr(), 0);
+            if blur {
+                (xlib.XChangeProperty)(
+                    display,
+                    window,
+                    atom,
+                    x11_dl::xlib::XA_CARDINAL,
+                    32,
+                    x11_dl::xlib::PropModeReplace,
+                    std::ptr::null(),
+                    0,
+                );
+            } else {
+                (xlib.XDeleteProperty)(display, window, atom);
+            }
+            (xlib.XFlush)(display);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows_platform, macos_platform, linux_platform)))]
+mod platform {
+    use crate::window::effect::WindowEffect;
+    use anyhow::Error;
+
+    pub fn apply(
+        effect: Option<WindowEffect>,
+        _window: &winit::window::Window,
+    ) -> Result<(), Error> {
+        match effect {
+            Some(effect) => Err(effect.unsupported()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::window::effect::WindowEffect;
+
+    #[test]
+    fn test_parse_effect() {
+        assert_eq!(WindowEffect::parse("none").unwrap(), None);
+        let effect = WindowEffect::parse("acrylic").unwrap().unwrap();
+        assert_eq!(effect, WindowEffect::Acrylic);
+        assert_eq!(effect.name(), "acrylic");
+        let error = WindowEffect::parse("glass").unwrap_err();
+        assert_eq!(error.code(), "ERR_INVALID_PARAMETER");
+    }
+}
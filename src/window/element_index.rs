@@ -0,0 +1,189 @@
+use crate as deft;
+use crate::element::Element;
+use crate::{js_serialize, some_or_return};
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub const ID_ATTRIBUTE: &str = "id";
+pub const TEST_ID_ATTRIBUTE: &str = "data-testid";
+
+/// Element in path from root to an element, for ui automation
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementPathEntry {
+    pub eid: u32,
+    pub tag: String,
+    /// Value of `id` attribute
+    pub id: Option<String>,
+    /// Value of `data-testid` attribute
+    pub test_id: Option<String>,
+}
+
+js_serialize!(ElementPathEntry);
+
+/// Path from root to element, the last entry is the element itself
+pub fn element_path(element: &Element) -> Vec<ElementPathEntry> {
+    let mut path = Vec::new();
+    let mut e = Some(element.clone());
+    while let Some(el) = e {
+        path.push(ElementPathEntry {
+            eid: el.get_eid(),
+            tag: el.tag.to_string(),
+            id: el.get_attribute_value(ID_ATTRIBUTE).cloned(),
+            test_id: el.get_attribute_value(TEST_ID_ATTRIBUTE).cloned(),
+        });
+        e = el.get_parent();
+    }
+    path.reverse();
+    path
+}
+
+/// Attached elements of a window indexed by `id` and `data-testid` attributes. The element
+/// attached first wins if values are duplicated, and the next one takes its place when it is
+/// removed.
+#[derive(Default)]
+pub struct ElementIndex {
+    ids: HashMap<String, Vec<Element>>,
+    test_ids: HashMap<String, Vec<Element>>,
+}
+
+impl ElementIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_indexed_attribute(key: &str) -> bool {
+        key == ID_ATTRIBUTE || key == TEST_ID_ATTRIBUTE
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Option<Element> {
+        self.ids.get(id)?.first().cloned()
+    }
+
+    pub fn get_by_test_id(&self, test_id: &str) -> Option<Element> {
+        self.test_ids.get(test_id)?.first().cloned()
+    }
+
+    /// Index element and its descendants
+    pub fn add_recurse(&mut self, element: &Element) {
+        for key in [ID_ATTRIBUTE, TEST_ID_ATTRIBUTE] {
+            if let Some(value) = element.get_attribute_value(key) {
+                self.add(element, key, value);
+            }
+        }
+        for c in element.get_children() {
+            self.add_recurse(&c);
+        }
+    }
+
+    /// Remove element and its descendants from index
+    pub fn remove_recurse(&mut self, element: &Element) {
+        for key in [ID_ATTRIBUTE, TEST_ID_ATTRIBUTE] {
+            if let Some(value) = element.get_attribute_value(key) {
+                self.remove(element, key, value);
+            }
+        }
+        for c in element.get_children() {
+            self.remove_recurse(&c);
+        }
+    }
+
+    pub fn update_attribute(
+        &mut self,
+        element: &Element,
+        key: &str,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+    ) {
+        if let Some(v) = old_value {
+            self.remove(element, key, v);
+        }
+        if let Some(v) = new_value {
+            self.add(element, key, v);
+        }
+    }
+
+    fn add(&mut self, element: &Element, key: &str, value: &str) {
+        let map = some_or_return!(self.map_mut(key));
+        let elements = map.entry(value.to_string()).or_default();
+        if !elements.contains(element) {
+            elements.push(element.clone());
+        }
+    }
+
+    fn remove(&mut self, element: &Element, key: &str, value: &str) {
+        let map = some_or_return!(self.map_mut(key));
+        let elements = some_or_return!(map.get_mut(value));
+        elements.retain(|e| e != element);
+        if elements.is_empty() {
+            map.remove(value);
+        }
+    }
+
+    fn map_mut(&mut self, key: &str) -> Option<&mut HashMap<String, Vec<Element>>> {
+        match key {
+            ID_ATTRIBUTE => Some(&mut self.ids),
+            TEST_ID_ATTRIBUTE => Some(&mut self.test_ids),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::container::Container;
+    use crate::element::Element;
+    use crate::window::element_index::{element_path, ElementIndex};
+
+    #[test]
+    fn test_element_index() {
+        let mut parent = Element::create(Container::create);
+        parent.set_attribute("id".to_string(), "list".to_string());
+        let mut child = Element::create(Container::create);
+        child.set_attribute("data-testid".to_string(), "item".to_string());
+        parent.add_child_view(child.clone(), None);
+
+        let mut index = ElementIndex::new();
+        index.add_recurse(&parent);
+        assert_eq!(index.get_by_id("list"), Some(parent.clone()));
+        assert_eq!(index.get_by_test_id("item"), Some(child.clone()));
+
+        // The first attached element wins
+        let mut other = Element::create(Container::create);
+        other.set_attribute("id".to_string(), "list".to_string());
+        index.add_recurse(&other);
+        index.remove_recurse(&other);
+        assert_eq!(index.get_by_id("list"), Some(parent.clone()));
+
+        // The next element takes place of the removed one
+        index.add_recurse(&other);
+        index.update_attribute(&parent, "id", Some("list"), None);
+        assert_eq!(index.get_by_id("list"), Some(other.clone()));
+        index.update_attribute(&parent, "id", None, Some("list"));
+        index.remove_recurse(&other);
+        assert_eq!(index.get_by_id("list"), Some(parent.clone()));
+
+        index.update_attribute(&child, "data-testid", Some("item"), Some("row"));
+        assert!(index.get_by_test_id("item").is_none());
+        assert_eq!(index.get_by_test_id("row"), Some(child.clone()));
+
+        index.remove_recurse(&parent);
+        assert!(index.get_by_id("list").is_none());
+        assert!(index.get_by_test_id("row").is_none());
+    }
+
+    #[test]
+    fn test_element_path() {
+        let mut parent = Element::create(Container::create);
+        parent.set_attribute("id".to_string(), "list".to_string());
+        let mut child = Element::create(Container::create);
+        child.set_attribute("data-testid".to_string(), "item".to_string());
+        parent.add_child_view(child.clone(), None);
+
+        let path = element_path(&child);
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[0].id.as_deref(), Some("list"));
+        assert_eq!(path[1].eid, child.get_eid());
+        assert_eq!(path[1].test_id.as_deref(), Some("item"));
+    }
+}
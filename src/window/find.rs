@@ -0,0 +1,237 @@
+use crate as deft;
+use crate::base::Rect;
+use crate::element::common::editable::Editable;
+use crate::element::label::Label;
+use crate::element::richtext::RichText;
+use crate::element::Element;
+use crate::js_serialize;
+use crate::style::display::Display;
+use crate::some_or_continue;
+use crate::text::textbox::TextBox;
+use quick_js::JsValue;
+use serde::Serialize;
+use std::any::TypeId;
+
+/// Highlighted matches of find-in-page, stored in the resource table of element
+#[derive(Clone)]
+pub struct FindHighlights {
+    /// Rects relative to the border box of element
+    pub rects: Vec<Rect>,
+    /// Index of the active match in rects
+    pub active: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindResult {
+    pub count: usize,
+    /// -1 if nothing matched
+    pub active_index: i32,
+}
+
+js_serialize!(FindResult);
+
+pub struct FindOptions {
+    pub case_sensitive: bool,
+}
+
+impl FindOptions {
+    pub fn from_js_value(value: &JsValue) -> Self {
+        let case_sensitive = match value {
+            JsValue::Object(map) => matches!(map.get("caseSensitive"), Some(JsValue::Bool(true))),
+            _ => false,
+        };
+        Self { case_sensitive }
+    }
+}
+
+struct FindMatch {
+    element: Element,
+    /// Index in highlights of element
+    index: usize,
+    rect: Rect,
+}
+
+#[derive(Default)]
+pub struct FindState {
+    matches: Vec<FindMatch>,
+    active: Option<usize>,
+}
+
+impl FindState {
+    /// Find text in labels, rich texts and text inputs of roots and highlight all matches
+    pub fn find(&mut self, roots: &[Element], query: &str, options: &FindOptions) -> FindResult {
+        self.clear();
+        if !query.is_empty() {
+            for root in roots {
+                self.find_recurse(root, query, options);
+            }
+        }
+        self.active = if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.update_highlights();
+        self.scroll_active_into_view();
+        self.result()
+    }
+
+    /// Move to next match, or previous match if backward
+    pub fn navigate(&mut self, backward: bool) -> FindResult {
+        let count = self.matches.len();
+        if let Some(active) = self.active {
+            let next = if backward {
+                (active + count - 1) % count
+            } else {
+                (active + 1) % count
+            };
+            self.active = Some(next);
+            self.update_highlights();
+            self.scroll_active_into_view();
+        }
+        self.result()
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.matches.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        for m in &mut self.matches {
+            m.element.resource_table.remove::<FindHighlights>();
+            m.element.mark_dirty(false);
+        }
+        self.matches.clear();
+        self.active = None;
+    }
+
+    fn result(&self) -> FindResult {
+        FindResult {
+            count: self.matches.len(),
+            active_index: self.active.map(|i| i as i32).unwrap_or(-1),
+        }
+    }
+
+    fn find_recurse(&mut self, element: &Element, query: &str, options: &FindOptions) {
+        if element.style.yoga_node.display == Display::None {
+            return;
+        }
+        let mut el = element.clone();
+        let type_id = element.get_backend().backend_type_id();
+        let case_sensitive = options.case_sensitive;
+        let rects = if type_id == TypeId::of::<Label>() {
+            el.get_backend_mut_as::<Label>().find_text(query, case_sensitive)
+        } else if type_id == TypeId::of::<RichText>() {
+            el.get_backend_mut_as::<RichText>().find_text(query, case_sensitive)
+        } else if type_id == TypeId::of::<Editable>() {
+            el.get_backend_mut_as::<Editable>().find_text(query, case_sensitive)
+        } else {
+            Vec::new()
+        };
+        for (index, rect) in rects.into_iter().enumerate() {
+            self.matches.push(FindMatch {
+                element: element.clone(),
+                index,
+                rect,
+            });
+        }
+        for c in element.get_children() {
+            self.find_recurse(&c, query, options);
+        }
+    }
+
+    fn update_highlights(&mut self) {
+        let active = self.active.and_then(|i| self.matches.get(i));
+        let active = active.map(|m| (m.element.clone(), m.index));
+        let mut i = 0;
+        while i < self.matches.len() {
+            let mut element = self.matches[i].element.clone();
+            let mut rects = Vec::new();
+            while i < self.matches.len() && self.matches[i].element == element {
+                rects.push(self.matches[i].rect);
+                i += 1;
+            }
+            let active = match &active {
+                Some((e, idx)) if e == &element => Some(*idx),
+                _ => None,
+            };
+            element.resource_table.put(FindHighlights { rects, active });
+            element.mark_dirty(false);
+        }
+    }
+
+    fn scroll_active_into_view(&self) {
+        if let Some(m) = self.active.and_then(|i| self.matches.get(i)) {
+            m.element.scroll_rect_into_view(&m.rect);
+        }
+    }
+}
+
+/// Bounds of text matching query in text box, relative to the origin text box is painted at
+pub fn find_in_text_box(text_box: &mut TextBox, query: &str, case_sensitive: bool) -> Vec<Rect> {
+    let mut result = Vec::new();
+    for (start, end) in find_char_ranges(&text_box.get_text(), query, case_sensitive) {
+        let mut bounds: Option<Rect> = None;
+        for offset in start..end {
+            let coord = some_or_continue!(text_box.get_text_coord_by_char_offset(offset));
+            let r = some_or_continue!(text_box.get_char_rect(coord));
+            bounds = Some(match bounds {
+                None => r,
+                Some(b) => {
+                    let left = b.x.min(r.x);
+                    let top = b.y.min(r.y);
+                    let right = b.right().max(r.right());
+                    let bottom = b.bottom().max(r.bottom());
+                    Rect::new(left, top, right - left, bottom - top)
+                }
+            });
+        }
+        result.extend(bounds);
+    }
+    result
+}
+
+/// Find non-overlapping matches of query, returns char ranges of (start, end)
+pub fn find_char_ranges(text: &str, query: &str, case_sensitive: bool) -> Vec<(usize, usize)> {
+    let text: Vec<char> = text.chars().collect();
+    let query: Vec<char> = query.chars().collect();
+    let mut result = Vec::new();
+    if query.is_empty() {
+        return result;
+    }
+    let eq = |a: char, b: char| {
+        if case_sensitive {
+            a == b
+        } else {
+            a == b || a.to_lowercase().eq(b.to_lowercase())
+        }
+    };
+    let mut i = 0;
+    while i + query.len() <= text.len() {
+        if query.iter().enumerate().all(|(j, q)| eq(text[i + j], *q)) {
+            result.push((i, i + query.len()));
+            i += query.len();
+        } else {
+            i += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::window::find::find_char_ranges;
+
+    #[test]
+    fn test_find_char_ranges() {
+        assert_eq!(find_char_ranges("abcABCabc", "abc", true), vec![(0, 3), (6, 9)]);
+        assert_eq!(
+            find_char_ranges("abcABCabc", "abc", false),
+            vec![(0, 3), (3, 6), (6, 9)]
+        );
+        assert_eq!(find_char_ranges("aaaa", "aa", true), vec![(0, 2), (2, 4)]);
+        assert_eq!(find_char_ranges("你好世界", "世", true), vec![(2, 3)]);
+        assert!(find_char_ranges("abc", "", true).is_empty());
+    }
+}
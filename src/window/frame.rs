@@ -0,0 +1,245 @@
+use crate::ext::ext_window::WindowAttrs;
+use anyhow::Error;
+use winit::window::WindowAttributes;
+
+/// Shadow and rounded corners of undecorated windows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStyle {
+    pub shadow: bool,
+    /// Radius of corners in logical pixels, 0 for square corners
+    pub corner_radius: f32,
+}
+
+impl FrameStyle {
+    /// Frame style of undecorated window, shadow is disabled by default for transparent or
+    /// shaped windows since the shadow of the window bounds doesn't match the visible content
+    pub fn from_attrs(attrs: &WindowAttrs) -> Option<Self> {
+        if attrs.decorations.unwrap_or(true) {
+            return None;
+        }
+        let transparent = attrs.transparent.unwrap_or(false) || attrs.shape.is_some();
+        Some(Self {
+            shadow: attrs.shadow.unwrap_or(!transparent),
+            corner_radius: attrs.corner_radius.unwrap_or(0.0).max(0.0),
+        })
+    }
+
+    /// Set attributes required before the window is created, e.g. visuals with alpha channel
+    pub fn apply_to_attributes(&self, attributes: WindowAttributes) -> WindowAttributes {
+        platform::apply_to_attributes(self, attributes)
+    }
+
+    /// Apply shadow and corners to created window, returns false if corners should be
+    /// clipped when painting since the platform can't round corners of windows
+    pub fn apply(&self, window: &winit::window::Window) -> Result<bool, Error> {
+        platform::apply(self, window)
+    }
+}
+
+#[cfg(windows_platform)]
+mod platform {
+    use crate::window::frame::FrameStyle;
+    use anyhow::{anyhow, Error};
+    use log::debug;
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Dwm::{
+        DwmSetWindowAttribute, DWMWA_WINDOW_CORNER_PREFERENCE, DWMWCP_DONOTROUND, DWMWCP_ROUND,
+        DWMWCP_ROUNDSMALL,
+    };
+    use winit::platform::windows::WindowAttributesExtWindows;
+    use winit::window::WindowAttributes;
+
+    /// Radius of small rounded corners of windows 11
+    const SMALL_CORNER_RADIUS: f32 = 4.0;
+
+    pub fn apply_to_attributes(
+        style: &FrameStyle,
+        attributes: WindowAttributes,
+    ) -> WindowAttributes {
+        attributes.with_undecorated_shadow(style.shadow)
+    }
+
+    pub fn apply(style: &FrameStyle, window: &winit::window::Window) -> Result<bool, Error> {
+        let RawWindowHandle::Win32(h) = window.raw_window_handle()? else {
+            return Err(anyhow!("unsupported window handle"));
+        };
+        // Radius of corners is decided by system, only the preference could be set
+        let preference = match style.corner_radius {
+            r if r <= 0.0 => DWMWCP_DONOTROUND,
+            r if r <= SMALL_CORNER_RADIUS => DWMWCP_ROUNDSMALL,
+            _ => DWMWCP_ROUND,
+        };
+        let result = unsafe {
+            DwmSetWindowAttribute(
+                HWND(h.hwnd.get() as *mut c_void),
+                DWMWA_WINDOW_CORNER_PREFERENCE,
+                &preference as *const _ as *const c_void,
+                size_of_val(&preference) as u32,
+            )
+        };
+        // Corners stay square before windows 11 since the window is opaque
+        if let Err(e) = result {
+            debug!("window corner preference is unsupported: {}", e);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(macos_platform)]
+mod platform {
+    use crate::window::frame::FrameStyle;
+    use anyhow::{anyhow, Error};
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use winit::platform::macos::WindowAttributesExtMacOS;
+    use winit::window::WindowAttributes;
+
+    pub fn apply_to_attributes(
+        style: &FrameStyle,
+        attributes: WindowAttributes,
+    ) -> WindowAttributes {
+        let attributes = attributes.with_has_shadow(style.shadow);
+        if style.corner_radius > 0.0 {
+            // Corners out of the layer mask are only invisible in non-opaque windows
+            attributes.with_transparent(true)
+        } else {
+            attributes
+        }
+    }
+
+    pub fn apply(style: &FrameStyle, window: &winit::window::Window) -> Result<bool, Error> {
+        if style.corner_radius <= 0.0 {
+            return Ok(true);
+        }
+        let RawWindowHandle::AppKit(h) = window.raw_window_handle()? else {
+            return Err(anyhow!("unsupported window handle"));
+        };
+        unsafe {
+            let view = h.ns_view.as_ptr() as *mut AnyObject;
+            let _: () = msg_send![view, setWantsLayer: true];
+            let layer: *mut AnyObject = msg_send![view, layer];
+            if layer.is_null() {
+                return Ok(false);
+            }
+            let _: () = msg_send![layer, setCornerRadius: style.corner_radius as f64];
+            let _: () = msg_send![layer, setMasksToBounds: true];
+            // Shadow follows the shape of the masked content
+            let ns_window: *mut AnyObject = msg_send![view, window];
+            if !ns_window.is_null() {
+                let _: () = msg_send![ns_window, invalidateShadow];
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(linux_platform)]
+mod platform {
+    use crate::window::frame::FrameStyle;
+    use anyhow::{anyhow, Error};
+    use raw_window_handle::{
+        HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+    };
+    use std::ffi::c_ulong;
+    use winit::window::WindowAttributes;
+
+    pub fn apply_to_attributes(
+        style: &FrameStyle,
+        attributes: WindowAttributes,
+    ) -> WindowAttributes {
+        if style.corner_radius > 0.0 {
+            // Visual with alpha channel, corners are clipped when painting
+            attributes.with_transparent(true)
+        } else {
+            attributes
+        }
+    }
+
+    pub fn apply(style: &FrameStyle, window: &winit::window::Window) -> Result<bool, Error> {
+        // Shadows are drawn by compositors, wayland compositors have no hint for them
+        if let (RawWindowHandle::Xlib(w), RawDisplayHandle::Xlib(d)) =
+            (window.raw_window_handle()?, window.raw_display_handle()?)
+        {
+            let display = d.display.ok_or_else(|| anyhow!("no display"))?;
+            set_x11_shadow_hint(display.as_ptr() as _, w.window, style.shadow)?;
+        }
+        Ok(style.corner_radius <= 0.0)
+    }
+
+    /// Set `_COMPTON_SHADOW` hint which is respected by compton and picom
+    fn set_x11_shadow_hint(
+        display: *mut x11_dl::xlib::Display,
+        window: c_ulong,
+        shadow: bool,
+    ) -> Result<(), Error> {
+        let xlib = x11_dl::xlib::Xlib::open()?;
+        unsafe {
+            let atom = (xlib.XInternAtom)(display, c"_COMPTON_SHADOW".as_ptr(), 0);
+            let value: c_ulong = shadow as c_ulong;
+            (xlib.XChangeProperty)(
+                display,
+                window,
+                atom,
+                x11_dl::xlib::XA_CARDINAL,
+                32,
+                x11_dl::xlib::PropModeReplace,
+                &value as *const c_ulong as *const u8,
+                1,
+            );
+            (xlib.XFlush)(display);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows_platform, macos_platform, linux_platform)))]
+mod platform {
+    use crate::window::frame::FrameStyle;
+    use anyhow::Error;
+    use winit::window::WindowAttributes;
+
+    pub fn apply_to_attributes(
+        _style: &FrameStyle,
+        attributes: WindowAttributes,
+    ) -> WindowAttributes {
+        attributes
+    }
+
+    pub fn apply(style: &FrameStyle, _window: &winit::window::Window) -> Result<bool, Error> {
+        Ok(style.corner_radius <= 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::ext_window::WindowAttrs;
+    use crate::window::frame::FrameStyle;
+
+    #[test]
+    fn test_from_attrs() {
+        assert_eq!(None, FrameStyle::from_attrs(&WindowAttrs::default()));
+        let attrs = WindowAttrs {
+            decorations: Some(false),
+            corner_radius: Some(8.0),
+            ..Default::default()
+        };
+        let style = FrameStyle::from_attrs(&attrs).unwrap();
+        assert!(style.shadow);
+        assert_eq!(8.0, style.corner_radius);
+        let attrs = WindowAttrs {
+            decorations: Some(false),
+            transparent: Some(true),
+            ..Default::default()
+        };
+        assert!(!FrameStyle::from_attrs(&attrs).unwrap().shadow);
+        let attrs = WindowAttrs {
+            decorations: Some(false),
+            shape: Some("M0 0 L100 0 L50 100 Z".to_string()),
+            ..Default::default()
+        };
+        assert!(!FrameStyle::from_attrs(&attrs).unwrap().shadow);
+    }
+}
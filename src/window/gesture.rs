@@ -0,0 +1,185 @@
+use std::f32::consts::PI;
+
+/// Scale change before a pinch is recognized as zoom
+const ZOOM_THRESHOLD: f32 = 0.05;
+/// Angle in radians before a pinch is recognized as rotation
+const ROTATE_THRESHOLD: f32 = 5.0 * PI / 180.0;
+/// Distance in pixels the center moves before recognized as pan
+const PAN_THRESHOLD: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TwoFingers {
+    center: (f32, f32),
+    distance: f32,
+    angle: f32,
+}
+
+impl TwoFingers {
+    fn new(a: (f32, f32), b: (f32, f32)) -> Self {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        Self {
+            center: ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0),
+            distance: (dx * dx + dy * dy).sqrt(),
+            angle: dy.atan2(dx),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GestureUpdate {
+    /// Center of fingers relative to window
+    pub center: (f32, f32),
+    /// Scale since gesture started, None if not recognized as zoom
+    pub zoom: Option<GestureDelta>,
+    /// Rotation in radians since gesture started, clockwise is positive
+    pub rotate: Option<GestureDelta>,
+    /// Translation of center since gesture started
+    pub pan: Option<(GestureDelta, GestureDelta)>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GestureDelta {
+    pub total: f32,
+    /// Change since last update, for zoom it is a factor instead of a difference
+    pub delta: f32,
+}
+
+/// Recognize pinch zoom, rotation and two-finger pan from touch points
+#[derive(Default)]
+pub struct GestureRecognizer {
+    start: Option<TwoFingers>,
+    last: Option<TwoFingers>,
+    zooming: bool,
+    rotating: bool,
+    panning: bool,
+}
+
+impl GestureRecognizer {
+    /// Update with (identifier, window_x, window_y) of current touches, gesture is reset
+    /// unless there are exactly two touches
+    pub fn update(&mut self, touches: &[(u64, f32, f32)]) -> Option<GestureUpdate> {
+        if touches.len() != 2 {
+            self.reset();
+            return None;
+        }
+        let (a, b) = if touches[0].0 < touches[1].0 {
+            (touches[0], touches[1])
+        } else {
+            (touches[1], touches[0])
+        };
+        let current = TwoFingers::new((a.1, a.2), (b.1, b.2));
+        let (start, last) = match (self.start, self.last) {
+            (Some(start), Some(last)) => (start, last),
+            _ => {
+                self.start = Some(current);
+                self.last = Some(current);
+                return None;
+            }
+        };
+        if current == last {
+            return None;
+        }
+        self.last = Some(current);
+
+        let scale = current.distance / start.distance.max(1.0);
+        let rotation = normalize_angle(current.angle - start.angle);
+        let pan_x = current.center.0 - start.center.0;
+        let pan_y = current.center.1 - start.center.1;
+        self.zooming = self.zooming || (scale - 1.0).abs() > ZOOM_THRESHOLD;
+        self.rotating = self.rotating || rotation.abs() > ROTATE_THRESHOLD;
+        self.panning = self.panning || (pan_x * pan_x + pan_y * pan_y).sqrt() > PAN_THRESHOLD;
+
+        let zoom = self.zooming.then(|| GestureDelta {
+            total: scale,
+            delta: current.distance / last.distance.max(1.0),
+        });
+        let rotate = self.rotating.then(|| GestureDelta {
+            total: rotation,
+            delta: normalize_angle(current.angle - last.angle),
+        });
+        let pan = self.panning.then(|| {
+            let x = GestureDelta {
+                total: pan_x,
+                delta: current.center.0 - last.center.0,
+            };
+            let y = GestureDelta {
+                total: pan_y,
+                delta: current.center.1 - last.center.1,
+            };
+            (x, y)
+        });
+        Some(GestureUpdate {
+            center: current.center,
+            zoom,
+            rotate,
+            pan,
+        })
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Normalize to (-PI, PI]
+fn normalize_angle(mut angle: f32) -> f32 {
+    while angle > PI {
+        angle -= 2.0 * PI;
+    }
+    while angle <= -PI {
+        angle += 2.0 * PI;
+    }
+    angle
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::window::gesture::GestureRecognizer;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_pinch_and_rotate() {
+        let mut recognizer = GestureRecognizer::default();
+        assert!(recognizer
+            .update(&[(1, 100.0, 100.0), (2, 200.0, 100.0)])
+            .is_none());
+        // Small movements are not recognized
+        let update = recognizer
+            .update(&[(1, 99.0, 100.0), (2, 201.0, 100.0)])
+            .unwrap();
+        assert!(update.zoom.is_none() && update.rotate.is_none() && update.pan.is_none());
+
+        let update = recognizer
+            .update(&[(2, 250.0, 100.0), (1, 50.0, 100.0)])
+            .unwrap();
+        let zoom = update.zoom.unwrap();
+        assert!((zoom.total - 2.0).abs() < 1e-4);
+        assert!((zoom.delta - 200.0 / 102.0).abs() < 1e-4);
+        assert_eq!((150.0, 100.0), update.center);
+        assert!(update.rotate.is_none());
+
+        // Rotate by 90 degrees clockwise around center
+        let update = recognizer
+            .update(&[(1, 150.0, 0.0), (2, 150.0, 200.0)])
+            .unwrap();
+        assert!((update.rotate.unwrap().total - PI / 2.0).abs() < 1e-4);
+        assert!(update.pan.is_none());
+    }
+
+    #[test]
+    fn test_pan_and_reset() {
+        let mut recognizer = GestureRecognizer::default();
+        recognizer.update(&[(1, 0.0, 0.0), (2, 100.0, 0.0)]);
+        let update = recognizer
+            .update(&[(1, 0.0, 20.0), (2, 100.0, 20.0)])
+            .unwrap();
+        let (x, y) = update.pan.unwrap();
+        assert_eq!((0.0, 20.0), (x.total, y.total));
+        assert!(update.zoom.is_none());
+
+        assert!(recognizer.update(&[(1, 0.0, 20.0)]).is_none());
+        assert!(recognizer
+            .update(&[(1, 0.0, 20.0), (3, 100.0, 20.0)])
+            .is_none());
+    }
+}
@@ -0,0 +1,222 @@
+use crate as deft;
+use crate::event::{str_to_named_key, KEY_MOD_ALT, KEY_MOD_CTRL, KEY_MOD_META, KEY_MOD_SHIFT};
+use crate::js_deserialize;
+use serde::{Deserialize, Serialize};
+use winit::event::{MouseButton, TouchPhase};
+use winit::keyboard::NamedKey;
+
+/// Synthetic input fed to window by automation drivers or interactive tutorials. Coordinates are
+/// logical pixels relative to the window.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum InjectedInput {
+    #[serde(rename = "mousemove")]
+    MouseMove(InjectedPointer),
+    #[serde(rename = "mousedown")]
+    MouseDown(InjectedMouseButton),
+    #[serde(rename = "mouseup")]
+    MouseUp(InjectedMouseButton),
+    /// Mousedown followed by mouseup
+    #[serde(rename = "click")]
+    Click(InjectedMouseButton),
+    #[serde(rename = "wheel")]
+    Wheel(InjectedWheel),
+    #[serde(rename = "keydown")]
+    KeyDown(InjectedKey),
+    #[serde(rename = "keyup")]
+    KeyUp(InjectedKey),
+    /// Text committed by input method
+    #[serde(rename = "text")]
+    Text(InjectedText),
+    #[serde(rename = "touch")]
+    Touch(InjectedTouch),
+}
+
+js_deserialize!(InjectedInput);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InjectedPointer {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InjectedMouseButton {
+    /// Cursor is moved to (x, y) first if specified
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    /// One of "left"(default), "right", "middle", "back" and "forward"
+    pub button: Option<String>,
+}
+
+impl InjectedMouseButton {
+    pub fn position(&self) -> Option<(f32, f32)> {
+        Some((self.x?, self.y?))
+    }
+
+    pub fn mouse_button(&self) -> MouseButton {
+        match self.button.as_deref() {
+            Some("right") => MouseButton::Right,
+            Some("middle") => MouseButton::Middle,
+            Some("back") => MouseButton::Back,
+            Some("forward") => MouseButton::Forward,
+            _ => MouseButton::Left,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InjectedWheel {
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    #[serde(default)]
+    pub delta_x: f32,
+    #[serde(default)]
+    pub delta_y: f32,
+    /// 0 for pixels(default), 1 for lines
+    #[serde(default)]
+    pub delta_mode: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InjectedKey {
+    /// Name of named key, e.g. "Enter" and "Control", or the character of key
+    pub key: String,
+    #[serde(default)]
+    pub ctrl_key: bool,
+    #[serde(default)]
+    pub shift_key: bool,
+    #[serde(default)]
+    pub alt_key: bool,
+    #[serde(default)]
+    pub meta_key: bool,
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+impl InjectedKey {
+    pub fn named_key(&self) -> Option<NamedKey> {
+        str_to_named_key(&self.key)
+    }
+
+    /// Modifiers specified explicitly by the event
+    pub fn modifiers(&self) -> u32 {
+        let mut modifiers = 0;
+        for (pressed, flag) in [
+            (self.ctrl_key, KEY_MOD_CTRL),
+            (self.shift_key, KEY_MOD_SHIFT),
+            (self.alt_key, KEY_MOD_ALT),
+            (self.meta_key, KEY_MOD_META),
+        ] {
+            if pressed {
+                modifiers |= flag;
+            }
+        }
+        modifiers
+    }
+
+    /// Modifier held while the key is pressed, 0 if it is not a modifier key
+    pub fn modifier_flag(&self) -> u32 {
+        match self.named_key() {
+            Some(NamedKey::Control) => KEY_MOD_CTRL,
+            Some(NamedKey::Shift) => KEY_MOD_SHIFT,
+            Some(NamedKey::Alt) => KEY_MOD_ALT,
+            Some(NamedKey::Super) | Some(NamedKey::Meta) => KEY_MOD_META,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InjectedText {
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InjectedTouch {
+    #[serde(default)]
+    pub id: u64,
+    /// One of "start", "move", "end" and "cancel"
+    pub phase: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl InjectedTouch {
+    pub fn touch_phase(&self) -> Option<TouchPhase> {
+        match self.phase.as_str() {
+            "start" => Some(TouchPhase::Started),
+            "move" => Some(TouchPhase::Moved),
+            "end" => Some(TouchPhase::Ended),
+            "cancel" => Some(TouchPhase::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// Modifiers held by injected modifier keys, kept apart from modifiers of real keyboard
+#[derive(Default)]
+pub struct InjectedModifiers {
+    held: u32,
+}
+
+impl InjectedModifiers {
+    /// Update held modifiers by key event and return modifiers of the event
+    pub fn apply(&mut self, key: &InjectedKey, pressed: bool) -> u32 {
+        let flag = key.modifier_flag();
+        if pressed {
+            self.held |= flag;
+        } else {
+            self.held &= !flag;
+        }
+        self.held | key.modifiers()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{KEY_MOD_CTRL, KEY_MOD_SHIFT};
+    use crate::window::input_injection::{InjectedKey, InjectedModifiers, InjectedMouseButton};
+    use winit::event::MouseButton;
+
+    fn key(key: &str) -> InjectedKey {
+        InjectedKey {
+            key: key.to_string(),
+            ctrl_key: false,
+            shift_key: false,
+            alt_key: false,
+            meta_key: false,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn test_injected_modifiers() {
+        let mut modifiers = InjectedModifiers::default();
+        assert_eq!(modifiers.apply(&key("Control"), true), KEY_MOD_CTRL);
+        assert_eq!(modifiers.apply(&key("a"), true), KEY_MOD_CTRL);
+        let mut shifted = key("b");
+        shifted.shift_key = true;
+        assert_eq!(
+            modifiers.apply(&shifted, true),
+            KEY_MOD_CTRL | KEY_MOD_SHIFT
+        );
+        assert_eq!(modifiers.apply(&key("Control"), false), 0);
+        assert_eq!(modifiers.apply(&key("a"), false), 0);
+    }
+
+    #[test]
+    fn test_mouse_button() {
+        let mut button = InjectedMouseButton {
+            x: Some(1.0),
+            y: None,
+            button: None,
+        };
+        assert_eq!(button.mouse_button(), MouseButton::Left);
+        assert!(button.position().is_none());
+        button.button = Some("right".to_string());
+        assert_eq!(button.mouse_button(), MouseButton::Right);
+    }
+}
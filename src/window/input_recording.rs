@@ -0,0 +1,310 @@
+use crate as deft;
+use crate::event::{named_key_to_str, KEY_MOD_ALT, KEY_MOD_CTRL, KEY_MOD_META, KEY_MOD_SHIFT};
+use crate::js::js_event_loop::js_create_event_loop_proxy;
+use crate::js_deserialize;
+use crate::timer::VirtualTime;
+use crate::window::input_injection::{
+    InjectedInput, InjectedKey, InjectedMouseButton, InjectedPointer, InjectedText, InjectedTouch,
+    InjectedWheel,
+};
+use anyhow::{anyhow, Error};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use winit::event::{ElementState, Ime, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
+use winit::keyboard::Key;
+
+/// Input recorded in a line of recording file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedInput {
+    /// Milliseconds since recording started
+    pub time: u64,
+    pub input: InjectedInput,
+}
+
+/// Write input events of a window to a file as json lines
+pub struct InputRecorder {
+    start: Instant,
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn create(path: &str) -> Result<Self, Error> {
+        let file = File::create(path)?;
+        Ok(Self {
+            start: Instant::now(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, input: InjectedInput) {
+        let recorded = RecordedInput {
+            time: self.start.elapsed().as_millis() as u64,
+            input,
+        };
+        let result = serde_json::to_writer(&mut self.writer, &recorded)
+            .map_err(Error::from)
+            .and_then(|_| Ok(self.writer.write_all(b"\n")?));
+        if let Err(e) = result {
+            error!("Failed to record input: {:?}", e);
+        }
+    }
+}
+
+impl Drop for InputRecorder {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Convert window event to input which could be injected again, positions are converted to
+/// logical pixels
+pub fn record_window_event(
+    event: &WindowEvent,
+    cursor: (f32, f32),
+    scale_factor: f64,
+    modifiers: u32,
+) -> Option<InjectedInput> {
+    let (cursor_x, cursor_y) = cursor;
+    let input = match event {
+        WindowEvent::CursorMoved { position, .. } => {
+            let position = position.to_logical::<f32>(scale_factor);
+            InjectedInput::MouseMove(InjectedPointer {
+                x: position.x,
+                y: position.y,
+            })
+        }
+        WindowEvent::MouseInput { state, button, .. } => {
+            let button = InjectedMouseButton {
+                x: Some(cursor_x),
+                y: Some(cursor_y),
+                button: Some(mouse_button_name(*button)?.to_string()),
+            };
+            match state {
+                ElementState::Pressed => InjectedInput::MouseDown(button),
+                ElementState::Released => InjectedInput::MouseUp(button),
+            }
+        }
+        WindowEvent::MouseWheel { delta, .. } => {
+            let (delta_x, delta_y, delta_mode) = match delta {
+                MouseScrollDelta::LineDelta(x, y) => (*x, *y, 1),
+                MouseScrollDelta::PixelDelta(d) => (d.x as f32, d.y as f32, 0),
+            };
+            InjectedInput::Wheel(InjectedWheel {
+                x: Some(cursor_x),
+                y: Some(cursor_y),
+                delta_x,
+                delta_y,
+                delta_mode,
+            })
+        }
+        WindowEvent::KeyboardInput { event, .. } => {
+            let key = match &event.logical_key {
+                Key::Named(n) => named_key_to_str(n).to_string(),
+                Key::Character(c) => c.to_string(),
+                _ => return None,
+            };
+            let key = InjectedKey {
+                key,
+                ctrl_key: modifiers & KEY_MOD_CTRL != 0,
+                shift_key: modifiers & KEY_MOD_SHIFT != 0,
+                alt_key: modifiers & KEY_MOD_ALT != 0,
+                meta_key: modifiers & KEY_MOD_META != 0,
+                repeat: event.repeat,
+            };
+            match event.state {
+                ElementState::Pressed => InjectedInput::KeyDown(key),
+                ElementState::Released => InjectedInput::KeyUp(key),
+            }
+        }
+        WindowEvent::Ime(Ime::Commit(text)) => {
+            InjectedInput::Text(InjectedText { text: text.clone() })
+        }
+        WindowEvent::Touch(touch) => {
+            let location = touch.location.to_logical::<f32>(scale_factor);
+            InjectedInput::Touch(InjectedTouch {
+                id: touch.id,
+                phase: touch_phase_name(touch.phase).to_string(),
+                x: location.x,
+                y: location.y,
+            })
+        }
+        _ => return None,
+    };
+    Some(input)
+}
+
+fn mouse_button_name(button: MouseButton) -> Option<&'static str> {
+    match button {
+        MouseButton::Left => Some("left"),
+        MouseButton::Right => Some("right"),
+        MouseButton::Middle => Some("middle"),
+        MouseButton::Back => Some("back"),
+        MouseButton::Forward => Some("forward"),
+        MouseButton::Other(_) => None,
+    }
+}
+
+fn touch_phase_name(phase: TouchPhase) -> &'static str {
+    match phase {
+        TouchPhase::Started => "start",
+        TouchPhase::Moved => "move",
+        TouchPhase::Ended => "end",
+        TouchPhase::Cancelled => "cancel",
+    }
+}
+
+pub fn parse_recording(content: &str) -> Result<Vec<RecordedInput>, Error> {
+    let mut inputs = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if !line.is_empty() {
+            inputs.push(serde_json::from_str(line)?);
+        }
+    }
+    Ok(inputs)
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReplayOptions {
+    /// Replay faster if greater than 1
+    pub speed: f32,
+    /// Freeze timers and advance them by recorded time between inputs
+    pub deterministic_timers: bool,
+}
+
+js_deserialize!(ReplayOptions);
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            deterministic_timers: true,
+        }
+    }
+}
+
+/// Recorded inputs being replayed on a window, timers are resumed when it is dropped
+pub struct InputReplay {
+    inputs: Vec<RecordedInput>,
+    /// Time of the last replayed input
+    time: u64,
+    virtual_time: Option<VirtualTime>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl InputReplay {
+    /// Start a thread which calls `step` on the main thread with index of each input when it
+    /// is time to replay
+    pub fn start<F: Fn(usize) + Send + Sync + Clone + 'static>(
+        inputs: Vec<RecordedInput>,
+        options: &ReplayOptions,
+        step: F,
+    ) -> Result<Self, Error> {
+        let virtual_time = if options.deterministic_timers {
+            let vt = VirtualTime::freeze();
+            Some(vt.ok_or_else(|| anyhow!("Timers are controlled by another input replay"))?)
+        } else {
+            None
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let times: Vec<u64> = inputs.iter().map(|it| it.time).collect();
+        let speed = if options.speed > 0.0 {
+            options.speed
+        } else {
+            1.0
+        };
+        let proxy = js_create_event_loop_proxy();
+        {
+            let cancelled = cancelled.clone();
+            thread::spawn(move || {
+                let mut last_time = 0;
+                for (i, time) in times.into_iter().enumerate() {
+                    let delay = time.saturating_sub(last_time) as f64 / speed as f64;
+                    thread::sleep(Duration::from_secs_f64(delay / 1000.0));
+                    last_time = time;
+                    if cancelled.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let step = step.clone();
+                    let cancelled = cancelled.clone();
+                    // Replay may be stopped before the task runs
+                    let task = move || {
+                        if !cancelled.load(Ordering::SeqCst) {
+                            step(i);
+                        }
+                    };
+                    if proxy.schedule_macro_task(task).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Ok(Self {
+            inputs,
+            time: 0,
+            virtual_time,
+            cancelled,
+        })
+    }
+
+    pub fn is_last(&self, index: usize) -> bool {
+        index + 1 >= self.inputs.len()
+    }
+
+    /// Take input at index and advance frozen timers to its time
+    pub fn take(&mut self, index: usize) -> Option<InjectedInput> {
+        let recorded = self.inputs.get(index)?;
+        let elapsed = recorded.time.saturating_sub(self.time);
+        self.time = recorded.time;
+        if let Some(vt) = &self.virtual_time {
+            vt.advance(Duration::from_millis(elapsed));
+        }
+        Some(recorded.input.clone())
+    }
+}
+
+impl Drop for InputReplay {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::window::input_injection::{InjectedInput, InjectedPointer, InjectedText};
+    use crate::window::input_recording::{parse_recording, RecordedInput};
+
+    #[test]
+    fn test_parse_recording() {
+        let inputs = vec![
+            RecordedInput {
+                time: 0,
+                input: InjectedInput::MouseMove(InjectedPointer { x: 1.0, y: 2.0 }),
+            },
+            RecordedInput {
+                time: 16,
+                input: InjectedInput::Text(InjectedText {
+                    text: "a".to_string(),
+                }),
+            },
+        ];
+        let content = inputs
+            .iter()
+            .map(|it| serde_json::to_string(it).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(content.contains(r#""type":"mousemove""#));
+        assert_eq!(
+            parse_recording(&format!("{}\n\n", content)).unwrap(),
+            inputs
+        );
+        assert!(parse_recording("{}").is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use crate::event::{KEY_MOD_ALT, KEY_MOD_CTRL, KEY_MOD_META};
+use log::debug;
+use winit::keyboard::KeyCode;
+use winit::window::{CursorGrabMode, Window, WindowLevel};
+
+/// State of kiosk mode, window settings changed by it are restored when it exits
+pub struct Kiosk {
+    passcode: Option<String>,
+    /// Whether fullscreen is entered by kiosk mode, i.e. window was not fullscreen before
+    entered_fullscreen: bool,
+}
+
+impl Kiosk {
+    /// Show window in fullscreen on top of other windows and confine cursor in it
+    pub fn enter(window: &Window, passcode: Option<String>) -> Self {
+        let kiosk = Self {
+            passcode,
+            entered_fullscreen: window.fullscreen().is_none(),
+        };
+        kiosk.apply(window);
+        kiosk
+    }
+
+    /// Apply kiosk settings to window, e.g. after native window is recreated
+    pub fn apply(&self, window: &Window) {
+        if window.fullscreen().is_none() {
+            platform::set_fullscreen(window, true);
+        }
+        window.set_window_level(WindowLevel::AlwaysOnTop);
+        if let Err(e) = window.set_cursor_grab(CursorGrabMode::Confined) {
+            debug!("failed to confine cursor: {}", e);
+        }
+        platform::set_system_ui_locked(true);
+    }
+
+    /// Whether kiosk mode could be exited with the passcode, any passcode is accepted
+    /// if kiosk mode is entered without passcode
+    pub fn check_passcode(&self, passcode: Option<&str>) -> bool {
+        match &self.passcode {
+            None => true,
+            Some(p) => passcode == Some(p.as_str()),
+        }
+    }
+
+    pub fn exit(self, window: &Window) {
+        platform::set_system_ui_locked(false);
+        let _ = window.set_cursor_grab(CursorGrabMode::None);
+        window.set_window_level(WindowLevel::Normal);
+        if self.entered_fullscreen {
+            platform::set_fullscreen(window, false);
+        }
+    }
+}
+
+/// Shortcuts which close windows or quit apps, e.g. Alt+F4, Ctrl+W and Cmd+Q
+pub fn is_close_shortcut(modifiers: u32, code: KeyCode) -> bool {
+    match code {
+        KeyCode::F4 => modifiers & (KEY_MOD_ALT | KEY_MOD_CTRL) != 0,
+        KeyCode::KeyW | KeyCode::KeyQ => modifiers & (KEY_MOD_CTRL | KEY_MOD_META) != 0,
+        _ => false,
+    }
+}
+
+#[cfg(macos_platform)]
+mod platform {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use winit::platform::macos::WindowExtMacOS;
+    use winit::window::{Fullscreen, Window};
+
+    /// NSApplicationPresentationOptions which disable apple menu, app switching, force quit,
+    /// logging out and hiding app. App switching could only be disabled when dock is hidden,
+    /// which is done by simple fullscreen.
+    const KIOSK_PRESENTATION_OPTIONS: usize = (1 << 4) | (1 << 5) | (1 << 6) | (1 << 7) | (1 << 8);
+
+    pub fn set_fullscreen(window: &Window, fullscreen: bool) {
+        // Simple fullscreen stays in current space and hides dock and menu bar
+        if fullscreen {
+            if !window.simple_fullscreen() && !window.set_simple_fullscreen(true) {
+                window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+            }
+        } else if !window.set_simple_fullscreen(false) {
+            window.set_fullscreen(None);
+        }
+    }
+
+    pub fn set_system_ui_locked(locked: bool) {
+        unsafe {
+            let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+            let options: usize = msg_send![app, presentationOptions];
+            let options = if locked {
+                options | KIOSK_PRESENTATION_OPTIONS
+            } else {
+                options & !KIOSK_PRESENTATION_OPTIONS
+            };
+            let _: () = msg_send![app, setPresentationOptions: options];
+        }
+    }
+}
+
+#[cfg(not(macos_platform))]
+mod platform {
+    use winit::window::{Fullscreen, Window};
+
+    pub fn set_fullscreen(window: &Window, fullscreen: bool) {
+        window.set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+    }
+
+    /// System shortcuts like Win key and Ctrl+Alt+Del could not be disabled by apps, they
+    /// should be disabled by policies of the system in kiosk setups
+    pub fn set_system_ui_locked(_locked: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{KEY_MOD_ALT, KEY_MOD_CTRL, KEY_MOD_META, KEY_MOD_SHIFT};
+    use crate::window::kiosk::is_close_shortcut;
+    use winit::keyboard::KeyCode;
+
+    #[test]
+    fn test_close_shortcut() {
+        assert!(is_close_shortcut(KEY_MOD_ALT, KeyCode::F4));
+        assert!(is_close_shortcut(KEY_MOD_CTRL, KeyCode::KeyW));
+        assert!(is_close_shortcut(KEY_MOD_META, KeyCode::KeyQ));
+        assert!(!is_close_shortcut(KEY_MOD_SHIFT, KeyCode::KeyQ));
+        assert!(!is_close_shortcut(KEY_MOD_CTRL, KeyCode::KeyA));
+    }
+}
@@ -0,0 +1,281 @@
+use crate as deft;
+use crate::base::Rect;
+use crate::{js_deserialize, js_serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    fn opposite(self) -> Self {
+        match self {
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    fn is_vertical(self) -> bool {
+        matches!(self, Side::Top | Side::Bottom)
+    }
+}
+
+/// Alignment of content to anchor along the side
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Alignment {
+    Center,
+    Start,
+    End,
+}
+
+/// Side of anchor where content is placed and how it is aligned, e.g. "bottom-start"
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Placement {
+    pub side: Side,
+    pub alignment: Alignment,
+}
+
+impl Placement {
+    pub fn new(side: Side, alignment: Alignment) -> Self {
+        Self { side, alignment }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let (side, alignment) = match value.split_once('-') {
+            Some((side, alignment)) => (side, Some(alignment)),
+            None => (value, None),
+        };
+        let side = match side {
+            "top" => Side::Top,
+            "bottom" => Side::Bottom,
+            "left" => Side::Left,
+            "right" => Side::Right,
+            _ => return None,
+        };
+        let alignment = match alignment {
+            None => Alignment::Center,
+            Some("start") => Alignment::Start,
+            Some("end") => Alignment::End,
+            _ => return None,
+        };
+        Some(Self { side, alignment })
+    }
+}
+
+impl TryFrom<String> for Placement {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value).ok_or_else(|| format!("invalid placement: {}", value))
+    }
+}
+
+impl Display for Placement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let side = match self.side {
+            Side::Top => "top",
+            Side::Bottom => "bottom",
+            Side::Left => "left",
+            Side::Right => "right",
+        };
+        match self.alignment {
+            Alignment::Center => write!(f, "{}", side),
+            Alignment::Start => write!(f, "{}-start", side),
+            Alignment::End => write!(f, "{}-end", side),
+        }
+    }
+}
+
+impl Serialize for Placement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// How popups, tooltips and dropdowns are positioned relative to their anchors
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlacementOptions {
+    pub placement: Placement,
+    /// Gap between anchor and content
+    pub offset: f32,
+    /// Move content to the opposite side if it overflows the boundary
+    pub flip: bool,
+    /// Move content along the side to keep it inside the boundary
+    pub shift: bool,
+    /// Minimal distance between content and edges of boundary
+    pub padding: f32,
+}
+
+js_deserialize!(PlacementOptions);
+
+impl Default for PlacementOptions {
+    fn default() -> Self {
+        Self {
+            placement: Placement::new(Side::Bottom, Alignment::Start),
+            offset: 0.0,
+            flip: true,
+            shift: true,
+            padding: 0.0,
+        }
+    }
+}
+
+impl PlacementOptions {
+    pub fn with_placement(placement: Placement) -> Self {
+        Self {
+            placement,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementResult {
+    pub x: f32,
+    pub y: f32,
+    /// Placement after flipping
+    pub placement: Placement,
+    /// Offset of anchor center from the left edge of content if placed on top or bottom,
+    /// otherwise from the top edge, for positioning arrows
+    pub arrow_offset: f32,
+}
+
+js_serialize!(PlacementResult);
+
+/// Position content of size next to anchor inside boundary
+pub fn compute_position(
+    anchor: &Rect,
+    size: (f32, f32),
+    boundary: &Rect,
+    options: &PlacementOptions,
+) -> PlacementResult {
+    let (width, height) = size;
+    let padding = options.padding;
+    let mut side = options.placement.side;
+    if options.flip {
+        let space = |side: Side| match side {
+            Side::Top => anchor.y - boundary.y - options.offset - padding - height,
+            Side::Bottom => boundary.bottom() - anchor.bottom() - options.offset - padding - height,
+            Side::Left => anchor.x - boundary.x - options.offset - padding - width,
+            Side::Right => boundary.right() - anchor.right() - options.offset - padding - width,
+        };
+        let opposite = side.opposite();
+        if space(side) < 0.0 && space(opposite) > space(side) {
+            side = opposite;
+        }
+    }
+    let main = match side {
+        Side::Top => anchor.y - options.offset - height,
+        Side::Bottom => anchor.bottom() + options.offset,
+        Side::Left => anchor.x - options.offset - width,
+        Side::Right => anchor.right() + options.offset,
+    };
+    let (anchor_start, anchor_length, content_length) = if side.is_vertical() {
+        (anchor.x, anchor.width, width)
+    } else {
+        (anchor.y, anchor.height, height)
+    };
+    let cross = match options.placement.alignment {
+        Alignment::Start => anchor_start,
+        Alignment::End => anchor_start + anchor_length - content_length,
+        Alignment::Center => anchor_start + (anchor_length - content_length) / 2.0,
+    };
+    let (mut x, mut y) = if side.is_vertical() {
+        (cross, main)
+    } else {
+        (main, cross)
+    };
+    if options.shift {
+        x = clamp_start(x, width, boundary.x + padding, boundary.right() - padding);
+        y = clamp_start(y, height, boundary.y + padding, boundary.bottom() - padding);
+    }
+    let arrow_offset = if side.is_vertical() {
+        anchor.x + anchor.width / 2.0 - x
+    } else {
+        anchor.y + anchor.height / 2.0 - y
+    };
+    PlacementResult {
+        x,
+        y,
+        placement: Placement::new(side, options.placement.alignment),
+        arrow_offset: arrow_offset.clamp(0.0, content_length.max(0.0)),
+    }
+}
+
+/// Keep [start, start + length] inside [min, max], or aligned to min if it is too long
+fn clamp_start(start: f32, length: f32, min: f32, max: f32) -> f32 {
+    if start + length > max {
+        (max - length).max(min)
+    } else {
+        start.max(min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::Rect;
+    use crate::window::placement::{
+        compute_position, Alignment, Placement, PlacementOptions, Side,
+    };
+
+    #[test]
+    fn test_parse_placement() {
+        let p = Placement::parse("top-end").unwrap();
+        assert_eq!(p, Placement::new(Side::Top, Alignment::End));
+        assert_eq!(p.to_string(), "top-end");
+        assert_eq!(
+            Placement::parse("left").unwrap().alignment,
+            Alignment::Center
+        );
+        assert!(Placement::parse("middle").is_none());
+        assert!(Placement::parse("top-middle").is_none());
+    }
+
+    #[test]
+    fn test_compute_position() {
+        let boundary = Rect::new(0.0, 0.0, 200.0, 200.0);
+        let anchor = Rect::new(50.0, 50.0, 20.0, 10.0);
+        let options = PlacementOptions::default().with_offset(4.0);
+        let r = compute_position(&anchor, (40.0, 30.0), &boundary, &options);
+        assert_eq!((r.x, r.y), (50.0, 64.0));
+        assert_eq!(r.placement.side, Side::Bottom);
+        assert_eq!(r.arrow_offset, 10.0);
+
+        // Flipped to top as bottom overflows
+        let anchor = Rect::new(50.0, 180.0, 20.0, 10.0);
+        let r = compute_position(&anchor, (40.0, 30.0), &boundary, &options);
+        assert_eq!((r.x, r.y), (50.0, 146.0));
+        assert_eq!(r.placement.side, Side::Top);
+
+        // Shifted into boundary
+        let anchor = Rect::new(190.0, 50.0, 10.0, 10.0);
+        let options =
+            PlacementOptions::with_placement(Placement::new(Side::Bottom, Alignment::Center));
+        let r = compute_position(&anchor, (40.0, 30.0), &boundary, &options);
+        assert_eq!(r.x, 160.0);
+        assert_eq!(r.arrow_offset, 35.0);
+
+        // Flipped to right as there is no space on the left
+        let anchor = Rect::new(10.0, 50.0, 10.0, 100.0);
+        let options =
+            PlacementOptions::with_placement(Placement::new(Side::Left, Alignment::Start));
+        let r = compute_position(&anchor, (40.0, 30.0), &boundary, &options);
+        assert_eq!(r.placement.side, Side::Right);
+        assert_eq!((r.x, r.y), (20.0, 50.0));
+    }
+}
@@ -0,0 +1,166 @@
+use crate as deft;
+use crate::element::body::Body;
+use crate::element::{Element, ElementBackend, ElementType};
+use crate::ext::ext_window::WindowAttrs;
+use crate::js::JsError;
+use crate::platform::support_multiple_windows;
+use crate::window::{Window, WindowCloseEventListener, WindowHandle};
+use crate::{js_deserialize, some_or_return};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use winit::window::WindowLevel;
+
+thread_local! {
+    /// Popped out elements by element id
+    static POP_OUTS: RefCell<HashMap<u32, PopOut>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PopOutOptions {
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub title: Option<String>,
+}
+
+js_deserialize!(PopOutOptions);
+
+struct PopOut {
+    element: Element,
+    parent: Element,
+    position: u32,
+    window: WindowHandle,
+}
+
+/// Move element into a frameless always-on-top window, the element is moved back to its parent
+/// when the window is closed or `pop_in` is called.
+pub fn pop_out(element: &Element, options: PopOutOptions) -> Result<WindowHandle, JsError> {
+    if let Some(window) = get_pop_out_window(element) {
+        return Ok(window);
+    }
+    if !support_multiple_windows() {
+        return Err(JsError::from_str("multiple windows are not supported"));
+    }
+    let mut parent = element
+        .get_parent()
+        .ok_or_else(|| JsError::from_str("root element could not be popped out"))?;
+    let position = parent
+        .get_child_position(element)
+        .ok_or_else(|| JsError::from_str("element not found in parent"))?;
+    let attrs = WindowAttrs {
+        width: Some(options.width.unwrap_or(320.0)),
+        height: Some(options.height.unwrap_or(180.0)),
+        title: options.title,
+        resizable: Some(true),
+        decorations: Some(false),
+        minimizable: Some(false),
+        maximizable: Some(false),
+        ..Default::default()
+    };
+    let raw_attrs =
+        winit::window::Window::default_attributes().with_window_level(WindowLevel::AlwaysOnTop);
+    let handle = Window::create_with_raw_attrs(attrs, raw_attrs)?;
+    let _ = parent.remove_child(position);
+    let mut body = Element::create(Body::create);
+    body.set_tag("body".to_string());
+    body.set_element_type(ElementType::Widget);
+    let _ = body.add_child(element.clone(), 0);
+    let eid = element.get_eid();
+    {
+        let mut window = handle.upgrade_mut()?;
+        window.set_body(body)?;
+        window.register_event_listener(WindowCloseEventListener::new(move |_e, _ctx| {
+            if let Some(pop_out) = POP_OUTS.with_borrow_mut(|m| m.remove(&eid)) {
+                restore(pop_out);
+            }
+        }));
+    }
+    POP_OUTS.with_borrow_mut(|m| {
+        m.insert(
+            eid,
+            PopOut {
+                element: element.clone(),
+                parent,
+                position,
+                window: handle.clone(),
+            },
+        )
+    });
+    Ok(handle)
+}
+
+/// Move popped out element back to its parent and close the window holding it
+pub fn pop_in(element: &Element) {
+    let pop_out = some_or_return!(POP_OUTS.with_borrow_mut(|m| m.remove(&element.get_eid())));
+    let window = pop_out.window.clone();
+    restore(pop_out);
+    if let Ok(mut window) = window.upgrade_mut() {
+        let _ = window.close();
+    }
+}
+
+/// Close the window of element popped out of parent without moving it back, e.g. it's removed
+/// from parent by js while popped out
+pub(crate) fn discard(element: &Element, parent: &Element) {
+    let pop_out = some_or_return!(POP_OUTS.with_borrow_mut(|m| {
+        let eid = element.get_eid();
+        let popped_out_of_parent = m.get(&eid).is_some_and(|p| &p.parent == parent);
+        popped_out_of_parent.then(|| m.remove(&eid)).flatten()
+    }));
+    detach(&pop_out.element);
+    if let Ok(mut window) = pop_out.window.upgrade_mut() {
+        let _ = window.close();
+    }
+}
+
+pub fn get_pop_out_window(element: &Element) -> Option<WindowHandle> {
+    POP_OUTS.with_borrow(|m| m.get(&element.get_eid()).map(|p| p.window.clone()))
+}
+
+fn restore(pop_out: PopOut) {
+    detach(&pop_out.element);
+    move_back(pop_out.element, pop_out.parent, pop_out.position);
+}
+
+fn detach(element: &Element) {
+    if let Some(mut body) = element.get_parent() {
+        if let Some(p) = body.get_child_position(element) {
+            let _ = body.remove_child(p);
+        }
+    }
+}
+
+/// Siblings may be changed while popped out, js side finds children by identity
+fn move_back(element: Element, mut parent: Element, position: u32) {
+    let position = position.min(parent.get_child_count());
+    let _ = parent.add_child(element, position as i32);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::container::Container;
+    use crate::element::{Element, ElementBackend};
+    use crate::window::pop_out::{detach, move_back};
+
+    #[test]
+    fn test_remove_child_after_restore() {
+        let mut parent = Element::create(Container::create);
+        let children: Vec<Element> = (0..3).map(|_| Element::create(Container::create)).collect();
+        for child in &children {
+            parent.add_child(child.clone(), -1).unwrap();
+        }
+        let mut body = Element::create(Container::create);
+        let position = parent.get_child_position(&children[1]).unwrap();
+        parent.remove_child(position).unwrap();
+        body.add_child(children[1].clone(), 0).unwrap();
+        // Removed by js while popped out
+        assert!(parent.remove_child_element(children[0].clone()));
+        detach(&children[1]);
+        move_back(children[1].clone(), parent.clone(), position);
+        assert!(parent.remove_child_element(children[2].clone()));
+        assert!(!parent.remove_child_element(children[2].clone()));
+        assert!(parent.get_children() == vec![children[1].clone()]);
+        assert_eq!(body.get_child_count(), 0);
+    }
+}
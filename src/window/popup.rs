@@ -6,10 +6,13 @@ use crate::event::ClickEventListener;
 use crate::ext::ext_window::WindowAttrs;
 use crate::platform::support_multiple_windows;
 use crate::window::page::PageWeak;
+use crate::window::placement::{compute_position, PlacementOptions, PlacementResult};
 use crate::window::{Window, WindowHandle, WindowResizeEventListener};
 use crate::winit::dpi::Position;
 use crate::{js_weak_value, ok_or_return};
 use deft_macros::{js_methods, mrc_object};
+use std::cell::Cell;
+use std::rc::Rc;
 use winit::dpi::LogicalPosition;
 #[cfg(windows)]
 use winit::platform::windows::WindowAttributesExtWindows;
@@ -23,6 +26,7 @@ enum PopupWrapper {
 #[mrc_object]
 pub struct Popup {
     wrapper: PopupWrapper,
+    placement: Rc<Cell<Option<PlacementResult>>>,
 }
 
 js_weak_value!(Popup, PopupWeak);
@@ -30,20 +34,44 @@ js_weak_value!(Popup, PopupWeak);
 #[js_methods]
 impl Popup {
     pub fn new(element: Element, target: Rect, owner_handle: &WindowHandle) -> Popup {
-        Self::new_ex(element, target, owner_handle, true)
+        Self::new_ex(
+            element,
+            target,
+            owner_handle,
+            true,
+            PlacementOptions::default(),
+        )
     }
     pub fn new_ex(
         element: Element,
         target: Rect,
         owner_handle: &WindowHandle,
         focusable: bool,
+        options: PlacementOptions,
     ) -> Popup {
         //TODO no unwrap
         let mut owner = owner_handle.upgrade_mut().unwrap();
+        let placement = Rc::new(Cell::new(None));
         if support_multiple_windows() {
             let (win_x, win_y) = owner.inner_position();
-            let pos_x = target.x + win_x;
-            let pos_y = target.bottom() + win_y;
+            let zoom = owner.get_zoom();
+            let anchor = Rect::new(
+                target.x * zoom + win_x,
+                target.y * zoom + win_y,
+                target.width * zoom,
+                target.height * zoom,
+            );
+            let current_monitor = owner.window.current_monitor();
+            let boundary = match &current_monitor {
+                Some(m) => {
+                    let scale_factor = m.scale_factor();
+                    let pos = m.position().to_logical::<f32>(scale_factor);
+                    let size = m.size().to_logical::<f32>(scale_factor);
+                    Rect::new(pos.x, pos.y, size.width, size.height)
+                }
+                None => Rect::new(f32::MIN / 2.0, f32::MIN / 2.0, f32::MAX, f32::MAX),
+            };
+            let initial = compute_position(&anchor, (0.0, 0.0), &boundary, &options);
             let window_attrs = WindowAttrs {
                 width: None,
                 height: None,
@@ -51,13 +79,17 @@ impl Popup {
                 resizable: Some(false),
                 decorations: Some(false),
                 override_redirect: Some(true),
-                position: Some((pos_x, pos_y)),
+                position: Some((initial.x, initial.y)),
                 visible: None,
                 closable: None,
                 minimizable: None,
                 maximizable: None,
                 window_type: Some("menu".to_string()),
                 preferred_renderers: Some(vec!["softbuffer".to_string()]),
+                resize_margin: None,
+                resize_edges: None,
+                storage_namespace: None,
+                ..Default::default()
             };
             let winit_attrs = WindowAttributes::default();
             #[cfg(windows_platform)]
@@ -65,41 +97,39 @@ impl Popup {
             let window_handle = Window::create_with_raw_attrs(window_attrs, winit_attrs).unwrap();
             //TODO no unwrap
             let mut window = window_handle.upgrade_mut().unwrap();
+            window.apply_zoom(zoom);
             let mut body = Element::create(Body::create);
-            body.tag = "body".to_string();
+            body.tag = "body".into();
             body.set_element_type(ElementType::Widget);
             let _ = body.add_child(element.clone(), 0);
             let _ = window.set_body(body);
-            let current_monitor = owner.window.current_monitor();
             let window_weak = window_handle.clone();
+            let placement_cell = placement.clone();
+            let mut last_pos = (initial.x, initial.y);
             window.register_event_listener(WindowResizeEventListener::new(move |e, _| {
-                if let Some(m) = &current_monitor {
-                    let window = ok_or_return!(window_weak.upgrade_mut());
-                    let content_width = e.width as f32;
-                    let content_height = e.height as f32;
-                    let scale_factor = m.scale_factor();
-                    let monitor_size = m.size().to_logical::<f32>(scale_factor);
-                    let new_pos_y =
-                        fix_pos(pos_y, target.height, content_height, monitor_size.height);
-                    let new_pos_x =
-                        fix_pos(pos_x, -target.width, content_width, monitor_size.width);
-                    if new_pos_x != pos_x || new_pos_y != pos_y {
-                        window
-                            .window
-                            .set_outer_position(Position::Logical(LogicalPosition {
-                                x: new_pos_x as f64,
-                                y: new_pos_y as f64,
-                            }))
-                    }
+                let window = ok_or_return!(window_weak.upgrade_mut());
+                let content_size = (e.width as f32 * zoom, e.height as f32 * zoom);
+                let result = compute_position(&anchor, content_size, &boundary, &options);
+                update_placement_attribute(&element, &placement_cell, result);
+                if (result.x, result.y) != last_pos {
+                    last_pos = (result.x, result.y);
+                    window
+                        .window
+                        .set_outer_position(Position::Logical(LogicalPosition {
+                            x: result.x as f64,
+                            y: result.y as f64,
+                        }))
                 }
             }));
 
             PopupData {
                 wrapper: PopupWrapper::Window(window_handle),
+                placement,
             }
             .to_ref()
         } else {
             let page = owner.create_page_ex(element, target.x, target.bottom(), focusable);
+            owner.anchor_page(&page, target, options, placement.clone());
             let page_weak = page.as_weak();
             page.get_body()
                 .clone()
@@ -110,11 +140,18 @@ impl Popup {
                 }));
             PopupData {
                 wrapper: PopupWrapper::Page(page.as_weak()),
+                placement,
             }
             .to_ref()
         }
     }
 
+    /// Position and placement after flipping, null before the content is laid out
+    #[js_func]
+    pub fn get_placement(&self) -> Option<PlacementResult> {
+        self.placement.get()
+    }
+
     #[js_func]
     pub fn close(&self) {
         match &self.wrapper {
@@ -131,15 +168,16 @@ impl Popup {
     }
 }
 
-fn fix_pos(offset: f32, target_length: f32, content_length: f32, max_length: f32) -> f32 {
-    if offset + content_length > max_length {
-        let offset_new = offset - target_length - content_length;
-        if offset_new > 0.0 {
-            offset_new
-        } else {
-            max_length - content_length
-        }
-    } else {
-        offset
+/// Expose actual placement as attribute of content so that arrows could be styled by it
+pub(crate) fn update_placement_attribute(
+    element: &Element,
+    placement: &Cell<Option<PlacementResult>>,
+    result: PlacementResult,
+) {
+    let old = placement.replace(Some(result));
+    if old.map(|it| it.placement) != Some(result.placement) {
+        element
+            .clone()
+            .set_attribute("placement".to_string(), result.placement.to_string());
     }
 }
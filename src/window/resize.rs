@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+/// Time without resizes after which an edge resizing is finished, the mouse release may not be
+/// delivered after the window manager takes over the drag
+pub const RESIZE_SETTLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Edge resizing driven by the window manager. Cursor events keep arriving during the drag on
+/// some platforms, so it only ends on mouse input or after no resize happened for a while.
+#[derive(Debug, Default)]
+pub struct EdgeResizing {
+    last_activity: Option<Instant>,
+}
+
+impl EdgeResizing {
+    pub fn is_active(&self) -> bool {
+        self.last_activity.is_some()
+    }
+
+    pub fn begin(&mut self, now: Instant) {
+        self.last_activity = Some(now);
+    }
+
+    pub fn on_resized(&mut self, now: Instant) {
+        if self.is_active() {
+            self.last_activity = Some(now);
+        }
+    }
+
+    /// Returns true if a resizing is ended
+    pub fn end(&mut self) -> bool {
+        self.last_activity.take().is_some()
+    }
+
+    /// Time to wait until the resizing settles, None if not resizing
+    pub fn settle_delay(&self, now: Instant) -> Option<Duration> {
+        let last_activity = self.last_activity?;
+        Some(RESIZE_SETTLE_TIMEOUT.saturating_sub(now.saturating_duration_since(last_activity)))
+    }
+
+    /// End the resizing if no resize happened within the settle timeout, returns true if ended
+    pub fn end_if_settled(&mut self, now: Instant) -> bool {
+        match self.settle_delay(now) {
+            Some(delay) if delay.is_zero() => self.end(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::window::resize::{EdgeResizing, RESIZE_SETTLE_TIMEOUT};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_multi_event_drag() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut resizing = EdgeResizing::default();
+        resizing.begin(start);
+        // Resizes keep the drag alive past the settle timeout
+        for millis in (100..=1000).step_by(100) {
+            resizing.on_resized(at(millis));
+            assert!(!resizing.end_if_settled(at(millis + 50)));
+            assert!(resizing.is_active());
+        }
+        assert_eq!(
+            Some(RESIZE_SETTLE_TIMEOUT - Duration::from_millis(200)),
+            resizing.settle_delay(at(1200))
+        );
+        assert!(resizing.end());
+        assert!(!resizing.end());
+        assert_eq!(None, resizing.settle_delay(at(1200)));
+    }
+
+    #[test]
+    fn test_settle() {
+        let start = Instant::now();
+        let mut resizing = EdgeResizing::default();
+        resizing.on_resized(start);
+        assert!(!resizing.is_active());
+        resizing.begin(start);
+        assert!(!resizing.end_if_settled(start + RESIZE_SETTLE_TIMEOUT / 2));
+        assert!(resizing.end_if_settled(start + RESIZE_SETTLE_TIMEOUT));
+        assert!(!resizing.is_active());
+    }
+}
@@ -0,0 +1,308 @@
+use crate as deft;
+use crate::event::{KEY_MOD_ALT, KEY_MOD_CMD_OR_CTRL, KEY_MOD_CTRL, KEY_MOD_META, KEY_MOD_SHIFT};
+use anyhow::{anyhow, Error};
+use deft_macros::js_func;
+use log::error;
+use quick_js::JsValue;
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use winit::keyboard::KeyCode;
+
+thread_local! {
+    /// Shortcuts of app, matched after shortcuts of the focused window
+    static GLOBAL_SHORTCUTS: RefCell<ShortcutRegistry> = RefCell::new(ShortcutRegistry::new());
+}
+
+/// Key combination like "Ctrl+Shift+P", keys are matched by physical position so that
+/// shortcuts work with any keyboard layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    pub modifiers: u32,
+    pub code: KeyCode,
+}
+
+impl Shortcut {
+    /// Parse accelerators like "Ctrl+S", "CmdOrCtrl+Shift+Z" or "Alt+F4",
+    /// "CmdOrCtrl" is Meta on macos and Ctrl on other platforms
+    pub fn parse(accelerator: &str) -> Result<Self, Error> {
+        let invalid = || anyhow!("invalid shortcut: {}", accelerator);
+        let tokens: Vec<&str> = accelerator.split('+').map(|t| t.trim()).collect();
+        // "Ctrl++" ends with an empty token followed by the plus key
+        let (key, modifier_tokens) = match tokens.as_slice() {
+            [rest @ .., "", ""] => ("+", rest),
+            [rest @ .., key] => (*key, rest),
+            [] => return Err(invalid()),
+        };
+        let mut modifiers = 0;
+        for token in modifier_tokens {
+            modifiers |= match token.to_lowercase().as_str() {
+                "ctrl" | "control" => KEY_MOD_CTRL,
+                "alt" | "option" => KEY_MOD_ALT,
+                "shift" => KEY_MOD_SHIFT,
+                "meta" | "cmd" | "command" | "super" | "win" => KEY_MOD_META,
+                "cmdorctrl" | "commandorcontrol" => KEY_MOD_CMD_OR_CTRL,
+                _ => return Err(invalid()),
+            };
+        }
+        let code = parse_key_code(key).ok_or_else(invalid)?;
+        Ok(Self { modifiers, code })
+    }
+
+    pub fn matches(&self, modifiers: u32, code: KeyCode) -> bool {
+        self.modifiers == modifiers && self.code == code
+    }
+}
+
+impl Display for Shortcut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (m, name) in [
+            (KEY_MOD_CTRL, "Ctrl+"),
+            (KEY_MOD_ALT, "Alt+"),
+            (KEY_MOD_SHIFT, "Shift+"),
+            (KEY_MOD_META, "Meta+"),
+        ] {
+            if self.modifiers & m != 0 {
+                f.write_str(name)?;
+            }
+        }
+        write!(f, "{:?}", self.code)
+    }
+}
+
+/// Physical key of key names of accelerators, letters and digits are keys of the US layout
+pub fn parse_key_code(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        let code = match c.to_ascii_uppercase() {
+            'A' => KeyCode::KeyA,
+            'B' => KeyCode::KeyB,
+            'C' => KeyCode::KeyC,
+            'D' => KeyCode::KeyD,
+            'E' => KeyCode::KeyE,
+            'F' => KeyCode::KeyF,
+            'G' => KeyCode::KeyG,
+            'H' => KeyCode::KeyH,
+            'I' => KeyCode::KeyI,
+            'J' => KeyCode::KeyJ,
+            'K' => KeyCode::KeyK,
+            'L' => KeyCode::KeyL,
+            'M' => KeyCode::KeyM,
+            'N' => KeyCode::KeyN,
+            'O' => KeyCode::KeyO,
+            'P' => KeyCode::KeyP,
+            'Q' => KeyCode::KeyQ,
+            'R' => KeyCode::KeyR,
+            'S' => KeyCode::KeyS,
+            'T' => KeyCode::KeyT,
+            'U' => KeyCode::KeyU,
+            'V' => KeyCode::KeyV,
+            'W' => KeyCode::KeyW,
+            'X' => KeyCode::KeyX,
+            'Y' => KeyCode::KeyY,
+            'Z' => KeyCode::KeyZ,
+            '0' | ')' => KeyCode::Digit0,
+            '1' | '!' => KeyCode::Digit1,
+            '2' | '@' => KeyCode::Digit2,
+            '3' | '#' => KeyCode::Digit3,
+            '4' | '$' => KeyCode::Digit4,
+            '5' | '%' => KeyCode::Digit5,
+            '6' | '^' => KeyCode::Digit6,
+            '7' | '&' => KeyCode::Digit7,
+            '8' | '*' => KeyCode::Digit8,
+            '9' | '(' => KeyCode::Digit9,
+            '-' | '_' => KeyCode::Minus,
+            '=' | '+' => KeyCode::Equal,
+            '[' | '{' => KeyCode::BracketLeft,
+            ']' | '}' => KeyCode::BracketRight,
+            '\\' | '|' => KeyCode::Backslash,
+            ';' | ':' => KeyCode::Semicolon,
+            '\'' | '"' => KeyCode::Quote,
+            ',' | '<' => KeyCode::Comma,
+            '.' | '>' => KeyCode::Period,
+            '/' | '?' => KeyCode::Slash,
+            '`' | '~' => KeyCode::Backquote,
+            _ => return None,
+        };
+        return Some(code);
+    }
+    let code = match key.to_lowercase().as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "escape" | "esc" => KeyCode::Escape,
+        "space" => KeyCode::Space,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" | "arrowup" => KeyCode::ArrowUp,
+        "down" | "arrowdown" => KeyCode::ArrowDown,
+        "left" | "arrowleft" => KeyCode::ArrowLeft,
+        "right" | "arrowright" => KeyCode::ArrowRight,
+        "plus" => KeyCode::Equal,
+        "f1" => KeyCode::F1,
+        "f2" => KeyCode::F2,
+        "f3" => KeyCode::F3,
+        "f4" => KeyCode::F4,
+        "f5" => KeyCode::F5,
+        "f6" => KeyCode::F6,
+        "f7" => KeyCode::F7,
+        "f8" => KeyCode::F8,
+        "f9" => KeyCode::F9,
+        "f10" => KeyCode::F10,
+        "f11" => KeyCode::F11,
+        "f12" => KeyCode::F12,
+        _ => return None,
+    };
+    Some(code)
+}
+
+struct ShortcutEntry {
+    id: u32,
+    shortcut: Shortcut,
+    enabled: bool,
+    handler: Rc<dyn Fn()>,
+}
+
+/// Shortcuts registered on a window or the app
+pub struct ShortcutRegistry {
+    next_id: u32,
+    entries: Vec<ShortcutEntry>,
+}
+
+impl ShortcutRegistry {
+    pub fn new() -> Self {
+        Self {
+            next_id: 1,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Register handler of accelerator, fails if the shortcut is registered already
+    pub fn register<F: Fn() + 'static>(
+        &mut self,
+        accelerator: &str,
+        handler: F,
+    ) -> Result<u32, Error> {
+        let shortcut = Shortcut::parse(accelerator)?;
+        if self.entries.iter().any(|e| e.shortcut == shortcut) {
+            return Err(anyhow!("shortcut {} is registered already", shortcut));
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push(ShortcutEntry {
+            id,
+            shortcut,
+            enabled: true,
+            handler: Rc::new(handler),
+        });
+        Ok(id)
+    }
+
+    pub fn unregister(&mut self, id: u32) {
+        self.entries.retain(|e| e.id != id);
+    }
+
+    pub fn set_enabled(&mut self, id: u32, enabled: bool) {
+        if let Some(e) = self.entries.iter_mut().find(|e| e.id == id) {
+            e.enabled = enabled;
+        }
+    }
+
+    /// Handler of the enabled shortcut matching the key
+    pub fn find(&self, modifiers: u32, code: KeyCode) -> Option<Rc<dyn Fn()>> {
+        self.entries
+            .iter()
+            .find(|e| e.enabled && e.shortcut.matches(modifiers, code))
+            .map(|e| e.handler.clone())
+    }
+}
+
+/// Handler of global shortcut matching the key
+pub fn find_global_shortcut(modifiers: u32, code: KeyCode) -> Option<Rc<dyn Fn()>> {
+    GLOBAL_SHORTCUTS.with_borrow(|r| r.find(modifiers, code))
+}
+
+/// Register a shortcut working in any window of app, shortcuts of windows take precedence
+pub fn register_global_shortcut<F: Fn() + 'static>(
+    accelerator: &str,
+    handler: F,
+) -> Result<u32, Error> {
+    GLOBAL_SHORTCUTS.with_borrow_mut(|r| r.register(accelerator, handler))
+}
+
+pub fn unregister_global_shortcut(id: u32) {
+    GLOBAL_SHORTCUTS.with_borrow_mut(|r| r.unregister(id));
+}
+
+pub fn set_global_shortcut_enabled(id: u32, enabled: bool) {
+    GLOBAL_SHORTCUTS.with_borrow_mut(|r| r.set_enabled(id, enabled));
+}
+
+/// Wrap js function as handler of shortcut
+pub fn js_shortcut_handler(handler: JsValue) -> impl Fn() + 'static {
+    move || {
+        if let Err(e) = handler.call_as_function(vec![]) {
+            error!("Failed to call shortcut handler: {:?}", e);
+        }
+    }
+}
+
+#[js_func]
+pub fn app_register_shortcut(accelerator: String, handler: JsValue) -> Result<u32, Error> {
+    register_global_shortcut(&accelerator, js_shortcut_handler(handler))
+}
+
+#[js_func]
+pub fn app_unregister_shortcut(id: u32) {
+    unregister_global_shortcut(id);
+}
+
+#[js_func]
+pub fn app_set_shortcut_enabled(id: u32, enabled: bool) {
+    set_global_shortcut_enabled(id, enabled);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{KEY_MOD_CTRL, KEY_MOD_SHIFT};
+    use crate::window::shortcut::{Shortcut, ShortcutRegistry};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use winit::keyboard::KeyCode;
+
+    #[test]
+    fn test_parse() {
+        let s = Shortcut::parse("Ctrl+Shift+P").unwrap();
+        assert_eq!(KEY_MOD_CTRL | KEY_MOD_SHIFT, s.modifiers);
+        assert_eq!(KeyCode::KeyP, s.code);
+        assert_eq!(KeyCode::Equal, Shortcut::parse("Ctrl++").unwrap().code);
+        assert_eq!(KeyCode::F5, Shortcut::parse("f5").unwrap().code);
+        assert_eq!("Ctrl+Shift+KeyP", s.to_string());
+        assert!(Shortcut::parse("Ctrl+Hyper+P").is_err());
+        assert!(Shortcut::parse("Ctrl+").is_err());
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = ShortcutRegistry::new();
+        let called = Rc::new(Cell::new(0));
+        let c = called.clone();
+        let id = registry
+            .register("Ctrl+S", move || c.set(c.get() + 1))
+            .unwrap();
+        assert!(registry.register("Control+s", || {}).is_err());
+        registry.find(KEY_MOD_CTRL, KeyCode::KeyS).unwrap()();
+        assert_eq!(1, called.get());
+        assert!(registry
+            .find(KEY_MOD_CTRL | KEY_MOD_SHIFT, KeyCode::KeyS)
+            .is_none());
+        registry.set_enabled(id, false);
+        assert!(registry.find(KEY_MOD_CTRL, KeyCode::KeyS).is_none());
+        registry.unregister(id);
+        assert!(registry.register("Ctrl+S", || {}).is_ok());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::base::Rect;
+
+/// Edges of window which stick to screen edges or other windows
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SnapEdges {
+    pub left: bool,
+    pub top: bool,
+    pub right: bool,
+    pub bottom: bool,
+}
+
+impl SnapEdges {
+    pub fn is_empty(&self) -> bool {
+        !(self.left || self.top || self.right || self.bottom)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        [
+            (self.left, "left"),
+            (self.top, "top"),
+            (self.right, "right"),
+            (self.bottom, "bottom"),
+        ]
+        .iter()
+        .filter(|(snapped, _)| *snapped)
+        .map(|(_, name)| name.to_string())
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapResult {
+    pub x: f32,
+    pub y: f32,
+    pub edges: SnapEdges,
+}
+
+/// Closest candidate on one axis, (offset, whether it's the leading edge)
+type Candidate = Option<(f32, bool)>;
+
+fn consider(best: &mut Candidate, offset: f32, leading: bool, threshold: f32) {
+    if offset.abs() > threshold {
+        return;
+    }
+    if best.map(|(o, _)| offset.abs() < o.abs()).unwrap_or(true) {
+        *best = Some((offset, leading));
+    }
+}
+
+fn overlaps(start: f32, end: f32, other_start: f32, other_end: f32, threshold: f32) -> bool {
+    start < other_end + threshold && other_start < end + threshold
+}
+
+/// Move window so that its edges stick to edges of screens from inside or to edges of other
+/// windows from outside when they are closer than threshold. Horizontal and vertical edges
+/// snap independently, all values are in the same coordinate space.
+pub fn snap_position(
+    window: Rect,
+    screens: &[Rect],
+    windows: &[Rect],
+    threshold: f32,
+) -> SnapResult {
+    let (left, top) = (window.x, window.y);
+    let (right, bottom) = (left + window.width, top + window.height);
+    let mut best_x: Candidate = None;
+    let mut best_y: Candidate = None;
+    for s in screens {
+        let (s_right, s_bottom) = (s.x + s.width, s.y + s.height);
+        // Skip screens the window is not on
+        if !overlaps(left, right, s.x, s_right, 0.0) || !overlaps(top, bottom, s.y, s_bottom, 0.0) {
+            continue;
+        }
+        consider(&mut best_x, s.x - left, true, threshold);
+        consider(&mut best_x, s_right - right, false, threshold);
+        consider(&mut best_y, s.y - top, true, threshold);
+        consider(&mut best_y, s_bottom - bottom, false, threshold);
+    }
+    for w in windows {
+        let (w_right, w_bottom) = (w.x + w.width, w.y + w.height);
+        if overlaps(top, bottom, w.y, w_bottom, threshold) {
+            consider(&mut best_x, w_right - left, true, threshold);
+            consider(&mut best_x, w.x - right, false, threshold);
+        }
+        if overlaps(left, right, w.x, w_right, threshold) {
+            consider(&mut best_y, w_bottom - top, true, threshold);
+            consider(&mut best_y, w.y - bottom, false, threshold);
+        }
+    }
+    let edges = SnapEdges {
+        left: best_x.map(|(_, leading)| leading).unwrap_or(false),
+        top: best_y.map(|(_, leading)| leading).unwrap_or(false),
+        right: best_x.map(|(_, leading)| !leading).unwrap_or(false),
+        bottom: best_y.map(|(_, leading)| !leading).unwrap_or(false),
+    };
+    SnapResult {
+        x: left + best_x.map(|(o, _)| o).unwrap_or(0.0),
+        y: top + best_y.map(|(o, _)| o).unwrap_or(0.0),
+        edges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::Rect;
+    use crate::window::snap::snap_position;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_snap_to_screen() {
+        let screen = rect(0.0, 0.0, 1920.0, 1080.0);
+        let result = snap_position(rect(8.0, 500.0, 400.0, 300.0), &[screen], &[], 10.0);
+        assert_eq!((0.0, 500.0), (result.x, result.y));
+        assert_eq!(vec!["left".to_string()], result.edges.names());
+
+        let result = snap_position(rect(1515.0, 775.0, 400.0, 300.0), &[screen], &[], 10.0);
+        assert_eq!((1520.0, 780.0), (result.x, result.y));
+        assert_eq!(vec!["right", "bottom"], result.edges.names());
+
+        let result = snap_position(rect(100.0, 100.0, 400.0, 300.0), &[screen], &[], 10.0);
+        assert_eq!((100.0, 100.0), (result.x, result.y));
+        assert!(result.edges.is_empty());
+    }
+
+    #[test]
+    fn test_snap_to_window() {
+        let other = rect(100.0, 100.0, 400.0, 300.0);
+        let result = snap_position(rect(505.0, 150.0, 200.0, 200.0), &[], &[other], 10.0);
+        assert_eq!((500.0, 150.0), (result.x, result.y));
+        assert!(result.edges.left);
+
+        // Windows far apart vertically don't stick
+        let result = snap_position(rect(505.0, 600.0, 200.0, 200.0), &[], &[other], 10.0);
+        assert_eq!((505.0, 600.0), (result.x, result.y));
+        assert!(result.edges.is_empty());
+    }
+}